@@ -31,6 +31,11 @@ pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, Contract
             to,
             coins,
         } => execute::sudo_transfer(deps, from, to, coins),
+        SudoMsg::TransferFee {
+            from,
+            to,
+            coins,
+        } => execute::sudo_transfer_fee(deps, from, to, coins),
     }
 }
 
@@ -57,6 +62,25 @@ pub fn execute(
             to,
             coins,
         } => execute::send(deps, info, to, coins),
+        ExecuteMsg::MultiSend {
+            outputs,
+        } => execute::multi_send(deps, info, outputs),
+        ExecuteMsg::SetSendEnabled {
+            denom,
+            enabled,
+        } => execute::set_send_enabled(deps, info, denom, enabled),
+        ExecuteMsg::SetFrozen {
+            denom,
+            frozen,
+        } => execute::set_frozen(deps, info, denom, frozen),
+        ExecuteMsg::SetSendHook {
+            denom,
+            hook,
+        } => execute::set_send_hook(deps, info, denom, hook),
+        ExecuteMsg::SetMetadata {
+            denom,
+            metadata,
+        } => execute::set_metadata(deps, info, denom, metadata),
         ExecuteMsg::Mint {
             to,
             denom,
@@ -67,6 +91,9 @@ pub fn execute(
             denom,
             amount,
         } => execute::burn(deps, info, from, denom, amount),
+        ExecuteMsg::BurnOwn {
+            coins,
+        } => execute::burn_own(deps, info, coins),
         ExecuteMsg::ForceTransfer {
             from,
             to,
@@ -103,6 +130,27 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractErr
             start_after,
             limit,
         } => to_binary(&query::balances(deps, address, start_after, limit)?),
+        QueryMsg::Holders {
+            denom,
+            start_after,
+            limit,
+        } => to_binary(&query::holders(deps, denom, start_after, limit)?),
+        QueryMsg::Metadata {
+            denom,
+        } => to_binary(&query::metadata(deps, denom)?),
+        QueryMsg::AllMetadata {
+            start_after,
+            limit,
+        } => to_binary(&query::all_metadata(deps, start_after, limit)?),
+        QueryMsg::SendHook {
+            denom,
+        } => to_binary(&query::send_hook(deps, denom)?),
+        QueryMsg::SendEnabled {
+            denom,
+        } => to_binary(&query::send_enabled(deps, denom)?),
+        QueryMsg::Frozen {
+            denom,
+        } => to_binary(&query::frozen(deps, denom)?),
     }
     .map_err(ContractError::from)
 }
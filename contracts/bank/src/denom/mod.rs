@@ -26,7 +26,7 @@ use std::{fmt, str::FromStr};
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{StdError, StdResult};
-use cw_storage_plus::{Key, KeyDeserialize, PrimaryKey};
+use cw_storage_plus::{Key, KeyDeserialize, Prefixer, PrimaryKey};
 
 mod error;
 mod namespace;
@@ -107,6 +107,12 @@ impl<'a> PrimaryKey<'a> for &Denom {
     }
 }
 
+impl<'a> Prefixer<'a> for &Denom {
+    fn prefix(&self) -> Vec<Key> {
+        vec![Key::Ref(self.0.as_bytes())]
+    }
+}
+
 impl KeyDeserialize for &Denom {
     type Output = Denom;
 
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use cw_ownable::OwnershipError;
 use thiserror::Error;
 
@@ -44,6 +44,33 @@ pub enum ContractError {
     ZeroAmount {
         denom: String,
     },
+
+    #[error("no coins to send")]
+    EmptySend,
+
+    #[error("sending denom {denom} is disabled")]
+    SendDisabled {
+        denom: String,
+    },
+
+    #[error("transfers of denom {denom} are frozen")]
+    Frozen {
+        denom: String,
+    },
+
+    #[error("transfer of denom {denom} rejected by its send hook: {reason}")]
+    SendRejected {
+        denom: String,
+        reason: String,
+    },
+
+    #[error("account {address} has {balance} of denom {denom}, less than the {amount} to transfer")]
+    InsufficientBalance {
+        address: String,
+        denom: String,
+        balance: Uint128,
+        amount: Uint128,
+    },
 }
 
 impl ContractError {
@@ -85,4 +112,37 @@ impl ContractError {
             denom: denom.into(),
         }
     }
+
+    pub fn send_disabled(denom: impl Into<String>) -> Self {
+        Self::SendDisabled {
+            denom: denom.into(),
+        }
+    }
+
+    pub fn frozen(denom: impl Into<String>) -> Self {
+        Self::Frozen {
+            denom: denom.into(),
+        }
+    }
+
+    pub fn send_rejected(denom: impl Into<String>, reason: impl Display) -> Self {
+        Self::SendRejected {
+            denom: denom.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn insufficient_balance(
+        address: impl Into<String>,
+        denom: impl Into<String>,
+        balance: Uint128,
+        amount: Uint128,
+    ) -> Self {
+        Self::InsufficientBalance {
+            address: address.into(),
+            denom: denom.into(),
+            balance,
+            amount,
+        }
+    }
 }
@@ -1,7 +1,8 @@
 use std::str::FromStr;
 
 use cosmwasm_std::{
-    to_binary, Addr, BlockInfo, Coin, DepsMut, MessageInfo, Response, Storage, Uint128, WasmMsg,
+    to_binary, Addr, BlockInfo, Coin, DepsMut, Empty, MessageInfo, Response, Storage, Uint128,
+    WasmMsg,
 };
 use cw_ownable::is_owner;
 use cw_sdk::helpers::{stringify_coins, stringify_option, validate_optional_addr};
@@ -9,10 +10,10 @@ use cw_sdk::helpers::{stringify_coins, stringify_option, validate_optional_addr}
 use crate::{
     denom::{Denom, Namespace, NamespaceConfig},
     error::ContractError,
-    msg::{Balance, HookMsg, UpdateNamespaceMsg},
+    msg::{Balance, HookMsg, Metadata, Output, SendHookQueryMsg, UpdateNamespaceMsg},
     state::{
-        decrease_balance, decrease_supply, increase_balance, increase_supply, BALANCES,
-        NAMESPACE_CONFIGS,
+        decrease_balance, decrease_supply, increase_balance, increase_supply, BALANCES, FROZEN,
+        METADATA, NAMESPACE_CONFIGS, SEND_DISABLED, SEND_HOOKS,
     },
 };
 
@@ -44,13 +45,11 @@ pub fn init(
 
             increase_supply(deps.storage, &denom, coin.amount)?;
 
-            BALANCES.update(deps.storage, (&addr, &denom), |balance| {
-                if balance.is_none() {
-                    Ok(coin.amount)
-                } else {
-                    Err(ContractError::duplicate_balance(&addr, denom.clone()))
-                }
-            })?;
+            if BALANCES.has(deps.storage, (&addr, &denom)) {
+                return Err(ContractError::duplicate_balance(&addr, denom));
+            }
+
+            increase_balance(deps.storage, &addr, &denom, coin.amount)?;
         }
     }
 
@@ -171,18 +170,171 @@ pub fn burn(
         .add_attribute("coin", format!("{amount}{denom}")))
 }
 
+pub fn burn_own(
+    deps: DepsMut,
+    info: MessageInfo,
+    coins: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    for coin in &coins {
+        let d = Denom::from_str(&coin.denom)?;
+
+        assert_non_zero_amount(&coin.denom, coin.amount)?;
+
+        decrease_balance(deps.storage, &info.sender, &d, coin.amount)?;
+        decrease_supply(deps.storage, &d, coin.amount)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "bank/burn_own")
+        .add_attribute("from", info.sender)
+        .add_attribute("coins", stringify_coins(&coins)))
+}
+
 pub fn send(
     deps: DepsMut,
     info: MessageInfo,
     to: String,
     coins: Vec<Coin>,
 ) -> Result<Response, ContractError> {
-    transfer(
-        deps.storage,
-        &info.sender,
-        &deps.api.addr_validate(&to)?,
-        &coins,
-    )
+    assert_sendable(deps.storage, &coins)?;
+
+    let to_addr = deps.api.addr_validate(&to)?;
+    transfer(deps, &info.sender, &to_addr, &coins, true)
+}
+
+pub fn multi_send(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    outputs: Vec<Output>,
+) -> Result<Response, ContractError> {
+    if outputs.is_empty() {
+        return Err(ContractError::EmptySend);
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "bank/multi_send")
+        .add_attribute("from", &info.sender);
+
+    for Output {
+        to,
+        coins,
+    } in outputs
+    {
+        assert_sendable(deps.storage, &coins)?;
+
+        let to_addr = deps.api.addr_validate(&to)?;
+        let msgs = transfer_coins(deps.branch(), &info.sender, &to_addr, &coins, true)?;
+
+        res = res
+            .add_messages(msgs)
+            .add_attribute("to", to_addr)
+            .add_attribute("coins", stringify_coins(&coins));
+    }
+
+    Ok(res)
+}
+
+pub fn set_send_enabled(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    let ns = (&d).into();
+
+    // The sender must be either the contract owner or the namespace's admin
+    if !is_owner(deps.storage, &info.sender)? {
+        assert_namespace_admin(deps.storage, &ns, &info.sender)?;
+    }
+
+    if enabled {
+        SEND_DISABLED.remove(deps.storage, &d);
+    } else {
+        SEND_DISABLED.save(deps.storage, &d, &Empty {})?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "bank/set_send_enabled")
+        .add_attribute("denom", denom)
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+pub fn set_frozen(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    let ns = (&d).into();
+
+    // The sender must be either the contract owner or the namespace's admin
+    if !is_owner(deps.storage, &info.sender)? {
+        assert_namespace_admin(deps.storage, &ns, &info.sender)?;
+    }
+
+    if frozen {
+        FROZEN.save(deps.storage, &d, &Empty {})?;
+    } else {
+        FROZEN.remove(deps.storage, &d);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "bank/set_frozen")
+        .add_attribute("denom", denom)
+        .add_attribute("frozen", frozen.to_string()))
+}
+
+pub fn set_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    metadata: Option<Metadata>,
+) -> Result<Response, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    let ns = (&d).into();
+
+    // The sender must be either the contract owner or the namespace's admin
+    if !is_owner(deps.storage, &info.sender)? {
+        assert_namespace_admin(deps.storage, &ns, &info.sender)?;
+    }
+
+    let symbol = metadata.as_ref().map(|metadata| metadata.symbol.clone());
+    match metadata {
+        Some(metadata) => METADATA.save(deps.storage, &d, &metadata)?,
+        None => METADATA.remove(deps.storage, &d),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "bank/set_metadata")
+        .add_attribute("denom", denom)
+        .add_attribute("symbol", stringify_option(symbol)))
+}
+
+pub fn set_send_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    hook: Option<String>,
+) -> Result<Response, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    let ns = (&d).into();
+
+    // The sender must be either the contract owner or the namespace's admin
+    if !is_owner(deps.storage, &info.sender)? {
+        assert_namespace_admin(deps.storage, &ns, &info.sender)?;
+    }
+
+    match validate_optional_addr(deps.api, hook.as_ref())? {
+        Some(hook_addr) => SEND_HOOKS.save(deps.storage, &d, &hook_addr)?,
+        None => SEND_HOOKS.remove(deps.storage, &d),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "bank/set_send_hook")
+        .add_attribute("denom", denom)
+        .add_attribute("hook", stringify_option(hook)))
 }
 
 pub fn sudo_transfer(
@@ -191,12 +343,20 @@ pub fn sudo_transfer(
     to: String,
     coins: Vec<Coin>,
 ) -> Result<Response, ContractError> {
-    transfer(
-        deps.storage,
-        &deps.api.addr_validate(&from)?,
-        &deps.api.addr_validate(&to)?,
-        &coins,
-    )
+    let from_addr = deps.api.addr_validate(&from)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+    transfer(deps, &from_addr, &to_addr, &coins, true)
+}
+
+pub fn sudo_transfer_fee(
+    deps: DepsMut,
+    from: String,
+    to: String,
+    coins: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let from_addr = deps.api.addr_validate(&from)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+    transfer(deps, &from_addr, &to_addr, &coins, false)
 }
 
 pub fn force_transfer(
@@ -206,28 +366,53 @@ pub fn force_transfer(
     denom: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    let from_addr = deps.api.addr_validate(&from)?;
+    let to_addr = deps.api.addr_validate(&to)?;
     transfer(
-        deps.storage,
-        &deps.api.addr_validate(&from)?,
-        &deps.api.addr_validate(&to)?,
+        deps,
+        &from_addr,
+        &to_addr,
         &[Coin {
             denom,
             amount,
         }],
+        true,
     )
 }
 
+/// Internal method: perform transfers of multiple coins, and return a response
+/// with the messages to invoke the after transfer hooks.
+fn transfer(
+    deps: DepsMut,
+    from_addr: &Addr,
+    to_addr: &Addr,
+    coins: &[Coin],
+    query_send_hooks: bool,
+) -> Result<Response, ContractError> {
+    let msgs = transfer_coins(deps, from_addr, to_addr, coins, query_send_hooks)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "bank/transfer")
+        .add_attribute("from", from_addr)
+        .add_attribute("to", to_addr)
+        .add_attribute("coins", stringify_coins(coins)))
+}
+
 /// Internal method: perform transfers of multiple coins.
 /// For each coin,
-/// 1. Update balances
-/// 2. If `after_transfer_hook` is defined for its namespace, compose a message
+/// 1. If a send hook is registered for it, and `query_send_hooks` is set,
+///    query the hook, which may reject the transfer
+/// 2. Update balances
+/// 3. If `after_transfer_hook` is defined for its namespace, compose a message
 ///    to invoke the hook
-fn transfer(
-    store: &mut dyn Storage,
+fn transfer_coins(
+    deps: DepsMut,
     from_addr: &Addr,
     to_addr: &Addr,
     coins: &[Coin],
-) -> Result<Response, ContractError> {
+    query_send_hooks: bool,
+) -> Result<Vec<WasmMsg>, ContractError> {
     let mut msgs = vec![];
 
     for coin in coins {
@@ -236,10 +421,37 @@ fn transfer(
 
         assert_non_zero_amount(&coin.denom, coin.amount)?;
 
-        decrease_balance(store, from_addr, &d, coin.amount)?;
-        increase_balance(store, to_addr, &d, coin.amount)?;
+        let balance = BALANCES.may_load(deps.storage, (from_addr, &d))?.unwrap_or_default();
+        if balance < coin.amount {
+            return Err(ContractError::insufficient_balance(
+                from_addr,
+                &coin.denom,
+                balance,
+                coin.amount,
+            ));
+        }
+
+        let send_hook = if query_send_hooks {
+            SEND_HOOKS.may_load(deps.storage, &d)?
+        } else {
+            None
+        };
+        if let Some(send_hook) = send_hook {
+            let query = SendHookQueryMsg::Send {
+                from: from_addr.to_string(),
+                to: to_addr.to_string(),
+                denom: coin.denom.clone(),
+                amount: coin.amount,
+            };
+            deps.querier
+                .query_wasm_smart::<Empty>(send_hook, &query)
+                .map_err(|err| ContractError::send_rejected(&coin.denom, err))?;
+        }
+
+        decrease_balance(deps.storage, from_addr, &d, coin.amount)?;
+        increase_balance(deps.storage, to_addr, &d, coin.amount)?;
 
-        if let Some(namespace_cfg) = NAMESPACE_CONFIGS.may_load(store, &ns)? {
+        if let Some(namespace_cfg) = NAMESPACE_CONFIGS.may_load(deps.storage, &ns)? {
             if let Some(after_transfer_hook) = namespace_cfg.after_transfer_hook {
                 msgs.push(WasmMsg::Execute {
                     contract_addr: after_transfer_hook.into(),
@@ -255,12 +467,7 @@ fn transfer(
         }
     }
 
-    Ok(Response::new()
-        .add_messages(msgs)
-        .add_attribute("action", "bank/transfer")
-        .add_attribute("from", from_addr)
-        .add_attribute("to", to_addr)
-        .add_attribute("coins", stringify_coins(coins)))
+    Ok(msgs)
 }
 
 fn assert_non_zero_amount(denom: &str, amount: Uint128) -> Result<(), ContractError> {
@@ -271,6 +478,28 @@ fn assert_non_zero_amount(denom: &str, amount: Uint128) -> Result<(), ContractEr
     Ok(())
 }
 
+/// Users may only send coins that have send enabled and aren't frozen, and
+/// must send at least one coin. Transfers made by the state machine, e.g. of
+/// fees, and force transfers by the namespace's admin aren't subject to these.
+fn assert_sendable(store: &dyn Storage, coins: &[Coin]) -> Result<(), ContractError> {
+    if coins.is_empty() {
+        return Err(ContractError::EmptySend);
+    }
+
+    for coin in coins {
+        let d = Denom::from_str(&coin.denom)?;
+        if SEND_DISABLED.has(store, &d) {
+            return Err(ContractError::send_disabled(&coin.denom));
+        }
+
+        if FROZEN.has(store, &d) {
+            return Err(ContractError::frozen(&coin.denom));
+        }
+    }
+
+    Ok(())
+}
+
 fn assert_namespace_admin(
     store: &dyn Storage,
     namespace: &Namespace,
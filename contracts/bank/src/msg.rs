@@ -9,7 +9,10 @@ pub struct InstantiateMsg {
     /// Typically this is set to a governance contract.
     pub owner: String,
 
-    /// Initial balances of each account.
+    /// Initial balances of each account. Genesis accounts are funded this
+    /// way, by the bank contract's instantiate message in the genesis state's
+    /// `msgs`. The total supply of each coin is set to the sum of its initial
+    /// balances.
     ///
     /// NOTE:
     /// - There must be no duplication in addresses.
@@ -28,6 +31,25 @@ pub struct Balance {
     pub coins: Vec<Coin>,
 }
 
+#[cw_serde]
+pub struct Output {
+    pub to: String,
+    pub coins: Vec<Coin>,
+}
+
+/// Display information of a coin
+#[cw_serde]
+pub struct Metadata {
+    /// The ticker shown to users, e.g. `ATOM` for `uatom`
+    pub symbol: String,
+
+    /// Number of decimal places between the base unit and the unit shown to
+    /// users, e.g. 6 for `uatom`
+    pub decimals: u8,
+
+    pub description: Option<String>,
+}
+
 #[cw_serde]
 pub struct UpdateNamespaceMsg {
     pub namespace: String,
@@ -48,6 +70,46 @@ pub enum ExecuteMsg {
         coins: Vec<Coin>,
     },
 
+    /// Send coins to multiple recipients at once. If any of the transfers
+    /// fails, none of them happens.
+    MultiSend {
+        outputs: Vec<Output>,
+    },
+
+    /// Enable or disable sending a coin by `Send` and `MultiSend`. Coins can be
+    /// sent by default. Transfers initiated by the state machine or by the
+    /// namespace's admin are not affected.
+    /// Only callable by the contract owner or the namespace's admin.
+    SetSendEnabled {
+        denom: String,
+        enabled: bool,
+    },
+
+    /// Pause or resume users' transfers of a coin, i.e. sends, multi-sends and
+    /// fee payments. Transfers made by the state machine, such as fee
+    /// deductions and refunds, and force transfers are not affected, nor are
+    /// minting and burning.
+    /// Only callable by the contract owner or the namespace's admin.
+    SetFrozen {
+        denom: String,
+        frozen: bool,
+    },
+
+    /// Register a contract to be queried on every transfer of a coin, or
+    /// remove the coin's hook if `hook` is `None`. See `SendHookQueryMsg`.
+    /// Only callable by the contract owner or the namespace's admin.
+    SetSendHook {
+        denom: String,
+        hook: Option<String>,
+    },
+
+    /// Set a coin's display information, or remove it if `metadata` is `None`.
+    /// Only callable by the contract owner or the namespace's admin.
+    SetMetadata {
+        denom: String,
+        metadata: Option<Metadata>,
+    },
+
     /// Mint a coin to the designated account's balance.
     /// Only callable by the namespace's admin.
     Mint {
@@ -64,6 +126,12 @@ pub enum ExecuteMsg {
         amount: Uint128,
     },
 
+    /// Burn coins from the sender's own balance, e.g. fees collected by a
+    /// contract that are meant to be destroyed.
+    BurnOwn {
+        coins: Vec<Coin>,
+    },
+
     /// Forcibly transfer a coin between designated accounts.
     /// Only callable by the namespace's admin.
     ForceTransfer {
@@ -87,6 +155,25 @@ pub enum HookMsg {
     },
 }
 
+/// The query that the bank contract makes to a coin's send hook, if one is
+/// registered, on every transfer of the coin, before the coin is moved and the
+/// namespace's `after_transfer_hook` is invoked. The hook rejects the transfer
+/// by returning an error, e.g. if either account is blocklisted, and allows it
+/// by returning an empty object. The hook is queried in the middle of the
+/// transfer, so it takes effect whether the transfer is made by a user or by
+/// the state machine, e.g. of funds attached to a message, but can't change
+/// any state. Fee payments and refunds are the exception: they don't query the
+/// hook, so that it can't fail a tx after the tx is admitted or executed.
+#[cw_serde]
+pub enum SendHookQueryMsg {
+    Send {
+        from: String,
+        to: String,
+        denom: String,
+        amount: Uint128,
+    },
+}
+
 #[cw_ownable_query]
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -110,8 +197,10 @@ pub enum QueryMsg {
         denom: String,
     },
 
-    /// Enumerate total supplies of all coins
+    /// Enumerate total supplies of all coins. Coins whose supply has dropped
+    /// to zero are not included.
     #[returns(Vec<Coin>)]
+    #[serde(alias = "total_supplies")]
     Supplies {
         start_after: Option<String>,
         limit: Option<u32>,
@@ -126,11 +215,81 @@ pub enum QueryMsg {
 
     /// The balances of all coins for a single account
     #[returns(Vec<Coin>)]
+    #[serde(alias = "all_balances")]
     Balances {
         address: String,
         start_after: Option<String>,
         limit: Option<u32>,
     },
+
+    /// Enumerate the accounts holding a non-zero balance of a single coin
+    #[returns(Vec<HolderResponse>)]
+    Holders {
+        denom: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// The display information of a single coin
+    #[returns(MetadataResponse)]
+    Metadata {
+        denom: String,
+    },
+
+    /// Enumerate the display information of all coins that have it
+    #[returns(Vec<MetadataResponse>)]
+    AllMetadata {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// The send hook registered for a coin, if any
+    #[returns(SendHookResponse)]
+    SendHook {
+        denom: String,
+    },
+
+    /// Whether a coin can be sent by `Send` and `MultiSend`
+    #[returns(SendEnabledResponse)]
+    SendEnabled {
+        denom: String,
+    },
+
+    /// Whether transfers of a coin are frozen
+    #[returns(FrozenResponse)]
+    Frozen {
+        denom: String,
+    },
+}
+
+#[cw_serde]
+pub struct SendEnabledResponse {
+    pub denom: String,
+    pub enabled: bool,
+}
+
+#[cw_serde]
+pub struct FrozenResponse {
+    pub denom: String,
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct MetadataResponse {
+    pub denom: String,
+    pub metadata: Option<Metadata>,
+}
+
+#[cw_serde]
+pub struct SendHookResponse {
+    pub denom: String,
+    pub hook: Option<String>,
+}
+
+#[cw_serde]
+pub struct HolderResponse {
+    pub address: String,
+    pub amount: Uint128,
 }
 
 pub type NamespaceResponse = UpdateNamespaceMsg;
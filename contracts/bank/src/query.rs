@@ -7,8 +7,13 @@ use cw_storage_plus::Bound;
 use crate::{
     denom::{Denom, Namespace},
     error::ContractError,
-    msg::NamespaceResponse,
-    state::{BALANCES, NAMESPACE_CONFIGS, SUPPLIES},
+    msg::{
+        FrozenResponse, HolderResponse, MetadataResponse, NamespaceResponse, SendEnabledResponse,
+        SendHookResponse,
+    },
+    state::{
+        BALANCES, FROZEN, HOLDERS, METADATA, NAMESPACE_CONFIGS, SEND_DISABLED, SEND_HOOKS, SUPPLIES,
+    },
 };
 
 pub fn namespace(deps: Deps, namespace: String) -> Result<NamespaceResponse, ContractError> {
@@ -84,3 +89,66 @@ pub fn balances(
         })
     })
 }
+
+pub fn holders(
+    deps: Deps,
+    denom: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<HolderResponse>, ContractError> {
+    let start = start_after.map(|address| Bound::ExclusiveRaw(address.into_bytes()));
+    let prefix = Denom::from_str(&denom)?;
+    paginate_map_prefix(HOLDERS, deps.storage, &prefix, start, limit, |address, amount| {
+        Ok(HolderResponse {
+            address: address.into(),
+            amount,
+        })
+    })
+}
+
+pub fn send_enabled(deps: Deps, denom: String) -> Result<SendEnabledResponse, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    Ok(SendEnabledResponse {
+        enabled: !SEND_DISABLED.has(deps.storage, &d),
+        denom,
+    })
+}
+
+pub fn frozen(deps: Deps, denom: String) -> Result<FrozenResponse, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    Ok(FrozenResponse {
+        frozen: FROZEN.has(deps.storage, &d),
+        denom,
+    })
+}
+
+pub fn metadata(deps: Deps, denom: String) -> Result<MetadataResponse, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    Ok(MetadataResponse {
+        metadata: METADATA.may_load(deps.storage, &d)?,
+        denom,
+    })
+}
+
+pub fn all_metadata(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<MetadataResponse>, ContractError> {
+    let start = start_after.map(|denom| Bound::ExclusiveRaw(denom.into_bytes()));
+    paginate_map(METADATA, deps.storage, start, limit, |denom, metadata| {
+        Ok(MetadataResponse {
+            denom: denom.into(),
+            metadata: Some(metadata),
+        })
+    })
+}
+
+pub fn send_hook(deps: Deps, denom: String) -> Result<SendHookResponse, ContractError> {
+    let d = Denom::from_str(&denom)?;
+    let hook = SEND_HOOKS.may_load(deps.storage, &d)?;
+    Ok(SendHookResponse {
+        denom,
+        hook: hook.map(String::from),
+    })
+}
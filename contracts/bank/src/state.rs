@@ -1,11 +1,26 @@
-use cosmwasm_std::{Addr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_std::{Addr, Empty, StdError, StdResult, Storage, Uint128};
 use cw_storage_plus::Map;
 
-use crate::denom::{Denom, Namespace, NamespaceConfig};
+use crate::{
+    denom::{Denom, Namespace, NamespaceConfig},
+    msg::Metadata,
+};
 
 pub const NAMESPACE_CONFIGS: Map<&Namespace, NamespaceConfig> = Map::new("ns_cfgs");
 pub const SUPPLIES: Map<&Denom, Uint128> = Map::new("supplies");
 pub const BALANCES: Map<(&Addr, &Denom), Uint128> = Map::new("balances");
+/// The same balances as `BALANCES`, indexed by denom first, so that the holders
+/// of a denom can be enumerated. Must be updated along with `BALANCES`.
+pub const HOLDERS: Map<(&Denom, &Addr), Uint128> = Map::new("holders");
+/// Display information of denoms, set by their namespaces' admins
+pub const METADATA: Map<&Denom, Metadata> = Map::new("metadata");
+/// Denoms that can't be sent by users. All others can.
+pub const SEND_DISABLED: Map<&Denom, Empty> = Map::new("send_disabled");
+/// Denoms that can't be transferred at all, including by the state machine and
+/// by force transfers, e.g. during an incident.
+pub const FROZEN: Map<&Denom, Empty> = Map::new("frozen");
+/// Contracts queried on every transfer of a denom, which may reject the transfer.
+pub const SEND_HOOKS: Map<&Denom, Addr> = Map::new("send_hooks");
 
 /// Increase the total supply of a denom by the specified amount.
 pub fn increase_supply(store: &mut dyn Storage, denom: &Denom, amount: Uint128) -> StdResult<()> {
@@ -39,10 +54,10 @@ pub fn increase_balance(
     denom: &Denom,
     amount: Uint128,
 ) -> StdResult<()> {
-    BALANCES.update(store, (addr, denom), |opt| {
+    let balance = BALANCES.update(store, (addr, denom), |opt| {
         opt.unwrap_or_else(Uint128::zero).checked_add(amount).map_err(StdError::from)
     })?;
-    Ok(())
+    HOLDERS.save(store, (denom, addr), &balance)
 }
 
 /// Decrease an account's balance of a denom by the specified amount.
@@ -60,8 +75,10 @@ pub fn decrease_balance(
 
     if balance.is_zero() {
         BALANCES.remove(store, (addr, denom));
+        HOLDERS.remove(store, (denom, addr));
     } else {
         BALANCES.save(store, (addr, denom), &balance)?;
+        HOLDERS.save(store, (denom, addr), &balance)?;
     }
 
     Ok(())
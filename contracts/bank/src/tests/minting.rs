@@ -1,4 +1,6 @@
-use cosmwasm_std::{testing::mock_info, Addr, OverflowError, OverflowOperation, StdError, Uint128};
+use cosmwasm_std::{
+    coin, testing::mock_info, Addr, OverflowError, OverflowOperation, StdError, Uint128,
+};
 
 use crate::{
     denom::{Denom, DenomError, Namespace},
@@ -204,3 +206,30 @@ fn proper_minting_and_burning() {
         assert!(opt.is_none());
     }
 }
+
+#[test]
+fn burning_own_coins() {
+    let mut deps = setup_test();
+
+    // anyone can burn their own coins, regardless of the namespace's admin
+    execute::burn_own(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        vec![coin(345, "uatom"), coin(23456, "factory/osmo1234abcd/uastro")],
+    )
+    .unwrap();
+
+    assert_supply(deps.as_ref(), "uatom", 46567); // 12345 + 34567 - 345
+    assert_balance(deps.as_ref(), "jake", "uatom", 12000);
+    assert_supply(deps.as_ref(), "factory/osmo1234abcd/uastro", 0);
+    assert_balance(deps.as_ref(), "jake", "factory/osmo1234abcd/uastro", 0);
+
+    // but not more than they have
+    let err = execute::burn_own(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        vec![coin(12001, "uatom")],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+}
@@ -1,6 +1,7 @@
 mod instantiation;
 mod minting;
 mod namespace;
+mod querying;
 mod transfer;
 
 use cosmwasm_std::{
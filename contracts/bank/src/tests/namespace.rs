@@ -1,10 +1,10 @@
 use cosmwasm_std::testing::mock_info;
 
 use crate::{
-    denom::DenomError,
+    denom::{DenomError, Namespace},
     error::ContractError,
     execute,
-    msg::NamespaceResponse,
+    msg::{Metadata, MetadataResponse, NamespaceResponse},
     query,
     tests::{setup_test, OWNER},
 };
@@ -128,3 +128,77 @@ fn invalid_namespace() {
 
     assert_eq!(err, DenomError::not_alphanumeric("abc@123").into());
 }
+
+#[test]
+fn metadata() {
+    let mut deps = setup_test();
+
+    let atom = Metadata {
+        symbol: "ATOM".into(),
+        decimals: 6,
+        description: None,
+    };
+    let astro = Metadata {
+        symbol: "ASTRO".into(),
+        decimals: 6,
+        description: Some("Astroport governance token".into()),
+    };
+
+    // only the owner or the namespace's admin can set metadata
+    let err = execute::set_metadata(
+        deps.as_mut(),
+        mock_info("token-factory", &[]),
+        "uatom".into(),
+        Some(atom.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::not_namespace_admin(Namespace::unchecked("")));
+
+    execute::set_metadata(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        "uatom".into(),
+        Some(atom.clone()),
+    )
+    .unwrap();
+    execute::set_metadata(
+        deps.as_mut(),
+        mock_info("token-factory", &[]),
+        "factory/osmo1234abcd/uastro".into(),
+        Some(astro.clone()),
+    )
+    .unwrap();
+
+    assert_eq!(
+        query::metadata(deps.as_ref(), "uatom".into()).unwrap(),
+        MetadataResponse {
+            denom: "uatom".into(),
+            metadata: Some(atom.clone()),
+        },
+    );
+    assert_eq!(
+        query::all_metadata(deps.as_ref(), None, None).unwrap(),
+        vec![
+            MetadataResponse {
+                denom: "factory/osmo1234abcd/uastro".into(),
+                metadata: Some(astro),
+            },
+            MetadataResponse {
+                denom: "uatom".into(),
+                metadata: Some(atom),
+            },
+        ],
+    );
+
+    // removing metadata
+    execute::set_metadata(
+        deps.as_mut(),
+        mock_info("token-factory", &[]),
+        "factory/osmo1234abcd/uastro".into(),
+        None,
+    )
+    .unwrap();
+    let res = query::metadata(deps.as_ref(), "factory/osmo1234abcd/uastro".into()).unwrap();
+    assert_eq!(res.metadata, None);
+    assert_eq!(query::all_metadata(deps.as_ref(), None, None).unwrap().len(), 1);
+}
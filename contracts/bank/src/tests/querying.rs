@@ -0,0 +1,149 @@
+use cosmwasm_std::{coin, testing::mock_info, Deps, Uint128};
+
+use crate::{
+    execute,
+    msg::HolderResponse,
+    query,
+    tests::{assert_supply, setup_test},
+};
+
+fn holder(address: &str, amount: u128) -> HolderResponse {
+    HolderResponse {
+        address: address.into(),
+        amount: Uint128::new(amount),
+    }
+}
+
+#[test]
+fn holders() {
+    let mut deps = setup_test();
+
+    let holders = query::holders(deps.as_ref(), "uatom".into(), None, None).unwrap();
+    assert_eq!(holders, vec![holder("jake", 12345), holder("pumpkin", 34567)]);
+
+    let holders = query::holders(deps.as_ref(), "ibc/12AB34CD".into(), None, None).unwrap();
+    assert_eq!(holders, vec![holder("pumpkin", 45678)]);
+
+    // pagination
+    let holders = query::holders(deps.as_ref(), "uatom".into(), None, Some(1)).unwrap();
+    assert_eq!(holders, vec![holder("jake", 12345)]);
+    let holders = query::holders(deps.as_ref(), "uatom".into(), Some("jake".into()), None).unwrap();
+    assert_eq!(holders, vec![holder("pumpkin", 34567)]);
+
+    // the index follows transfers, and accounts whose balance drops to zero are
+    // no longer holders
+    execute::send(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "larry".into(),
+        vec![coin(12345, "uatom")],
+    )
+    .unwrap();
+    let holders = query::holders(deps.as_ref(), "uatom".into(), None, None).unwrap();
+    assert_eq!(holders, vec![holder("larry", 12345), holder("pumpkin", 34567)]);
+
+    // a coin nobody holds
+    let holders = query::holders(deps.as_ref(), "uosmo".into(), None, None).unwrap();
+    assert!(holders.is_empty());
+}
+
+#[test]
+fn balances() {
+    let deps = setup_test();
+
+    let balances = query::balances(deps.as_ref(), "jake".into(), None, None).unwrap();
+    assert_eq!(balances, vec![coin(23456, "factory/osmo1234abcd/uastro"), coin(12345, "uatom")]);
+
+    let balances = query::balances(
+        deps.as_ref(),
+        "jake".into(),
+        Some("factory/osmo1234abcd/uastro".into()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(balances, vec![coin(12345, "uatom")]);
+}
+
+/// The total supply of each coin must equal the sum of its holders' balances.
+fn assert_supply_invariant(deps: Deps) {
+    let supplies = query::supplies(deps, None, None).unwrap();
+    for supply in &supplies {
+        let holders = query::holders(deps, supply.denom.clone(), None, None).unwrap();
+        let total: Uint128 = holders.iter().map(|holder| holder.amount).sum();
+        assert_eq!(total, supply.amount, "supply of {} doesn't match balances", supply.denom);
+    }
+}
+
+#[test]
+fn supplies() {
+    let mut deps = setup_test();
+
+    let supplies = query::supplies(deps.as_ref(), None, None).unwrap();
+    assert_eq!(
+        supplies,
+        vec![
+            coin(23456, "factory/osmo1234abcd/uastro"),
+            coin(45678, "ibc/12AB34CD"),
+            coin(46912, "uatom"),
+        ],
+    );
+    assert_supply_invariant(deps.as_ref());
+
+    execute::mint(
+        deps.as_mut(),
+        mock_info("gov", &[]),
+        "larry".into(),
+        "uatom".into(),
+        Uint128::new(1000),
+    )
+    .unwrap();
+    execute::send(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "pumpkin".into(),
+        vec![coin(345, "uatom"), coin(456, "factory/osmo1234abcd/uastro")],
+    )
+    .unwrap();
+    execute::force_transfer(
+        deps.as_mut(),
+        "pumpkin".into(),
+        "larry".into(),
+        "ibc/12AB34CD".into(),
+        Uint128::new(678),
+    )
+    .unwrap();
+    execute::sudo_transfer(
+        deps.as_mut(),
+        "larry".into(),
+        "jake".into(),
+        vec![coin(500, "uatom")],
+    )
+    .unwrap();
+    execute::burn(
+        deps.as_mut(),
+        mock_info("ibc-transfer", &[]),
+        "pumpkin".into(),
+        "ibc/12AB34CD".into(),
+        Uint128::new(45000),
+    )
+    .unwrap();
+
+    // transfers don't change the supply, while minting and burning do
+    assert_supply(deps.as_ref(), "uatom", 47912);
+    assert_supply(deps.as_ref(), "factory/osmo1234abcd/uastro", 23456);
+    assert_supply(deps.as_ref(), "ibc/12AB34CD", 678);
+    assert_supply_invariant(deps.as_ref());
+
+    // once all of a coin is burned, it's no longer enumerated
+    execute::burn(
+        deps.as_mut(),
+        mock_info("ibc-transfer", &[]),
+        "larry".into(),
+        "ibc/12AB34CD".into(),
+        Uint128::new(678),
+    )
+    .unwrap();
+    let supplies = query::supplies(deps.as_ref(), Some("ibc/12AB34CD".into()), None).unwrap();
+    assert_eq!(supplies, vec![coin(47912, "uatom")]);
+    assert_supply_invariant(deps.as_ref());
+}
@@ -1,10 +1,17 @@
-use cosmwasm_std::{coin, testing::mock_info, to_binary, SubMsg, Uint128, WasmMsg};
+use cosmwasm_std::{
+    coin, from_binary, testing::mock_info, to_binary, ContractResult, Empty, SubMsg, SystemResult,
+    Uint128, WasmMsg, WasmQuery,
+};
 
 use crate::{
+    denom::Namespace,
+    error::ContractError,
     execute,
-    msg::HookMsg,
+    msg::{
+        FrozenResponse, HookMsg, Output, SendEnabledResponse, SendHookQueryMsg, SendHookResponse,
+    },
     query,
-    tests::{assert_supply, setup_test, OWNER},
+    tests::{assert_balance, assert_supply, setup_test, OWNER},
 };
 
 #[test]
@@ -98,3 +105,391 @@ fn send() {
     assert_supply(deps.as_ref(), "ibc/12AB34CD", 45678);
     assert_supply(deps.as_ref(), "mars/uxmars", 69420);
 }
+
+#[test]
+fn multi_send() {
+    let mut deps = setup_test();
+
+    let res = execute::multi_send(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        vec![
+            Output {
+                to: "pumpkin".into(),
+                coins: vec![coin(10000, "uatom")],
+            },
+            Output {
+                to: "larry".into(),
+                coins: vec![coin(2345, "uatom"), coin(3456, "factory/osmo1234abcd/uastro")],
+            },
+        ],
+    )
+    .unwrap();
+
+    // only the factory coin has an after transfer hook
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "token-factory".into(),
+            msg: to_binary(&HookMsg::AfterTransfer {
+                from: "jake".into(),
+                to: "larry".into(),
+                denom: "factory/osmo1234abcd/uastro".into(),
+                amount: Uint128::new(3456),
+            })
+            .unwrap(),
+            funds: vec![],
+        })],
+    );
+
+    assert_balance(deps.as_ref(), "jake", "uatom", 0);
+    assert_balance(deps.as_ref(), "jake", "factory/osmo1234abcd/uastro", 20000);
+    assert_balance(deps.as_ref(), "pumpkin", "uatom", 44567);
+    assert_balance(deps.as_ref(), "larry", "uatom", 2345);
+    assert_balance(deps.as_ref(), "larry", "factory/osmo1234abcd/uastro", 3456);
+
+    // there must be at least one output
+    let err = execute::multi_send(deps.as_mut(), mock_info("jake", &[]), vec![]).unwrap_err();
+    assert_eq!(err, ContractError::EmptySend);
+}
+
+#[test]
+fn insufficient_balance() {
+    let mut deps = setup_test();
+
+    let err = execute::send(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "pumpkin".into(),
+        vec![coin(12346, "uatom")],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::insufficient_balance(
+            "jake",
+            "uatom",
+            Uint128::new(12345),
+            Uint128::new(12346),
+        ),
+    );
+
+    // jake can afford the first output but not the second. on chain, the whole
+    // execution is reverted; here the first transfer remains applied
+    let err = execute::multi_send(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        vec![
+            Output {
+                to: "pumpkin".into(),
+                coins: vec![coin(10000, "uatom")],
+            },
+            Output {
+                to: "larry".into(),
+                coins: vec![coin(10000, "uatom")],
+            },
+        ],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::insufficient_balance(
+            "jake",
+            "uatom",
+            Uint128::new(2345),
+            Uint128::new(10000),
+        ),
+    );
+
+    // there must be at least one coin
+    let err = execute::send(deps.as_mut(), mock_info("jake", &[]), "pumpkin".into(), vec![])
+        .unwrap_err();
+    assert_eq!(err, ContractError::EmptySend);
+}
+
+#[test]
+fn send_enabled() {
+    let mut deps = setup_test();
+
+    // only the owner or the namespace's admin can disable sending
+    let err = execute::set_send_enabled(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "ibc/12AB34CD".into(),
+        false,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::not_namespace_admin(Namespace::unchecked("ibc")));
+
+    execute::set_send_enabled(
+        deps.as_mut(),
+        mock_info("ibc-transfer", &[]),
+        "ibc/12AB34CD".into(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        query::send_enabled(deps.as_ref(), "ibc/12AB34CD".into()).unwrap(),
+        SendEnabledResponse {
+            denom: "ibc/12AB34CD".into(),
+            enabled: false,
+        },
+    );
+
+    let err = execute::send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        "jake".into(),
+        vec![coin(100, "uatom"), coin(100, "ibc/12AB34CD")],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::send_disabled("ibc/12AB34CD"));
+
+    // the state machine can still transfer the coin, e.g. to pay fees
+    execute::sudo_transfer(
+        deps.as_mut(),
+        "pumpkin".into(),
+        "jake".into(),
+        vec![coin(100, "ibc/12AB34CD")],
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "jake", "ibc/12AB34CD", 100);
+
+    execute::set_send_enabled(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        "ibc/12AB34CD".into(),
+        true,
+    )
+    .unwrap();
+    execute::send(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "pumpkin".into(),
+        vec![coin(100, "ibc/12AB34CD")],
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "pumpkin", "ibc/12AB34CD", 45678);
+}
+
+#[test]
+fn freezing() {
+    let mut deps = setup_test();
+
+    // only the owner or the namespace's admin can freeze a coin
+    let err = execute::set_frozen(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "ibc/12AB34CD".into(),
+        true,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::not_namespace_admin(Namespace::unchecked("ibc")));
+
+    execute::set_frozen(
+        deps.as_mut(),
+        mock_info("ibc-transfer", &[]),
+        "ibc/12AB34CD".into(),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        query::frozen(deps.as_ref(), "ibc/12AB34CD".into()).unwrap(),
+        FrozenResponse {
+            denom: "ibc/12AB34CD".into(),
+            frozen: true,
+        },
+    );
+
+    // users can't send the coin
+    let err = execute::send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        "jake".into(),
+        vec![coin(100, "uatom"), coin(100, "ibc/12AB34CD")],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::frozen("ibc/12AB34CD"));
+
+    let err = execute::multi_send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        vec![Output {
+            to: "jake".into(),
+            coins: vec![coin(100, "ibc/12AB34CD")],
+        }],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::frozen("ibc/12AB34CD"));
+
+    // but the state machine can still transfer it, e.g. to refund a fee paid
+    // before the coin was frozen, and the namespace's admin can force-transfer
+    // it
+    execute::sudo_transfer(
+        deps.as_mut(),
+        "pumpkin".into(),
+        "jake".into(),
+        vec![coin(100, "ibc/12AB34CD")],
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "jake", "ibc/12AB34CD", 100);
+
+    execute::force_transfer(
+        deps.as_mut(),
+        "pumpkin".into(),
+        "jake".into(),
+        "ibc/12AB34CD".into(),
+        Uint128::new(100),
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "jake", "ibc/12AB34CD", 200);
+    assert_balance(deps.as_ref(), "pumpkin", "ibc/12AB34CD", 45478);
+
+    // unfreezing
+    execute::set_frozen(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        "ibc/12AB34CD".into(),
+        false,
+    )
+    .unwrap();
+    assert!(!query::frozen(deps.as_ref(), "ibc/12AB34CD".into()).unwrap().frozen);
+
+    execute::send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        "jake".into(),
+        vec![coin(100, "ibc/12AB34CD")],
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "jake", "ibc/12AB34CD", 300);
+}
+
+#[test]
+fn send_hooks() {
+    let mut deps = setup_test();
+
+    // only the owner or the namespace's admin can register send hooks
+    let err = execute::set_send_hook(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "ibc/12AB34CD".into(),
+        Some("blocklist".into()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::not_namespace_admin(Namespace::unchecked("ibc")));
+
+    execute::set_send_hook(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        "ibc/12AB34CD".into(),
+        Some("blocklist".into()),
+    )
+    .unwrap();
+    assert_eq!(
+        query::send_hook(deps.as_ref(), "ibc/12AB34CD".into()).unwrap(),
+        SendHookResponse {
+            denom: "ibc/12AB34CD".into(),
+            hook: Some("blocklist".into()),
+        },
+    );
+
+    // the hook rejects transfers to a blocklisted account
+    deps.querier.update_wasm(|query| {
+        let WasmQuery::Smart {
+            contract_addr,
+            msg,
+        } = query
+        else {
+            panic!("unexpected query: {query:?}");
+        };
+        assert_eq!(contract_addr, "blocklist");
+        let SendHookQueryMsg::Send {
+            to,
+            ..
+        } = from_binary(msg).unwrap();
+        let result = if to == "badguy" {
+            ContractResult::Err("account is blocklisted".into())
+        } else {
+            ContractResult::Ok(to_binary(&Empty {}).unwrap())
+        };
+        SystemResult::Ok(result)
+    });
+
+    execute::send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        "jake".into(),
+        vec![coin(100, "uatom"), coin(200, "ibc/12AB34CD")],
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "jake", "ibc/12AB34CD", 200);
+
+    let err = execute::send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        "badguy".into(),
+        vec![coin(200, "ibc/12AB34CD")],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::SendRejected { .. }));
+    assert_balance(deps.as_ref(), "pumpkin", "ibc/12AB34CD", 45478);
+
+    // coins without a hook aren't affected
+    execute::send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        "badguy".into(),
+        vec![coin(100, "uatom")],
+    )
+    .unwrap();
+
+    // the hook also applies to transfers made by the state machine, e.g. of
+    // funds attached to a message, and by the namespace's admin
+    let err = execute::sudo_transfer(
+        deps.as_mut(),
+        "pumpkin".into(),
+        "badguy".into(),
+        vec![coin(200, "ibc/12AB34CD")],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::SendRejected { .. }));
+    let err = execute::force_transfer(
+        deps.as_mut(),
+        "pumpkin".into(),
+        "badguy".into(),
+        "ibc/12AB34CD".into(),
+        Uint128::new(200),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::SendRejected { .. }));
+
+    // but not to fees, which are transferred without querying the hook
+    execute::sudo_transfer_fee(
+        deps.as_mut(),
+        "pumpkin".into(),
+        "badguy".into(),
+        vec![coin(200, "ibc/12AB34CD")],
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "badguy", "ibc/12AB34CD", 200);
+
+    // removing the hook
+    execute::set_send_hook(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        "ibc/12AB34CD".into(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(query::send_hook(deps.as_ref(), "ibc/12AB34CD".into()).unwrap().hook, None);
+
+    execute::send(
+        deps.as_mut(),
+        mock_info("pumpkin", &[]),
+        "badguy".into(),
+        vec![coin(200, "ibc/12AB34CD")],
+    )
+    .unwrap();
+    assert_balance(deps.as_ref(), "badguy", "ibc/12AB34CD", 400);
+}
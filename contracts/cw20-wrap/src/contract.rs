@@ -0,0 +1,64 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{from_binary, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg},
+    query,
+};
+
+pub const CONTRACT_NAME: &str = "crates.io:cw20-wrap";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Register {
+            cw20,
+            nonce,
+        } => execute::register(deps, env, info, cw20, nonce),
+        ExecuteMsg::Receive(cw20_msg) => match from_binary(&cw20_msg.msg)? {
+            ReceiveMsg::Wrap {
+                recipient,
+            } => execute::wrap(deps, info, cw20_msg.sender, cw20_msg.amount, recipient),
+        },
+        ExecuteMsg::Unwrap {
+            recipient,
+        } => execute::unwrap(deps, env, info, recipient),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Wrapped {
+            cw20,
+        } => to_binary(&query::wrapped(deps, cw20)?),
+        QueryMsg::Cw20 {
+            denom,
+        } => to_binary(&query::cw20(deps, denom)?),
+        QueryMsg::AllWrapped {
+            start_after,
+            limit,
+        } => to_binary(&query::all_wrapped(deps, start_after, limit)?),
+    }
+    .map_err(ContractError::from)
+}
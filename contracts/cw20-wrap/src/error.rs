@@ -0,0 +1,48 @@
+use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[cfg_attr(any(test, feature = "library"), derive(PartialEq))]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("cw20 token {cw20} is already registered")]
+    AlreadyRegistered {
+        cw20: String,
+    },
+
+    #[error("cw20 token {cw20} is not registered")]
+    NotRegistered {
+        cw20: String,
+    },
+
+    #[error("denom {denom} is not a wrapped cw20 token")]
+    NotWrapped {
+        denom: String,
+    },
+}
+
+impl ContractError {
+    pub fn already_registered(cw20: impl Into<String>) -> Self {
+        Self::AlreadyRegistered {
+            cw20: cw20.into(),
+        }
+    }
+
+    pub fn not_registered(cw20: impl Into<String>) -> Self {
+        Self::NotRegistered {
+            cw20: cw20.into(),
+        }
+    }
+
+    pub fn not_wrapped(denom: impl Into<String>) -> Self {
+        Self::NotWrapped {
+            denom: denom.into(),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use cosmwasm_std::{to_binary, DepsMut, Env, MessageInfo, Response, Uint128, WasmMsg};
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, TokenInfoResponse};
+use cw_bank::msg::Metadata;
+use cw_token_factory::{msg as token_factory, NAMESPACE};
+use cw_utils::one_coin;
+
+use crate::{
+    error::ContractError,
+    state::{CW20S, DENOMS},
+    TOKEN_FACTORY,
+};
+
+pub fn register(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20: String,
+    nonce: String,
+) -> Result<Response, ContractError> {
+    let cw20_addr = deps.api.addr_validate(&cw20)?;
+
+    if DENOMS.has(deps.storage, &cw20_addr) {
+        return Err(ContractError::already_registered(cw20));
+    }
+
+    // this also makes sure that the contract is indeed a cw20 token
+    let token_info: TokenInfoResponse =
+        deps.querier.query_wasm_smart(&cw20, &Cw20QueryMsg::TokenInfo {})?;
+
+    let denom = format!("{NAMESPACE}/{}/{nonce}", env.contract.address);
+
+    DENOMS.save(deps.storage, &cw20_addr, &denom)?;
+    CW20S.save(deps.storage, &denom, &cw20_addr)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cw20-wrap/register")
+        .add_attribute("cw20", &cw20)
+        .add_attribute("denom", denom)
+        .add_message(WasmMsg::Execute {
+            contract_addr: TOKEN_FACTORY.into(),
+            msg: to_binary(&token_factory::ExecuteMsg::CreateToken {
+                nonce,
+                admin: env.contract.address.into(),
+                after_transfer_hook: None,
+                hook_failure_policy: token_factory::HookFailurePolicy::Revert,
+                before_send_hook: None,
+                metadata: Some(Metadata {
+                    symbol: token_info.symbol,
+                    decimals: token_info.decimals,
+                    description: Some(format!("{}, wrapped from cw20 {cw20}", token_info.name)),
+                }),
+                max_supply: None,
+            })?,
+            // the token creation fee
+            funds: info.funds,
+        }))
+}
+
+pub fn wrap(
+    deps: DepsMut,
+    info: MessageInfo,
+    sender: String,
+    amount: Uint128,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // the sender of the Receive message is the cw20 contract
+    let Some(denom) = DENOMS.may_load(deps.storage, &info.sender)? else {
+        return Err(ContractError::not_registered(info.sender));
+    };
+
+    let recipient = recipient.unwrap_or(sender);
+    deps.api.addr_validate(&recipient)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cw20-wrap/wrap")
+        .add_attribute("recipient", &recipient)
+        .add_attribute("coin", format!("{amount}{denom}"))
+        .add_message(WasmMsg::Execute {
+            contract_addr: TOKEN_FACTORY.into(),
+            msg: to_binary(&token_factory::ExecuteMsg::Mint {
+                to: recipient,
+                denom,
+                amount,
+            })?,
+            funds: vec![],
+        }))
+}
+
+pub fn unwrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let coin = one_coin(&info)?;
+
+    let Some(cw20_addr) = CW20S.may_load(deps.storage, &coin.denom)? else {
+        return Err(ContractError::not_wrapped(coin.denom));
+    };
+
+    let recipient = recipient.unwrap_or_else(|| info.sender.into());
+    deps.api.addr_validate(&recipient)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cw20-wrap/unwrap")
+        .add_attribute("recipient", &recipient)
+        .add_attribute("coin", coin.to_string())
+        .add_message(WasmMsg::Execute {
+            contract_addr: TOKEN_FACTORY.into(),
+            msg: to_binary(&token_factory::ExecuteMsg::Burn {
+                from: env.contract.address.into(),
+                denom: coin.denom,
+                amount: coin.amount,
+            })?,
+            funds: vec![],
+        })
+        .add_message(WasmMsg::Execute {
+            contract_addr: cw20_addr.into(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient,
+                amount: coin.amount,
+            })?,
+            funds: vec![],
+        }))
+}
@@ -0,0 +1,12 @@
+pub mod contract;
+pub mod error;
+pub mod execute;
+pub mod msg;
+pub mod query;
+pub mod state;
+
+#[cfg(test)]
+mod tests;
+
+/// The token factory contract's label
+pub const TOKEN_FACTORY: &str = "token-factory";
@@ -0,0 +1,72 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cw20::Cw20ReceiveMsg;
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Create a token-factory denom that a cw20 token is to be wrapped into,
+    /// with the cw20 token's symbol and decimals as its metadata. The denom
+    /// will be `factory/{this contract}/{nonce}`, of which this contract is the
+    /// admin.
+    ///
+    /// Anyone can register any cw20 token that hasn't been registered yet. The
+    /// message must include the token-factory's token creation fee, if any.
+    Register {
+        cw20: String,
+        nonce: String,
+    },
+
+    /// Invoked by a cw20 token contract when tokens are sent to this contract.
+    /// The tokens are held in escrow, and the same amount of the native denom
+    /// minted in their place.
+    Receive(Cw20ReceiveMsg),
+
+    /// Burn the native coins sent along with this message, and release the
+    /// same amount of the cw20 token from escrow.
+    Unwrap {
+        /// The account to receive the cw20 tokens.
+        /// Default to the sender if not provided.
+        recipient: Option<String>,
+    },
+}
+
+/// The message to be included in the cw20 `send` message when wrapping tokens
+#[cw_serde]
+pub enum ReceiveMsg {
+    Wrap {
+        /// The account to receive the native coins.
+        /// Default to the cw20 sender if not provided.
+        recipient: Option<String>,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Query the native denom that a cw20 token is wrapped into
+    #[returns(WrappedResponse)]
+    Wrapped {
+        cw20: String,
+    },
+
+    /// Query the cw20 token that a native denom unwraps into
+    #[returns(WrappedResponse)]
+    Cw20 {
+        denom: String,
+    },
+
+    /// Enumerate all registered cw20 tokens
+    #[returns(Vec<WrappedResponse>)]
+    AllWrapped {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct WrappedResponse {
+    pub cw20: String,
+    pub denom: String,
+}
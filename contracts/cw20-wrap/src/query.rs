@@ -0,0 +1,45 @@
+use cosmwasm_std::{Deps, StdResult};
+use cw_paginate::paginate_map;
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::WrappedResponse,
+    state::{CW20S, DENOMS},
+};
+
+pub fn wrapped(deps: Deps, cw20: String) -> Result<WrappedResponse, ContractError> {
+    let cw20_addr = deps.api.addr_validate(&cw20)?;
+    let Some(denom) = DENOMS.may_load(deps.storage, &cw20_addr)? else {
+        return Err(ContractError::not_registered(cw20));
+    };
+    Ok(WrappedResponse {
+        cw20,
+        denom,
+    })
+}
+
+pub fn cw20(deps: Deps, denom: String) -> Result<WrappedResponse, ContractError> {
+    let Some(cw20_addr) = CW20S.may_load(deps.storage, &denom)? else {
+        return Err(ContractError::not_wrapped(denom));
+    };
+    Ok(WrappedResponse {
+        cw20: cw20_addr.into(),
+        denom,
+    })
+}
+
+pub fn all_wrapped(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<WrappedResponse>> {
+    let start_addr = start_after.map(|cw20| deps.api.addr_validate(&cw20)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+    paginate_map(DENOMS, deps.storage, start, limit, |cw20, denom| {
+        Ok(WrappedResponse {
+            cw20: cw20.into(),
+            denom,
+        })
+    })
+}
@@ -0,0 +1,8 @@
+use cosmwasm_std::Addr;
+use cw_storage_plus::Map;
+
+/// The native denom that each registered cw20 token is wrapped into
+pub const DENOMS: Map<&Addr, String> = Map::new("denoms");
+
+/// The cw20 token that each native denom unwraps into
+pub const CW20S: Map<&str, Addr> = Map::new("cw20s");
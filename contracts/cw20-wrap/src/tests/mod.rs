@@ -0,0 +1,60 @@
+mod registering;
+mod wrapping;
+
+use cosmwasm_std::{
+    coin, from_slice,
+    testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+    to_binary, Coin, ContractResult, Empty, OwnedDeps, SystemError, SystemResult, Uint128,
+    WasmQuery,
+};
+use cw20::{Cw20QueryMsg, TokenInfoResponse};
+
+use crate::execute;
+
+const CW20: &str = "astro";
+const DENOM: &str = "factory/cosmos2contract/uastro";
+
+fn fee() -> Coin {
+    coin(12345, "ujuno")
+}
+
+/// Mock a cw20 token at the address "astro"
+fn mock_cw20(querier: &mut MockQuerier) {
+    querier.update_wasm(|query| match query {
+        WasmQuery::Smart {
+            contract_addr,
+            msg,
+        } if contract_addr == CW20 => {
+            let Cw20QueryMsg::TokenInfo {} = from_slice(msg).unwrap() else {
+                panic!("unexpected cw20 query");
+            };
+            let res = TokenInfoResponse {
+                name: "Astroport".into(),
+                symbol: "ASTRO".into(),
+                decimals: 6,
+                total_supply: Uint128::new(1_000_000_000),
+            };
+            SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+        },
+        _ => SystemResult::Err(SystemError::NoSuchContract {
+            addr: "not a cw20 token".into(),
+        }),
+    });
+}
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
+    let mut deps = mock_dependencies();
+
+    mock_cw20(&mut deps.querier);
+
+    execute::register(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("larry", &[fee()]),
+        CW20.into(),
+        "uastro".into(),
+    )
+    .unwrap();
+
+    deps
+}
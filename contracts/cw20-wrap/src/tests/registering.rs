@@ -0,0 +1,98 @@
+use cosmwasm_std::{
+    testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR},
+    to_binary, SubMsg, WasmMsg,
+};
+use cw_bank::msg::Metadata;
+use cw_token_factory::msg as token_factory;
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::WrappedResponse,
+    query,
+    tests::{fee, mock_cw20, setup_test, CW20, DENOM},
+    TOKEN_FACTORY,
+};
+
+#[test]
+fn already_registered() {
+    let mut deps = setup_test();
+
+    // the setup function has registered the cw20 token already
+    let err = execute::register(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[fee()]),
+        CW20.into(),
+        "uastro2".into(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::already_registered(CW20));
+
+    let res = query::wrapped(deps.as_ref(), CW20.into()).unwrap();
+    assert_eq!(
+        res,
+        WrappedResponse {
+            cw20: CW20.into(),
+            denom: DENOM.into(),
+        },
+    );
+
+    let res = query::cw20(deps.as_ref(), DENOM.into()).unwrap();
+    assert_eq!(res.cw20, CW20);
+
+    let res = query::all_wrapped(deps.as_ref(), None, None).unwrap();
+    assert_eq!(res.len(), 1);
+}
+
+#[test]
+fn creating_denom() {
+    let mut deps = mock_dependencies();
+
+    mock_cw20(&mut deps.querier);
+
+    let res = execute::register(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("larry", &[fee()]),
+        CW20.into(),
+        "uastro".into(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: TOKEN_FACTORY.into(),
+            msg: to_binary(&token_factory::ExecuteMsg::CreateToken {
+                nonce: "uastro".into(),
+                admin: MOCK_CONTRACT_ADDR.into(),
+                after_transfer_hook: None,
+                hook_failure_policy: token_factory::HookFailurePolicy::Revert,
+                before_send_hook: None,
+                metadata: Some(Metadata {
+                    symbol: "ASTRO".into(),
+                    decimals: 6,
+                    description: Some("Astroport, wrapped from cw20 astro".into()),
+                }),
+                max_supply: None,
+            })
+            .unwrap(),
+            funds: vec![fee()],
+        })],
+    );
+}
+
+#[test]
+fn not_cw20() {
+    let mut deps = setup_test();
+
+    let err = execute::register(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("larry", &[fee()]),
+        "notcw20".into(),
+        "unot".into(),
+    );
+    assert!(matches!(err, Err(ContractError::Std(_))));
+}
@@ -0,0 +1,115 @@
+use cosmwasm_std::{
+    coin,
+    testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+    to_binary, SubMsg, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_token_factory::msg as token_factory;
+use cw_utils::PaymentError;
+
+use crate::{
+    error::ContractError,
+    execute,
+    tests::{setup_test, CW20, DENOM},
+    TOKEN_FACTORY,
+};
+
+#[test]
+fn wrapping() {
+    let mut deps = setup_test();
+
+    // only registered cw20 tokens can be wrapped
+    let err = execute::wrap(
+        deps.as_mut(),
+        mock_info("notcw20", &[]),
+        "alice".into(),
+        Uint128::new(12345),
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::not_registered("notcw20"));
+
+    // the sender receives the native coins if no recipient is given
+    let res = execute::wrap(
+        deps.as_mut(),
+        mock_info(CW20, &[]),
+        "alice".into(),
+        Uint128::new(12345),
+        None,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![mint_msg("alice", 12345)]);
+
+    let res = execute::wrap(
+        deps.as_mut(),
+        mock_info(CW20, &[]),
+        "alice".into(),
+        Uint128::new(12345),
+        Some("bob".into()),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![mint_msg("bob", 12345)]);
+}
+
+#[test]
+fn unwrapping() {
+    let mut deps = setup_test();
+
+    let err = execute::unwrap(deps.as_mut(), mock_env(), mock_info("alice", &[]), None)
+        .unwrap_err();
+    assert_eq!(err, PaymentError::NoFunds {}.into());
+
+    let err = execute::unwrap(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[coin(12345, "uosmo")]),
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::not_wrapped("uosmo"));
+
+    let res = execute::unwrap(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[coin(12345, DENOM)]),
+        Some("bob".into()),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: TOKEN_FACTORY.into(),
+                msg: to_binary(&token_factory::ExecuteMsg::Burn {
+                    from: MOCK_CONTRACT_ADDR.into(),
+                    denom: DENOM.into(),
+                    amount: Uint128::new(12345),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: CW20.into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "bob".into(),
+                    amount: Uint128::new(12345),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ],
+    );
+}
+
+fn mint_msg(to: &str, amount: u128) -> SubMsg {
+    SubMsg::new(WasmMsg::Execute {
+        contract_addr: TOKEN_FACTORY.into(),
+        msg: to_binary(&token_factory::ExecuteMsg::Mint {
+            to: to.into(),
+            denom: DENOM.into(),
+            amount: Uint128::new(amount),
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
@@ -0,0 +1,66 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::{ExecuteMsg, InstantiateMsg, QueryMsg},
+    query,
+};
+
+pub const CONTRACT_NAME: &str = "crates.io:cw-merkle-airdrop";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    execute::init(deps)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateAirdrop {
+            merkle_root,
+            expiration,
+        } => execute::create_airdrop(deps, &env.block, info, merkle_root, expiration),
+        ExecuteMsg::Claim {
+            id,
+            amount,
+            proof,
+        } => execute::claim(deps, &env.block, info, id, amount, proof),
+        ExecuteMsg::Clawback {
+            id,
+            to,
+        } => execute::clawback(deps, &env.block, info, id, to),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Airdrop {
+            id,
+        } => to_binary(&query::airdrop(deps, id)?),
+        QueryMsg::Airdrops {
+            start_after,
+            limit,
+        } => to_binary(&query::airdrops(deps, start_after, limit)?),
+        QueryMsg::Claimed {
+            id,
+            address,
+        } => to_binary(&query::claimed(deps, id, address)?),
+    }
+    .map_err(ContractError::from)
+}
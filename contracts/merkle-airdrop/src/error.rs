@@ -0,0 +1,118 @@
+use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[cfg_attr(any(test, feature = "library"), derive(PartialEq))]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("{0}")]
+    Hex(#[from] hex::FromHexError),
+
+    #[error("merkle root and proof hashes must be 32 bytes")]
+    InvalidHashLength,
+
+    #[error("expiration height {expiration} is not after the current height")]
+    InvalidExpiration {
+        expiration: u64,
+    },
+
+    #[error("airdrop {id} not found")]
+    AirdropNotFound {
+        id: u64,
+    },
+
+    #[error("{address} has already claimed airdrop {id}")]
+    AlreadyClaimed {
+        id: u64,
+        address: String,
+    },
+
+    #[error("merkle proof is invalid")]
+    InvalidProof,
+
+    #[error("airdrop {id} doesn't have enough coins left for the claim")]
+    Exhausted {
+        id: u64,
+    },
+
+    #[error("airdrop {id} expired at height {expiration}")]
+    Expired {
+        id: u64,
+        expiration: u64,
+    },
+
+    #[error("airdrop {id} doesn't expire until height {expiration}")]
+    NotExpired {
+        id: u64,
+        expiration: u64,
+    },
+
+    #[error("sender is not the creator of airdrop {id}")]
+    NotCreator {
+        id: u64,
+    },
+
+    #[error("airdrop {id} has already been clawed back")]
+    AlreadyClawedBack {
+        id: u64,
+    },
+}
+
+impl ContractError {
+    pub fn invalid_expiration(expiration: u64) -> Self {
+        Self::InvalidExpiration {
+            expiration,
+        }
+    }
+
+    pub fn airdrop_not_found(id: u64) -> Self {
+        Self::AirdropNotFound {
+            id,
+        }
+    }
+
+    pub fn already_claimed(id: u64, address: impl Into<String>) -> Self {
+        Self::AlreadyClaimed {
+            id,
+            address: address.into(),
+        }
+    }
+
+    pub fn exhausted(id: u64) -> Self {
+        Self::Exhausted {
+            id,
+        }
+    }
+
+    pub fn expired(id: u64, expiration: u64) -> Self {
+        Self::Expired {
+            id,
+            expiration,
+        }
+    }
+
+    pub fn not_expired(id: u64, expiration: u64) -> Self {
+        Self::NotExpired {
+            id,
+            expiration,
+        }
+    }
+
+    pub fn not_creator(id: u64) -> Self {
+        Self::NotCreator {
+            id,
+        }
+    }
+
+    pub fn already_clawed_back(id: u64) -> Self {
+        Self::AlreadyClawedBack {
+            id,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use cosmwasm_std::{
+    coins, to_binary, BlockInfo, DepsMut, Empty, MessageInfo, Response, Uint128, WasmMsg,
+};
+use cw_bank::msg as bank;
+use cw_utils::one_coin;
+
+use crate::{
+    error::ContractError,
+    helpers::{decode_hash, verify_proof},
+    msg::Airdrop,
+    state::{AIRDROPS, AIRDROP_COUNT, CLAIMED},
+    BANK,
+};
+
+pub fn init(deps: DepsMut) -> Result<Response, ContractError> {
+    AIRDROP_COUNT.save(deps.storage, &0)?;
+
+    Ok(Response::default())
+}
+
+pub fn create_airdrop(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    merkle_root: String,
+    expiration: u64,
+) -> Result<Response, ContractError> {
+    let coin = one_coin(&info)?;
+
+    decode_hash(&merkle_root)?;
+
+    if expiration <= block.height {
+        return Err(ContractError::invalid_expiration(expiration));
+    }
+
+    let id = AIRDROP_COUNT.update(deps.storage, |count| -> Result<_, ContractError> {
+        Ok(count + 1)
+    })?;
+
+    AIRDROPS.save(
+        deps.storage,
+        id,
+        &Airdrop {
+            creator: info.sender.clone(),
+            merkle_root,
+            denom: coin.denom,
+            total: coin.amount,
+            claimed: Uint128::zero(),
+            expiration,
+            clawed_back: false,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "merkle-airdrop/create_airdrop")
+        .add_attribute("creator", info.sender)
+        .add_attribute("id", id.to_string()))
+}
+
+pub fn claim(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    id: u64,
+    amount: Uint128,
+    proof: Vec<String>,
+) -> Result<Response, ContractError> {
+    let Some(mut airdrop) = AIRDROPS.may_load(deps.storage, id)? else {
+        return Err(ContractError::airdrop_not_found(id));
+    };
+
+    if block.height > airdrop.expiration {
+        return Err(ContractError::expired(id, airdrop.expiration));
+    }
+
+    if CLAIMED.has(deps.storage, (id, &info.sender)) {
+        return Err(ContractError::already_claimed(id, info.sender));
+    }
+
+    verify_proof(&airdrop.merkle_root, &format!("{}{amount}", info.sender), &proof)?;
+
+    // the contract holds the coins of all airdrops, so one whose merkle tree
+    // adds up to more than it was funded with mustn't eat into the others
+    airdrop.claimed = airdrop.claimed.checked_add(amount)?;
+    if airdrop.claimed > airdrop.total {
+        return Err(ContractError::exhausted(id));
+    }
+
+    CLAIMED.save(deps.storage, (id, &info.sender), &Empty {})?;
+    AIRDROPS.save(deps.storage, id, &airdrop)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "merkle-airdrop/claim")
+        .add_attribute("id", id.to_string())
+        .add_attribute("address", &info.sender)
+        .add_attribute("coin", format!("{amount}{}", airdrop.denom))
+        .add_message(send_msg(info.sender.into(), amount, airdrop.denom)?))
+}
+
+pub fn clawback(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    id: u64,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    let Some(mut airdrop) = AIRDROPS.may_load(deps.storage, id)? else {
+        return Err(ContractError::airdrop_not_found(id));
+    };
+
+    if info.sender != airdrop.creator {
+        return Err(ContractError::not_creator(id));
+    }
+
+    if block.height <= airdrop.expiration {
+        return Err(ContractError::not_expired(id, airdrop.expiration));
+    }
+
+    if airdrop.clawed_back {
+        return Err(ContractError::already_clawed_back(id));
+    }
+
+    airdrop.clawed_back = true;
+    AIRDROPS.save(deps.storage, id, &airdrop)?;
+
+    let to = to.unwrap_or_else(|| info.sender.into());
+    deps.api.addr_validate(&to)?;
+
+    let unclaimed = airdrop.total - airdrop.claimed;
+
+    let mut res = Response::new()
+        .add_attribute("action", "merkle-airdrop/clawback")
+        .add_attribute("id", id.to_string())
+        .add_attribute("to", &to)
+        .add_attribute("coin", format!("{unclaimed}{}", airdrop.denom));
+
+    if !unclaimed.is_zero() {
+        res = res.add_message(send_msg(to, unclaimed, airdrop.denom)?);
+    }
+
+    Ok(res)
+}
+
+/// Compose the bank message that sends coins from this contract.
+fn send_msg(to: String, amount: Uint128, denom: String) -> Result<WasmMsg, ContractError> {
+    Ok(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::Send {
+            to,
+            coins: coins(amount.u128(), denom),
+        })?,
+        funds: vec![],
+    })
+}
@@ -0,0 +1,39 @@
+use cw_sdk::hash::{sha256, HASH_LENGTH};
+
+use crate::error::ContractError;
+
+/// Decode a hex-encoded SHA-256 hash.
+pub(crate) fn decode_hash(hash: &str) -> Result<Vec<u8>, ContractError> {
+    let bytes = hex::decode(hash)?;
+    if bytes.len() != HASH_LENGTH {
+        return Err(ContractError::InvalidHashLength);
+    }
+    Ok(bytes)
+}
+
+/// Verify that the leaf `{address}{amount}` is in the merkle tree of the given
+/// root. Each pair of sibling nodes is hashed in ascending order, so the proof
+/// doesn't need to say which side each sibling is on.
+pub(crate) fn verify_proof(
+    merkle_root: &str,
+    leaf: &str,
+    proof: &[String],
+) -> Result<(), ContractError> {
+    let mut hash = sha256(leaf.as_bytes());
+
+    for sibling in proof {
+        let sibling = decode_hash(sibling)?;
+        let (first, second) = if hash <= sibling {
+            (hash, sibling)
+        } else {
+            (sibling, hash)
+        };
+        hash = sha256(&[first, second].concat());
+    }
+
+    if hash != decode_hash(merkle_root)? {
+        return Err(ContractError::InvalidProof);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+pub mod contract;
+pub mod error;
+pub mod execute;
+pub mod helpers;
+pub mod msg;
+pub mod query;
+pub mod state;
+
+#[cfg(test)]
+mod tests;
+
+/// The bank contract's label
+pub const BANK: &str = "bank";
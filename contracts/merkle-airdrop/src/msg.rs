@@ -0,0 +1,100 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Uint128};
+
+#[cw_serde]
+pub struct Airdrop {
+    /// The account who created and funded the airdrop, and who may claw back
+    /// the unclaimed coins once it expires
+    pub creator: Addr,
+
+    /// Hex-encoded root of the merkle tree whose leaves are the SHA-256 hashes
+    /// of `{address}{amount}` of every eligible account
+    pub merkle_root: String,
+
+    pub denom: String,
+
+    /// The amount the airdrop was funded with
+    pub total: Uint128,
+
+    /// The amount claimed so far
+    pub claimed: Uint128,
+
+    /// The block height after which coins can no longer be claimed, and the
+    /// creator may claw back the rest
+    pub expiration: u64,
+
+    /// Whether the unclaimed coins have been clawed back
+    pub clawed_back: bool,
+}
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Create an airdrop of the coin sent along with this message.
+    CreateAirdrop {
+        merkle_root: String,
+        expiration: u64,
+    },
+
+    /// Claim the sender's allocation in an airdrop, proving it with the
+    /// hex-encoded hashes of the merkle tree nodes from the leaf up.
+    Claim {
+        id: u64,
+        amount: Uint128,
+        proof: Vec<String>,
+    },
+
+    /// Send the unclaimed coins of an expired airdrop back.
+    /// Only callable by the airdrop's creator.
+    Clawback {
+        id: u64,
+        /// The account to receive the coins.
+        /// Default to the creator if not provided.
+        to: Option<String>,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Query a single airdrop by id
+    #[returns(AirdropResponse)]
+    Airdrop {
+        id: u64,
+    },
+
+    /// Enumerate all airdrops
+    #[returns(Vec<AirdropResponse>)]
+    Airdrops {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Query whether an account has claimed its allocation in an airdrop
+    #[returns(ClaimedResponse)]
+    Claimed {
+        id: u64,
+        address: String,
+    },
+}
+
+#[cw_serde]
+pub struct AirdropResponse {
+    pub id: u64,
+    pub creator: String,
+    pub merkle_root: String,
+    pub denom: String,
+    pub total: Uint128,
+    pub claimed: Uint128,
+    pub expiration: u64,
+    pub clawed_back: bool,
+}
+
+#[cw_serde]
+pub struct ClaimedResponse {
+    pub id: u64,
+    pub address: String,
+    pub claimed: bool,
+}
@@ -0,0 +1,49 @@
+use cosmwasm_std::{Deps, StdResult};
+use cw_paginate::paginate_map;
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::{Airdrop, AirdropResponse, ClaimedResponse},
+    state::{AIRDROPS, CLAIMED},
+};
+
+pub fn airdrop(deps: Deps, id: u64) -> Result<AirdropResponse, ContractError> {
+    let Some(airdrop) = AIRDROPS.may_load(deps.storage, id)? else {
+        return Err(ContractError::airdrop_not_found(id));
+    };
+    Ok(airdrop_response(id, airdrop))
+}
+
+pub fn airdrops(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<AirdropResponse>> {
+    let start = start_after.map(Bound::exclusive);
+    paginate_map(AIRDROPS, deps.storage, start, limit, |id, airdrop| {
+        Ok(airdrop_response(id, airdrop))
+    })
+}
+
+pub fn claimed(deps: Deps, id: u64, address: String) -> StdResult<ClaimedResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    Ok(ClaimedResponse {
+        id,
+        claimed: CLAIMED.has(deps.storage, (id, &addr)),
+        address,
+    })
+}
+
+fn airdrop_response(id: u64, airdrop: Airdrop) -> AirdropResponse {
+    AirdropResponse {
+        id,
+        creator: airdrop.creator.into(),
+        merkle_root: airdrop.merkle_root,
+        denom: airdrop.denom,
+        total: airdrop.total,
+        claimed: airdrop.claimed,
+        expiration: airdrop.expiration,
+        clawed_back: airdrop.clawed_back,
+    }
+}
@@ -0,0 +1,14 @@
+use cosmwasm_std::{Addr, Empty};
+use cw_storage_plus::{Item, Map};
+
+use crate::msg::Airdrop;
+
+/// The total number of airdrops created, which is also the id of the latest
+pub const AIRDROP_COUNT: Item<u64> = Item::new("airdrop_count");
+
+/// Airdrops indexed by id
+pub const AIRDROPS: Map<u64, Airdrop> = Map::new("airdrops");
+
+/// Accounts who have claimed their allocations, indexed by airdrop id and
+/// address
+pub const CLAIMED: Map<(u64, &Addr), Empty> = Map::new("claimed");
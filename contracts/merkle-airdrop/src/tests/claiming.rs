@@ -0,0 +1,135 @@
+use cosmwasm_std::{coins, testing::mock_info, to_binary, SubMsg, Uint128, WasmMsg};
+use cw_bank::msg as bank;
+
+use crate::{
+    error::ContractError,
+    execute,
+    query,
+    tests::{block_at, merkle_tree, setup_test, CREATOR, DENOM},
+    BANK,
+};
+
+fn send_msg(to: &str, amount: u128) -> SubMsg {
+    SubMsg::new(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::Send {
+            to: to.into(),
+            coins: coins(amount, DENOM),
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
+
+#[test]
+fn claiming() {
+    let mut deps = setup_test();
+
+    let (_, [alice_proof, bob_proof, _]) = merkle_tree();
+
+    // can't claim more than the allocation
+    let err = execute::claim(
+        deps.as_mut(),
+        &block_at(1),
+        mock_info("alice", &[]),
+        1,
+        Uint128::new(101),
+        alice_proof.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidProof);
+
+    // can't claim with another account's proof
+    let err = execute::claim(
+        deps.as_mut(),
+        &block_at(1),
+        mock_info("alice", &[]),
+        1,
+        Uint128::new(200),
+        bob_proof,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidProof);
+
+    let res = execute::claim(
+        deps.as_mut(),
+        &block_at(1),
+        mock_info("alice", &[]),
+        1,
+        Uint128::new(100),
+        alice_proof.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![send_msg("alice", 100)]);
+
+    let res = query::claimed(deps.as_ref(), 1, "alice".into()).unwrap();
+    assert!(res.claimed);
+
+    let res = query::airdrop(deps.as_ref(), 1).unwrap();
+    assert_eq!(res.claimed, Uint128::new(100));
+
+    // can't claim twice
+    let err = execute::claim(
+        deps.as_mut(),
+        &block_at(2),
+        mock_info("alice", &[]),
+        1,
+        Uint128::new(100),
+        alice_proof,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::already_claimed(1, "alice"));
+}
+
+#[test]
+fn expiring_and_clawing_back() {
+    let mut deps = setup_test();
+
+    let (_, [alice_proof, _, charlie_proof]) = merkle_tree();
+
+    execute::claim(
+        deps.as_mut(),
+        &block_at(100),
+        mock_info("charlie", &[]),
+        1,
+        Uint128::new(300),
+        charlie_proof,
+    )
+    .unwrap();
+
+    // can't claim after expiration
+    let err = execute::claim(
+        deps.as_mut(),
+        &block_at(101),
+        mock_info("alice", &[]),
+        1,
+        Uint128::new(100),
+        alice_proof,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::expired(1, block_at(100).height));
+
+    // can't claw back before expiration
+    let err = execute::clawback(deps.as_mut(), &block_at(100), mock_info(CREATOR, &[]), 1, None)
+        .unwrap_err();
+    assert_eq!(err, ContractError::not_expired(1, block_at(100).height));
+
+    // only the creator can claw back
+    let err = execute::clawback(deps.as_mut(), &block_at(101), mock_info("alice", &[]), 1, None)
+        .unwrap_err();
+    assert_eq!(err, ContractError::not_creator(1));
+
+    let res = execute::clawback(
+        deps.as_mut(),
+        &block_at(101),
+        mock_info(CREATOR, &[]),
+        1,
+        Some("treasury".into()),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![send_msg("treasury", 300)]);
+
+    let err = execute::clawback(deps.as_mut(), &block_at(102), mock_info(CREATOR, &[]), 1, None)
+        .unwrap_err();
+    assert_eq!(err, ContractError::already_clawed_back(1));
+}
@@ -0,0 +1,65 @@
+use cosmwasm_std::{coin, testing::mock_info, Uint128};
+use cw_utils::PaymentError;
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::AirdropResponse,
+    query,
+    tests::{block_at, merkle_tree, setup_test, CREATOR, DENOM},
+};
+
+#[test]
+fn creating() {
+    let mut deps = setup_test();
+
+    let (merkle_root, _) = merkle_tree();
+
+    let res = query::airdrop(deps.as_ref(), 1).unwrap();
+    assert_eq!(
+        res,
+        AirdropResponse {
+            id: 1,
+            creator: CREATOR.into(),
+            merkle_root: merkle_root.clone(),
+            denom: DENOM.into(),
+            total: Uint128::new(600),
+            claimed: Uint128::zero(),
+            expiration: block_at(100).height,
+            clawed_back: false,
+        },
+    );
+
+    // must be funded with exactly one coin
+    let err = execute::create_airdrop(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info(CREATOR, &[]),
+        merkle_root.clone(),
+        block_at(100).height,
+    )
+    .unwrap_err();
+    assert_eq!(err, PaymentError::NoFunds {}.into());
+
+    // must expire in the future
+    let err = execute::create_airdrop(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info(CREATOR, &[coin(600, DENOM)]),
+        merkle_root,
+        block_at(0).height,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::invalid_expiration(block_at(0).height));
+
+    // the merkle root must be a hex-encoded SHA-256 hash
+    let err = execute::create_airdrop(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info(CREATOR, &[coin(600, DENOM)]),
+        "abcd".into(),
+        block_at(100).height,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidHashLength);
+}
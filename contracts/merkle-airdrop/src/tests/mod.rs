@@ -0,0 +1,73 @@
+mod claiming;
+mod creating;
+
+use cosmwasm_std::{
+    coin,
+    testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+    BlockInfo, Empty, OwnedDeps,
+};
+use cw_sdk::hash::sha256;
+
+use crate::execute;
+
+const CREATOR: &str = "larry";
+const DENOM: &str = "factory/larry/uastro";
+
+/// The allocations in the test airdrop
+const ALLOCATIONS: [(&str, u128); 3] = [("alice", 100), ("bob", 200), ("charlie", 300)];
+
+fn hash_pair(a: &[u8], b: &[u8]) -> Vec<u8> {
+    if a <= b {
+        sha256(&[a, b].concat())
+    } else {
+        sha256(&[b, a].concat())
+    }
+}
+
+/// The merkle root of the test airdrop, and the proof of each allocation.
+/// The tree is:
+///
+/// ```plain
+///         root
+///        /    \
+///      ab      c
+///     /  \
+///    a    b
+/// ```
+fn merkle_tree() -> (String, [Vec<String>; 3]) {
+    let [a, b, c] =
+        ALLOCATIONS.map(|(address, amount)| sha256(format!("{address}{amount}").as_bytes()));
+    let ab = hash_pair(&a, &b);
+    let root = hash_pair(&ab, &c);
+    let proofs = [
+        vec![hex::encode(&b), hex::encode(&c)],
+        vec![hex::encode(&a), hex::encode(&c)],
+        vec![hex::encode(&ab)],
+    ];
+    (hex::encode(root), proofs)
+}
+
+/// The mock block, `blocks` after the mock env's
+fn block_at(blocks: u64) -> BlockInfo {
+    let mut block = mock_env().block;
+    block.height += blocks;
+    block
+}
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
+    let mut deps = mock_dependencies();
+
+    execute::init(deps.as_mut()).unwrap();
+
+    let (merkle_root, _) = merkle_tree();
+    execute::create_airdrop(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info(CREATOR, &[coin(600, DENOM)]),
+        merkle_root,
+        block_at(100).height,
+    )
+    .unwrap();
+
+    deps
+}
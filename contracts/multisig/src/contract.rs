@@ -0,0 +1,68 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::{ExecuteMsg, InstantiateMsg, QueryMsg},
+    query,
+};
+
+pub const CONTRACT_NAME: &str = "crates.io:cw-multisig";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    execute::init(deps, msg.members, msg.threshold)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Propose {
+            description,
+            msgs,
+        } => execute::propose(deps, info, description, msgs),
+        ExecuteMsg::Confirm {
+            id,
+        } => execute::confirm(deps, info, id),
+        ExecuteMsg::Revoke {
+            id,
+        } => execute::revoke(deps, info, id),
+        ExecuteMsg::Execute {
+            id,
+        } => execute::execute(deps, info, id),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query::config(deps)?),
+        QueryMsg::Proposal {
+            id,
+        } => to_binary(&query::proposal(deps, id)?),
+        QueryMsg::Proposals {
+            start_after,
+            limit,
+        } => to_binary(&query::proposals(deps, start_after, limit)?),
+        QueryMsg::Confirmations {
+            id,
+            start_after,
+            limit,
+        } => to_binary(&query::confirmations(deps, id, start_after, limit)?),
+    }
+    .map_err(ContractError::from)
+}
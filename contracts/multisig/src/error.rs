@@ -0,0 +1,104 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[cfg_attr(any(test, feature = "library"), derive(PartialEq))]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("threshold must be between 1 and the number of members {members}, got {threshold}")]
+    InvalidThreshold {
+        threshold: u32,
+        members: u32,
+    },
+
+    #[error("{member} is listed more than once as a member")]
+    DuplicateMember {
+        member: String,
+    },
+
+    #[error("sender is not a member of the multisig")]
+    NotMember,
+
+    #[error("proposal must contain at least one message")]
+    EmptyProposal,
+
+    #[error("proposal {id} not found")]
+    ProposalNotFound {
+        id: u64,
+    },
+
+    #[error("proposal {id} has already been executed")]
+    AlreadyExecuted {
+        id: u64,
+    },
+
+    #[error("{member} has already confirmed proposal {id}")]
+    AlreadyConfirmed {
+        id: u64,
+        member: String,
+    },
+
+    #[error("{member} has not confirmed proposal {id}")]
+    NotConfirmed {
+        id: u64,
+        member: String,
+    },
+
+    #[error("proposal {id} has {confirmations} confirmations, below the threshold {threshold}")]
+    ThresholdNotReached {
+        id: u64,
+        confirmations: u32,
+        threshold: u32,
+    },
+}
+
+impl ContractError {
+    pub fn invalid_threshold(threshold: u32, members: usize) -> Self {
+        Self::InvalidThreshold {
+            threshold,
+            members: members as u32,
+        }
+    }
+
+    pub fn duplicate_member(member: impl Into<String>) -> Self {
+        Self::DuplicateMember {
+            member: member.into(),
+        }
+    }
+
+    pub fn proposal_not_found(id: u64) -> Self {
+        Self::ProposalNotFound {
+            id,
+        }
+    }
+
+    pub fn already_executed(id: u64) -> Self {
+        Self::AlreadyExecuted {
+            id,
+        }
+    }
+
+    pub fn already_confirmed(id: u64, member: impl Into<String>) -> Self {
+        Self::AlreadyConfirmed {
+            id,
+            member: member.into(),
+        }
+    }
+
+    pub fn not_confirmed(id: u64, member: impl Into<String>) -> Self {
+        Self::NotConfirmed {
+            id,
+            member: member.into(),
+        }
+    }
+
+    pub fn threshold_not_reached(id: u64, confirmations: u32, threshold: u32) -> Self {
+        Self::ThresholdNotReached {
+            id,
+            confirmations,
+            threshold,
+        }
+    }
+}
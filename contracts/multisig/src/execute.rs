@@ -0,0 +1,148 @@
+use cosmwasm_std::{Addr, CosmosMsg, Deps, DepsMut, Empty, MessageInfo, Response, Storage};
+
+use crate::{
+    error::ContractError,
+    msg::Proposal,
+    state::{CONFIRMATIONS, MEMBERS, PROPOSALS, PROPOSAL_COUNT, THRESHOLD},
+};
+
+pub fn init(
+    deps: DepsMut,
+    members: Vec<String>,
+    threshold: u32,
+) -> Result<Response, ContractError> {
+    if threshold == 0 || threshold as usize > members.len() {
+        return Err(ContractError::invalid_threshold(threshold, members.len()));
+    }
+
+    for member in &members {
+        let member_addr = deps.api.addr_validate(member)?;
+        if MEMBERS.has(deps.storage, &member_addr) {
+            return Err(ContractError::duplicate_member(member));
+        }
+        MEMBERS.save(deps.storage, &member_addr, &Empty {})?;
+    }
+
+    THRESHOLD.save(deps.storage, &threshold)?;
+    PROPOSAL_COUNT.save(deps.storage, &0)?;
+
+    Ok(Response::default())
+}
+
+pub fn propose(
+    deps: DepsMut,
+    info: MessageInfo,
+    description: String,
+    msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    assert_member(deps.as_ref(), &info.sender)?;
+
+    if msgs.is_empty() {
+        return Err(ContractError::EmptyProposal);
+    }
+
+    let id = PROPOSAL_COUNT.update(deps.storage, |count| -> Result<_, ContractError> {
+        Ok(count + 1)
+    })?;
+
+    // the proposer confirms the proposal by proposing it
+    CONFIRMATIONS.save(deps.storage, (id, &info.sender), &Empty {})?;
+    PROPOSALS.save(
+        deps.storage,
+        id,
+        &Proposal {
+            proposer: info.sender.clone(),
+            description,
+            msgs,
+            confirmations: 1,
+            executed: false,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "multisig/propose")
+        .add_attribute("proposer", info.sender)
+        .add_attribute("id", id.to_string()))
+}
+
+pub fn confirm(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    assert_member(deps.as_ref(), &info.sender)?;
+
+    let mut proposal = load_pending_proposal(deps.storage, id)?;
+
+    if CONFIRMATIONS.has(deps.storage, (id, &info.sender)) {
+        return Err(ContractError::already_confirmed(id, info.sender));
+    }
+
+    CONFIRMATIONS.save(deps.storage, (id, &info.sender), &Empty {})?;
+    proposal.confirmations += 1;
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "multisig/confirm")
+        .add_attribute("member", info.sender)
+        .add_attribute("id", id.to_string())
+        .add_attribute("confirmations", proposal.confirmations.to_string()))
+}
+
+pub fn revoke(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    assert_member(deps.as_ref(), &info.sender)?;
+
+    let mut proposal = load_pending_proposal(deps.storage, id)?;
+
+    if !CONFIRMATIONS.has(deps.storage, (id, &info.sender)) {
+        return Err(ContractError::not_confirmed(id, info.sender));
+    }
+
+    CONFIRMATIONS.remove(deps.storage, (id, &info.sender));
+    proposal.confirmations -= 1;
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "multisig/revoke")
+        .add_attribute("member", info.sender)
+        .add_attribute("id", id.to_string())
+        .add_attribute("confirmations", proposal.confirmations.to_string()))
+}
+
+pub fn execute(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    assert_member(deps.as_ref(), &info.sender)?;
+
+    let mut proposal = load_pending_proposal(deps.storage, id)?;
+
+    let threshold = THRESHOLD.load(deps.storage)?;
+    if proposal.confirmations < threshold {
+        return Err(ContractError::threshold_not_reached(id, proposal.confirmations, threshold));
+    }
+
+    proposal.executed = true;
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "multisig/execute")
+        .add_attribute("executor", info.sender)
+        .add_attribute("id", id.to_string())
+        .add_messages(proposal.msgs))
+}
+
+/// Load a proposal that exists and hasn't been executed.
+fn load_pending_proposal(store: &dyn Storage, id: u64) -> Result<Proposal, ContractError> {
+    let Some(proposal) = PROPOSALS.may_load(store, id)? else {
+        return Err(ContractError::proposal_not_found(id));
+    };
+
+    if proposal.executed {
+        return Err(ContractError::already_executed(id));
+    }
+
+    Ok(proposal)
+}
+
+/// Assert that the sender is a member of the multisig.
+fn assert_member(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if !MEMBERS.has(deps.storage, sender) {
+        return Err(ContractError::NotMember);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, CosmosMsg};
+
+#[cw_serde]
+pub struct Proposal {
+    /// The member who created the proposal
+    pub proposer: Addr,
+
+    /// A human readable description of what the proposal does
+    pub description: String,
+
+    /// The messages to be executed by the multisig once the proposal has
+    /// enough confirmations
+    pub msgs: Vec<CosmosMsg>,
+
+    /// The number of members who have confirmed the proposal
+    pub confirmations: u32,
+
+    /// Whether the proposal has been executed
+    pub executed: bool,
+}
+
+/// The instantiate message only consists of addresses and numbers, so that the
+/// multisig can be instantiated by a genesis message, before any other contract
+/// or account exists.
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The accounts that may propose and confirm. Can't be changed later.
+    pub members: Vec<String>,
+
+    /// The number of confirmations a proposal needs to be executed. Must be at
+    /// least 1, and no more than the number of members.
+    pub threshold: u32,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Propose the multisig to execute the given messages. The proposer's
+    /// confirmation is counted automatically.
+    /// Only callable by a member.
+    Propose {
+        description: String,
+        msgs: Vec<CosmosMsg>,
+    },
+
+    /// Confirm a proposal that hasn't been executed.
+    /// Only callable by a member.
+    Confirm {
+        id: u64,
+    },
+
+    /// Withdraw the sender's confirmation of a proposal that hasn't been
+    /// executed.
+    /// Only callable by a member.
+    Revoke {
+        id: u64,
+    },
+
+    /// Execute a proposal that has reached the threshold.
+    /// Only callable by a member.
+    Execute {
+        id: u64,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Query the members and threshold
+    #[returns(ConfigResponse)]
+    Config {},
+
+    /// Query a single proposal by id
+    #[returns(ProposalResponse)]
+    Proposal {
+        id: u64,
+    },
+
+    /// Enumerate all proposals
+    #[returns(Vec<ProposalResponse>)]
+    Proposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Enumerate the members who have confirmed a proposal
+    #[returns(Vec<String>)]
+    Confirmations {
+        id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub members: Vec<String>,
+    pub threshold: u32,
+}
+
+#[cw_serde]
+pub struct ProposalResponse {
+    pub id: u64,
+    pub proposer: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg>,
+    pub confirmations: u32,
+    pub executed: bool,
+}
@@ -0,0 +1,61 @@
+use cosmwasm_std::{Deps, Order, StdResult};
+use cw_paginate::{paginate_map, paginate_map_prefix};
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::{ConfigResponse, Proposal, ProposalResponse},
+    state::{CONFIRMATIONS, MEMBERS, PROPOSALS, THRESHOLD},
+};
+
+pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+    let members = MEMBERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|member| member.map(String::from))
+        .collect::<StdResult<_>>()?;
+    Ok(ConfigResponse {
+        members,
+        threshold: THRESHOLD.load(deps.storage)?,
+    })
+}
+
+pub fn proposal(deps: Deps, id: u64) -> Result<ProposalResponse, ContractError> {
+    let Some(proposal) = PROPOSALS.may_load(deps.storage, id)? else {
+        return Err(ContractError::proposal_not_found(id));
+    };
+    Ok(proposal_response(id, proposal))
+}
+
+pub fn proposals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ProposalResponse>> {
+    let start = start_after.map(Bound::exclusive);
+    paginate_map(PROPOSALS, deps.storage, start, limit, |id, proposal| {
+        Ok(proposal_response(id, proposal))
+    })
+}
+
+pub fn confirmations(
+    deps: Deps,
+    id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let start = start_after.map(|member| Bound::ExclusiveRaw(member.into_bytes()));
+    paginate_map_prefix(CONFIRMATIONS, deps.storage, id, start, limit, |member, _| {
+        Ok(member.into())
+    })
+}
+
+fn proposal_response(id: u64, proposal: Proposal) -> ProposalResponse {
+    ProposalResponse {
+        id,
+        proposer: proposal.proposer.into(),
+        description: proposal.description,
+        msgs: proposal.msgs,
+        confirmations: proposal.confirmations,
+        executed: proposal.executed,
+    }
+}
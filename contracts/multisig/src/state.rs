@@ -0,0 +1,19 @@
+use cosmwasm_std::{Addr, Empty};
+use cw_storage_plus::{Item, Map};
+
+use crate::msg::Proposal;
+
+/// The accounts that may propose and confirm
+pub const MEMBERS: Map<&Addr, Empty> = Map::new("members");
+
+/// The number of confirmations a proposal needs to be executed
+pub const THRESHOLD: Item<u32> = Item::new("threshold");
+
+/// The total number of proposals created, which is also the id of the latest
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+
+/// Proposals indexed by id
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+
+/// Members who have confirmed each proposal, indexed by proposal id and member
+pub const CONFIRMATIONS: Map<(u64, &Addr), Empty> = Map::new("confirmations");
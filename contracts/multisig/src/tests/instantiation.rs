@@ -0,0 +1,42 @@
+use cosmwasm_std::testing::mock_dependencies;
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::ConfigResponse,
+    query,
+    tests::{setup_test, MEMBERS, THRESHOLD},
+};
+
+#[test]
+fn proper_instantiation() {
+    let deps = setup_test();
+
+    let cfg = query::config(deps.as_ref()).unwrap();
+    assert_eq!(
+        cfg,
+        ConfigResponse {
+            members: MEMBERS.map(String::from).to_vec(),
+            threshold: THRESHOLD,
+        },
+    );
+}
+
+#[test]
+fn invalid_threshold() {
+    let mut deps = mock_dependencies();
+
+    let err = execute::init(deps.as_mut(), vec!["alice".into()], 0).unwrap_err();
+    assert_eq!(err, ContractError::invalid_threshold(0, 1));
+
+    let err = execute::init(deps.as_mut(), vec!["alice".into()], 2).unwrap_err();
+    assert_eq!(err, ContractError::invalid_threshold(2, 1));
+}
+
+#[test]
+fn duplicate_member() {
+    let mut deps = mock_dependencies();
+
+    let err = execute::init(deps.as_mut(), vec!["alice".into(), "alice".into()], 1).unwrap_err();
+    assert_eq!(err, ContractError::duplicate_member("alice"));
+}
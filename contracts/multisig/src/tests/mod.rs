@@ -0,0 +1,28 @@
+mod instantiation;
+mod proposals;
+
+use cosmwasm_std::{
+    coins,
+    testing::{mock_dependencies, MockApi, MockQuerier, MockStorage},
+    BankMsg, CosmosMsg, Empty, OwnedDeps,
+};
+
+use crate::execute;
+
+const MEMBERS: [&str; 3] = ["alice", "bob", "charlie"];
+const THRESHOLD: u32 = 2;
+
+fn send_msg() -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        to_address: "jake".into(),
+        amount: coins(12345, "ustake"),
+    })
+}
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
+    let mut deps = mock_dependencies();
+
+    execute::init(deps.as_mut(), MEMBERS.map(String::from).to_vec(), THRESHOLD).unwrap();
+
+    deps
+}
@@ -0,0 +1,119 @@
+use cosmwasm_std::{testing::mock_info, SubMsg};
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::ProposalResponse,
+    query,
+    tests::{send_msg, setup_test},
+};
+
+#[test]
+fn not_member() {
+    let mut deps = setup_test();
+
+    let err = execute::propose(deps.as_mut(), mock_info("badguy", &[]), "".into(), vec![send_msg()])
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotMember);
+
+    execute::propose(deps.as_mut(), mock_info("alice", &[]), "".into(), vec![send_msg()]).unwrap();
+
+    let err = execute::confirm(deps.as_mut(), mock_info("badguy", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::NotMember);
+
+    let err = execute::execute(deps.as_mut(), mock_info("badguy", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::NotMember);
+}
+
+#[test]
+fn proposing() {
+    let mut deps = setup_test();
+
+    let err = execute::propose(deps.as_mut(), mock_info("alice", &[]), "".into(), vec![])
+        .unwrap_err();
+    assert_eq!(err, ContractError::EmptyProposal);
+
+    execute::propose(
+        deps.as_mut(),
+        mock_info("alice", &[]),
+        "pay jake".into(),
+        vec![send_msg()],
+    )
+    .unwrap();
+
+    // the proposer's confirmation is counted
+    let proposal = query::proposal(deps.as_ref(), 1).unwrap();
+    assert_eq!(
+        proposal,
+        ProposalResponse {
+            id: 1,
+            proposer: "alice".into(),
+            description: "pay jake".into(),
+            msgs: vec![send_msg()],
+            confirmations: 1,
+            executed: false,
+        },
+    );
+
+    let confirmations = query::confirmations(deps.as_ref(), 1, None, None).unwrap();
+    assert_eq!(confirmations, vec!["alice".to_string()]);
+
+    let err = query::proposal(deps.as_ref(), 2).unwrap_err();
+    assert_eq!(err, ContractError::proposal_not_found(2));
+}
+
+#[test]
+fn confirming_and_revoking() {
+    let mut deps = setup_test();
+
+    execute::propose(deps.as_mut(), mock_info("alice", &[]), "".into(), vec![send_msg()]).unwrap();
+
+    let err = execute::confirm(deps.as_mut(), mock_info("alice", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::already_confirmed(1, "alice"));
+
+    let err = execute::revoke(deps.as_mut(), mock_info("bob", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::not_confirmed(1, "bob"));
+
+    execute::confirm(deps.as_mut(), mock_info("bob", &[]), 1).unwrap();
+
+    let confirmations = query::confirmations(deps.as_ref(), 1, None, None).unwrap();
+    assert_eq!(confirmations, vec!["alice".to_string(), "bob".to_string()]);
+
+    execute::revoke(deps.as_mut(), mock_info("alice", &[]), 1).unwrap();
+
+    let proposal = query::proposal(deps.as_ref(), 1).unwrap();
+    assert_eq!(proposal.confirmations, 1);
+
+    let confirmations = query::confirmations(deps.as_ref(), 1, None, None).unwrap();
+    assert_eq!(confirmations, vec!["bob".to_string()]);
+}
+
+#[test]
+fn executing() {
+    let mut deps = setup_test();
+
+    execute::propose(deps.as_mut(), mock_info("alice", &[]), "".into(), vec![send_msg()]).unwrap();
+
+    // can't execute before the threshold is reached
+    let err = execute::execute(deps.as_mut(), mock_info("alice", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::threshold_not_reached(1, 1, 2));
+
+    execute::confirm(deps.as_mut(), mock_info("charlie", &[]), 1).unwrap();
+
+    // any member can execute once the threshold is reached
+    let res = execute::execute(deps.as_mut(), mock_info("bob", &[]), 1).unwrap();
+    assert_eq!(res.messages, vec![SubMsg::new(send_msg())]);
+
+    let proposal = query::proposal(deps.as_ref(), 1).unwrap();
+    assert!(proposal.executed);
+
+    // an executed proposal can't be executed again, or confirmed or revoked
+    let err = execute::execute(deps.as_mut(), mock_info("bob", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::already_executed(1));
+
+    let err = execute::confirm(deps.as_mut(), mock_info("bob", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::already_executed(1));
+
+    let err = execute::revoke(deps.as_mut(), mock_info("alice", &[]), 1).unwrap_err();
+    assert_eq!(err, ContractError::already_executed(1));
+}
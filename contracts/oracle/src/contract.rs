@@ -0,0 +1,88 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SudoMsg},
+    query,
+};
+
+pub const CONTRACT_NAME: &str = "crates.io:cw-oracle";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    execute::init(deps, &msg.owner, msg.feeders, msg.config)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateOwnership(action) => execute::update_ownership(
+            deps,
+            &env.block,
+            &info.sender,
+            action,
+        ),
+        ExecuteMsg::UpdateConfig(config) => execute::update_config(deps, info, config),
+        ExecuteMsg::UpdateFeeders {
+            add,
+            remove,
+        } => execute::update_feeders(deps, info, add, remove),
+        ExecuteMsg::FeedPrices {
+            prices,
+        } => execute::feed_prices(deps, &env.block, info, prices),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::ExtendVote {} => execute::extend_vote(),
+        SudoMsg::VerifyVoteExtension {
+            extension,
+            ..
+        } => execute::verify_vote_extension(&extension),
+        SudoMsg::ProcessVoteExtensions {
+            votes,
+            total_power,
+        } => execute::process_vote_extensions(deps, &env.block, votes, total_power),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Ownership {} => to_binary(&cw_ownable::get_ownership(deps.storage)?),
+        QueryMsg::Config {} => to_binary(&query::config(deps)?),
+        QueryMsg::Feeders {
+            start_after,
+            limit,
+        } => to_binary(&query::feeders(deps, start_after, limit)?),
+        QueryMsg::Price {
+            denom,
+        } => to_binary(&query::price(deps, &env.block, denom)?),
+        QueryMsg::Prices {
+            start_after,
+            limit,
+        } => to_binary(&query::prices(deps, start_after, limit)?),
+        QueryMsg::Twap {
+            denom,
+            window,
+        } => to_binary(&query::twap(deps, &env.block, denom, window)?),
+    }
+    .map_err(ContractError::from)
+}
@@ -0,0 +1,84 @@
+use cosmwasm_std::{Decimal, StdError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[cfg_attr(any(test, feature = "library"), derive(PartialEq))]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Ownership(#[from] cw_ownable::OwnershipError),
+
+    #[error("min_feeders must be at least 1")]
+    ZeroMinFeeders,
+
+    #[error("vote_quorum must be less than 1")]
+    VoteQuorumTooHigh,
+
+    #[error("sender is not a feeder")]
+    NotFeeder,
+
+    #[error("invalid price {price} for denom {denom}")]
+    InvalidPrice {
+        denom: String,
+        price: Decimal,
+    },
+
+    #[error("denom {denom} is reported more than once")]
+    DuplicateDenom {
+        denom: String,
+    },
+
+    #[error("no price found for denom {denom}")]
+    PriceNotFound {
+        denom: String,
+    },
+
+    #[error("price of denom {denom} is stale, last updated at {updated_at}")]
+    PriceStale {
+        denom: String,
+        updated_at: u64,
+    },
+
+    #[error("TWAP window {window} is longer than the maximum {max}")]
+    WindowTooLong {
+        window: u64,
+        max: u64,
+    },
+}
+
+impl ContractError {
+    pub fn invalid_price(denom: impl Into<String>, price: Decimal) -> Self {
+        Self::InvalidPrice {
+            denom: denom.into(),
+            price,
+        }
+    }
+
+    pub fn duplicate_denom(denom: impl Into<String>) -> Self {
+        Self::DuplicateDenom {
+            denom: denom.into(),
+        }
+    }
+
+    pub fn price_not_found(denom: impl Into<String>) -> Self {
+        Self::PriceNotFound {
+            denom: denom.into(),
+        }
+    }
+
+    pub fn price_stale(denom: impl Into<String>, updated_at: u64) -> Self {
+        Self::PriceStale {
+            denom: denom.into(),
+            updated_at,
+        }
+    }
+
+    pub fn window_too_long(window: u64, max: u64) -> Self {
+        Self::WindowTooLong {
+            window,
+            max,
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{
+    from_binary, from_slice, Addr, BlockInfo, Decimal, DepsMut, Empty, MessageInfo, Order,
+    Response, StdResult, Storage,
+};
+use cw_ownable::Action as OwnershipAction;
+use cw_sdk::ExtendedVote;
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::{Config, PriceFeed, Submission},
+    state::{CONFIG, FEEDERS, PRICES, SNAPSHOTS, SUBMISSIONS},
+};
+
+pub fn init(
+    deps: DepsMut,
+    owner: &str,
+    feeders: Vec<String>,
+    config: Config,
+) -> Result<Response, ContractError> {
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner))?;
+
+    validate_config(&config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    for feeder in &feeders {
+        let feeder_addr = deps.api.addr_validate(feeder)?;
+        FEEDERS.save(deps.storage, &feeder_addr, &Empty {})?;
+    }
+
+    Ok(Response::default())
+}
+
+pub fn update_ownership(
+    deps: DepsMut,
+    block: &BlockInfo,
+    sender: &Addr,
+    action: OwnershipAction,
+) -> Result<Response, ContractError> {
+    let ownership = cw_ownable::update_ownership(deps, block, sender, action)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "oracle/update_ownership")
+        .add_attributes(ownership.into_attributes()))
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    validate_config(&config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "oracle/update_config"))
+}
+
+pub fn update_feeders(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    for feeder in &add {
+        let feeder_addr = deps.api.addr_validate(feeder)?;
+        FEEDERS.save(deps.storage, &feeder_addr, &Empty {})?;
+    }
+
+    for feeder in &remove {
+        let feeder_addr = deps.api.addr_validate(feeder)?;
+        FEEDERS.remove(deps.storage, &feeder_addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "oracle/update_feeders")
+        .add_attribute("added", add.join(","))
+        .add_attribute("removed", remove.join(",")))
+}
+
+pub fn feed_prices(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    prices: Vec<PriceFeed>,
+) -> Result<Response, ContractError> {
+    if !FEEDERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::NotFeeder);
+    }
+
+    validate_prices(&prices)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let now = block.time.seconds();
+
+    let mut res = Response::new()
+        .add_attribute("action", "oracle/feed_prices")
+        .add_attribute("feeder", &info.sender);
+
+    for PriceFeed {
+        denom,
+        price,
+    } in prices
+    {
+        SUBMISSIONS.save(
+            deps.storage,
+            (&denom, &info.sender),
+            &Submission {
+                price,
+                time: now,
+            },
+        )?;
+
+        // the submissions of all feeders that are still fresh. submissions of
+        // feeders who have since been removed don't count
+        let mut fresh = vec![];
+        for item in SUBMISSIONS.prefix(&denom).range(deps.storage, None, None, Order::Ascending) {
+            let (feeder, submission) = item?;
+            let is_fresh = submission.time + cfg.staleness_window >= now;
+            if is_fresh && FEEDERS.has(deps.storage, &feeder) {
+                fresh.push(submission.price);
+            }
+        }
+
+        if fresh.len() < cfg.min_feeders as usize {
+            continue;
+        }
+
+        let median = median(fresh);
+        set_price(deps.storage, &cfg, &denom, median, now)?;
+        res = res.add_attribute(denom, median.to_string());
+    }
+
+    Ok(res)
+}
+
+/// The contract can't observe prices off-chain, so it doesn't extend votes.
+pub fn extend_vote() -> Result<Response, ContractError> {
+    Ok(Response::default())
+}
+
+/// A vote extension must either be empty, or be a JSON-encoded list of prices.
+pub fn verify_vote_extension(extension: &[u8]) -> Result<Response, ContractError> {
+    if !extension.is_empty() {
+        let prices: Vec<PriceFeed> = from_slice(extension)?;
+        validate_prices(&prices)?;
+    }
+
+    Ok(Response::default())
+}
+
+/// Set the price of each denom to the median of the prices the validators
+/// attached to their votes, weighted by voting power. Denoms reported by no
+/// more than `vote_quorum` of the total power keep their previous price.
+pub fn process_vote_extensions(
+    deps: DepsMut,
+    block: &BlockInfo,
+    votes: Vec<ExtendedVote>,
+    total_power: u64,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let now = block.time.seconds();
+
+    // the extensions were verified before the block was accepted, so they all
+    // decode correctly
+    let mut prices_by_denom: BTreeMap<String, Vec<(Decimal, u64)>> = BTreeMap::new();
+    for vote in votes {
+        if vote.extension.is_empty() {
+            continue;
+        }
+        let prices: Vec<PriceFeed> = from_binary(&vote.extension)?;
+        for PriceFeed {
+            denom,
+            price,
+        } in prices
+        {
+            prices_by_denom.entry(denom).or_default().push((price, vote.power));
+        }
+    }
+
+    let mut res = Response::new().add_attribute("action", "oracle/process_vote_extensions");
+
+    for (denom, prices) in prices_by_denom {
+        let reported_power: u128 = prices.iter().map(|(_, power)| *power as u128).sum();
+        if !has_quorum(reported_power, total_power.into(), cfg.vote_quorum) {
+            continue;
+        }
+
+        let median = weighted_median(prices);
+        set_price(deps.storage, &cfg, &denom, median, now)?;
+        res = res.add_attribute(denom, median.to_string());
+    }
+
+    Ok(res)
+}
+
+/// Update the latest price of a denom, and record a snapshot of it for TWAPs,
+/// pruning snapshots that are too old to be queried.
+fn set_price(
+    store: &mut dyn Storage,
+    cfg: &Config,
+    denom: &str,
+    price: Decimal,
+    now: u64,
+) -> StdResult<()> {
+    PRICES.save(store, denom, &(price, now))?;
+    SNAPSHOTS.save(store, (denom, now), &price)?;
+
+    // a TWAP over the longest window needs the last snapshot before the window
+    // starts, as the price at its start, so that one is kept
+    let cutoff = now.saturating_sub(cfg.max_twap_window);
+    let mut expired = SNAPSHOTS
+        .prefix(denom)
+        .keys(store, None, Some(Bound::exclusive(cutoff)), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    expired.pop();
+    for time in expired {
+        SNAPSHOTS.remove(store, (denom, time));
+    }
+
+    Ok(())
+}
+
+/// The median of the given prices. For an even number of prices, the average
+/// of the two in the middle.
+fn median(mut prices: Vec<Decimal>) -> Decimal {
+    prices.sort();
+    let mid = prices.len() / 2;
+    if prices.len() % 2 == 0 {
+        (prices[mid - 1] + prices[mid]) * Decimal::percent(50)
+    } else {
+        prices[mid]
+    }
+}
+
+/// Whether the reporting power is more than the quorum's fraction of the total
+/// power.
+fn has_quorum(reported_power: u128, total_power: u128, quorum: Decimal) -> bool {
+    total_power > 0 && Decimal::from_ratio(reported_power, total_power) > quorum
+}
+
+/// The lowest price such that the prices no higher than it have at least half
+/// of the total weight.
+fn weighted_median(mut prices: Vec<(Decimal, u64)>) -> Decimal {
+    prices.sort();
+    let total: u128 = prices.iter().map(|(_, power)| *power as u128).sum();
+    let mut cumulative = 0u128;
+    for (price, power) in &prices {
+        cumulative += *power as u128;
+        if cumulative * 2 >= total {
+            return *price;
+        }
+    }
+    // the prices aren't empty, since every denom comes with at least one
+    prices[prices.len() - 1].0
+}
+
+fn validate_config(cfg: &Config) -> Result<(), ContractError> {
+    if cfg.min_feeders == 0 {
+        return Err(ContractError::ZeroMinFeeders);
+    }
+
+    if cfg.vote_quorum >= Decimal::one() {
+        return Err(ContractError::VoteQuorumTooHigh);
+    }
+
+    Ok(())
+}
+
+fn validate_prices(prices: &[PriceFeed]) -> Result<(), ContractError> {
+    let mut denoms = vec![];
+    for PriceFeed {
+        denom,
+        price,
+    } in prices
+    {
+        if price.is_zero() {
+            return Err(ContractError::invalid_price(denom, *price));
+        }
+        if denoms.contains(&denom) {
+            return Err(ContractError::duplicate_denom(denom));
+        }
+        denoms.push(denom);
+    }
+
+    Ok(())
+}
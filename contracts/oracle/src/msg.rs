@@ -0,0 +1,124 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Decimal;
+use cw_ownable::{cw_ownable_execute, cw_ownable_query};
+
+pub use cw_sdk::vote_extensions::SudoMsg;
+
+#[cw_serde]
+pub struct Config {
+    /// How many seconds a price, or a feeder's submission, remains valid for
+    pub staleness_window: u64,
+
+    /// The least number of fresh submissions from feeders for a price to be
+    /// set
+    pub min_feeders: u32,
+
+    /// The longest window, in seconds, over which a TWAP can be queried. Price
+    /// snapshots older than this are pruned.
+    pub max_twap_window: u64,
+
+    /// The fraction of the total voting power that the validators reporting a
+    /// denom's price in their vote extensions must exceed for the price to be
+    /// set, e.g. two thirds. Must be less than one
+    pub vote_quorum: Decimal,
+}
+
+/// A price reported by a feeder or a validator
+#[cw_serde]
+pub struct PriceFeed {
+    pub denom: String,
+    pub price: Decimal,
+}
+
+/// A price reported by a feeder, and when
+#[cw_serde]
+pub struct Submission {
+    pub price: Decimal,
+    pub time: u64,
+}
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The account to be appointed as contract owner
+    pub owner: String,
+
+    /// The accounts allowed to submit prices
+    pub feeders: Vec<String>,
+
+    pub config: Config,
+}
+
+#[cw_ownable_execute]
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Update the contract's configuration.
+    /// Only callable by the owner.
+    UpdateConfig(Config),
+
+    /// Add or remove feeders.
+    /// Only callable by the owner.
+    UpdateFeeders {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+
+    /// Submit prices, replacing the sender's previous submissions of the same
+    /// denoms. The price of each denom is updated to the median of the fresh
+    /// submissions.
+    /// Only callable by a feeder.
+    FeedPrices {
+        prices: Vec<PriceFeed>,
+    },
+}
+
+#[cw_ownable_query]
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Query the contract's configuration
+    #[returns(Config)]
+    Config {},
+
+    /// Enumerate the accounts allowed to submit prices
+    #[returns(Vec<String>)]
+    Feeders {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Query the latest price of a denom. Errors if it's stale.
+    #[returns(PriceResponse)]
+    Price {
+        denom: String,
+    },
+
+    /// Enumerate the latest prices of all denoms, including stale ones
+    #[returns(Vec<PriceResponse>)]
+    Prices {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Query the time-weighted average price of a denom over the last `window`
+    /// seconds
+    #[returns(TwapResponse)]
+    Twap {
+        denom: String,
+        window: u64,
+    },
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    pub denom: String,
+    pub price: Decimal,
+    /// Time, in seconds since the epoch, when the price was last updated
+    pub updated_at: u64,
+}
+
+#[cw_serde]
+pub struct TwapResponse {
+    pub denom: String,
+    pub price: Decimal,
+    pub window: u64,
+}
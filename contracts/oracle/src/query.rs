@@ -0,0 +1,118 @@
+use cosmwasm_std::{BlockInfo, Decimal, Deps, Order, StdResult};
+use cw_paginate::paginate_map;
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::{Config, PriceResponse, TwapResponse},
+    state::{CONFIG, FEEDERS, PRICES, SNAPSHOTS},
+};
+
+pub fn config(deps: Deps) -> StdResult<Config> {
+    CONFIG.load(deps.storage)
+}
+
+pub fn feeders(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let start_addr = start_after.map(|feeder| deps.api.addr_validate(&feeder)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+    paginate_map(FEEDERS, deps.storage, start, limit, |feeder, _| Ok(feeder.into()))
+}
+
+pub fn price(deps: Deps, block: &BlockInfo, denom: String) -> Result<PriceResponse, ContractError> {
+    let Some((price, updated_at)) = PRICES.may_load(deps.storage, &denom)? else {
+        return Err(ContractError::price_not_found(denom));
+    };
+
+    let cfg = CONFIG.load(deps.storage)?;
+    if updated_at + cfg.staleness_window < block.time.seconds() {
+        return Err(ContractError::price_stale(denom, updated_at));
+    }
+
+    Ok(PriceResponse {
+        denom,
+        price,
+        updated_at,
+    })
+}
+
+pub fn prices(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PriceResponse>> {
+    let start = start_after.as_deref().map(Bound::exclusive);
+    paginate_map(PRICES, deps.storage, start, limit, |denom, (price, updated_at)| {
+        Ok(PriceResponse {
+            denom,
+            price,
+            updated_at,
+        })
+    })
+}
+
+/// Each snapshot's price is weighted by how long it lasted within the window,
+/// i.e. until the next snapshot, or until now for the latest one. The window
+/// starts at the price of the last snapshot before it.
+pub fn twap(
+    deps: Deps,
+    block: &BlockInfo,
+    denom: String,
+    window: u64,
+) -> Result<TwapResponse, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if window > cfg.max_twap_window {
+        return Err(ContractError::window_too_long(window, cfg.max_twap_window));
+    }
+
+    let now = block.time.seconds();
+    let start = now.saturating_sub(window);
+
+    let mut snapshots = vec![];
+
+    let before = SNAPSHOTS
+        .prefix(&denom)
+        .range(deps.storage, None, Some(Bound::inclusive(start)), Order::Descending)
+        .next()
+        .transpose()?;
+    if let Some((_, price)) = before {
+        snapshots.push((start, price));
+    }
+
+    for item in SNAPSHOTS.prefix(&denom).range(
+        deps.storage,
+        Some(Bound::exclusive(start)),
+        None,
+        Order::Ascending,
+    ) {
+        snapshots.push(item?);
+    }
+
+    let Some(&(first, first_price)) = snapshots.first() else {
+        return Err(ContractError::price_not_found(denom));
+    };
+
+    // the window has no length, or its only snapshot was taken just now
+    if now == first {
+        return Ok(TwapResponse {
+            denom,
+            price: first_price,
+            window,
+        });
+    }
+
+    let mut sum = Decimal::zero();
+    for (i, (time, price)) in snapshots.iter().enumerate() {
+        let until = snapshots.get(i + 1).map_or(now, |(next, _)| *next);
+        sum += *price * Decimal::from_ratio(until - time, 1u128);
+    }
+
+    Ok(TwapResponse {
+        denom,
+        price: sum / Decimal::from_ratio(now - first, 1u128),
+        window,
+    })
+}
@@ -0,0 +1,19 @@
+use cosmwasm_std::{Addr, Decimal, Empty};
+use cw_storage_plus::{Item, Map};
+
+use crate::msg::{Config, Submission};
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// The accounts allowed to submit prices
+pub const FEEDERS: Map<&Addr, Empty> = Map::new("feeders");
+
+/// The latest submission of each feeder, indexed by denom and feeder
+pub const SUBMISSIONS: Map<(&str, &Addr), Submission> = Map::new("submissions");
+
+/// The latest price of each denom, and the time it was updated
+pub const PRICES: Map<&str, (Decimal, u64)> = Map::new("prices");
+
+/// The price of each denom every time it's updated, indexed by denom and time,
+/// from which TWAPs are computed
+pub const SNAPSHOTS: Map<(&str, u64), Decimal> = Map::new("snapshots");
@@ -0,0 +1,166 @@
+use cosmwasm_std::{testing::mock_info, Decimal};
+use cw_ownable::OwnershipError;
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::PriceResponse,
+    query,
+    tests::{block_at, feed, setup_test, OWNER},
+};
+
+#[test]
+fn not_feeder() {
+    let mut deps = setup_test();
+
+    let err = execute::feed_prices(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info("badguy", &[]),
+        vec![feed("uatom", 10)],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFeeder);
+}
+
+#[test]
+fn invalid_prices() {
+    let mut deps = setup_test();
+
+    let err = execute::feed_prices(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info("alice", &[]),
+        vec![feed("uatom", 0)],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::invalid_price("uatom", Decimal::zero()));
+
+    let err = execute::feed_prices(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info("alice", &[]),
+        vec![feed("uatom", 10), feed("uatom", 11)],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::duplicate_denom("uatom"));
+}
+
+#[test]
+fn medianizing() {
+    let mut deps = setup_test();
+
+    // a single submission is fewer than the minimum, so no price is set
+    execute::feed_prices(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info("alice", &[]),
+        vec![feed("uatom", 10)],
+    )
+    .unwrap();
+
+    let err = query::price(deps.as_ref(), &block_at(0), "uatom".into()).unwrap_err();
+    assert_eq!(err, ContractError::price_not_found("uatom"));
+
+    // two submissions: the average of the two
+    execute::feed_prices(
+        deps.as_mut(),
+        &block_at(10),
+        mock_info("bob", &[]),
+        vec![feed("uatom", 13)],
+    )
+    .unwrap();
+
+    let price = query::price(deps.as_ref(), &block_at(10), "uatom".into()).unwrap();
+    assert_eq!(
+        price,
+        PriceResponse {
+            denom: "uatom".into(),
+            price: Decimal::percent(1150),
+            updated_at: block_at(10).time.seconds(),
+        },
+    );
+
+    // three submissions: the one in the middle
+    execute::feed_prices(
+        deps.as_mut(),
+        &block_at(20),
+        mock_info("charlie", &[]),
+        vec![feed("uatom", 100)],
+    )
+    .unwrap();
+
+    let price = query::price(deps.as_ref(), &block_at(20), "uatom".into()).unwrap();
+    assert_eq!(price.price, Decimal::from_ratio(13u128, 1u128));
+
+    // alice's submission goes stale, leaving bob's and charlie's
+    execute::feed_prices(
+        deps.as_mut(),
+        &block_at(65),
+        mock_info("charlie", &[]),
+        vec![feed("uatom", 15)],
+    )
+    .unwrap();
+
+    let price = query::price(deps.as_ref(), &block_at(65), "uatom".into()).unwrap();
+    assert_eq!(price.price, Decimal::from_ratio(14u128, 1u128));
+
+    // the price itself goes stale if not updated
+    let err = query::price(deps.as_ref(), &block_at(200), "uatom".into()).unwrap_err();
+    assert_eq!(err, ContractError::price_stale("uatom", block_at(65).time.seconds()));
+}
+
+#[test]
+fn updating_feeders() {
+    let mut deps = setup_test();
+
+    execute::feed_prices(
+        deps.as_mut(),
+        &block_at(0),
+        mock_info("bob", &[]),
+        vec![feed("uatom", 10)],
+    )
+    .unwrap();
+
+    let err = execute::update_feeders(
+        deps.as_mut(),
+        mock_info("alice", &[]),
+        vec!["jake".into()],
+        vec![],
+    )
+    .unwrap_err();
+    assert_eq!(err, OwnershipError::NotOwner.into());
+
+    execute::update_feeders(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        vec!["jake".into()],
+        vec!["bob".into()],
+    )
+    .unwrap();
+
+    let feeders = query::feeders(deps.as_ref(), None, None).unwrap();
+    assert_eq!(feeders, vec!["alice".to_string(), "charlie".into(), "jake".into()]);
+
+    let err = execute::feed_prices(
+        deps.as_mut(),
+        &block_at(10),
+        mock_info("bob", &[]),
+        vec![feed("uatom", 10)],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFeeder);
+
+    // bob's earlier submission no longer counts, so alice's alone is fewer than
+    // the minimum
+    execute::feed_prices(
+        deps.as_mut(),
+        &block_at(10),
+        mock_info("alice", &[]),
+        vec![feed("uatom", 10)],
+    )
+    .unwrap();
+
+    let err = query::price(deps.as_ref(), &block_at(10), "uatom".into()).unwrap_err();
+    assert_eq!(err, ContractError::price_not_found("uatom"));
+}
@@ -0,0 +1,47 @@
+mod feeding;
+mod twap;
+mod vote_extensions;
+
+use cosmwasm_std::{
+    testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage},
+    BlockInfo, Decimal, Empty, OwnedDeps,
+};
+
+use crate::{
+    execute,
+    msg::{Config, PriceFeed},
+};
+
+const OWNER: &str = "larry";
+const FEEDERS: [&str; 3] = ["alice", "bob", "charlie"];
+
+fn config() -> Config {
+    Config {
+        staleness_window: 60,
+        min_feeders: 2,
+        max_twap_window: 3600,
+        vote_quorum: Decimal::from_ratio(2u128, 3u128),
+    }
+}
+
+/// The mock block, `seconds` after the mock env's
+fn block_at(seconds: u64) -> BlockInfo {
+    let mut block = mock_env().block;
+    block.time = block.time.plus_seconds(seconds);
+    block
+}
+
+fn feed(denom: &str, price: u64) -> PriceFeed {
+    PriceFeed {
+        denom: denom.into(),
+        price: Decimal::from_ratio(price, 1u128),
+    }
+}
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
+    let mut deps = mock_dependencies();
+
+    execute::init(deps.as_mut(), OWNER, FEEDERS.map(String::from).to_vec(), config()).unwrap();
+
+    deps
+}
@@ -0,0 +1,60 @@
+use cosmwasm_std::{testing::mock_info, Decimal, DepsMut};
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::TwapResponse,
+    query,
+    tests::{block_at, feed, setup_test},
+};
+
+fn set_price(mut deps: DepsMut, seconds: u64, price: u64) {
+    for feeder in ["alice", "bob"] {
+        execute::feed_prices(
+            deps.branch(),
+            &block_at(seconds),
+            mock_info(feeder, &[]),
+            vec![feed("uatom", price)],
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn twap() {
+    let mut deps = setup_test();
+
+    let err = query::twap(deps.as_ref(), &block_at(0), "uatom".into(), 100).unwrap_err();
+    assert_eq!(err, ContractError::price_not_found("uatom"));
+
+    let err = query::twap(deps.as_ref(), &block_at(0), "uatom".into(), 3601).unwrap_err();
+    assert_eq!(err, ContractError::window_too_long(3601, 3600));
+
+    set_price(deps.as_mut(), 0, 10);
+    set_price(deps.as_mut(), 100, 20);
+    set_price(deps.as_mut(), 150, 40);
+
+    // 10 for 100 seconds, 20 for 50 seconds, 40 for 50 seconds
+    let res = query::twap(deps.as_ref(), &block_at(200), "uatom".into(), 200).unwrap();
+    assert_eq!(
+        res,
+        TwapResponse {
+            denom: "uatom".into(),
+            price: Decimal::from_ratio(20u128, 1u128),
+            window: 200,
+        },
+    );
+
+    // the window starts at the price before it: 10 for 50 seconds, 20 for 50
+    // seconds, 40 for 50 seconds
+    let res = query::twap(deps.as_ref(), &block_at(200), "uatom".into(), 150).unwrap();
+    assert_eq!(res.price, Decimal::from_ratio(70u128, 3u128));
+
+    // a window longer than the price history starts at the first snapshot
+    let res = query::twap(deps.as_ref(), &block_at(200), "uatom".into(), 1000).unwrap();
+    assert_eq!(res.price, Decimal::from_ratio(20u128, 1u128));
+
+    // a window of zero length is the latest price
+    let res = query::twap(deps.as_ref(), &block_at(200), "uatom".into(), 0).unwrap();
+    assert_eq!(res.price, Decimal::from_ratio(40u128, 1u128));
+}
@@ -0,0 +1,75 @@
+use cosmwasm_std::{to_binary, Binary, Decimal};
+use cw_sdk::ExtendedVote;
+
+use crate::{
+    error::ContractError,
+    execute,
+    query,
+    tests::{block_at, feed, setup_test},
+};
+
+fn vote(power: u64, prices: &[(&str, u64)]) -> ExtendedVote {
+    let prices: Vec<_> = prices.iter().map(|(denom, price)| feed(denom, *price)).collect();
+    ExtendedVote {
+        validator: Binary::default(),
+        power,
+        round: 0,
+        extension: to_binary(&prices).unwrap(),
+        extension_signature: Binary::default(),
+    }
+}
+
+#[test]
+fn verifying() {
+    // validators may choose not to extend their votes
+    execute::verify_vote_extension(&[]).unwrap();
+
+    let extension = to_binary(&vec![feed("uatom", 10), feed("uosmo", 1)]).unwrap();
+    execute::verify_vote_extension(&extension).unwrap();
+
+    let extension = to_binary(&vec![feed("uatom", 10), feed("uatom", 11)]).unwrap();
+    let err = execute::verify_vote_extension(&extension).unwrap_err();
+    assert_eq!(err, ContractError::duplicate_denom("uatom"));
+
+    let err = execute::verify_vote_extension(b"not prices").unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+}
+
+#[test]
+fn processing() {
+    let mut deps = setup_test();
+
+    let votes = vec![
+        vote(10, &[("uatom", 10), ("uosmo", 1)]),
+        vote(30, &[("uatom", 12)]),
+        vote(25, &[("uatom", 11), ("uosmo", 2)]),
+        ExtendedVote {
+            validator: Binary::default(),
+            power: 15,
+            round: 0,
+            extension: Binary::default(),
+            extension_signature: Binary::default(),
+        },
+    ];
+
+    // a validator with 10 power didn't vote
+    execute::process_vote_extensions(deps.as_mut(), &block_at(0), votes, 90).unwrap();
+
+    // prices weighted by power: 10 with 10, 11 with 25, 12 with 30. the median
+    // is the lowest price with at least half of the reporting power at or
+    // below it
+    let price = query::price(deps.as_ref(), &block_at(0), "uatom".into()).unwrap();
+    assert_eq!(price.price, Decimal::from_ratio(11u128, 1u128));
+
+    // uosmo is reported by 35 of the 90 power, not more than the quorum of two
+    // thirds, so its price isn't set
+    let err = query::price(deps.as_ref(), &block_at(0), "uosmo".into()).unwrap_err();
+    assert_eq!(err, ContractError::price_not_found("uosmo"));
+
+    // in the next block, uatom is reported by only 40 of the 90 power, so its
+    // price stays the same
+    let votes = vec![vote(10, &[("uatom", 20)]), vote(30, &[("uatom", 20)])];
+    execute::process_vote_extensions(deps.as_mut(), &block_at(1), votes, 90).unwrap();
+    let price = query::price(deps.as_ref(), &block_at(1), "uatom".into()).unwrap();
+    assert_eq!(price.price, Decimal::from_ratio(11u128, 1u128));
+}
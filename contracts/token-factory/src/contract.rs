@@ -1,6 +1,6 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response};
 
 use crate::{
     error::ContractError,
@@ -20,7 +20,13 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    execute::init(deps, &msg.owner, msg.token_creation_fee)
+    execute::init(
+        deps,
+        &msg.owner,
+        msg.token_creation_fee,
+        msg.fee_recipient,
+        msg.hook_gas_limit,
+    )
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -40,6 +46,12 @@ pub fn execute(
         ExecuteMsg::UpdateFee {
             token_creation_fee,
         } => execute::update_fee(deps, info, token_creation_fee),
+        ExecuteMsg::UpdateFeeRecipient {
+            fee_recipient,
+        } => execute::update_fee_recipient(deps, info, fee_recipient),
+        ExecuteMsg::UpdateHookGasLimit {
+            hook_gas_limit,
+        } => execute::update_hook_gas_limit(deps, info, hook_gas_limit),
         ExecuteMsg::WithdrawFee {
             to,
         } => execute::withdraw_fee(deps, env, info, to),
@@ -47,28 +59,95 @@ pub fn execute(
             nonce,
             admin,
             after_transfer_hook,
-        } => execute::create_token(deps, info, nonce, admin, after_transfer_hook),
+            hook_failure_policy,
+            before_send_hook,
+            metadata,
+            max_supply,
+        } => execute::create_token(
+            deps,
+            info,
+            nonce,
+            admin,
+            after_transfer_hook,
+            hook_failure_policy,
+            before_send_hook,
+            metadata,
+            max_supply,
+        ),
         ExecuteMsg::UpdateToken(UpdateTokenMsg {
             denom,
             admin,
             after_transfer_hook,
-        }) => execute::update_token(deps, info, denom, admin, after_transfer_hook),
+            hook_failure_policy,
+            before_send_hook,
+            metadata,
+        }) => execute::update_token(
+            deps,
+            info,
+            denom,
+            admin,
+            after_transfer_hook,
+            hook_failure_policy,
+            before_send_hook,
+            metadata,
+        ),
         ExecuteMsg::Mint {
             to,
             denom,
             amount,
         } => execute::mint(deps, info, to, denom, amount),
+        ExecuteMsg::MintBatch {
+            outputs,
+        } => execute::mint_batch(deps, info, outputs),
         ExecuteMsg::Burn {
             from,
             denom,
             amount,
         } => execute::burn(deps, info, from, denom, amount),
+        ExecuteMsg::BurnBatch {
+            inputs,
+        } => execute::burn_batch(deps, info, inputs),
         ExecuteMsg::ForceTransfer {
             from,
             to,
             denom,
             amount,
         } => execute::force_transfer(deps, info, from, to, denom, amount),
+        ExecuteMsg::Approve {
+            spender,
+            denom,
+            amount,
+            expires,
+        } => execute::approve(deps, info, spender, denom, amount, expires),
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            denom,
+            amount,
+            expires,
+        } => execute::increase_allowance(deps, info, spender, denom, amount, expires),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            denom,
+            amount,
+            expires,
+        } => execute::decrease_allowance(deps, info, spender, denom, amount, expires),
+        ExecuteMsg::TransferFrom {
+            owner,
+            to,
+            denom,
+            amount,
+        } => execute::transfer_from(deps, env, info, owner, to, denom, amount),
+        ExecuteMsg::BurnFrom {
+            owner,
+            denom,
+            amount,
+        } => execute::burn_from(deps, env, info, owner, denom, amount),
+        ExecuteMsg::Freeze {
+            denom,
+        } => execute::freeze(deps, info, denom),
+        ExecuteMsg::Unfreeze {
+            denom,
+        } => execute::unfreeze(deps, info, denom),
         ExecuteMsg::AfterTransfer {
             from,
             to,
@@ -78,14 +157,35 @@ pub fn execute(
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        execute::AFTER_TRANSFER_REPLY_ID => execute::after_transfer_failed(msg.result),
+        _ => Err(ContractError::UnexpectedReply),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
         QueryMsg::Ownership {} => to_binary(&cw_ownable::get_ownership(deps.storage)?),
         QueryMsg::TokenCreationFee {} => to_binary(&query::token_creation_fee(deps)?),
+        QueryMsg::FeeRecipient {} => to_binary(&query::fee_recipient(deps)?),
+        QueryMsg::HookGasLimit {} => to_binary(&query::hook_gas_limit(deps)?),
         QueryMsg::Token {
             denom,
         } => to_binary(&query::token(deps, denom)?),
+        QueryMsg::Supply {
+            denom,
+        } => to_binary(&query::supply(deps, denom)?),
+        QueryMsg::Frozen {
+            denom,
+        } => to_binary(&query::frozen(deps, denom)?),
+        QueryMsg::Allowance {
+            owner,
+            spender,
+            denom,
+        } => to_binary(&query::allowance(deps, owner, spender, denom)?),
         QueryMsg::Tokens {
             start_after,
             limit,
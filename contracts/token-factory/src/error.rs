@@ -26,14 +26,44 @@ pub enum ContractError {
     #[error("the contract has no coins to transfer")]
     NoBalance,
 
+    #[error("batch must contain at least one mint or burn")]
+    EmptyBatch,
+
+    #[error("cannot set an allowance to one's own account")]
+    CannotSetOwnAccount,
+
+    #[error("allowance of {spender} to spend {denom} of {owner} has expired")]
+    AllowanceExpired {
+        owner: String,
+        spender: String,
+        denom: String,
+    },
+
+    #[error("{spender} may spend {allowance} {denom} of {owner}, less than {amount}")]
+    InsufficientAllowance {
+        owner: String,
+        spender: String,
+        denom: String,
+        allowance: Uint128,
+        amount: Uint128,
+    },
+
     #[error("sender is not the bank contract")]
     NotBank,
 
+    #[error("unexpected reply to a submessage")]
+    UnexpectedReply,
+
     #[error("sender is not the admin of denom {denom}")]
     NotTokenAdmin {
         denom: String,
     },
 
+    #[error("denom {denom} is not accepted for the token creation fee")]
+    FeeDenomNotAccepted {
+        denom: String,
+    },
+
     #[error("incorrect fee amount: expected {expected}, received {received}")]
     IncorrectFee {
         expected: Coin,
@@ -55,10 +85,22 @@ pub enum ContractError {
         denom: String,
     },
 
+    #[error("supply of denom {denom} would be {supply}, above the max supply {max_supply}")]
+    MaxSupplyExceeded {
+        denom: String,
+        supply: Uint128,
+        max_supply: Uint128,
+    },
+
     #[error("token of denom {denom} does not exist")]
     TokenNotFound {
         denom: String,
     },
+
+    #[error("transfers of denom {denom} are frozen")]
+    Frozen {
+        denom: String,
+    },
 }
 
 impl ContractError {
@@ -68,6 +110,12 @@ impl ContractError {
         }
     }
 
+    pub fn fee_denom_not_accepted(denom: impl Into<String>) -> Self {
+        Self::FeeDenomNotAccepted {
+            denom: denom.into(),
+        }
+    }
+
     pub fn incorrect_fee(expected: Coin, received: Uint128) -> Self {
         Self::IncorrectFee {
             expected,
@@ -75,6 +123,34 @@ impl ContractError {
         }
     }
 
+    pub fn allowance_expired(
+        owner: impl Into<String>,
+        spender: impl Into<String>,
+        denom: impl Into<String>,
+    ) -> Self {
+        Self::AllowanceExpired {
+            owner: owner.into(),
+            spender: spender.into(),
+            denom: denom.into(),
+        }
+    }
+
+    pub fn insufficient_allowance(
+        owner: impl Into<String>,
+        spender: impl Into<String>,
+        denom: impl Into<String>,
+        allowance: Uint128,
+        amount: Uint128,
+    ) -> Self {
+        Self::InsufficientAllowance {
+            owner: owner.into(),
+            spender: spender.into(),
+            denom: denom.into(),
+            allowance,
+            amount,
+        }
+    }
+
     pub fn incorrect_denom_format(denom: impl Into<String>) -> Self {
         Self::InvalidDenomFormat {
             denom: denom.into(),
@@ -93,9 +169,27 @@ impl ContractError {
         }
     }
 
+    pub fn max_supply_exceeded(
+        denom: impl Into<String>,
+        supply: Uint128,
+        max_supply: Uint128,
+    ) -> Self {
+        Self::MaxSupplyExceeded {
+            denom: denom.into(),
+            supply,
+            max_supply,
+        }
+    }
+
     pub fn token_not_found(denom: impl Into<String>) -> Self {
         Self::TokenNotFound {
             denom: denom.into(),
         }
     }
+
+    pub fn frozen(denom: impl Into<String>) -> Self {
+        Self::Frozen {
+            denom: denom.into(),
+        }
+    }
 }
@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
+
 use cosmwasm_std::{
-    to_binary, Addr, BlockInfo, Coin, Deps, DepsMut, Env, MessageInfo, Response, Uint128, WasmMsg,
+    to_binary, Addr, BlockInfo, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, StdResult, Storage, SubMsg, SubMsgResult, Uint128, WasmMsg,
 };
 use cw_bank::{denom::Denom, msg as bank};
 use cw_ownable::{assert_owner, Action as OwnershipAction};
@@ -7,26 +10,36 @@ use cw_sdk::{
     address,
     helpers::{stringify_coins, stringify_option, validate_optional_addr},
 };
-use cw_utils::must_pay;
+use cw_utils::{one_coin, Expiration};
 
 use crate::{
     error::ContractError,
-    helpers::parse_denom,
-    msg::TokenConfig,
-    state::{TOKEN_CONFIGS, TOKEN_CREATION_FEE},
+    helpers::{parse_denom, query_supply},
+    msg::{Allowance, BurnInput, FeeRecipient, HookFailurePolicy, MintOutput, TokenConfig},
+    state::{ALLOWANCES, FEE_RECIPIENT, FROZEN, HOOK_GAS_LIMIT, TOKEN_CONFIGS, TOKEN_CREATION_FEE},
     BANK,
     NAMESPACE,
 };
 
+/// ID of the submessages invoking after transfer hooks that fail gracefully
+pub const AFTER_TRANSFER_REPLY_ID: u64 = 1;
+
 pub fn init(
     deps: DepsMut,
     owner: &str,
-    token_creation_fee: Option<Coin>,
+    token_creation_fee: Vec<Coin>,
+    fee_recipient: FeeRecipient,
+    hook_gas_limit: u64,
 ) -> Result<Response, ContractError> {
     cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner))?;
 
     TOKEN_CREATION_FEE.save(deps.storage, &token_creation_fee)?;
 
+    validate_fee_recipient(deps.as_ref(), &fee_recipient)?;
+    FEE_RECIPIENT.save(deps.storage, &fee_recipient)?;
+
+    HOOK_GAS_LIMIT.save(deps.storage, &hook_gas_limit)?;
+
     Ok(Response::default())
 }
 
@@ -46,7 +59,7 @@ pub fn update_ownership(
 pub fn update_fee(
     deps: DepsMut,
     info: MessageInfo,
-    token_creation_fee: Option<Coin>,
+    token_creation_fee: Vec<Coin>,
 ) -> Result<Response, ContractError> {
     cw_ownable::assert_owner(deps.as_ref().storage, &info.sender)?;
 
@@ -54,7 +67,34 @@ pub fn update_fee(
 
     Ok(Response::new()
         .add_attribute("action", "token-factory/update_fee")
-        .add_attribute("new_fee", stringify_option(token_creation_fee)))
+        .add_attribute("new_fee", stringify_coins(&token_creation_fee)))
+}
+
+pub fn update_fee_recipient(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee_recipient: FeeRecipient,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.as_ref().storage, &info.sender)?;
+
+    validate_fee_recipient(deps.as_ref(), &fee_recipient)?;
+    FEE_RECIPIENT.save(deps.storage, &fee_recipient)?;
+
+    Ok(Response::new().add_attribute("action", "token-factory/update_fee_recipient"))
+}
+
+pub fn update_hook_gas_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    hook_gas_limit: u64,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.as_ref().storage, &info.sender)?;
+
+    HOOK_GAS_LIMIT.save(deps.storage, &hook_gas_limit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/update_hook_gas_limit")
+        .add_attribute("new_hook_gas_limit", hook_gas_limit.to_string()))
 }
 
 pub fn withdraw_fee(
@@ -94,21 +134,33 @@ pub fn withdraw_fee(
         }))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_token(
     deps: DepsMut,
     info: MessageInfo,
     nonce: String,
     admin: String,
     after_transfer_hook: Option<String>,
+    hook_failure_policy: HookFailurePolicy,
+    before_send_hook: Option<String>,
+    metadata: Option<bank::Metadata>,
+    max_supply: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let fee = TOKEN_CREATION_FEE.load(deps.storage)?;
-
-    if let Some(fee) = fee {
-        let received_amount = must_pay(&info, &fee.denom)?;
-        if received_amount != fee.amount {
-            return Err(ContractError::incorrect_fee(fee, received_amount));
+    let fees = TOKEN_CREATION_FEE.load(deps.storage)?;
+
+    // if there's a fee, the creator must pay exactly one of the accepted ones
+    let fee_msg = if fees.is_empty() {
+        None
+    } else {
+        let paid = one_coin(&info)?;
+        let Some(fee) = fees.into_iter().find(|fee| fee.denom == paid.denom) else {
+            return Err(ContractError::fee_denom_not_accepted(paid.denom));
+        };
+        if paid.amount != fee.amount {
+            return Err(ContractError::incorrect_fee(fee, paid.amount));
         }
-    }
+        distribute_fee_msg(deps.as_ref(), paid)?
+    };
 
     let denom = format!("{NAMESPACE}/{}/{nonce}", &info.sender);
     Denom::validate(&denom)?;
@@ -120,14 +172,32 @@ pub fn create_token(
         Ok(TokenConfig {
             admin: Some(deps.api.addr_validate(&admin)?),
             after_transfer_hook: validate_optional_addr(deps.api, after_transfer_hook.as_ref())?,
+            hook_failure_policy: hook_failure_policy.clone(),
+            before_send_hook: validate_optional_addr(deps.api, before_send_hook.as_ref())?,
+            metadata: metadata.clone(),
+            max_supply,
         })
     })?;
 
-    Ok(Response::new()
+    let mut res = Response::new()
+        .add_messages(fee_msg)
         .add_attribute("action", "token-factory/create_token")
-        .add_attribute("denom", denom)
+        .add_attribute("denom", &denom)
         .add_attribute("admin", admin)
-        .add_attribute("after_transfer_hook", stringify_option(after_transfer_hook)))
+        .add_attribute("after_transfer_hook", stringify_option(after_transfer_hook))
+        .add_attribute("hook_failure_policy", hook_failure_policy.to_string())
+        .add_attribute("before_send_hook", stringify_option(before_send_hook.clone()))
+        .add_attribute("max_supply", stringify_option(max_supply));
+
+    if before_send_hook.is_some() {
+        res = res.add_message(set_send_hook_msg(denom.clone(), before_send_hook)?);
+    }
+
+    if metadata.is_some() {
+        res = res.add_message(set_metadata_msg(denom, metadata)?);
+    }
+
+    Ok(res)
 }
 
 pub fn update_token(
@@ -136,6 +206,9 @@ pub fn update_token(
     denom: String,
     admin: Option<String>,
     after_transfer_hook: Option<String>,
+    hook_failure_policy: HookFailurePolicy,
+    before_send_hook: Option<String>,
+    metadata: Option<bank::Metadata>,
 ) -> Result<Response, ContractError> {
     let (creator, nonce) = assert_denom_admin(deps.as_ref(), &denom, &info.sender)?;
 
@@ -143,32 +216,139 @@ pub fn update_token(
         let mut token_cfg = opt.ok_or_else(|| ContractError::token_not_found(&denom))?;
         token_cfg.admin = validate_optional_addr(deps.api, admin.as_ref())?;
         token_cfg.after_transfer_hook = validate_optional_addr(deps.api,after_transfer_hook.as_ref())?;
+        token_cfg.hook_failure_policy = hook_failure_policy.clone();
+        token_cfg.before_send_hook = validate_optional_addr(deps.api, before_send_hook.as_ref())?;
+        token_cfg.metadata = metadata.clone();
         Ok(token_cfg)
     })?;
 
     Ok(Response::new()
         .add_attribute("action", "token-factory/update_token")
-        .add_attribute("denom", denom)
+        .add_attribute("denom", &denom)
         .add_attribute("admin", stringify_option(admin))
-        .add_attribute("after_transfer_hook", stringify_option(after_transfer_hook)))
+        .add_attribute("after_transfer_hook", stringify_option(after_transfer_hook))
+        .add_attribute("hook_failure_policy", hook_failure_policy.to_string())
+        .add_attribute("before_send_hook", stringify_option(before_send_hook.clone()))
+        .add_message(set_send_hook_msg(denom.clone(), before_send_hook)?)
+        .add_message(set_metadata_msg(denom, metadata)?))
 }
 
-pub fn mint(
+pub fn approve(
     deps: DepsMut,
     info: MessageInfo,
+    spender: String,
+    denom: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender_addr = validate_spender(deps.as_ref(), &info.sender, &spender, &denom)?;
+    let key = (&info.sender, &spender_addr, denom.as_str());
+
+    if amount.is_zero() {
+        ALLOWANCES.remove(deps.storage, key);
+    } else {
+        let allowance = Allowance {
+            amount,
+            expires: expires.unwrap_or_default(),
+        };
+        ALLOWANCES.save(deps.storage, key, &allowance)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/approve")
+        .add_attribute("owner", &info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("coin", format!("{amount}{denom}")))
+}
+
+pub fn increase_allowance(
+    deps: DepsMut,
+    info: MessageInfo,
+    spender: String,
+    denom: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender_addr = validate_spender(deps.as_ref(), &info.sender, &spender, &denom)?;
+    let key = (&info.sender, &spender_addr, denom.as_str());
+
+    ALLOWANCES.update(deps.storage, key, |allowance| -> StdResult<_> {
+        let mut allowance = allowance.unwrap_or_default();
+        allowance.amount = allowance.amount.checked_add(amount)?;
+        if let Some(expires) = expires {
+            allowance.expires = expires;
+        }
+        Ok(allowance)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/increase_allowance")
+        .add_attribute("owner", &info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("coin", format!("{amount}{denom}")))
+}
+
+pub fn decrease_allowance(
+    deps: DepsMut,
+    info: MessageInfo,
+    spender: String,
+    denom: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender_addr = validate_spender(deps.as_ref(), &info.sender, &spender, &denom)?;
+    let key = (&info.sender, &spender_addr, denom.as_str());
+
+    let mut allowance = ALLOWANCES.may_load(deps.storage, key)?.unwrap_or_default();
+    allowance.amount = allowance.amount.saturating_sub(amount);
+    if let Some(expires) = expires {
+        allowance.expires = expires;
+    }
+
+    if allowance.amount.is_zero() {
+        ALLOWANCES.remove(deps.storage, key);
+    } else {
+        ALLOWANCES.save(deps.storage, key, &allowance)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/decrease_allowance")
+        .add_attribute("owner", &info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("coin", format!("{amount}{denom}")))
+}
+
+pub fn transfer_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
     to: String,
     denom: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    assert_denom_admin(deps.as_ref(), &denom, &info.sender)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let (creator, nonce) = assert_token_exists(deps.as_ref(), &denom)?;
+
+    // the bank doesn't check force transfers against the freeze, since the
+    // admin may need to move a frozen token, but spenders are users like any
+    // other
+    if FROZEN.has(deps.storage, (&creator, &nonce)) {
+        return Err(ContractError::frozen(&denom));
+    }
+
+    deduct_allowance(deps.storage, &env.block, &owner_addr, &info.sender, &denom, amount)?;
 
     Ok(Response::new()
-        .add_attribute("action", "token-factory/mint")
+        .add_attribute("action", "token-factory/transfer_from")
+        .add_attribute("owner", &owner)
+        .add_attribute("spender", &info.sender)
         .add_attribute("to", &to)
         .add_attribute("coin", format!("{amount}{denom}"))
         .add_message(WasmMsg::Execute {
             contract_addr: BANK.into(),
-            msg: to_binary(&bank::ExecuteMsg::Mint {
+            msg: to_binary(&bank::ExecuteMsg::ForceTransfer {
+                from: owner,
                 to,
                 denom,
                 amount,
@@ -177,6 +357,106 @@ pub fn mint(
         }))
 }
 
+pub fn burn_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    denom: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    assert_token_exists(deps.as_ref(), &denom)?;
+
+    deduct_allowance(deps.storage, &env.block, &owner_addr, &info.sender, &denom, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/burn_from")
+        .add_attribute("owner", &owner)
+        .add_attribute("spender", &info.sender)
+        .add_attribute("coin", format!("{amount}{denom}"))
+        .add_message(burn_coins_msg(owner, denom, amount)?))
+}
+
+pub fn freeze(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let (creator, nonce) = assert_denom_admin(deps.as_ref(), &denom, &info.sender)?;
+
+    FROZEN.save(deps.storage, (&creator, &nonce), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/freeze")
+        .add_attribute("denom", &denom)
+        .add_message(set_frozen_msg(denom, true)?))
+}
+
+pub fn unfreeze(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let (creator, nonce) = assert_denom_admin(deps.as_ref(), &denom, &info.sender)?;
+
+    FROZEN.remove(deps.storage, (&creator, &nonce));
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/unfreeze")
+        .add_attribute("denom", &denom)
+        .add_message(set_frozen_msg(denom, false)?))
+}
+
+pub fn mint(
+    deps: DepsMut,
+    info: MessageInfo,
+    to: String,
+    denom: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/mint")
+        .add_attribute("to", &to)
+        .add_attribute("coin", format!("{amount}{denom}"))
+        .add_message(mint_msg(deps.as_ref(), &info.sender, to, denom, amount, Uint128::zero())?))
+}
+
+pub fn mint_batch(
+    deps: DepsMut,
+    info: MessageInfo,
+    outputs: Vec<MintOutput>,
+) -> Result<Response, ContractError> {
+    if outputs.is_empty() {
+        return Err(ContractError::EmptyBatch);
+    }
+
+    let mut res = Response::new().add_attribute("action", "token-factory/mint_batch");
+
+    // the bank's supplies don't reflect the mints until the messages are
+    // executed, so the amounts minted earlier in the batch are added up here
+    let mut minted: BTreeMap<String, Uint128> = BTreeMap::new();
+
+    for MintOutput {
+        to,
+        denom,
+        amount,
+    } in outputs
+    {
+        let pending = minted.entry(denom.clone()).or_default();
+        let msg =
+            mint_msg(deps.as_ref(), &info.sender, to.clone(), denom.clone(), amount, *pending)?;
+        *pending = pending.checked_add(amount).map_err(StdError::from)?;
+
+        res = res
+            .add_attribute("to", &to)
+            .add_attribute("coin", format!("{amount}{denom}"))
+            .add_message(msg);
+    }
+
+    Ok(res)
+}
+
 pub fn burn(
     deps: DepsMut,
     info: MessageInfo,
@@ -184,21 +464,37 @@ pub fn burn(
     denom: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    assert_denom_admin(deps.as_ref(), &denom, &info.sender)?;
-
     Ok(Response::new()
         .add_attribute("action", "token-factory/burn")
         .add_attribute("from", &from)
         .add_attribute("coin", format!("{amount}{denom}"))
-        .add_message(WasmMsg::Execute {
-            contract_addr: BANK.into(),
-            msg: to_binary(&bank::ExecuteMsg::Burn {
-                from,
-                denom,
-                amount,
-            })?,
-            funds: vec![],
-        }))
+        .add_message(burn_msg(deps.as_ref(), &info.sender, from, denom, amount)?))
+}
+
+pub fn burn_batch(
+    deps: DepsMut,
+    info: MessageInfo,
+    inputs: Vec<BurnInput>,
+) -> Result<Response, ContractError> {
+    if inputs.is_empty() {
+        return Err(ContractError::EmptyBatch);
+    }
+
+    let mut res = Response::new().add_attribute("action", "token-factory/burn_batch");
+
+    for BurnInput {
+        from,
+        denom,
+        amount,
+    } in inputs
+    {
+        res = res
+            .add_attribute("from", &from)
+            .add_attribute("coin", format!("{amount}{denom}"))
+            .add_message(burn_msg(deps.as_ref(), &info.sender, from, denom, amount)?);
+    }
+
+    Ok(res)
 }
 
 pub fn force_transfer(
@@ -245,21 +541,256 @@ pub fn after_transfer(
         return Ok(Response::default());
     };
 
+    let attrs = vec![
+        ("action", "token-factory/after_transfer".to_string()),
+        ("from", from.clone()),
+        ("to", to.clone()),
+        ("coin", format!("{amount}{denom}")),
+    ];
+
+    let msg = WasmMsg::Execute {
+        contract_addr: after_transfer_hook.into(),
+        msg: to_binary(&bank::HookMsg::AfterTransfer {
+            from,
+            to,
+            denom,
+            amount,
+        })?,
+        funds: vec![],
+    };
+
+    // the hook is given a limited amount of gas, so that it can't make
+    // transfers of the token prohibitively expensive. if the token's policy is
+    // to ignore the hook's failure, we catch the error in the reply entry point
+    // instead of letting it revert the transfer
+    let submsg = match token_cfg.hook_failure_policy {
+        HookFailurePolicy::Revert => SubMsg::new(msg),
+        HookFailurePolicy::Ignore => SubMsg::reply_on_error(msg, AFTER_TRANSFER_REPLY_ID),
+    };
+    let gas_limit = HOOK_GAS_LIMIT.load(deps.storage)?;
+
     Ok(Response::new()
-        .add_attribute("action", "token-factory/after_transfer")
-        .add_attribute("from", &from)
-        .add_attribute("to", &to)
-        .add_attribute("coin", format!("{amount}{denom}"))
-        .add_message(WasmMsg::Execute {
-            contract_addr: after_transfer_hook.into(),
-            msg: to_binary(&bank::HookMsg::AfterTransfer {
-                from,
+        .add_attributes(attrs)
+        .add_submessage(submsg.with_gas_limit(gas_limit)))
+}
+
+/// Handle the failure of an after transfer hook whose token's policy is to
+/// ignore it. The hook's state changes have already been reverted.
+pub fn after_transfer_failed(result: SubMsgResult) -> Result<Response, ContractError> {
+    let SubMsgResult::Err(err) = result else {
+        return Err(ContractError::UnexpectedReply);
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "token-factory/after_transfer_failed")
+        .add_attribute("error", err))
+}
+
+/// Compose the message that sends a paid fee to the fee recipient, if the fee
+/// isn't to be kept in the contract.
+fn distribute_fee_msg(deps: Deps, fee: Coin) -> Result<Option<WasmMsg>, ContractError> {
+    let msg = match FEE_RECIPIENT.load(deps.storage)? {
+        FeeRecipient::Keep => return Ok(None),
+        FeeRecipient::Burn => WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::BurnOwn {
+                coins: vec![fee],
+            })?,
+            funds: vec![],
+        },
+        FeeRecipient::Address(to) => WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::Send {
                 to,
-                denom,
-                amount,
+                coins: vec![fee],
             })?,
             funds: vec![],
-        }))
+        },
+        FeeRecipient::CommunityPool {
+            address,
+            msg,
+        } => WasmMsg::Execute {
+            contract_addr: address,
+            msg,
+            funds: vec![fee],
+        },
+    };
+
+    Ok(Some(msg))
+}
+
+fn validate_fee_recipient(deps: Deps, fee_recipient: &FeeRecipient) -> Result<(), ContractError> {
+    match fee_recipient {
+        FeeRecipient::Keep | FeeRecipient::Burn => {},
+        FeeRecipient::Address(address)
+        | FeeRecipient::CommunityPool {
+            address,
+            ..
+        } => {
+            deps.api.addr_validate(address)?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Internal method: check that the sender may mint the token and that the
+/// token's max supply isn't exceeded, and compose the bank message that mints
+/// the coins.
+///
+/// The supply is queried from the bank contract, on top of which `pending` is
+/// the amount already being minted by the same message but not executed yet.
+fn mint_msg(
+    deps: Deps,
+    sender: &Addr,
+    to: String,
+    denom: String,
+    amount: Uint128,
+    pending: Uint128,
+) -> Result<WasmMsg, ContractError> {
+    let (creator, nonce) = assert_denom_admin(deps, &denom, sender)?;
+    let token_cfg = TOKEN_CONFIGS.load(deps.storage, (&creator, &nonce))?;
+
+    if let Some(max_supply) = token_cfg.max_supply {
+        let supply = query_supply(&deps.querier, &denom)?
+            .checked_add(pending)
+            .and_then(|supply| supply.checked_add(amount))
+            .map_err(StdError::from)?;
+        if supply > max_supply {
+            return Err(ContractError::max_supply_exceeded(&denom, supply, max_supply));
+        }
+    }
+
+    Ok(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::Mint {
+            to,
+            denom,
+            amount,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// Internal method: check that the sender may burn the token, and compose the
+/// bank message that burns the coins.
+fn burn_msg(
+    deps: Deps,
+    sender: &Addr,
+    from: String,
+    denom: String,
+    amount: Uint128,
+) -> Result<WasmMsg, ContractError> {
+    assert_denom_admin(deps, &denom, sender)?;
+    burn_coins_msg(from, denom, amount)
+}
+
+/// Internal method: compose the bank message that burns the coins.
+fn burn_coins_msg(from: String, denom: String, amount: Uint128) -> Result<WasmMsg, ContractError> {
+    Ok(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::Burn {
+            from,
+            denom,
+            amount,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// Compose a message that registers a token's before send hook in the bank
+/// contract, or removes it if `None`.
+fn set_send_hook_msg(denom: String, hook: Option<String>) -> Result<WasmMsg, ContractError> {
+    Ok(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::SetSendHook {
+            denom,
+            hook,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// Compose a message that freezes or unfreezes a token in the bank contract.
+fn set_frozen_msg(denom: String, frozen: bool) -> Result<WasmMsg, ContractError> {
+    Ok(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::SetFrozen {
+            denom,
+            frozen,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// Compose a message that sets a token's metadata in the bank contract, or
+/// removes it if `None`.
+fn set_metadata_msg(
+    denom: String,
+    metadata: Option<bank::Metadata>,
+) -> Result<WasmMsg, ContractError> {
+    Ok(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::SetMetadata {
+            denom,
+            metadata,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// Validate the spender of an allowance, and that the token exists.
+fn validate_spender(
+    deps: Deps,
+    owner: &Addr,
+    spender: &str,
+    denom: &str,
+) -> Result<Addr, ContractError> {
+    let spender_addr = deps.api.addr_validate(spender)?;
+    if spender_addr == *owner {
+        return Err(ContractError::CannotSetOwnAccount);
+    }
+
+    assert_token_exists(deps, denom)?;
+
+    Ok(spender_addr)
+}
+
+/// Deduct the amount to be spent from the spender's allowance, failing if the
+/// allowance has expired or is insufficient.
+fn deduct_allowance(
+    store: &mut dyn Storage,
+    block: &BlockInfo,
+    owner: &Addr,
+    spender: &Addr,
+    denom: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let key = (owner, spender, denom);
+    let mut allowance = ALLOWANCES.may_load(store, key)?.unwrap_or_default();
+
+    if allowance.expires.is_expired(block) {
+        return Err(ContractError::allowance_expired(owner, spender, denom));
+    }
+
+    if allowance.amount < amount {
+        return Err(ContractError::insufficient_allowance(
+            owner,
+            spender,
+            denom,
+            allowance.amount,
+            amount,
+        ));
+    }
+
+    allowance.amount -= amount;
+    if allowance.amount.is_zero() {
+        ALLOWANCES.remove(store, key);
+    } else {
+        ALLOWANCES.save(store, key, &allowance)?;
+    }
+
+    Ok(())
 }
 
 /// Assert that the sender is the bank contract.
@@ -273,6 +804,17 @@ fn assert_sender_bank(sender: &Addr) -> Result<(), ContractError> {
     Ok(())
 }
 
+/// Assert that the token exists. Return the denom's creator and nonce.
+fn assert_token_exists(deps: Deps, denom: &str) -> Result<(Addr, String), ContractError> {
+    let (creator, nonce) = parse_denom(deps.api, denom)?;
+
+    if !TOKEN_CONFIGS.has(deps.storage, (&creator, &nonce)) {
+        return Err(ContractError::token_not_found(denom));
+    }
+
+    Ok((creator, nonce))
+}
+
 /// Assert that sender is the denom's current admin. Return the denom's creator
 /// and nonce.
 fn assert_denom_admin(
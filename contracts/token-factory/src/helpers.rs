@@ -1,6 +1,7 @@
-use cosmwasm_std::{Addr, Api};
+use cosmwasm_std::{Addr, Api, Coin, QuerierWrapper, StdResult, Uint128};
+use cw_bank::msg as bank;
 
-use crate::{error::ContractError, NAMESPACE};
+use crate::{error::ContractError, BANK, NAMESPACE};
 
 pub(crate) fn parse_denom(api: &dyn Api, denom: &str) -> Result<(Addr, String), ContractError> {
     let Some((namespace, subdenom)) = denom.split_once('/') else {
@@ -18,6 +19,18 @@ pub(crate) fn parse_denom(api: &dyn Api, denom: &str) -> Result<(Addr, String),
     Ok((api.addr_validate(creator)?, nonce.to_owned()))
 }
 
+/// Query the bank contract for a token's supply, which holders may also reduce
+/// by burning their own coins in the bank.
+pub(crate) fn query_supply(querier: &QuerierWrapper, denom: &str) -> StdResult<Uint128> {
+    let supply: Coin = querier.query_wasm_smart(
+        BANK,
+        &bank::QueryMsg::Supply {
+            denom: denom.into(),
+        },
+    )?;
+    Ok(supply.amount)
+}
+
 #[cfg(test)]
 use cosmwasm_std::testing::MockApi;
 
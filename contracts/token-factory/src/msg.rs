@@ -1,6 +1,10 @@
+use std::fmt;
+
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, Uint128};
+use cw_bank::msg::Metadata;
 use cw_ownable::{cw_ownable_execute, cw_ownable_query};
+use cw_utils::Expiration;
 
 #[cw_serde]
 pub struct TokenConfig {
@@ -12,6 +16,26 @@ pub struct TokenConfig {
     /// Any AfterTransfer hook message sent by the bank contract will be
     /// forwarded to this address.
     pub after_transfer_hook: Option<Addr>,
+
+    /// Whether a transfer of the token is reverted or goes through if the
+    /// after transfer hook fails. Tokens created before the policy existed
+    /// default to reverting.
+    #[serde(default)]
+    pub hook_failure_policy: HookFailurePolicy,
+
+    /// A contract queried on every transfer of the token, before the after
+    /// transfer hook, which may reject the transfer by returning an error. It
+    /// is registered in the bank contract as the denom's send hook, so it is
+    /// queried with the bank's `SendHookQueryMsg` directly.
+    pub before_send_hook: Option<Addr>,
+
+    /// The token's display information, which is also set in the bank
+    /// contract.
+    pub metadata: Option<Metadata>,
+
+    /// The most that may be minted of the token, net of burns. Set at
+    /// creation and can't be changed. `None` means uncapped.
+    pub max_supply: Option<Uint128>,
 }
 
 #[cw_serde]
@@ -19,6 +43,75 @@ pub struct UpdateTokenMsg {
     pub denom: String,
     pub admin: Option<String>,
     pub after_transfer_hook: Option<String>,
+    #[serde(default)]
+    pub hook_failure_policy: HookFailurePolicy,
+    pub before_send_hook: Option<String>,
+    pub metadata: Option<Metadata>,
+}
+
+/// What happens to a transfer when the token's after transfer hook fails,
+/// including by running out of gas
+#[cw_serde]
+#[derive(Default)]
+pub enum HookFailurePolicy {
+    /// The transfer is reverted along with the hook
+    #[default]
+    Revert,
+
+    /// The hook's changes are reverted, but the transfer goes through
+    Ignore,
+}
+
+impl fmt::Display for HookFailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookFailurePolicy::Revert => write!(f, "revert"),
+            HookFailurePolicy::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+/// The amount of a token that a spender may transfer or burn on behalf of its
+/// holder
+#[cw_serde]
+#[derive(Default)]
+pub struct Allowance {
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct MintOutput {
+    pub to: String,
+    pub denom: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct BurnInput {
+    pub from: String,
+    pub denom: String,
+    pub amount: Uint128,
+}
+
+/// Where the fee paid for creating each token goes
+#[cw_serde]
+pub enum FeeRecipient {
+    /// Kept in the contract, until withdrawn by the owner
+    Keep,
+
+    /// Burned
+    Burn,
+
+    /// Sent to an account
+    Address(String),
+
+    /// Sent to a contract, e.g. a community pool, along with a message that
+    /// the contract is executed with
+    CommunityPool {
+        address: String,
+        msg: Binary,
+    },
 }
 
 #[cw_serde]
@@ -26,20 +119,39 @@ pub struct InstantiateMsg {
     /// The account to be appointed as contract owner
     pub owner: String,
 
-    /// An optional fee for creating new denoms. Set to `None` to make it free.
-    pub token_creation_fee: Option<Coin>,
+    /// The fees accepted for creating new denoms, of which the creator pays
+    /// any one. Set to empty to make it free.
+    pub token_creation_fee: Vec<Coin>,
+
+    /// Where the fees go
+    pub fee_recipient: FeeRecipient,
+
+    /// The most gas an after transfer hook may consume
+    pub hook_gas_limit: u64,
 }
 
 #[cw_ownable_execute]
 #[cw_serde]
 pub enum ExecuteMsg {
-    /// Update the fee for creating new denoms.
+    /// Update the fees accepted for creating new denoms.
     /// Only callable by the owner.
     UpdateFee {
-        token_creation_fee: Option<Coin>,
+        token_creation_fee: Vec<Coin>,
+    },
+
+    /// Update where the fees go.
+    /// Only callable by the owner.
+    UpdateFeeRecipient {
+        fee_recipient: FeeRecipient,
     },
 
-    /// Withdraw fees collected in the contract.
+    /// Update the most gas an after transfer hook may consume.
+    /// Only callable by the owner.
+    UpdateHookGasLimit {
+        hook_gas_limit: u64,
+    },
+
+    /// Withdraw fees kept in the contract.
     /// Only callable by the owner.
     WithdrawFee {
         /// Address to which the fees are to be sent.
@@ -61,6 +173,20 @@ pub enum ExecuteMsg {
 
         /// See the comments on `TokenConfig` on what this hook is.
         after_transfer_hook: Option<String>,
+
+        /// Whether transfers go through if the after transfer hook fails.
+        #[serde(default)]
+        hook_failure_policy: HookFailurePolicy,
+
+        /// See the comments on `TokenConfig` on what this hook is.
+        before_send_hook: Option<String>,
+
+        /// The token's symbol, decimals and description, to be set in the
+        /// bank contract.
+        metadata: Option<Metadata>,
+
+        /// A hard cap on the token's supply, which can't be changed later.
+        max_supply: Option<Uint128>,
     },
 
     /// Update a token's configuration.
@@ -75,6 +201,13 @@ pub enum ExecuteMsg {
         amount: Uint128,
     },
 
+    /// Mint tokens to multiple accounts at once, e.g. for an airdrop. The
+    /// outputs may be of different tokens, and the sender must be the admin
+    /// of each. If any of the mints fails, none of them happens.
+    MintBatch {
+        outputs: Vec<MintOutput>,
+    },
+
     /// Burn tokens from from designated account's balance.
     /// Only callable by the token's admin.
     Burn {
@@ -83,6 +216,12 @@ pub enum ExecuteMsg {
         amount: Uint128,
     },
 
+    /// Burn tokens from multiple accounts at once. The sender must be the
+    /// admin of each token. If any of the burns fails, none of them happens.
+    BurnBatch {
+        inputs: Vec<BurnInput>,
+    },
+
     /// Forcibly transfer tokens between two accounts.
     /// Only callable by the token's admin.
     ForceTransfer {
@@ -92,6 +231,63 @@ pub enum ExecuteMsg {
         amount: Uint128,
     },
 
+    /// Set the amount of a token that `spender` may transfer or burn on behalf
+    /// of the sender, replacing any previous allowance.
+    Approve {
+        spender: String,
+        denom: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+
+    /// Increase the amount of a token that `spender` may transfer or burn on
+    /// behalf of the sender. The expiration is updated if provided.
+    IncreaseAllowance {
+        spender: String,
+        denom: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+
+    /// Decrease the amount of a token that `spender` may transfer or burn on
+    /// behalf of the sender, removing the allowance if it drops to zero. The
+    /// expiration is updated if provided.
+    DecreaseAllowance {
+        spender: String,
+        denom: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+
+    /// Transfer tokens from `owner` to `to`, using the sender's allowance.
+    TransferFrom {
+        owner: String,
+        to: String,
+        denom: String,
+        amount: Uint128,
+    },
+
+    /// Burn tokens from `owner`'s balance, using the sender's allowance.
+    BurnFrom {
+        owner: String,
+        denom: String,
+        amount: Uint128,
+    },
+
+    /// Pause users' transfers of a token, including fee payments, by freezing
+    /// it in the bank contract. Force transfers and transfers made by the state
+    /// machine are not affected.
+    /// Only callable by the token's admin.
+    Freeze {
+        denom: String,
+    },
+
+    /// Resume transfers of a frozen token.
+    /// Only callable by the token's admin.
+    Unfreeze {
+        denom: String,
+    },
+
     /// Invoked every time a token is transferred.
     /// Only callable by the bank contract.
     AfterTransfer {
@@ -106,16 +302,45 @@ pub enum ExecuteMsg {
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    /// Query the token creation fee
-    #[returns(Option<Coin>)]
+    /// Query the fees accepted for creating new denoms
+    #[returns(Vec<Coin>)]
     TokenCreationFee {},
 
+    /// Query where the fees go
+    #[returns(FeeRecipient)]
+    FeeRecipient {},
+
+    /// Query the most gas an after transfer hook may consume
+    #[returns(u64)]
+    HookGasLimit {},
+
     /// Query the configuration of a single token by denom
     #[returns(TokenResponse)]
     Token {
         denom: String,
     },
 
+    /// Query a token's current supply and its cap
+    #[returns(SupplyResponse)]
+    Supply {
+        denom: String,
+    },
+
+    /// Query the amount of a token that `spender` may transfer or burn on
+    /// behalf of `owner`
+    #[returns(Allowance)]
+    Allowance {
+        owner: String,
+        spender: String,
+        denom: String,
+    },
+
+    /// Query whether a token's transfers are paused
+    #[returns(FrozenResponse)]
+    Frozen {
+        denom: String,
+    },
+
     /// Enumerate the config of all tokens
     #[returns(Vec<TokenResponse>)]
     Tokens {
@@ -125,3 +350,16 @@ pub enum QueryMsg {
 }
 
 pub type TokenResponse = UpdateTokenMsg;
+
+#[cw_serde]
+pub struct FrozenResponse {
+    pub denom: String,
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct SupplyResponse {
+    pub denom: String,
+    pub supply: Uint128,
+    pub max_supply: Option<Uint128>,
+}
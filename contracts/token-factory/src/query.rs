@@ -4,16 +4,24 @@ use cw_storage_plus::Bound;
 
 use crate::{
     error::ContractError,
-    helpers::parse_denom,
-    msg::TokenResponse,
-    state::{TOKEN_CONFIGS, TOKEN_CREATION_FEE},
+    helpers::{parse_denom, query_supply},
+    msg::{Allowance, FeeRecipient, FrozenResponse, SupplyResponse, TokenResponse},
+    state::{ALLOWANCES, FEE_RECIPIENT, FROZEN, HOOK_GAS_LIMIT, TOKEN_CONFIGS, TOKEN_CREATION_FEE},
     NAMESPACE,
 };
 
-pub fn token_creation_fee(deps: Deps) -> StdResult<Option<Coin>> {
+pub fn token_creation_fee(deps: Deps) -> StdResult<Vec<Coin>> {
     TOKEN_CREATION_FEE.load(deps.storage)
 }
 
+pub fn fee_recipient(deps: Deps) -> StdResult<FeeRecipient> {
+    FEE_RECIPIENT.load(deps.storage)
+}
+
+pub fn hook_gas_limit(deps: Deps) -> StdResult<u64> {
+    HOOK_GAS_LIMIT.load(deps.storage)
+}
+
 pub fn token(deps: Deps, denom: String) -> Result<TokenResponse, ContractError> {
     let (creator, nonce) = parse_denom(deps.api, &denom)?;
     let cfg = TOKEN_CONFIGS.load(deps.storage, (&creator, &nonce))?;
@@ -21,9 +29,43 @@ pub fn token(deps: Deps, denom: String) -> Result<TokenResponse, ContractError>
         denom,
         admin: cfg.admin.map(String::from),
         after_transfer_hook: cfg.after_transfer_hook.map(String::from),
+        hook_failure_policy: cfg.hook_failure_policy,
+        before_send_hook: cfg.before_send_hook.map(String::from),
+        metadata: cfg.metadata,
+    })
+}
+
+pub fn supply(deps: Deps, denom: String) -> Result<SupplyResponse, ContractError> {
+    let (creator, nonce) = parse_denom(deps.api, &denom)?;
+    let cfg = TOKEN_CONFIGS.load(deps.storage, (&creator, &nonce))?;
+    Ok(SupplyResponse {
+        supply: query_supply(&deps.querier, &denom)?,
+        denom,
+        max_supply: cfg.max_supply,
     })
 }
 
+pub fn frozen(deps: Deps, denom: String) -> Result<FrozenResponse, ContractError> {
+    let (creator, nonce) = parse_denom(deps.api, &denom)?;
+    TOKEN_CONFIGS.load(deps.storage, (&creator, &nonce))?;
+    Ok(FrozenResponse {
+        frozen: FROZEN.has(deps.storage, (&creator, &nonce)),
+        denom,
+    })
+}
+
+pub fn allowance(
+    deps: Deps,
+    owner: String,
+    spender: String,
+    denom: String,
+) -> Result<Allowance, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let key = (&owner_addr, &spender_addr, denom.as_str());
+    Ok(ALLOWANCES.may_load(deps.storage, key)?.unwrap_or_default())
+}
+
 pub fn tokens(
     deps: Deps,
     start_after: Option<String>,
@@ -45,6 +87,9 @@ pub fn tokens(
             denom: format!("{NAMESPACE}/{creator}/{nonce}"),
             admin: cfg.admin.map(String::from),
             after_transfer_hook: cfg.after_transfer_hook.map(String::from),
+            hook_failure_policy: cfg.hook_failure_policy,
+            before_send_hook: cfg.before_send_hook.map(String::from),
+            metadata: cfg.metadata,
         })
     })
 }
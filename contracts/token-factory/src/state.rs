@@ -1,10 +1,18 @@
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Coin, Empty};
 use cw_storage_plus::{Item, Map};
 
-use crate::msg::TokenConfig;
+use crate::msg::{Allowance, FeeRecipient, TokenConfig};
 
 /// The contract's configuration
-pub const TOKEN_CREATION_FEE: Item<Option<Coin>> = Item::new("token_creation_fee");
+pub const TOKEN_CREATION_FEE: Item<Vec<Coin>> = Item::new("token_creation_fee");
+pub const FEE_RECIPIENT: Item<FeeRecipient> = Item::new("fee_recipient");
+pub const HOOK_GAS_LIMIT: Item<u64> = Item::new("hook_gas_limit");
 
 /// Configuration of tokens indexed by creator address and subdenom
 pub const TOKEN_CONFIGS: Map<(&Addr, &str), TokenConfig> = Map::new("tkn_cfgs");
+
+/// Tokens whose transfers are paused by their admins
+pub const FROZEN: Map<(&Addr, &str), Empty> = Map::new("frozen");
+
+/// Allowances indexed by owner, spender and denom
+pub const ALLOWANCES: Map<(&Addr, &Addr, &str), Allowance> = Map::new("allowances");
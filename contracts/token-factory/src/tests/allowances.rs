@@ -0,0 +1,254 @@
+use cosmwasm_std::{
+    testing::{mock_env, mock_info},
+    to_binary, SubMsg, Uint128, WasmMsg,
+};
+use cw_bank::msg as bank;
+use cw_utils::Expiration;
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::Allowance,
+    query,
+    tests::{setup_test, DENOM},
+    BANK,
+};
+
+const OWNER: &str = "alice";
+const SPENDER: &str = "bob";
+
+#[test]
+fn approving() {
+    let mut deps = setup_test();
+
+    // can't approve oneself
+    let err = execute::approve(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        OWNER.into(),
+        DENOM.into(),
+        Uint128::new(100),
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CannotSetOwnAccount);
+
+    // can't approve a token that doesn't exist
+    let err = execute::approve(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        "factory/larry/uosmo".into(),
+        Uint128::new(100),
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::token_not_found("factory/larry/uosmo"));
+
+    execute::approve(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        DENOM.into(),
+        Uint128::new(100),
+        Some(Expiration::AtHeight(54321)),
+    )
+    .unwrap();
+
+    execute::increase_allowance(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        DENOM.into(),
+        Uint128::new(50),
+        None,
+    )
+    .unwrap();
+
+    let allowance = query::allowance(deps.as_ref(), OWNER.into(), SPENDER.into(), DENOM.into());
+    assert_eq!(
+        allowance.unwrap(),
+        Allowance {
+            amount: Uint128::new(150),
+            expires: Expiration::AtHeight(54321),
+        },
+    );
+
+    execute::decrease_allowance(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        DENOM.into(),
+        Uint128::new(30),
+        Some(Expiration::Never {}),
+    )
+    .unwrap();
+
+    let allowance = query::allowance(deps.as_ref(), OWNER.into(), SPENDER.into(), DENOM.into());
+    assert_eq!(
+        allowance.unwrap(),
+        Allowance {
+            amount: Uint128::new(120),
+            expires: Expiration::Never {},
+        },
+    );
+
+    // decreasing by more than the allowance removes it
+    execute::decrease_allowance(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        DENOM.into(),
+        Uint128::new(999),
+        None,
+    )
+    .unwrap();
+
+    let allowance = query::allowance(deps.as_ref(), OWNER.into(), SPENDER.into(), DENOM.into());
+    assert_eq!(allowance.unwrap(), Allowance::default());
+}
+
+#[test]
+fn transferring_from() {
+    let mut deps = setup_test();
+
+    execute::approve(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        DENOM.into(),
+        Uint128::new(100),
+        None,
+    )
+    .unwrap();
+
+    // can't spend more than the allowance
+    let err = execute::transfer_from(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SPENDER, &[]),
+        OWNER.into(),
+        "charlie".into(),
+        DENOM.into(),
+        Uint128::new(101),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::insufficient_allowance(
+            OWNER,
+            SPENDER,
+            DENOM,
+            Uint128::new(100),
+            Uint128::new(101),
+        ),
+    );
+
+    let res = execute::transfer_from(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SPENDER, &[]),
+        OWNER.into(),
+        "charlie".into(),
+        DENOM.into(),
+        Uint128::new(60),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::ForceTransfer {
+                from: OWNER.into(),
+                to: "charlie".into(),
+                denom: DENOM.into(),
+                amount: Uint128::new(60),
+            })
+            .unwrap(),
+            funds: vec![],
+        })],
+    );
+
+    let allowance = query::allowance(deps.as_ref(), OWNER.into(), SPENDER.into(), DENOM.into());
+    assert_eq!(allowance.unwrap().amount, Uint128::new(40));
+}
+
+#[test]
+fn burning_from() {
+    let mut deps = setup_test();
+
+    execute::mint(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        OWNER.into(),
+        DENOM.into(),
+        Uint128::new(100),
+    )
+    .unwrap();
+
+    execute::approve(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        DENOM.into(),
+        Uint128::new(100),
+        None,
+    )
+    .unwrap();
+
+    let res = execute::burn_from(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SPENDER, &[]),
+        OWNER.into(),
+        DENOM.into(),
+        Uint128::new(100),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::Burn {
+                from: OWNER.into(),
+                denom: DENOM.into(),
+                amount: Uint128::new(100),
+            })
+            .unwrap(),
+            funds: vec![],
+        })],
+    );
+
+    // the allowance is used up and removed
+    let allowance = query::allowance(deps.as_ref(), OWNER.into(), SPENDER.into(), DENOM.into());
+    assert_eq!(allowance.unwrap(), Allowance::default());
+}
+
+#[test]
+fn expired_allowance() {
+    let mut deps = setup_test();
+
+    let env = mock_env();
+
+    execute::approve(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        SPENDER.into(),
+        DENOM.into(),
+        Uint128::new(100),
+        Some(Expiration::AtHeight(env.block.height)),
+    )
+    .unwrap();
+
+    let err = execute::transfer_from(
+        deps.as_mut(),
+        env,
+        mock_info(SPENDER, &[]),
+        OWNER.into(),
+        "charlie".into(),
+        DENOM.into(),
+        Uint128::new(1),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::allowance_expired(OWNER, SPENDER, DENOM));
+}
@@ -1,13 +1,16 @@
-use cosmwasm_std::{coin, coins, testing::mock_info, Coin, DepsMut, Uint128};
-use cw_bank::denom::DenomError;
+use cosmwasm_std::{
+    coin, coins, testing::mock_info, to_binary, Coin, DepsMut, SubMsg, Uint128, WasmMsg,
+};
+use cw_bank::{denom::DenomError, msg as bank};
 use cw_utils::PaymentError;
 
 use crate::{
     error::ContractError,
     execute,
-    msg::TokenResponse,
+    msg::{HookFailurePolicy, TokenResponse},
     query,
     tests::{fee, setup_test, DENOM, OWNER},
+    BANK,
 };
 
 #[test]
@@ -21,6 +24,10 @@ fn incorrect_fee() {
             "uastro".into(),
             "larry".into(),
             None,
+            HookFailurePolicy::Revert,
+            None,
+            None,
+            None,
         )
         .unwrap_err()
     }
@@ -37,7 +44,7 @@ fn incorrect_fee() {
     // incorrect fee denom
     assert_eq!(
         create(deps.as_mut(), &coins(12345, "umars")),
-        PaymentError::MissingDenom("ujuno".into()).into(),
+        ContractError::fee_denom_not_accepted("umars"),
     );
 
     // correct fee denom but incorrect amount
@@ -59,6 +66,10 @@ fn invalid_denom() {
         invalid_nonce.into(),
         "larry".into(),
         None,
+        HookFailurePolicy::Revert,
+        None,
+        None,
+        None,
     )
     .unwrap_err();
 
@@ -77,6 +88,10 @@ fn proper_token_creation() {
         "umars".into(),
         "jake".into(),
         Some("pumpkin".into()),
+        HookFailurePolicy::Revert,
+        None,
+        None,
+        None,
     )
     .unwrap();
 
@@ -87,6 +102,9 @@ fn proper_token_creation() {
             denom: denom.into(),
             admin: Some("jake".into()),
             after_transfer_hook: Some("pumpkin".into()),
+            hook_failure_policy: HookFailurePolicy::Revert,
+            before_send_hook: None,
+            metadata: None,
         },
     );
 }
@@ -101,6 +119,10 @@ fn duplicate_denom() {
         "uastro".into(),
         "larry".into(),
         None,
+        HookFailurePolicy::Revert,
+        None,
+        None,
+        None,
     )
     .unwrap_err();
 
@@ -117,6 +139,9 @@ fn not_owner_or_admin() {
         DENOM.into(),
         None,
         None,
+        HookFailurePolicy::Revert,
+        None,
+        None,
     )
     .unwrap_err();
 
@@ -135,6 +160,9 @@ fn proper_token_update() {
             DENOM.into(),
             None,
             Some("some_contract".into()),
+            HookFailurePolicy::Revert,
+            None,
+            None,
         )
         .unwrap();
 
@@ -145,6 +173,9 @@ fn proper_token_update() {
                 denom: DENOM.into(),
                 admin: None,
                 after_transfer_hook: Some("some_contract".into()),
+                hook_failure_policy: HookFailurePolicy::Revert,
+                before_send_hook: None,
+                metadata: None,
             },
         );
     }
@@ -157,8 +188,70 @@ fn proper_token_update() {
             DENOM.into(),
             Some(OWNER.into()),
             Some("another_contract".into()),
+            HookFailurePolicy::Revert,
+            None,
+            None,
         )
         .unwrap_err();
         assert_eq!(err, ContractError::not_token_admin(DENOM));
     }
 }
+
+#[test]
+fn metadata() {
+    let mut deps = setup_test();
+
+    let denom = "factory/larry/umars";
+    let metadata = bank::Metadata {
+        symbol: "MARS".into(),
+        decimals: 6,
+        description: Some("Mars Protocol token".into()),
+    };
+
+    fn set_metadata_msg(denom: &str, metadata: Option<bank::Metadata>) -> SubMsg {
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::SetMetadata {
+                denom: denom.into(),
+                metadata,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    }
+
+    // the metadata is forwarded to the bank contract on creation
+    let res = execute::create_token(
+        deps.as_mut(),
+        mock_info("larry", &[fee()]),
+        "umars".into(),
+        "jake".into(),
+        None,
+        HookFailurePolicy::Revert,
+        None,
+        Some(metadata.clone()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![set_metadata_msg(denom, Some(metadata.clone()))]);
+
+    let token = query::token(deps.as_ref(), denom.into()).unwrap();
+    assert_eq!(token.metadata, Some(metadata.clone()));
+
+    // and on every update, removing it if not provided
+    let res = execute::update_token(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        denom.into(),
+        Some("jake".into()),
+        None,
+        HookFailurePolicy::Revert,
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(res.messages.last(), Some(&set_metadata_msg(denom, None)));
+
+    let token = query::token(deps.as_ref(), denom.into()).unwrap();
+    assert_eq!(token.metadata, None);
+}
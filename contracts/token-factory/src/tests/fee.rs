@@ -1,28 +1,47 @@
 use cosmwasm_std::{
     coin,
     testing::{mock_env, mock_info},
+    to_binary, Coin, DepsMut, Empty, Response, SubMsg, Uint128, WasmMsg,
 };
+use cw_bank::msg as bank;
 use cw_ownable::OwnershipError;
 
 use crate::{
+    error::ContractError,
     execute,
+    msg::{FeeRecipient, HookFailurePolicy},
     query,
-    tests::{setup_test, OWNER},
+    tests::{fee, setup_test, OWNER},
+    BANK,
 };
 
+fn create(deps: DepsMut, nonce: &str, fee: Coin) -> Result<Response, ContractError> {
+    execute::create_token(
+        deps,
+        mock_info("larry", &[fee]),
+        nonce.into(),
+        "larry".into(),
+        None,
+        HookFailurePolicy::Revert,
+        None,
+        None,
+        None,
+    )
+}
+
 #[test]
 fn updating_fee() {
     let mut deps = setup_test();
 
     // non-owner cannot update fee
     {
-        let err = execute::update_fee(deps.as_mut(), mock_info("jake", &[]), None).unwrap_err();
+        let err = execute::update_fee(deps.as_mut(), mock_info("jake", &[]), vec![]).unwrap_err();
         assert_eq!(err, OwnershipError::NotOwner.into());
     }
 
     // owner properly updates fee
     {
-        let fee = Some(coin(88888, "umars"));
+        let fee = vec![coin(88888, "umars"), coin(12345, "ujuno")];
 
         execute::update_fee(deps.as_mut(), mock_info(OWNER, &[]), fee.clone()).unwrap();
 
@@ -51,3 +70,94 @@ fn withdrawing_fee() {
     // further tests require querying the bank contract
     // for those we move to integration tests instead
 }
+
+#[test]
+fn multiple_fee_denoms() {
+    let mut deps = setup_test();
+
+    execute::update_fee(
+        deps.as_mut(),
+        mock_info(OWNER, &[]),
+        vec![fee(), coin(88888, "umars")],
+    )
+    .unwrap();
+
+    // either of the fees may be paid
+    create(deps.as_mut(), "utoken", fee()).unwrap();
+    create(deps.as_mut(), "uother", coin(88888, "umars")).unwrap();
+
+    // but the amount must be that of the fee in the paid denom
+    let err = create(deps.as_mut(), "uthird", coin(12345, "umars")).unwrap_err();
+    assert_eq!(err, ContractError::incorrect_fee(coin(88888, "umars"), Uint128::new(12345)));
+
+    let err = create(deps.as_mut(), "uthird", coin(12345, "uosmo")).unwrap_err();
+    assert_eq!(err, ContractError::fee_denom_not_accepted("uosmo"));
+}
+
+#[test]
+fn distributing_fee() {
+    let mut deps = setup_test();
+
+    // non-owner cannot update the fee recipient
+    let err = execute::update_fee_recipient(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        FeeRecipient::Burn,
+    )
+    .unwrap_err();
+    assert_eq!(err, OwnershipError::NotOwner.into());
+
+    // the fee is kept in the contract by default
+    let res = create(deps.as_mut(), "ukeep", fee()).unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    let cases = [
+        (
+            FeeRecipient::Burn,
+            WasmMsg::Execute {
+                contract_addr: BANK.into(),
+                msg: to_binary(&bank::ExecuteMsg::BurnOwn {
+                    coins: vec![fee()],
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+        ),
+        (
+            FeeRecipient::Address("treasury".into()),
+            WasmMsg::Execute {
+                contract_addr: BANK.into(),
+                msg: to_binary(&bank::ExecuteMsg::Send {
+                    to: "treasury".into(),
+                    coins: vec![fee()],
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+        ),
+        (
+            FeeRecipient::CommunityPool {
+                address: "community-pool".into(),
+                msg: to_binary(&Empty {}).unwrap(),
+            },
+            WasmMsg::Execute {
+                contract_addr: "community-pool".into(),
+                msg: to_binary(&Empty {}).unwrap(),
+                funds: vec![fee()],
+            },
+        ),
+    ];
+
+    for (i, (fee_recipient, expected)) in cases.into_iter().enumerate() {
+        execute::update_fee_recipient(
+            deps.as_mut(),
+            mock_info(OWNER, &[]),
+            fee_recipient.clone(),
+        )
+        .unwrap();
+        assert_eq!(query::fee_recipient(deps.as_ref()).unwrap(), fee_recipient);
+
+        let res = create(deps.as_mut(), &format!("utoken{i}"), fee()).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(expected)]);
+    }
+}
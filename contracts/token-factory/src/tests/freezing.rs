@@ -0,0 +1,114 @@
+use cosmwasm_std::{
+    testing::{mock_env, mock_info},
+    to_binary, DepsMut, Response, SubMsg, Uint128, WasmMsg,
+};
+use cw_bank::msg as bank;
+
+use crate::{
+    error::ContractError,
+    execute,
+    msg::{FrozenResponse, HookFailurePolicy},
+    query,
+    tests::{setup_test, DENOM},
+    BANK,
+};
+
+fn set_frozen_msg(frozen: bool) -> SubMsg {
+    SubMsg::new(WasmMsg::Execute {
+        contract_addr: BANK.into(),
+        msg: to_binary(&bank::ExecuteMsg::SetFrozen {
+            denom: DENOM.into(),
+            frozen,
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
+
+/// Transfer the token from alice to charlie as bob, whom alice has approved.
+fn transfer_from(deps: DepsMut) -> Result<Response, ContractError> {
+    execute::transfer_from(
+        deps,
+        mock_env(),
+        mock_info("bob", &[]),
+        "alice".into(),
+        "charlie".into(),
+        DENOM.into(),
+        Uint128::new(100),
+    )
+}
+
+#[test]
+fn not_admin() {
+    let mut deps = setup_test();
+
+    let err = execute::freeze(deps.as_mut(), mock_info("badguy", &[]), DENOM.into()).unwrap_err();
+    assert_eq!(err, ContractError::not_token_admin(DENOM));
+
+    let err = execute::unfreeze(deps.as_mut(), mock_info("badguy", &[]), DENOM.into()).unwrap_err();
+    assert_eq!(err, ContractError::not_token_admin(DENOM));
+}
+
+#[test]
+fn freezing_and_unfreezing() {
+    let mut deps = setup_test();
+
+    execute::approve(
+        deps.as_mut(),
+        mock_info("alice", &[]),
+        "bob".into(),
+        DENOM.into(),
+        Uint128::new(100),
+        None,
+    )
+    .unwrap();
+
+    // freezing freezes the token in the bank contract, which then rejects
+    // users' sends of it
+    let res = execute::freeze(deps.as_mut(), mock_info("jake", &[]), DENOM.into()).unwrap();
+    assert_eq!(res.messages, vec![set_frozen_msg(true)]);
+    assert_eq!(
+        query::frozen(deps.as_ref(), DENOM.into()).unwrap(),
+        FrozenResponse {
+            denom: DENOM.into(),
+            frozen: true,
+        },
+    );
+
+    // the bank lets the admin's force transfers through, but spenders can't
+    // transfer the token on the owner's behalf either
+    let err = transfer_from(deps.as_mut()).unwrap_err();
+    assert_eq!(err, ContractError::frozen(DENOM));
+
+    // updating a frozen token still registers its new before send hook, since
+    // the freeze doesn't depend on the hook
+    let res = execute::update_token(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        DENOM.into(),
+        Some("jake".into()),
+        Some("pumpkin".into()),
+        HookFailurePolicy::Revert,
+        Some("blocklist".into()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.first(),
+        Some(&SubMsg::new(WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::SetSendHook {
+                denom: DENOM.into(),
+                hook: Some("blocklist".into()),
+            })
+            .unwrap(),
+            funds: vec![],
+        })),
+    );
+
+    let res = execute::unfreeze(deps.as_mut(), mock_info("jake", &[]), DENOM.into()).unwrap();
+    assert_eq!(res.messages, vec![set_frozen_msg(false)]);
+    assert!(!query::frozen(deps.as_ref(), DENOM.into()).unwrap().frozen);
+
+    transfer_from(deps.as_mut()).unwrap();
+}
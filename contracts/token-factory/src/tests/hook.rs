@@ -1,19 +1,28 @@
-use cosmwasm_std::{testing::mock_info, to_binary, Addr, Storage, SubMsg, Uint128, WasmMsg};
+use cosmwasm_std::{
+    from_slice, testing::mock_info, to_binary, Addr, Storage, SubMsg, SubMsgResult, Uint128,
+    WasmMsg,
+};
 use cw_bank::msg as bank;
+use cw_ownable::OwnershipError;
 use cw_sdk::address;
 
 use crate::{
     error::ContractError,
     execute,
-    msg::TokenConfig,
+    msg::{HookFailurePolicy, TokenConfig, UpdateTokenMsg},
+    query,
     state::TOKEN_CONFIGS,
-    tests::setup_test,
+    tests::{fee, setup_test, HOOK_GAS_LIMIT, OWNER},
     BANK,
 };
 
 const DENOM: &str = "factory/osmo1234abcd/uastro";
 
-fn set_hook(store: &mut dyn Storage, after_transfer_hook: Option<&str>) {
+fn set_hook(
+    store: &mut dyn Storage,
+    after_transfer_hook: Option<&str>,
+    hook_failure_policy: HookFailurePolicy,
+) {
     TOKEN_CONFIGS
         .save(
             store,
@@ -21,6 +30,10 @@ fn set_hook(store: &mut dyn Storage, after_transfer_hook: Option<&str>) {
             &TokenConfig {
                 admin: None,
                 after_transfer_hook: after_transfer_hook.map(Addr::unchecked),
+                hook_failure_policy,
+                before_send_hook: None,
+                metadata: None,
+                max_supply: None,
             },
         )
         .unwrap();
@@ -47,7 +60,7 @@ fn not_bank() {
 fn hook_undefined() {
     let mut deps = setup_test();
 
-    set_hook(deps.as_mut().storage, None);
+    set_hook(deps.as_mut().storage, None, HookFailurePolicy::Revert);
 
     let res = execute::after_transfer(
         deps.as_mut(),
@@ -66,7 +79,29 @@ fn hook_undefined() {
 fn hook_defined() {
     let mut deps = setup_test();
 
-    set_hook(deps.as_mut().storage, Some("jake"));
+    set_hook(deps.as_mut().storage, Some("jake"), HookFailurePolicy::Revert);
+
+    let res = execute::after_transfer(
+        deps.as_mut(),
+        mock_info(address::derive_from_label(BANK).unwrap().as_str(), &[]),
+        "alice".into(),
+        "bob".into(),
+        DENOM.into(),
+        Uint128::new(12345),
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(after_transfer_msg()).with_gas_limit(HOOK_GAS_LIMIT)],
+    );
+}
+
+#[test]
+fn hook_failure_ignored() {
+    let mut deps = setup_test();
+
+    set_hook(deps.as_mut().storage, Some("jake"), HookFailurePolicy::Ignore);
 
     let res = execute::after_transfer(
         deps.as_mut(),
@@ -80,16 +115,111 @@ fn hook_defined() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(WasmMsg::Execute {
-            contract_addr: "jake".into(),
-            msg: to_binary(&bank::HookMsg::AfterTransfer {
-                from: "alice".into(),
-                to: "bob".into(),
-                denom: DENOM.into(),
-                amount: Uint128::new(12345)
+        vec![SubMsg::reply_on_error(after_transfer_msg(), execute::AFTER_TRANSFER_REPLY_ID)
+            .with_gas_limit(HOOK_GAS_LIMIT)],
+    );
+
+    // the hook's error is caught rather than reverting the transfer
+    let res = execute::after_transfer_failed(SubMsgResult::Err("out of gas".into())).unwrap();
+    assert_eq!(res.attributes[1], ("error", "out of gas"));
+}
+
+#[test]
+fn hook_failure_policy_defaults_to_revert() {
+    let mut deps = setup_test();
+
+    // configs stored before the policy existed revert transfers on failure
+    deps.storage.set(
+        &TOKEN_CONFIGS.key((&Addr::unchecked("larry"), "uastro")),
+        br#"{"admin":"jake","after_transfer_hook":"pumpkin"}"#,
+    );
+    let token = query::token(deps.as_ref(), "factory/larry/uastro".into()).unwrap();
+    assert_eq!(token.hook_failure_policy, HookFailurePolicy::Revert);
+
+    // and so do messages that leave it out
+    let msg: UpdateTokenMsg =
+        from_slice(br#"{"denom":"factory/larry/uastro","admin":"jake"}"#).unwrap();
+    assert_eq!(msg.hook_failure_policy, HookFailurePolicy::Revert);
+}
+
+#[test]
+fn updating_hook_gas_limit() {
+    let mut deps = setup_test();
+
+    let err = execute::update_hook_gas_limit(deps.as_mut(), mock_info("jake", &[]), 1_000_000)
+        .unwrap_err();
+    assert_eq!(err, OwnershipError::NotOwner.into());
+
+    execute::update_hook_gas_limit(deps.as_mut(), mock_info(OWNER, &[]), 1_000_000).unwrap();
+
+    let hook_gas_limit = query::hook_gas_limit(deps.as_ref()).unwrap();
+    assert_eq!(hook_gas_limit, 1_000_000);
+}
+
+fn after_transfer_msg() -> WasmMsg {
+    WasmMsg::Execute {
+        contract_addr: "jake".into(),
+        msg: to_binary(&bank::HookMsg::AfterTransfer {
+            from: "alice".into(),
+            to: "bob".into(),
+            denom: DENOM.into(),
+            amount: Uint128::new(12345),
+        })
+        .unwrap(),
+        funds: vec![],
+    }
+}
+
+#[test]
+fn before_send_hook() {
+    let mut deps = setup_test();
+
+    let denom = "factory/larry/umars";
+
+    fn set_send_hook_msg(denom: &str, hook: Option<&str>) -> SubMsg {
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::SetSendHook {
+                denom: denom.into(),
+                hook: hook.map(String::from),
             })
             .unwrap(),
-            funds: vec![]
-        })],
-    );
+            funds: vec![],
+        })
+    }
+
+    // the hook is registered in the bank contract on creation
+    let res = execute::create_token(
+        deps.as_mut(),
+        mock_info("larry", &[fee()]),
+        "umars".into(),
+        "jake".into(),
+        None,
+        HookFailurePolicy::Revert,
+        Some("blocklist".into()),
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![set_send_hook_msg(denom, Some("blocklist"))]);
+
+    let token = query::token(deps.as_ref(), denom.into()).unwrap();
+    assert_eq!(token.before_send_hook, Some("blocklist".into()));
+
+    // and updated, or removed, along with the token's configuration
+    let res = execute::update_token(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        denom.into(),
+        Some("jake".into()),
+        None,
+        HookFailurePolicy::Revert,
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(res.messages.first(), Some(&set_send_hook_msg(denom, None)));
+
+    let token = query::token(deps.as_ref(), denom.into()).unwrap();
+    assert_eq!(token.before_send_hook, None);
 }
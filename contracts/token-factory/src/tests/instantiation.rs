@@ -2,8 +2,9 @@ use cosmwasm_std::Addr;
 use cw_ownable::Ownership;
 
 use crate::{
+    msg::FeeRecipient,
     query,
-    tests::{fee, setup_test, OWNER},
+    tests::{fee, setup_test, HOOK_GAS_LIMIT, OWNER},
 };
 
 #[test]
@@ -11,7 +12,13 @@ fn proper_instantiation() {
     let deps = setup_test();
 
     let token_creation_fee = query::token_creation_fee(deps.as_ref()).unwrap();
-    assert_eq!(token_creation_fee, Some(fee()));
+    assert_eq!(token_creation_fee, vec![fee()]);
+
+    let fee_recipient = query::fee_recipient(deps.as_ref()).unwrap();
+    assert_eq!(fee_recipient, FeeRecipient::Keep);
+
+    let hook_gas_limit = query::hook_gas_limit(deps.as_ref()).unwrap();
+    assert_eq!(hook_gas_limit, HOOK_GAS_LIMIT);
 
     let ownership = cw_ownable::get_ownership(deps.as_ref().storage).unwrap();
     assert_eq!(
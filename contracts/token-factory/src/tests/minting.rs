@@ -1,10 +1,14 @@
-use cosmwasm_std::{testing::mock_info, to_binary, SubMsg, Uint128, WasmMsg};
+use cosmwasm_std::{
+    testing::mock_info, to_binary, DepsMut, Response, StdError, SubMsg, Uint128, WasmMsg,
+};
 use cw_bank::msg as bank;
 
 use crate::{
     error::ContractError,
     execute,
-    tests::{setup_test, DENOM},
+    msg::{BurnInput, HookFailurePolicy, MintOutput, SupplyResponse},
+    query,
+    tests::{fee, mock_bank_supplies, setup_test, DENOM},
     BANK,
 };
 
@@ -58,6 +62,26 @@ fn minting() {
 fn burning() {
     let mut deps = setup_test();
 
+    // can't burn more than has been minted
+    let err = execute::burn(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "someone".into(),
+        DENOM.into(),
+        Uint128::new(999999),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+    execute::mint(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        "someone".into(),
+        DENOM.into(),
+        Uint128::new(999999),
+    )
+    .unwrap();
+
     let res = execute::burn(
         deps.as_mut(),
         mock_info("jake", &[]),
@@ -111,3 +135,146 @@ fn force_transferring() {
         })],
     );
 }
+
+#[test]
+fn max_supply() {
+    let mut deps = setup_test();
+
+    let denom = "factory/larry/umars";
+
+    execute::create_token(
+        deps.as_mut(),
+        mock_info("larry", &[fee()]),
+        "umars".into(),
+        "jake".into(),
+        None,
+        HookFailurePolicy::Revert,
+        None,
+        None,
+        Some(Uint128::new(1000)),
+    )
+    .unwrap();
+
+    fn mint(deps: DepsMut, denom: &str, amount: u128) -> Result<Response, ContractError> {
+        execute::mint(
+            deps,
+            mock_info("jake", &[]),
+            "someone".into(),
+            denom.into(),
+            Uint128::new(amount),
+        )
+    }
+
+    mock_bank_supplies(&mut deps.querier, &[(denom, 600)]);
+
+    let err = mint(deps.as_mut(), denom, 401).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::max_supply_exceeded(denom, Uint128::new(1001), Uint128::new(1000)),
+    );
+
+    // holders burning their own coins in the bank make room for minting again
+    mock_bank_supplies(&mut deps.querier, &[(denom, 400)]);
+    mint(deps.as_mut(), denom, 600).unwrap();
+
+    mock_bank_supplies(&mut deps.querier, &[(denom, 1000)]);
+    assert_eq!(
+        query::supply(deps.as_ref(), denom.into()).unwrap(),
+        SupplyResponse {
+            denom: denom.into(),
+            supply: Uint128::new(1000),
+            max_supply: Some(Uint128::new(1000)),
+        },
+    );
+
+    // the amounts minted earlier in a batch count towards the cap
+    mock_bank_supplies(&mut deps.querier, &[(denom, 400)]);
+    let output = |amount: u128| MintOutput {
+        to: "someone".into(),
+        denom: denom.into(),
+        amount: Uint128::new(amount),
+    };
+    let err =
+        execute::mint_batch(deps.as_mut(), mock_info("jake", &[]), vec![output(300), output(301)])
+            .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::max_supply_exceeded(denom, Uint128::new(1001), Uint128::new(1000)),
+    );
+    execute::mint_batch(deps.as_mut(), mock_info("jake", &[]), vec![output(300), output(300)])
+        .unwrap();
+
+    // tokens created without a max supply are uncapped
+    mint(deps.as_mut(), DENOM, u128::MAX).unwrap();
+    assert_eq!(
+        query::supply(deps.as_ref(), DENOM.into()).unwrap(),
+        SupplyResponse {
+            denom: DENOM.into(),
+            supply: Uint128::zero(),
+            max_supply: None,
+        },
+    );
+}
+
+#[test]
+fn batch_minting_and_burning() {
+    let mut deps = setup_test();
+
+    let output = |to: &str, amount: u128| MintOutput {
+        to: to.into(),
+        denom: DENOM.into(),
+        amount: Uint128::new(amount),
+    };
+    let mint_msg = |to: &str, amount: u128| {
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: BANK.into(),
+            msg: to_binary(&bank::ExecuteMsg::Mint {
+                to: to.into(),
+                denom: DENOM.into(),
+                amount: Uint128::new(amount),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    };
+
+    // there must be at least one output
+    let err = execute::mint_batch(deps.as_mut(), mock_info("jake", &[]), vec![]).unwrap_err();
+    assert_eq!(err, ContractError::EmptyBatch);
+
+    // the sender must be the admin of every token
+    let err = execute::mint_batch(
+        deps.as_mut(),
+        mock_info(BAD_GUY, &[]),
+        vec![output("alice", 100), output("bob", 200)],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::not_token_admin(DENOM));
+
+    let res = execute::mint_batch(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        vec![output("alice", 100), output("bob", 200)],
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![mint_msg("alice", 100), mint_msg("bob", 200)]);
+
+    let res = execute::burn_batch(
+        deps.as_mut(),
+        mock_info("jake", &[]),
+        vec![
+            BurnInput {
+                from: "alice".into(),
+                denom: DENOM.into(),
+                amount: Uint128::new(50),
+            },
+            BurnInput {
+                from: "bob".into(),
+                denom: DENOM.into(),
+                amount: Uint128::new(50),
+            },
+        ],
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+}
@@ -1,19 +1,29 @@
+mod allowances;
 mod creating;
 mod fee;
+mod freezing;
 mod hook;
 mod instantiation;
 mod minting;
 
+use std::collections::BTreeMap;
+
 use cosmwasm_std::{
-    coin,
+    coin, from_binary,
     testing::{mock_dependencies, mock_info, MockApi, MockQuerier, MockStorage},
-    Empty, OwnedDeps, Coin,
+    to_binary, ContractResult, Empty, OwnedDeps, Coin, SystemError, SystemResult, WasmQuery,
 };
+use cw_bank::msg as bank;
 
-use crate::execute;
+use crate::{
+    execute,
+    msg::{FeeRecipient, HookFailurePolicy},
+    BANK,
+};
 
 const OWNER: &str = "larry";
 const DENOM: &str = "factory/larry/uastro";
+const HOOK_GAS_LIMIT: u64 = 200_000;
 
 fn fee() -> Coin {
     coin(12345, "ujuno")
@@ -22,7 +32,7 @@ fn fee() -> Coin {
 fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
     let mut deps = mock_dependencies();
 
-    execute::init(deps.as_mut(), OWNER, Some(fee())).unwrap();
+    execute::init(deps.as_mut(), OWNER, vec![fee()], FeeRecipient::Keep, HOOK_GAS_LIMIT).unwrap();
 
     execute::create_token(
         deps.as_mut(),
@@ -30,8 +40,43 @@ fn setup_test() -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
         "uastro".into(),
         "jake".into(),
         Some("pumpkin".into()),
+        HookFailurePolicy::Revert,
+        None,
+        None,
+        None,
     )
     .unwrap();
 
     deps
 }
+
+/// Make the mock querier answer the bank contract's supply queries with the
+/// given amounts, and zero for any other denom.
+fn mock_bank_supplies(querier: &mut MockQuerier, supplies: &[(&str, u128)]) {
+    let supplies: BTreeMap<String, u128> =
+        supplies.iter().map(|(denom, amount)| (denom.to_string(), *amount)).collect();
+
+    querier.update_wasm(move |query| {
+        let WasmQuery::Smart {
+            contract_addr,
+            msg,
+        } = query
+        else {
+            return SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "non-smart wasm query".into(),
+            });
+        };
+        if contract_addr != BANK {
+            return SystemResult::Err(SystemError::NoSuchContract {
+                addr: contract_addr.clone(),
+            });
+        }
+        let Ok(bank::QueryMsg::Supply { denom }) = from_binary(msg) else {
+            return SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "bank query other than supply".into(),
+            });
+        };
+        let amount = supplies.get(&denom).copied().unwrap_or_default();
+        SystemResult::Ok(ContractResult::Ok(to_binary(&coin(amount, denom)).unwrap()))
+    });
+}
@@ -0,0 +1,128 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Subcommand};
+use cw_sdk::{address, GenesisContractState, SdkQuery};
+use cw_state_machine::StateMachine;
+use cw_store::Store;
+use tracing::info;
+
+use super::query::{query_raw_page, DUMP_PAGE_SIZE};
+use crate::{client::create_http_client, path, print, AppConfig, ClientConfig, DaemonError};
+
+#[derive(Args)]
+pub struct ContractStateCmd {
+    #[command(subcommand)]
+    subcommand: ContractStateSubcmd,
+}
+
+#[derive(Subcommand)]
+pub enum ContractStateSubcmd {
+    /// Export all key-value pairs in a contract's store from a node, in the
+    /// same format as the contract states in a genesis state
+    Export {
+        /// Contract address
+        contract: String,
+
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Tendermint RPC endpoint; overrides default value in client config
+        #[arg(long)]
+        node: Option<String>,
+    },
+
+    /// Replace all key-value pairs in a contract's store with those in a file
+    /// written by `export`, and commit the change to the local database. The
+    /// node must not be running.
+    ///
+    /// The app hash of the last committed block changes, which CometBFT refuses
+    /// to start with. Use this on devnets, or before forking the state into a
+    /// new genesis with `fork`.
+    Import {
+        /// Address of the contract to import the state into, which may differ
+        /// from the address it was exported from
+        contract: String,
+
+        /// Path to the exported contract state
+        file: PathBuf,
+    },
+}
+
+impl ContractStateCmd {
+    pub async fn run(self, home_dir: &Path) -> Result<(), DaemonError> {
+        match self.subcommand {
+            ContractStateSubcmd::Export {
+                contract,
+                out,
+                node,
+            } => {
+                let client_cfg = ClientConfig::load(home_dir)?;
+                let client = create_http_client(node.as_ref(), &client_cfg)?;
+                let contract_addr = address::validate(&contract)?;
+
+                let mut state = vec![];
+                let mut start_after = None;
+                loop {
+                    let query = SdkQuery::WasmAllRaw {
+                        contract: contract.clone(),
+                        prefix: None,
+                        start_after: start_after.take(),
+                        limit: Some(DUMP_PAGE_SIZE),
+                    };
+                    let page = query_raw_page(&client, None, query).await?;
+                    let done = page.len() < DUMP_PAGE_SIZE as usize;
+                    start_after = page.last().map(|(key, _)| key.clone());
+                    state.extend(page);
+
+                    if done {
+                        break;
+                    }
+                }
+
+                let contract_state = GenesisContractState {
+                    address: contract_addr.into(),
+                    state,
+                };
+                match &out {
+                    Some(out) => {
+                        fs::write(out, serde_json::to_vec_pretty(&contract_state)?)?;
+                        info!(
+                            "Exported {} key-value pairs to {}",
+                            contract_state.state.len(),
+                            path::stringify(out)?,
+                        );
+                        Ok(())
+                    },
+                    None => print::json(&contract_state),
+                }
+            },
+
+            ContractStateSubcmd::Import {
+                contract,
+                file,
+            } => {
+                if !file.exists() {
+                    return Err(DaemonError::file_not_found(&file)?);
+                }
+                let contract_state: GenesisContractState =
+                    serde_json::from_slice(&fs::read(&file)?)?;
+
+                let store = Store::open(AppConfig::load(home_dir)?.db_path(home_dir))?;
+                let mut state_machine = StateMachine::new(store);
+                let (height, app_hash) =
+                    state_machine.import_contract_state(&contract, &contract_state.state)?;
+
+                info!(
+                    "Imported {} key-value pairs into {contract} at height {height}, app hash {}",
+                    contract_state.state.len(),
+                    hex::encode_upper(app_hash),
+                );
+                Ok(())
+            },
+        }
+    }
+}
@@ -1,7 +1,22 @@
+use bip32::{DerivationPath, Language, Mnemonic};
 use clap::{Args, Subcommand};
-use cw_sdk::address;
+use cosmwasm_std::{coins, Binary};
+use cw_sdk::{
+    address, canonical::to_canonical_vec, hash::sha256, proto, Fee, LegacySignDoc, PubKeyType,
+    SdkMsg, SignMode, Tx, TxBody, TxSignature,
+};
+use cw_state_machine::auth;
+use k256::ecdsa::VerifyingKey;
+use serde_json::{json, Value};
 
-use crate::DaemonError;
+use super::start::DEV_MNEMONIC;
+use crate::{print, DaemonError, Key};
+
+/// Chain id that the test vectors are signed for
+const TEST_VECTOR_CHAIN_ID: &str = "cw-sdk-test-1";
+
+/// BIP-32 path that the test vectors' key is derived at
+const TEST_VECTOR_HD_PATH: &str = "m/44'/118'/0'/0/0";
 
 #[derive(Args)]
 pub struct DebugCmd {
@@ -13,10 +28,12 @@ pub struct DebugCmd {
 pub enum DebugSubcmd {
     /// Derive a base account's address based on its pubkey
     DeriveBaseAddress {
-        /// Public key in either hex encoding
+        /// Public key in hex encoding
         pubkey: String,
 
-        // TODO: add a `--base64` flag to allow using base64-encoded pubkeys
+        /// The pubkey is in base64 encoding instead
+        #[arg(long)]
+        base64: bool,
     },
 
     /// Derive a contract's address based on its label
@@ -24,6 +41,50 @@ pub enum DebugSubcmd {
         /// Contract label
         label: String,
     },
+
+    /// Decode a tx, as broadcasted in either JSON or protobuf, and print it
+    /// along with its hash and sign mode
+    DecodeTx {
+        /// Tx bytes in hex encoding
+        tx: String,
+
+        /// The tx is in base64 encoding instead
+        #[arg(long)]
+        base64: bool,
+    },
+
+    /// Validate an address and print the bytes it encodes
+    DecodeAddr {
+        /// Bech32 address
+        address: String,
+    },
+
+    /// Parse a secp256k1 pubkey, either compressed or not, and print it in all
+    /// encodings along with the addresses derived from it
+    Pubkey {
+        /// Public key in hex encoding
+        pubkey: String,
+
+        /// The pubkey is in base64 encoding instead
+        #[arg(long)]
+        base64: bool,
+    },
+
+    /// Print arbitrary bytes in hex and base64 encodings, and as a string and
+    /// JSON if they are valid UTF-8 and JSON
+    RawBytes {
+        /// Bytes in hex encoding
+        data: String,
+
+        /// The bytes are in base64 encoding instead
+        #[arg(long)]
+        base64: bool,
+    },
+
+    /// Print test vectors of tx bodies signed in each sign mode by a key of a
+    /// fixed mnemonic, so that client implementations can check that they
+    /// produce the same sign bytes, signatures and tx bytes
+    TestVectors,
 }
 
 impl DebugCmd {
@@ -31,8 +92,9 @@ impl DebugCmd {
         match self.subcommand {
             DebugSubcmd::DeriveBaseAddress {
                 pubkey,
+                base64,
             } => {
-                let pubkey_bytes = hex::decode(pubkey)?;
+                let pubkey_bytes = decode_blob(&pubkey, base64)?;
                 let addr = address::derive_from_pubkey(&pubkey_bytes)?;
                 println!("{addr}");
             },
@@ -43,8 +105,244 @@ impl DebugCmd {
                 let addr = address::derive_from_label(&label)?;
                 println!("{addr}");
             },
+
+            DebugSubcmd::DecodeTx {
+                tx,
+                base64,
+            } => {
+                let tx_bytes = decode_blob(&tx, base64)?;
+                let (tx, sign_mode) = auth::decode_tx(&tx_bytes)?;
+                let sign_mode = match sign_mode {
+                    SignMode::Json => "json",
+                    SignMode::Direct {
+                        ..
+                    } => "direct",
+                    SignMode::LegacyAminoJson => "legacy_amino_json",
+                };
+                print::json(json!({
+                    "hash": hex::encode_upper(sha256(&tx_bytes)),
+                    "sign_mode": sign_mode,
+                    "tx": tx,
+                }))?;
+            },
+
+            DebugSubcmd::DecodeAddr {
+                address,
+            } => {
+                let addr = address::validate(&address)?;
+                let bytes = address::canonicalize(addr.as_str())?;
+                print::json(json!({
+                    "address": addr,
+                    "length": bytes.len(),
+                    "hex": hex::encode(bytes.as_slice()),
+                    "base64": Binary::from(bytes.as_slice()),
+                }))?;
+            },
+
+            DebugSubcmd::Pubkey {
+                pubkey,
+                base64,
+            } => {
+                let pubkey = VerifyingKey::from_sec1_bytes(&decode_blob(&pubkey, base64)?)?;
+                let compressed = pubkey.to_encoded_point(true);
+                let uncompressed = pubkey.to_encoded_point(false);
+                print::json(json!({
+                    "compressed": {
+                        "hex": hex::encode(compressed.as_bytes()),
+                        "base64": Binary::from(compressed.as_bytes()),
+                    },
+                    "uncompressed": {
+                        "hex": hex::encode(uncompressed.as_bytes()),
+                        "base64": Binary::from(uncompressed.as_bytes()),
+                    },
+                    "address": address::derive_from_pubkey(compressed.as_bytes())?,
+                    "eth_address": address::derive_from_eth_pubkey(compressed.as_bytes())?,
+                }))?;
+            },
+
+            DebugSubcmd::RawBytes {
+                data,
+                base64,
+            } => {
+                let bytes = decode_blob(&data, base64)?;
+                let utf8 = std::str::from_utf8(&bytes).ok();
+                let json = serde_json::from_slice::<Value>(&bytes).ok();
+                print::json(json!({
+                    "length": bytes.len(),
+                    "hex": hex::encode(&bytes),
+                    "base64": Binary::from(bytes.as_slice()),
+                    "utf8": utf8,
+                    "json": json,
+                }))?;
+            },
+
+            DebugSubcmd::TestVectors => {
+                let mnemonic = Mnemonic::new(DEV_MNEMONIC, Language::English)?;
+                let path: DerivationPath = TEST_VECTOR_HD_PATH.parse()?;
+                let key = Key::from_mnemonic("test", &mnemonic, &path)?;
+                let pubkey = key.pubkey().to_bytes();
+
+                let mut vectors = vec![];
+                for (name, body) in test_vector_bodies(key.address()?.as_str())? {
+                    for sign_mode in ["json", "direct", "legacy_amino_json"] {
+                        vectors.push(test_vector(&key, &name, &body, sign_mode)?);
+                    }
+                }
+
+                print::json(json!({
+                    "mnemonic": DEV_MNEMONIC,
+                    "hd_path": TEST_VECTOR_HD_PATH,
+                    "privkey": hex::encode(key.privkey()?.to_bytes()),
+                    "pubkey": Binary::from(pubkey.as_slice()),
+                    "address": key.address()?,
+                    "vectors": vectors,
+                }))?;
+            },
         }
 
         Ok(())
     }
 }
+
+/// Decode a blob given on the command line in either hex or base64 encoding.
+/// A `0x` prefix is allowed for hex.
+fn decode_blob(blob: &str, base64: bool) -> Result<Vec<u8>, DaemonError> {
+    if base64 {
+        return Ok(Binary::from_base64(blob)?.to_vec());
+    }
+    let blob = blob.strip_prefix("0x").unwrap_or(blob);
+    hex::decode(blob).map_err(DaemonError::from)
+}
+
+/// The tx bodies that test vectors are generated for, sent by the given
+/// address: from the simplest possible one, to one with every optional field
+/// set and strings that need escaping in JSON.
+fn test_vector_bodies(sender: &str) -> Result<Vec<(String, TxBody)>, DaemonError> {
+    let empty = TxBody::single(sender, TEST_VECTOR_CHAIN_ID, 0, 1, vec![]);
+
+    let send = TxBody {
+        fee: Fee {
+            amount: coins(2500, "ucosm"),
+            gas_limit: 200_000,
+            ..Default::default()
+        },
+        ..TxBody::single(sender, TEST_VECTOR_CHAIN_ID, 3, 12, vec![SdkMsg::Execute {
+            contract: "bank".into(),
+            msg: json!({
+                "send": {
+                    "to": address::derive_from_label("faucet")?,
+                    "coins": coins(1_000_000, "ucosm"),
+                },
+            }),
+            funds: vec![],
+        }])
+    };
+
+    let instantiate = TxBody {
+        fee: Fee {
+            amount: coins(5000, "uatom"),
+            gas_limit: 400_000,
+            payer: None,
+            granter: Some(address::derive_from_label("sponsor")?.into()),
+        },
+        timeout_height: Some(123_456),
+        idempotency_key: Some("vector-3".into()),
+        ..TxBody::single(sender, TEST_VECTOR_CHAIN_ID, 7, 42, vec![
+            SdkMsg::StoreCode {
+                wasm_byte_code: b"\0asm\x01\0\0\0".to_vec().into(),
+            },
+            SdkMsg::Instantiate {
+                code_id: 1,
+                msg: json!({
+                    "name": "Ünïcödé \"quoted\" <tag> & \\ 🦀",
+                    "decimals": 6,
+                    "nested": { "z": [], "a": null },
+                }),
+                funds: coins(1, "uatom"),
+                label: "token".into(),
+                admin: Some(sender.into()),
+            },
+        ])
+    };
+
+    Ok(vec![("empty".into(), empty), ("send".into(), send), ("instantiate".into(), instantiate)])
+}
+
+/// Sign the tx body in the given sign mode and encode the tx the way it would
+/// be broadcasted, then check that `auth` accepts it before it's printed.
+fn test_vector(
+    key: &Key,
+    name: &str,
+    body: &TxBody,
+    sign_mode_name: &str,
+) -> Result<Value, DaemonError> {
+    let signer = &body.signers[0];
+    let body_bytes = proto::encode_body(body)?;
+    let (sign_mode, sign_doc) = match sign_mode_name {
+        "direct" => (
+            SignMode::Direct {
+                body_bytes: body_bytes.clone().into(),
+            },
+            json!(Binary::from(body_bytes.as_slice())),
+        ),
+        "legacy_amino_json" => (
+            SignMode::LegacyAminoJson,
+            json!(LegacySignDoc::new(body, &body.chain_id, signer.account_number, signer.sequence)),
+        ),
+        _ => (SignMode::Json, json!(body.sign_doc(signer)?)),
+    };
+    let sign_bytes =
+        sign_mode.sign_bytes(body, &body.chain_id, signer.account_number, signer.sequence)?;
+
+    let signature = TxSignature {
+        pubkey: Some(key.pubkey().to_bytes().to_vec().into()),
+        pubkey_type: PubKeyType::Secp256k1,
+        signature: key.sign_bytes(&sign_bytes)?.to_vec().into(),
+        multisig: None,
+        legacy_amino_json: sign_mode == SignMode::LegacyAminoJson,
+    };
+    let tx = Tx {
+        body: body.clone(),
+        signatures: vec![signature.clone()],
+    };
+    let tx_bytes = match sign_mode {
+        SignMode::Direct {
+            ..
+        } => proto::encode_tx(body_bytes, &tx.signatures),
+        _ => to_canonical_vec(&tx)?,
+    };
+
+    // the node must decode the tx and derive the same sign bytes from it
+    let (decoded, decoded_mode) = auth::decode_tx(&tx_bytes)?;
+    let decoded_mode = if signature.legacy_amino_json {
+        SignMode::LegacyAminoJson
+    } else {
+        decoded_mode
+    };
+    let decoded_sign_bytes = decoded_mode.sign_bytes(
+        &decoded.body,
+        &decoded.body.chain_id,
+        signer.account_number,
+        signer.sequence,
+    )?;
+    assert_eq!(decoded_sign_bytes, sign_bytes, "sign bytes of test vector {name} don't round trip");
+    auth::verify_signature(
+        PubKeyType::Secp256k1,
+        &key.pubkey().to_bytes(),
+        &decoded_sign_bytes,
+        &signature.signature,
+    )?;
+
+    Ok(json!({
+        "name": format!("{name}/{sign_mode_name}"),
+        "sign_mode": sign_mode_name,
+        "body": body,
+        "sign_doc": sign_doc,
+        "sign_bytes": hex::encode(&sign_bytes),
+        "sign_bytes_hash": hex::encode(sha256(&sign_bytes)),
+        "signature": signature.signature,
+        "tx": tx,
+        "tx_bytes": hex::encode(&tx_bytes),
+        "tx_hash": hex::encode_upper(sha256(&tx_bytes)),
+    }))
+}
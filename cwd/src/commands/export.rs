@@ -0,0 +1,40 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use clap::Args;
+use tracing::info;
+
+use cw_state_machine::StateMachine;
+use cw_store::Store;
+
+use crate::{path, print, AppConfig, DaemonError};
+
+/// To restart a chain from the exported state, include it as the `app_state`
+/// of the new chain's Tendermint genesis file, and set `initial_height` to the
+/// exported height plus one.
+#[derive(Args)]
+pub struct ExportCmd {
+    /// Write the exported genesis state to this file instead of printing it.
+    /// The node must not be running, as the database can only be opened by one
+    /// process at a time.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+impl ExportCmd {
+    pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
+        let store = Store::open(AppConfig::load(home_dir)?.db_path(home_dir))?;
+        let state_machine = StateMachine::new(store);
+
+        let (height, _) = state_machine.info()?;
+        let gen_state = state_machine.export()?;
+
+        match &self.out {
+            Some(out) => {
+                fs::write(out, serde_json::to_vec_pretty(&gen_state)?)?;
+                info!("Exported state at height {height} to {}", path::stringify(out)?);
+                Ok(())
+            },
+            None => print::json(&gen_state),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{SecondsFormat, Utc};
+use clap::Args;
+use serde_json::Value;
+use tracing::info;
+
+use cw_state_machine::StateMachine;
+use cw_store::Store;
+
+use crate::{path, print, AppConfig, DaemonError};
+
+#[derive(Args)]
+pub struct ForkCmd {
+    /// Height of the state to fork from. Only the state at the last committed
+    /// height is retained, so this must be the last committed height.
+    #[arg(long)]
+    height: u64,
+
+    /// Override a field in the new genesis, in the form of `key=value`. Keys
+    /// of nested fields are separated by dots, e.g.
+    /// `consensus_params.block.max_bytes=1000000`. May be repeated.
+    #[arg(long = "set-param")]
+    set_params: Vec<String>,
+
+    /// Replace the validator set with the one in this JSON file, which is in
+    /// the same format as the `validators` field in a Tendermint genesis file
+    #[arg(long)]
+    replace_validator_set: Option<PathBuf>,
+
+    /// Path to the Tendermint home directory, whose genesis file is used as
+    /// the template of the new genesis. Default to `~/.tendermint`.
+    #[arg(long)]
+    tendermint_home: Option<PathBuf>,
+
+    /// Write the new genesis to this file instead of printing it
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+impl ForkCmd {
+    pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
+        let tm_home = match &self.tendermint_home {
+            None => path::default_tm_home()?,
+            Some(tm_home) => tm_home.clone(),
+        };
+
+        let genesis_path = tm_home.join("config/genesis.json");
+        if !genesis_path.exists() {
+            return Err(DaemonError::file_not_found(&genesis_path)?);
+        }
+        let mut genesis: Value = serde_json::from_slice(&fs::read(&genesis_path)?)?;
+
+        // export the app state
+        let store = Store::open(AppConfig::load(home_dir)?.db_path(home_dir))?;
+        let state_machine = StateMachine::new(store);
+
+        let (height, _) = state_machine.info()?;
+        if height as u64 != self.height {
+            return Err(DaemonError::fork_height_mismatch(self.height, height as u64));
+        }
+
+        genesis["app_state"] = serde_json::to_value(state_machine.export()?)?;
+
+        // the new chain starts at the block after the forked height.
+        // Tendermint encodes 64-bit integers as strings in JSON
+        genesis["initial_height"] = Value::String((self.height + 1).to_string());
+        let genesis_time = Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true);
+        genesis["genesis_time"] = Value::String(genesis_time);
+
+        if let Some(validators_path) = &self.replace_validator_set {
+            let validators: Value = serde_json::from_slice(&fs::read(validators_path)?)?;
+            if !validators.is_array() {
+                return Err(DaemonError::invalid_validator_set(validators_path)?);
+            }
+            genesis["validators"] = validators;
+        }
+
+        for param in &self.set_params {
+            set_param(&mut genesis, param)?;
+        }
+
+        match &self.out {
+            Some(out) => {
+                fs::write(out, serde_json::to_vec_pretty(&genesis)?)?;
+                info!("Forked genesis at height {height} written to {}", path::stringify(out)?);
+                Ok(())
+            },
+            None => print::json(&genesis),
+        }
+    }
+}
+
+/// Set a field in the genesis, given a `key=value` string where nested keys are
+/// separated by dots.
+///
+/// If the field already exists as a string, the value is used as is; this way,
+/// numbers which Tendermint encodes as strings retain their encoding.
+/// Otherwise, the value is parsed as JSON, or used as a string if that fails.
+fn set_param(genesis: &mut Value, param: &str) -> Result<(), DaemonError> {
+    let (key, value) = param.split_once('=').ok_or_else(|| DaemonError::invalid_param(param))?;
+
+    let mut target = genesis;
+    for key in key.split('.') {
+        target = target
+            .as_object_mut()
+            .ok_or_else(|| DaemonError::invalid_param(param))?
+            .entry(key)
+            .or_insert(Value::Null);
+    }
+
+    *target = match target {
+        Value::String(_) => Value::String(value.into()),
+        _ => serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.into())),
+    };
+
+    Ok(())
+}
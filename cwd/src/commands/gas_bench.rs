@@ -0,0 +1,273 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use cosmwasm_std::{
+    testing::{mock_env, mock_info},
+    ContractResult, Empty, MemoryStorage, Storage,
+};
+use cosmwasm_vm::{call_execute, call_instantiate};
+use cw_sdk::{hash::sha256, GasConfig, PubKeyType};
+use cw_state_machine::{
+    auth,
+    backend::{create_instance, WasmConfig},
+    error::Error as StateMachineError,
+};
+use cw_store::Store;
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use serde::Serialize;
+use tracing::info;
+
+use crate::{print::OutputArgs, DaemonError};
+
+/// Sizes of the values written and read, in bytes. The flat and per-byte costs
+/// of storage ops are fitted to the times measured at the two sizes.
+const SMALL_VALUE: usize = 32;
+const LARGE_VALUE: usize = 4096;
+
+/// Wasm gas per nanosecond that CosmWasm's gas costs are calibrated for: one
+/// teragas per millisecond. Used when no contract is given to benchmark.
+const NOMINAL_WASM_GAS_PER_NANO: f64 = 1_000_000.0;
+
+/// Sender of the messages the benchmarked contract is called with
+const SENDER: &str = "gas-bench";
+
+#[derive(Args)]
+pub struct GasBenchCmd {
+    /// Number of times each storage op and signature verification is timed
+    #[arg(long, default_value_t = 10_000)]
+    iterations: u32,
+
+    /// Wasm gas that one unit of SDK gas is worth. The default is that of the
+    /// `gas` params, which prices a unit of SDK gas at about 140ns.
+    #[arg(long, default_value_t = GasConfig::default().wasm_gas_per_sdk_gas)]
+    wasm_gas_per_sdk_gas: u64,
+
+    /// Representative contract to time wasm execution with. If not given, wasm
+    /// execution is assumed to take as long as CosmWasm's gas costs intend.
+    #[arg(long, requires = "execute_msg")]
+    wasm: Option<PathBuf>,
+
+    /// Message to instantiate the contract with
+    #[arg(long, default_value = "{}")]
+    instantiate_msg: String,
+
+    /// Message to execute the contract with, repeatedly
+    #[arg(long)]
+    execute_msg: Option<String>,
+
+    /// Number of times the contract is executed
+    #[arg(long, default_value_t = 100)]
+    wasm_iterations: u32,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// Gas costs of the ops measured, in SDK gas, such that a unit of SDK gas
+/// takes as long on this machine as the given amount of wasm gas.
+#[derive(Debug, Serialize)]
+pub struct GasTable {
+    /// The costs to set the `gas` params to, e.g. in a governance proposal.
+    /// The cost of event attributes isn't measured, and is left as default.
+    pub gas: GasConfig,
+
+    /// The cost of verifying a secp256k1 signature. Txs aren't charged for
+    /// their signatures, so this is only for reference.
+    pub secp256k1_verify: u64,
+}
+
+/// Time per op of each kind measured, in nanoseconds
+#[derive(Debug, Default)]
+struct Timings {
+    read_small: f64,
+    read_large: f64,
+    write_small: f64,
+    write_large: f64,
+    delete: f64,
+    secp256k1_verify: f64,
+}
+
+impl GasBenchCmd {
+    pub fn run(&self) -> Result<(), DaemonError> {
+        let wasm_gas_per_nano = match &self.wasm {
+            Some(wasm) => self.bench_wasm(wasm)?,
+            None => NOMINAL_WASM_GAS_PER_NANO,
+        };
+        let nanos_per_gas = self.wasm_gas_per_sdk_gas as f64 / wasm_gas_per_nano;
+        info!("One unit of SDK gas is worth {nanos_per_gas:.1}ns on this machine");
+
+        let mut timings = Timings::default();
+        self.bench_storage(&mut timings)?;
+        self.bench_signatures(&mut timings)?;
+        info!(?timings, "Measured the time per op in nanoseconds");
+
+        let (read_flat, read_per_byte) = fit(timings.read_small, timings.read_large);
+        let (write_flat, write_per_byte) = fit(timings.write_small, timings.write_large);
+        let to_gas = |nanos: f64| (nanos / nanos_per_gas).round() as u64;
+
+        self.output.print(GasTable {
+            gas: GasConfig {
+                wasm_gas_per_sdk_gas: self.wasm_gas_per_sdk_gas,
+                read_flat: to_gas(read_flat),
+                read_per_byte: to_gas(read_per_byte),
+                write_flat: to_gas(write_flat),
+                write_per_byte: to_gas(write_per_byte),
+                delete: to_gas(timings.delete),
+                ..Default::default()
+            },
+            secp256k1_verify: to_gas(timings.secp256k1_verify),
+        })
+    }
+
+    /// Execute the contract repeatedly, and return the wasm gas it consumes
+    /// per nanosecond. Instantiating the contract, and compiling it, aren't
+    /// timed. Neither is gas charged for storage ops, which are timed apart.
+    fn bench_wasm(&self, wasm: &Path) -> Result<f64, DaemonError> {
+        let code = fs::read(wasm)?;
+        let execute_msg = self.execute_msg.as_deref().unwrap_or_default().as_bytes();
+
+        let env = mock_env();
+        let info = mock_info(SENDER, &[]);
+        let wasm_cfg = WasmConfig::default();
+        let mut instance = create_instance(
+            &code,
+            MemoryStorage::new(),
+            &env.contract.address,
+            &env.block,
+            u64::MAX,
+            GasConfig::default(),
+            &wasm_cfg,
+            None,
+        )
+        .map_err(StateMachineError::from)?;
+
+        let result = call_instantiate::<_, _, _, Empty>(
+            &mut instance,
+            &env,
+            &info,
+            self.instantiate_msg.as_bytes(),
+        )
+        .map_err(StateMachineError::from)?;
+        if let ContractResult::Err(err) = result {
+            return Err(DaemonError::bench_contract_failed(err));
+        }
+
+        let gas_before = instance.create_gas_report().used_internally;
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..self.wasm_iterations {
+            let start = Instant::now();
+            let result = call_execute::<_, _, _, Empty>(&mut instance, &env, &info, execute_msg)
+                .map_err(StateMachineError::from)?;
+            elapsed += start.elapsed();
+
+            if let ContractResult::Err(err) = result {
+                return Err(DaemonError::bench_contract_failed(err));
+            }
+        }
+        let gas_used = instance.create_gas_report().used_internally - gas_before;
+
+        let wasm_gas_per_nano = gas_used as f64 / elapsed.as_nanos() as f64;
+        info!(gas_used, ?elapsed, "Executed the contract {} times", self.wasm_iterations);
+        Ok(wasm_gas_per_nano)
+    }
+
+    /// Time writes, reads and deletes of values of both sizes against a fresh
+    /// Merk store in a temporary directory. Writes and deletes include the time
+    /// it takes to commit them.
+    fn bench_storage(&self, timings: &mut Timings) -> Result<(), DaemonError> {
+        let dir = env::temp_dir().join(format!("cwd-gas-bench-{}", process::id()));
+        let store = Store::open(&dir)?;
+
+        for (size, read, write) in [
+            (SMALL_VALUE, &mut timings.read_small, &mut timings.write_small),
+            (LARGE_VALUE, &mut timings.read_large, &mut timings.write_large),
+        ] {
+            let keys = self.keys(size);
+            let value = vec![1; size];
+
+            *write = self.time_per_op(|| {
+                let mut pending = store.pending_wrap();
+                for key in &keys {
+                    pending.set(key, &value);
+                }
+                store.commit()
+            })?;
+
+            *read = self.time_per_op(|| {
+                let committed = store.wrap();
+                for key in &keys {
+                    committed.get(key);
+                }
+                Ok::<_, DaemonError>(())
+            })?;
+        }
+
+        let keys = self.keys(SMALL_VALUE);
+        timings.delete = self.time_per_op(|| {
+            let mut pending = store.pending_wrap();
+            for key in &keys {
+                pending.remove(key);
+            }
+            store.commit()
+        })?;
+
+        drop(store);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    /// Time the verification of secp256k1 signatures over tx-sized messages.
+    fn bench_signatures(&self, timings: &mut Timings) -> Result<(), DaemonError> {
+        let sk = SigningKey::from_bytes(&sha256(SENDER.as_bytes()))?;
+        let pubkey = sk.verifying_key().to_bytes();
+        let sign_bytes = vec![1; 256];
+        let signature: Signature = sk.sign(&sign_bytes);
+
+        timings.secp256k1_verify = self.time_per_op(|| {
+            for _ in 0..self.iterations {
+                auth::verify_signature(
+                    PubKeyType::Secp256k1,
+                    &pubkey,
+                    &sign_bytes,
+                    signature.as_ref(),
+                )?;
+            }
+            Ok::<_, StateMachineError>(())
+        })?;
+
+        Ok(())
+    }
+
+    /// Distinct, uniformly distributed keys, one per iteration, so that writes
+    /// insert new nodes all over the tree rather than update the same ones.
+    fn keys(&self, size: usize) -> Vec<Vec<u8>> {
+        (0..self.iterations)
+            .map(|i| sha256(&[&size.to_be_bytes()[..], &i.to_be_bytes()].concat()))
+            .collect()
+    }
+
+    /// Run a closure that performs one op per iteration, and return the time
+    /// each op took in nanoseconds.
+    fn time_per_op<E>(&self, f: impl FnOnce() -> Result<(), E>) -> Result<f64, DaemonError>
+    where
+        DaemonError: From<E>,
+    {
+        let start = Instant::now();
+        f()?;
+        Ok(start.elapsed().as_nanos() as f64 / self.iterations as f64)
+    }
+}
+
+/// Fit the flat and per-byte cost of an op to the times it took with small and
+/// large values. Neither can be negative, which measurement noise could make
+/// them otherwise.
+fn fit(small: f64, large: f64) -> (f64, f64) {
+    let per_byte = ((large - small) / (LARGE_VALUE - SMALL_VALUE) as f64).max(0.0);
+    let flat = (small - per_byte * SMALL_VALUE as f64).max(0.0);
+    (flat, per_byte)
+}
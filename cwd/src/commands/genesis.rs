@@ -1,14 +1,38 @@
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::{
+    env::temp_dir,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use clap::{Args, Subcommand};
+use cosmwasm_std::{Addr, Binary, Coin, Uint128};
 use serde::Serialize;
-use tendermint::genesis::Genesis as TmGenesis;
-use tracing::info;
+use serde_json::{json, Value};
+use tendermint::{genesis::Genesis as TmGenesis, validator};
+use tracing::{error, info};
 
-use cw_sdk::{address, hash::sha256, GenesisState, SdkMsg};
+use cw_sdk::{
+    address,
+    hash::{sha256, HASH_LENGTH},
+    staking, Account, GenesisAccount, GenesisState, PubKeyType, SdkMsg, SignMode, Tx, TxBody,
+    Validator,
+};
+use cw_state_machine::{auth, StateMachine, STAKING};
+use cw_store::Store;
 
-use crate::{path, print, DaemonError};
+use crate::{keyring::KeyringBackend, path, print, DaemonError, Keyring};
+
+/// Label of the bank contract, whose instantiate message sets the initial
+/// balances
+const BANK: &str = "bank";
+
+/// Directory under the Tendermint home directory where genesis txs are written,
+/// to be collected into the genesis file
+const GENTX_DIR: &str = "config/gentx";
+
+/// The first bytes of every wasm binary
+const WASM_MAGIC: &[u8] = b"\0asm";
 
 #[derive(Args)]
 pub struct GenesisCmd {
@@ -29,14 +53,27 @@ pub enum GenesisSubcommand {
         address: String,
     },
 
+    /// Add a base account to the genesis state
+    AddAccount {
+        /// Address of the account
+        address: String,
+
+        /// The account's secp256k1 pubkey in base64 encoding, from which the
+        /// address must be derived
+        #[arg(long)]
+        pubkey: String,
+    },
+
     /// Add a "store code" message to the genesis state
-    Store {
+    #[command(alias = "store")]
+    AddCode {
         /// Path to the wasm byte code
         wasm_byte_code_path: PathBuf,
     },
 
     /// Add an "instantiate contract" message to the genesis state
-    Instantiate {
+    #[command(alias = "instantiate")]
+    AddContract {
         /// Code id
         code_id: u64,
         /// Instantiate message in JSON format
@@ -67,6 +104,50 @@ pub enum GenesisSubcommand {
         funds: Option<String>,
     },
 
+    /// Set an account's initial balances in the bank contract's instantiate
+    /// message, replacing those set before
+    SetBalance {
+        /// Address of the account
+        address: String,
+        /// Coins separated by commas, e.g. `100uatom,50ucosm`. If empty, the
+        /// account's balances are removed.
+        coins: String,
+    },
+
+    /// Create a genesis tx that creates a validator for this node in the
+    /// staking contract, signed by the validator's operator. The operator must
+    /// be a genesis account.
+    Gentx {
+        /// Name of the operator's key in the keyring
+        key: String,
+
+        /// Coins to self-delegate, e.g. `1000000ustake`
+        #[arg(long)]
+        amount: String,
+
+        /// A human readable name for the validator
+        #[arg(long, default_value = "")]
+        moniker: String,
+
+        /// Where private keys are saved; overrides default value in client config
+        #[arg(long)]
+        keyring_backend: Option<KeyringBackend>,
+    },
+
+    /// Collect the genesis txs of all validators into the genesis state,
+    /// replacing those collected before. Their signatures are verified against
+    /// the genesis accounts.
+    CollectGentxs {
+        /// Directory of the genesis txs. Default to `config/gentx` under the
+        /// Tendermint home directory.
+        #[arg(long)]
+        gentx_dir: Option<PathBuf>,
+    },
+
+    /// Check the genesis state for mistakes that would fail the chain's launch,
+    /// then execute it against a temporary database to be sure
+    Validate,
+
     /// List all codes in the genesis state
     ListCodes,
 
@@ -75,7 +156,7 @@ pub enum GenesisSubcommand {
 }
 
 impl GenesisCmd {
-    pub fn run(self) -> Result<(), DaemonError> {
+    pub fn run(self, home_dir: &Path) -> Result<(), DaemonError> {
         let tm_home = match &self.tendermint_home {
             None => path::default_tm_home()?,
             Some(tm_home) => tm_home.clone(),
@@ -105,18 +186,45 @@ impl GenesisCmd {
                 update_and_write(&mut genesis, &app_state, &genesis_path)
             },
 
-            GenesisSubcommand::Store {
+            GenesisSubcommand::AddAccount {
+                address,
+                pubkey,
+            } => {
+                let addr = address::validate(&address)?;
+                let pubkey = Binary::from_base64(&pubkey)?;
+                if address::derive_from_pubkey(&pubkey)? != addr {
+                    return Err(DaemonError::invalid_genesis(format!(
+                        "address {addr} is not derived from the pubkey"
+                    )));
+                }
+
+                let account_number = add_account(&mut app_state, &addr, pubkey)?;
+                info!("Added account with account number {account_number}");
+                update_and_write(&mut genesis, &app_state, &genesis_path)
+            },
+
+            GenesisSubcommand::AddCode {
                 wasm_byte_code_path,
             } => {
-                // TODO: check whether the file exists
-                let wasm_byte_code = fs::read(wasm_byte_code_path)?;
+                if !wasm_byte_code_path.exists() {
+                    return Err(DaemonError::file_not_found(&wasm_byte_code_path)?);
+                }
+                let wasm_byte_code = fs::read(&wasm_byte_code_path)?;
+                if !wasm_byte_code.starts_with(WASM_MAGIC) {
+                    return Err(DaemonError::invalid_genesis(format!(
+                        "{} is not a wasm binary",
+                        path::stringify(&wasm_byte_code_path)?,
+                    )));
+                }
+
                 app_state.msgs.push(SdkMsg::StoreCode {
                     wasm_byte_code: wasm_byte_code.into(),
                 });
+                info!("Added code with code id {}", code_count(&app_state));
                 update_and_write(&mut genesis, &app_state, &genesis_path)
             },
 
-            GenesisSubcommand::Instantiate {
+            GenesisSubcommand::AddContract {
                 code_id,
                 msg,
                 funds,
@@ -126,6 +234,21 @@ impl GenesisCmd {
                 if funds.is_some() {
                     return Err(DaemonError::unsupported_feature("sending funds"));
                 }
+                if code_id == 0 || code_id > code_count(&app_state) {
+                    return Err(DaemonError::invalid_genesis(format!(
+                        "code id {code_id} doesn't exist"
+                    )));
+                }
+                let contract_addr = address::derive_from_label(&label)?;
+                if contract_exists(&app_state, &label, contract_addr.as_str()) {
+                    return Err(DaemonError::invalid_genesis(format!(
+                        "contract with label `{label}` already exists"
+                    )));
+                }
+                if let Some(admin) = &admin {
+                    address::validate(admin)?;
+                }
+
                 app_state.msgs.push(SdkMsg::Instantiate {
                     code_id,
                     msg: serde_json::from_str(&msg)?,
@@ -152,8 +275,139 @@ impl GenesisCmd {
                 update_and_write(&mut genesis, &app_state, &genesis_path)
             },
 
+            GenesisSubcommand::SetBalance {
+                address,
+                coins,
+            } => {
+                let addr = address::validate(&address)?;
+                set_balance(&mut app_state, &addr, parse_coins(&coins)?)?;
+                update_and_write(&mut genesis, &app_state, &genesis_path)
+            },
+
+            GenesisSubcommand::Gentx {
+                key,
+                amount,
+                moniker,
+                keyring_backend,
+            } => {
+                let key = Keyring::open(home_dir, keyring_backend)?.get(&key)?;
+                let operator_addr = key.address()?;
+                let account_number = app_state
+                    .accounts
+                    .iter()
+                    .find(|account| account.address == operator_addr.as_str())
+                    .and_then(|account| match &account.account {
+                        Account::Base {
+                            account_number,
+                            ..
+                        } => Some(*account_number),
+                        _ => None,
+                    })
+                    .ok_or_else(|| {
+                        DaemonError::invalid_genesis(format!(
+                            "operator {operator_addr} is not a base account in the genesis state"
+                        ))
+                    })?;
+
+                let msg = SdkMsg::Execute {
+                    contract: STAKING.into(),
+                    msg: serde_json::to_value(staking::ExecuteMsg::CreateValidator {
+                        pubkey: consensus_pubkey(&tm_home)?,
+                        moniker,
+                    })?,
+                    funds: parse_coins(&amount)?,
+                };
+                // the operator's first tx, sent after the genesis messages
+                let body = TxBody::single(
+                    &operator_addr,
+                    genesis.chain_id.as_str(),
+                    account_number,
+                    1,
+                    vec![msg],
+                );
+                let signature = key.sign_tx(&body, &body.signers[0])?;
+                let tx = Tx {
+                    body,
+                    signatures: vec![signature],
+                };
+
+                let gentx_dir = tm_home.join(GENTX_DIR);
+                fs::create_dir_all(&gentx_dir)?;
+                let gentx_path = gentx_dir.join(format!("gentx-{operator_addr}.json"));
+                fs::write(&gentx_path, serde_json::to_vec_pretty(&tx)?)?;
+                info!("Genesis tx written to {}", path::stringify(&gentx_path)?);
+                Ok(())
+            },
+
+            GenesisSubcommand::CollectGentxs {
+                gentx_dir,
+            } => {
+                let gentx_dir = gentx_dir.unwrap_or_else(|| tm_home.join(GENTX_DIR));
+                if !gentx_dir.exists() {
+                    return Err(DaemonError::file_not_found(&gentx_dir)?);
+                }
+
+                // collect in the order of file names, so that every validator
+                // arrives at the same genesis file
+                let mut gentx_paths = fs::read_dir(&gentx_dir)?
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                gentx_paths.retain(|path| path.extension().map_or(false, |ext| ext == "json"));
+                gentx_paths.sort();
+
+                let mut gen_txs = vec![];
+                for gentx_path in &gentx_paths {
+                    let tx: Tx = serde_json::from_slice(&fs::read(gentx_path)?)?;
+                    if let Err(err) = verify_gentx(&app_state, genesis.chain_id.as_str(), &tx) {
+                        error!("Invalid genesis tx {}", path::stringify(gentx_path)?);
+                        return Err(err);
+                    }
+                    gen_txs.push(tx);
+                }
+
+                info!("Collected {} genesis txs", gen_txs.len());
+                app_state.gen_txs = gen_txs;
+                update_and_write(&mut genesis, &app_state, &genesis_path)
+            },
+
+            GenesisSubcommand::Validate => {
+                // parse again, as a malformed app state is replaced by the
+                // default one above
+                let app_state: GenesisState = serde_json::from_value(genesis.app_state.clone())?;
+
+                let problems = find_problems(&app_state, genesis.chain_id.as_str());
+                if !problems.is_empty() {
+                    for problem in &problems {
+                        error!("{problem}");
+                    }
+                    return Err(DaemonError::invalid_genesis(format!(
+                        "found {} problems in the genesis state",
+                        problems.len(),
+                    )));
+                }
+
+                let validators = genesis
+                    .validators
+                    .iter()
+                    .map(tm_to_validator)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let (app_hash, validators) = dry_run_init_chain(
+                    genesis.chain_id.to_string(),
+                    genesis.initial_height as u64,
+                    validators,
+                    app_state,
+                )?;
+
+                info!(
+                    "Genesis state is valid, app hash {} with {} validators",
+                    hex::encode_upper(app_hash),
+                    validators.len(),
+                );
+                Ok(())
+            },
+
             GenesisSubcommand::ListCodes => {
-                let mut code_count = 0;
+                let mut code_count = imported_code_count(&app_state);
                 let mut codes = vec![];
                 for msg in &app_state.msgs {
                     if let SdkMsg::StoreCode {
@@ -195,6 +449,349 @@ impl GenesisCmd {
     }
 }
 
+/// Add a base account with a secp256k1 pubkey to the genesis state, returning
+/// its account number.
+pub(crate) fn add_account(
+    app_state: &mut GenesisState,
+    addr: &Addr,
+    pubkey: Binary,
+) -> Result<u64, DaemonError> {
+    if app_state.accounts.iter().any(|account| account.address == addr.as_str()) {
+        return Err(DaemonError::invalid_genesis(format!("account {addr} already exists")));
+    }
+
+    let account_number = next_account_number(app_state);
+    app_state.accounts.push(GenesisAccount {
+        address: addr.to_string(),
+        account: Account::Base {
+            pubkey,
+            pubkey_type: PubKeyType::Secp256k1,
+            account_number,
+            sequence: 0,
+        },
+    });
+    Ok(account_number)
+}
+
+/// Set an account's initial balances in the bank contract's instantiate
+/// message, replacing those set before. Fails if the bank contract hasn't been
+/// added.
+pub(crate) fn set_balance(
+    app_state: &mut GenesisState,
+    addr: &Addr,
+    coins: Vec<Coin>,
+) -> Result<(), DaemonError> {
+    let balances = app_state
+        .msgs
+        .iter_mut()
+        .find_map(|msg| match msg {
+            SdkMsg::Instantiate {
+                label,
+                msg,
+                ..
+            } if label == BANK => msg.get_mut("balances"),
+            _ => None,
+        })
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| DaemonError::invalid_genesis("the bank contract must be added first"))?;
+
+    balances.retain(|balance| balance["address"] != addr.as_str());
+    if !coins.is_empty() {
+        balances.push(json!({
+            "address": addr,
+            "coins": coins,
+        }));
+    }
+    Ok(())
+}
+
+/// The account number to be assigned to the next genesis account, following
+/// those of the existing ones, the same way they are imported
+fn next_account_number(app_state: &GenesisState) -> u64 {
+    app_state
+        .accounts
+        .iter()
+        .filter_map(|genesis_account| match &genesis_account.account {
+            Account::Base {
+                account_number,
+                ..
+            }
+            | Account::Multisig {
+                account_number,
+                ..
+            } => Some(account_number + 1),
+            Account::Contract {
+                ..
+            } => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// The largest id of the codes imported from an existing chain. Codes stored by
+/// genesis messages are assigned the ids following it.
+fn imported_code_count(app_state: &GenesisState) -> u64 {
+    app_state.codes.iter().map(|code| code.code_id).max().unwrap_or(0)
+}
+
+/// The number of codes once genesis messages are executed, i.e. the largest
+/// code id
+fn code_count(app_state: &GenesisState) -> u64 {
+    let stored = app_state
+        .msgs
+        .iter()
+        .filter(|msg| matches!(msg, SdkMsg::StoreCode { .. }))
+        .count() as u64;
+    imported_code_count(app_state) + stored
+}
+
+/// Whether a contract of the given label is imported, or instantiated by a
+/// genesis message
+fn contract_exists(app_state: &GenesisState, label: &str, contract_addr: &str) -> bool {
+    let instantiated = app_state.msgs.iter().any(|msg| {
+        matches!(msg, SdkMsg::Instantiate { label: existing, .. } if existing == label)
+    });
+    let imported = app_state.accounts.iter().any(|account| account.address == contract_addr);
+    instantiated || imported
+}
+
+/// The ed25519 consensus pubkey of this node's validator, from the Tendermint
+/// home directory
+fn consensus_pubkey(tm_home: &Path) -> Result<Binary, DaemonError> {
+    let key_path = tm_home.join("config/priv_validator_key.json");
+    if !key_path.exists() {
+        return Err(DaemonError::file_not_found(&key_path)?);
+    }
+    let key: Value = serde_json::from_slice(&fs::read(&key_path)?)?;
+    let pubkey = key["pub_key"]["value"]
+        .as_str()
+        .ok_or_else(|| DaemonError::malformed_payload("validator key has no pubkey"))?;
+    Binary::from_base64(pubkey).map_err(DaemonError::from)
+}
+
+/// Check that a genesis tx is signed by a single genesis account for the given
+/// chain, as it will be executed in InitChain, so that a bad one is caught
+/// before the chain fails to launch.
+fn verify_gentx(app_state: &GenesisState, chain_id: &str, tx: &Tx) -> Result<(), DaemonError> {
+    let ([signer], [signature]) = (tx.body.signers.as_slice(), tx.signatures.as_slice()) else {
+        return Err(DaemonError::invalid_genesis("expecting exactly one signer"));
+    };
+    if tx.body.chain_id != chain_id {
+        return Err(DaemonError::invalid_genesis(format!(
+            "expecting chain id {chain_id}, found {}",
+            tx.body.chain_id,
+        )));
+    }
+
+    let Some((pubkey, pubkey_type)) = app_state
+        .accounts
+        .iter()
+        .filter(|account| account.address == signer.address)
+        .find_map(|account| match &account.account {
+            Account::Base {
+                pubkey,
+                pubkey_type,
+                account_number,
+                ..
+            } if *account_number == signer.account_number => Some((pubkey, *pubkey_type)),
+            _ => None,
+        })
+    else {
+        return Err(DaemonError::invalid_genesis(format!(
+            "signer {} is not a base account of account number {} in the genesis state",
+            signer.address, signer.account_number,
+        )));
+    };
+
+    let sign_bytes = signature.sign_bytes(
+        &SignMode::Json,
+        &tx.body,
+        chain_id,
+        signer.account_number,
+        signer.sequence,
+    )?;
+    auth::verify_signature(pubkey_type, pubkey, &sign_bytes, &signature.signature)?;
+
+    Ok(())
+}
+
+/// Check the genesis state for problems that can be found without executing
+/// it, and describe each of them
+fn find_problems(app_state: &GenesisState, chain_id: &str) -> Vec<String> {
+    let mut problems = vec![];
+
+    if !app_state.msgs.is_empty() || !app_state.deployer.is_empty() {
+        if let Err(err) = address::validate(&app_state.deployer) {
+            problems.push(format!("invalid deployer address `{}`: {err}", app_state.deployer));
+        }
+    }
+
+    let mut addresses = vec![];
+    let mut account_numbers = vec![];
+    let mut labels = vec![];
+    for genesis_account in &app_state.accounts {
+        let addr = &genesis_account.address;
+        if let Err(err) = address::validate(addr) {
+            problems.push(format!("invalid account address `{addr}`: {err}"));
+        }
+        if addresses.contains(&addr) {
+            problems.push(format!("account {addr} appears more than once"));
+        }
+        addresses.push(addr);
+
+        match &genesis_account.account {
+            Account::Base {
+                pubkey,
+                pubkey_type,
+                account_number,
+                ..
+            } => {
+                let derived = match pubkey_type {
+                    PubKeyType::Secp256k1 => address::derive_from_pubkey(pubkey),
+                    PubKeyType::EthSecp256k1 => address::derive_from_eth_pubkey(pubkey),
+                };
+                if !matches!(derived, Ok(derived) if derived == addr.as_str()) {
+                    problems.push(format!("account {addr} is not derived from its pubkey"));
+                }
+                account_numbers.push((addr, *account_number));
+            },
+            Account::Multisig {
+                account_number,
+                ..
+            } => account_numbers.push((addr, *account_number)),
+            Account::Contract {
+                label,
+                ..
+            } => labels.push(label.as_str()),
+        }
+    }
+    for (i, (addr, account_number)) in account_numbers.iter().enumerate() {
+        if account_numbers[..i].iter().any(|(_, earlier)| earlier == account_number) {
+            problems.push(format!("account {addr} reuses account number {account_number}"));
+        }
+    }
+
+    let mut code_ids = vec![];
+    for code in &app_state.codes {
+        if code_ids.contains(&code.code_id) {
+            problems.push(format!("code id {} appears more than once", code.code_id));
+        }
+        code_ids.push(code.code_id);
+        if !code.wasm_byte_code.starts_with(WASM_MAGIC) {
+            problems.push(format!("code {} is not a wasm binary", code.code_id));
+        }
+    }
+
+    let illegal_prefix = format!("{}1", address::ADDRESS_PREFIX);
+    let mut code_count = imported_code_count(app_state);
+    for (index, msg) in app_state.msgs.iter().enumerate() {
+        match msg {
+            SdkMsg::StoreCode {
+                wasm_byte_code,
+            } => {
+                code_count += 1;
+                if !wasm_byte_code.starts_with(WASM_MAGIC) {
+                    problems
+                        .push(format!("message {index}: code {code_count} is not a wasm binary"));
+                }
+            },
+            SdkMsg::Instantiate {
+                code_id,
+                label,
+                admin,
+                ..
+            } => {
+                if *code_id == 0 || *code_id > code_count {
+                    problems.push(format!(
+                        "message {index}: code id {code_id} is not stored by an earlier message"
+                    ));
+                }
+                if label.starts_with(&illegal_prefix) {
+                    problems.push(format!(
+                        "message {index}: label `{label}` must not start with `{illegal_prefix}`"
+                    ));
+                }
+                if labels.contains(&label.as_str()) {
+                    problems.push(format!("message {index}: label `{label}` is already taken"));
+                }
+                labels.push(label.as_str());
+                if let Some(Err(err)) = admin.as_deref().map(address::validate) {
+                    problems.push(format!("message {index}: invalid admin address: {err}"));
+                }
+            },
+            _ => (),
+        }
+    }
+
+    for (index, tx) in app_state.gen_txs.iter().enumerate() {
+        if let Err(err) = verify_gentx(app_state, chain_id, tx) {
+            problems.push(format!("genesis tx {index}: {err}"));
+        }
+    }
+
+    problems
+}
+
+/// Cast a validator in the Tendermint genesis file into a cw-sdk validator
+fn tm_to_validator(info: &validator::Info) -> Result<Validator, DaemonError> {
+    let pubkey = info.pub_key.ed25519().ok_or_else(|| {
+        DaemonError::invalid_genesis(format!(
+            "validator {} doesn't have an ed25519 pubkey",
+            info.address,
+        ))
+    })?;
+    Ok(Validator {
+        pubkey: pubkey.as_bytes().to_vec().into(),
+        power: info.power.value(),
+    })
+}
+
+/// Execute InitChain with the genesis state against a temporary database,
+/// which is deleted afterwards. Return the app hash and the validator set.
+fn dry_run_init_chain(
+    chain_id: String,
+    initial_height: u64,
+    validators: Vec<Validator>,
+    app_state: GenesisState,
+) -> Result<([u8; HASH_LENGTH], Vec<Validator>), DaemonError> {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let db_path = temp_dir().join(format!("cwd-genesis-validate-{time}"));
+
+    // the database is closed once the state machine is dropped, before it's
+    // deleted
+    let result = StateMachine::new(Store::open(&db_path)?).init_chain(
+        chain_id,
+        initial_height,
+        validators,
+        app_state,
+    );
+    fs::remove_dir_all(&db_path).ok();
+
+    result.map_err(|err| DaemonError::invalid_genesis(format!("InitChain failed: {err}")))
+}
+
+/// Parse coins separated by commas, e.g. `100uatom,50ucosm`. Each denom may
+/// appear only once, and amounts must be non-zero.
+pub(crate) fn parse_coins(coins_str: &str) -> Result<Vec<Coin>, DaemonError> {
+    let mut coins: Vec<Coin> = vec![];
+    for coin_str in coins_str.split(',').filter(|s| !s.is_empty()) {
+        let split_at = coin_str.find(|c: char| !c.is_ascii_digit()).unwrap_or(coin_str.len());
+        let (amount, denom) = coin_str.split_at(split_at);
+        let amount = match amount.parse::<u128>() {
+            Ok(amount) if amount > 0 && !denom.is_empty() => amount,
+            _ => return Err(DaemonError::invalid_coins(coins_str)),
+        };
+        if coins.iter().any(|coin| coin.denom == denom) {
+            return Err(DaemonError::invalid_coins(coins_str));
+        }
+        coins.push(Coin {
+            denom: denom.into(),
+            amount: Uint128::new(amount),
+        });
+    }
+    Ok(coins)
+}
+
 /// Update the genesis state and write to file
 fn update_and_write(
     genesis: &mut TmGenesis,
@@ -1,13 +1,23 @@
 use std::fs;
 use std::path::Path;
 
+use chrono::{SecondsFormat, Utc};
 use clap::Args;
+use cw_sdk::GenesisState;
+use serde_json::json;
 use tracing::info;
 
 use crate::{path, AppConfig, ClientConfig, DaemonError};
 
 #[derive(Args)]
-pub struct InitCmd;
+pub struct InitCmd {
+    /// A human-readable name of the node
+    moniker: String,
+
+    /// Chain id of the genesis file
+    #[arg(long, default_value = "dev-1")]
+    chain_id: String,
+}
 
 impl InitCmd {
     pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
@@ -15,19 +25,65 @@ impl InitCmd {
             return Err(DaemonError::file_exists(home_dir)?);
         }
 
+        let app_cfg = AppConfig {
+            moniker: self.moniker.clone(),
+            ..Default::default()
+        };
+
         fs::create_dir_all(home_dir.join("config"))?;
-        fs::create_dir_all(home_dir.join("data"))?;
+        fs::create_dir_all(app_cfg.db_path(home_dir))?;
         fs::create_dir_all(home_dir.join("keys"))?;
-
-        let app_cfg = AppConfig::default();
-        let app_cfg_str = toml::to_string_pretty(&app_cfg)?;
+        // serialized through a toml value, which emits the tables, e.g. `[wasm]`,
+        // after the plain values regardless of the order of the fields
+        let app_cfg_str = toml::to_string_pretty(&toml::Value::try_from(&app_cfg)?)?;
         fs::write(home_dir.join("config/app.toml"), app_cfg_str)?;
 
-        let client_cfg = ClientConfig::default();
+        let client_cfg = ClientConfig {
+            chain_id: self.chain_id.clone(),
+            ..Default::default()
+        };
         let client_cfg_str = toml::to_string_pretty(&client_cfg)?;
         fs::write(home_dir.join("config/client.toml"), client_cfg_str)?;
 
+        let genesis_path = home_dir.join("config/genesis.json");
+        fs::write(&genesis_path, serde_json::to_vec_pretty(&self.genesis()?)?)?;
+
         info!("initialized home directory at {}", path::stringify(home_dir)?);
+        info!(
+            "genesis written to {}; copy it to the Tendermint home directory before starting",
+            path::stringify(&genesis_path)?,
+        );
         Ok(())
     }
+
+    /// A Tendermint genesis with no validators and an empty app state, and
+    /// Tendermint's default consensus params. Validators, the deployer and
+    /// genesis messages are to be added before the chain starts.
+    fn genesis(&self) -> Result<serde_json::Value, DaemonError> {
+        Ok(json!({
+            "genesis_time": Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true),
+            "chain_id": self.chain_id,
+            "initial_height": "1",
+            "consensus_params": {
+                "block": {
+                    "max_bytes": "22020096",
+                    "max_gas": "-1",
+                },
+                "evidence": {
+                    "max_age_num_blocks": "100000",
+                    "max_age_duration": "172800000000000",
+                    "max_bytes": "1048576",
+                },
+                "validator": {
+                    "pub_key_types": ["ed25519"],
+                },
+                "version": {
+                    "app": "0",
+                },
+            },
+            "validators": [],
+            "app_hash": "",
+            "app_state": serde_json::to_value(GenesisState::default())?,
+        }))
+    }
 }
@@ -1,20 +1,27 @@
-use std::path::Path;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use bip32::{Language, Mnemonic};
+use bip32::{DerivationPath, Language, Mnemonic};
 use clap::{Args, Subcommand};
 use rand_core::OsRng;
 
-use crate::{print, prompt, DaemonError, Key, Keyring};
+use crate::{keyring::KeyringBackend, print, prompt, DaemonError, Key, Keyring};
 
 #[derive(Args)]
 pub struct KeysCmd {
     #[command(subcommand)]
     pub subcommand: KeysSubcmd,
+
+    /// Where private keys are saved; overrides default value in client config
+    #[arg(long, global = true)]
+    keyring_backend: Option<KeyringBackend>,
 }
 
 #[derive(Subcommand)]
 pub enum KeysSubcmd {
-    /// Add or recover a private key and save it to an encrypted file
+    /// Add or recover a private key and save it in the keyring
     Add {
         /// A human-readable name of the key
         name: String,
@@ -23,9 +30,27 @@ pub enum KeysSubcmd {
         #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
         recover: bool,
 
-        /// BIP-44 coin type for HD derivation
-        #[arg(long, default_value_t = 118)]
-        coin_type: u32,
+        /// Use the key derived at the HD path on a connected Ledger device,
+        /// instead of saving a private key
+        #[arg(
+            long,
+            default_value_t = false,
+            action = clap::ArgAction::SetTrue,
+            conflicts_with = "recover"
+        )]
+        ledger: bool,
+
+        #[command(flatten)]
+        hd_path: HdPathArgs,
+    },
+
+    /// Recover a private key from a BIP-39 mnemonic and save it in the keyring
+    Recover {
+        /// A human-readable name of the key
+        name: String,
+
+        #[command(flatten)]
+        hd_path: HdPathArgs,
     },
 
     /// Delete a given key
@@ -39,50 +64,112 @@ pub enum KeysSubcmd {
     Show {
         /// Name of the key to show
         name: String,
+
+        /// Only display the key's address, in bech32
+        #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+        address: bool,
     },
 
     /// List all keys
     #[command(alias = "ls")]
     List,
+
+    /// Export a private key, encrypted with a passphrase, so that it can be
+    /// imported into another keyring
+    Export {
+        /// Name of the key to export
+        name: String,
+
+        /// Export the private key unencrypted, in hex encoding. Anyone who sees
+        /// it can take control of the account!
+        #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+        unsafe_hex: bool,
+    },
+
+    /// Import a private key exported from another keyring
+    Import {
+        /// A human-readable name of the key
+        name: String,
+
+        /// Path to the file containing the exported key
+        file: PathBuf,
+
+        /// The file contains the private key unencrypted, in hex encoding
+        #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+        unsafe_hex: bool,
+    },
+}
+
+/// The BIP-32 path at which a key is derived from a mnemonic, by default
+/// `m/44'/{coin_type}'/{account}'/0/{index}` as in BIP-44.
+#[derive(Args)]
+pub struct HdPathArgs {
+    /// BIP-44 coin type for HD derivation
+    #[arg(long, default_value_t = 118)]
+    coin_type: u32,
+
+    /// BIP-44 account number for HD derivation
+    #[arg(long, default_value_t = 0)]
+    account: u32,
+
+    /// BIP-44 address index for HD derivation
+    #[arg(long, default_value_t = 0)]
+    index: u32,
+
+    /// Full BIP-32 derivation path, e.g. `m/44'/118'/0'/0/0`; overrides the
+    /// coin type, account and index
+    #[arg(long)]
+    hd_path: Option<String>,
+}
+
+impl HdPathArgs {
+    fn path(&self) -> Result<DerivationPath, DaemonError> {
+        let path = match &self.hd_path {
+            Some(path) => path.clone(),
+            None => format!("m/44'/{}'/{}'/0/{}", self.coin_type, self.account, self.index),
+        };
+        path.parse().map_err(DaemonError::from)
+    }
 }
 
 impl KeysCmd {
     pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
-        let keyring = Keyring::new(home_dir.join("keys"))?;
+        let keyring = Keyring::open(home_dir, self.keyring_backend)?;
 
         match &self.subcommand {
             KeysSubcmd::Add {
                 name,
-                recover,
-                coin_type,
+                ledger: true,
+                hd_path,
+                ..
             } => {
-                let mnemonic = if *recover {
-                    let phrase: String = prompt::input("enter your BIP-39 mnemonic")?;
-                    println!("\n");
-                    Mnemonic::new(phrase, Language::English)?
-                } else {
-                    Mnemonic::random(OsRng, Language::English)
-                };
-
-                let key = Key::from_mnemonic(name, &mnemonic, *coin_type)?;
+                let key = Key::from_ledger(name, &hd_path.path()?)?;
                 keyring.set(&key)?;
-
-                println!();
                 print::key(&key)?;
-
-                if !recover {
-                    println!("\n**Important** write this mnemonic phrase in a safe place!");
-                    println!("It is the only way to recover your account if you ever forget your password.");
-                    println!();
-                    print::mnemonic(mnemonic.phrase());
-                }
             },
 
+            KeysSubcmd::Add {
+                name,
+                recover,
+                hd_path,
+                ..
+            } => add_key(&keyring, name, *recover, hd_path)?,
+
+            KeysSubcmd::Recover {
+                name,
+                hd_path,
+            } => add_key(&keyring, name, true, hd_path)?,
+
             KeysSubcmd::Show {
                 name,
+                address,
             } => {
                 let key = keyring.get(name)?;
-                print::key(&key)?;
+                if *address {
+                    println!("{}", key.address()?);
+                } else {
+                    print::key(&key)?;
+                }
             },
 
             KeysSubcmd::List => {
@@ -93,8 +180,69 @@ impl KeysCmd {
             KeysSubcmd::Delete {
                 name,
             } => keyring.delete(name)?,
+
+            KeysSubcmd::Export {
+                name,
+                unsafe_hex,
+            } => {
+                if *unsafe_hex {
+                    let key = keyring.get(name)?;
+                    println!("{}", hex::encode(key.privkey()?.to_bytes()));
+                } else {
+                    println!("{}", keyring.export(name)?);
+                }
+            },
+
+            KeysSubcmd::Import {
+                name,
+                file,
+                unsafe_hex,
+            } => {
+                let contents = fs::read_to_string(file)?;
+                let key = if *unsafe_hex {
+                    let key = Key::from_privkey_bytes(name, &hex::decode(contents.trim())?)?;
+                    keyring.set(&key)?;
+                    key
+                } else {
+                    keyring.import(name, contents.trim().as_bytes())?
+                };
+
+                print::key(&key)?;
+            },
         }
 
         Ok(())
     }
 }
+
+/// Create a key from a random mnemonic, or one provided by the user, and save
+/// it in the keyring.
+fn add_key(
+    keyring: &Keyring,
+    name: &str,
+    recover: bool,
+    hd_path: &HdPathArgs,
+) -> Result<(), DaemonError> {
+    let mnemonic = if recover {
+        let phrase: String = prompt::input("enter your BIP-39 mnemonic")?;
+        println!("\n");
+        Mnemonic::new(phrase, Language::English)?
+    } else {
+        Mnemonic::random(OsRng, Language::English)
+    };
+
+    let key = Key::from_mnemonic(name, &mnemonic, &hd_path.path()?)?;
+    keyring.set(&key)?;
+
+    println!();
+    print::key(&key)?;
+
+    if !recover {
+        println!("\n**Important** write this mnemonic phrase in a safe place!");
+        println!("It is the only way to recover your account if you ever forget your password.");
+        println!();
+        print::mnemonic(mnemonic.phrase());
+    }
+
+    Ok(())
+}
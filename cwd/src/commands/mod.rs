@@ -1,14 +1,22 @@
+mod contract_state;
 mod debug;
+mod export;
+mod fork;
+mod gas_bench;
 mod genesis;
 mod init;
 mod keys;
+mod prune;
 mod query;
 mod reset;
+mod rollback;
 mod start;
 mod tendermint;
 mod tx;
 
 pub use self::{
-    debug::DebugCmd, genesis::GenesisCmd, init::InitCmd, keys::KeysCmd, query::QueryCmd,
-    reset::ResetCmd, start::StartCmd, tendermint::TendermintCmd, tx::TxCmd,
+    contract_state::ContractStateCmd, debug::DebugCmd, export::ExportCmd, fork::ForkCmd,
+    gas_bench::GasBenchCmd, genesis::GenesisCmd, init::InitCmd, keys::KeysCmd, prune::PruneCmd,
+    query::QueryCmd, reset::ResetCmd, rollback::RollbackCmd, start::StartCmd,
+    tendermint::TendermintCmd, tx::TxCmd,
 };
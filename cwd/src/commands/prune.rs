@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use clap::Args;
+use tracing::info;
+
+use cw_store::Store;
+
+use crate::{path, AppConfig, DaemonError};
+
+/// Only the latest state is retained in the Merk tree. Besides it, the store
+/// keeps a checkpoint of the state before the last commit, for the `rollback`
+/// command. Pruning deletes the checkpoint, then compacts the database to reclaim the disk space
+/// taken by overwritten and deleted values. The node must not be running.
+#[derive(Args)]
+pub struct PruneCmd {
+    /// Keep the undo checkpoint, so that the last committed block can still be rolled
+    /// back. The database is compacted either way.
+    #[arg(long)]
+    keep_undo: bool,
+}
+
+impl PruneCmd {
+    pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
+        let data_dir = AppConfig::load(home_dir)?.db_path(home_dir);
+        let size_before = path::dir_size(&data_dir)?;
+
+        if !self.keep_undo {
+            let store = Store::open(&data_dir)?;
+            if store.prune()? {
+                info!("Deleted undo checkpoint of the last commit");
+            }
+            // the database must be closed before it can be compacted
+        }
+
+        cw_store::compact(&data_dir)?;
+
+        let size_after = path::dir_size(&data_dir)?;
+        info!(
+            "Compacted application database at {}: {size_before} bytes -> {size_after} bytes",
+            path::stringify(&data_dir)?,
+        );
+
+        Ok(())
+    }
+}
@@ -1,23 +1,28 @@
 use std::{
-    fs,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
     path::{Path, PathBuf},
     time::{Duration, UNIX_EPOCH},
 };
 
 use chrono::{DateTime, SecondsFormat, Utc};
 use clap::{Args, Subcommand};
-use cosmwasm_std::{BlockInfo, ContractResult};
+use cosmwasm_std::{from_binary, Binary, BlockInfo, Coin, ContractResult};
 use cw_sdk::{
-    hash::sha256, AccountResponse, CodeResponse, ContractResponse, InfoResponse, SdkQuery,
+    AccountResponse, AllowanceResponse, AuthzGrant, CodeResponse, ContractGasUsage,
+    ContractResponse, InfoResponse, Params, SdkQuery, TxResponse, ValidatorsResponse,
     WasmRawResponse, WasmSmartResponse,
 };
+use cw_state_machine::StateMachine;
+use cw_store::Store;
 use serde::Serialize;
 use serde_json::Value;
+use tendermint_rpc::HttpClient;
 use tracing::{error, info};
 
 use crate::{
     client::{create_http_client, do_abci_query},
-    path, print, ClientConfig, DaemonError,
+    path, print::OutputArgs, ClientConfig, DaemonError,
 };
 
 #[derive(Args)]
@@ -28,6 +33,9 @@ pub struct QueryCmd {
     /// Tendermint RPC endpoint; overrides default value in client config
     #[arg(long)]
     node: Option<String>,
+
+    #[command(flatten)]
+    output: OutputArgs,
 }
 
 #[derive(Subcommand)]
@@ -69,6 +77,20 @@ pub enum QuerySubcmd {
         limit: Option<u32>,
     },
 
+    /// Enumerate the contracts instantiated from a wasm byte code, by address
+    ContractsByCode {
+        /// Code id
+        code_id: u64,
+
+        /// Start after this contract address
+        #[arg(long)]
+        start_after: Option<String>,
+
+        /// The maximum number of results to be returned in this query
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+
     /// Retrieve the metadata and wasm byte code corresponding to the given code id
     Code {
         /// Code id
@@ -76,7 +98,7 @@ pub enum QuerySubcmd {
 
         /// If given, then save the wasm byte code to this path
         #[arg(long)]
-        output: Option<PathBuf>,
+        out: Option<PathBuf>,
     },
 
     /// Enumerate all wasm byte codes
@@ -90,6 +112,51 @@ pub enum QuerySubcmd {
         limit: Option<u32>,
     },
 
+    /// Query the validator set at a given height
+    Validators {
+        /// Block height (default: last committed height)
+        #[arg(long)]
+        height: Option<u64>,
+    },
+
+    /// Query the fee allowance granted by an account to another
+    Allowance {
+        /// Granter address
+        granter: String,
+        /// Grantee address
+        grantee: String,
+    },
+
+    /// Enumerate the fee allowances granted by an account
+    Allowances {
+        /// Granter address
+        granter: String,
+
+        /// Start after this grantee address
+        #[arg(long)]
+        start_after: Option<String>,
+
+        /// The maximum number of results to be returned in this query
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+
+    /// Query the authorizations granted by an account to another
+    AuthzGrants {
+        /// Granter address
+        granter: String,
+        /// Grantee address
+        grantee: String,
+
+        /// Start after this authorization key, e.g. `store_code`
+        #[arg(long)]
+        start_after: Option<String>,
+
+        /// The maximum number of results to be returned in this query
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+
     /// Perform a wasm raw query
     WasmRaw {
         /// Contract address
@@ -98,6 +165,58 @@ pub enum QuerySubcmd {
         key: String,
     },
 
+    /// Dump the raw key-value pairs in a contract's store as JSON lines
+    WasmDump {
+        /// Contract address
+        contract: String,
+
+        /// Only dump keys that start with this prefix, in hex encoding
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Attempt to decode keys as cw-storage-plus map keys, and values as
+        /// JSON
+        #[arg(long)]
+        decode: bool,
+
+        /// Read from the database in this directory instead of querying a
+        /// node. The node that owns the database must not be running.
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+
+    /// Query the balance of a single coin for a single account
+    Balance {
+        /// Account address
+        address: String,
+        /// Coin denomination
+        denom: String,
+    },
+
+    /// Enumerate the balances of all coins for a single account
+    Balances {
+        /// Account address
+        address: String,
+
+        /// Start after this denomination
+        #[arg(long)]
+        start_after: Option<String>,
+
+        /// The maximum number of results to be returned in this query
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+
+    /// Query the total supply of a single coin
+    Supply {
+        /// Coin denomination
+        denom: String,
+    },
+
     /// Perform a wasm smart query
     WasmSmart {
         /// Contract address
@@ -105,6 +224,38 @@ pub enum QuerySubcmd {
         /// Query message in JSON format
         msg: String,
     },
+
+    /// Query the result of a tx included in a block, from the node's tx index
+    Tx {
+        /// Hash of the tx, in hex encoding
+        hash: String,
+    },
+
+    /// Enumerate the txs that emitted an event with the given attribute, from
+    /// the node's tx index
+    TxsByEvent {
+        /// Event type and attribute key, e.g. `wasm._contract_address`
+        event: String,
+
+        /// Attribute value
+        value: String,
+
+        /// Start after the tx with this hash, in hex encoding
+        #[arg(long)]
+        start_after: Option<String>,
+
+        /// The maximum number of results to be returned in this query
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+
+    /// Query the contracts that have consumed the most gas since the node
+    /// started
+    ContractGasUsage {
+        /// The maximum number of results to be returned in this query
+        #[arg(long)]
+        limit: Option<u32>,
+    },
 }
 
 impl QueryCmd {
@@ -116,7 +267,7 @@ impl QueryCmd {
             QuerySubcmd::Info => {
                 let response: InfoResponse = do_abci_query(&client, SdkQuery::Info {}).await?;
 
-                print::json(PrettyInfoResponse::from(response))?;
+                self.output.print(PrettyInfoResponse::from(response))?;
             },
 
             QuerySubcmd::Account {
@@ -130,7 +281,7 @@ impl QueryCmd {
                 )
                 .await?;
 
-                print::json(response)?;
+                self.output.print(response)?;
             },
 
             QuerySubcmd::Accounts {
@@ -146,7 +297,7 @@ impl QueryCmd {
                 )
                 .await?;
 
-                print::json(response)?;
+                self.output.print(response)?;
             },
 
             QuerySubcmd::Contract {
@@ -160,7 +311,7 @@ impl QueryCmd {
                 )
                 .await?;
 
-                print::json(response)?;
+                self.output.print(response)?;
             },
 
             QuerySubcmd::Contracts {
@@ -176,28 +327,48 @@ impl QueryCmd {
                 )
                 .await?;
 
-                print::json(response)?;
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::ContractsByCode {
+                code_id,
+                start_after,
+                limit,
+            } => {
+                let response: Vec<ContractResponse> = do_abci_query(
+                    &client,
+                    SdkQuery::ContractsByCode {
+                        code_id,
+                        start_after,
+                        limit,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
             },
 
             QuerySubcmd::Code {
                 code_id,
-                output,
+                out,
             } => {
-                let response: CodeResponse = do_abci_query(
+                let mut response: CodeResponse = do_abci_query(
                     &client,
                     SdkQuery::Code {
                         code_id,
+                        with_byte_code: out.is_some(),
                     },
                 )
                 .await?;
 
                 // only print the hash, not the bytecode
-                print::json(HashedCodeResponse::from(&response))?;
+                let wasm_byte_code = response.wasm_byte_code.take();
+                self.output.print(response)?;
 
                 // save the wasm byte code to file if an output path is specified
-                if let Some(output) = &output {
-                    fs::write(output, response.wasm_byte_code.as_slice())?;
-                    info!("Wasm byte code written to {}", path::stringify(output)?);
+                if let (Some(out), Some(wasm_byte_code)) = (&out, wasm_byte_code) {
+                    fs::write(out, wasm_byte_code.as_slice())?;
+                    info!("Wasm byte code written to {}", path::stringify(out)?);
                 }
             },
 
@@ -205,19 +376,84 @@ impl QueryCmd {
                 start_after,
                 limit,
             } => {
-                let response = do_abci_query::<_, Vec<CodeResponse>>(
+                let response: Vec<CodeResponse> = do_abci_query(
                     &client,
                     SdkQuery::Codes {
                         start_after,
                         limit,
                     },
                 )
-                .await?
-                .iter()
-                .map(HashedCodeResponse::from)
-                .collect::<Vec<_>>();
+                .await?;
 
-                print::json(response)?;
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::Allowance {
+                granter,
+                grantee,
+            } => {
+                let response: AllowanceResponse = do_abci_query(
+                    &client,
+                    SdkQuery::Allowance {
+                        granter,
+                        grantee,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::Allowances {
+                granter,
+                start_after,
+                limit,
+            } => {
+                let response: Vec<AllowanceResponse> = do_abci_query(
+                    &client,
+                    SdkQuery::Allowances {
+                        granter,
+                        start_after,
+                        limit,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::AuthzGrants {
+                granter,
+                grantee,
+                start_after,
+                limit,
+            } => {
+                let response: Vec<AuthzGrant> = do_abci_query(
+                    &client,
+                    SdkQuery::AuthzGrants {
+                        granter,
+                        grantee,
+                        start_after,
+                        limit,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::Validators {
+                height,
+            } => {
+                let response: ValidatorsResponse = do_abci_query(
+                    &client,
+                    SdkQuery::Validators {
+                        height,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
             },
 
             QuerySubcmd::WasmRaw {
@@ -233,7 +469,103 @@ impl QueryCmd {
                 )
                 .await?;
 
-                print::json(response)?;
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::WasmDump {
+                contract,
+                prefix,
+                out,
+                decode,
+                data_dir,
+            } => {
+                let state_machine = match &data_dir {
+                    Some(data_dir) => Some(StateMachine::new(Store::open(data_dir)?)),
+                    None => None,
+                };
+                let prefix = prefix.map(hex::decode).transpose()?.map(Binary);
+
+                let mut writer: Box<dyn Write> = match &out {
+                    Some(out) => Box::new(BufWriter::new(File::create(out)?)),
+                    None => Box::new(io::stdout().lock()),
+                };
+
+                let mut start_after = None;
+                let mut count = 0;
+                loop {
+                    let query = SdkQuery::WasmAllRaw {
+                        contract: contract.clone(),
+                        prefix: prefix.clone(),
+                        start_after: start_after.take(),
+                        limit: Some(DUMP_PAGE_SIZE),
+                    };
+                    let page = query_raw_page(&client, state_machine.as_ref(), query).await?;
+
+                    for (key, value) in &page {
+                        let pair = DumpedPair::new(key, value, decode);
+                        writeln!(writer, "{}", serde_json::to_string(&pair)?)?;
+                    }
+                    count += page.len();
+
+                    if page.len() < DUMP_PAGE_SIZE as usize {
+                        break;
+                    }
+                    start_after = page.last().map(|(key, _)| key.clone());
+                }
+
+                writer.flush()?;
+
+                if let Some(out) = &out {
+                    info!("Dumped {count} key-value pairs to {}", path::stringify(out)?);
+                }
+            },
+
+            QuerySubcmd::Balance {
+                address,
+                denom,
+            } => {
+                let response: Coin = do_abci_query(
+                    &client,
+                    SdkQuery::Balance {
+                        address,
+                        denom,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::Balances {
+                address,
+                start_after,
+                limit,
+            } => {
+                let response: Vec<Coin> = do_abci_query(
+                    &client,
+                    SdkQuery::AllBalances {
+                        address,
+                        start_after,
+                        limit,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::Supply {
+                denom,
+            } => {
+                let response: Coin = do_abci_query(
+                    &client,
+                    SdkQuery::Supply {
+                        denom,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
             },
 
             QuerySubcmd::WasmSmart {
@@ -254,7 +586,7 @@ impl QueryCmd {
                         // attempt to decode the response as generic JSON
                         match serde_json::from_slice::<Value>(bytes.as_slice()) {
                             Ok(s) => {
-                                print::json(s)?;
+                                self.output.print(s)?;
                             },
                             Err(err) => {
                                 error!("Query successful but failed to decode response: {err}");
@@ -264,34 +596,143 @@ impl QueryCmd {
                     ContractResult::Err(err) => error!("Query failed: {err}"),
                 }
             },
+
+            QuerySubcmd::Tx {
+                hash,
+            } => {
+                let response: TxResponse = do_abci_query(
+                    &client,
+                    SdkQuery::Tx {
+                        hash,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::TxsByEvent {
+                event,
+                value,
+                start_after,
+                limit,
+            } => {
+                let response: Vec<TxResponse> = do_abci_query(
+                    &client,
+                    SdkQuery::TxsByEvent {
+                        event,
+                        value,
+                        start_after,
+                        limit,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
+
+            QuerySubcmd::ContractGasUsage {
+                limit,
+            } => {
+                let response: Vec<ContractGasUsage> = do_abci_query(
+                    &client,
+                    SdkQuery::ContractGasUsage {
+                        limit,
+                    },
+                )
+                .await?;
+
+                self.output.print(response)?;
+            },
         };
 
         Ok(())
     }
 }
 
-/// Just like `CodeResponse` but includes the byte code's hash instead of the
-/// full byte code. Used for CLI output.
+/// The number of key-value pairs to request in each page when dumping a
+/// contract's store
+pub(super) const DUMP_PAGE_SIZE: u32 = 100;
+
+/// Query a page of raw key-value pairs, either from a node or directly from a
+/// local database.
+pub(super) async fn query_raw_page(
+    client: &HttpClient,
+    state_machine: Option<&StateMachine>,
+    query: SdkQuery,
+) -> Result<Vec<(Binary, Binary)>, DaemonError> {
+    match state_machine {
+        Some(state_machine) => {
+            let response = state_machine
+                .query(query)
+                .map_err(|err| DaemonError::query_failed(err.to_string()))?;
+            from_binary(&response).map_err(|err| DaemonError::query_failed(err.to_string()))
+        },
+        None => do_abci_query(client, query).await,
+    }
+}
+
+/// A key-value pair in a contract's store. Used for `wasm-dump` output.
 #[derive(Serialize)]
-pub struct HashedCodeResponse {
-    code_id: u64,
-    hash: String, // hex-encoded SHA-256 hash
+pub struct DumpedPair {
+    /// The raw key, in hex encoding
+    key: String,
+    /// If decoding is enabled and the key appears to be a cw-storage-plus map
+    /// key, the map's namespace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    /// If decoding is enabled and the key appears to be a cw-storage-plus map
+    /// key, the remainder of the key after the namespace, in hex encoding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    map_key: Option<String>,
+    /// If decoding is enabled and the value is valid JSON, the decoded value;
+    /// otherwise the raw value in hex encoding
+    value: Value,
 }
 
-impl From<&CodeResponse> for HashedCodeResponse {
-    fn from(res: &CodeResponse) -> Self {
+impl DumpedPair {
+    fn new(key: &[u8], value: &[u8], decode: bool) -> Self {
+        let (namespace, map_key) = match decode.then(|| decode_map_key(key)).flatten() {
+            Some((namespace, map_key)) => (Some(namespace), Some(hex::encode(map_key))),
+            None => (None, None),
+        };
+        let value = match decode.then(|| serde_json::from_slice(value).ok()).flatten() {
+            Some(value) => value,
+            None => Value::String(hex::encode(value)),
+        };
         Self {
-            code_id: res.code_id,
-            hash: hex::encode(sha256(&res.wasm_byte_code)),
+            key: hex::encode(key),
+            namespace,
+            map_key,
+            value,
         }
     }
 }
 
+/// cw-storage-plus map keys are prefixed with the map's namespace, which is in
+/// turn prefixed with its length as a 2-byte big endian integer. Split such a
+/// key into the namespace and the rest of the key.
+///
+/// This is a best effort: other keys, e.g. those of `Item`s, may happen to be
+/// decodable this way too.
+fn decode_map_key(key: &[u8]) -> Option<(String, &[u8])> {
+    if key.len() < 2 {
+        return None;
+    }
+    let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+    if key.len() < 2 + len {
+        return None;
+    }
+    let namespace = String::from_utf8(key[2..2 + len].to_vec()).ok()?;
+    Some((namespace, &key[2 + len..]))
+}
+
 /// Like InfoResponse but BlockInfo is substituted with PrettyBlockInfo.
 #[derive(Serialize)]
 pub struct PrettyInfoResponse {
     last_committed_block: PrettyBlockInfo,
     code_count: u64,
+    params: Params,
 }
 
 impl From<InfoResponse> for PrettyInfoResponse {
@@ -299,6 +740,7 @@ impl From<InfoResponse> for PrettyInfoResponse {
         Self {
             last_committed_block: res.last_committed_block.into(),
             code_count: res.code_count,
+            params: res.params,
         }
     }
 }
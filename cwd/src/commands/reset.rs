@@ -3,14 +3,14 @@ use std::{fs, path::Path};
 use clap::Args;
 use tracing::info;
 
-use crate::{path::stringify, DaemonError};
+use crate::{path::stringify, AppConfig, DaemonError};
 
 #[derive(Args)]
 pub struct ResetCmd;
 
 impl ResetCmd {
     pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
-        let data_dir = home_dir.join("data");
+        let data_dir = AppConfig::load(home_dir)?.db_path(home_dir);
 
         // Rust doesn't provide a function to delete all files under a folder
         // but not the folder itself.
@@ -19,6 +19,12 @@ impl ResetCmd {
         fs::remove_dir_all(&data_dir)?;
         fs::create_dir(&data_dir)?;
 
+        // the undo checkpoint lives next to the database
+        let undo_dir = cw_store::undo_path(&data_dir);
+        if undo_dir.exists() {
+            fs::remove_dir_all(undo_dir)?;
+        }
+
         info!("Deleted application database at {}", stringify(&data_dir)?);
 
         Ok(())
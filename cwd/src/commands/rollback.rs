@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use clap::Args;
+use tracing::info;
+
+use cw_state_machine::StateMachine;
+use cw_store::Store;
+
+use crate::{AppConfig, DaemonError};
+
+/// Similar to `cometbft rollback`, this is meant to be used to recover from an
+/// app hash mismatch, e.g. caused by a bad upgrade. Tendermint's own state
+/// must be rolled back as well, using `tendermint rollback`.
+#[derive(Args)]
+pub struct RollbackCmd;
+
+impl RollbackCmd {
+    pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
+        let store = Store::open(AppConfig::load(home_dir)?.db_path(home_dir))?;
+        let mut state_machine = StateMachine::new(store);
+
+        let (height, _) = state_machine.info()?;
+        let (new_height, app_hash) = state_machine.rollback()?;
+
+        info!(
+            "Rolled back state from height {height} to {new_height}, app hash {}",
+            hex::encode_upper(app_hash),
+        );
+
+        Ok(())
+    }
+}
@@ -1,52 +1,204 @@
-use std::path::Path;
-use std::sync::mpsc;
+use std::{fs, future, path::Path, time::Duration};
 
+use bip32::{DerivationPath, Language, Mnemonic};
 use clap::Args;
-use tendermint_abci::ServerBuilder;
-use tracing::info;
+use tendermint::genesis::Genesis as TmGenesis;
+use tracing::{info, warn};
 
-use cw_server::{App, AppDriver};
-use cw_state_machine::StateMachine;
+use cw_sdk::GenesisState;
+use cw_server::AppDriver;
+use cw_state_machine::{
+    backend::ModuleCache, error::Error as StateMachineError,
+    vote_extensions::VoteExtensionHandler, StateMachine,
+};
 use cw_store::Store;
 
-use crate::{AppConfig, DaemonError};
+use super::genesis::{add_account, parse_coins, set_balance};
+use crate::{
+    config::{DbBackend, Pruning},
+    keyring::KeyringBackend,
+    AbciVersion, AppConfig, DaemonError, Key, Keyring,
+};
+
+/// Mnemonic from which the devnet's test accounts are derived, so that their
+/// addresses stay the same across runs. Never use it for real funds!
+pub(crate) const DEV_MNEMONIC: &str = concat!(
+    "abandon abandon abandon abandon abandon abandon ",
+    "abandon abandon abandon abandon abandon about",
+);
 
 #[derive(Args)]
-pub struct StartCmd;
+pub struct StartCmd {
+    /// Serve queries from the last committed state, but refuse to execute any
+    /// block, e.g. to inspect a node whose state has diverged
+    #[arg(long)]
+    safe_mode: bool,
+
+    /// Run a single-node devnet: blocks are produced in-process instead of by
+    /// CometBFT, and test accounts are funded at genesis
+    #[arg(long)]
+    dev: bool,
+
+    /// In devnet mode, produce a block every this many milliseconds, instead
+    /// of one as soon as txs are broadcasted
+    #[arg(long, requires = "dev")]
+    block_time: Option<u64>,
+
+    /// Number of test accounts funded at genesis in devnet mode
+    #[arg(long, requires = "dev", default_value_t = 10)]
+    dev_accounts: u32,
+
+    /// Coins each test account is funded with in devnet mode
+    #[arg(long, requires = "dev", default_value = "1000000000000ucosm")]
+    dev_balance: String,
+}
 
 impl StartCmd {
-    pub fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
+    pub async fn run(&self, home_dir: &Path) -> Result<(), DaemonError> {
         // load config from disk
         let app_cfg = AppConfig::load(home_dir)?;
-        info!("Loaded application config");
+        info!(moniker = %app_cfg.moniker, "Loaded application config");
 
         // load merk store from disk
-        let store = Store::open(home_dir.join("./data"))?;
+        let store = match app_cfg.db_backend {
+            DbBackend::Merk => Store::open(app_cfg.db_path(home_dir))?,
+        };
         info!("Loaded Merk store");
 
         // create a new state machine instance wrapping the store
-        let state_machine = StateMachine::new(store);
+        let mut state_machine = StateMachine::new(store);
+        state_machine.set_wasm_config(app_cfg.wasm);
+        if app_cfg.wasm.module_cache {
+            let modules = ModuleCache::open(app_cfg.module_cache_path(home_dir), &app_cfg.wasm)
+                .map_err(StateMachineError::from)?;
+            state_machine.set_module_cache(modules);
+            info!("Loaded compiled contract module cache");
+        }
+        state_machine.set_fee_denom(app_cfg.fee_denom);
+        state_machine.set_min_gas_prices(app_cfg.min_gas_prices);
+        state_machine.set_keep_undo_log(app_cfg.pruning == Pruning::Default);
+        state_machine.set_max_txs_per_account(app_cfg.max_txs_per_account);
+        if !app_cfg.vote_extensions_contract.is_empty() {
+            let handler = VoteExtensionHandler::Contract(app_cfg.vote_extensions_contract);
+            let enable_height = app_cfg.vote_extensions_enable_height;
+            state_machine.set_vote_extension_handler(handler, enable_height);
+        }
+        state_machine.set_halt(app_cfg.halt_height, app_cfg.halt_time);
+        state_machine.migrate()?;
+        if self.safe_mode {
+            state_machine.enable_safe_mode();
+            warn!("Started in safe mode; blocks will not be executed");
+        }
 
-        // create a channel between the App and AppDriver
-        let (cmd_tx, cmd_rx) = mpsc::channel();
-        let app = App {
-            cmd_tx,
+        let dev_genesis = if self.dev {
+            Some(self.dev_genesis(home_dir)?)
+        } else {
+            None
         };
-        let mut driver = AppDriver {
-            state_machine,
-            cmd_rx,
+
+        // create the AppDriver, along with the App that sends it commands
+        let (mut driver, mut app) = AppDriver::new(state_machine);
+        app.set_index(app_cfg.index);
+        if app_cfg.query_workers > 0 {
+            driver.enable_query_workers(&mut app, app_cfg.query_workers);
+            info!(workers = app_cfg.query_workers, "Serving queries from a snapshot of the state");
+        }
+
+        // spin up the gRPC server, if enabled, which sends queries to the
+        // AppDriver alongside the ABCI server, and broadcasts txs through the
+        // CometBFT RPC
+        let (grpc_app, grpc_addr) = (app.clone(), app_cfg.grpc_addr);
+        let grpc_rpc_addr = app_cfg.rpc_addr.clone();
+        let grpc_server = async move {
+            if grpc_addr.is_empty() {
+                return future::pending().await;
+            }
+            info!("Serving gRPC queries at {grpc_addr}");
+            cw_server::serve_grpc(grpc_app, &grpc_addr, &grpc_rpc_addr).await
         };
 
-        // create the ABCI server
-        let server = ServerBuilder::default().bind(app_cfg.listen_addr, app)?;
+        // likewise for the REST gateway
+        let (rest_app, rest_addr, rpc_addr) =
+            (app.clone(), app_cfg.rest_addr, app_cfg.rpc_addr.clone());
+        let rest_server = async move {
+            if rest_addr.is_empty() {
+                return future::pending().await;
+            }
+            info!("Serving REST gateway at {rest_addr}");
+            cw_server::serve_rest(rest_app, &rest_addr, &rpc_addr).await
+        };
 
-        // spin up the App and AppDriver
-        std::thread::spawn(move || server.listen().unwrap());
+        // spin up the ABCI server for the configured ABCI version. each of its
+        // connections is served in a separate task, which sends commands to the
+        // AppDriver. in devnet mode, blocks are produced in-process instead,
+        // and the RPC address is served in place of CometBFT's.
+        let (abci_version, listen_addr) = (app_cfg.abci_version, app_cfg.listen_addr);
+        let (devnet_rpc_addr, block_time) = (app_cfg.rpc_addr, self.block_time);
+        let server = async move {
+            match (dev_genesis, abci_version) {
+                (Some((chain_id, gen_state)), _) => {
+                    info!("Running a single-node devnet, serving RPC at {devnet_rpc_addr}");
+                    let block_time = block_time.map(Duration::from_millis);
+                    cw_server::serve_devnet(app, &devnet_rpc_addr, chain_id, gen_state, block_time)
+                        .await
+                },
+                (None, AbciVersion::V0_38) => cw_server::serve(app, &listen_addr).await,
+                (None, AbciVersion::V0_34) => cw_server::serve_legacy(app, &listen_addr).await,
+            }
+        };
 
         // NOTE: in basecoin, the app driver is spawned in threads.
-        // here we can't do the same because the Merk store is not thread safe.
-        driver.run();
+        // here we can't do the same because the Merk store is not thread safe,
+        // so the driver runs in this task instead.
+        tokio::select! {
+            result = server => result?,
+            result = grpc_server => result?,
+            result = rest_server => result?,
+            _ = driver.run() => (),
+        }
 
         Ok(())
     }
+    /// The chain id and genesis state from the genesis file written by `init`,
+    /// with the devnet's test accounts added and funded. Their keys are saved in
+    /// the `test` keyring backend as `dev0`, `dev1`, etc. The genesis file
+    /// itself is left untouched.
+    fn dev_genesis(&self, home_dir: &Path) -> Result<(String, GenesisState), DaemonError> {
+        let genesis_path = home_dir.join("config/genesis.json");
+        if !genesis_path.exists() {
+            return Err(DaemonError::file_not_found(&genesis_path)?);
+        }
+        let genesis: TmGenesis = serde_json::from_slice(&fs::read(&genesis_path)?)?;
+        let mut app_state: GenesisState =
+            serde_json::from_value(genesis.app_state).unwrap_or_default();
+
+        let keyring = Keyring::open(home_dir, Some(KeyringBackend::Test))?;
+        let mnemonic = Mnemonic::new(DEV_MNEMONIC, Language::English)?;
+        let coins = parse_coins(&self.dev_balance)?;
+        let mut funded = true;
+        for index in 0..self.dev_accounts {
+            let name = format!("dev{index}");
+            let path: DerivationPath = format!("m/44'/118'/0'/0/{index}").parse()?;
+            let key = Key::from_mnemonic(&name, &mnemonic, &path)?;
+            if !keyring.filename(&name).exists() {
+                keyring.set(&key)?;
+            }
+
+            let addr = key.address()?;
+            if !app_state.accounts.iter().any(|account| account.address == addr.as_str()) {
+                let pubkey = key.pubkey().to_bytes().to_vec().into();
+                add_account(&mut app_state, &addr, pubkey)?;
+            }
+            // without the bank contract there's nothing to fund the accounts with
+            if funded {
+                funded = set_balance(&mut app_state, &addr, coins.clone()).is_ok();
+            }
+            info!(%addr, "Test account {name}");
+        }
+        if !funded {
+            warn!("The bank contract is not in the genesis state; test accounts are not funded");
+        }
+
+        Ok((genesis.chain_id.to_string(), app_state))
+    }
 }
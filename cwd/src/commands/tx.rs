@@ -7,13 +7,22 @@ use std::{
 use clap::{Args, Subcommand};
 use colored::*;
 use cosmwasm_std::Addr;
-use cw_sdk::{Account, AccountResponse, SdkMsg, SdkQuery, TxBody};
-use tendermint_rpc::Client;
+use cw_sdk::{
+    address, canonical::to_canonical_vec, proto, Account, AccountResponse, Fee, InfoResponse,
+    MultiSignature, MultisigPubKey, PubKeyType, SdkMsg, SdkQuery, SignMode, SignerInfo,
+    SimulateResponse, Tx, TxBody, TxSignature, DEFAULT_GAS_LIMIT,
+};
+use cw_state_machine::auth;
+use tendermint_rpc::{Client, HttpClient};
 use tracing::warn;
 
+use super::genesis::parse_coins;
 use crate::{
     client::{create_http_client, do_abci_query},
-    print, prompt, ClientConfig, DaemonError, Keyring,
+    keyring::KeyringBackend,
+    path,
+    print::{self, OutputArgs},
+    prompt, ClientConfig, DaemonError, Key, Keyring,
 };
 
 #[derive(Args)]
@@ -21,24 +30,70 @@ pub struct TxCmd {
     #[command(subcommand)]
     pub subcommand: TxSubcmd,
 
-    /// Name of the key which will sign the transaction
+    /// Name of the key which will sign the transaction. When only generating
+    /// the unsigned transaction, the sender's address may be given instead.
+    /// Not needed for broadcasting a signed transaction.
     #[arg(long)]
-    from: String,
+    from: Option<String>,
 
     /// Chain id; overrides default value in client config
     #[arg(long)]
     chain_id: Option<String>,
 
+    /// Where private keys are saved; overrides default value in client config
+    #[arg(long)]
+    keyring_backend: Option<KeyringBackend>,
+
+    /// Account number of the signing account
+    #[arg(long)]
+    account_number: Option<u64>,
+
     /// Sequence number of the signing account
     #[arg(long)]
     sequence: Option<u64>,
 
+    /// A unique key for deduplicating retries: if a tx from the same account
+    /// carrying the same key has been executed recently, this tx is rejected
+    #[arg(long)]
+    idempotency_key: Option<String>,
+
+    /// Coins to pay as the fee, e.g. `2500ucosm`
+    #[arg(long)]
+    fees: Option<String>,
+
+    /// The maximum amount of gas the transaction may consume
+    #[arg(long, default_value_t = DEFAULT_GAS_LIMIT)]
+    gas: u64,
+
     /// Tendermint RPC endpoint; overrides default value in client config
     #[arg(long)]
     node: Option<String>,
+
+    /// Encode the transaction in protobuf and sign it in SIGN_MODE_DIRECT,
+    /// instead of in canonical JSON
+    #[arg(long)]
+    proto: bool,
+
+    /// Simulate the transaction against the last committed state and print
+    /// the gas it uses and the events it emits, instead of broadcasting it
+    #[arg(long)]
+    simulate: bool,
+
+    /// Print the unsigned transaction in JSON, e.g. to be signed by `tx sign`
+    /// on another machine, instead of signing it
+    #[arg(long, conflicts_with = "simulate")]
+    generate_only: bool,
+
+    /// Don't connect to the node: the account number and sequence must be
+    /// provided, and the signed transaction is printed instead of broadcasted
+    #[arg(long, conflicts_with = "simulate")]
+    offline: bool,
+
+    #[command(flatten)]
+    output: OutputArgs,
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 pub enum TxSubcmd {
     /// Upload wasm byte code
     Store {
@@ -87,81 +142,138 @@ pub enum TxSubcmd {
         /// Migrate message in JSON format
         msg: String,
     },
+
+    /// Grant an account an allowance to pay tx fees from the sender's balance
+    GrantAllowance {
+        /// Grantee address
+        grantee: String,
+        /// The allowance in JSON format
+        allowance: String,
+    },
+
+    /// Revoke the fee allowance granted to an account
+    RevokeAllowance {
+        /// Grantee address
+        grantee: String,
+    },
+
+    /// Authorize an account to send messages on the sender's behalf
+    GrantAuthz {
+        /// Grantee address
+        grantee: String,
+        /// The authorization in JSON format
+        authorization: String,
+        /// UNIX timestamp (in seconds) at which the grant expires
+        #[arg(long)]
+        expiration: Option<u64>,
+    },
+
+    /// Revoke an authorization granted to an account
+    RevokeAuthz {
+        /// Grantee address
+        grantee: String,
+        /// The authorization in JSON format
+        authorization: String,
+    },
+
+    /// Send messages on behalf of accounts that have authorized the sender
+    Exec {
+        /// The messages in JSON format, as an array of `{"sender":..,"msg":..}`
+        msgs: String,
+    },
+
+    /// Replace the sender account's pubkey with that of another key
+    RotateKey {
+        /// Name of the key in the keyring which will control the account
+        new_key: String,
+    },
+
+    /// Sign a transaction generated with `--generate-only`, as one of its
+    /// signers. Signatures of the other signers in the file are kept.
+    Sign {
+        /// Path to the transaction in JSON format
+        file: PathBuf,
+
+        /// Sign as a member of this multisig signer instead, and print only
+        /// the signature, to be combined with the other members' by
+        /// `tx multisign`
+        #[arg(long)]
+        multisig: Option<String>,
+    },
+
+    /// Combine the signatures of a multisig signer's members into the
+    /// multisig's signature of a transaction
+    Multisign {
+        /// Path to the transaction in JSON format
+        file: PathBuf,
+
+        /// Address of the multisig signer
+        multisig: String,
+
+        /// Paths to the members' signatures, produced by `tx sign --multisig`
+        #[arg(required = true)]
+        signature_files: Vec<PathBuf>,
+
+        /// The multisig's pubkey in JSON format, as `{"pubkeys":[..],"threshold":..}`.
+        /// Required if the account doesn't exist on chain yet, or when offline.
+        #[arg(long)]
+        multisig_pubkey: Option<String>,
+    },
+
+    /// Broadcast a transaction signed with `--offline`
+    Broadcast {
+        /// Path to the transaction in JSON format
+        file: PathBuf,
+    },
 }
 
 impl TxCmd {
     pub async fn run(self, home_dir: &Path) -> Result<(), DaemonError> {
-        // load sender key
-        let keyring = Keyring::new(home_dir.join("keys"))?;
-        let key = keyring.get(&self.from)?;
-        let sender_addr = key.address()?;
+        let keyring = Keyring::open(home_dir, self.keyring_backend)?;
 
-        // create tendermint client
+        // create tendermint client. it isn't used in offline mode
         let client_cfg = ClientConfig::load(home_dir)?;
         let client = create_http_client(self.node.as_ref(), &client_cfg)?;
 
-        // find chain id
-        let chain_id = self.chain_id.as_ref().unwrap_or(&client_cfg.chain_id);
+        let msg = match self.subcommand.clone() {
+            TxSubcmd::Sign {
+                file,
+                multisig,
+            } => {
+                let Tx {
+                    body,
+                    signatures,
+                } = read_tx(&file)?;
+                let key = keyring.get(self.from()?)?;
+                if let Some(multisig) = multisig {
+                    let (_, signer) = find_signer(&body, &multisig)?;
+                    return print::json(self.sign(&key, &body, signer)?);
+                }
+                return self.sign_and_broadcast(&client, &key, body, signatures).await;
+            },
 
-        // query the sender's sequence number if not provided
-        let sequence = match self.sequence {
-            None => {
-                let result = do_abci_query::<_, AccountResponse>(
-                    &client,
-                    SdkQuery::Account {
-                        address: sender_addr.to_string(),
-                    },
-                )
-                .await;
-
-                let sequence = match result {
-                    // if the account exists and is a base account, we take the
-                    // sequence number
-                    Ok(AccountResponse {
-                        account: Account::Base {
-                            sequence,
-                            ..
-                        },
-                        ..
-                    }) => sequence,
-
-                    // if the account exists but is a contract, we throw error
-                    // because contracts can't sign txs
-                    Ok(AccountResponse {
-                        account: Account::Contract {
-                            ..
-                        },
-                        ..
-                    }) => return Err(DaemonError::sender_is_contract(&sender_addr)),
-
-                    // if query results in an error, and the error is that the
-                    // account is not found, we use zero.
-                    // the first tx ever to be submitted should have the
-                    // sequence of 1.
-                    //
-                    // TODO: instead of string matching, we should establish a
-                    // standardized list of error codes and match the code instead
-                    Err(DaemonError::QueryFailed {
-                        err,
-                    }) if err.contains(&format!("{} not found", type_name::<Account<Addr>>())) => {
-                        warn!(
-                            "Account with address {} not found on chain. Use default sequence number of 1",
-                            &sender_addr,
-                        );
-                        0
-                    },
-
-                    // for other errors, we cannot handle them here, so we throw
-                    Err(err) => return Err(err),
+            TxSubcmd::Multisign {
+                file,
+                multisig,
+                signature_files,
+                multisig_pubkey,
+            } => {
+                let tx = read_tx(&file)?;
+                let pubkey = match multisig_pubkey {
+                    Some(pubkey) => Some(serde_json::from_str(&pubkey)?),
+                    None => None,
                 };
+                return self.multisign(&client, tx, &multisig, &signature_files, pubkey).await;
+            },
 
-                // needs to be 1 greater than the on-chain sequence
-                sequence + 1
+            TxSubcmd::Broadcast {
+                file,
+            } => {
+                let tx = read_tx(&file)?;
+                let tx_bytes = self.encode_tx(&tx)?;
+                return self.broadcast(&client, tx, tx_bytes).await;
             },
-            Some(sequence) => sequence,
-        };
 
-        let msg = match self.subcommand {
             TxSubcmd::Store {
                 wasm_byte_code_path,
             } => {
@@ -215,27 +327,433 @@ impl TxCmd {
                 code_id,
                 msg: serde_json::from_str(&msg)?,
             },
+
+            TxSubcmd::GrantAllowance {
+                grantee,
+                allowance,
+            } => SdkMsg::GrantAllowance {
+                grantee,
+                allowance: serde_json::from_str(&allowance)?,
+            },
+
+            TxSubcmd::RevokeAllowance {
+                grantee,
+            } => SdkMsg::RevokeAllowance {
+                grantee,
+            },
+
+            TxSubcmd::GrantAuthz {
+                grantee,
+                authorization,
+                expiration,
+            } => SdkMsg::GrantAuthz {
+                grantee,
+                authorization: serde_json::from_str(&authorization)?,
+                expiration,
+            },
+
+            TxSubcmd::RevokeAuthz {
+                grantee,
+                authorization,
+            } => SdkMsg::RevokeAuthz {
+                grantee,
+                authorization: serde_json::from_str(&authorization)?,
+            },
+
+            TxSubcmd::Exec {
+                msgs,
+            } => SdkMsg::Exec {
+                msgs: serde_json::from_str(&msgs)?,
+            },
+
+            TxSubcmd::RotateKey {
+                new_key,
+            } => SdkMsg::RotateKey {
+                new_pubkey: keyring.get(&new_key)?.pubkey().to_bytes().to_vec().into(),
+                new_pubkey_type: PubKeyType::Secp256k1,
+            },
+        };
+
+        // load sender key. when only generating the unsigned tx, the sender
+        // may be given as an address, in which case no key is needed
+        let from = self.from()?;
+        let (key, sender_addr) = match address::validate(from) {
+            Ok(sender_addr) if self.generate_only => (None, sender_addr),
+            _ => {
+                let key = keyring.get(from)?;
+                let sender_addr = key.address()?;
+                (Some(key), sender_addr)
+            },
+        };
+
+        // find chain id
+        let chain_id = self.chain_id.as_ref().unwrap_or(&client_cfg.chain_id);
+
+        // query the sender's account number and sequence if not provided
+        let (account_number, sequence) = match (self.account_number, self.sequence) {
+            (Some(account_number), Some(sequence)) => (account_number, sequence),
+            _ if self.offline => return Err(DaemonError::MissingSignerInfo),
+            (account_number, sequence) => {
+                let (onchain_account_number, onchain_sequence) =
+                    query_account(&client, &sender_addr).await?;
+                (
+                    account_number.unwrap_or(onchain_account_number),
+                    // needs to be 1 greater than the on-chain sequence
+                    sequence.unwrap_or(onchain_sequence + 1),
+                )
+            },
         };
 
         let body = TxBody {
-            sender: sender_addr.into(),
-            msgs: vec![msg],
-            chain_id: chain_id.into(),
-            sequence,
+            fee: self.fee()?,
+            idempotency_key: self.idempotency_key.clone(),
+            ..TxBody::single(sender_addr, chain_id, account_number, sequence, vec![msg])
         };
 
-        let tx = key.sign_tx(&body)?;
-        let tx_bytes = serde_json::to_vec(&tx)?;
+        if self.generate_only {
+            return print::json(Tx {
+                body,
+                signatures: vec![],
+            });
+        }
+
+        let key = match key {
+            Some(key) => key,
+            None => keyring.get(from)?,
+        };
+        self.sign_and_broadcast(&client, &key, body, vec![]).await
+    }
+
+    fn fee(&self) -> Result<Fee, DaemonError> {
+        Ok(Fee {
+            amount: self.fees.as_deref().map(parse_coins).transpose()?.unwrap_or_default(),
+            gas_limit: self.gas,
+            ..Default::default()
+        })
+    }
+
+    fn from(&self) -> Result<&str, DaemonError> {
+        self.from.as_deref().ok_or_else(|| DaemonError::missing_argument("--from"))
+    }
 
-        println!("{}", "🤖 Transaction signed:".bold());
-        print::json(&tx)?;
+    /// Sign the tx body as the given signer, in the sign mode the tx is to be
+    /// encoded in.
+    fn sign(
+        &self,
+        key: &Key,
+        body: &TxBody,
+        signer: &SignerInfo,
+    ) -> Result<TxSignature, DaemonError> {
+        if self.proto {
+            key.sign_tx_direct(body, &proto::encode_body(body)?, signer)
+        } else {
+            key.sign_tx(body, signer)
+        }
+    }
+
+    /// How the sign bytes of the tx body are computed, given how the tx is to
+    /// be encoded
+    fn sign_mode(&self, body: &TxBody) -> Result<SignMode, DaemonError> {
+        if self.proto {
+            Ok(SignMode::Direct {
+                body_bytes: proto::encode_body(body)?.into(),
+            })
+        } else {
+            Ok(SignMode::Json)
+        }
+    }
+
+    /// Encode the tx to be broadcasted, in protobuf or canonical JSON
+    fn encode_tx(&self, tx: &Tx) -> Result<Vec<u8>, DaemonError> {
+        if self.proto {
+            Ok(proto::encode_tx(proto::encode_body(&tx.body)?, &tx.signatures))
+        } else {
+            to_canonical_vec(tx).map_err(DaemonError::from)
+        }
+    }
+
+    /// Sign the tx body as one of its signers, then print the tx and, unless
+    /// offline, simulate or broadcast it. The signatures of the other signers
+    /// are kept as they are.
+    async fn sign_and_broadcast(
+        &self,
+        client: &HttpClient,
+        key: &Key,
+        body: TxBody,
+        mut signatures: Vec<TxSignature>,
+    ) -> Result<(), DaemonError> {
+        let (index, signer) = find_signer(&body, key.address()?.as_str())?;
+        let signature = self.sign(key, &body, signer)?;
+
+        // the other signers' signatures are left empty if they haven't signed
+        signatures.resize(body.signers.len(), empty_signature());
+        signatures[index] = signature;
+
+        let tx = Tx {
+            body,
+            signatures,
+        };
+        self.finish(client, tx).await
+    }
+
+    /// Combine the members' signatures of a multisig signer into its
+    /// signature, then print the tx and, unless offline, simulate or broadcast
+    /// it. The members' signatures are verified before they are combined.
+    ///
+    /// The multisig's pubkey is queried from the chain if not provided, in
+    /// which case it isn't included in the tx.
+    async fn multisign(
+        &self,
+        client: &HttpClient,
+        tx: Tx,
+        multisig: &str,
+        signature_files: &[PathBuf],
+        pubkey: Option<MultisigPubKey>,
+    ) -> Result<(), DaemonError> {
+        let Tx {
+            body,
+            mut signatures,
+        } = tx;
+        let (index, signer) = find_signer(&body, multisig)?;
+
+        let (pubkey, include_pubkey) = match pubkey {
+            Some(pubkey) => {
+                if pubkey.derive_address()? != multisig {
+                    return Err(DaemonError::invalid_multisig(format!(
+                        "pubkey doesn't match the address {multisig}"
+                    )));
+                }
+                (pubkey, true)
+            },
+            None if self.offline => {
+                return Err(DaemonError::missing_argument("--multisig-pubkey"));
+            },
+            None => (query_multisig_pubkey(client, multisig).await?, false),
+        };
+
+        // all members must have signed the same sign doc
+        let sign_mode = self.sign_mode(&body)?;
+        let mut member_signatures = vec![None; pubkey.pubkeys.len()];
+        let mut legacy_amino_json = None;
+        for file in signature_files {
+            let signature: TxSignature = serde_json::from_slice(&fs::read(file)?)?;
+            let Some(member_pubkey) = &signature.pubkey else {
+                return Err(DaemonError::invalid_multisig(format!(
+                    "signature in {} has no pubkey",
+                    path::stringify(file)?,
+                )));
+            };
+            let Some(position) = pubkey.pubkeys.iter().position(|pk| pk == member_pubkey) else {
+                return Err(DaemonError::invalid_multisig(format!(
+                    "{} is not a member",
+                    hex::encode(member_pubkey.as_slice()),
+                )));
+            };
+            if *legacy_amino_json.get_or_insert(signature.legacy_amino_json)
+                != signature.legacy_amino_json
+            {
+                return Err(DaemonError::invalid_multisig("members signed different sign docs"));
+            }
+
+            let sign_bytes = signature.sign_bytes(
+                &sign_mode,
+                &body,
+                &body.chain_id,
+                signer.account_number,
+                signer.sequence,
+            )?;
+            auth::verify_signature(
+                PubKeyType::Secp256k1,
+                member_pubkey,
+                &sign_bytes,
+                &signature.signature,
+            )?;
+
+            member_signatures[position] = Some(signature.signature);
+        }
+
+        let found = member_signatures.iter().flatten().count();
+        if found < pubkey.threshold as usize {
+            return Err(DaemonError::invalid_multisig(format!(
+                "found {found} signatures, but the threshold is {}",
+                pubkey.threshold,
+            )));
+        }
+
+        signatures.resize(body.signers.len(), empty_signature());
+        signatures[index] = TxSignature {
+            multisig: Some(MultiSignature {
+                pubkey: include_pubkey.then_some(pubkey),
+                signatures: member_signatures,
+            }),
+            legacy_amino_json: legacy_amino_json.unwrap_or_default(),
+            ..empty_signature()
+        };
+
+        let tx = Tx {
+            body,
+            signatures,
+        };
+        self.finish(client, tx).await
+    }
+
+    /// Print the signed tx and, unless offline, simulate or broadcast it.
+    async fn finish(&self, client: &HttpClient, tx: Tx) -> Result<(), DaemonError> {
+        // print only the tx when offline, so that it can be saved to a file
+        if self.offline {
+            return print::json(&tx);
+        }
+
+        let tx_bytes = self.encode_tx(&tx)?;
+
+        eprintln!("{}", "🤖 Transaction signed:".bold());
+        self.output.print(&tx)?;
+
+        self.broadcast(client, tx, tx_bytes).await
+    }
+
+    /// Simulate the tx if requested, or otherwise broadcast it once the user
+    /// confirms.
+    async fn broadcast(
+        &self,
+        client: &HttpClient,
+        tx: Tx,
+        tx_bytes: Vec<u8>,
+    ) -> Result<(), DaemonError> {
+        if self.simulate {
+            let response: SimulateResponse = do_abci_query(
+                client,
+                SdkQuery::Simulate {
+                    tx,
+                },
+            )
+            .await?;
+            return self.output.print(response);
+        }
 
         if prompt::confirm(format!("{}", "🤔 Broadcast?".bold()))? {
             let response = client.broadcast_tx_async(tx_bytes).await?;
-            print::json(response)?;
-            println!("{}", "🙌 Successfully broadcasted!".bold());
+            self.output.print(response)?;
+            eprintln!("{}", "🙌 Successfully broadcasted!".bold());
         }
 
         Ok(())
     }
 }
+
+/// Read a tx from a JSON file
+fn read_tx(file: &Path) -> Result<Tx, DaemonError> {
+    let json = fs::read(file)?;
+    serde_json::from_slice(&json).map_err(DaemonError::from)
+}
+
+/// Find the signer of the given address in the tx body, and its index.
+fn find_signer<'a>(
+    body: &'a TxBody,
+    address: &str,
+) -> Result<(usize, &'a SignerInfo), DaemonError> {
+    body.signers
+        .iter()
+        .enumerate()
+        .find(|(_, signer)| signer.address == address)
+        .ok_or_else(|| DaemonError::not_signer(address))
+}
+
+/// The placeholder of a signer that hasn't signed
+fn empty_signature() -> TxSignature {
+    TxSignature {
+        pubkey: None,
+        pubkey_type: PubKeyType::Secp256k1,
+        signature: Default::default(),
+        multisig: None,
+        legacy_amino_json: false,
+    }
+}
+
+/// Query the pubkey of a multisig account
+async fn query_multisig_pubkey(
+    client: &HttpClient,
+    address: &str,
+) -> Result<MultisigPubKey, DaemonError> {
+    let response: AccountResponse = do_abci_query(
+        client,
+        SdkQuery::Account {
+            address: address.into(),
+        },
+    )
+    .await?;
+
+    match response.account {
+        Account::Multisig {
+            pubkey,
+            ..
+        } => Ok(pubkey),
+        _ => Err(DaemonError::invalid_multisig(format!("{address} is not a multisig account"))),
+    }
+}
+
+/// Query the sender's account number and sequence number.
+///
+/// If the account doesn't exist on chain yet, it will be assigned the next
+/// account number when its first tx is executed, and its sequence is zero.
+async fn query_account(
+    client: &HttpClient,
+    sender_addr: &Addr,
+) -> Result<(u64, u64), DaemonError> {
+    let result = do_abci_query::<_, AccountResponse>(
+        client,
+        SdkQuery::Account {
+            address: sender_addr.to_string(),
+        },
+    )
+    .await;
+
+    match result {
+        // if the account exists and is a base or multisig account, we take the
+        // account number and sequence number
+        Ok(AccountResponse {
+            account:
+                Account::Base {
+                    account_number,
+                    sequence,
+                    ..
+                }
+                | Account::Multisig {
+                    account_number,
+                    sequence,
+                    ..
+                },
+            ..
+        }) => Ok((account_number, sequence)),
+
+        // if the account exists but is a contract, we throw error because
+        // contracts can't sign txs
+        Ok(AccountResponse {
+            account: Account::Contract {
+                ..
+            },
+            ..
+        }) => Err(DaemonError::sender_is_contract(sender_addr)),
+
+        // if query results in an error, and the error is that the account is
+        // not found, we use the next account number and a sequence of zero.
+        // the first tx ever to be submitted should have the sequence of 1.
+        //
+        // TODO: instead of string matching, we should establish a
+        // standardized list of error codes and match the code instead
+        Err(DaemonError::QueryFailed {
+            err,
+        }) if err.contains(&format!("{} not found", type_name::<Account<Addr>>())) => {
+            let info = do_abci_query::<_, InfoResponse>(client, SdkQuery::Info {}).await?;
+            warn!(
+                "Account with address {} not found on chain. Use account number {} and default sequence number of 1",
+                sender_addr,
+                info.account_count,
+            );
+            Ok((info.account_count, 0))
+        },
+
+        // for other errors, we cannot handle them here, so we throw
+        Err(err) => Err(err),
+    }
+}
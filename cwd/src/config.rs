@@ -1,31 +1,267 @@
-use std::fs;
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
 
+use cosmwasm_std::Decimal;
+use cw_server::IndexConfig;
+use cw_state_machine::backend::WasmConfig;
 use serde::{Deserialize, Serialize};
+use toml::Value;
 
-use crate::DaemonError;
+use crate::{keyring::KeyringBackend, DaemonError};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppConfig {
-    /// Address to listen for ABCI requests
+    /// A human-readable name of the node, included in its logs
+    #[serde(default)]
+    pub moniker: String,
+
+    /// Address to listen for ABCI requests: either a TCP address, such as
+    /// `tcp://127.0.0.1:26658`, or a Unix domain socket, such as
+    /// `unix:///path/to/app.sock`. Tendermint's `proxy_app` must be set to the
+    /// same address.
     pub listen_addr: String,
+
+    /// TCP address to serve gRPC queries at, such as `127.0.0.1:9090`. If
+    /// empty, the gRPC server is disabled.
+    #[serde(default)]
+    pub grpc_addr: String,
+
+    /// TCP address to serve the REST gateway at, such as `127.0.0.1:1317`. If
+    /// empty, the gateway is disabled.
+    #[serde(default)]
+    pub rest_addr: String,
+
+    /// URL of the CometBFT RPC that txs broadcasted through gRPC or the REST
+    /// gateway are forwarded to
+    #[serde(default = "default_rpc_addr")]
+    pub rpc_addr: String,
+
+    /// Version of the ABCI protocol spoken by the consensus engine. Nodes
+    /// configured before CometBFT 0.38 was supported run Tendermint 0.34.
+    #[serde(default = "AbciVersion::legacy")]
+    pub abci_version: AbciVersion,
+
+    /// The database backend that stores the application state
+    #[serde(default)]
+    pub db_backend: DbBackend,
+
+    /// Directory of the application database, relative to the home directory
+    /// unless absolute
+    #[serde(default = "default_db_dir")]
+    pub db_dir: PathBuf,
+
+    /// Which historical data the database retains besides the latest state
+    #[serde(default)]
+    pub pruning: Pruning,
+
+    /// Options of the wasm instances that contracts are run in: whether their
+    /// debug messages are printed, the gas limit of smart queries, and whether
+    /// their compiled modules are cached under the home directory
+    #[serde(default)]
+    pub wasm: WasmConfig,
+
+    /// The number of threads answering queries that only read the committed
+    /// state, e.g. smart queries, from an in-memory snapshot of it, so that
+    /// they don't compete with the execution of blocks. The snapshot holds a
+    /// copy of the whole state in memory. Zero means queries are answered one
+    /// at a time in between blocks.
+    #[serde(default)]
+    pub query_workers: usize,
+
+    /// The denom in which gas prices are measured. Txs are prioritized in the
+    /// mempool by the amount of this denom they pay per unit of gas. If empty,
+    /// all txs have the same priority.
+    #[serde(default)]
+    pub fee_denom: String,
+
+    /// The minimum gas prices, by denom, that txs must pay to be admitted to
+    /// this node's mempool, e.g. `uatom = "0.025"`. A tx must meet the price of
+    /// one of the denoms, i.e. pay at least the price times its gas limit in
+    /// that denom. If empty, any gas price is accepted.
+    #[serde(default)]
+    pub min_gas_prices: BTreeMap<String, Decimal>,
+
+    /// The maximum number of txs of the same signer that this node includes in
+    /// a block it proposes. Zero means unlimited.
+    #[serde(default)]
+    pub max_txs_per_account: u64,
+
+    /// Label of the contract that extends and verifies votes, e.g. an oracle,
+    /// and processes the extensions included in blocks. If empty, votes aren't
+    /// extended. Requires ABCI 0.38, and all validators must use the same one.
+    #[serde(default)]
+    pub vote_extensions_contract: String,
+
+    /// The chain's `vote_extensions_enable_height` consensus parameter, which
+    /// must be set along with the vote extensions contract. Proposals of later
+    /// heights are rejected unless they include the extended votes.
+    #[serde(default)]
+    pub vote_extensions_enable_height: u64,
+
+    /// Halt the node once the block at this height is committed, e.g. for a
+    /// coordinated upgrade. Zero means disabled.
+    #[serde(default)]
+    pub halt_height: u64,
+
+    /// Halt the node once a block whose time is at or after this UNIX
+    /// timestamp (in seconds) is committed. Zero means disabled.
+    #[serde(default)]
+    pub halt_time: u64,
+
+    /// Which event attributes Tendermint's tx indexer indexes, so that txs can
+    /// be searched by them, e.g. `wasm._contract_address`. By default, all
+    /// attributes are indexed. For example, to only index the contract address
+    /// of wasm events, and all attributes of message events but the sender:
+    ///
+    /// ```toml
+    /// [index.events.wasm]
+    /// allow = ["_contract_address"]
+    ///
+    /// [index.events.message]
+    /// deny = ["sender"]
+    /// ```
+    #[serde(default)]
+    pub index: IndexConfig,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
-            // including the `tcp://` prefix causes an error...?
-            listen_addr: "127.0.0.1:26658".into(),
+            moniker: "".into(),
+            listen_addr: "tcp://127.0.0.1:26658".into(),
+            grpc_addr: "127.0.0.1:9090".into(),
+            rest_addr: "127.0.0.1:1317".into(),
+            rpc_addr: default_rpc_addr(),
+            abci_version: AbciVersion::V0_38,
+            db_backend: DbBackend::default(),
+            db_dir: default_db_dir(),
+            pruning: Pruning::default(),
+            wasm: WasmConfig::default(),
+            query_workers: 0,
+            fee_denom: "".into(),
+            min_gas_prices: BTreeMap::new(),
+            max_txs_per_account: 0,
+            vote_extensions_contract: "".into(),
+            vote_extensions_enable_height: 0,
+            halt_height: 0,
+            halt_time: 0,
+            index: IndexConfig::default(),
         }
     }
 }
 
+fn default_rpc_addr() -> String {
+    "http://127.0.0.1:26657".into()
+}
+
+fn default_db_dir() -> PathBuf {
+    "data".into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AbciVersion {
+    /// Tendermint 0.34, where blocks are executed in BeginBlock, DeliverTx,
+    /// EndBlock and Commit
+    #[serde(rename = "0.34")]
+    V0_34,
+
+    /// CometBFT 0.38, where blocks are executed in FinalizeBlock
+    #[serde(rename = "0.38")]
+    V0_38,
+}
+
+impl AbciVersion {
+    fn legacy() -> Self {
+        Self::V0_34
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DbBackend {
+    /// A Merkle AVL tree over RocksDB, which is the only backend supported
+    #[default]
+    Merk,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pruning {
+    /// Keep the undo checkpoint of the last commit, so that it can be rolled back
+    #[default]
+    Default,
+
+    /// Skip the undo checkpoint before each commit, to save disk space
+    Everything,
+}
+
+/// Prefix of the environment variables that override the fields of app.toml
+const ENV_PREFIX: &str = "CWD_";
+
 impl AppConfig {
+    /// Load the config from app.toml, with fields overridden by environment
+    /// variables named after them: e.g. `CWD_FEE_DENOM` overrides `fee_denom`,
+    /// and `CWD_WASM__PRINT_DEBUG` overrides `print_debug` of the `[wasm]`
+    /// table. Values are parsed as TOML if possible, e.g. numbers and booleans,
+    /// and taken as strings otherwise.
     pub fn load(home_dir: &Path) -> Result<Self, DaemonError> {
         let cfg_path = home_dir.join("config/app.toml");
         let cfg_bytes = fs::read(cfg_path)?;
-        toml::from_slice(&cfg_bytes).map_err(DaemonError::from)
+        let mut cfg: Value = toml::from_slice(&cfg_bytes)?;
+        override_from_env(&mut cfg, env::vars());
+        cfg.try_into().map_err(DaemonError::from)
     }
+
+    /// The path of the application database
+    pub fn db_path(&self, home_dir: &Path) -> PathBuf {
+        home_dir.join(&self.db_dir)
+    }
+
+    /// The path of the cache of compiled contract modules
+    pub fn module_cache_path(&self, home_dir: &Path) -> PathBuf {
+        home_dir.join("wasm")
+    }
+}
+
+fn override_from_env(cfg: &mut Value, vars: impl Iterator<Item = (String, String)>) {
+    'vars: for (name, value) in vars {
+        let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let path = path.to_lowercase();
+        let mut keys = path.split("__").collect::<Vec<_>>();
+        let Some(field) = keys.pop() else {
+            continue;
+        };
+
+        // create the tables along the path if missing. a variable whose path
+        // runs through a value that isn't a table is ignored.
+        let Some(mut table) = cfg.as_table_mut() else {
+            return;
+        };
+        for key in keys {
+            let entry = table.entry(key).or_insert_with(|| Value::Table(Default::default()));
+            match entry.as_table_mut() {
+                Some(inner) => table = inner,
+                None => continue 'vars,
+            }
+        }
+
+        table.insert(field.into(), parse_env_value(&value));
+    }
+}
+
+/// Parse the value of an environment variable as a TOML value, falling back to
+/// a string. TOML has no syntax for a standalone value, so it's parsed as the
+/// value of a key.
+fn parse_env_value(value: &str) -> Value {
+    toml::from_str::<toml::value::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.into()))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -34,6 +270,9 @@ pub struct ClientConfig {
     pub chain_id: String,
     /// Tendermint RPC address for broadcasting transactions for performing queries
     pub node: String,
+    /// Where private keys are saved: `test`, `file` or `os`
+    #[serde(default)]
+    pub keyring_backend: KeyringBackend,
 }
 
 impl Default for ClientConfig {
@@ -41,6 +280,7 @@ impl Default for ClientConfig {
         Self {
             chain_id: "".into(),
             node: "http://localhost:26657".into(),
+            keyring_backend: KeyringBackend::default(),
         }
     }
 }
@@ -10,7 +10,10 @@ pub enum DaemonError {
     Address(#[from] cw_sdk::address::AddressError),
 
     #[error(transparent)]
-    BCrypt(#[from] bcrypt::BcryptError),
+    AgeDecrypt(#[from] age::DecryptError),
+
+    #[error(transparent)]
+    Argon2(#[from] argon2::password_hash::Error),
 
     #[error(transparent)]
     Bip32(#[from] bip32::Error),
@@ -28,19 +31,28 @@ pub enum DaemonError {
     Io(#[from] std::io::Error),
 
     #[error(transparent)]
-    Jose(#[from] josekit::JoseError),
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Keyring(#[from] ::keyring::Error),
 
     #[error(transparent)]
-    Json(#[from] serde_json::Error),
+    Ledger(#[from] ledger_transport_hid::LedgerHIDError),
 
     #[error(transparent)]
     Merk(#[from] cw_store::MerkError),
 
     #[error(transparent)]
-    Tendermint(#[from] tendermint::Error),
+    RocksDb(#[from] cw_store::RocksDbError),
+
+    #[error(transparent)]
+    StateMachine(#[from] cw_state_machine::error::Error),
+
+    #[error(transparent)]
+    Std(#[from] cosmwasm_std::StdError),
 
     #[error(transparent)]
-    TendermintAbci(#[from] tendermint_abci::Error),
+    Tendermint(#[from] tendermint::Error),
 
     #[error(transparent)]
     TendermintRpc(#[from] tendermint_rpc::Error),
@@ -51,6 +63,12 @@ pub enum DaemonError {
     #[error(transparent)]
     TomlSer(#[from] toml::ser::Error),
 
+    #[error(transparent)]
+    TowerAbci(#[from] tower_abci::BoxError),
+
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+
     #[error("failed to determine system home directory")]
     HomeDirFailed,
 
@@ -60,6 +78,9 @@ pub enum DaemonError {
     #[error("password is incorrect")]
     IncorrectPassword,
 
+    #[error("account number and sequence must be provided when offline")]
+    MissingSignerInfo,
+
     #[error("file already exists: {filename}")]
     FileExists {
         filename: String,
@@ -70,21 +91,82 @@ pub enum DaemonError {
         filename: String,
     },
 
-    #[error("failed to cast JWT payload to key: {reason}")]
+    #[error("invalid multisig: {reason}")]
+    InvalidMultisig {
+        reason: String,
+    },
+
+    #[error("{address} is not a signer of the tx")]
+    NotSigner {
+        address: String,
+    },
+
+    #[error("missing argument: {name}")]
+    MissingArgument {
+        name: String,
+    },
+
+    #[error("malformed key: {reason}")]
     MalformedPayload {
         reason: String,
     },
 
+    #[error("can't fork at height {height}: the last committed height is {committed}")]
+    ForkHeightMismatch {
+        height: u64,
+        committed: u64,
+    },
+
+    #[error("invalid param override `{param}`: expecting `key=value` where key is a valid path")]
+    InvalidParam {
+        param: String,
+    },
+
+    #[error("invalid coins `{coins}`: expecting e.g. `100uatom,50ucosm`")]
+    InvalidCoins {
+        coins: String,
+    },
+
+    #[error("invalid genesis: {reason}")]
+    InvalidGenesis {
+        reason: String,
+    },
+
+    #[error("validator set in {filename} is not a JSON array")]
+    InvalidValidatorSet {
+        filename: String,
+    },
+
     #[error("ABCI query failed: {err}")]
     QueryFailed {
         err: String,
     },
 
+    #[error("Ledger returned error code {code:#06x}; is the Cosmos app open?")]
+    LedgerApp {
+        code: u16,
+    },
+
+    #[error("key `{name}` is stored on a Ledger device; its private key can't be accessed")]
+    LedgerKey {
+        name: String,
+    },
+
+    #[error("HD path {path} is not supported by Ledger: expecting m/44'/x'/x'/x/x")]
+    UnsupportedHdPath {
+        path: String,
+    },
+
     #[error("tx sender {address} is a contract account")]
     SenderIsContract {
         address: String,
     },
 
+    #[error("benchmark contract failed: {reason}")]
+    BenchContractFailed {
+        reason: String,
+    },
+
     #[error("feature is not supported yet: {feature}")]
     UnsupportedFeature {
         feature: String,
@@ -92,6 +174,12 @@ pub enum DaemonError {
 }
 
 impl DaemonError {
+    pub fn bench_contract_failed(reason: impl Into<String>) -> Self {
+        Self::BenchContractFailed {
+            reason: reason.into(),
+        }
+    }
+
     pub fn file_exists(filename: &Path) -> Result<Self, Self> {
         Ok(Self::FileExists {
             filename: path::stringify(filename)?,
@@ -104,18 +192,79 @@ impl DaemonError {
         })
     }
 
+    pub fn fork_height_mismatch(height: u64, committed: u64) -> Self {
+        Self::ForkHeightMismatch {
+            height,
+            committed,
+        }
+    }
+
+    pub fn invalid_coins(coins: impl Into<String>) -> Self {
+        Self::InvalidCoins {
+            coins: coins.into(),
+        }
+    }
+
+    pub fn invalid_genesis(reason: impl Into<String>) -> Self {
+        Self::InvalidGenesis {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn invalid_param(param: impl Into<String>) -> Self {
+        Self::InvalidParam {
+            param: param.into(),
+        }
+    }
+
+    pub fn invalid_validator_set(filename: &Path) -> Result<Self, Self> {
+        Ok(Self::InvalidValidatorSet {
+            filename: path::stringify(filename)?,
+        })
+    }
+
+    pub fn invalid_multisig(reason: impl Into<String>) -> Self {
+        Self::InvalidMultisig {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn ledger_app(code: u16) -> Self {
+        Self::LedgerApp {
+            code,
+        }
+    }
+
+    pub fn ledger_key(name: impl Into<String>) -> Self {
+        Self::LedgerKey {
+            name: name.into(),
+        }
+    }
+
     pub fn malformed_payload(reason: impl Into<String>) -> Self {
         Self::MalformedPayload {
             reason: reason.into(),
         }
     }
 
+    pub fn missing_argument(name: impl Into<String>) -> Self {
+        Self::MissingArgument {
+            name: name.into(),
+        }
+    }
+
     pub fn sender_is_contract(address: impl Into<String>) -> Self {
         Self::SenderIsContract {
             address: address.into(),
         }
     }
 
+    pub fn not_signer(address: impl Into<String>) -> Self {
+        Self::NotSigner {
+            address: address.into(),
+        }
+    }
+
     pub fn query_failed(err: impl Into<String>) -> Self {
         Self::QueryFailed {
             err: err.into(),
@@ -127,4 +276,10 @@ impl DaemonError {
             feature: feature.into(),
         }
     }
+
+    pub fn unsupported_hd_path(path: impl Into<String>) -> Self {
+        Self::UnsupportedHdPath {
+            path: path.into(),
+        }
+    }
 }
@@ -1,11 +1,12 @@
-use bip32::{Mnemonic, XPrv};
+use bip32::{DerivationPath, Mnemonic, XPrv};
 use cosmwasm_std::Addr;
-use josekit::jwt::JwtPayload;
 use k256::ecdsa::{signature::Signer, Signature, SigningKey, VerifyingKey};
 
-use cw_sdk::{address, Tx, TxBody};
+use colored::Colorize;
+use cw_sdk::{address, proto, LegacySignDoc, PubKeyType, SignerInfo, TxBody, TxSignature};
+use serde::{Deserialize, Serialize};
 
-use crate::DaemonError;
+use crate::{ledger::Ledger, print, DaemonError};
 
 /// Represents a private key that is to be saved in the keyring.
 ///
@@ -16,27 +17,39 @@ use crate::DaemonError;
 pub struct Key {
     /// The key's name
     pub name: String,
-    /// The private key
-    sk: SigningKey,
+    /// What signs with the key
+    signer: KeySigner,
+}
+
+#[derive(Debug, Clone)]
+enum KeySigner {
+    /// The private key, saved in the keyring
+    Local(SigningKey),
+    /// A key on a Ledger device, of which only the pubkey and the path it's
+    /// derived at are saved in the keyring
+    Ledger {
+        pubkey: VerifyingKey,
+        path: DerivationPath,
+    },
 }
 
 impl Key {
-    /// Create a new key instance from a given name and BIP-32 mnemonic phrase
+    /// Create a new key instance from a given name and BIP-39 mnemonic phrase,
+    /// derived at the given BIP-32 path
     pub fn from_mnemonic(
         name: impl Into<String>,
         mnemonic: &Mnemonic,
-        coin_type: u32,
+        path: &DerivationPath,
     ) -> Result<Self, DaemonError> {
         // The `to_seed` function takes a password to generate salt. Here we just use an empty str.
         // For reference, both Terra Station and Keplr use an empty string as well:
         // - https://github.com/terra-money/terra.js/blob/v3.1.7/src/key/MnemonicKey.ts#L79
         // - https://github.com/chainapsis/keplr-wallet/blob/b6062a4d24f3dcb15dda063b1ece7d1fbffdbfc8/packages/crypto/src/mnemonic.ts#L63
         let seed = mnemonic.to_seed("");
-        let path = format!("m/44'/{coin_type}'/0'/0/0");
-        let xprv = XPrv::derive_from_path(&seed, &path.parse()?)?;
+        let xprv = XPrv::derive_from_path(&seed, path)?;
         Ok(Self {
             name: name.into(),
-            sk: xprv.into(),
+            signer: KeySigner::Local(xprv.into()),
         })
     }
 
@@ -48,18 +61,46 @@ impl Key {
         let sk = SigningKey::from_bytes(sk_bytes)?;
         Ok(Self {
             name: name.into(),
-            sk,
+            signer: KeySigner::Local(sk),
         })
     }
 
-    /// Return a reference to the private key
-    pub fn privkey(&self) -> &SigningKey {
-        &self.sk
+    /// Create a new key instance referring to the key derived at the given
+    /// BIP-32 path on the connected Ledger device
+    pub fn from_ledger(
+        name: impl Into<String>,
+        path: &DerivationPath,
+    ) -> Result<Self, DaemonError> {
+        let pubkey = Ledger::connect()?.pubkey(path)?;
+        Ok(Self {
+            name: name.into(),
+            signer: KeySigner::Ledger {
+                pubkey,
+                path: path.clone(),
+            },
+        })
+    }
+
+    /// Return a reference to the private key. Errors if the key is on a Ledger
+    /// device.
+    pub fn privkey(&self) -> Result<&SigningKey, DaemonError> {
+        match &self.signer {
+            KeySigner::Local(sk) => Ok(sk),
+            KeySigner::Ledger {
+                ..
+            } => Err(DaemonError::ledger_key(&self.name)),
+        }
     }
 
     /// Return the pubkey
     pub fn pubkey(&self) -> VerifyingKey {
-        self.sk.verifying_key()
+        match &self.signer {
+            KeySigner::Local(sk) => sk.verifying_key(),
+            KeySigner::Ledger {
+                pubkey,
+                ..
+            } => *pubkey,
+        }
     }
 
     /// Return the key's address bytes, generated according to
@@ -69,50 +110,139 @@ impl Key {
     }
 
     /// Sign an arbitrary byte array. The bytes are SHA-256 hashed before signing
-    pub fn sign_bytes(&self, bytes: &[u8]) -> Signature {
-        self.sk.sign(bytes)
+    pub fn sign_bytes(&self, bytes: &[u8]) -> Result<Signature, DaemonError> {
+        Ok(self.privkey()?.sign(bytes))
     }
 
-    /// Sign a tx body, returns the full tx.
-    pub fn sign_tx(&self, body: &TxBody) -> Result<Tx, DaemonError> {
-        let body_bytes = serde_json::to_vec(body)?;
-        let signature = self.sign_bytes(&body_bytes);
-        Ok(Tx {
-            body: body.clone(),
+    /// Sign the sign doc of a tx body as the given signer, returns the signature.
+    pub fn sign_tx(&self, body: &TxBody, signer: &SignerInfo) -> Result<TxSignature, DaemonError> {
+        if let KeySigner::Ledger {
+            path,
+            ..
+        } = &self.signer
+        {
+            return self.sign_tx_ledger(path, body, signer);
+        }
+
+        let sign_bytes = body.sign_doc(signer)?.to_sign_bytes()?;
+        self.tx_signature(&sign_bytes)
+    }
+
+    /// Sign a tx in SIGN_MODE_DIRECT as the given signer, given the protobuf
+    /// encoding of its body.
+    pub fn sign_tx_direct(
+        &self,
+        body: &TxBody,
+        body_bytes: &[u8],
+        signer: &SignerInfo,
+    ) -> Result<TxSignature, DaemonError> {
+        if let KeySigner::Ledger {
+            path,
+            ..
+        } = &self.signer
+        {
+            return self.sign_tx_ledger(path, body, signer);
+        }
+
+        let sign_bytes =
+            proto::sign_bytes(body_bytes, &body.chain_id, signer.account_number, signer.sequence);
+        self.tx_signature(&sign_bytes)
+    }
+
+    /// Sign a tx on a Ledger device. The device can only sign the legacy
+    /// amino JSON sign doc, regardless of how the tx is encoded, so the
+    /// signature is flagged as such.
+    fn sign_tx_ledger(
+        &self,
+        path: &DerivationPath,
+        body: &TxBody,
+        signer: &SignerInfo,
+    ) -> Result<TxSignature, DaemonError> {
+        let sign_doc =
+            LegacySignDoc::new(body, &body.chain_id, signer.account_number, signer.sequence);
+
+        println!("{}", "🔐 Review and approve the tx on your Ledger device:".bold());
+        print::json(&sign_doc)?;
+
+        let signature = Ledger::connect()?.sign(path, &sign_doc.to_sign_bytes()?)?;
+        Ok(TxSignature {
             pubkey: Some(self.pubkey().to_bytes().to_vec().into()),
+            pubkey_type: PubKeyType::Secp256k1,
             signature: signature.to_vec().into(),
+            multisig: None,
+            legacy_amino_json: true,
+        })
+    }
+
+    fn tx_signature(&self, sign_bytes: &[u8]) -> Result<TxSignature, DaemonError> {
+        Ok(TxSignature {
+            pubkey: Some(self.pubkey().to_bytes().to_vec().into()),
+            pubkey_type: PubKeyType::Secp256k1,
+            signature: self.sign_bytes(sign_bytes)?.to_vec().into(),
+            multisig: None,
+            legacy_amino_json: false,
         })
     }
 }
 
-impl TryFrom<Key> for JwtPayload {
-    type Error = josekit::JoseError;
+/// The representation of a key in the keyring, in JSON. Exactly one of the
+/// private key or the Ledger key is given.
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    name: String,
+    /// Hex-encoded private key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ledger: Option<LedgerKeyFile>,
+}
 
-    fn try_from(key: Key) -> Result<Self, Self::Error> {
-        let sk_str = hex::encode(key.sk.to_bytes().as_slice());
-        let mut payload = JwtPayload::new();
-        payload.set_claim("name", Some(key.name.into()))?;
-        payload.set_claim("sk", Some(sk_str.into()))?;
-        Ok(payload)
-    }
+#[derive(Serialize, Deserialize)]
+struct LedgerKeyFile {
+    /// Hex-encoded compressed pubkey
+    pubkey: String,
+    /// BIP-32 path the key is derived at, e.g. `m/44'/118'/0'/0/0`
+    hd_path: String,
 }
 
-impl TryFrom<JwtPayload> for Key {
-    type Error = DaemonError;
-
-    fn try_from(payload: JwtPayload) -> Result<Self, Self::Error> {
-        let name = payload
-            .claim("name")
-            .ok_or_else(|| DaemonError::malformed_payload("key `name` not found"))?
-            .as_str()
-            .ok_or_else(|| DaemonError::malformed_payload("incorrect JSON value type for `name`"))?;
-        let sk_str = payload
-            .claim("sk")
-            .ok_or_else(|| DaemonError::malformed_payload("key `sk` not found"))?
-            .as_str()
-            .ok_or_else(|| DaemonError::malformed_payload("incorrect JSON value type for `sk`"))?;
-
-        let sk_bytes = hex::decode(sk_str)?;
-        Key::from_privkey_bytes(name, &sk_bytes)
+impl Key {
+    /// Serialize the key into JSON, as it is saved in the keyring
+    pub fn to_json(&self) -> Result<Vec<u8>, DaemonError> {
+        let (sk, ledger) = match &self.signer {
+            KeySigner::Local(sk) => (Some(hex::encode(sk.to_bytes().as_slice())), None),
+            KeySigner::Ledger {
+                pubkey,
+                path,
+            } => {
+                let ledger = LedgerKeyFile {
+                    pubkey: hex::encode(pubkey.to_bytes().as_slice()),
+                    hd_path: path.to_string(),
+                };
+                (None, Some(ledger))
+            },
+        };
+        let file = KeyFile {
+            name: self.name.clone(),
+            sk,
+            ledger,
+        };
+        serde_json::to_vec(&file).map_err(DaemonError::from)
+    }
+
+    /// Deserialize a key from JSON, as it is saved in the keyring
+    pub fn from_json(json: &[u8]) -> Result<Self, DaemonError> {
+        let file: KeyFile = serde_json::from_slice(json)
+            .map_err(|err| DaemonError::malformed_payload(err.to_string()))?;
+        match (file.sk, file.ledger) {
+            (Some(sk), None) => Key::from_privkey_bytes(file.name, &hex::decode(sk)?),
+            (None, Some(ledger)) => Ok(Self {
+                name: file.name,
+                signer: KeySigner::Ledger {
+                    pubkey: VerifyingKey::from_sec1_bytes(&hex::decode(ledger.pubkey)?)?,
+                    path: ledger.hd_path.parse()?,
+                },
+            }),
+            _ => Err(DaemonError::malformed_payload("expecting a private key or a Ledger key")),
+        }
     }
 }
@@ -1,30 +1,88 @@
-//! The logics of this file is largely cloned from Go SDK's file keyring:
-//! https://github.com/cosmos/keyring/blob/master/file.go
+//! The logics of this file is largely cloned from Go SDK's keyring:
+//! https://github.com/cosmos/keyring
+//!
+//! Keys can be saved in one of three backends:
+//!
+//! - `test`: unencrypted files, for local testing only;
+//! - `file`: files encrypted with age, using the keyring's password, which is
+//!   checked against an argon2 hash before any key is decrypted;
+//! - `os`: the operating system's keychain, e.g. macOS Keychain or the Secret
+//!   Service on Linux. An empty file is saved alongside each key, so that keys
+//!   can be listed.
 
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    secrecy::Secret,
+    DecryptError, Decryptor, Encryptor,
+};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use clap::ValueEnum;
 use colored::Colorize;
-use josekit::{jwe, jwt};
+use serde::{Deserialize, Serialize};
 
-use crate::{path, prompt, DaemonError, Key};
+use crate::{path, prompt, ClientConfig, DaemonError, Key};
 
-/// Keyring is a wrapper around a PathBuf, which represents the directory where the encrypted key
-/// files are to be saved.
-pub struct Keyring(PathBuf);
+/// Where the keys are saved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyringBackend {
+    /// Unencrypted files. Anyone who can read them can take control of the
+    /// accounts!
+    Test,
+
+    /// Files encrypted with the keyring's password
+    #[default]
+    File,
+
+    /// The operating system's keychain
+    Os,
+}
+
+/// Keyring is a wrapper around a PathBuf, which represents the directory where the key files are
+/// to be saved, and the backend in which the keys are saved.
+pub struct Keyring {
+    dir: PathBuf,
+    backend: KeyringBackend,
+}
 
 impl Keyring {
     /// Create a new keyring under the given directory
-    pub fn new(dir: PathBuf) -> Result<Self, DaemonError> {
+    pub fn new(dir: PathBuf, backend: KeyringBackend) -> Result<Self, DaemonError> {
         if !dir.exists() {
             fs::create_dir_all(&dir)?;
         }
-        Ok(Self(dir))
+        Ok(Self {
+            dir,
+            backend,
+        })
+    }
+
+    /// Open the keyring under the application home directory, with the given
+    /// backend, or otherwise the one configured in the client config, if it
+    /// exists.
+    pub fn open(home_dir: &Path, backend: Option<KeyringBackend>) -> Result<Self, DaemonError> {
+        let backend = match backend {
+            Some(backend) => backend,
+            None if home_dir.join("config/client.toml").exists() => {
+                ClientConfig::load(home_dir)?.keyring_backend
+            },
+            None => KeyringBackend::default(),
+        };
+        Self::new(home_dir.join("keys"), backend)
     }
 
     /// Return the key directory
     pub fn dir(&self) -> &Path {
-        &self.0
+        &self.dir
     }
 
     /// Return the absolute path of a key file given the key's name.
@@ -49,7 +107,8 @@ impl Keyring {
             let password_hash_bytes = fs::read(&password_hash_path)?;
             let password_hash = String::from_utf8(password_hash_bytes)?;
 
-            if bcrypt::verify(&password, &password_hash)? {
+            let password_hash = PasswordHash::new(&password_hash)?;
+            if Argon2::default().verify_password(password.as_bytes(), &password_hash).is_ok() {
                 Ok(password)
             } else {
                 Err(DaemonError::IncorrectPassword)
@@ -62,10 +121,9 @@ impl Keyring {
                 path::stringify(self.dir())?,
             ))?;
 
-            // Go SDK uses a difficult of 2
-            // We use 4 here which is smallest value allowed by the bcrypt library
-            let password_hash = bcrypt::hash(&password, 4)?;
-            fs::write(&password_hash_path, password_hash)?;
+            let salt = SaltString::generate(&mut OsRng);
+            let password_hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+            fs::write(&password_hash_path, password_hash.to_string())?;
 
             Ok(password)
         }
@@ -78,71 +136,148 @@ impl Keyring {
             return Err(DaemonError::file_exists(&filename)?);
         }
 
-        // header
-        // these are copied from the tutorial. not sure if i'm using the correct values
-        let mut header = jwe::JweHeader::new();
-        header.set_token_type("JWT");
-        header.set_algorithm("PBES2-HS256+A128KW");
-        header.set_content_encryption("A128CBC-HS256");
-
-        // cast key into JWT payload
-        let payload = key.clone().try_into()?;
-
-        // encrypt { header, payload } into token
-        let password = self.unlock()?;
-        let encrypter = jwe::PBES2_HS256_A128KW.encrypter_from_bytes(password)?;
-        let token = jwt::encode_with_encrypter(&payload, &header, &encrypter)?;
+        let contents = match self.backend {
+            KeyringBackend::Test => key.to_json()?,
+            KeyringBackend::File => encrypt(&key.to_json()?, &self.unlock()?)?,
+            KeyringBackend::Os => {
+                let json = String::from_utf8(key.to_json()?)?;
+                self.os_entry(&key.name)?.set_password(&json)?;
+                vec![]
+            },
+        };
 
-        // save the token to file
-        fs::write(filename, token)?;
+        // save the key to file
+        fs::write(filename, contents)?;
 
         Ok(())
     }
 
     /// Read binary data stored in the keyring with the given name
     pub fn get(&self, name: &str) -> Result<Key, DaemonError> {
-        // load the file
-        let token = {
-            let filename = self.filename(name);
-            if !filename.exists() {
-                return Err(DaemonError::file_not_found(&filename)?);
-            }
-            fs::read(&filename)?
-        };
-
-        // decrypt { header, payload } from token
-        let password = self.unlock()?;
-        let decrypter = jwe::PBES2_HS256_A128KW.decrypter_from_bytes(password.as_bytes())?;
-        let (payload, _) = jwt::decode_with_decrypter(token, &decrypter)?;
+        let filename = self.filename(name);
+        if !filename.exists() {
+            return Err(DaemonError::file_not_found(&filename)?);
+        }
 
-        // recover key from payload
-        payload.try_into().map_err(DaemonError::from)
+        let password = match self.backend {
+            KeyringBackend::File => Some(self.unlock()?),
+            _ => None,
+        };
+        self.load(name, password.as_deref())
     }
 
     /// Read binary data of all keys stored in the keyring
     pub fn list(&self) -> Result<Vec<Key>, DaemonError> {
-        let password = self.unlock()?;
-        let decrypter = jwe::PBES2_HS256_A128KW.decrypter_from_bytes(password.as_bytes())?;
-
-        self.dir()
-            .read_dir()?
-            .map(|entry| {
-                let entry = entry?;
-                let token = fs::read(entry.path())?;
-                let (payload, _) = jwt::decode_with_decrypter(token, &decrypter)?;
-                payload.try_into().map_err(DaemonError::from)
-            })
-            .filter(|res| res.is_ok())
-            .collect()
+        let password = match self.backend {
+            KeyringBackend::File => Some(self.unlock()?),
+            _ => None,
+        };
+
+        let mut keys = vec![];
+        for entry in self.dir().read_dir()? {
+            let path = entry?.path();
+            if path.extension().map_or(true, |ext| ext != "key") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if let Ok(key) = self.load(name, password.as_deref()) {
+                keys.push(key);
+            }
+        }
+
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(keys)
+    }
+
+    /// Return a key in the keyring, encrypted with a passphrase of the user's
+    /// choice instead of the keyring's password, so that it can be imported
+    /// into another keyring.
+    pub fn export(&self, name: &str) -> Result<String, DaemonError> {
+        let key = self.get(name)?;
+        let message = "🔑 Enter a passphrase to encrypt the exported key".bold();
+        let passphrase = prompt::password(format!("{message}"))?;
+        let armored = encrypt(&key.to_json()?, &passphrase)?;
+        String::from_utf8(armored).map_err(DaemonError::from)
+    }
+
+    /// Save a key exported from another keyring under the given name, and
+    /// return it.
+    pub fn import(&self, name: &str, armored: &[u8]) -> Result<Key, DaemonError> {
+        let message = "🔑 Enter the passphrase to decrypt the key".bold();
+        let passphrase = prompt::password(format!("{message}"))?;
+        let mut key = Key::from_json(&decrypt(armored, &passphrase)?)?;
+        key.name = name.into();
+        self.set(&key)?;
+        Ok(key)
     }
 
     /// Delete a key
     pub fn delete(&self, name: &str) -> Result<(), DaemonError> {
         let filename = self.filename(name);
-        if filename.exists() {
-            fs::remove_file(filename).map_err(DaemonError::from)
-        } else {
-            Err(DaemonError::file_not_found(&filename)?)
+        if !filename.exists() {
+            return Err(DaemonError::file_not_found(&filename)?);
         }
+
+        if self.backend == KeyringBackend::Os {
+            self.os_entry(name)?.delete_password()?;
+        }
+        fs::remove_file(filename).map_err(DaemonError::from)
+    }
+
+    /// Read a key from the backend. The password is only needed for the file
+    /// backend.
+    fn load(&self, name: &str, password: Option<&str>) -> Result<Key, DaemonError> {
+        let json = match (self.backend, password) {
+            (KeyringBackend::Test, _) => fs::read(self.filename(name))?,
+            (KeyringBackend::File, Some(password)) => {
+                decrypt(&fs::read(self.filename(name))?, password)?
+            },
+            (KeyringBackend::File, None) => return Err(DaemonError::IncorrectPassword),
+            (KeyringBackend::Os, _) => self.os_entry(name)?.get_password()?.into_bytes(),
+        };
+        Key::from_json(&json)
     }
+
+    /// The entry of a key in the operating system's keychain. Entries are
+    /// scoped to the keyring's directory, so that keys of the same name in
+    /// different application homes don't collide.
+    fn os_entry(&self, name: &str) -> Result<::keyring::Entry, DaemonError> {
+        let service = format!("cwd:{}", path::stringify(self.dir())?);
+        ::keyring::Entry::new(&service, name).map_err(DaemonError::from)
+    }
+}
+
+/// Encrypt data with a password, into ASCII-armored age format.
+fn encrypt(plaintext: &[u8], password: &str) -> Result<Vec<u8>, DaemonError> {
+    let encryptor = Encryptor::with_user_passphrase(Secret::new(password.to_owned()));
+
+    let mut armored = vec![];
+    let mut writer =
+        encryptor.wrap_output(ArmoredWriter::wrap_output(&mut armored, Format::AsciiArmor)?)?;
+    writer.write_all(plaintext)?;
+    writer.finish().and_then(|armor| armor.finish())?;
+
+    Ok(armored)
+}
+
+/// Decrypt data encrypted with a password, in either binary or ASCII-armored
+/// age format.
+fn decrypt(ciphertext: &[u8], password: &str) -> Result<Vec<u8>, DaemonError> {
+    let Decryptor::Passphrase(decryptor) = Decryptor::new(ArmoredReader::new(ciphertext))? else {
+        return Err(DaemonError::malformed_payload("key is not encrypted with a passphrase"));
+    };
+
+    let mut reader = decryptor
+        .decrypt(&Secret::new(password.to_owned()), None)
+        .map_err(|err| match err {
+            DecryptError::DecryptionFailed => DaemonError::IncorrectPassword,
+            err => err.into(),
+        })?;
+
+    let mut plaintext = vec![];
+    reader.read_to_end(&mut plaintext)?;
+
+    Ok(plaintext)
 }
@@ -0,0 +1,112 @@
+//! Signing with Ledger hardware wallets, through the Cosmos app:
+//! https://github.com/cosmos/ledger-cosmos/blob/main/docs/APDUSPEC.md
+//!
+//! The private key never leaves the device. The app only signs sign docs in
+//! the shape of Cosmos SDK's SIGN_MODE_LEGACY_AMINO_JSON, which it displays
+//! for the user to review before approving.
+
+use bip32::{ChildNumber, DerivationPath};
+use k256::ecdsa::{Signature, VerifyingKey};
+use ledger_transport::APDUCommand;
+use ledger_transport_hid::{hidapi::HidApi, LedgerHIDError, TransportNativeHID};
+
+use crate::DaemonError;
+
+const CLA: u8 = 0x55;
+const INS_SIGN_SECP256K1: u8 = 0x02;
+const INS_GET_ADDR_SECP256K1: u8 = 0x04;
+
+/// Payload descriptors of the chunks of a message to be signed
+const P1_INIT: u8 = 0;
+const P1_ADD: u8 = 1;
+const P1_LAST: u8 = 2;
+
+/// Maximum size of the data of each chunk
+const CHUNK_SIZE: usize = 250;
+
+/// Return code of a successful command
+const RETCODE_OK: u16 = 0x9000;
+
+/// The bech32 prefix of the addresses that the app derives alongside pubkeys.
+/// The address isn't used, but the app requires a prefix.
+const HRP: &str = "cosmos";
+
+/// A connection to a Ledger device over USB
+pub struct Ledger {
+    transport: TransportNativeHID,
+}
+
+impl Ledger {
+    /// Connect to the first Ledger device found
+    pub fn connect() -> Result<Self, DaemonError> {
+        let api = HidApi::new().map_err(LedgerHIDError::from)?;
+        Ok(Self {
+            transport: TransportNativeHID::new(&api)?,
+        })
+    }
+
+    /// Return the pubkey derived at the given path
+    pub fn pubkey(&self, path: &DerivationPath) -> Result<VerifyingKey, DaemonError> {
+        let mut data = vec![HRP.len() as u8];
+        data.extend(HRP.as_bytes());
+        data.extend(serialize_path(path)?);
+
+        // the response is the 33-byte compressed pubkey followed by the address
+        let response = self.exchange(INS_GET_ADDR_SECP256K1, 0, data)?;
+        let pubkey = response
+            .get(..33)
+            .ok_or_else(|| DaemonError::malformed_payload("Ledger returned a truncated pubkey"))?;
+        VerifyingKey::from_sec1_bytes(pubkey).map_err(DaemonError::from)
+    }
+
+    /// Sign the given sign bytes with the key derived at the given path, once
+    /// the user approves on the device.
+    pub fn sign(&self, path: &DerivationPath, sign_bytes: &[u8]) -> Result<Signature, DaemonError> {
+        self.exchange(INS_SIGN_SECP256K1, P1_INIT, serialize_path(path)?)?;
+
+        let chunks = sign_bytes.chunks(CHUNK_SIZE).collect::<Vec<_>>();
+        let mut response = vec![];
+        for (i, chunk) in chunks.iter().enumerate() {
+            let p1 = if i == chunks.len() - 1 {
+                P1_LAST
+            } else {
+                P1_ADD
+            };
+            response = self.exchange(INS_SIGN_SECP256K1, p1, chunk.to_vec())?;
+        }
+
+        // the device returns a DER-encoded signature, which may have a high S
+        let signature = Signature::from_der(&response)?;
+        Ok(signature.normalize_s().unwrap_or(signature))
+    }
+
+    fn exchange(&self, ins: u8, p1: u8, data: Vec<u8>) -> Result<Vec<u8>, DaemonError> {
+        let answer = self.transport.exchange(&APDUCommand {
+            cla: CLA,
+            ins,
+            p1,
+            p2: 0,
+            data,
+        })?;
+
+        if answer.retcode() != RETCODE_OK {
+            return Err(DaemonError::ledger_app(answer.retcode()));
+        }
+
+        Ok(answer.data().to_vec())
+    }
+}
+
+/// Serialize a BIP-44 path as the app expects it: five little-endian u32s,
+/// i.e. `m/44'/{coin_type}'/{account}'/{change}/{index}`.
+fn serialize_path(path: &DerivationPath) -> Result<Vec<u8>, DaemonError> {
+    let children = path.iter().collect::<Vec<ChildNumber>>();
+    let is_bip44 = children.len() == 5
+        && children[..3].iter().all(ChildNumber::is_hardened)
+        && !children[3..].iter().any(ChildNumber::is_hardened);
+    if !is_bip44 {
+        return Err(DaemonError::unsupported_hd_path(path.to_string()));
+    }
+
+    Ok(children.into_iter().flat_map(|child| u32::from(child).to_le_bytes()).collect())
+}
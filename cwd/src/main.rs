@@ -4,6 +4,7 @@ mod config;
 mod error;
 mod key;
 mod keyring;
+mod ledger;
 mod path;
 mod print;
 mod prompt;
@@ -12,13 +13,14 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 use tracing::error;
-use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::{filter::LevelFilter, fmt::format::FmtSpan};
 
 use crate::{
     commands::{
-        DebugCmd, GenesisCmd, InitCmd, KeysCmd, QueryCmd, ResetCmd, StartCmd, TendermintCmd, TxCmd,
+        ContractStateCmd, DebugCmd, ExportCmd, ForkCmd, GasBenchCmd, GenesisCmd, InitCmd, KeysCmd,
+        PruneCmd, QueryCmd, ResetCmd, RollbackCmd, StartCmd, TendermintCmd, TxCmd,
     },
-    config::{AppConfig, ClientConfig},
+    config::{AbciVersion, AppConfig, ClientConfig},
     error::DaemonError,
     key::Key,
     keyring::Keyring,
@@ -41,9 +43,22 @@ pub struct Cli {
 
 #[derive(Subcommand)]
 pub enum Command {
+    /// Export or import the raw state of a single contract
+    ContractState(ContractStateCmd),
+
     /// Helper command useful for developers
     Debug(DebugCmd),
 
+    /// Export the committed application state as a genesis state
+    Export(ExportCmd),
+
+    /// Create a new genesis from the committed application state, with overrides
+    Fork(ForkCmd),
+
+    /// Measure the cost of storage ops, signature verification and wasm
+    /// execution on this machine, and print the gas costs they suggest
+    GasBench(GasBenchCmd),
+
     /// Utilities for preparing the genesis state
     Genesis(GenesisCmd),
 
@@ -53,10 +68,16 @@ pub enum Command {
     /// Manage private keys
     Keys(KeysCmd),
 
+    /// Compact the application database, deleting the undo checkpoint of the last commit
+    Prune(PruneCmd),
+
     /// Query the application state
     #[command(alias = "q")]
     Query(QueryCmd),
 
+    /// Revert the application state to before the last committed block
+    Rollback(RollbackCmd),
+
     /// Start the ABCI server
     Start(StartCmd),
 
@@ -85,15 +106,23 @@ async fn run() -> Result<(), DaemonError> {
     } else {
         LevelFilter::INFO
     };
-    tracing_subscriber::fmt().with_max_level(log_level).init();
+    // log each block, tx and message span as it closes, along with the gas it
+    // used and how long it took
+    tracing_subscriber::fmt().with_max_level(log_level).with_span_events(FmtSpan::CLOSE).init();
 
     match cli.command {
+        Command::ContractState(cmd) => cmd.run(&home_dir).await,
         Command::Debug(cmd) => cmd.run(),
-        Command::Genesis(cmd) => cmd.run(),
+        Command::Export(cmd) => cmd.run(&home_dir),
+        Command::Fork(cmd) => cmd.run(&home_dir),
+        Command::GasBench(cmd) => cmd.run(),
+        Command::Genesis(cmd) => cmd.run(&home_dir),
         Command::Init(cmd) => cmd.run(&home_dir),
         Command::Keys(cmd) => cmd.run(&home_dir),
+        Command::Prune(cmd) => cmd.run(&home_dir),
         Command::Query(cmd) => cmd.run(&home_dir).await,
-        Command::Start(cmd) => cmd.run(&home_dir),
+        Command::Rollback(cmd) => cmd.run(&home_dir),
+        Command::Start(cmd) => cmd.run(&home_dir).await,
         Command::Tendermint(cmd) => cmd.run(&home_dir).await,
         Command::Tx(cmd) => cmd.run(&home_dir).await,
         Command::UnsafeResetAll(cmd) => cmd.run(&home_dir),
@@ -1,4 +1,7 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::DaemonError;
 
@@ -24,3 +27,18 @@ pub fn stringify(path: &Path) -> Result<String, DaemonError> {
         .into_string()
         .map_err(|_| DaemonError::PathFailed)
 }
+
+/// Return the total size in bytes of the files under a directory, recursively.
+pub fn dir_size(dir: &Path) -> Result<u64, DaemonError> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
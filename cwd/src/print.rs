@@ -1,8 +1,103 @@
+use clap::{Args, ValueEnum};
 use cosmwasm_std::Addr;
 use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Serializer, Value};
 
 use crate::{DaemonError, Key};
 
+/// How responses are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON, indented by `--indent` spaces
+    #[default]
+    Json,
+
+    /// YAML
+    Yaml,
+
+    /// One `path: value` line for each field, where the path joins the keys
+    /// and array indexes leading to the field with dots
+    Text,
+}
+
+/// Output options shared by commands that print responses
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t)]
+    output: OutputFormat,
+
+    /// Number of spaces to indent JSON output by; zero for compact output
+    #[arg(long, global = true, default_value_t = 2)]
+    indent: usize,
+}
+
+impl OutputArgs {
+    /// Print a serializable object in the chosen format
+    pub fn print(&self, data: impl Serialize) -> Result<(), DaemonError> {
+        match self.output {
+            OutputFormat::Json => {
+                let data_str = if self.indent == 0 {
+                    serde_json::to_string(&data)?
+                } else {
+                    let indent = " ".repeat(self.indent);
+                    let mut bytes = vec![];
+                    let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+                    data.serialize(&mut Serializer::with_formatter(&mut bytes, formatter))?;
+                    String::from_utf8(bytes)?
+                };
+                println!("{data_str}");
+            },
+            OutputFormat::Yaml => {
+                print!("{}", serde_yaml::to_string(&data)?);
+            },
+            OutputFormat::Text => {
+                let mut lines = vec![];
+                text_lines(&serde_json::to_value(data)?, "", &mut lines);
+                for line in lines {
+                    println!("{line}");
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Flatten a JSON value into `path: value` lines, one for each scalar, empty
+/// object or empty array it contains. Strings are printed without quotes.
+fn text_lines(value: &Value, path: &str, lines: &mut Vec<String>) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    let leaf = match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                text_lines(value, &join(key), lines);
+            }
+            return;
+        },
+        Value::Array(values) if !values.is_empty() => {
+            for (index, value) in values.iter().enumerate() {
+                text_lines(value, &join(&index.to_string()), lines);
+            }
+            return;
+        },
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    };
+
+    if path.is_empty() {
+        lines.push(leaf);
+    } else {
+        lines.push(format!("{path}: {leaf}"));
+    }
+}
+
 /// Print a BIP-38 mnemonic phrase
 pub fn mnemonic(phrase: &str) {
     let words = phrase.split(' ').collect::<Vec<_>>();
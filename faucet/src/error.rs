@@ -0,0 +1,86 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FaucetError {
+    #[error(transparent)]
+    Address(#[from] cw_sdk::address::AddressError),
+
+    #[error(transparent)]
+    Client(#[from] cw_sdk_client::ClientError),
+
+    #[error(transparent)]
+    FromHex(#[from] hex::FromHexError),
+
+    #[error(transparent)]
+    Server(#[from] axum::Error),
+
+    #[error("{var} must be set to the faucet's private key in hex encoding")]
+    MissingPrivateKey {
+        var: String,
+    },
+
+    #[error("invalid coins `{coins}`: expecting e.g. `100uatom,50ucosm`")]
+    InvalidCoins {
+        coins: String,
+    },
+
+    #[error("too many requests; try again in {retry_after} seconds")]
+    RateLimited {
+        retry_after: u64,
+    },
+
+    #[error("tx {hash} failed: {log}")]
+    TxFailed {
+        hash: String,
+        log: String,
+    },
+}
+
+impl FaucetError {
+    pub fn missing_private_key(var: impl Into<String>) -> Self {
+        Self::MissingPrivateKey {
+            var: var.into(),
+        }
+    }
+
+    pub fn invalid_coins(coins: impl Into<String>) -> Self {
+        Self::InvalidCoins {
+            coins: coins.into(),
+        }
+    }
+
+    pub fn rate_limited(retry_after: u64) -> Self {
+        Self::RateLimited {
+            retry_after,
+        }
+    }
+
+    pub fn tx_failed(hash: impl Into<String>, log: impl Into<String>) -> Self {
+        Self::TxFailed {
+            hash: hash.into(),
+            log: log.into(),
+        }
+    }
+}
+
+impl IntoResponse for FaucetError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            FaucetError::Address(_) => StatusCode::BAD_REQUEST,
+            FaucetError::RateLimited {
+                ..
+            } => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = json!({
+            "error": self.to_string(),
+        });
+        (status, Json(body)).into_response()
+    }
+}
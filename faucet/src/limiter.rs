@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Tracks when each address or IP last requested coins, so that each may only
+/// request once per cooldown period.
+pub struct RateLimiter {
+    cooldown: Duration,
+    last_requests: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last_requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// Record a request by all of the given keys, unless any of them has
+    /// requested within the cooldown period, in which case return how long it
+    /// has to wait.
+    pub fn check(&self, keys: &[String], now: Instant) -> Result<(), Duration> {
+        let mut last_requests = self.last_requests.lock().unwrap();
+
+        // forget requests whose cooldown has passed, so that the map doesn't
+        // grow indefinitely
+        last_requests.retain(|_, requested_at| now < *requested_at + self.cooldown);
+
+        let wait = keys
+            .iter()
+            .filter_map(|key| last_requests.get(key))
+            .map(|requested_at| *requested_at + self.cooldown - now)
+            .max();
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+
+        for key in keys {
+            last_requests.insert(key.clone(), now);
+        }
+        Ok(())
+    }
+
+    /// Forget a request by the given keys, e.g. because sending the coins
+    /// failed, so that they may request again right away.
+    pub fn forget(&self, keys: &[String]) {
+        let mut last_requests = self.last_requests.lock().unwrap();
+        for key in keys {
+            last_requests.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiting() {
+        let limiter = RateLimiter::new(Duration::from_secs(60));
+        let now = Instant::now();
+        let alice = vec!["alice".to_string(), "1.1.1.1".to_string()];
+        let bob = vec!["bob".to_string(), "2.2.2.2".to_string()];
+        let bob_same_ip = vec!["bob".to_string(), "1.1.1.1".to_string()];
+
+        limiter.check(&alice, now).unwrap();
+        limiter.check(&bob, now + Duration::from_secs(10)).unwrap();
+
+        // alice can't request again before the cooldown has passed
+        let wait = limiter.check(&alice, now + Duration::from_secs(20)).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(40));
+
+        // neither can bob, from alice's IP, as long as either is cooling down
+        let wait = limiter.check(&bob_same_ip, now + Duration::from_secs(30)).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(40));
+
+        // a failed request is forgotten
+        limiter.forget(&bob);
+        limiter.check(&bob, now + Duration::from_secs(30)).unwrap();
+
+        // once the cooldown has passed, alice can request again
+        limiter.check(&alice, now + Duration::from_secs(60)).unwrap();
+    }
+}
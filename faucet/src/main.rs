@@ -0,0 +1,207 @@
+mod error;
+mod limiter;
+
+use std::{
+    env,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    routing::get,
+    Json, Router, Server,
+};
+use clap::Parser;
+use cosmwasm_std::{Addr, Coin, Uint128};
+use cw_sdk::{address, SdkMsg};
+use cw_sdk_client::{Client, SecpSigner, Signer, TxBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+use crate::{error::FaucetError, limiter::RateLimiter};
+
+/// Environment variable holding the faucet's secp256k1 private key, in hex
+/// encoding. It's not taken as an argument, so that it doesn't show up in the
+/// process list or shell history.
+const PRIVATE_KEY_VAR: &str = "FAUCET_PRIVATE_KEY";
+
+#[derive(Parser)]
+#[command(author, version, about)]
+pub struct Cli {
+    /// Chain id of the network
+    #[arg(long)]
+    chain_id: String,
+
+    /// CometBFT RPC of the node to query and broadcast txs to
+    #[arg(long, default_value = "http://127.0.0.1:26657")]
+    node: String,
+
+    /// TCP address to serve the faucet at
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    listen: SocketAddr,
+
+    /// Coins sent per request, separated by commas, e.g. `1000000ucosm,100uatom`
+    #[arg(long)]
+    amount: String,
+
+    /// Label or address of the bank contract
+    #[arg(long, default_value = "bank")]
+    bank: String,
+
+    /// Coins paid as the fee of each tx, separated by commas
+    #[arg(long, default_value = "")]
+    fee: String,
+
+    /// Gas limit of each tx
+    #[arg(long, default_value_t = 1_000_000)]
+    gas_limit: u64,
+
+    /// Seconds before the same address, or the same IP, may request again
+    #[arg(long, default_value_t = 86400)]
+    cooldown: u64,
+}
+
+struct Faucet {
+    client: Client,
+    signer: SecpSigner,
+    address: Addr,
+    amount: Vec<Coin>,
+    bank: String,
+    fee: Vec<Coin>,
+    gas_limit: u64,
+    limiter: RateLimiter,
+
+    /// Txs are sent one at a time, as each one must be signed with the sequence
+    /// following the previous one's
+    send_lock: Mutex<()>,
+}
+
+#[derive(Deserialize)]
+struct FaucetRequest {
+    address: String,
+}
+
+impl Faucet {
+    /// Send the configured amount to the recipient, and wait for the tx to be
+    /// included in a block. Return the tx hash and height.
+    async fn send(&self, recipient: &Addr) -> Result<Value, FaucetError> {
+        let _guard = self.send_lock.lock().await;
+
+        let tx = TxBuilder::new()
+            .msg(SdkMsg::Execute {
+                contract: self.bank.clone(),
+                msg: json!({
+                    "send": {
+                        "to": recipient,
+                        "coins": self.amount,
+                    },
+                }),
+                funds: vec![],
+            })
+            .fee(self.fee.clone(), self.gas_limit);
+        let response = self.client.sign_and_broadcast(tx, &self.signer).await?;
+        if response.code != 0 {
+            return Err(FaucetError::tx_failed(response.hash, response.log));
+        }
+
+        info!(%recipient, hash = response.hash, "Sent coins");
+        Ok(json!({
+            "hash": response.hash,
+            "height": response.height,
+        }))
+    }
+}
+
+async fn info(State(faucet): State<Arc<Faucet>>) -> Json<Value> {
+    Json(json!({
+        "address": faucet.address,
+        "amount": faucet.amount,
+        "cooldown": faucet.limiter.cooldown().as_secs(),
+    }))
+}
+
+async fn request_coins(
+    State(faucet): State<Arc<Faucet>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Json(request): Json<FaucetRequest>,
+) -> Result<Json<Value>, FaucetError> {
+    let recipient = address::validate(&request.address)?;
+
+    let keys = [recipient.to_string(), peer.ip().to_string()];
+    faucet
+        .limiter
+        .check(&keys, Instant::now())
+        .map_err(|wait| FaucetError::rate_limited(wait.as_secs() + 1))?;
+
+    match faucet.send(&recipient).await {
+        Ok(response) => Ok(Json(response)),
+        Err(err) => {
+            // let the recipient try again, as it hasn't received anything
+            error!(%recipient, "Failed to send coins: {err}");
+            faucet.limiter.forget(&keys);
+            Err(err)
+        },
+    }
+}
+
+/// Parse coins separated by commas, e.g. `100uatom,50ucosm`
+fn parse_coins(coins_str: &str) -> Result<Vec<Coin>, FaucetError> {
+    coins_str
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|coin_str| {
+            let split_at = coin_str.find(|c: char| !c.is_ascii_digit()).unwrap_or(coin_str.len());
+            let (amount, denom) = coin_str.split_at(split_at);
+            match amount.parse::<u128>() {
+                Ok(amount) if !denom.is_empty() => Ok(Coin {
+                    denom: denom.into(),
+                    amount: Uint128::new(amount),
+                }),
+                _ => Err(FaucetError::invalid_coins(coins_str)),
+            }
+        })
+        .collect()
+}
+
+async fn run() -> Result<(), FaucetError> {
+    let cli = Cli::parse();
+
+    tracing_subscriber::fmt().init();
+
+    let sk_hex = env::var(PRIVATE_KEY_VAR)
+        .map_err(|_| FaucetError::missing_private_key(PRIVATE_KEY_VAR))?;
+    let signer = SecpSigner::from_bytes(&hex::decode(sk_hex.trim())?)?;
+    let faucet = Faucet {
+        client: Client::new(&cli.node, cli.chain_id)?,
+        address: signer.address()?,
+        signer,
+        amount: parse_coins(&cli.amount)?,
+        bank: cli.bank,
+        fee: parse_coins(&cli.fee)?,
+        gas_limit: cli.gas_limit,
+        limiter: RateLimiter::new(Duration::from_secs(cli.cooldown)),
+        send_lock: Mutex::new(()),
+    };
+    info!(address = %faucet.address, "Serving faucet at {}", cli.listen);
+
+    let router =
+        Router::new().route("/", get(info).post(request_coins)).with_state(Arc::new(faucet));
+    Server::bind(&cli.listen)
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .map_err(axum::Error::new)?;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(err) = run().await {
+        error!("{err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,163 @@
+use cosmwasm_std::Coin;
+use cw_sdk::{Fee, SdkMsg, SignMode, SignerInfo, Tx, TxBody, TxMsg};
+
+use crate::{ClientError, Signer};
+
+/// Builds a tx sent by a single signer. Unless set here, the chain id is the
+/// client's, and the account number and sequence are queried from the node.
+#[derive(Debug, Clone, Default)]
+pub struct TxBuilder {
+    pub(crate) msgs: Vec<SdkMsg>,
+    pub(crate) fee: Fee,
+    pub(crate) timeout_height: Option<u64>,
+    pub(crate) idempotency_key: Option<String>,
+    pub(crate) chain_id: Option<String>,
+    pub(crate) account_number: Option<u64>,
+    pub(crate) sequence: Option<u64>,
+}
+
+impl TxBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a message to be executed, after those added before
+    pub fn msg(mut self, msg: SdkMsg) -> Self {
+        self.msgs.push(msg);
+        self
+    }
+
+    /// Append multiple messages to be executed, in order
+    pub fn msgs(mut self, msgs: impl IntoIterator<Item = SdkMsg>) -> Self {
+        self.msgs.extend(msgs);
+        self
+    }
+
+    /// Set the fee paid by the signer, and the maximum amount of gas the tx may
+    /// consume
+    pub fn fee(mut self, amount: Vec<Coin>, gas_limit: u64) -> Self {
+        self.fee.amount = amount;
+        self.fee.gas_limit = gas_limit;
+        self
+    }
+
+    /// Have the fee paid by a granter who has granted the signer an allowance
+    pub fn fee_granter(mut self, granter: impl Into<String>) -> Self {
+        self.fee.granter = Some(granter.into());
+        self
+    }
+
+    /// Reject the tx if it's not included in a block by the given height
+    pub fn timeout_height(mut self, height: u64) -> Self {
+        self.timeout_height = Some(height);
+        self
+    }
+
+    /// Set the key by which retries of the tx are deduplicated. There's no
+    /// memo in cw-sdk txs; this is what Cosmos SDK tooling, e.g. Ledger
+    /// devices, displays as the memo.
+    pub fn memo(mut self, idempotency_key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(idempotency_key.into());
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = Some(chain_id.into());
+        self
+    }
+
+    /// Use the given account number and sequence instead of querying them, e.g.
+    /// to sign offline, or to send multiple txs in the same block
+    pub fn signer_info(mut self, account_number: u64, sequence: u64) -> Self {
+        self.account_number = Some(account_number);
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Build the tx body as sent by the given signer, with all messages sent
+    /// by it
+    pub fn build(self, sender: &str, chain_id: &str, account_number: u64, sequence: u64) -> TxBody {
+        TxBody {
+            chain_id: chain_id.into(),
+            signers: vec![SignerInfo {
+                address: sender.into(),
+                account_number,
+                sequence,
+            }],
+            msgs: self
+                .msgs
+                .into_iter()
+                .map(|msg| TxMsg {
+                    sender: sender.into(),
+                    msg,
+                })
+                .collect(),
+            fee: self.fee,
+            timeout_height: self.timeout_height,
+            idempotency_key: self.idempotency_key,
+        }
+    }
+
+    /// Build the tx body and sign it, to be encoded in canonical JSON
+    pub fn sign(
+        self,
+        signer: &impl Signer,
+        chain_id: &str,
+        account_number: u64,
+        sequence: u64,
+    ) -> Result<Tx, ClientError> {
+        let sender = signer.address()?;
+        let body = self.build(sender.as_str(), chain_id, account_number, sequence);
+        let signature = signer.sign_tx(&SignMode::Json, &body, &body.signers[0])?;
+        Ok(Tx {
+            body,
+            signatures: vec![signature],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::coins;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn building_tx() {
+        let body = TxBuilder::new()
+            .msg(SdkMsg::Execute {
+                contract: "bank".into(),
+                msg: json!({}),
+                funds: vec![],
+            })
+            .fee(coins(1000, "ucosm"), 200_000)
+            .fee_granter("sponsor")
+            .memo("retry-1")
+            .build("alice", "dev-1", 12, 34);
+
+        assert_eq!(
+            body,
+            TxBody {
+                idempotency_key: Some("retry-1".into()),
+                fee: Fee {
+                    amount: coins(1000, "ucosm"),
+                    gas_limit: 200_000,
+                    payer: None,
+                    granter: Some("sponsor".into()),
+                },
+                ..TxBody::single(
+                    "alice",
+                    "dev-1",
+                    12,
+                    34,
+                    vec![SdkMsg::Execute {
+                        contract: "bank".into(),
+                        msg: json!({}),
+                        funds: vec![],
+                    }],
+                )
+            },
+        );
+    }
+}
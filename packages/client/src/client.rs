@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use cosmwasm_std::Addr;
+use cw_sdk::{
+    canonical::to_canonical_vec, hash::sha256, Account, AccountResponse, SdkQuery, Tx, TxResponse,
+};
+use serde::de::DeserializeOwned;
+use tendermint_rpc::{Client as _, HttpClient};
+use tokio::time::{sleep, Instant};
+
+use crate::{ClientError, Signer, TxBuilder};
+
+/// How often the node is polled for the result of a broadcasted tx, by default
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long to wait for a broadcasted tx to be included in a block, by default
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Queries a node and broadcasts txs to it through the CometBFT RPC.
+pub struct Client {
+    pub(crate) rpc_url: String,
+    rpc: HttpClient,
+    chain_id: String,
+    poll_interval: Duration,
+    timeout: Duration,
+}
+
+impl Client {
+    /// Connect to the CometBFT RPC at the given URL, e.g.
+    /// `http://127.0.0.1:26657`, of a node of the given chain.
+    pub fn new(rpc_url: &str, chain_id: impl Into<String>) -> Result<Self, ClientError> {
+        Ok(Self {
+            rpc_url: rpc_url.into(),
+            rpc: HttpClient::new(rpc_url)?,
+            chain_id: chain_id.into(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    pub fn set_poll_interval(&mut self, poll_interval: Duration) {
+        self.poll_interval = poll_interval;
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Send a query to the state machine and deserialize the JSON response.
+    pub async fn query<R: DeserializeOwned>(&self, query: &SdkQuery) -> Result<R, ClientError> {
+        let query_bytes = serde_json::to_vec(query)?;
+        let result = self.rpc.abci_query(Some("app".into()), query_bytes, None, false).await?;
+        if result.code.is_err() {
+            return Err(ClientError::query_failed(result.log));
+        }
+        serde_json::from_slice(&result.value).map_err(ClientError::from)
+    }
+
+    /// Return the account number and the sequence of the next tx of the given
+    /// address.
+    ///
+    /// If the account doesn't exist yet, it is assigned the next account number
+    /// when its first tx is executed, which has a sequence of one.
+    pub async fn signer_info(&self, address: &Addr) -> Result<(u64, u64), ClientError> {
+        match self.query_account(address.as_str()).await {
+            Ok(AccountResponse {
+                account:
+                    Account::Base {
+                        account_number,
+                        sequence,
+                        ..
+                    }
+                    | Account::Multisig {
+                        account_number,
+                        sequence,
+                        ..
+                    },
+                ..
+            }) => Ok((account_number, sequence + 1)),
+            Ok(AccountResponse {
+                account: Account::Contract {
+                    ..
+                },
+                ..
+            }) => Err(ClientError::sender_is_contract(address)),
+            // TODO: match the error code once queries report them
+            Err(ClientError::QueryFailed {
+                log,
+            }) if log.contains("not found") => {
+                let info = self.query_info().await?;
+                Ok((info.account_count, 1))
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Build the tx and sign it, querying the signer's account number and
+    /// sequence unless they are set in the builder.
+    pub async fn sign(&self, builder: TxBuilder, signer: &impl Signer) -> Result<Tx, ClientError> {
+        let (account_number, sequence) = match (builder.account_number, builder.sequence) {
+            (Some(account_number), Some(sequence)) => (account_number, sequence),
+            _ => self.signer_info(&signer.address()?).await?,
+        };
+        let chain_id = builder.chain_id.clone().unwrap_or_else(|| self.chain_id.clone());
+        builder.sign(signer, &chain_id, account_number, sequence)
+    }
+
+    /// Broadcast the tx, encoded in canonical JSON, once it has passed CheckTx.
+    /// Return the tx hash, hex-encoded.
+    pub async fn broadcast(&self, tx: &Tx) -> Result<String, ClientError> {
+        let tx_bytes = to_canonical_vec(tx)?;
+        let hash = hex::encode_upper(sha256(&tx_bytes));
+
+        let response = self.rpc.broadcast_tx_sync(tx_bytes).await?;
+        if response.code.is_err() {
+            return Err(ClientError::check_tx_failed(
+                response.code.value(),
+                response.codespace,
+                response.log,
+            ));
+        }
+
+        Ok(hash)
+    }
+
+    /// Poll the node until the tx of the given hash is included in a block, or
+    /// until timing out. The tx may have failed; check the response's code.
+    pub async fn wait_for_tx(&self, hash: &str) -> Result<TxResponse, ClientError> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match self.query_tx(hash).await {
+                Ok(response) => return Ok(response),
+                Err(ClientError::QueryFailed {
+                    log,
+                }) if log.contains("no tx found") => {
+                    if Instant::now() + self.poll_interval > deadline {
+                        return Err(ClientError::timeout(hash));
+                    }
+                    sleep(self.poll_interval).await;
+                },
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Sign the tx, broadcast it, and wait for it to be included in a block.
+    pub async fn sign_and_broadcast(
+        &self,
+        builder: TxBuilder,
+        signer: &impl Signer,
+    ) -> Result<TxResponse, ClientError> {
+        let tx = self.sign(builder, signer).await?;
+        let hash = self.broadcast(&tx).await?;
+        self.wait_for_tx(&hash).await
+    }
+}
@@ -0,0 +1,86 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Address(#[from] cw_sdk::address::AddressError),
+
+    #[error(transparent)]
+    Ecdsa(#[from] k256::ecdsa::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Std(#[from] cosmwasm_std::StdError),
+
+    #[error(transparent)]
+    TendermintRpc(#[from] tendermint_rpc::Error),
+
+    #[error("ABCI query failed: {log}")]
+    QueryFailed {
+        log: String,
+    },
+
+    #[error("query of contract {contract} failed: {error}")]
+    ContractQueryFailed {
+        contract: String,
+        error: String,
+    },
+
+    #[error("tx failed CheckTx with code {code} in codespace `{codespace}`: {log}")]
+    CheckTxFailed {
+        code: u32,
+        codespace: String,
+        log: String,
+    },
+
+    #[error("tx {hash} was not included in a block before timing out")]
+    Timeout {
+        hash: String,
+    },
+
+    #[error("{address} is a contract account; its account number and sequence must be set")]
+    SenderIsContract {
+        address: String,
+    },
+}
+
+impl ClientError {
+    pub fn query_failed(log: impl Into<String>) -> Self {
+        Self::QueryFailed {
+            log: log.into(),
+        }
+    }
+
+    pub fn contract_query_failed(contract: impl Into<String>, error: impl Into<String>) -> Self {
+        Self::ContractQueryFailed {
+            contract: contract.into(),
+            error: error.into(),
+        }
+    }
+
+    pub fn check_tx_failed(
+        code: u32,
+        codespace: impl Into<String>,
+        log: impl Into<String>,
+    ) -> Self {
+        Self::CheckTxFailed {
+            code,
+            codespace: codespace.into(),
+            log: log.into(),
+        }
+    }
+
+    pub fn timeout(hash: impl Into<String>) -> Self {
+        Self::Timeout {
+            hash: hash.into(),
+        }
+    }
+
+    pub fn sender_is_contract(address: impl Into<String>) -> Self {
+        Self::SenderIsContract {
+            address: address.into(),
+        }
+    }
+}
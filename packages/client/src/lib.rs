@@ -0,0 +1,20 @@
+//! Client for cw-sdk chains, so that projects don't each have to reimplement
+//! building, signing and broadcasting txs.
+//!
+//! - `TxBuilder` collects the messages, fee and other parameters of a tx;
+//! - a `Signer` signs the tx body, e.g. `SecpSigner` with a private key held in
+//!   memory;
+//! - `Client` fills in the signer's account number and sequence from the node,
+//!   broadcasts the tx through the CometBFT RPC, and polls for its result. It
+//!   also wraps each `SdkQuery` in a typed method, e.g. `query_account`;
+//! - `EventStream` streams the decoded events of committed blocks and txs over
+//!   CometBFT's websocket, reconnecting whenever the connection is lost.
+
+mod builder;
+mod client;
+mod error;
+mod query;
+mod signer;
+mod subscribe;
+
+pub use crate::{builder::*, client::*, error::*, signer::*, subscribe::*};
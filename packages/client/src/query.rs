@@ -0,0 +1,97 @@
+use cosmwasm_std::{Binary, Coin, ContractResult};
+use cw_sdk::{
+    AccountResponse, CodeResponse, ContractResponse, InfoResponse, SdkQuery, TxResponse,
+    WasmRawResponse, WasmSmartResponse,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Client, ClientError};
+
+/// Typed wrappers of the `SdkQuery`s, deserializing into their response types.
+impl Client {
+    pub async fn query_info(&self) -> Result<InfoResponse, ClientError> {
+        self.query(&SdkQuery::Info {}).await
+    }
+
+    pub async fn query_account(&self, address: &str) -> Result<AccountResponse, ClientError> {
+        self.query(&SdkQuery::Account {
+            address: address.into(),
+        })
+        .await
+    }
+
+    pub async fn query_contract(&self, label: &str) -> Result<ContractResponse, ClientError> {
+        self.query(&SdkQuery::Contract {
+            label: label.into(),
+        })
+        .await
+    }
+
+    pub async fn query_code(
+        &self,
+        code_id: u64,
+        with_byte_code: bool,
+    ) -> Result<CodeResponse, ClientError> {
+        self.query(&SdkQuery::Code {
+            code_id,
+            with_byte_code,
+        })
+        .await
+    }
+
+    pub async fn query_balance(&self, address: &str, denom: &str) -> Result<Coin, ClientError> {
+        self.query(&SdkQuery::Balance {
+            address: address.into(),
+            denom: denom.into(),
+        })
+        .await
+    }
+
+    pub async fn query_tx(&self, hash: &str) -> Result<TxResponse, ClientError> {
+        self.query(&SdkQuery::Tx {
+            hash: hash.into(),
+        })
+        .await
+    }
+
+    /// Query the raw value under the given key in a contract's store, or `None`
+    /// if the key is not found.
+    pub async fn query_contract_raw(
+        &self,
+        contract: &str,
+        key: impl Into<Binary>,
+    ) -> Result<Option<Binary>, ClientError> {
+        let response: WasmRawResponse = self
+            .query(&SdkQuery::WasmRaw {
+                contract: contract.into(),
+                key: key.into(),
+            })
+            .await?;
+        Ok(response.value)
+    }
+
+    /// Send a smart query to a contract, which may be referred to by its label,
+    /// and deserialize its JSON response. If the contract returns an error, it's
+    /// returned as `ContractQueryFailed`.
+    pub async fn query_contract_smart<M, R>(
+        &self,
+        contract: &str,
+        msg: &M,
+    ) -> Result<R, ClientError>
+    where
+        M: Serialize,
+        R: DeserializeOwned,
+    {
+        let response: WasmSmartResponse = self
+            .query(&SdkQuery::WasmSmart {
+                contract: contract.into(),
+                msg: serde_json::to_value(msg)?,
+            })
+            .await?;
+
+        match response.result {
+            ContractResult::Ok(data) => serde_json::from_slice(&data).map_err(ClientError::from),
+            ContractResult::Err(err) => Err(ClientError::contract_query_failed(contract, err)),
+        }
+    }
+}
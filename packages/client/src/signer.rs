@@ -0,0 +1,106 @@
+use cosmwasm_std::{Addr, Binary};
+use cw_sdk::{address, PubKeyType, SignMode, SignerInfo, TxBody, TxSignature};
+use k256::ecdsa::{signature::Signer as _, SigningKey};
+
+use crate::ClientError;
+
+/// Something that can sign txs as an account, e.g. a private key held in
+/// memory, a hardware wallet, or a remote signing service.
+///
+/// Only `pubkey` and `sign` need to be implemented; the signer's address and
+/// tx signatures are derived from them.
+pub trait Signer {
+    /// The signer's public key, in the encoding of its pubkey type
+    fn pubkey(&self) -> Binary;
+
+    /// The scheme of the signer's public key
+    fn pubkey_type(&self) -> PubKeyType {
+        PubKeyType::Secp256k1
+    }
+
+    /// Sign the given bytes, which the signer is responsible for hashing
+    fn sign(&self, sign_bytes: &[u8]) -> Result<Binary, ClientError>;
+
+    /// The address of the account controlled by the signer
+    fn address(&self) -> Result<Addr, ClientError> {
+        let pubkey = self.pubkey();
+        let addr = match self.pubkey_type() {
+            PubKeyType::Secp256k1 => address::derive_from_pubkey(&pubkey)?,
+            PubKeyType::EthSecp256k1 => address::derive_from_eth_pubkey(&pubkey)?,
+        };
+        Ok(addr)
+    }
+
+    /// Sign the tx body as the given signer, in the sign mode the tx is to be
+    /// encoded in.
+    fn sign_tx(
+        &self,
+        sign_mode: &SignMode,
+        body: &TxBody,
+        signer: &SignerInfo,
+    ) -> Result<TxSignature, ClientError> {
+        let sign_bytes =
+            sign_mode.sign_bytes(body, &body.chain_id, signer.account_number, signer.sequence)?;
+        Ok(TxSignature {
+            pubkey: Some(self.pubkey()),
+            pubkey_type: self.pubkey_type(),
+            signature: self.sign(&sign_bytes)?,
+            multisig: None,
+            legacy_amino_json: false,
+        })
+    }
+}
+
+/// Signs with a secp256k1 private key held in memory. The sign bytes are
+/// SHA-256 hashed before signing.
+pub struct SecpSigner {
+    sk: SigningKey,
+}
+
+impl SecpSigner {
+    pub fn new(sk: SigningKey) -> Self {
+        Self {
+            sk,
+        }
+    }
+
+    /// Create a signer from the 32 bytes of a private key
+    pub fn from_bytes(sk_bytes: &[u8]) -> Result<Self, ClientError> {
+        Ok(Self::new(SigningKey::from_bytes(sk_bytes)?))
+    }
+}
+
+impl Signer for SecpSigner {
+    fn pubkey(&self) -> Binary {
+        self.sk.verifying_key().to_bytes().to_vec().into()
+    }
+
+    fn sign(&self, sign_bytes: &[u8]) -> Result<Binary, ClientError> {
+        let signature: k256::ecdsa::Signature = self.sk.try_sign(sign_bytes)?;
+        Ok(signature.as_ref().to_vec().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+
+    use super::*;
+
+    #[test]
+    fn signing_tx() {
+        let signer = SecpSigner::from_bytes(&[1; 32]).unwrap();
+        let addr = signer.address().unwrap();
+        let body = TxBody::single(addr.as_str(), "dev-1", 0, 1, vec![]);
+
+        let signature = signer.sign_tx(&SignMode::Json, &body, &body.signers[0]).unwrap();
+        assert_eq!(signature.pubkey, Some(signer.pubkey()));
+        assert_eq!(address::derive_from_pubkey(&signer.pubkey()).unwrap(), addr);
+
+        // the signature verifies against the sign doc, as the state machine does
+        let sign_bytes = body.sign_doc(&body.signers[0]).unwrap().to_sign_bytes().unwrap();
+        let vk = VerifyingKey::from_sec1_bytes(&signer.pubkey()).unwrap();
+        let signature = Signature::try_from(signature.signature.as_slice()).unwrap();
+        assert!(vk.verify(&sign_bytes, &signature).is_ok());
+    }
+}
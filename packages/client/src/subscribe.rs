@@ -0,0 +1,254 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use cosmwasm_std::Event;
+use cw_sdk::hash::sha256;
+use futures::{stream, Stream, StreamExt};
+use serde::Serialize;
+use tendermint::abci;
+use tendermint_rpc::{
+    event::{Event as RpcEvent, EventData},
+    query::EventType,
+    SubscriptionClient, WebSocketClient,
+};
+use tokio::{sync::mpsc, time::sleep};
+
+use crate::Client;
+
+/// How many decoded events are buffered before the stream is read from. Once
+/// full, events are no longer read from the websocket until there's room.
+const EVENT_BUFFER_SIZE: usize = 100;
+
+/// How long to wait before reconnecting, after the connection to the node is
+/// lost or fails to be established
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The events of a committed block, or of one of its txs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainEvent {
+    /// Events emitted outside of txs, e.g. by the staking contract at the end
+    /// of the block
+    Block {
+        height: u64,
+        events: Vec<Event>,
+    },
+
+    /// Events emitted by a tx. Empty if the tx failed.
+    Tx {
+        height: u64,
+        /// SHA-256 hash of the tx bytes, hex-encoded
+        hash: String,
+        events: Vec<Event>,
+    },
+}
+
+impl ChainEvent {
+    pub fn events(&self) -> &[Event] {
+        match self {
+            ChainEvent::Block {
+                events,
+                ..
+            }
+            | ChainEvent::Tx {
+                events,
+                ..
+            } => events,
+        }
+    }
+
+    /// Keep only the events that match the filter. Return `None` if none do.
+    pub fn filter(mut self, filter: &EventFilter) -> Option<Self> {
+        let (ChainEvent::Block {
+            events,
+            ..
+        }
+        | ChainEvent::Tx {
+            events,
+            ..
+        }) = &mut self;
+        events.retain(|event| filter.matches(event));
+        (!events.is_empty()).then_some(self)
+    }
+}
+
+/// Which events to stream. An event matches if it meets all of the given
+/// conditions; an empty filter matches all events.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// The event type, e.g. `wasm`
+    pub ty: Option<String>,
+
+    /// The address of the contract that emitted the event
+    pub contract: Option<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &Event) -> bool {
+        self.ty.as_ref().map_or(true, |ty| &event.ty == ty)
+            && self.contract.as_ref().map_or(true, |contract| {
+                event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key == "_contract_address" && &attr.value == contract)
+            })
+    }
+}
+
+/// A stream of the events of the blocks committed by a node, from the moment
+/// it's created, received over CometBFT's websocket.
+///
+/// If the connection is lost, the stream reconnects and resubscribes on its
+/// own. Events committed while disconnected are missed; indexers that need all
+/// of them should catch up by querying the blocks in between.
+pub struct EventStream {
+    events_rx: mpsc::Receiver<ChainEvent>,
+}
+
+impl EventStream {
+    /// Subscribe to the node at the given websocket URL, e.g.
+    /// `ws://127.0.0.1:26657/websocket`. Must be called within a Tokio runtime.
+    pub fn new(websocket_url: impl Into<String>, filter: EventFilter) -> Self {
+        let (events_tx, events_rx) = mpsc::channel(EVENT_BUFFER_SIZE);
+        tokio::spawn(keep_subscribed(websocket_url.into(), filter, events_tx));
+        Self {
+            events_rx,
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = ChainEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ChainEvent>> {
+        self.events_rx.poll_recv(cx)
+    }
+}
+
+impl Client {
+    /// Stream the events that match the filter, through the websocket of the
+    /// node this client connects to.
+    pub fn subscribe(&self, filter: EventFilter) -> EventStream {
+        let rpc_url = self.rpc_url.trim_end_matches('/');
+        // `http` becomes `ws`, and `https` becomes `wss`
+        EventStream::new(format!("{}/websocket", rpc_url.replacen("http", "ws", 1)), filter)
+    }
+}
+
+/// Forward events to the stream, reconnecting whenever the connection is lost,
+/// until the stream is dropped.
+async fn keep_subscribed(url: String, filter: EventFilter, events_tx: mpsc::Sender<ChainEvent>) {
+    loop {
+        // errors are not surfaced; the stream only ever yields events
+        forward_events(&url, &filter, &events_tx).await.ok();
+        if events_tx.is_closed() {
+            return;
+        }
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Subscribe to new blocks and txs, and forward their decoded events to the
+/// stream, until the connection is lost or the stream is dropped.
+async fn forward_events(
+    url: &str,
+    filter: &EventFilter,
+    events_tx: &mpsc::Sender<ChainEvent>,
+) -> Result<(), tendermint_rpc::Error> {
+    let (client, driver) = WebSocketClient::new(url).await?;
+    tokio::spawn(driver.run());
+
+    let blocks = client.subscribe(EventType::NewBlock.into()).await?;
+    let txs = client.subscribe(EventType::Tx.into()).await?;
+    let mut rpc_events = stream::select(blocks, txs);
+
+    while let Some(rpc_event) = rpc_events.next().await {
+        let Some(event) = decode_event(rpc_event?).and_then(|event| event.filter(filter)) else {
+            continue;
+        };
+        if events_tx.send(event).await.is_err() {
+            break;
+        }
+    }
+
+    client.close()
+}
+
+/// Decode the events of a block or a tx from a websocket event. Blocks of both
+/// CometBFT 0.38 and Tendermint 0.34 are supported.
+fn decode_event(rpc_event: RpcEvent) -> Option<ChainEvent> {
+    match rpc_event.data {
+        EventData::NewBlock {
+            block: Some(block),
+            result_finalize_block: Some(result),
+            ..
+        } => Some(ChainEvent::Block {
+            height: block.header.height.value(),
+            events: abci_to_wasm_events(result.events),
+        }),
+        EventData::LegacyNewBlock {
+            block: Some(block),
+            result_begin_block,
+            result_end_block,
+        } => {
+            let begin_events = result_begin_block.map(|result| result.events).unwrap_or_default();
+            let end_events = result_end_block.map(|result| result.events).unwrap_or_default();
+            Some(ChainEvent::Block {
+                height: block.header.height.value(),
+                events: abci_to_wasm_events(begin_events.into_iter().chain(end_events)),
+            })
+        },
+        EventData::Tx {
+            tx_result,
+        } => Some(ChainEvent::Tx {
+            height: tx_result.height as u64,
+            hash: hex::encode_upper(sha256(&tx_result.tx)),
+            events: abci_to_wasm_events(tx_result.result.events),
+        }),
+        _ => None,
+    }
+}
+
+fn abci_to_wasm_events(events: impl IntoIterator<Item = abci::Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .map(|event| {
+            let attributes = event.attributes.into_iter().map(|attr| (attr.key, attr.value));
+            Event::new(event.kind).add_attributes(attributes)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filtering_events() {
+        let transfer = Event::new("transfer").add_attribute("amount", "100ucosm");
+        let wasm = Event::new("wasm").add_attribute("_contract_address", "bank");
+        let event = ChainEvent::Tx {
+            height: 1,
+            hash: "ABCD".into(),
+            events: vec![transfer, wasm.clone()],
+        };
+
+        let filter = EventFilter::default();
+        assert_eq!(event.clone().filter(&filter), Some(event.clone()));
+
+        let filter = EventFilter {
+            ty: Some("wasm".into()),
+            contract: Some("bank".into()),
+        };
+        assert_eq!(event.clone().filter(&filter).unwrap().events(), [wasm]);
+
+        let filter = EventFilter {
+            ty: Some("transfer".into()),
+            contract: Some("bank".into()),
+        };
+        assert_eq!(event.filter(&filter), None);
+    }
+}
@@ -2,6 +2,57 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Binary};
 use cw_address_like::AddressLike;
 
+use crate::address::{self, AddressError};
+
+/// The public key schemes supported for base accounts.
+#[derive(Copy, Default)]
+#[cw_serde]
+pub enum PubKeyType {
+    /// A secp256k1 key, whose address is derived according to ADR-028, and
+    /// which signs the SHA-256 hash of the sign bytes.
+    #[default]
+    Secp256k1,
+
+    /// A secp256k1 key used the Ethereum way, e.g. one derived by Metamask.
+    /// Its address is the last 20 bytes of the Keccak-256 hash of the
+    /// uncompressed pubkey, and it signs the Keccak-256 hash of the sign bytes.
+    ///
+    /// Signatures are 65 bytes long, with the recovery id appended, so that
+    /// the pubkey can be recovered and doesn't need to be included in the tx.
+    EthSecp256k1,
+}
+
+impl PubKeyType {
+    /// Derive the address of a pubkey of this type.
+    pub fn derive_address(self, pubkey_bytes: &[u8]) -> Result<Addr, AddressError> {
+        match self {
+            PubKeyType::Secp256k1 => address::derive_from_pubkey(pubkey_bytes),
+            PubKeyType::EthSecp256k1 => address::derive_from_eth_pubkey(pubkey_bytes),
+        }
+    }
+}
+
+/// A threshold multisig public key, i.e. a set of secp256k1 keys, at least
+/// `threshold` of which must sign a tx for it to be valid.
+///
+/// The keys must be sorted in ascending order by their bytes and contain no
+/// duplicates, so that each set of keys has exactly one representation.
+#[cw_serde]
+pub struct MultisigPubKey {
+    /// The members' secp256k1 public keys
+    pub pubkeys: Vec<Binary>,
+
+    /// The minimum number of members who must sign
+    pub threshold: u32,
+}
+
+impl MultisigPubKey {
+    /// Derive the address of the multisig.
+    pub fn derive_address(&self) -> Result<Addr, AddressError> {
+        address::derive_from_multisig(&self.pubkeys, self.threshold)
+    }
+}
+
 /// The account type to be stored on-chain.
 #[cw_serde]
 pub enum Account<T: AddressLike> {
@@ -11,11 +62,34 @@ pub enum Account<T: AddressLike> {
         /// The account's secp256k1 public key
         pubkey: Binary,
 
+        /// The scheme of the public key
+        #[serde(default)]
+        pubkey_type: PubKeyType,
+
+        /// The account number, assigned by the chain when the account is
+        /// created. It is included in the signed tx body, so that signatures
+        /// can't be replayed if the account is ever deleted and recreated.
+        #[serde(default)]
+        account_number: u64,
+
         /// The account's sequence number, used to prevent replay attacks.
         /// The first tx ever to be submitted by the account should come with the sequence of 1.
         sequence: u64,
     },
 
+    /// An account that is controlled by k-of-n public/private key pairs.
+    Multisig {
+        /// The account's multisig public key
+        pubkey: MultisigPubKey,
+
+        /// The account number, assigned by the chain when the account is
+        /// created
+        account_number: u64,
+
+        /// The account's sequence number, used to prevent replay attacks
+        sequence: u64,
+    },
+
     /// An account that is controlled by wasm code.
     Contract {
         /// Identifier of the wasm byte code associated with this contract.
@@ -34,9 +108,22 @@ impl From<Account<Addr>> for Account<String> {
         match acct {
             Account::Base {
                 pubkey,
+                pubkey_type,
+                account_number,
                 sequence,
             } => Account::Base {
                 pubkey,
+                pubkey_type,
+                account_number,
+                sequence,
+            },
+            Account::Multisig {
+                pubkey,
+                account_number,
+                sequence,
+            } => Account::Multisig {
+                pubkey,
+                account_number,
                 sequence,
             },
             Account::Contract {
@@ -1,8 +1,9 @@
 use bech32::{FromBase32, ToBase32, Variant};
 use cosmwasm_std::{Addr, CanonicalAddr};
+use k256::{elliptic_curve::sec1::ToEncodedPoint, PublicKey};
 use thiserror::Error;
 
-use crate::hash::sha256;
+use crate::hash::{keccak256, sha256};
 
 /// Currently we simply hardcode the prefix in the state machine's binary.
 ///
@@ -22,15 +23,25 @@ pub const ADDRESS_PREFIX: &str = "cw";
 ///   https://ethereum-magicians.org/t/increasing-address-size-from-20-to-32-bytes/5485/43
 pub const ADDRESS_LENGTH: usize = 32;
 
+/// Accounts controlled by Ethereum-style keys have 20-byte addresses, same as
+/// on Ethereum, so that a Metamask-derived key has the same address bytes on
+/// both chains.
+pub const ETH_ADDRESS_LENGTH: usize = 20;
+
 /// According to ADR-028, each basic address (one that is represented by a
 /// single key pair), needs to have a "type" string denoting the public key
 /// scheme used.
 ///
-/// For now, cw-sdk only supports the secp256k1 scheme. The type string is
-/// defined by:
+/// This applies to secp256k1 keys; Ethereum-style keys instead derive their
+/// addresses the same way as on Ethereum. The type string is defined by:
 /// https://github.com/cosmos/cosmos-sdk/blob/main/proto/cosmos/crypto/secp256k1/keys.proto
 pub const PUBKEY_TYPE: &str = "cosmos.crypto.secp256k1.PubKey";
 
+/// The type string of multisig pubkeys, used in the same way as `PUBKEY_TYPE`.
+/// Defined by:
+/// https://github.com/cosmos/cosmos-sdk/blob/main/proto/cosmos/crypto/multisig/keys.proto
+pub const MULTISIG_PUBKEY_TYPE: &str = "cosmos.crypto.multisig.LegacyAminoPubKey";
+
 /// Takes a human readable address and returns a canonical binary representation of it.
 pub fn canonicalize(human: &str) -> Result<CanonicalAddr, AddressError> {
     let (prefix, addr_bytes_base32, variant) = bech32::decode(human)?;
@@ -44,7 +55,7 @@ pub fn canonicalize(human: &str) -> Result<CanonicalAddr, AddressError> {
         Err(AddressError::IncorrectVariant)
     } else if prefix != ADDRESS_PREFIX {
         Err(AddressError::incorrect_prefix(prefix))
-    } else if addr_len != ADDRESS_LENGTH {
+    } else if addr_len != ADDRESS_LENGTH && addr_len != ETH_ADDRESS_LENGTH {
         Err(AddressError::incorrect_length(addr_len))
     } else {
         Ok(addr_bytes.into())
@@ -86,6 +97,53 @@ pub fn derive_from_pubkey(pubkey_bytes: &[u8]) -> Result<Addr, AddressError> {
     humanize_prehash(&bytes)
 }
 
+/// Derive an account address based on an Ethereum-style public key, which may
+/// be either compressed or uncompressed.
+///
+/// The address bytes are computed the same way as on Ethereum:
+///
+/// ```plain
+/// address_bytes := keccak256(uncompressed_pubkey_bytes[1:])[12:]
+/// ```
+///
+/// Where `uncompressed_pubkey_bytes[1:]` is the uncompressed pubkey without
+/// the leading `0x04` tag byte.
+pub fn derive_from_eth_pubkey(pubkey_bytes: &[u8]) -> Result<Addr, AddressError> {
+    let pubkey =
+        PublicKey::from_sec1_bytes(pubkey_bytes).map_err(|_| AddressError::InvalidPubkey)?;
+    let uncompressed = pubkey.to_encoded_point(false);
+    let hash = keccak256(&uncompressed.as_bytes()[1..]);
+    humanize(&hash[(hash.len() - ETH_ADDRESS_LENGTH)..].to_vec().into())
+}
+
+/// Derive a multisig account address based on the threshold and the members'
+/// public keys.
+///
+/// The address bytes are computed as:
+///
+/// ```plain
+/// address_bytes := sha256(MULTISIG_PUBKEY_TYPE | threshold | sha256(pubkey_1) | ... | sha256(pubkey_n))[:ADDRESS_LENGTH]
+/// ```
+///
+/// Where `threshold` is encoded as 4 big-endian bytes, the pubkeys are sorted
+/// in ascending order by their bytes, and `|` means bytes concatenation without
+/// using any separator. The same set of keys thus derives the same address
+/// regardless of the order in which they are provided.
+pub fn derive_from_multisig(
+    pubkeys: &[impl AsRef<[u8]>],
+    threshold: u32,
+) -> Result<Addr, AddressError> {
+    let mut pubkeys = pubkeys.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+    pubkeys.sort();
+
+    let mut bytes = MULTISIG_PUBKEY_TYPE.to_string().into_bytes();
+    bytes.extend(threshold.to_be_bytes());
+    for pubkey in pubkeys {
+        bytes.extend(sha256(pubkey));
+    }
+    humanize_prehash(&bytes)
+}
+
 /// Derive contract address based on a human-readable label.
 ///
 /// The address bytes are computed as:
@@ -138,12 +196,16 @@ pub enum AddressError {
         found: String,
     },
 
-    #[error("incorrect address length: expecting {expect} bytes, found {found}")]
+    #[error("incorrect address length: expecting {expect} or {eth_expect} bytes, found {found}")]
     IncorrectLength {
         expect: usize,
+        eth_expect: usize,
         found: usize,
     },
 
+    #[error("invalid secp256k1 public key")]
+    InvalidPubkey,
+
     #[error("address verification failed: input {input}, recovered {recovered}")]
     RecoveredMismatch {
         input: String,
@@ -162,6 +224,7 @@ impl AddressError {
     pub fn incorrect_length(found: usize) -> Self {
         Self::IncorrectLength {
             expect: ADDRESS_LENGTH,
+            eth_expect: ETH_ADDRESS_LENGTH,
             found,
         }
     }
@@ -173,3 +236,42 @@ impl AddressError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use k256::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn deriving_from_eth_pubkey() {
+        // the well-known Ethereum address of the private key `1`
+        let mut sk_bytes = [0u8; 32];
+        sk_bytes[31] = 1;
+        let pubkey = SecretKey::from_be_bytes(&sk_bytes).unwrap().public_key();
+        // 0x7e5f4552091a69125d5dfcb7b8c2659029395bdf
+        let expect = [
+            0x7e, 0x5f, 0x45, 0x52, 0x09, 0x1a, 0x69, 0x12, 0x5d, 0x5d,
+            0xfc, 0xb7, 0xb8, 0xc2, 0x65, 0x90, 0x29, 0x39, 0x5b, 0xdf,
+        ];
+
+        // compressed and uncompressed pubkeys derive the same address
+        for compress in [true, false] {
+            let pubkey_bytes = pubkey.to_encoded_point(compress);
+            let addr = derive_from_eth_pubkey(pubkey_bytes.as_bytes()).unwrap();
+            assert_eq!(canonicalize(addr.as_str()).unwrap().as_slice(), expect);
+        }
+    }
+
+    #[test]
+    fn deriving_from_multisig() {
+        let addr = derive_from_multisig(&[b"alice", b"bobby", b"carol"], 2).unwrap();
+
+        // the order of the pubkeys doesn't matter
+        assert_eq!(derive_from_multisig(&[b"carol", b"alice", b"bobby"], 2).unwrap(), addr);
+
+        // but the threshold and the set of pubkeys do
+        assert_ne!(derive_from_multisig(&[b"alice", b"bobby", b"carol"], 3).unwrap(), addr);
+        assert_ne!(derive_from_multisig(&[b"alice", b"bobby"], 2).unwrap(), addr);
+    }
+}
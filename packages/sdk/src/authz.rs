@@ -0,0 +1,42 @@
+use cosmwasm_schema::cw_serde;
+
+/// What an account (the granter) authorizes another (the grantee) to do on its
+/// behalf.
+#[cw_serde]
+pub enum Authorization {
+    /// Send messages of the given type, e.g. `store_code`. See `SdkMsg::kind`
+    /// for the message types.
+    Generic {
+        msg: String,
+    },
+
+    /// Execute the given contract, with any message and funds
+    ContractExecute {
+        contract: String,
+    },
+}
+
+impl Authorization {
+    /// The key under which a grant of this authorization is stored, which is
+    /// unique for each authorization.
+    pub fn key(&self) -> String {
+        match self {
+            Authorization::Generic {
+                msg,
+            } => msg.clone(),
+            Authorization::ContractExecute {
+                contract,
+            } => format!("execute/{contract}"),
+        }
+    }
+}
+
+/// An authorization granted to a grantee.
+#[cw_serde]
+pub struct AuthzGrant {
+    pub authorization: Authorization,
+
+    /// UNIX timestamp (in seconds) at which the grant expires. If not
+    /// provided, the grant doesn't expire.
+    pub expiration: Option<u64>,
+}
@@ -0,0 +1,105 @@
+use std::any::type_name;
+
+use cosmwasm_std::{StdError, StdResult};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Serialize a value into canonical JSON.
+///
+/// Floating point numbers are rejected, as there isn't a single way to format
+/// them that every client agrees on. Amounts are to be encoded as strings, and
+/// other numbers as integers.
+pub fn to_canonical_vec<T: Serialize>(data: &T) -> StdResult<Vec<u8>> {
+    let value = serde_json::to_value(data)
+        .map_err(|err| StdError::serialize_err(type_name::<T>(), err))?;
+    let mut bytes = vec![];
+    write_value(&mut bytes, &value)
+        .map_err(|err| StdError::serialize_err(type_name::<T>(), err))?;
+    Ok(bytes)
+}
+
+/// Return whether the bytes are valid JSON in canonical form.
+pub fn is_canonical(bytes: &[u8]) -> bool {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(value) => to_canonical_vec(&value).map_or(false, |canonical| canonical == bytes),
+        Err(_) => false,
+    }
+}
+
+fn write_value(bytes: &mut Vec<u8>, value: &Value) -> Result<(), &'static str> {
+    match value {
+        Value::Null => bytes.extend_from_slice(b"null"),
+        Value::Bool(b) => bytes.extend_from_slice(if *b { b"true" } else { b"false" }),
+        Value::Number(number) if number.is_f64() => {
+            return Err("floating point numbers are not allowed in canonical JSON");
+        },
+        Value::Number(number) => bytes.extend_from_slice(number.to_string().as_bytes()),
+        Value::String(string) => write_string(bytes, string),
+        Value::Array(array) => {
+            bytes.push(b'[');
+            for (i, item) in array.iter().enumerate() {
+                if i > 0 {
+                    bytes.push(b',');
+                }
+                write_value(bytes, item)?;
+            }
+            bytes.push(b']');
+        },
+        Value::Object(object) => {
+            // sort explicitly, instead of relying on the map type used by
+            // serde_json, which depends on its `preserve_order` feature
+            let mut entries = object.iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+
+            bytes.push(b'{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    bytes.push(b',');
+                }
+                write_string(bytes, key);
+                bytes.push(b':');
+                write_value(bytes, item)?;
+            }
+            bytes.push(b'}');
+        },
+    }
+    Ok(())
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    // serializing a string can't fail
+    serde_json::to_writer(bytes, string).expect("[cw-sdk]: failed to serialize string");
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn sorting_keys() {
+        let value = json!({ "b": [1, { "d": null, "c": true }], "a": "x" });
+        let bytes = to_canonical_vec(&value).unwrap();
+        assert_eq!(bytes, br#"{"a":"x","b":[1,{"c":true,"d":null}]}"#);
+        assert!(is_canonical(&bytes));
+    }
+
+    #[test]
+    fn rejecting_non_canonical() {
+        assert!(!is_canonical(br#"{"b":1,"a":2}"#));
+        assert!(!is_canonical(br#"{"a": 1}"#));
+        assert!(!is_canonical(b"not json"));
+    }
+
+    #[test]
+    fn rejecting_floats() {
+        assert!(to_canonical_vec(&json!({ "amount": 1.5 })).is_err());
+        assert!(to_canonical_vec(&[0.1f64]).is_err());
+        assert!(!is_canonical(br#"{"amount":1.0}"#));
+        assert!(!is_canonical(br#"{"amount":1e3}"#));
+
+        // integers of any sign are fine
+        assert!(is_canonical(br#"{"a":-1,"b":18446744073709551615}"#));
+    }
+}
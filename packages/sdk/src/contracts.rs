@@ -1,5 +1,7 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Coin;
+use cosmwasm_std::{Binary, Coin};
+
+use crate::{tx::Tx, validator::ExtendedVote};
 
 pub mod bank {
     use super::*;
@@ -8,12 +10,184 @@ pub mod bank {
     pub enum SudoMsg {
         /// Forcibly transfer coins between two accounts.
         ///
-        /// Callable by the state machine when handling gas fee payments and
-        /// funds attached to messages.
+        /// Callable by the state machine when handling funds attached to
+        /// messages.
         Transfer {
             from: String,
             to: String,
             coins: Vec<Coin>,
         },
+
+        /// Forcibly transfer a tx's fee from its payer to the fee collector,
+        /// or refund part of it. Unlike `Transfer`, the coins' send hooks
+        /// aren't queried, so that a hook can't fail a tx's fee payment after
+        /// it's admitted to the mempool, or its refund after it's executed.
+        ///
+        /// Callable by the state machine when handling gas fee payments.
+        TransferFee {
+            from: String,
+            to: String,
+            coins: Vec<Coin>,
+        },
+    }
+
+    /// The queries of the bank contract that the state machine passes through,
+    /// so that clients can look up balances without knowing the contract's
+    /// query schema. A subset of the contract's `QueryMsg`.
+    #[cw_serde]
+    pub enum QueryMsg {
+        /// The total supply of a single coin. Returns `Coin`.
+        Supply {
+            denom: String,
+        },
+
+        /// The balance of a single coin for a single account. Returns `Coin`.
+        Balance {
+            address: String,
+            denom: String,
+        },
+
+        /// The balances of all coins for a single account. Returns `Vec<Coin>`.
+        Balances {
+            address: String,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+    }
+}
+
+pub mod staking {
+    use super::*;
+
+    /// The messages of the staking contract that clients send without knowing
+    /// the contract's full schema. A subset of the contract's `ExecuteMsg`.
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        /// Register the sender as a validator, with the funds attached to the
+        /// message as its self-delegation. Sent in genesis txs, which are
+        /// created by `cwd genesis gentx`.
+        CreateValidator {
+            /// The validator's ed25519 consensus public key
+            pubkey: Binary,
+
+            /// A human readable name for the validator
+            moniker: String,
+        },
+    }
+
+    #[cw_serde]
+    pub enum SudoMsg {
+        /// Invoked by the state machine at the end of each block.
+        ///
+        /// The contract is expected to set the response's `data` field to the
+        /// JSON-encoded validator set updates, i.e. a `Vec<cw_sdk::Validator>`,
+        /// or leave it empty if the validator set doesn't change.
+        EndBlock {},
+    }
+}
+
+pub mod vote_extensions {
+    use super::*;
+
+    /// The hooks a contract must implement in order to handle vote extensions,
+    /// e.g. an oracle whose validators attach observed prices to their votes.
+    #[cw_serde]
+    pub enum SudoMsg {
+        /// Invoked by the state machine when this node's validator votes for a
+        /// block. The contract is expected to set the response's `data` field
+        /// to the extension, or leave it empty to not extend the vote.
+        ///
+        /// State changes made by the call are discarded.
+        ExtendVote {},
+
+        /// Invoked by the state machine when another validator's vote extension
+        /// is received, and when a proposal including it is processed. The
+        /// contract returns error to reject the extension.
+        ///
+        /// State changes made by the call are discarded.
+        VerifyVoteExtension {
+            validator: Binary,
+            extension: Binary,
+        },
+
+        /// Invoked by the state machine at the beginning of each block, with
+        /// the extensions of the votes for the previous block, as aggregated by
+        /// the block's proposer, and the total power of the validator set that
+        /// voted, including validators whose votes are missing.
+        ProcessVoteExtensions {
+            votes: Vec<ExtendedVote>,
+            total_power: u64,
+        },
+    }
+}
+
+pub mod smart_account {
+    use super::*;
+
+    /// The hooks a contract must implement in order to send txs, i.e. to be a
+    /// smart account, e.g. one controlled by session keys or passkeys, or one
+    /// that enforces spending limits.
+    #[cw_serde]
+    pub enum SudoMsg {
+        /// Invoked by the state machine to authenticate a tx signed by the
+        /// contract account, in place of signature verification.
+        ///
+        /// `sign_bytes` are the bytes that the state machine would have had a
+        /// key sign, computed using the account number and sequence in the
+        /// contract's `SignerInfo`. The state machine doesn't track a contract
+        /// account's sequence, so the contract is responsible for preventing
+        /// replay attacks itself. `signature` is the contract's entry in the
+        /// tx's signatures, which is opaque to the state machine.
+        ///
+        /// The contract returns error to reject the tx. State changes made by
+        /// the call are discarded if the tx fails.
+        BeforeTx {
+            tx: Tx,
+            sign_bytes: Binary,
+            signature: Binary,
+        },
+
+        /// Invoked by the state machine after the tx's messages are executed.
+        /// The contract may return error to revert the entire tx, e.g. if a
+        /// spending limit is exceeded.
+        AfterTx {
+            tx: Tx,
+        },
+    }
+}
+
+pub mod taxman {
+    use super::*;
+
+    /// The hooks a contract labeled `taxman` must implement in order to decide
+    /// the fees that txs pay, e.g. to offer discounts, to accept fees in other
+    /// tokens, or to rebate unused gas. Without such a contract, the fee set in
+    /// a tx is charged as is, and nothing is refunded.
+    #[cw_serde]
+    pub enum SudoMsg {
+        /// Invoked by the state machine before the tx's messages are executed.
+        ///
+        /// The contract is expected to set the response's `data` field to the
+        /// JSON-encoded coins to charge the payer, i.e. a `Vec<Coin>`, or leave
+        /// it empty to charge the fee set in the tx. The contract returns error
+        /// to reject the tx, e.g. if the fee is too low.
+        ChargeFee {
+            payer: String,
+            fee: Vec<Coin>,
+            tx: Tx,
+        },
+
+        /// Invoked by the state machine after the tx's messages are executed,
+        /// with the coins charged and the gas the tx used.
+        ///
+        /// The contract may set the response's `data` field to JSON-encoded
+        /// coins to refund the payer from the fee collector, i.e. a `Vec<Coin>`,
+        /// or leave it empty to refund nothing.
+        SettleFee {
+            payer: String,
+            charged: Vec<Coin>,
+            gas_limit: u64,
+            gas_used: u64,
+        },
     }
 }
@@ -0,0 +1,190 @@
+use cosmwasm_schema::cw_serde;
+
+/// Machine-readable code of the error a tx failed with, reported in tx results
+/// along with its codespace, so that clients can tell failure causes apart
+/// without parsing error messages.
+///
+/// The code is part of the tx result that validators agree on, so the code of
+/// an existing variant must never change. Codes are unique across codespaces;
+/// new variants take the next free code in their codespace's range. Error
+/// messages, which may include details that differ between nodes, are only
+/// reported in the tx result's log.
+#[derive(Copy, Eq, Hash)]
+#[cw_serde]
+#[repr(u32)]
+pub enum ErrorCode {
+    // codespace `sdk`: the state machine itself
+    /// The node failed to process the tx for reasons unrelated to the tx, e.g.
+    /// a database error
+    Internal = 1,
+    /// The tx, or a message or data it contains, can't be decoded
+    InvalidEncoding = 2,
+    /// The tx exceeds the maximum size
+    TxTooLarge = 3,
+    /// The tx has more messages than allowed
+    TooManyMsgs = 4,
+    /// The tx wasn't included in a block by its timeout height
+    TxTimedOut = 5,
+    /// The tx has already been executed with the same idempotency key
+    DuplicateTx = 6,
+    /// The tx uses a feature that isn't supported yet
+    Unsupported = 7,
+    /// An account, code or other item the tx refers to doesn't exist
+    NotFound = 8,
+    /// The extended votes included in the block are invalid
+    InvalidVoteExtensions = 9,
+    /// The chain-level parameters the tx sets are invalid
+    InvalidParams = 10,
+
+    // codespace `auth`: authentication of the tx's signers
+    /// An address is malformed, or doesn't match the pubkey it's derived from
+    InvalidAddress = 101,
+    /// A signature is malformed or doesn't verify
+    InvalidSignature = 102,
+    /// A pubkey doesn't match the one stored on-chain for the account
+    InvalidPubkey = 103,
+    /// The tx is signed for a different chain
+    WrongChainId = 104,
+    /// A signer's account number doesn't match
+    WrongAccountNumber = 105,
+    /// A signer's sequence number doesn't match
+    WrongSequence = 106,
+    /// A message sender or the fee payer isn't a signer of the tx, or the
+    /// signers are otherwise invalid
+    Unauthorized = 107,
+    /// A contract account signing the tx rejected it
+    RejectedByAccount = 108,
+    /// The operator's ante handler rejected the tx
+    RejectedByAnteHandler = 109,
+    /// An account already exists at the address
+    AccountExists = 110,
+    /// The account's type doesn't support the operation, e.g. a contract
+    /// account where a base account is expected
+    InvalidAccountType = 111,
+
+    // codespace `feegrant`: fees and fee allowances
+    /// The fee couldn't be deducted from the payer
+    InsufficientFee = 201,
+    /// No fee allowance is granted to the payer
+    AllowanceNotFound = 202,
+    /// The fee allowance has expired
+    AllowanceExpired = 203,
+    /// The fee exceeds the allowance
+    AllowanceExceeded = 204,
+    /// A message type isn't allowed by the fee allowance
+    MsgNotAllowed = 205,
+    /// The fee allowance is invalid
+    InvalidAllowance = 206,
+
+    // codespace `authz`: authorizations
+    /// The grantee isn't authorized to send the message
+    GrantNotFound = 301,
+    /// The authorization has expired
+    GrantExpired = 302,
+    /// Exec messages are nested
+    NestedExec = 303,
+    /// The authorization is invalid
+    InvalidGrant = 304,
+
+    // codespace `wasm`: contract calls
+    /// A contract returned an error
+    ContractError = 401,
+    /// A contract call ran out of gas
+    OutOfGas = 402,
+    /// The wasm VM failed to run a contract call
+    VmError = 403,
+    /// The contract label is not allowed
+    InvalidLabel = 404,
+    /// The funds attached to a message couldn't be transferred
+    FundTransferFailed = 405,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 36] = [
+        ErrorCode::Internal,
+        ErrorCode::InvalidEncoding,
+        ErrorCode::TxTooLarge,
+        ErrorCode::TooManyMsgs,
+        ErrorCode::TxTimedOut,
+        ErrorCode::DuplicateTx,
+        ErrorCode::Unsupported,
+        ErrorCode::NotFound,
+        ErrorCode::InvalidVoteExtensions,
+        ErrorCode::InvalidParams,
+        ErrorCode::InvalidAddress,
+        ErrorCode::InvalidSignature,
+        ErrorCode::InvalidPubkey,
+        ErrorCode::WrongChainId,
+        ErrorCode::WrongAccountNumber,
+        ErrorCode::WrongSequence,
+        ErrorCode::Unauthorized,
+        ErrorCode::RejectedByAccount,
+        ErrorCode::RejectedByAnteHandler,
+        ErrorCode::AccountExists,
+        ErrorCode::InvalidAccountType,
+        ErrorCode::InsufficientFee,
+        ErrorCode::AllowanceNotFound,
+        ErrorCode::AllowanceExpired,
+        ErrorCode::AllowanceExceeded,
+        ErrorCode::MsgNotAllowed,
+        ErrorCode::InvalidAllowance,
+        ErrorCode::GrantNotFound,
+        ErrorCode::GrantExpired,
+        ErrorCode::NestedExec,
+        ErrorCode::InvalidGrant,
+        ErrorCode::ContractError,
+        ErrorCode::OutOfGas,
+        ErrorCode::VmError,
+        ErrorCode::InvalidLabel,
+        ErrorCode::FundTransferFailed,
+    ];
+
+    /// The numeric code reported in tx results. Zero means success.
+    pub const fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// The codespace reported in tx results along with the code.
+    pub const fn codespace(self) -> &'static str {
+        match self.code() / 100 {
+            0 => "sdk",
+            1 => "auth",
+            2 => "feegrant",
+            3 => "authz",
+            _ => "wasm",
+        }
+    }
+
+    /// Look up the error code reported in a tx result, e.g. by a client.
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|error_code| error_code.code() == code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn codes_are_unique() {
+        let codes = ErrorCode::ALL.into_iter().map(ErrorCode::code).collect::<HashSet<_>>();
+        assert_eq!(codes.len(), ErrorCode::ALL.len());
+        assert!(!codes.contains(&0));
+
+        for error_code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_code(error_code.code()), Some(error_code));
+        }
+        assert_eq!(ErrorCode::from_code(0), None);
+    }
+
+    #[test]
+    fn codespaces() {
+        assert_eq!(ErrorCode::TxTooLarge.codespace(), "sdk");
+        assert_eq!(ErrorCode::WrongSequence.codespace(), "auth");
+        assert_eq!(ErrorCode::InsufficientFee.codespace(), "feegrant");
+        assert_eq!(ErrorCode::NestedExec.codespace(), "authz");
+        assert_eq!(ErrorCode::OutOfGas.codespace(), "wasm");
+    }
+}
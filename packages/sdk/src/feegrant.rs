@@ -0,0 +1,59 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Coin;
+
+/// An allowance granted by one account (the granter) to another (the grantee),
+/// which allows the grantee to pay tx fees from the granter's balance.
+///
+/// Timestamps are UNIX timestamps in seconds.
+#[cw_serde]
+pub enum Allowance {
+    /// The grantee may spend up to `spend_limit` in fees in total, until the
+    /// allowance expires.
+    Basic {
+        /// The maximum amount of fees that may be spent. If empty, there is no
+        /// limit.
+        spend_limit: Vec<Coin>,
+
+        /// The time at which the allowance expires. If not provided, the
+        /// allowance doesn't expire.
+        expiration: Option<u64>,
+    },
+
+    /// Same as `Basic`, but additionally the grantee may spend up to
+    /// `period_spend_limit` in fees within each period.
+    Periodic {
+        /// The maximum amount of fees that may be spent in total. If empty,
+        /// there is no limit other than the per-period one.
+        spend_limit: Vec<Coin>,
+
+        /// The time at which the allowance expires. If not provided, the
+        /// allowance doesn't expire.
+        expiration: Option<u64>,
+
+        /// Length of each period, in seconds
+        period: u64,
+
+        /// The maximum amount of fees that may be spent within each period
+        period_spend_limit: Vec<Coin>,
+
+        /// The amount that may still be spent in the current period.
+        /// Set by the state machine when the allowance is granted, and at the
+        /// beginning of each period.
+        #[serde(default)]
+        period_can_spend: Vec<Coin>,
+
+        /// The time at which the current period ends.
+        /// Set by the state machine when the allowance is granted, and at the
+        /// beginning of each period.
+        #[serde(default)]
+        period_reset: u64,
+    },
+
+    /// Wraps another allowance, which may then only be used to pay the fees of
+    /// txs whose messages are all of the listed types, e.g. `execute`. See
+    /// `SdkMsg::kind` for the message types.
+    AllowedMsgs {
+        allowance: Box<Allowance>,
+        allowed_msgs: Vec<String>,
+    },
+}
@@ -1,6 +1,7 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Binary;
 
-use crate::msg::SdkMsg;
+use crate::{account::Account, msg::SdkMsg, params::Params, tx::Tx};
 
 /// This should be included inside `~/.tendermint/genesis.json`, under the
 /// `app_state` field.
@@ -19,6 +20,50 @@ pub struct GenesisState {
     /// The application developers must provide a trusted deployer account.
     pub deployer: String,
 
+    /// Chain-level parameters. Defaults are used if not provided.
+    #[serde(default)]
+    pub params: Params,
+
     /// Messages to be executed in order during the InitChain call.
     pub msgs: Vec<SdkMsg>,
+
+    /// Accounts exported from the state of an existing chain.
+    #[serde(default)]
+    pub accounts: Vec<GenesisAccount>,
+
+    /// Wasm byte codes exported from the state of an existing chain.
+    #[serde(default)]
+    pub codes: Vec<GenesisCode>,
+
+    /// Raw states of contracts exported from the state of an existing chain.
+    #[serde(default)]
+    pub contract_states: Vec<GenesisContractState>,
+
+    /// Txs signed by validator operators ahead of the launch, e.g. to create
+    /// their validators in the staking contract. Unlike `msgs`, they are sent
+    /// on behalf of their signers, so their signatures are verified. They are
+    /// executed in order after the messages.
+    #[serde(default)]
+    pub gen_txs: Vec<Tx>,
+}
+
+#[cw_serde]
+pub struct GenesisAccount {
+    pub address: String,
+    pub account: Account<String>,
+}
+
+#[cw_serde]
+pub struct GenesisCode {
+    pub code_id: u64,
+    pub wasm_byte_code: Binary,
+}
+
+#[cw_serde]
+pub struct GenesisContractState {
+    /// Address of the contract
+    pub address: String,
+
+    /// All key-value pairs in the contract's storage, in ascending order by keys
+    pub state: Vec<(Binary, Binary)>,
 }
@@ -1,4 +1,5 @@
 use sha2::{Digest, Sha256};
+use sha3::Keccak256;
 
 /// Byte length of the SHA-256 hash
 pub const HASH_LENGTH: usize = 32;
@@ -9,3 +10,10 @@ pub fn sha256(bytes: &[u8]) -> Vec<u8> {
     hasher.update(bytes);
     hasher.finalize().to_vec()
 }
+
+/// Perform a Keccak-256 hash of the given bytes, as used by Ethereum
+pub fn keccak256(bytes: &[u8]) -> Vec<u8> {
+    let mut hasher = Keccak256::new();
+    hasher.update(bytes);
+    hasher.finalize().to_vec()
+}
@@ -11,12 +11,16 @@ mod genesis;
 
 /// Defines the account types.
 ///
-/// Cw-sdk supports two types of accounts:
+/// Cw-sdk supports three types of accounts:
 ///
 /// - base account: a.k.a. externally-owned account (EoA), is an account
 ///   controlled by a single public/private key pair. For cw-sdk we use
 ///   secp256k1 keys.
-/// - contract account: an account controlled by a wasm binary code.
+/// - multisig account: an account controlled by a set of secp256k1 keys, a
+///   threshold number of which must sign each tx.
+/// - contract account: an account controlled by a wasm binary code. A contract
+///   account may also send txs, in which case it authenticates them itself by
+///   implementing the `smart_account` sudo hooks.
 ///
 /// Each account is identified an address. The algorithms for deriving addresses
 /// are described below in the `address` module.
@@ -33,7 +37,8 @@ mod msg;
 /// Defines the transaction type.
 ///
 /// A transaction contains one or more execute messages, a few parameters used
-/// for preventing replay attacks, and the user's signature.
+/// for preventing replay attacks, and the signatures of one or more users who
+/// send the messages.
 mod tx;
 
 /// Defines the required API for core contracts.
@@ -60,8 +65,45 @@ mod tx;
 /// a chain where governance is a multisig, if you wish.)
 mod contracts;
 
+/// Defines the authorization types.
+///
+/// An account may authorize another to send certain messages on its behalf,
+/// e.g. so that a bot can execute a contract for a user. The grantee does so by
+/// sending an `Exec` message that wraps the messages to be sent.
+mod authz;
+
+/// Defines the fee allowance types.
+///
+/// An account may grant another an allowance to pay tx fees from its balance,
+/// e.g. so that a dapp can sponsor its users' txs. The allowance may be limited
+/// in amount, in time, per period, or to certain message types.
+mod feegrant;
+
+/// Defines the chain-level parameters.
+///
+/// Parameters are set at genesis and stored in the chain's state, so that all
+/// nodes enforce the same limits, such as the maximum size of a tx, and charge
+/// the same gas costs. Governance may update them afterwards.
+mod params;
+
+/// Defines the error codes reported in tx results.
+///
+/// Each error the state machine may fail a tx with maps to a code and a
+/// codespace, which clients can match on instead of parsing error messages.
+mod error_code;
+
+/// Defines the validator type.
+///
+/// Validator set updates are returned to Tendermint at the end of each block.
+/// The state machine records the full validator set at each height, so that it
+/// can be queried later, e.g. by IBC relayers or light clients.
+mod validator;
+
 // export types for easy access
-pub use crate::{account::*, contracts::*, genesis::*, msg::*, tx::*};
+pub use crate::{
+    account::*, authz::*, contracts::*, error_code::*, feegrant::*, genesis::*, msg::*, params::*,
+    tx::*, validator::*,
+};
 
 //------------------------------------------------------------------------------
 // Functions
@@ -75,7 +117,10 @@ pub use crate::{account::*, contracts::*, genesis::*, msg::*, tx::*};
 /// Each address is a 256-bit byte array, encoded in bech32, derived
 /// deterministically from the account data:
 ///
-/// - a base account's address is derived from its public key
+/// - a base account's address is derived from its public key; accounts
+///   controlled by Ethereum-style keys have 160-bit addresses instead
+/// - a multisig account's address is derived from its threshold and the
+///   sorted set of its members' public keys
 /// - a contract account's address is derived from its label
 ///
 /// ## Contract labels
@@ -119,7 +164,43 @@ pub use crate::{account::*, contracts::*, genesis::*, msg::*, tx::*};
 /// address, returning the real underlying address as a cosmwasm_std::Addr.
 pub mod address;
 
-/// Defines the hash function (SHA-256) used throughout cw-sdk.
+/// Defines the canonical JSON encoding, in which sign bytes are computed and
+/// txs are broadcasted.
+///
+/// Signature verification must not depend on how a particular JSON library
+/// orders fields or escapes strings, so that clients written in any language
+/// can reproduce the sign bytes exactly. In canonical form:
+///
+/// - object keys are sorted in ascending order by their UTF-8 bytes;
+/// - there is no whitespace outside of strings;
+/// - strings are escaped minimally: `"` and `\` are escaped with a backslash,
+///   control characters as `\b`, `\f`, `\n`, `\r`, `\t` or `\u00XX`, and
+///   all other characters, including non-ASCII ones, are left as is;
+/// - numbers are integers without leading zeros or exponents.
+pub mod canonical;
+
+/// Defines the protobuf encoding of txs, messages and pubkeys, which is accepted
+/// alongside JSON, so that standard Cosmos tooling and hardware wallets that
+/// only understand protobuf sign docs (SIGN_MODE_DIRECT) can be used.
+///
+/// Messages and pubkeys are wrapped in `Any`, with the following type URLs:
+///
+/// - `/cw_sdk.v1.MsgStoreCode`, `/cw_sdk.v1.MsgInstantiate`,
+///   `/cw_sdk.v1.MsgExecute`, `/cw_sdk.v1.MsgMigrate`,
+///   `/cw_sdk.v1.MsgGrantAllowance`, `/cw_sdk.v1.MsgRevokeAllowance`,
+///   `/cw_sdk.v1.MsgGrantAuthz`, `/cw_sdk.v1.MsgRevokeAuthz`,
+///   `/cw_sdk.v1.MsgExec`, `/cw_sdk.v1.MsgRotateKey`,
+///   `/cw_sdk.v1.MsgUpdateParams` for messages;
+/// - `/cosmos.crypto.secp256k1.PubKey`,
+///   `/ethermint.crypto.v1.ethsecp256k1.PubKey` and
+///   `/cosmos.crypto.multisig.LegacyAminoPubKey` for pubkeys.
+///
+/// Contract messages, fee allowances, authorizations and parameters are embedded
+/// in the protobuf messages as JSON bytes.
+pub mod proto;
+
+/// Defines the hash functions used throughout cw-sdk: SHA-256, and Keccak-256
+/// for Ethereum-style keys.
 pub mod hash;
 
 /// A few helper functions used by contracts.
@@ -1,8 +1,15 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, BlockInfo, Coin, ContractResult};
+use cosmwasm_std::{Binary, BlockInfo, Coin, ContractResult, Event};
 use serde_json::Value;
 
-use crate::account::Account;
+use crate::{
+    account::{Account, PubKeyType},
+    authz::{Authorization, AuthzGrant},
+    feegrant::Allowance,
+    params::Params,
+    tx::{Tx, TxMsg},
+    validator::Validator,
+};
 
 #[cw_serde]
 pub enum SdkMsg {
@@ -56,6 +63,106 @@ pub enum SdkMsg {
         code_id: u64,
         msg: Value,
     },
+
+    /// Grant an account an allowance to pay tx fees from the sender's balance,
+    /// replacing any existing allowance granted to the same account.
+    GrantAllowance {
+        grantee: String,
+        allowance: Allowance,
+    },
+
+    /// Revoke the fee allowance granted to an account
+    RevokeAllowance {
+        grantee: String,
+    },
+
+    /// Authorize an account to send messages on the sender's behalf,
+    /// replacing any existing grant of the same authorization.
+    GrantAuthz {
+        grantee: String,
+        authorization: Authorization,
+
+        /// UNIX timestamp (in seconds) at which the grant expires
+        expiration: Option<u64>,
+    },
+
+    /// Revoke an authorization granted to an account
+    RevokeAuthz {
+        grantee: String,
+        authorization: Authorization,
+    },
+
+    /// Send messages on behalf of other accounts, each of which must have
+    /// authorized the sender to do so. The messages may not be `Exec`s
+    /// themselves.
+    Exec {
+        /// The messages to be sent, and the accounts on whose behalf they are
+        msgs: Vec<TxMsg>,
+    },
+
+    /// Replace the pubkey of the sender, which must be a base account, e.g.
+    /// because the old key is compromised or is moved to a hardware wallet.
+    /// The account keeps its address, and thus its assets.
+    ///
+    /// As with any message, the tx must be signed by the old key. Subsequent
+    /// txs must be signed by the new key.
+    RotateKey {
+        new_pubkey: Binary,
+
+        /// The scheme of the new pubkey, which may differ from the old one
+        #[serde(default)]
+        new_pubkey_type: PubKeyType,
+    },
+
+    /// Replace the chain-level parameters, e.g. the gas costs. Only the
+    /// governance contract, i.e. the contract account labeled `gov`, may send
+    /// this message, once a proposal to do so has passed. The new parameters
+    /// take effect from the next message on.
+    UpdateParams {
+        params: Params,
+    },
+}
+
+impl SdkMsg {
+    /// The message's type, i.e. the snake-cased name of its variant, as it
+    /// appears in JSON.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SdkMsg::StoreCode {
+                ..
+            } => "store_code",
+            SdkMsg::Instantiate {
+                ..
+            } => "instantiate",
+            SdkMsg::Execute {
+                ..
+            } => "execute",
+            SdkMsg::Migrate {
+                ..
+            } => "migrate",
+            SdkMsg::GrantAllowance {
+                ..
+            } => "grant_allowance",
+            SdkMsg::RevokeAllowance {
+                ..
+            } => "revoke_allowance",
+            SdkMsg::GrantAuthz {
+                ..
+            } => "grant_authz",
+            SdkMsg::RevokeAuthz {
+                ..
+            } => "revoke_authz",
+            SdkMsg::Exec {
+                ..
+            } => "exec",
+            SdkMsg::RotateKey {
+                ..
+            } => "rotate_key",
+            SdkMsg::UpdateParams {
+                ..
+            } => "update_params",
+        }
+    }
 }
 
 #[cw_serde]
@@ -91,19 +198,62 @@ pub enum SdkQuery {
         limit: Option<u32>,
     },
 
-    /// Query a single wasm byte code by id
+    /// Enumerate the contracts instantiated from a wasm byte code, by address
+    #[returns(Vec<ContractResponse>)]
+    ContractsByCode {
+        code_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Query a single wasm byte code by id. The byte code itself is only
+    /// included if requested, as it can be several hundred KiB.
     #[returns(CodeResponse)]
     Code {
         code_id: u64,
+        #[serde(default)]
+        with_byte_code: bool,
     },
 
-    /// Enumerate all wasm byte codes by code id
+    /// Enumerate all wasm byte codes by code id, without the byte codes
     #[returns(Vec<CodeResponse>)]
     Codes {
         start_after: Option<u64>,
         limit: Option<u32>,
     },
 
+    /// Query the validator set at the given height.
+    /// If height is not provided, use the last committed height.
+    #[returns(ValidatorsResponse)]
+    Validators {
+        height: Option<u64>,
+    },
+
+    /// Query the fee allowance granted by an account to another
+    #[returns(AllowanceResponse)]
+    Allowance {
+        granter: String,
+        grantee: String,
+    },
+
+    /// Enumerate the fee allowances granted by an account, by grantee address
+    #[returns(Vec<AllowanceResponse>)]
+    Allowances {
+        granter: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Enumerate the authorizations granted by an account to another, by the
+    /// keys of the authorizations. See `Authorization::key`.
+    #[returns(Vec<AuthzGrant>)]
+    AuthzGrants {
+        granter: String,
+        grantee: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
     /// Perform raw query on a wasm contract
     #[returns(WasmRawResponse)]
     WasmRaw {
@@ -111,18 +261,100 @@ pub enum SdkQuery {
         key: Binary,
     },
 
+    /// Enumerate raw key-value pairs in a wasm contract's store, in ascending
+    /// order by keys. If a prefix is provided, only return keys that start
+    /// with it.
+    #[returns(Vec<(Binary, Binary)>)]
+    WasmAllRaw {
+        contract: String,
+        prefix: Option<Binary>,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+
+    /// Query the balance of a single coin for a single account, from the bank
+    /// contract
+    #[returns(Coin)]
+    Balance {
+        address: String,
+        denom: String,
+    },
+
+    /// Enumerate the balances of all coins for a single account by denom, from
+    /// the bank contract
+    #[returns(Vec<Coin>)]
+    AllBalances {
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Query the total supply of a single coin, from the bank contract
+    #[returns(Coin)]
+    Supply {
+        denom: String,
+    },
+
     /// Perform smart query on a wasm contract
     #[returns(WasmSmartResponse)]
     WasmSmart {
         contract: String,
         msg: Value,
     },
+
+    /// Execute a tx against the last committed state without committing it,
+    /// e.g. to estimate its gas usage before it's broadcasted.
+    ///
+    /// Signatures aren't verified, so that the tx can be simulated before it's
+    /// signed, but the signers' pubkeys must be provided, unless their accounts
+    /// already exist. Everything else is checked the same as in DeliverTx.
+    #[returns(SimulateResponse)]
+    Simulate {
+        tx: Tx,
+    },
+
+    /// Query the result of a tx included in a block, by the SHA-256 hash of its
+    /// bytes, hex-encoded.
+    ///
+    /// Txs are indexed by each node for itself, as they are executed, so only
+    /// txs of blocks that the node has executed can be found.
+    #[returns(TxResponse)]
+    Tx {
+        hash: String,
+    },
+
+    /// Enumerate the txs that emitted an event with the given attribute, in the
+    /// order they were executed. The attribute is specified as in Tendermint's
+    /// `tx_search` queries, e.g. `wasm._contract_address`, along with its value.
+    #[returns(Vec<TxResponse>)]
+    TxsByEvent {
+        event: String,
+        value: String,
+        /// Hash of the last tx of the previous page, hex-encoded
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Query the contracts that have consumed the most gas, in descending order
+    /// by gas used.
+    ///
+    /// The usage is accounted for by each node for itself, since it started,
+    /// and includes simulated txs as well as the calls the state machine makes
+    /// into contracts on its own behalf.
+    #[returns(Vec<ContractGasUsage>)]
+    ContractGasUsage {
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
 pub struct InfoResponse {
     pub last_committed_block: BlockInfo,
     pub code_count: u64,
+    /// The number of account numbers assigned so far, which is also the
+    /// number to be assigned to the next account created
+    pub account_count: u64,
+    pub params: Params,
 }
 
 #[cw_serde]
@@ -142,7 +374,24 @@ pub struct ContractResponse {
 #[cw_serde]
 pub struct CodeResponse {
     pub code_id: u64,
-    pub wasm_byte_code: Binary,
+    /// Hex-encoded SHA-256 hash of the byte code
+    pub code_hash: String,
+    /// Size of the byte code, in bytes
+    pub size: u64,
+    pub wasm_byte_code: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct ValidatorsResponse {
+    pub height: u64,
+    pub validators: Vec<Validator>,
+}
+
+#[cw_serde]
+pub struct AllowanceResponse {
+    pub granter: String,
+    pub grantee: String,
+    pub allowance: Allowance,
 }
 
 #[cw_serde]
@@ -152,6 +401,59 @@ pub struct WasmRawResponse {
     pub value: Option<Binary>,
 }
 
+#[cw_serde]
+pub struct SimulateResponse {
+    /// Gas consumed by the tx's contract calls, including the system calls
+    /// made on its behalf
+    pub gas_used: u64,
+
+    /// Events emitted by the tx
+    pub events: Vec<Event>,
+
+    /// Data returned by each of the tx's messages, in order
+    pub data: Vec<Option<Binary>>,
+}
+
+#[cw_serde]
+pub struct TxResponse {
+    /// SHA-256 hash of the tx bytes, hex-encoded
+    pub hash: String,
+
+    /// Height of the block that includes the tx
+    pub height: u64,
+
+    /// Position of the tx in the block
+    pub index: u32,
+
+    /// Zero if the tx succeeded, otherwise the code of the error it failed
+    /// with; see `ErrorCode`
+    pub code: u32,
+
+    /// Codespace of the error the tx failed with; empty if it succeeded
+    pub codespace: String,
+
+    /// Message of the error the tx failed with; empty if it succeeded
+    pub log: String,
+
+    pub gas_wanted: u64,
+
+    pub gas_used: u64,
+
+    /// Events emitted by the tx; empty if it failed
+    pub events: Vec<Event>,
+}
+
+#[cw_serde]
+pub struct ContractGasUsage {
+    pub address: String,
+
+    /// The number of calls into the contract's entry points
+    pub calls: u64,
+
+    /// The total gas consumed by the calls
+    pub gas_used: u64,
+}
+
 #[cw_serde]
 pub struct WasmSmartResponse {
     /// Smart query result.
@@ -0,0 +1,177 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Response;
+
+/// Chain-level parameters, which all nodes must agree on.
+///
+/// They are set at genesis, and enforced both when txs are admitted to the
+/// mempool and when they are delivered.
+#[cw_serde]
+pub struct Params {
+    /// The maximum size of a tx in bytes, as it is encoded when broadcasted.
+    /// Bounds the size of wasm byte codes that can be stored, so that a single
+    /// tx can't stall block production.
+    pub max_tx_bytes: u64,
+
+    /// The maximum number of messages in a tx, not counting the messages
+    /// wrapped in an `Exec` message.
+    pub max_msgs_per_tx: u64,
+
+    /// The gas costs that txs are charged, in SDK gas.
+    #[serde(default)]
+    pub gas: GasConfig,
+
+    /// The gas limits of the calls that the state machine itself makes into
+    /// contracts, in wasm gas.
+    #[serde(default)]
+    pub system_gas: SystemGasLimits,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            max_tx_bytes: 2 * 1024 * 1024,
+            max_msgs_per_tx: 100,
+            gas: GasConfig::default(),
+            system_gas: SystemGasLimits::default(),
+        }
+    }
+}
+
+/// The gas costs that txs are charged, in SDK gas, i.e. the unit of the gas
+/// limit and the gas price of txs' fees.
+///
+/// Contracts are metered in wasm gas by the VM, which is converted into SDK gas
+/// once the call is done. Their storage ops are charged to the VM's meter as
+/// they happen, so that they count towards the call's gas limit.
+///
+/// The defaults are those of wasmd and the Cosmos SDK. The `gas-bench` command
+/// of cwd suggests costs based on how long the ops take on a given machine.
+#[derive(Copy, Eq)]
+#[cw_serde]
+pub struct GasConfig {
+    /// The amount of wasm gas that one unit of SDK gas is worth. Must not be
+    /// zero.
+    pub wasm_gas_per_sdk_gas: u64,
+
+    /// The cost of reading a value, regardless of its size
+    pub read_flat: u64,
+
+    /// The cost of reading each byte of a key and the value found
+    pub read_per_byte: u64,
+
+    /// The cost of writing a value, regardless of its size
+    pub write_flat: u64,
+
+    /// The cost of writing each byte of a key and its value
+    pub write_per_byte: u64,
+
+    /// The cost of deleting a value
+    pub delete: u64,
+
+    /// The cost of each event attribute emitted by a contract
+    pub event_attribute: u64,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            wasm_gas_per_sdk_gas: 140_000_000,
+            read_flat: 1000,
+            read_per_byte: 3,
+            write_flat: 2000,
+            write_per_byte: 30,
+            delete: 1000,
+            event_attribute: 10,
+        }
+    }
+}
+
+impl GasConfig {
+    /// Convert an amount of wasm gas into SDK gas, rounding up.
+    pub fn to_sdk_gas(&self, wasm_gas: u64) -> u64 {
+        let per_sdk_gas = self.wasm_gas_per_sdk_gas.max(1);
+        wasm_gas / per_sdk_gas + u64::from(wasm_gas % per_sdk_gas > 0)
+    }
+
+    /// Convert an amount of SDK gas into wasm gas.
+    pub fn to_wasm_gas(&self, sdk_gas: u64) -> u64 {
+        sdk_gas.saturating_mul(self.wasm_gas_per_sdk_gas)
+    }
+
+    /// The cost of reading a key and the value found, of the given total size.
+    pub fn read_cost(&self, bytes: usize) -> u64 {
+        self.read_flat.saturating_add(self.read_per_byte.saturating_mul(bytes as u64))
+    }
+
+    /// The cost of writing a key and its value, of the given total size.
+    pub fn write_cost(&self, bytes: usize) -> u64 {
+        self.write_flat.saturating_add(self.write_per_byte.saturating_mul(bytes as u64))
+    }
+
+    /// The cost of the attributes emitted in a contract's response, both those
+    /// of the response itself and those of its events.
+    pub fn events_cost<T>(&self, response: &Response<T>) -> u64 {
+        let attributes = response.attributes.len()
+            + response.events.iter().map(|event| event.attributes.len()).sum::<usize>();
+        self.event_attribute.saturating_mul(attributes as u64)
+    }
+}
+
+/// The maximum amount of wasm gas that each kind of call the state machine
+/// itself makes into contracts may consume, e.g. transferring the funds attached
+/// to a message, or updating the validator set at the end of a block.
+///
+/// These calls are part of executing blocks, so all nodes must bound them the
+/// same way. None of the limits may be zero.
+#[derive(Copy, Eq)]
+#[cw_serde]
+#[serde(default)]
+pub struct SystemGasLimits {
+    pub fund_transfer: u64,
+    pub end_block: u64,
+    pub before_tx: u64,
+    pub after_tx: u64,
+    pub vote_extensions: u64,
+    pub taxman: u64,
+}
+
+impl Default for SystemGasLimits {
+    fn default() -> Self {
+        Self {
+            fund_transfer: 10_000_000_000,
+            end_block: 100_000_000_000,
+            before_tx: 10_000_000_000,
+            after_tx: 10_000_000_000,
+            vote_extensions: 10_000_000_000,
+            taxman: 10_000_000_000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::Event;
+
+    use super::*;
+
+    #[test]
+    fn converting_gas() {
+        let gas = GasConfig {
+            wasm_gas_per_sdk_gas: 100,
+            ..Default::default()
+        };
+        assert_eq!(gas.to_sdk_gas(0), 0);
+        assert_eq!(gas.to_sdk_gas(100), 1);
+        assert_eq!(gas.to_sdk_gas(101), 2);
+        assert_eq!(gas.to_wasm_gas(2), 200);
+    }
+
+    #[test]
+    fn charging_events() {
+        let gas = GasConfig::default();
+        let response: Response = Response::new()
+            .add_attribute("action", "transfer")
+            .add_event(Event::new("transfer").add_attribute("from", "a").add_attribute("to", "b"));
+        assert_eq!(gas.events_cost(&response), 3 * gas.event_attribute);
+    }
+}
@@ -0,0 +1,807 @@
+use cosmwasm_std::{Binary, Coin, StdError, StdResult, Uint128};
+use prost::Message;
+
+use crate::{
+    account::{MultisigPubKey, PubKeyType},
+    authz::Authorization,
+    feegrant::Allowance,
+    msg::SdkMsg,
+    params::Params,
+    tx::{Fee, MultiSignature, SignMode, SignerInfo, Tx, TxBody, TxMsg, TxSignature},
+};
+
+pub const STORE_CODE_TYPE_URL: &str = "/cw_sdk.v1.MsgStoreCode";
+pub const INSTANTIATE_TYPE_URL: &str = "/cw_sdk.v1.MsgInstantiate";
+pub const EXECUTE_TYPE_URL: &str = "/cw_sdk.v1.MsgExecute";
+pub const MIGRATE_TYPE_URL: &str = "/cw_sdk.v1.MsgMigrate";
+pub const GRANT_ALLOWANCE_TYPE_URL: &str = "/cw_sdk.v1.MsgGrantAllowance";
+pub const REVOKE_ALLOWANCE_TYPE_URL: &str = "/cw_sdk.v1.MsgRevokeAllowance";
+pub const GRANT_AUTHZ_TYPE_URL: &str = "/cw_sdk.v1.MsgGrantAuthz";
+pub const REVOKE_AUTHZ_TYPE_URL: &str = "/cw_sdk.v1.MsgRevokeAuthz";
+pub const EXEC_TYPE_URL: &str = "/cw_sdk.v1.MsgExec";
+pub const ROTATE_KEY_TYPE_URL: &str = "/cw_sdk.v1.MsgRotateKey";
+pub const UPDATE_PARAMS_TYPE_URL: &str = "/cw_sdk.v1.MsgUpdateParams";
+pub const SECP256K1_PUBKEY_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+pub const ETH_SECP256K1_PUBKEY_TYPE_URL: &str = "/ethermint.crypto.v1.ethsecp256k1.PubKey";
+pub const MULTISIG_PUBKEY_TYPE_URL: &str = "/cosmos.crypto.multisig.LegacyAminoPubKey";
+
+/// Same as `google.protobuf.Any`.
+#[derive(Clone, PartialEq, Message)]
+pub struct Any {
+    #[prost(string, tag = "1")]
+    pub type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+}
+
+/// The tx as it is broadcasted. Same as `cosmos.tx.v1beta1.TxRaw`, except that
+/// the pubkeys and the signatures are included directly instead of in an
+/// `AuthInfo`.
+#[derive(Clone, PartialEq, Message)]
+pub struct TxRaw {
+    /// Protobuf encoding of `ProtoTxBody`
+    #[prost(bytes = "vec", tag = "1")]
+    pub body_bytes: Vec<u8>,
+    /// One for each signer, in the same order as the signers in the body
+    #[prost(message, repeated, tag = "2")]
+    pub signatures: Vec<ProtoSignature>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoSignature {
+    /// `Any`-wrapped `Secp256k1PubKey`, with the type URL of the key's scheme
+    #[prost(message, optional, tag = "1")]
+    pub pubkey: Option<Any>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: Vec<u8>,
+    /// The key's scheme, for when the pubkey is omitted
+    #[prost(enumeration = "ProtoPubKeyType", tag = "3")]
+    pub pubkey_type: i32,
+    /// The members' signatures, if the signer is a multisig account
+    #[prost(message, optional, tag = "4")]
+    pub multisig: Option<ProtoMultiSignature>,
+    /// Whether the signer signed the `LegacySignDoc` instead of the `ProtoSignDoc`
+    #[prost(bool, tag = "5")]
+    pub legacy_amino_json: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoMultiSignature {
+    /// `Any`-wrapped `LegacyAminoPubKey`
+    #[prost(message, optional, tag = "1")]
+    pub pubkey: Option<Any>,
+    /// One for each member; empty if the member doesn't sign
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub signatures: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+#[repr(i32)]
+pub enum ProtoPubKeyType {
+    Secp256k1 = 0,
+    EthSecp256k1 = 1,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoTxBody {
+    #[prost(string, tag = "1")]
+    pub chain_id: String,
+    #[prost(message, repeated, tag = "2")]
+    pub signers: Vec<ProtoSignerInfo>,
+    #[prost(message, repeated, tag = "3")]
+    pub msgs: Vec<ProtoTxMsg>,
+    #[prost(string, optional, tag = "4")]
+    pub idempotency_key: Option<String>,
+    #[prost(message, optional, tag = "5")]
+    pub fee: Option<ProtoFee>,
+    #[prost(uint64, optional, tag = "6")]
+    pub timeout_height: Option<u64>,
+}
+
+/// Same as `cosmos.tx.v1beta1.Fee`, except that empty payer and granter are
+/// omitted rather than empty strings.
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoFee {
+    #[prost(message, repeated, tag = "1")]
+    pub amount: Vec<ProtoCoin>,
+    #[prost(uint64, tag = "2")]
+    pub gas_limit: u64,
+    #[prost(string, optional, tag = "3")]
+    pub payer: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub granter: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoSignerInfo {
+    #[prost(string, tag = "1")]
+    pub address: String,
+    #[prost(uint64, tag = "2")]
+    pub account_number: u64,
+    #[prost(uint64, tag = "3")]
+    pub sequence: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoTxMsg {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    /// `Any`-wrapped message
+    #[prost(message, optional, tag = "2")]
+    pub msg: Option<Any>,
+}
+
+/// What the sender signs in SIGN_MODE_DIRECT.
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoSignDoc {
+    /// The tx body bytes exactly as they are included in `TxRaw`
+    #[prost(bytes = "vec", tag = "1")]
+    pub body_bytes: Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub chain_id: String,
+    #[prost(uint64, tag = "3")]
+    pub account_number: u64,
+    #[prost(uint64, tag = "4")]
+    pub sequence: u64,
+}
+
+/// Same as CometBFT's `tendermint.types.CanonicalVoteExtension`, which
+/// validators sign along with the extensions of their votes.
+#[derive(Clone, PartialEq, Message)]
+pub struct CanonicalVoteExtension {
+    #[prost(bytes = "vec", tag = "1")]
+    pub extension: Vec<u8>,
+    #[prost(sfixed64, tag = "2")]
+    pub height: i64,
+    #[prost(sfixed64, tag = "3")]
+    pub round: i64,
+    #[prost(string, tag = "4")]
+    pub chain_id: String,
+}
+
+/// Same as `cosmos.crypto.secp256k1.PubKey` and
+/// `ethermint.crypto.v1.ethsecp256k1.PubKey`.
+#[derive(Clone, PartialEq, Message)]
+pub struct Secp256k1PubKey {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: Vec<u8>,
+}
+
+/// Same as `cosmos.crypto.multisig.LegacyAminoPubKey`. The members' pubkeys are
+/// `Any`-wrapped `Secp256k1PubKey`s.
+#[derive(Clone, PartialEq, Message)]
+pub struct LegacyAminoPubKey {
+    #[prost(uint32, tag = "1")]
+    pub threshold: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub public_keys: Vec<Any>,
+}
+
+/// Same as `cosmos.base.v1beta1.Coin`.
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoCoin {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgStoreCode {
+    #[prost(bytes = "vec", tag = "1")]
+    pub wasm_byte_code: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgInstantiate {
+    #[prost(uint64, tag = "1")]
+    pub code_id: u64,
+    /// JSON-encoded instantiate message
+    #[prost(bytes = "vec", tag = "2")]
+    pub msg: Vec<u8>,
+    #[prost(message, repeated, tag = "3")]
+    pub funds: Vec<ProtoCoin>,
+    #[prost(string, tag = "4")]
+    pub label: String,
+    #[prost(string, optional, tag = "5")]
+    pub admin: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgExecute {
+    #[prost(string, tag = "1")]
+    pub contract: String,
+    /// JSON-encoded execute message
+    #[prost(bytes = "vec", tag = "2")]
+    pub msg: Vec<u8>,
+    #[prost(message, repeated, tag = "3")]
+    pub funds: Vec<ProtoCoin>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgMigrate {
+    #[prost(string, tag = "1")]
+    pub contract: String,
+    #[prost(uint64, tag = "2")]
+    pub code_id: u64,
+    /// JSON-encoded migrate message
+    #[prost(bytes = "vec", tag = "3")]
+    pub msg: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgGrantAllowance {
+    #[prost(string, tag = "1")]
+    pub grantee: String,
+    /// JSON-encoded `Allowance`
+    #[prost(bytes = "vec", tag = "2")]
+    pub allowance: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgRevokeAllowance {
+    #[prost(string, tag = "1")]
+    pub grantee: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgGrantAuthz {
+    #[prost(string, tag = "1")]
+    pub grantee: String,
+    /// JSON-encoded `Authorization`
+    #[prost(bytes = "vec", tag = "2")]
+    pub authorization: Vec<u8>,
+    #[prost(uint64, optional, tag = "3")]
+    pub expiration: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgRevokeAuthz {
+    #[prost(string, tag = "1")]
+    pub grantee: String,
+    /// JSON-encoded `Authorization`
+    #[prost(bytes = "vec", tag = "2")]
+    pub authorization: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgExec {
+    #[prost(message, repeated, tag = "1")]
+    pub msgs: Vec<ProtoTxMsg>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgRotateKey {
+    /// The new pubkey, whose type URL indicates its scheme
+    #[prost(message, optional, tag = "1")]
+    pub new_pubkey: Option<Any>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgUpdateParams {
+    /// JSON-encoded `Params`
+    #[prost(bytes = "vec", tag = "1")]
+    pub params: Vec<u8>,
+}
+
+/// Encode a tx body in protobuf.
+pub fn encode_body(body: &TxBody) -> StdResult<Vec<u8>> {
+    let body = ProtoTxBody {
+        chain_id: body.chain_id.clone(),
+        signers: body
+            .signers
+            .iter()
+            .map(|signer| ProtoSignerInfo {
+                address: signer.address.clone(),
+                account_number: signer.account_number,
+                sequence: signer.sequence,
+            })
+            .collect(),
+        msgs: body.msgs.iter().map(encode_tx_msg).collect::<StdResult<_>>()?,
+        idempotency_key: body.idempotency_key.clone(),
+        fee: Some(ProtoFee {
+            amount: body.fee.amount.iter().map(encode_coin).collect(),
+            gas_limit: body.fee.gas_limit,
+            payer: body.fee.payer.clone(),
+            granter: body.fee.granter.clone(),
+        }),
+        timeout_height: body.timeout_height,
+    };
+    Ok(body.encode_to_vec())
+}
+
+/// Return the bytes to be signed in SIGN_MODE_DIRECT.
+pub fn sign_bytes(
+    body_bytes: &[u8],
+    chain_id: &str,
+    account_number: u64,
+    sequence: u64,
+) -> Vec<u8> {
+    ProtoSignDoc {
+        body_bytes: body_bytes.to_vec(),
+        chain_id: chain_id.into(),
+        account_number,
+        sequence,
+    }
+    .encode_to_vec()
+}
+
+/// Return the bytes that a validator signs along with its vote's extension,
+/// i.e. the length-delimited encoding of the `CanonicalVoteExtension`, as
+/// CometBFT computes them.
+pub fn vote_extension_sign_bytes(
+    extension: &[u8],
+    height: u64,
+    round: u32,
+    chain_id: &str,
+) -> Vec<u8> {
+    CanonicalVoteExtension {
+        extension: extension.to_vec(),
+        height: height as i64,
+        round: round as i64,
+        chain_id: chain_id.into(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+/// Encode a signed tx in protobuf, given the encoded body.
+pub fn encode_tx(body_bytes: Vec<u8>, signatures: &[TxSignature]) -> Vec<u8> {
+    TxRaw {
+        body_bytes,
+        signatures: signatures
+            .iter()
+            .map(|signature| ProtoSignature {
+                pubkey: signature
+                    .pubkey
+                    .as_ref()
+                    .map(|key| encode_pubkey(key, signature.pubkey_type)),
+                signature: signature.signature.to_vec(),
+                pubkey_type: match signature.pubkey_type {
+                    PubKeyType::Secp256k1 => ProtoPubKeyType::Secp256k1,
+                    PubKeyType::EthSecp256k1 => ProtoPubKeyType::EthSecp256k1,
+                } as i32,
+                multisig: signature.multisig.as_ref().map(|multisig| ProtoMultiSignature {
+                    pubkey: multisig.pubkey.as_ref().map(encode_multisig_pubkey),
+                    signatures: multisig
+                        .signatures
+                        .iter()
+                        .map(|signature| {
+                            signature.as_ref().map(Binary::to_vec).unwrap_or_default()
+                        })
+                        .collect(),
+                }),
+                legacy_amino_json: signature.legacy_amino_json,
+            })
+            .collect(),
+    }
+    .encode_to_vec()
+}
+
+/// Decode a protobuf-encoded tx. Return the tx, along with the sign mode that
+/// carries the raw body bytes, which the signature is verified against.
+pub fn decode_tx(tx_bytes: &[u8]) -> StdResult<(Tx, SignMode)> {
+    let raw = TxRaw::decode(tx_bytes).map_err(|err| StdError::parse_err("TxRaw", err))?;
+    let body = ProtoTxBody::decode(raw.body_bytes.as_slice())
+        .map_err(|err| StdError::parse_err("ProtoTxBody", err))?;
+
+    let tx = Tx {
+        body: TxBody {
+            chain_id: body.chain_id,
+            signers: body
+                .signers
+                .into_iter()
+                .map(|signer| SignerInfo {
+                    address: signer.address,
+                    account_number: signer.account_number,
+                    sequence: signer.sequence,
+                })
+                .collect(),
+            msgs: body.msgs.into_iter().map(decode_tx_msg).collect::<StdResult<_>>()?,
+            fee: body.fee.map(decode_fee).transpose()?.unwrap_or_default(),
+            timeout_height: body.timeout_height,
+            idempotency_key: body.idempotency_key,
+        },
+        signatures: raw
+            .signatures
+            .into_iter()
+            .map(|signature| -> StdResult<_> {
+                let pubkey_type = match ProtoPubKeyType::from_i32(signature.pubkey_type) {
+                    Some(ProtoPubKeyType::Secp256k1) => PubKeyType::Secp256k1,
+                    Some(ProtoPubKeyType::EthSecp256k1) => PubKeyType::EthSecp256k1,
+                    None => {
+                        return Err(StdError::generic_err(format!(
+                            "unsupported pubkey type: {}",
+                            signature.pubkey_type,
+                        )));
+                    },
+                };
+                Ok(TxSignature {
+                    pubkey: signature
+                        .pubkey
+                        .map(|any| decode_pubkey(any, pubkey_type))
+                        .transpose()?,
+                    pubkey_type,
+                    signature: signature.signature.into(),
+                    multisig: signature.multisig.map(decode_multisig).transpose()?,
+                    legacy_amino_json: signature.legacy_amino_json,
+                })
+            })
+            .collect::<StdResult<_>>()?,
+    };
+
+    Ok((tx, SignMode::Direct {
+        body_bytes: raw.body_bytes.into(),
+    }))
+}
+
+fn pubkey_type_url(pubkey_type: PubKeyType) -> &'static str {
+    match pubkey_type {
+        PubKeyType::Secp256k1 => SECP256K1_PUBKEY_TYPE_URL,
+        PubKeyType::EthSecp256k1 => ETH_SECP256K1_PUBKEY_TYPE_URL,
+    }
+}
+
+fn encode_pubkey(pubkey: &Binary, pubkey_type: PubKeyType) -> Any {
+    Any {
+        type_url: pubkey_type_url(pubkey_type).into(),
+        value: Secp256k1PubKey {
+            key: pubkey.to_vec(),
+        }
+        .encode_to_vec(),
+    }
+}
+
+fn decode_pubkey(any: Any, pubkey_type: PubKeyType) -> StdResult<Binary> {
+    if any.type_url != pubkey_type_url(pubkey_type) {
+        return Err(StdError::generic_err(format!("unsupported pubkey type: {}", any.type_url)));
+    }
+    let pubkey = Secp256k1PubKey::decode(any.value.as_slice())
+        .map_err(|err| StdError::parse_err("Secp256k1PubKey", err))?;
+    Ok(pubkey.key.into())
+}
+
+fn encode_multisig_pubkey(pubkey: &MultisigPubKey) -> Any {
+    Any {
+        type_url: MULTISIG_PUBKEY_TYPE_URL.into(),
+        value: LegacyAminoPubKey {
+            threshold: pubkey.threshold,
+            public_keys: pubkey
+                .pubkeys
+                .iter()
+                .map(|key| encode_pubkey(key, PubKeyType::Secp256k1))
+                .collect(),
+        }
+        .encode_to_vec(),
+    }
+}
+
+fn decode_multisig(multisig: ProtoMultiSignature) -> StdResult<MultiSignature> {
+    let pubkey = match multisig.pubkey {
+        Some(any) => {
+            if any.type_url != MULTISIG_PUBKEY_TYPE_URL {
+                return Err(StdError::generic_err(format!(
+                    "unsupported pubkey type: {}",
+                    any.type_url,
+                )));
+            }
+            let pubkey = LegacyAminoPubKey::decode(any.value.as_slice())
+                .map_err(|err| StdError::parse_err("LegacyAminoPubKey", err))?;
+            Some(MultisigPubKey {
+                pubkeys: pubkey
+                    .public_keys
+                    .into_iter()
+                    .map(|any| decode_pubkey(any, PubKeyType::Secp256k1))
+                    .collect::<StdResult<_>>()?,
+                threshold: pubkey.threshold,
+            })
+        },
+        None => None,
+    };
+
+    Ok(MultiSignature {
+        pubkey,
+        signatures: multisig
+            .signatures
+            .into_iter()
+            .map(|signature| (!signature.is_empty()).then(|| signature.into()))
+            .collect(),
+    })
+}
+
+fn encode_tx_msg(msg: &TxMsg) -> StdResult<ProtoTxMsg> {
+    Ok(ProtoTxMsg {
+        sender: msg.sender.clone(),
+        msg: Some(encode_msg(&msg.msg)?),
+    })
+}
+
+fn decode_tx_msg(msg: ProtoTxMsg) -> StdResult<TxMsg> {
+    let any = msg.msg.ok_or_else(|| StdError::generic_err("missing message"))?;
+    Ok(TxMsg {
+        sender: msg.sender,
+        msg: decode_msg(any)?,
+    })
+}
+
+fn encode_msg(msg: &SdkMsg) -> StdResult<Any> {
+    let (type_url, value) = match msg {
+        SdkMsg::StoreCode {
+            wasm_byte_code,
+        } => (
+            STORE_CODE_TYPE_URL,
+            MsgStoreCode {
+                wasm_byte_code: wasm_byte_code.to_vec(),
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::Instantiate {
+            code_id,
+            msg,
+            funds,
+            label,
+            admin,
+        } => (
+            INSTANTIATE_TYPE_URL,
+            MsgInstantiate {
+                code_id: *code_id,
+                msg: to_json_bytes(msg)?,
+                funds: funds.iter().map(encode_coin).collect(),
+                label: label.clone(),
+                admin: admin.clone(),
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::Execute {
+            contract,
+            msg,
+            funds,
+        } => (
+            EXECUTE_TYPE_URL,
+            MsgExecute {
+                contract: contract.clone(),
+                msg: to_json_bytes(msg)?,
+                funds: funds.iter().map(encode_coin).collect(),
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::Migrate {
+            contract,
+            code_id,
+            msg,
+        } => (
+            MIGRATE_TYPE_URL,
+            MsgMigrate {
+                contract: contract.clone(),
+                code_id: *code_id,
+                msg: to_json_bytes(msg)?,
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::GrantAllowance {
+            grantee,
+            allowance,
+        } => (
+            GRANT_ALLOWANCE_TYPE_URL,
+            MsgGrantAllowance {
+                grantee: grantee.clone(),
+                allowance: serde_json::to_vec(allowance)
+                    .map_err(|err| StdError::serialize_err("Allowance", err))?,
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::RevokeAllowance {
+            grantee,
+        } => (
+            REVOKE_ALLOWANCE_TYPE_URL,
+            MsgRevokeAllowance {
+                grantee: grantee.clone(),
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::GrantAuthz {
+            grantee,
+            authorization,
+            expiration,
+        } => (
+            GRANT_AUTHZ_TYPE_URL,
+            MsgGrantAuthz {
+                grantee: grantee.clone(),
+                authorization: encode_authorization(authorization)?,
+                expiration: *expiration,
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::RevokeAuthz {
+            grantee,
+            authorization,
+        } => (
+            REVOKE_AUTHZ_TYPE_URL,
+            MsgRevokeAuthz {
+                grantee: grantee.clone(),
+                authorization: encode_authorization(authorization)?,
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::Exec {
+            msgs,
+        } => (
+            EXEC_TYPE_URL,
+            MsgExec {
+                msgs: msgs.iter().map(encode_tx_msg).collect::<StdResult<_>>()?,
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::RotateKey {
+            new_pubkey,
+            new_pubkey_type,
+        } => (
+            ROTATE_KEY_TYPE_URL,
+            MsgRotateKey {
+                new_pubkey: Some(encode_pubkey(new_pubkey, *new_pubkey_type)),
+            }
+            .encode_to_vec(),
+        ),
+        SdkMsg::UpdateParams {
+            params,
+        } => (
+            UPDATE_PARAMS_TYPE_URL,
+            MsgUpdateParams {
+                params: serde_json::to_vec(params)
+                    .map_err(|err| StdError::serialize_err("Params", err))?,
+            }
+            .encode_to_vec(),
+        ),
+    };
+    Ok(Any {
+        type_url: type_url.into(),
+        value,
+    })
+}
+
+fn decode_msg(any: Any) -> StdResult<SdkMsg> {
+    let value = any.value.as_slice();
+    match any.type_url.as_str() {
+        STORE_CODE_TYPE_URL => {
+            let msg = MsgStoreCode::decode(value)
+                .map_err(|err| StdError::parse_err("MsgStoreCode", err))?;
+            Ok(SdkMsg::StoreCode {
+                wasm_byte_code: msg.wasm_byte_code.into(),
+            })
+        },
+        INSTANTIATE_TYPE_URL => {
+            let msg = MsgInstantiate::decode(value)
+                .map_err(|err| StdError::parse_err("MsgInstantiate", err))?;
+            Ok(SdkMsg::Instantiate {
+                code_id: msg.code_id,
+                msg: from_json_bytes(&msg.msg)?,
+                funds: msg.funds.into_iter().map(decode_coin).collect::<StdResult<_>>()?,
+                label: msg.label,
+                admin: msg.admin,
+            })
+        },
+        EXECUTE_TYPE_URL => {
+            let msg = MsgExecute::decode(value)
+                .map_err(|err| StdError::parse_err("MsgExecute", err))?;
+            Ok(SdkMsg::Execute {
+                contract: msg.contract,
+                msg: from_json_bytes(&msg.msg)?,
+                funds: msg.funds.into_iter().map(decode_coin).collect::<StdResult<_>>()?,
+            })
+        },
+        MIGRATE_TYPE_URL => {
+            let msg = MsgMigrate::decode(value)
+                .map_err(|err| StdError::parse_err("MsgMigrate", err))?;
+            Ok(SdkMsg::Migrate {
+                contract: msg.contract,
+                code_id: msg.code_id,
+                msg: from_json_bytes(&msg.msg)?,
+            })
+        },
+        GRANT_ALLOWANCE_TYPE_URL => {
+            let msg = MsgGrantAllowance::decode(value)
+                .map_err(|err| StdError::parse_err("MsgGrantAllowance", err))?;
+            Ok(SdkMsg::GrantAllowance {
+                grantee: msg.grantee,
+                allowance: serde_json::from_slice::<Allowance>(&msg.allowance)
+                    .map_err(|err| StdError::parse_err("Allowance", err))?,
+            })
+        },
+        REVOKE_ALLOWANCE_TYPE_URL => {
+            let msg = MsgRevokeAllowance::decode(value)
+                .map_err(|err| StdError::parse_err("MsgRevokeAllowance", err))?;
+            Ok(SdkMsg::RevokeAllowance {
+                grantee: msg.grantee,
+            })
+        },
+        GRANT_AUTHZ_TYPE_URL => {
+            let msg = MsgGrantAuthz::decode(value)
+                .map_err(|err| StdError::parse_err("MsgGrantAuthz", err))?;
+            Ok(SdkMsg::GrantAuthz {
+                grantee: msg.grantee,
+                authorization: decode_authorization(&msg.authorization)?,
+                expiration: msg.expiration,
+            })
+        },
+        REVOKE_AUTHZ_TYPE_URL => {
+            let msg = MsgRevokeAuthz::decode(value)
+                .map_err(|err| StdError::parse_err("MsgRevokeAuthz", err))?;
+            Ok(SdkMsg::RevokeAuthz {
+                grantee: msg.grantee,
+                authorization: decode_authorization(&msg.authorization)?,
+            })
+        },
+        EXEC_TYPE_URL => {
+            let msg =
+                MsgExec::decode(value).map_err(|err| StdError::parse_err("MsgExec", err))?;
+            Ok(SdkMsg::Exec {
+                msgs: msg.msgs.into_iter().map(decode_tx_msg).collect::<StdResult<_>>()?,
+            })
+        },
+        ROTATE_KEY_TYPE_URL => {
+            let msg = MsgRotateKey::decode(value)
+                .map_err(|err| StdError::parse_err("MsgRotateKey", err))?;
+            let any = msg.new_pubkey.ok_or_else(|| StdError::generic_err("missing pubkey"))?;
+            let new_pubkey_type = match any.type_url.as_str() {
+                SECP256K1_PUBKEY_TYPE_URL => PubKeyType::Secp256k1,
+                ETH_SECP256K1_PUBKEY_TYPE_URL => PubKeyType::EthSecp256k1,
+                type_url => {
+                    return Err(StdError::generic_err(format!(
+                        "unsupported pubkey type: {type_url}"
+                    )));
+                },
+            };
+            Ok(SdkMsg::RotateKey {
+                new_pubkey: decode_pubkey(any, new_pubkey_type)?,
+                new_pubkey_type,
+            })
+        },
+        UPDATE_PARAMS_TYPE_URL => {
+            let msg = MsgUpdateParams::decode(value)
+                .map_err(|err| StdError::parse_err("MsgUpdateParams", err))?;
+            Ok(SdkMsg::UpdateParams {
+                params: serde_json::from_slice::<Params>(&msg.params)
+                    .map_err(|err| StdError::parse_err("Params", err))?,
+            })
+        },
+        type_url => Err(StdError::generic_err(format!("unsupported message type: {type_url}"))),
+    }
+}
+
+fn encode_authorization(authorization: &Authorization) -> StdResult<Vec<u8>> {
+    serde_json::to_vec(authorization).map_err(|err| StdError::serialize_err("Authorization", err))
+}
+
+fn decode_authorization(bytes: &[u8]) -> StdResult<Authorization> {
+    serde_json::from_slice(bytes).map_err(|err| StdError::parse_err("Authorization", err))
+}
+
+fn decode_fee(fee: ProtoFee) -> StdResult<Fee> {
+    Ok(Fee {
+        amount: fee.amount.into_iter().map(decode_coin).collect::<StdResult<_>>()?,
+        gas_limit: fee.gas_limit,
+        payer: fee.payer,
+        granter: fee.granter,
+    })
+}
+
+fn encode_coin(coin: &Coin) -> ProtoCoin {
+    ProtoCoin {
+        denom: coin.denom.clone(),
+        amount: coin.amount.to_string(),
+    }
+}
+
+fn decode_coin(coin: ProtoCoin) -> StdResult<Coin> {
+    Ok(Coin {
+        denom: coin.denom,
+        amount: coin.amount.parse::<Uint128>()?,
+    })
+}
+
+fn to_json_bytes(value: &serde_json::Value) -> StdResult<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|err| StdError::serialize_err("Value", err))
+}
+
+fn from_json_bytes(bytes: &[u8]) -> StdResult<serde_json::Value> {
+    serde_json::from_slice(bytes).map_err(|err| StdError::parse_err("Value", err))
+}
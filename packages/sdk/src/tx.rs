@@ -1,40 +1,397 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Binary;
+use cosmwasm_std::{Binary, Coin, StdResult};
 
-use crate::msg::SdkMsg;
+use crate::{
+    account::{MultisigPubKey, PubKeyType},
+    canonical::to_canonical_vec,
+    msg::SdkMsg,
+    proto,
+};
 
 /// Tendermint will provide this as JSON bytes by in the CheckTx and DeliverTx
 /// requests. The state machine should deserialize the bytes upon receipt.
+///
+/// The bytes must be in canonical form, as defined in the `canonical` module;
+/// otherwise the tx is rejected.
 #[cw_serde]
 pub struct Tx {
-    /// Transaction body, which includes the sender address, messages to be
-    /// executed in order, and some parameters for prevention of replay attacks.
+    /// Transaction body, which includes the signers, messages to be executed
+    /// in order, and some parameters for prevention of replay attacks.
     pub body: TxBody,
 
-    /// The sender's secp256k1 public key.
-    /// Optional if the accounts already exists in the state.
+    /// The signers' signatures, in the same order as the signers are listed in
+    /// the tx body.
+    pub signatures: Vec<TxSignature>,
+}
+
+/// A signer's signature of the tx body.
+#[cw_serde]
+pub struct TxSignature {
+    /// The signer's secp256k1 public key.
+    /// Optional if the accounts already exists in the state, or if the key is
+    /// Ethereum-style, in which case it is recovered from the signature.
     pub pubkey: Option<Binary>,
 
+    /// The scheme of the signer's public key.
+    /// Only used if the account doesn't exist in the state yet.
+    #[serde(default)]
+    pub pubkey_type: PubKeyType,
+
     /// Secp256k1 signature.
-    /// The content is `sha256(JSON.stringify(txbody))`, signed by the
-    /// corresponding private key.
+    /// The content is `hash(signdoc.to_sign_bytes())`, where `signdoc` is the
+    /// `SignDoc` of the tx body for this signer, signed by the corresponding
+    /// private key, and `hash` is SHA-256, or Keccak-256 for Ethereum-style
+    /// keys.
+    /// Empty for a multisig signer. For a contract account, this is opaque to
+    /// the state machine and is passed to the contract's `before_tx` hook.
     pub signature: Binary,
+
+    /// The members' signatures, if the signer is a multisig account, in which
+    /// case `pubkey` and `signature` are ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<MultiSignature>,
+
+    /// Whether the signer signed the `LegacySignDoc` of the tx body, instead
+    /// of the sign doc of the tx's sign mode, e.g. with a Ledger device.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub legacy_amino_json: bool,
+}
+
+impl TxSignature {
+    /// Return the bytes signed by this signature, given the sign mode of the
+    /// tx, the chain id, and the signer's account number and sequence.
+    pub fn sign_bytes(
+        &self,
+        sign_mode: &SignMode,
+        body: &TxBody,
+        chain_id: &str,
+        account_number: u64,
+        sequence: u64,
+    ) -> StdResult<Vec<u8>> {
+        if self.legacy_amino_json {
+            SignMode::LegacyAminoJson.sign_bytes(body, chain_id, account_number, sequence)
+        } else {
+            sign_mode.sign_bytes(body, chain_id, account_number, sequence)
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
-/// Body of the transaction. This is what the sender needs to sign.
+/// A multisig signer's signature of the tx body, which consists of its
+/// members' signatures of the same sign doc.
 #[cw_serde]
-pub struct TxBody {
-    /// The sender's address
-    pub sender: String,
+pub struct MultiSignature {
+    /// The signer's multisig public key.
+    /// Optional if the account already exists in the state.
+    pub pubkey: Option<MultisigPubKey>,
+
+    /// One for each member, in the same order as the members' pubkeys, or
+    /// `None` if the member doesn't sign. At least as many members as the
+    /// threshold must sign.
+    pub signatures: Vec<Option<Binary>>,
+}
 
+/// Body of the transaction. This is what the signers need to sign.
+#[cw_serde]
+pub struct TxBody {
     /// Identifier of the chain where this tx is to be broadcasted.
     /// Used to prevent reply attacks.
     pub chain_id: String,
 
-    /// The sender's sequence number.
+    /// The accounts that sign this tx. There must be at least one, and no
+    /// account may be listed more than once.
+    ///
+    /// The first one is the primary signer, to which the idempotency key is
+    /// attributed.
+    pub signers: Vec<SignerInfo>,
+
+    /// Wasm messages to be executed in order. Each message must be sent by one
+    /// of the signers. The tx is atomic: if any message fails, none of them
+    /// takes effect.
+    pub msgs: Vec<TxMsg>,
+
+    /// The fee to be paid for this tx, and who pays it
+    #[serde(default)]
+    pub fee: Fee,
+
+    /// An optional block height after which this tx is no longer valid.
+    /// If the tx isn't included in a block by this height, it is rejected,
+    /// so that the signers can safely replace a tx that is stuck in mempool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_height: Option<u64>,
+
+    /// An optional key chosen by the primary signer, used to deduplicate
+    /// retries. If the primary signer has already executed a tx carrying the
+    /// same key within a time window, this tx is rejected, with the original tx
+    /// hash included in the error, and the node's broadcast endpoints return
+    /// the original tx's hash and result instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+impl TxBody {
+    /// Create a tx body signed by a single signer, who sends all the messages.
+    pub fn single(
+        sender: impl Into<String>,
+        chain_id: impl Into<String>,
+        account_number: u64,
+        sequence: u64,
+        msgs: Vec<SdkMsg>,
+    ) -> Self {
+        let sender = sender.into();
+        Self {
+            chain_id: chain_id.into(),
+            signers: vec![SignerInfo {
+                address: sender.clone(),
+                account_number,
+                sequence,
+            }],
+            msgs: msgs
+                .into_iter()
+                .map(|msg| TxMsg {
+                    sender: sender.clone(),
+                    msg,
+                })
+                .collect(),
+            fee: Fee::default(),
+            timeout_height: None,
+            idempotency_key: None,
+        }
+    }
+
+    /// Return the sign doc of this tx body for the given signer, using the
+    /// chain id included in the body, and the signer's account number and
+    /// sequence.
+    pub fn sign_doc(&self, signer: &SignerInfo) -> StdResult<SignDoc> {
+        Ok(SignDoc {
+            body_bytes: to_canonical_vec(self)?.into(),
+            chain_id: self.chain_id.clone(),
+            account_number: signer.account_number,
+            sequence: signer.sequence,
+        })
+    }
+}
+
+/// An account that signs the tx, and the parameters used to prevent replay
+/// attacks of its signature.
+#[cw_serde]
+pub struct SignerInfo {
+    /// The signer's address
+    pub address: String,
+
+    /// The signer's account number.
+    /// For an account's first tx, this is the number to be assigned to it,
+    /// i.e. the number of accounts that have been created so far.
+    pub account_number: u64,
+
+    /// The signer's sequence number.
     /// Used to prvent replay attacks.
     pub sequence: u64,
+}
+
+/// The gas limit of a tx's fee unless another is set.
+pub const DEFAULT_GAS_LIMIT: u64 = 200_000;
+
+/// The fee of a tx.
+///
+/// The fee is deducted from the granter if one is specified, otherwise from the
+/// payer, and is transferred to the fee collector before any message is
+/// executed. It is kept even if the tx's messages fail. The payer must be a
+/// signer of the tx. The granter must either be a signer too, or have granted
+/// the payer a fee allowance that covers the fee.
+#[cw_serde]
+pub struct Fee {
+    /// Coins to be paid as the fee. May be empty, in which case no fee is paid.
+    pub amount: Vec<Coin>,
+
+    /// The maximum amount of gas the tx may consume. Its messages fail once
+    /// they have consumed more, along with authenticating the tx and charging
+    /// the fee.
+    pub gas_limit: u64,
+
+    /// The signer who pays the fee. If not specified, the primary signer pays.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
+
+    /// The account who pays the fee on behalf of the payer, e.g. a sponsor of
+    /// the tx. Takes precedence over the payer if specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granter: Option<String>,
+}
+
+impl Default for Fee {
+    fn default() -> Self {
+        Self {
+            amount: vec![],
+            gas_limit: DEFAULT_GAS_LIMIT,
+            payer: None,
+            granter: None,
+        }
+    }
+}
+
+/// A message, and the signer that sends it.
+#[cw_serde]
+pub struct TxMsg {
+    /// The sender's address; must be one of the tx's signers
+    pub sender: String,
+
+    pub msg: SdkMsg,
+}
+
+/// The content that each signer signs.
+///
+/// When verifying a tx, the state machine constructs the sign doc from the
+/// tx body and the chain id, account number and sequence that it expects. If
+/// any of these differs from what the signer signed, the signature is invalid.
+#[cw_serde]
+pub struct SignDoc {
+    /// The tx body, serialized as canonical JSON
+    pub body_bytes: Binary,
+
+    /// Identifier of the chain where the tx is to be broadcasted
+    pub chain_id: String,
+
+    /// The signer's account number
+    pub account_number: u64,
+
+    /// The signer's sequence number
+    pub sequence: u64,
+}
+
+impl SignDoc {
+    /// Return the bytes to be signed, which is the sign doc serialized as
+    /// canonical JSON, with `body_bytes` encoded in base64. For example:
+    ///
+    /// ```json
+    /// {"account_number":0,"body_bytes":"eyJhY2NvdW50X251bWJlciI6...","chain_id":"dev-1","sequence":1}
+    /// ```
+    pub fn to_sign_bytes(&self) -> StdResult<Vec<u8>> {
+        to_canonical_vec(self)
+    }
+}
+
+/// The content that a signer signs in place of the `SignDoc`, in the shape of
+/// Cosmos SDK's SIGN_MODE_LEGACY_AMINO_JSON, which is the only one that the
+/// Cosmos app on Ledger devices can display and sign.
+///
+/// Numbers are encoded as strings, as in amino JSON. Each message is wrapped
+/// as `{"type":"cw-sdk/{kind}","value":{..}}`, and the idempotency key is
+/// signed as the memo. The other signers of the tx aren't included, the same
+/// as in Cosmos SDK. For example:
+///
+/// ```json
+/// {"account_number":"0","chain_id":"dev-1","fee":{"amount":[],"gas":"0"},"memo":"","msgs":[{"type":"cw-sdk/execute","value":{..}}],"sequence":"1"}
+/// ```
+#[cw_serde]
+pub struct LegacySignDoc {
+    pub account_number: String,
+    pub chain_id: String,
+    pub fee: LegacyFee,
+    pub memo: String,
+    pub msgs: Vec<LegacyMsg>,
+    pub sequence: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_height: Option<String>,
+}
+
+#[cw_serde]
+pub struct LegacyFee {
+    pub amount: Vec<Coin>,
+    pub gas: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granter: Option<String>,
+}
+
+#[cw_serde]
+pub struct LegacyMsg {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: TxMsg,
+}
+
+impl LegacySignDoc {
+    pub fn new(body: &TxBody, chain_id: &str, account_number: u64, sequence: u64) -> Self {
+        Self {
+            account_number: account_number.to_string(),
+            chain_id: chain_id.into(),
+            fee: LegacyFee {
+                amount: body.fee.amount.clone(),
+                gas: body.fee.gas_limit.to_string(),
+                payer: body.fee.payer.clone(),
+                granter: body.fee.granter.clone(),
+            },
+            memo: body.idempotency_key.clone().unwrap_or_default(),
+            msgs: body
+                .msgs
+                .iter()
+                .map(|msg| LegacyMsg {
+                    ty: format!("cw-sdk/{}", msg.msg.kind()),
+                    value: msg.clone(),
+                })
+                .collect(),
+            sequence: sequence.to_string(),
+            timeout_height: body.timeout_height.map(|height| height.to_string()),
+        }
+    }
+
+    /// Return the bytes to be signed, which is the sign doc serialized as
+    /// canonical JSON.
+    pub fn to_sign_bytes(&self) -> StdResult<Vec<u8>> {
+        to_canonical_vec(self)
+    }
+}
+
+/// How the bytes to be signed are computed, which depends on how the tx is
+/// encoded when broadcasted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignMode {
+    /// The tx is encoded in canonical JSON. Each signer signs the `SignDoc`,
+    /// also encoded in canonical JSON.
+    Json,
+
+    /// The tx is encoded in protobuf. Each signer signs the `ProtoSignDoc`,
+    /// which includes the body bytes exactly as they are broadcasted.
+    /// Equivalent to Cosmos SDK's SIGN_MODE_DIRECT.
+    Direct {
+        body_bytes: Binary,
+    },
+
+    /// Each signer signs the `LegacySignDoc`, regardless of how the tx is
+    /// encoded. Equivalent to Cosmos SDK's SIGN_MODE_LEGACY_AMINO_JSON. Used
+    /// by signatures whose `legacy_amino_json` flag is set.
+    LegacyAminoJson,
+}
 
-    /// Wasm messages to be executed in order
-    pub msgs: Vec<SdkMsg>,
+impl SignMode {
+    /// Return the bytes to be signed for the tx body, given the chain id,
+    /// account number and sequence.
+    pub fn sign_bytes(
+        &self,
+        body: &TxBody,
+        chain_id: &str,
+        account_number: u64,
+        sequence: u64,
+    ) -> StdResult<Vec<u8>> {
+        match self {
+            SignMode::Json => SignDoc {
+                body_bytes: to_canonical_vec(body)?.into(),
+                chain_id: chain_id.into(),
+                account_number,
+                sequence,
+            }
+            .to_sign_bytes(),
+            SignMode::Direct {
+                body_bytes,
+            } => Ok(proto::sign_bytes(body_bytes, chain_id, account_number, sequence)),
+            SignMode::LegacyAminoJson => {
+                LegacySignDoc::new(body, chain_id, account_number, sequence).to_sign_bytes()
+            },
+        }
+    }
 }
@@ -0,0 +1,40 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Binary;
+
+/// A validator, as it is reported to Tendermint.
+///
+/// The same type is used for both validator sets and validator set updates.
+/// In an update, a power of zero means the validator is to be removed from the
+/// set.
+#[cw_serde]
+pub struct Validator {
+    /// The validator's ed25519 consensus public key
+    pub pubkey: Binary,
+
+    /// The validator's voting power
+    pub power: u64,
+}
+
+/// A validator's vote for the last block, and the extension attached to it.
+///
+/// Validators attach extensions to their precommit votes, e.g. the prices
+/// observed by an oracle. The proposer of the next block aggregates them into
+/// the block, so that the state machine can process them.
+#[cw_serde]
+pub struct ExtendedVote {
+    /// The validator's consensus address, i.e. the first 20 bytes of the
+    /// SHA-256 hash of its consensus public key
+    pub validator: Binary,
+
+    /// The validator's voting power
+    pub power: u64,
+
+    /// The round in which the vote was cast
+    pub round: u32,
+
+    pub extension: Binary,
+
+    /// The validator's ed25519 signature of the extension's canonical sign
+    /// bytes, as computed by `proto::vote_extension_sign_bytes`
+    pub extension_signature: Binary,
+}
@@ -1,93 +1,200 @@
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use cosmwasm_std::{Binary, Event as WasmEvent, Timestamp};
+use cw_sdk::{hash::HASH_LENGTH, ErrorCode, ExtendedVote, GenesisState, SdkQuery, Validator};
+use cw_state_machine::{
+    ante::CheckTxType,
+    auth,
+    error::{Error as StateMachineError, Result as StateMachineResult},
+    query::reads_committed_state,
+    TxOutcome,
+};
+use tendermint::{
+    abci::{
+        request::{self, CheckTxKind},
+        response,
+        types::{BlockSignatureInfo, ExecTxResult, ExtendedVoteInfo},
+        Code, Event,
+    },
+    block::{BlockIdFlag, Height},
+    v0_38::abci::{
+        ConsensusRequest, ConsensusResponse, InfoRequest, InfoResponse, MempoolRequest,
+        MempoolResponse, SnapshotRequest, SnapshotResponse,
+    },
+    validator, AppHash, PublicKey, Time,
+};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tower::Service;
+use tower_abci::{v038::Server, BoxError};
+use tracing::warn;
+
+use crate::{
+    listen::{remove_stale_socket, ListenAddr},
+    AppCommand, CommittedBlock, IndexConfig, QueryWorkers,
+};
+
+/// The future returned by the ABCI services.
+pub(crate) type ServiceFuture<T> = Pin<Box<dyn Future<Output = Result<T, BoxError>> + Send>>;
+
+/// ABCI application for CometBFT 0.38. Blocks are executed as a whole in the
+/// FinalizeBlock ABCI method.
+///
+/// The app serves each of the ABCI connections as a separate service, which
+/// sends commands to the `AppDriver` through a channel of its own.
+///
+/// Nodes running Tendermint 0.34 use `LegacyApp` instead.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub consensus_tx: mpsc::Sender<AppCommand>,
+    pub mempool_tx: mpsc::Sender<AppCommand>,
+    pub info_tx: mpsc::Sender<AppCommand>,
 
-use cosmwasm_std::{Attribute as WasmAttribute, BlockInfo, Event as WasmEvent, Timestamp};
-use cw_sdk::{GenesisState, SdkQuery, Tx};
-use tendermint_proto::abci::{self, Event, EventAttribute};
+    /// Which event attributes are flagged to be indexed
+    pub index: Arc<IndexConfig>,
 
-use crate::AppCommand;
+    /// The events of each committed block are sent here by the driver
+    pub events_tx: broadcast::Sender<Arc<CommittedBlock>>,
 
-#[derive(Clone, Debug)]
-pub struct App {
-    pub cmd_tx: Sender<AppCommand>,
+    /// If set, answer the queries that only read the committed state in place
+    /// of the driver
+    pub query_workers: Option<QueryWorkers>,
 }
 
 impl App {
-    fn execute_command<T>(&self, cmd: AppCommand, result_rx: &Receiver<T>) -> T {
-        // send command to AppDriver via the command channel
-        self.cmd_tx.send(cmd).unwrap_or_else(|err| {
-            panic!("failed to send command to AppDriver: {err}");
-        });
+    pub fn set_index(&mut self, index: IndexConfig) {
+        self.index = Arc::new(index);
+    }
 
-        // receive result from AppDriver via the result channel
-        result_rx.recv().unwrap_or_else(|err| {
-            panic!("failed to receive result from AppDriver: {err}");
-        })
+    /// Receive the events of each block committed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<CommittedBlock>> {
+        self.events_tx.subscribe()
     }
+
+    /// Answer a query, on a query worker if enabled and the query only reads
+    /// the committed state, or by the driver otherwise.
+    pub(crate) async fn handle_query(&self, query: SdkQuery) -> StateMachineResult<Binary> {
+        match &self.query_workers {
+            Some(workers) if reads_committed_state(&query) => workers.query(query).await,
+            _ => {
+                execute_command(&self.info_tx, |result_tx| AppCommand::Query {
+                    query,
+                    result_tx,
+                })
+                .await
+            },
+        }
+    }
+}
+
+/// Send a command to the AppDriver through the given channel, and wait for the
+/// result. If the channel is full, wait until the driver has caught up.
+pub(crate) async fn execute_command<T>(
+    cmd_tx: &mpsc::Sender<AppCommand>,
+    cmd: impl FnOnce(oneshot::Sender<T>) -> AppCommand,
+) -> T {
+    let (result_tx, result_rx) = oneshot::channel();
+
+    // send command to AppDriver via the command channel
+    cmd_tx.send(cmd(result_tx)).await.unwrap_or_else(|err| {
+        panic!("failed to send command to AppDriver: {err}");
+    });
+
+    // receive result from AppDriver via the result channel
+    result_rx.await.unwrap_or_else(|err| {
+        panic!("failed to receive result from AppDriver: {err}");
+    })
 }
 
-impl tendermint_abci::Application for App {
+/// Serve the app to CometBFT 0.38 at the given TCP or Unix socket address, until
+/// the server fails.
+pub async fn serve(app: App, listen_addr: &str) -> Result<(), BoxError> {
+    let server = Server::builder()
+        .consensus(app.clone())
+        .mempool(app.clone())
+        .info(app.clone())
+        .snapshot(app)
+        .finish()
+        .ok_or("failed to build ABCI server: missing service")?;
+
+    match ListenAddr::parse(listen_addr)? {
+        ListenAddr::Tcp(addr) => server.listen_tcp(addr).await,
+        ListenAddr::Unix(path) => {
+            remove_stale_socket(&path)?;
+            server.listen_unix(path).await
+        },
+    }
+}
+
+impl App {
     /// Provide information about the ABCI application.
     ///
-    /// TODO: `abci::Requestinfo` has three parameters: version, block_version,
+    /// TODO: `request::Info` has three parameters: version, block_version,
     /// and p2p_version. I don't know what they mean or how to handle them.
     /// For now they are just ignored.
-    fn info(&self, _request: abci::RequestInfo) -> abci::ResponseInfo {
-        let (result_tx, result_rx) = channel();
-
-        let result = self.execute_command(
-            AppCommand::Info {
-                result_tx,
-            },
-            &result_rx,
-        );
+    pub(crate) async fn info(&self) -> response::Info {
+        let result = execute_command(&self.info_tx, |result_tx| AppCommand::Info {
+            result_tx,
+        })
+        .await;
 
         let (height, app_hash) = result.unwrap_or_else(|err| {
             panic!("ABCI Info request failed with error: {err}");
         });
 
-        abci::ResponseInfo {
+        response::Info {
             data: env!("CARGO_PKG_NAME").into(),
-            version: env!("CARGO_PKG_VERSION").into(),
-            app_version: 1,
-            last_block_height: height,
-            last_block_app_hash: app_hash.to_vec().into(),
+            version: cw_state_machine::VERSION.into(),
+            app_version: cw_state_machine::APP_VERSION,
+            last_block_height: height.try_into().unwrap(),
+            last_block_app_hash: app_hash_to_abci(app_hash),
         }
     }
 
     /// Called once upon genesis.
-    fn init_chain(&self, request: abci::RequestInitChain) -> abci::ResponseInitChain {
-        let (result_tx, result_rx) = channel();
-
-        let gen_state: GenesisState = serde_json::from_slice(&request.app_state_bytes).unwrap_or_else(|err| {
-            panic!("failed to parse genesis state: {err}");
-        });
+    pub(crate) async fn init_chain(&self, request: request::InitChain) -> response::InitChain {
+        let gen_state: GenesisState =
+            serde_json::from_slice(&request.app_state_bytes).unwrap_or_else(|err| {
+                panic!("failed to parse genesis state: {err}");
+            });
+
+        let validators = request.validators.into_iter().map(abci_to_validator).collect();
+
+        let result = execute_command(&self.consensus_tx, |result_tx| AppCommand::InitChain {
+            chain_id: request.chain_id,
+            initial_height: request.initial_height.value(),
+            validators,
+            gen_state,
+            result_tx,
+        })
+        .await;
 
-        let result = self.execute_command(
-            AppCommand::InitChain {
-                chain_id: request.chain_id,
-                gen_state,
-                result_tx,
+        let (app_hash, validators) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                stall_if_safe_mode(&err).await;
+                panic!("ABCI InitChain request failed with error: {err}");
             },
-            &result_rx,
-        );
-
-        let app_hash = result.unwrap_or_else(|err| {
-            panic!("ABCI InitChain request failed with error: {err}");
-        });
+        };
 
-        abci::ResponseInitChain {
-            app_hash: app_hash.to_vec().into(),
-            ..Default::default()
+        response::InitChain {
+            consensus_params: None,
+            validators: validators.into_iter().map(validator_to_abci).collect(),
+            app_hash: app_hash_to_abci(app_hash),
         }
     }
 
     /// Query the application for data at the current or past height.
-    fn query(&self, request: abci::RequestQuery) -> abci::ResponseQuery {
+    pub(crate) async fn query(&self, request: request::Query) -> response::Query {
         let path = request.path.split('/').collect::<Vec<_>>();
 
         if path.is_empty() {
-            return abci::ResponseQuery {
-                code: 1,
+            return response::Query {
+                code: Code::from(1),
                 log: "no query path provided".into(),
                 ..Default::default()
             };
@@ -95,39 +202,41 @@ impl tendermint_abci::Application for App {
 
         match &path[0] {
             &"app" => {
-                let (result_tx, result_rx) = channel();
-
                 let query: SdkQuery = serde_json::from_slice(&request.data).unwrap_or_else(|err| {
                     panic!("failed to deserialize query message: {err}");
                 });
 
-                let result = self.execute_command(
-                    AppCommand::Query {
-                        query,
-                        result_tx,
-                    },
-                    &result_rx,
-                );
-
-                match result {
-                    Ok(response) => abci::ResponseQuery {
-                        code: 0,
+                match self.handle_query(query).await {
+                    Ok(response) => response::Query {
+                        code: Code::Ok,
                         value: response.to_vec().into(),
                         ..Default::default()
                     },
-                    Err(error) => abci::ResponseQuery {
+                    Err(error) => response::Query {
                         // TODO: we need to define error codes instead of using
                         // `1` for all errors
-                        code: 1,
+                        code: Code::from(1),
                         log: error.to_string(),
                         ..Default::default()
                     },
                 }
             },
+            &"metrics" => {
+                let metrics = execute_command(&self.info_tx, |result_tx| AppCommand::Metrics {
+                    result_tx,
+                })
+                .await;
+
+                response::Query {
+                    code: Code::Ok,
+                    value: metrics.into_bytes().into(),
+                    ..Default::default()
+                }
+            },
             &"store" => {
                 // unimplemented
-                abci::ResponseQuery {
-                    code: 1,
+                response::Query {
+                    code: Code::from(1),
                     log: "store query is not implemented yet".into(),
                     ..Default::default()
                 }
@@ -137,14 +246,14 @@ impl tendermint_abci::Application for App {
                 // however, return no error to signal that the peer should not be rejected
                 // see:
                 // https://github.com/tendermint/tendermint/blob/v0.34.x/spec/abci/apps.md#query-connection
-                abci::ResponseQuery {
-                    code: 0,
+                response::Query {
+                    code: Code::Ok,
                     log: "p2p query is not implemented yet".into(),
                     ..Default::default()
                 }
             },
-            prefix => abci::ResponseQuery {
-                code: 1,
+            prefix => response::Query {
+                code: Code::from(1),
                 log: format!("unsupported query path prefix: {prefix}"),
                 ..Default::default()
             },
@@ -152,130 +261,452 @@ impl tendermint_abci::Application for App {
     }
 
     /// Check the given transaction before putting it into the local mempool.
-    fn check_tx(&self, _request: abci::RequestCheckTx) -> abci::ResponseCheckTx {
-        Default::default()
-    }
-
-    /// Signals the beginning of a new block, prior to any `DeliverTx` calls.
-    fn begin_block(&self, request: abci::RequestBeginBlock) -> abci::ResponseBeginBlock {
-        let (result_tx, result_rx) = channel();
+    ///
+    /// The tx's priority is reported as well; CometBFT 0.38, which has removed
+    /// the priority mempool, ignores it.
+    pub(crate) async fn check_tx(&self, request: request::CheckTx) -> response::CheckTx {
+        let (tx, sign_mode) = match auth::decode_tx(&request.tx) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                let error_code = ErrorCode::from(&error);
+                return response::CheckTx {
+                    code: Code::from(error_code.code()),
+                    log: error.to_string(),
+                    codespace: error_code.codespace().into(),
+                    ..Default::default()
+                };
+            },
+        };
 
-        let header = request.header.unwrap_or_else(|| {
-            panic!("ABCI BeginBlock request failed: header is not provided");
-        });
-        let protobuf_time = header.time.unwrap_or_else(|| {
-            panic!("ABCI BeginBlock request failed: header does not contain block time");
-        });
-        let time = Timestamp::from_nanos(
-            u64::try_from(protobuf_time.seconds).unwrap() * 10u64.pow(9) +
-            u64::try_from(protobuf_time.nanos).unwrap(),
-        );
-        let block = BlockInfo {
-            height: header.height as u64,
-            time,
-            chain_id: header.chain_id,
+        let check_type = match request.kind {
+            CheckTxKind::New => CheckTxType::New,
+            CheckTxKind::Recheck => CheckTxType::Recheck,
         };
 
-        let result = self.execute_command(
-            AppCommand::BeginBlock {
-                block,
+        let result = execute_command(&self.mempool_tx, |result_tx| AppCommand::CheckTx {
+            tx,
+            sign_mode,
+            tx_len: request.tx.len(),
+            check_type,
+            result_tx,
+        })
+        .await;
+
+        match result {
+            Ok(outcome) => response::CheckTx {
+                gas_wanted: outcome.gas_wanted as i64,
+                priority: outcome.priority,
+                ..Default::default()
+            },
+            Err(error) => {
+                let error_code = ErrorCode::from(&error);
+
+                // a retried tx reports the hash of the original in its data, so
+                // that the broadcast endpoints can return the original's result
+                let data = match &error {
+                    StateMachineError::DuplicateTx {
+                        tx_hash,
+                        ..
+                    } => hex::decode(tx_hash).unwrap_or_default().into(),
+                    _ => Default::default(),
+                };
+
+                response::CheckTx {
+                    code: Code::from(error_code.code()),
+                    data,
+                    log: error.to_string(),
+                    codespace: error_code.codespace().into(),
+                    ..Default::default()
+                }
+            },
+        }
+    }
+
+    /// Propose the extended votes for the last block, followed by the valid txs
+    /// in the mempool, ordered by priority, as many as fit in a block.
+    async fn prepare_proposal(
+        &self,
+        request: request::PrepareProposal,
+    ) -> response::PrepareProposal {
+        let votes = request
+            .local_last_commit
+            .map(|commit| {
+                let round = commit.round.value();
+                commit
+                    .votes
+                    .into_iter()
+                    .filter_map(|vote| abci_to_extended_vote(vote, round))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let result = execute_command(&self.consensus_tx, |result_tx| {
+            AppCommand::PrepareProposal {
+                votes,
+                txs: request.txs.iter().map(|tx| tx.to_vec()).collect(),
+                max_tx_bytes: request.max_tx_bytes as u64,
                 result_tx,
+            }
+        })
+        .await;
+
+        // CometBFT has already limited the txs to the maximum size, so if they
+        // can't be filtered, proposing them as they are is still valid
+        match result {
+            Ok(txs) => response::PrepareProposal {
+                txs: txs.into_iter().map(Into::into).collect(),
+            },
+            Err(err) => {
+                warn!("Failed to prepare proposal at height {}: {err}", request.height);
+                response::PrepareProposal {
+                    txs: request.txs,
+                }
             },
-            &result_rx,
-        );
+        }
+    }
 
-        let events = result.unwrap_or_else(|err| {
-            panic!("ABCI BeginBlock request failed with error: {err}");
-        });
+    /// Reject a proposal only if the extended votes it includes are invalid.
+    /// Invalid txs in the proposal fail individually when the block is executed.
+    async fn process_proposal(
+        &self,
+        request: request::ProcessProposal,
+    ) -> response::ProcessProposal {
+        let height = request.height.value();
+
+        let result = execute_command(&self.consensus_tx, |result_tx| {
+            AppCommand::ProcessProposal {
+                height,
+                txs: request.txs.into_iter().map(|tx| tx.to_vec()).collect(),
+                result_tx,
+            }
+        })
+        .await;
 
-        abci::ResponseBeginBlock {
-            events: wasm_event_to_abci(events),
+        match result {
+            Ok(()) => response::ProcessProposal::Accept,
+            Err(err) => {
+                warn!("Rejecting proposal at height {height}: {err}");
+                response::ProcessProposal::Reject
+            },
         }
     }
 
-    /// Apply a transaction to the application's state.
-    fn deliver_tx(&self, request: abci::RequestDeliverTx) -> abci::ResponseDeliverTx {
-        let (result_tx, result_rx) = channel();
+    /// Attach an extension to this node's validator's vote, as provided by the
+    /// vote extension handler. If the handler fails, the vote isn't extended.
+    async fn extend_vote(&self, request: request::ExtendVote) -> response::ExtendVote {
+        let height = request.height.value();
+
+        let result = execute_command(&self.consensus_tx, |result_tx| AppCommand::ExtendVote {
+            height,
+            result_tx,
+        })
+        .await;
 
-        let tx: Tx = serde_json::from_slice(&request.tx).unwrap_or_else(|err| {
-            panic!("failed to deserialize tx: {err}");
+        let vote_extension = result.unwrap_or_else(|err| {
+            warn!("Failed to extend vote at height {height}: {err}");
+            vec![]
         });
 
-        let result = self.execute_command(
-            AppCommand::DeliverTx {
-                tx,
+        response::ExtendVote {
+            vote_extension: vote_extension.into(),
+        }
+    }
+
+    /// Verify another validator's vote extension using the vote extension
+    /// handler.
+    async fn verify_vote_extension(
+        &self,
+        request: request::VerifyVoteExtension,
+    ) -> response::VerifyVoteExtension {
+        let result = execute_command(&self.consensus_tx, |result_tx| {
+            AppCommand::VerifyVoteExtension {
+                height: request.height.value(),
+                validator: request.validator_address.as_bytes().to_vec(),
+                extension: request.vote_extension.to_vec(),
                 result_tx,
-            },
-            &result_rx,
-        );
+            }
+        })
+        .await;
 
         match result {
-            // TODO: what should we put in `data` and `log` fields?
-            // for now i just serialize the events into a JSON string as log
-            Ok(events) => abci::ResponseDeliverTx {
-                code: 0,
-                log: serde_json::to_string(&events).unwrap(),
-                events: wasm_event_to_abci(events),
-                ..Default::default()
-            },
-            Err(error) => abci::ResponseDeliverTx {
-                code: 1,
-                log: error.to_string(),
-                ..Default::default()
+            Ok(()) => response::VerifyVoteExtension::Accept,
+            Err(_) => response::VerifyVoteExtension::Reject,
+        }
+    }
+
+    /// Execute a decided block and commit it. Returns the outcome of each tx,
+    /// the validator set updates and the app hash.
+    async fn finalize_block(&self, request: request::FinalizeBlock) -> response::FinalizeBlock {
+        let result = execute_command(&self.consensus_tx, |result_tx| AppCommand::FinalizeBlock {
+            height: request.height.value(),
+            time: time_to_wasm(request.time),
+            txs: request.txs.into_iter().map(|tx| tx.to_vec()).collect(),
+            result_tx,
+        })
+        .await;
+
+        let outcome = match result {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                stall_if_safe_mode(&err).await;
+                stall_if_halted(&err).await;
+                panic!("ABCI FinalizeBlock request failed with error: {err}");
             },
+        };
+
+        response::FinalizeBlock {
+            events: wasm_event_to_abci(outcome.events, &self.index),
+            tx_results: outcome
+                .tx_results
+                .into_iter()
+                .map(|outcome| tx_outcome_to_abci(outcome, &self.index))
+                .collect(),
+            validator_updates: outcome
+                .validator_updates
+                .into_iter()
+                .map(validator_to_abci)
+                .collect(),
+            consensus_param_updates: None,
+            app_hash: app_hash_to_abci(outcome.app_hash),
         }
     }
+}
 
-    /// Signals the end of a block.
-    fn end_block(&self, _request: abci::RequestEndBlock) -> abci::ResponseEndBlock {
-        Default::default()
+impl Service<ConsensusRequest> for App {
+    type Response = ConsensusResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<ConsensusResponse>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
     }
 
-    /// Commit the current state at the current height.
-    fn commit(&self) -> abci::ResponseCommit {
-        let (result_tx, result_rx) = channel();
+    fn call(&mut self, request: ConsensusRequest) -> Self::Future {
+        let app = self.clone();
+        Box::pin(async move {
+            Ok(match request {
+                ConsensusRequest::InitChain(request) => {
+                    ConsensusResponse::InitChain(app.init_chain(request).await)
+                },
+                ConsensusRequest::PrepareProposal(request) => {
+                    ConsensusResponse::PrepareProposal(app.prepare_proposal(request).await)
+                },
+                ConsensusRequest::ProcessProposal(request) => {
+                    ConsensusResponse::ProcessProposal(app.process_proposal(request).await)
+                },
+                ConsensusRequest::ExtendVote(request) => {
+                    ConsensusResponse::ExtendVote(app.extend_vote(request).await)
+                },
+                ConsensusRequest::VerifyVoteExtension(request) => {
+                    ConsensusResponse::VerifyVoteExtension(
+                        app.verify_vote_extension(request).await,
+                    )
+                },
+                ConsensusRequest::FinalizeBlock(request) => {
+                    ConsensusResponse::FinalizeBlock(app.finalize_block(request).await)
+                },
+                // the block has already been committed in FinalizeBlock; see
+                // `StateMachine::finalize_block` for why. retain all blocks
+                ConsensusRequest::Commit => ConsensusResponse::Commit(response::Commit {
+                    data: Default::default(),
+                    retain_height: Height::default(),
+                }),
+            })
+        })
+    }
+}
 
-        let result = self.execute_command(
-            AppCommand::Commit {
-                result_tx,
-            },
-            &result_rx,
-        );
+impl Service<MempoolRequest> for App {
+    type Response = MempoolResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<MempoolResponse>;
 
-        let (height, app_hash) = result.unwrap_or_else(|err| {
-            panic!("Commit failed: {err}");
-        });
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
 
-        abci::ResponseCommit {
-            data: app_hash.to_vec().into(),
-            // TODO: I don't really know what retain_height means. I assume it
-            // means the block height that was just committed.
-            retain_height: height,
-        }
+    fn call(&mut self, request: MempoolRequest) -> Self::Future {
+        let app = self.clone();
+        Box::pin(async move {
+            Ok(match request {
+                MempoolRequest::CheckTx(request) => {
+                    MempoolResponse::CheckTx(app.check_tx(request).await)
+                },
+            })
+        })
     }
 }
 
-/// Casting CosmWasm event attributes into ABCI event attributes
-fn wasm_attrs_to_abci(wasm_attrs: Vec<WasmAttribute>) -> Vec<EventAttribute> {
-    wasm_attrs
-        .into_iter()
-        .map(|attr| EventAttribute {
-            key: attr.key.into_bytes().into(),
-            value: attr.value.into_bytes().into(),
-            // Not sure what "index" means, but Go SDK returns `true` for all attributes,
-            // so I'll do the same here =)
-            index: true,
+impl Service<InfoRequest> for App {
+    type Response = InfoResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<InfoResponse>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: InfoRequest) -> Self::Future {
+        let app = self.clone();
+        Box::pin(async move {
+            Ok(match request {
+                InfoRequest::Info(_) => InfoResponse::Info(app.info().await),
+                InfoRequest::Query(request) => InfoResponse::Query(app.query(request).await),
+                InfoRequest::Echo(request) => InfoResponse::Echo(response::Echo {
+                    message: request.message,
+                }),
+            })
         })
-        .collect()
+    }
 }
 
-/// Casting CosmWasm events into ABCI events
-fn wasm_event_to_abci(wasm_events: Vec<WasmEvent>) -> Vec<Event> {
+/// State sync is not supported; no snapshot is offered or accepted.
+impl Service<SnapshotRequest> for App {
+    type Response = SnapshotResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<SnapshotResponse>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: SnapshotRequest) -> Self::Future {
+        let response = match request {
+            SnapshotRequest::ListSnapshots => SnapshotResponse::ListSnapshots(Default::default()),
+            SnapshotRequest::OfferSnapshot(_) => {
+                SnapshotResponse::OfferSnapshot(response::OfferSnapshot::Reject)
+            },
+            SnapshotRequest::LoadSnapshotChunk(_) => {
+                SnapshotResponse::LoadSnapshotChunk(Default::default())
+            },
+            SnapshotRequest::ApplySnapshotChunk(_) => {
+                SnapshotResponse::ApplySnapshotChunk(no_snapshot_chunk())
+            },
+        };
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+/// In safe mode, the state machine refuses to execute blocks. If we panic, the
+/// consensus connection is dropped, which causes Tendermint to shut down along
+/// with its RPC server. Instead, we block the consensus connection forever, so
+/// that the chain doesn't progress but queries can still be served over the
+/// query connection.
+pub(crate) async fn stall_if_safe_mode(err: &StateMachineError) {
+    if matches!(err, StateMachineError::SafeMode) {
+        warn!("Safe mode: refusing to execute blocks, stalling the consensus connection");
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Once the halt height or time is reached, the state machine refuses to begin
+/// any further block. The last block has been committed at this point, and the
+/// driver stops, so that the node shuts down cleanly and can be upgraded. In
+/// the meantime, we block the consensus connection rather than panic.
+pub(crate) async fn stall_if_halted(err: &StateMachineError) {
+    if matches!(err, StateMachineError::Halted { .. }) {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// The response to a snapshot chunk, which is never expected since no snapshot
+/// is ever accepted.
+pub(crate) fn no_snapshot_chunk() -> response::ApplySnapshotChunk {
+    response::ApplySnapshotChunk {
+        result: response::ApplySnapshotChunkResult::Abort,
+        refetch_chunks: vec![],
+        reject_senders: vec![],
+    }
+}
+
+/// Casting CosmWasm events into ABCI events, flagging the attributes to be
+/// indexed according to the node's config
+pub(crate) fn wasm_event_to_abci(wasm_events: Vec<WasmEvent>, index: &IndexConfig) -> Vec<Event> {
     wasm_events
         .into_iter()
-        .map(|event| Event {
-            r#type: event.ty,
-            attributes: wasm_attrs_to_abci(event.attributes),
+        .map(|event| {
+            let attributes = event
+                .attributes
+                .into_iter()
+                .map(|attr| {
+                    let indexed = index.is_indexed(&event.ty, &attr.key);
+                    (attr.key, attr.value, indexed)
+                })
+                .collect::<Vec<_>>();
+            Event::new(event.ty, attributes)
         })
         .collect()
 }
+
+/// Casting the outcome of a tx into an ABCI tx result. A failed tx is reported
+/// with its error's code and codespace, which are part of the result that
+/// validators agree on, and its error message as log, which isn't.
+pub(crate) fn tx_outcome_to_abci(outcome: TxOutcome, index: &IndexConfig) -> ExecTxResult {
+    let (code, log, events, codespace) = match outcome.result {
+        // for now i just serialize the events into a JSON string as log
+        Ok(events) => (Code::Ok, serde_json::to_string(&events).unwrap(), events, ""),
+        Err(error) => {
+            let error_code = ErrorCode::from(&error);
+            (Code::from(error_code.code()), error.to_string(), vec![], error_code.codespace())
+        },
+    };
+
+    ExecTxResult {
+        code,
+        log,
+        gas_wanted: outcome.gas_wanted.try_into().unwrap_or(i64::MAX),
+        gas_used: outcome.gas_used.try_into().unwrap_or(i64::MAX),
+        events: wasm_event_to_abci(events, index),
+        codespace: codespace.into(),
+        ..Default::default()
+    }
+}
+
+/// Casting a Tendermint timestamp into a CosmWasm one
+pub(crate) fn time_to_wasm(time: Time) -> Timestamp {
+    Timestamp::from_nanos(time.unix_timestamp_nanos() as u64)
+}
+
+/// Casting the state machine's app hash into an ABCI one
+pub(crate) fn app_hash_to_abci(app_hash: [u8; HASH_LENGTH]) -> AppHash {
+    AppHash::try_from(app_hash.to_vec()).unwrap()
+}
+
+/// Casting an ABCI validator update into a cw-sdk validator.
+/// Only ed25519 consensus keys are supported.
+pub(crate) fn abci_to_validator(update: validator::Update) -> Validator {
+    let pubkey = update.pub_key.ed25519().unwrap_or_else(|| {
+        panic!("unsupported validator pubkey type: only ed25519 is supported");
+    });
+    Validator {
+        pubkey: pubkey.as_bytes().to_vec().into(),
+        power: update.power.value(),
+    }
+}
+
+/// Casting a cw-sdk validator into an ABCI validator update.
+pub(crate) fn validator_to_abci(validator: Validator) -> validator::Update {
+    validator::Update {
+        pub_key: PublicKey::from_raw_ed25519(&validator.pubkey).unwrap(),
+        power: validator.power.try_into().unwrap(),
+    }
+}
+
+/// Casting a vote for the last block, as provided by CometBFT, into a cw-sdk
+/// extended vote. Validators that didn't vote for the block are skipped.
+fn abci_to_extended_vote(vote: ExtendedVoteInfo, round: u32) -> Option<ExtendedVote> {
+    if !matches!(vote.sig_info, BlockSignatureInfo::Flag(BlockIdFlag::Commit)) {
+        return None;
+    }
+    Some(ExtendedVote {
+        validator: vote.validator.address.to_vec().into(),
+        power: vote.validator.power.value(),
+        round,
+        extension: vote.vote_extension.to_vec().into(),
+        extension_signature: vote
+            .extension_signature
+            .map(|sig| sig.as_bytes().to_vec())
+            .unwrap_or_default()
+            .into(),
+    })
+}
@@ -1,25 +1,34 @@
-use std::sync::mpsc::Sender;
+use cosmwasm_std::{Binary, BlockInfo, Event, Timestamp};
 
-use cosmwasm_std::{Binary, BlockInfo, Event};
-
-use cw_sdk::{hash::HASH_LENGTH, GenesisState, SdkQuery, Tx};
-use cw_state_machine::error::Result as StateMachineResult;
+use cw_sdk::{hash::HASH_LENGTH, ExtendedVote, GenesisState, SdkQuery, SignMode, Tx, Validator};
+use cw_state_machine::{
+    ante::{CheckTxOutcome, CheckTxType},
+    error::Result as StateMachineResult,
+    FinalizeBlockOutcome, TxOutcome,
+};
+use tokio::sync::oneshot::Sender;
 
 /// The ABCI server and the driver maintains a channel between them, and
 /// communicate by sending commands.
 /// This enum defines the commands allowed to be transmitted through the channel.
-#[derive(Debug, Clone)]
+///
+/// Each command carries a one-shot channel, through which the driver returns
+/// the result.
+#[derive(Debug)]
 pub enum AppCommand {
     /// Returns the last committed block height and app hash
     Info {
         result_tx: Sender<StateMachineResult<(i64, [u8; HASH_LENGTH])>>,
     },
 
-    /// Provide the genesis state, returns the app hash.
+    /// Provide the initial height, validator set and genesis state, returns the
+    /// app hash and the validator set that replaces the provided one, if any.
     InitChain {
         chain_id: String,
+        initial_height: u64,
+        validators: Vec<Validator>,
         gen_state: GenesisState,
-        result_tx: Sender<StateMachineResult<[u8; HASH_LENGTH]>>,
+        result_tx: Sender<StateMachineResult<([u8; HASH_LENGTH], Vec<Validator>)>>,
     },
 
     /// Provide the query message, returns the query response in binary format.
@@ -28,6 +37,11 @@ pub enum AppCommand {
         result_tx: Sender<StateMachineResult<Binary>>,
     },
 
+    /// Returns the store's metrics in Prometheus text exposition format.
+    Metrics {
+        result_tx: Sender<String>,
+    },
+
     /// Provide chain id, block height and time, return events emitted during
     /// the begin block process.
     BeginBlock {
@@ -35,10 +49,72 @@ pub enum AppCommand {
         result_tx: Sender<StateMachineResult<Vec<Event>>>,
     },
 
-    /// Provide a tx, returns the events emitted during tx execution.
-    DeliverTx {
+    /// Provide a tx, its sign mode, its size in bytes and whether it is being
+    /// rechecked, returns whether it should be admitted to (or kept in) the
+    /// mempool, and if so, the gas it wants and its priority.
+    CheckTx {
         tx: Tx,
-        result_tx: Sender<StateMachineResult<Vec<Event>>>,
+        sign_mode: SignMode,
+        tx_len: usize,
+        check_type: CheckTxType,
+        result_tx: Sender<StateMachineResult<CheckTxOutcome>>,
+    },
+
+    /// Provide a tx as it is included in the block, returns the events emitted
+    /// during tx execution or the error it failed with, along with the gas it
+    /// wanted and used.
+    DeliverTx {
+        tx: Vec<u8>,
+        result_tx: Sender<TxOutcome>,
+    },
+
+    /// Returns the events emitted during the end block process, and the
+    /// validator set updates.
+    EndBlock {
+        result_tx: Sender<StateMachineResult<(Vec<Event>, Vec<Validator>)>>,
+    },
+
+    /// Provide the height of the block being voted for, returns the extension
+    /// to attach to this node's validator's vote.
+    ExtendVote {
+        height: u64,
+        result_tx: Sender<StateMachineResult<Vec<u8>>>,
+    },
+
+    /// Provide the height of the block being voted for, and a validator's
+    /// consensus address and vote extension, returns whether it is valid.
+    VerifyVoteExtension {
+        height: u64,
+        validator: Vec<u8>,
+        extension: Vec<u8>,
+        result_tx: Sender<StateMachineResult<()>>,
+    },
+
+    /// Provide the extended votes for the last block, the mempool txs and the
+    /// maximum size of the block's txs, returns the txs to propose.
+    PrepareProposal {
+        votes: Vec<ExtendedVote>,
+        txs: Vec<Vec<u8>>,
+        max_tx_bytes: u64,
+        result_tx: Sender<StateMachineResult<Vec<Vec<u8>>>>,
+    },
+
+    /// Provide the height and raw txs of a proposed block, returns whether it
+    /// should be accepted.
+    ProcessProposal {
+        height: u64,
+        txs: Vec<Vec<u8>>,
+        result_tx: Sender<StateMachineResult<()>>,
+    },
+
+    /// Provide the height and time of a block and its raw txs, returns the
+    /// outcome of the block, which is committed. Replaces BeginBlock, DeliverTx,
+    /// EndBlock and Commit in ABCI 0.38.
+    FinalizeBlock {
+        height: u64,
+        time: Timestamp,
+        txs: Vec<Vec<u8>>,
+        result_tx: Sender<StateMachineResult<FinalizeBlockOutcome>>,
     },
 
     /// Returns the block height and app hash that was committed.
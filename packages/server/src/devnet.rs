@@ -0,0 +1,332 @@
+//! Single-node development network. Instead of being driven by CometBFT, the
+//! app produces its own blocks: either one as soon as txs are broadcasted, or
+//! one at a fixed interval.
+//!
+//! A minimal CometBFT-compatible JSON-RPC server stands in for the node's, so
+//! that the CLI and the gRPC and REST gateways work unchanged. Only the methods
+//! they use are supported: `abci_query`, `broadcast_tx_async`,
+//! `broadcast_tx_sync`, `broadcast_tx_commit` and `health`.
+
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{extract::State, routing::post, Json, Router, Server};
+use cosmwasm_std::Timestamp;
+use cw_sdk::{hash::sha256, GenesisState};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tendermint::{
+    abci::{
+        request::{self, CheckTxKind},
+        response,
+        types::ExecTxResult,
+    },
+    block::Height,
+    Hash,
+};
+use tendermint_rpc::endpoint::{abci_query, broadcast};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time,
+};
+use tower_abci::BoxError;
+
+use crate::{
+    app::{execute_command, stall_if_halted, stall_if_safe_mode, tx_outcome_to_abci},
+    App, AppCommand, CommittedBlock,
+};
+
+/// How many checked txs may wait for the next block before broadcasting
+/// blocks until there's room
+const MEMPOOL_SIZE: usize = 1000;
+
+/// JSON-RPC error codes
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A tx that has passed CheckTx, waiting to be included in the next block
+struct PendingTx {
+    tx: Vec<u8>,
+
+    /// Where the tx's result is sent once its block is committed, if the
+    /// broadcaster is waiting for it
+    result_tx: Option<oneshot::Sender<(Height, ExecTxResult)>>,
+}
+
+#[derive(Clone)]
+struct Devnet {
+    app: App,
+    mempool_tx: mpsc::Sender<PendingTx>,
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Run the app as a single-node devnet, until the RPC server fails or a block
+/// fails to execute. The genesis state is only applied if the chain hasn't been
+/// initialized yet. Blocks are produced every `block_time` if given, otherwise
+/// as soon as txs are broadcasted.
+///
+/// The JSON-RPC server listens at the address of the given CometBFT RPC URL,
+/// e.g. `http://127.0.0.1:26657`.
+pub async fn serve_devnet(
+    app: App,
+    rpc_addr: &str,
+    chain_id: String,
+    gen_state: GenesisState,
+    block_time: Option<Duration>,
+) -> Result<(), BoxError> {
+    let addr: SocketAddr = rpc_addr.strip_prefix("http://").unwrap_or(rpc_addr).parse()?;
+
+    let mut height = app.info().await.last_block_height.value();
+    if height == 0 {
+        let result = execute_command(&app.consensus_tx, |result_tx| AppCommand::InitChain {
+            chain_id,
+            initial_height: 1,
+            validators: vec![],
+            gen_state,
+            result_tx,
+        })
+        .await;
+        result?;
+    }
+
+    let (mempool_tx, mut mempool_rx) = mpsc::channel(MEMPOOL_SIZE);
+    let router = Router::new().route("/", post(rpc)).with_state(Devnet {
+        app: app.clone(),
+        mempool_tx,
+    });
+    let server = Server::bind(&addr).serve(router.into_make_service());
+
+    let producer = async move {
+        let mut interval = block_time.map(time::interval);
+        loop {
+            // wait until it's time for the next block
+            let mut pending = match &mut interval {
+                Some(interval) => {
+                    interval.tick().await;
+                    vec![]
+                },
+                None => match mempool_rx.recv().await {
+                    Some(pending_tx) => vec![pending_tx],
+                    None => return Ok::<_, BoxError>(()),
+                },
+            };
+            while let Ok(pending_tx) = mempool_rx.try_recv() {
+                pending.push(pending_tx);
+            }
+
+            height += 1;
+            produce_block(&app, height, pending).await?;
+        }
+    };
+
+    tokio::select! {
+        result = server => result.map_err(Into::into),
+        result = producer => result,
+    }
+}
+
+/// Execute and commit a block with the given txs, then send each tx's result to
+/// the broadcaster waiting for it, and the block's events to subscribers.
+async fn produce_block(app: &App, height: u64, pending: Vec<PendingTx>) -> Result<(), BoxError> {
+    let (txs, result_txs): (Vec<_>, Vec<_>) =
+        pending.into_iter().map(|pending_tx| (pending_tx.tx, pending_tx.result_tx)).unzip();
+
+    let result = execute_command(&app.consensus_tx, |result_tx| AppCommand::FinalizeBlock {
+        height,
+        time: now(),
+        txs: txs.clone(),
+        result_tx,
+    })
+    .await;
+
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            stall_if_safe_mode(&err).await;
+            stall_if_halted(&err).await;
+            return Err(err.into());
+        },
+    };
+
+    // the block is committed in FinalizeBlock, so no Commit command is sent;
+    // the events are streamed to subscribers here instead
+    let mut block = CommittedBlock::new(height);
+    block.events = outcome.events;
+    for (tx, tx_outcome) in txs.iter().zip(&outcome.tx_results) {
+        block.push_tx(tx, tx_outcome);
+    }
+    app.events_tx.send(Arc::new(block)).ok();
+
+    let block_height = Height::try_from(height)?;
+    for (tx_outcome, result_tx) in outcome.tx_results.into_iter().zip(result_txs) {
+        if let Some(result_tx) = result_tx {
+            result_tx.send((block_height, tx_outcome_to_abci(tx_outcome, &app.index))).ok();
+        }
+    }
+
+    Ok(())
+}
+
+async fn rpc(State(devnet): State<Devnet>, Json(request): Json<RpcRequest>) -> Json<Value> {
+    let result = match request.method.as_str() {
+        "abci_query" => match parse_params(request.params) {
+            Ok(params) => devnet.abci_query(params).await,
+            Err(err) => Err(err),
+        },
+        "broadcast_tx_async" | "broadcast_tx_sync" => match parse_params(request.params) {
+            Ok(params) => devnet.broadcast_tx_sync(params).await,
+            Err(err) => Err(err),
+        },
+        "broadcast_tx_commit" => match parse_params(request.params) {
+            Ok(params) => devnet.broadcast_tx_commit(params).await,
+            Err(err) => Err(err),
+        },
+        "health" => Ok(json!({})),
+        method => Err(json!({
+            "code": METHOD_NOT_FOUND,
+            "message": "Method not found",
+            "data": format!("{method} is not supported in devnet mode"),
+        })),
+    };
+
+    Json(match result {
+        Ok(result) => json!({
+            "jsonrpc": "2.0",
+            "id": request.id,
+            "result": result,
+        }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": request.id,
+            "error": error,
+        }),
+    })
+}
+
+impl Devnet {
+    async fn abci_query(&self, params: abci_query::Request) -> Result<Value, Value> {
+        let query = self
+            .app
+            .query(request::Query {
+                data: params.data.into(),
+                path: params.path.unwrap_or_default(),
+                height: Default::default(),
+                prove: false,
+            })
+            .await;
+
+        let response = abci_query::Response {
+            response: abci_query::AbciQuery {
+                code: query.code,
+                log: query.log,
+                info: query.info,
+                index: query.index,
+                key: query.key.to_vec(),
+                value: query.value.to_vec(),
+                proof: None,
+                height: query.height,
+                codespace: query.codespace,
+            },
+        };
+        to_result(&response)
+    }
+
+    /// Check the tx and put it into the mempool if it passes. Async broadcasts
+    /// are handled the same way, as CheckTx is near instant anyway.
+    async fn broadcast_tx_sync(&self, params: broadcast::tx_sync::Request) -> Result<Value, Value> {
+        let hash = tx_hash(&params.tx);
+        let check_tx = self.check_and_push(params.tx, None).await;
+
+        let response = broadcast::tx_sync::Response {
+            codespace: check_tx.codespace,
+            code: check_tx.code,
+            data: check_tx.data,
+            log: check_tx.log,
+            hash,
+        };
+        to_result(&response)
+    }
+
+    /// Check the tx and, if it passes, wait for it to be committed.
+    async fn broadcast_tx_commit(
+        &self,
+        params: broadcast::tx_commit::Request,
+    ) -> Result<Value, Value> {
+        let hash = tx_hash(&params.tx);
+        let (result_tx, result_rx) = oneshot::channel();
+        let check_tx = self.check_and_push(params.tx, Some(result_tx)).await;
+
+        let (height, tx_result) = if check_tx.code.is_ok() {
+            result_rx.await.unwrap_or_default()
+        } else {
+            Default::default()
+        };
+
+        let response = broadcast::tx_commit::Response {
+            check_tx,
+            tx_result,
+            hash,
+            height,
+        };
+        to_result(&response)
+    }
+
+    async fn check_and_push(
+        &self,
+        tx: Vec<u8>,
+        result_tx: Option<oneshot::Sender<(Height, ExecTxResult)>>,
+    ) -> response::CheckTx {
+        let check_tx = self
+            .app
+            .check_tx(request::CheckTx {
+                tx: tx.clone().into(),
+                kind: CheckTxKind::New,
+            })
+            .await;
+
+        if check_tx.code.is_ok() {
+            let pending_tx = PendingTx {
+                tx,
+                result_tx,
+            };
+            // the block producer only stops along with the RPC server
+            self.mempool_tx.send(pending_tx).await.ok();
+        }
+
+        check_tx
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, Value> {
+    serde_json::from_value(params).map_err(|err| {
+        json!({
+            "code": INVALID_PARAMS,
+            "message": "Invalid params",
+            "data": err.to_string(),
+        })
+    })
+}
+
+fn to_result(response: &impl serde::Serialize) -> Result<Value, Value> {
+    Ok(serde_json::to_value(response).expect("failed to serialize RPC response"))
+}
+
+fn tx_hash(tx: &[u8]) -> Hash {
+    Hash::Sha256(sha256(tx).try_into().unwrap())
+}
+
+/// The current time, used as the time of the block being produced
+fn now() -> Timestamp {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Timestamp::from_nanos(since_epoch.as_nanos() as u64)
+}
@@ -1,45 +1,292 @@
-use std::sync::mpsc::Receiver;
+use std::sync::Arc;
 
-use cw_state_machine::StateMachine;
+use cw_state_machine::{error::Error as StateMachineError, StateMachine};
+use cw_store::Snapshot;
+use tokio::sync::{
+    broadcast,
+    mpsc::{self, Receiver},
+    oneshot::Sender,
+    watch,
+};
 
-use crate::AppCommand;
+use tracing::info;
+
+use crate::{App, AppCommand, CommittedBlock, QueryWorkers, EVENT_QUEUE_SIZE};
+
+/// How many commands each ABCI connection may queue up for the driver. Once a
+/// connection's queue is full, it waits for the driver to catch up before it
+/// reads more requests from Tendermint.
+pub const COMMAND_QUEUE_SIZE: usize = 100;
 
 /// The driver is a wrapper around the actual state machine.
-/// It maintains a channel with the ABCI server, and performs actions or queries
-/// on the state machine on request for the ABCI server.
+/// It maintains a channel with each of the ABCI server's connections, and
+/// performs actions or queries on the state machine on request for them.
 pub struct AppDriver {
     pub state_machine: StateMachine,
-    pub cmd_rx: Receiver<AppCommand>,
+
+    /// Commands from the consensus connection, which executes blocks
+    pub consensus_rx: Receiver<AppCommand>,
+
+    /// Commands from the mempool connection, which checks txs
+    pub mempool_rx: Receiver<AppCommand>,
+
+    /// Commands from the info connection, which serves queries
+    pub info_rx: Receiver<AppCommand>,
+
+    /// The events of the block being executed, to be sent to subscribers once
+    /// the block is committed
+    pub pending_block: Option<CommittedBlock>,
+
+    /// Where the events of committed blocks are sent
+    pub events_tx: broadcast::Sender<Arc<CommittedBlock>>,
+
+    /// Where the snapshot of the committed state is published for the query
+    /// workers every time a block is committed, if they are enabled
+    pub snapshot_tx: Option<watch::Sender<Snapshot>>,
+
+    /// Whether the halt height or time has been reached, after which the driver
+    /// stops performing commands
+    pub halted: bool,
 }
 
 impl AppDriver {
-    pub fn run(&mut self) {
+    /// Create a driver for the given state machine, along with the app that
+    /// sends it commands on behalf of the ABCI server's connections.
+    pub fn new(state_machine: StateMachine) -> (Self, App) {
+        let (consensus_tx, consensus_rx) = mpsc::channel(COMMAND_QUEUE_SIZE);
+        let (mempool_tx, mempool_rx) = mpsc::channel(COMMAND_QUEUE_SIZE);
+        let (info_tx, info_rx) = mpsc::channel(COMMAND_QUEUE_SIZE);
+        let (events_tx, _) = broadcast::channel(EVENT_QUEUE_SIZE);
+
+        let driver = Self {
+            state_machine,
+            consensus_rx,
+            mempool_rx,
+            info_rx,
+            pending_block: None,
+            events_tx: events_tx.clone(),
+            snapshot_tx: None,
+            halted: false,
+        };
+        let app = App {
+            consensus_tx,
+            mempool_tx,
+            info_tx,
+            index: Default::default(),
+            events_tx,
+            query_workers: None,
+        };
+
+        (driver, app)
+    }
+
+    /// Have the given app answer the queries that only read the committed
+    /// state on up to `workers` threads at a time, from a snapshot of the
+    /// state, instead of sending them to the driver. The queries that need the
+    /// state machine itself, such as simulations, are still sent to the
+    /// driver.
+    ///
+    /// The snapshot holds a copy of the whole state in memory.
+    pub fn enable_query_workers(&mut self, app: &mut App, workers: usize) {
+        self.state_machine.enable_snapshot();
+        let snapshot = self.state_machine.snapshot().expect("snapshot has just been enabled");
+        let (snapshot_tx, snapshot_rx) = watch::channel(snapshot);
+
+        self.snapshot_tx = Some(snapshot_tx);
+        app.query_workers = Some(QueryWorkers::new(
+            snapshot_rx,
+            workers,
+            self.state_machine.wasm_config(),
+            self.state_machine.module_cache(),
+        ));
+    }
+
+    /// Perform commands until all connections are closed, or the halt height or
+    /// time is reached.
+    ///
+    /// The state machine can only perform one command at a time, since the
+    /// Merk store is not thread safe. Whenever several connections are waiting,
+    /// the consensus connection's command is performed first, then the mempool
+    /// connection's, so that a burst of txs or queries doesn't delay blocks.
+    /// A command that is already being performed, however long, is not
+    /// interrupted. To keep heavy queries from holding up blocks this way,
+    /// enable the query workers.
+    pub async fn run(&mut self) {
         loop {
-            match self.cmd_rx.recv().unwrap() {
-                AppCommand::Info {
-                    result_tx,
-                } => result_tx.send(self.state_machine.info()).unwrap(),
-                AppCommand::InitChain {
-                    chain_id,
-                    gen_state,
-                    result_tx,
-                } => result_tx.send(self.state_machine.init_chain(chain_id, gen_state)).unwrap(),
-                AppCommand::Query {
-                    query,
-                    result_tx,
-                } => result_tx.send(self.state_machine.query(query)).unwrap(),
-                AppCommand::BeginBlock {
-                    block,
-                    result_tx,
-                } => result_tx.send(self.state_machine.begin_block(block)).unwrap(),
-                AppCommand::DeliverTx {
-                    tx,
-                    result_tx,
-                } => result_tx.send(self.state_machine.deliver_tx(tx)).unwrap(),
-                AppCommand::Commit {
-                    result_tx,
-                } => result_tx.send(self.state_machine.commit()).unwrap(),
+            let cmd = tokio::select! {
+                biased;
+                Some(cmd) = self.consensus_rx.recv() => cmd,
+                Some(cmd) = self.mempool_rx.recv() => cmd,
+                Some(cmd) = self.info_rx.recv() => cmd,
+                else => return,
+            };
+            self.perform(cmd);
+            if self.halted {
+                return;
             }
         }
     }
+
+    fn perform(&mut self, cmd: AppCommand) {
+        match cmd {
+            AppCommand::Info {
+                result_tx,
+            } => reply(result_tx, self.state_machine.info()),
+            AppCommand::InitChain {
+                chain_id,
+                initial_height,
+                validators,
+                gen_state,
+                result_tx,
+            } => {
+                let result =
+                    self.state_machine.init_chain(chain_id, initial_height, validators, gen_state);
+                reply(result_tx, result)
+            },
+            AppCommand::Query {
+                query,
+                result_tx,
+            } => reply(result_tx, self.state_machine.query(query)),
+            AppCommand::Metrics {
+                result_tx,
+            } => reply(result_tx, self.state_machine.metrics()),
+            AppCommand::BeginBlock {
+                block,
+                result_tx,
+            } => {
+                let height = block.height;
+                let result = self.state_machine.begin_block(block);
+                self.check_halted(&result);
+                if let Ok(events) = &result {
+                    let mut pending_block = CommittedBlock::new(height);
+                    pending_block.events.extend(events.iter().cloned());
+                    self.pending_block = Some(pending_block);
+                }
+                reply(result_tx, result)
+            },
+            AppCommand::CheckTx {
+                tx,
+                sign_mode,
+                tx_len,
+                check_type,
+                result_tx,
+            } => {
+                let result = self.state_machine.check_tx(&tx, &sign_mode, tx_len, check_type);
+                reply(result_tx, result)
+            },
+            AppCommand::DeliverTx {
+                tx,
+                result_tx,
+            } => {
+                let outcome = self.state_machine.deliver_raw_tx(&tx);
+                if let Some(pending_block) = &mut self.pending_block {
+                    pending_block.push_tx(&tx, &outcome);
+                }
+                reply(result_tx, outcome)
+            },
+            AppCommand::EndBlock {
+                result_tx,
+            } => {
+                let result = self.state_machine.end_block();
+                if let (Some(pending_block), Ok((events, _))) = (&mut self.pending_block, &result) {
+                    pending_block.events.extend(events.iter().cloned());
+                }
+                reply(result_tx, result)
+            },
+            AppCommand::ExtendVote {
+                height,
+                result_tx,
+            } => reply(result_tx, self.state_machine.extend_vote(height)),
+            AppCommand::VerifyVoteExtension {
+                height,
+                validator,
+                extension,
+                result_tx,
+            } => {
+                let result =
+                    self.state_machine.verify_vote_extension(height, &validator, &extension);
+                reply(result_tx, result)
+            },
+            AppCommand::PrepareProposal {
+                votes,
+                txs,
+                max_tx_bytes,
+                result_tx,
+            } => {
+                let result = self.state_machine.prepare_proposal(votes, txs, max_tx_bytes);
+                reply(result_tx, result)
+            },
+            AppCommand::ProcessProposal {
+                height,
+                txs,
+                result_tx,
+            } => reply(result_tx, self.state_machine.process_proposal(height, &txs)),
+            AppCommand::FinalizeBlock {
+                height,
+                time,
+                txs,
+                result_tx,
+            } => {
+                let result = self.state_machine.finalize_block(height, time, &txs);
+                self.check_halted(&result);
+                if let Ok(outcome) = &result {
+                    let mut pending_block = CommittedBlock::new(height);
+                    pending_block.events = outcome.events.clone();
+                    for (tx, tx_outcome) in txs.iter().zip(&outcome.tx_results) {
+                        pending_block.push_tx(tx, tx_outcome);
+                    }
+                    self.pending_block = Some(pending_block);
+                    // the block is committed in FinalizeBlock already
+                    self.publish_snapshot();
+                }
+                reply(result_tx, result)
+            },
+            AppCommand::Commit {
+                result_tx,
+            } => {
+                let result = self.state_machine.commit();
+                if result.is_ok() {
+                    self.publish_snapshot();
+                }
+                let pending_block = self.pending_block.take();
+                if let (Some(block), Ok(_)) = (pending_block, &result) {
+                    // there may be no subscribers, in which case the events are
+                    // dropped
+                    self.events_tx.send(Arc::new(block)).ok();
+                }
+                reply(result_tx, result)
+            },
+        }
+    }
+
+    /// Note whether the state machine has refused to begin a block because the
+    /// halt height or time is reached.
+    fn check_halted<T>(&mut self, result: &Result<T, StateMachineError>) {
+        if let Err(StateMachineError::Halted {
+            height,
+        }) = result
+        {
+            info!("Halted after committing block {height}, shutting down");
+            self.halted = true;
+        }
+    }
+
+    /// Publish the snapshot of the state as of the block just committed to the
+    /// query workers, if enabled. Queries already running keep reading the
+    /// previous one.
+    fn publish_snapshot(&self) {
+        if let (Some(snapshot_tx), Some(snapshot)) =
+            (&self.snapshot_tx, self.state_machine.snapshot())
+        {
+            // the workers hold a receiver for as long as the app exists, and
+            // if it's gone, there's no one to publish to anyway
+            snapshot_tx.send(snapshot).ok();
+        }
+    }
+}
+
+/// Return the result of a command to the connection that sent it. The result
+/// is dropped if the connection has been closed in the meantime.
+fn reply<T>(result_tx: Sender<T>, result: T) {
+    result_tx.send(result).ok();
 }
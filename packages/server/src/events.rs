@@ -0,0 +1,116 @@
+use cosmwasm_std::Event;
+use cw_sdk::hash::sha256;
+use cw_state_machine::TxOutcome;
+use serde::{Deserialize, Serialize};
+
+/// How many committed blocks are buffered for each subscriber. A subscriber
+/// that falls further behind is disconnected.
+pub const EVENT_QUEUE_SIZE: usize = 100;
+
+/// The events emitted in a committed block, as streamed to subscribers.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommittedBlock {
+    pub height: u64,
+
+    /// Events emitted at the beginning and the end of the block
+    pub events: Vec<Event>,
+
+    /// The events emitted by each tx, in the order the txs were executed
+    pub txs: Vec<CommittedTx>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommittedTx {
+    /// SHA-256 hash of the tx bytes, hex-encoded
+    pub hash: String,
+
+    /// Events emitted by the tx. Empty if the tx failed.
+    pub events: Vec<Event>,
+}
+
+impl CommittedBlock {
+    pub fn new(height: u64) -> Self {
+        Self {
+            height,
+            events: vec![],
+            txs: vec![],
+        }
+    }
+
+    /// Record the outcome of the next tx of the block.
+    pub fn push_tx(&mut self, tx: &[u8], outcome: &TxOutcome) {
+        self.txs.push(CommittedTx {
+            hash: hex::encode_upper(sha256(tx)),
+            events: outcome.result.as_ref().map(Clone::clone).unwrap_or_default(),
+        });
+    }
+
+    /// Return the block with only the events that match the filter, or `None`
+    /// if none match. Txs without matching events are left out.
+    pub fn filter(&self, filter: &EventFilter) -> Option<Self> {
+        let matching = |events: &[Event]| {
+            events.iter().filter(|event| filter.matches(event)).cloned().collect::<Vec<_>>()
+        };
+
+        let events = matching(&self.events);
+        let txs = self
+            .txs
+            .iter()
+            .filter_map(|tx| {
+                let events = matching(&tx.events);
+                (!events.is_empty()).then(|| CommittedTx {
+                    hash: tx.hash.clone(),
+                    events,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if events.is_empty() && txs.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            height: self.height,
+            events,
+            txs,
+        })
+    }
+}
+
+/// Which events a subscriber is interested in. An event matches if it meets
+/// all of the given conditions.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventFilter {
+    /// The event type, e.g. `wasm`
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+
+    /// The address of the contract that emitted the event
+    pub contract: Option<String>,
+
+    /// The key of an attribute the event must have
+    pub key: Option<String>,
+
+    /// The value of that attribute. Ignored if no key is given.
+    pub value: Option<String>,
+}
+
+impl EventFilter {
+    pub fn is_empty(&self) -> bool {
+        self.ty.is_none() && self.contract.is_none() && self.key.is_none()
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        let has_attribute = |key: &str, value: Option<&str>| {
+            event.attributes.iter().any(|attr| {
+                attr.key == key && value.map_or(true, |value| attr.value == value)
+            })
+        };
+
+        self.ty.as_ref().map_or(true, |ty| &event.ty == ty)
+            && self.contract.as_deref().map_or(true, |contract| {
+                has_attribute("_contract_address", Some(contract))
+            })
+            && self.key.as_deref().map_or(true, |key| has_attribute(key, self.value.as_deref()))
+    }
+}
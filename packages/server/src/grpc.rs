@@ -0,0 +1,400 @@
+//! gRPC services serving the state machine's queries, and broadcasting and
+//! simulating txs, so that clients and block explorers can interact with the
+//! chain through generated clients, instead of encoding `SdkQuery` messages
+//! into ABCI queries themselves.
+//!
+//! Queries and simulations are answered the same way as ABCI queries, by the
+//! AppDriver or a query worker, from the last committed state. Broadcasted
+//! txs are forwarded to CometBFT's RPC, which relays them to the app's mempool;
+//! a retry of a tx already executed with the same idempotency key is answered
+//! with the original tx's result.
+
+use std::net::SocketAddr;
+
+use cosmwasm_std::{from_binary, ContractResult, Event};
+use cw_sdk::{
+    hash::sha256, Account, AccountResponse, CodeResponse, ContractResponse, ErrorCode,
+    InfoResponse, PubKeyType, SdkQuery, SimulateResponse, TxResponse, WasmRawResponse,
+    WasmSmartResponse,
+};
+use cw_state_machine::{auth, error::Error as StateMachineError};
+use serde::de::DeserializeOwned;
+use tendermint::abci::Code;
+use tendermint_rpc::{Client, HttpClient};
+use tonic::{transport::Server, Request, Response, Status};
+use tower_abci::BoxError;
+
+use crate::App;
+
+pub mod proto {
+    tonic::include_proto!("cw_sdk.v1");
+}
+
+use proto::{
+    query_account_response, query_server::QueryServer, service_server::ServiceServer,
+    BroadcastMode, BroadcastTxRequest, BroadcastTxResponse, QueryAccountRequest,
+    QueryAccountResponse, QueryCodeRequest, QueryCodeResponse, QueryContractRequest,
+    QueryContractResponse, QueryInfoRequest, QueryInfoResponse, QueryWasmRawRequest,
+    QueryWasmRawResponse, QueryWasmSmartRequest, QueryWasmSmartResponse, SimulateRequest,
+};
+
+#[derive(Clone, Debug)]
+pub struct GrpcQuery {
+    pub app: App,
+}
+
+#[derive(Clone, Debug)]
+pub struct GrpcTx {
+    pub app: App,
+
+    /// Client of the CometBFT RPC that txs are broadcasted through
+    pub rpc: HttpClient,
+}
+
+/// Serve the gRPC services at the given TCP address, e.g. `127.0.0.1:9090`,
+/// until the server fails. Txs are broadcasted through the CometBFT RPC at the
+/// given URL, e.g. `http://127.0.0.1:26657`.
+pub async fn serve_grpc(app: App, listen_addr: &str, rpc_addr: &str) -> Result<(), BoxError> {
+    let addr: SocketAddr = listen_addr.parse()?;
+    let rpc = HttpClient::new(rpc_addr)?;
+    Server::builder()
+        .add_service(QueryServer::new(GrpcQuery {
+            app: app.clone(),
+        }))
+        .add_service(ServiceServer::new(GrpcTx {
+            app,
+            rpc,
+        }))
+        .serve(addr)
+        .await
+        .map_err(Into::into)
+}
+
+/// Send a query to the AppDriver or a query worker, and decode the response.
+pub(crate) async fn query<T: DeserializeOwned>(app: &App, query: SdkQuery) -> Result<T, Status> {
+    let response = app.handle_query(query).await.map_err(error_to_status)?;
+    from_binary(&response).map_err(|err| Status::internal(err.to_string()))
+}
+
+pub(crate) fn error_to_status(error: StateMachineError) -> Status {
+    let message = error.to_string();
+    match ErrorCode::from(&error) {
+        ErrorCode::NotFound => Status::not_found(message),
+        ErrorCode::InvalidEncoding | ErrorCode::InvalidAddress | ErrorCode::InvalidLabel => {
+            Status::invalid_argument(message)
+        },
+        ErrorCode::Unsupported => Status::unimplemented(message),
+        _ => Status::internal(message),
+    }
+}
+
+#[tonic::async_trait]
+impl proto::query_server::Query for GrpcQuery {
+    async fn info(
+        &self,
+        _request: Request<QueryInfoRequest>,
+    ) -> Result<Response<QueryInfoResponse>, Status> {
+        let info: InfoResponse = query(&self.app, SdkQuery::Info {}).await?;
+        let block = info.last_committed_block;
+        Ok(Response::new(QueryInfoResponse {
+            height: block.height,
+            time: block.time.nanos(),
+            chain_id: block.chain_id,
+            code_count: info.code_count,
+            account_count: info.account_count,
+        }))
+    }
+
+    async fn account(
+        &self,
+        request: Request<QueryAccountRequest>,
+    ) -> Result<Response<QueryAccountResponse>, Status> {
+        let AccountResponse {
+            address,
+            account,
+        } = query(
+            &self.app,
+            SdkQuery::Account {
+                address: request.into_inner().address,
+            },
+        )
+        .await?;
+        Ok(Response::new(QueryAccountResponse {
+            address,
+            account: Some(account_to_proto(account)),
+        }))
+    }
+
+    async fn code(
+        &self,
+        request: Request<QueryCodeRequest>,
+    ) -> Result<Response<QueryCodeResponse>, Status> {
+        let request = request.into_inner();
+        let code: CodeResponse = query(
+            &self.app,
+            SdkQuery::Code {
+                code_id: request.code_id,
+                with_byte_code: request.with_byte_code,
+            },
+        )
+        .await?;
+        Ok(Response::new(QueryCodeResponse {
+            code_id: code.code_id,
+            wasm_byte_code: code.wasm_byte_code.map(Into::into).unwrap_or_default(),
+            code_hash: code.code_hash,
+            size: code.size,
+        }))
+    }
+
+    async fn contract(
+        &self,
+        request: Request<QueryContractRequest>,
+    ) -> Result<Response<QueryContractResponse>, Status> {
+        let contract: ContractResponse = query(
+            &self.app,
+            SdkQuery::Contract {
+                label: request.into_inner().label,
+            },
+        )
+        .await?;
+        Ok(Response::new(QueryContractResponse {
+            address: contract.address,
+            code_id: contract.code_id,
+            label: contract.label,
+            admin: contract.admin.unwrap_or_default(),
+        }))
+    }
+
+    async fn wasm_raw(
+        &self,
+        request: Request<QueryWasmRawRequest>,
+    ) -> Result<Response<QueryWasmRawResponse>, Status> {
+        let request = request.into_inner();
+        let response: WasmRawResponse = query(
+            &self.app,
+            SdkQuery::WasmRaw {
+                contract: request.contract,
+                key: request.key.into(),
+            },
+        )
+        .await?;
+        Ok(Response::new(QueryWasmRawResponse {
+            value: response.value.map(Into::into),
+        }))
+    }
+
+    async fn wasm_smart(
+        &self,
+        request: Request<QueryWasmSmartRequest>,
+    ) -> Result<Response<QueryWasmSmartResponse>, Status> {
+        let request = request.into_inner();
+        let msg = serde_json::from_slice(&request.msg)
+            .map_err(|err| Status::invalid_argument(format!("invalid query message: {err}")))?;
+        let response: WasmSmartResponse = query(
+            &self.app,
+            SdkQuery::WasmSmart {
+                contract: request.contract,
+                msg,
+            },
+        )
+        .await?;
+        match response.result {
+            ContractResult::Ok(data) => Ok(Response::new(QueryWasmSmartResponse {
+                data: data.into(),
+            })),
+            ContractResult::Err(err) => Err(Status::unknown(err)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl proto::service_server::Service for GrpcTx {
+    async fn broadcast_tx(
+        &self,
+        request: Request<BroadcastTxRequest>,
+    ) -> Result<Response<BroadcastTxResponse>, Status> {
+        let request = request.into_inner();
+        let hash = sha256(&request.tx_bytes);
+
+        let response = match request.mode() {
+            BroadcastMode::Async => {
+                self.rpc.broadcast_tx_async(request.tx_bytes).await.map_err(rpc_error_to_status)?;
+                BroadcastTxResponse {
+                    hash,
+                    ..Default::default()
+                }
+            },
+            BroadcastMode::Sync => {
+                let response = self
+                    .rpc
+                    .broadcast_tx_sync(request.tx_bytes)
+                    .await
+                    .map_err(rpc_error_to_status)?;
+                let retry = BroadcastTxResponse {
+                    hash,
+                    code: response.code.value(),
+                    codespace: codespace(response.code),
+                    log: response.log,
+                    ..Default::default()
+                };
+                self.dedup(retry, &response.data).await
+            },
+            BroadcastMode::Commit => {
+                let response = self
+                    .rpc
+                    .broadcast_tx_commit(request.tx_bytes)
+                    .await
+                    .map_err(rpc_error_to_status)?;
+
+                // if the tx failed CheckTx, it wasn't included in a block
+                let check_tx = response.check_tx;
+                if check_tx.code.is_err() {
+                    let retry = BroadcastTxResponse {
+                        hash,
+                        code: check_tx.code.value(),
+                        codespace: check_tx.codespace,
+                        log: check_tx.log,
+                        gas_wanted: check_tx.gas_wanted as u64,
+                        gas_used: check_tx.gas_used as u64,
+                        ..Default::default()
+                    };
+                    self.dedup(retry, &check_tx.data).await
+                } else {
+                    let tx_result = response.tx_result;
+                    BroadcastTxResponse {
+                        hash,
+                        code: tx_result.code.value(),
+                        codespace: tx_result.codespace,
+                        log: tx_result.log,
+                        height: response.height.value(),
+                        gas_wanted: tx_result.gas_wanted as u64,
+                        gas_used: tx_result.gas_used as u64,
+                    }
+                }
+            },
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn simulate(
+        &self,
+        request: Request<SimulateRequest>,
+    ) -> Result<Response<proto::SimulateResponse>, Status> {
+        let (tx, _) = auth::decode_tx(&request.into_inner().tx_bytes).map_err(error_to_status)?;
+        let response: SimulateResponse = query(
+            &self.app,
+            SdkQuery::Simulate {
+                tx,
+            },
+        )
+        .await?;
+        Ok(Response::new(proto::SimulateResponse {
+            gas_used: response.gas_used,
+            events: response.events.into_iter().map(event_to_proto).collect(),
+            data: response.data.into_iter().map(|data| data.unwrap_or_default().into()).collect(),
+        }))
+    }
+}
+
+impl GrpcTx {
+    /// If CheckTx rejected a tx as a retry of one already executed with the
+    /// same idempotency key, return the result of the original instead, which
+    /// CheckTx reports the hash of in its data.
+    ///
+    /// The original's result is looked up in this node's tx index. If it isn't
+    /// there, e.g. because the original was executed before the node was synced
+    /// from a snapshot, the retry's error is returned with the original's hash.
+    async fn dedup(&self, retry: BroadcastTxResponse, data: &[u8]) -> BroadcastTxResponse {
+        if retry.code != ErrorCode::DuplicateTx.code() || data.is_empty() {
+            return retry;
+        }
+
+        let query = SdkQuery::Tx {
+            hash: hex::encode_upper(data),
+        };
+        match query::<TxResponse>(&self.app, query).await {
+            Ok(original) => BroadcastTxResponse {
+                hash: data.to_vec(),
+                code: original.code,
+                codespace: original.codespace,
+                log: original.log,
+                height: original.height,
+                gas_wanted: original.gas_wanted,
+                gas_used: original.gas_used,
+            },
+            Err(_) => BroadcastTxResponse {
+                hash: data.to_vec(),
+                ..retry
+            },
+        }
+    }
+}
+
+fn rpc_error_to_status(error: tendermint_rpc::Error) -> Status {
+    Status::unavailable(format!("failed to broadcast tx: {error}"))
+}
+
+/// The codespace of a CheckTx result code. The RPC doesn't report it in sync
+/// mode, but codes are unique across codespaces.
+fn codespace(code: Code) -> String {
+    ErrorCode::from_code(code.value())
+        .map(|error_code| error_code.codespace().into())
+        .unwrap_or_default()
+}
+
+fn event_to_proto(event: Event) -> proto::Event {
+    proto::Event {
+        r#type: event.ty,
+        attributes: event
+            .attributes
+            .into_iter()
+            .map(|attr| proto::EventAttribute {
+                key: attr.key,
+                value: attr.value,
+            })
+            .collect(),
+    }
+}
+
+fn account_to_proto(account: Account<String>) -> query_account_response::Account {
+    match account {
+        Account::Base {
+            pubkey,
+            pubkey_type,
+            account_number,
+            sequence,
+        } => query_account_response::Account::Base(proto::BaseAccount {
+            pubkey: pubkey.into(),
+            pubkey_type: pubkey_type_to_proto(pubkey_type) as i32,
+            account_number,
+            sequence,
+        }),
+        Account::Multisig {
+            pubkey,
+            account_number,
+            sequence,
+        } => query_account_response::Account::Multisig(proto::MultisigAccount {
+            pubkeys: pubkey.pubkeys.into_iter().map(Into::into).collect(),
+            threshold: pubkey.threshold,
+            account_number,
+            sequence,
+        }),
+        Account::Contract {
+            code_id,
+            label,
+            admin,
+        } => query_account_response::Account::Contract(proto::ContractAccount {
+            code_id,
+            label,
+            admin: admin.unwrap_or_default(),
+        }),
+    }
+}
+
+fn pubkey_type_to_proto(pubkey_type: PubKeyType) -> proto::PubKeyType {
+    match pubkey_type {
+        PubKeyType::Secp256k1 => proto::PubKeyType::Secp256k1,
+        PubKeyType::EthSecp256k1 => proto::PubKeyType::EthSecp256k1,
+    }
+}
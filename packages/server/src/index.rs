@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Which event attributes are flagged to be indexed by Tendermint's tx indexer,
+/// so that txs can be searched by them with `tx_search` queries, e.g.
+/// `wasm._contract_address='cw1...'`.
+///
+/// The index flags aren't part of the tx results that validators agree on, so
+/// each node may index different attributes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Whether the attributes of event types that aren't listed in `events` are
+    /// indexed
+    pub index_all: bool,
+
+    /// The attributes to index, or not to, for each event type
+    pub events: BTreeMap<String, AttributeFilter>,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            index_all: true,
+            events: BTreeMap::new(),
+        }
+    }
+}
+
+impl IndexConfig {
+    /// Whether the attribute with the given key of events of the given type is
+    /// indexed.
+    pub fn is_indexed(&self, ty: &str, key: &str) -> bool {
+        match self.events.get(ty) {
+            Some(filter) => filter.is_indexed(key),
+            None => self.index_all,
+        }
+    }
+}
+
+/// The attributes of an event type to index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributeFilter {
+    /// The attributes to index. If empty, all attributes that aren't denied are
+    /// indexed.
+    pub allow: Vec<String>,
+
+    /// The attributes not to index, even if allowed
+    pub deny: Vec<String>,
+}
+
+impl AttributeFilter {
+    fn is_indexed(&self, key: &str) -> bool {
+        let listed = |keys: &[String]| keys.iter().any(|k| k == key);
+        !listed(&self.deny) && (self.allow.is_empty() || listed(&self.allow))
+    }
+}
@@ -0,0 +1,263 @@
+//! ABCI application for Tendermint 0.34, for nodes that haven't upgraded to
+//! CometBFT 0.38 yet.
+//!
+//! Blocks are executed in the BeginBlock, DeliverTx, EndBlock and Commit ABCI
+//! methods, which the state machine still provides alongside FinalizeBlock.
+//! The other methods are served the same way as for CometBFT 0.38.
+
+use std::task::{Context, Poll};
+
+use cosmwasm_std::BlockInfo;
+use tendermint::{
+    abci::{request, response, Code},
+    v0_34::abci::{
+        ConsensusRequest, ConsensusResponse, InfoRequest, InfoResponse, MempoolRequest,
+        MempoolResponse, SnapshotRequest, SnapshotResponse,
+    },
+};
+use tower::Service;
+use tower_abci::{v034::Server, BoxError};
+
+use crate::{
+    app::{
+        execute_command, no_snapshot_chunk, stall_if_halted, stall_if_safe_mode, time_to_wasm,
+        tx_outcome_to_abci, validator_to_abci, wasm_event_to_abci, ServiceFuture,
+    },
+    listen::{remove_stale_socket, ListenAddr},
+    App, AppCommand,
+};
+
+#[derive(Clone, Debug)]
+pub struct LegacyApp {
+    pub app: App,
+}
+
+/// Serve the app to Tendermint 0.34 at the given TCP or Unix socket address,
+/// until the server fails.
+pub async fn serve_legacy(app: App, listen_addr: &str) -> Result<(), BoxError> {
+    let app = LegacyApp {
+        app,
+    };
+    let server = Server::builder()
+        .consensus(app.clone())
+        .mempool(app.clone())
+        .info(app.clone())
+        .snapshot(app)
+        .finish()
+        .ok_or("failed to build ABCI server: missing service")?;
+
+    match ListenAddr::parse(listen_addr)? {
+        ListenAddr::Tcp(addr) => server.listen_tcp(addr).await,
+        ListenAddr::Unix(path) => {
+            remove_stale_socket(&path)?;
+            server.listen_unix(path).await
+        },
+    }
+}
+
+impl LegacyApp {
+    /// Signals the beginning of a new block, prior to any `DeliverTx` calls.
+    async fn begin_block(&self, request: request::BeginBlock) -> response::BeginBlock {
+        let block = BlockInfo {
+            height: request.header.height.value(),
+            time: time_to_wasm(request.header.time),
+            chain_id: request.header.chain_id.to_string(),
+        };
+
+        let result = execute_command(&self.app.consensus_tx, |result_tx| AppCommand::BeginBlock {
+            block,
+            result_tx,
+        })
+        .await;
+
+        let events = match result {
+            Ok(events) => events,
+            Err(err) => {
+                stall_if_safe_mode(&err).await;
+                stall_if_halted(&err).await;
+                panic!("ABCI BeginBlock request failed with error: {err}");
+            },
+        };
+
+        response::BeginBlock {
+            events: wasm_event_to_abci(events, &self.app.index),
+        }
+    }
+
+    /// Apply a transaction to the application's state.
+    async fn deliver_tx(&self, request: request::DeliverTx) -> response::DeliverTx {
+        let outcome = execute_command(&self.app.consensus_tx, |result_tx| AppCommand::DeliverTx {
+            tx: request.tx.to_vec(),
+            result_tx,
+        })
+        .await;
+
+        // the same fields as in CometBFT 0.38's tx results
+        let result = tx_outcome_to_abci(outcome, &self.app.index);
+        response::DeliverTx {
+            code: result.code,
+            data: result.data,
+            log: result.log,
+            info: result.info,
+            gas_wanted: result.gas_wanted,
+            gas_used: result.gas_used,
+            events: result.events,
+            codespace: result.codespace,
+        }
+    }
+
+    /// Signals the end of a block. Returns the validator set updates.
+    async fn end_block(&self) -> response::EndBlock {
+        let result = execute_command(&self.app.consensus_tx, |result_tx| AppCommand::EndBlock {
+            result_tx,
+        })
+        .await;
+
+        let (events, validator_updates) = match result {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                stall_if_safe_mode(&err).await;
+                panic!("ABCI EndBlock request failed with error: {err}");
+            },
+        };
+
+        response::EndBlock {
+            validator_updates: validator_updates.into_iter().map(validator_to_abci).collect(),
+            consensus_param_updates: None,
+            events: wasm_event_to_abci(events, &self.app.index),
+        }
+    }
+
+    /// Commit the current state at the current height.
+    async fn commit(&self) -> response::Commit {
+        let result = execute_command(&self.app.consensus_tx, |result_tx| AppCommand::Commit {
+            result_tx,
+        })
+        .await;
+
+        let (height, app_hash) = match result {
+            Ok(committed) => committed,
+            Err(err) => {
+                stall_if_safe_mode(&err).await;
+                panic!("Commit failed: {err}");
+            },
+        };
+
+        response::Commit {
+            data: app_hash.to_vec().into(),
+            // TODO: I don't really know what retain_height means. I assume it
+            // means the block height that was just committed.
+            retain_height: height.try_into().unwrap(),
+        }
+    }
+}
+
+impl Service<ConsensusRequest> for LegacyApp {
+    type Response = ConsensusResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<ConsensusResponse>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: ConsensusRequest) -> Self::Future {
+        let legacy = self.clone();
+        Box::pin(async move {
+            Ok(match request {
+                ConsensusRequest::InitChain(request) => {
+                    ConsensusResponse::InitChain(legacy.app.init_chain(request).await)
+                },
+                ConsensusRequest::BeginBlock(request) => {
+                    ConsensusResponse::BeginBlock(legacy.begin_block(request).await)
+                },
+                ConsensusRequest::DeliverTx(request) => {
+                    ConsensusResponse::DeliverTx(legacy.deliver_tx(request).await)
+                },
+                ConsensusRequest::EndBlock(_) => {
+                    ConsensusResponse::EndBlock(legacy.end_block().await)
+                },
+                ConsensusRequest::Commit => ConsensusResponse::Commit(legacy.commit().await),
+            })
+        })
+    }
+}
+
+impl Service<MempoolRequest> for LegacyApp {
+    type Response = MempoolResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<MempoolResponse>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: MempoolRequest) -> Self::Future {
+        let legacy = self.clone();
+        Box::pin(async move {
+            Ok(match request {
+                MempoolRequest::CheckTx(request) => {
+                    MempoolResponse::CheckTx(legacy.app.check_tx(request).await)
+                },
+            })
+        })
+    }
+}
+
+impl Service<InfoRequest> for LegacyApp {
+    type Response = InfoResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<InfoResponse>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: InfoRequest) -> Self::Future {
+        let legacy = self.clone();
+        Box::pin(async move {
+            Ok(match request {
+                InfoRequest::Info(_) => InfoResponse::Info(legacy.app.info().await),
+                InfoRequest::Query(request) => {
+                    InfoResponse::Query(legacy.app.query(request).await)
+                },
+                InfoRequest::Echo(request) => InfoResponse::Echo(response::Echo {
+                    message: request.message,
+                }),
+                // no options are supported, but reject none either
+                InfoRequest::SetOption(_) => InfoResponse::SetOption(response::SetOption {
+                    code: Code::Ok,
+                    log: String::new(),
+                    info: String::new(),
+                }),
+            })
+        })
+    }
+}
+
+/// State sync is not supported; no snapshot is offered or accepted.
+impl Service<SnapshotRequest> for LegacyApp {
+    type Response = SnapshotResponse;
+    type Error = BoxError;
+    type Future = ServiceFuture<SnapshotResponse>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: SnapshotRequest) -> Self::Future {
+        let response = match request {
+            SnapshotRequest::ListSnapshots => SnapshotResponse::ListSnapshots(Default::default()),
+            SnapshotRequest::OfferSnapshot(_) => {
+                SnapshotResponse::OfferSnapshot(response::OfferSnapshot::Reject)
+            },
+            SnapshotRequest::LoadSnapshotChunk(_) => {
+                SnapshotResponse::LoadSnapshotChunk(Default::default())
+            },
+            SnapshotRequest::ApplySnapshotChunk(_) => {
+                SnapshotResponse::ApplySnapshotChunk(no_snapshot_chunk())
+            },
+        };
+        Box::pin(async move { Ok(response) })
+    }
+}
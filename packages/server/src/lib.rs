@@ -1,7 +1,23 @@
 mod app;
 mod channel;
+mod devnet;
 mod driver;
+mod events;
+mod grpc;
+mod index;
+mod legacy;
+mod listen;
+mod rest;
+mod workers;
 
 pub use app::*;
 pub use channel::*;
+pub use devnet::*;
 pub use driver::*;
+pub use events::*;
+pub use grpc::*;
+pub use index::*;
+pub use legacy::*;
+pub use listen::ListenAddr;
+pub use rest::*;
+pub use workers::*;
@@ -0,0 +1,44 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use tower_abci::BoxError;
+
+/// An address at which the ABCI server listens for connections from the
+/// consensus engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    /// A TCP address, e.g. `tcp://127.0.0.1:26658`. The scheme may be omitted.
+    Tcp(String),
+
+    /// A Unix domain socket, e.g. `unix:///home/user/.cwd/app.sock`, which
+    /// keeps the ABCI server off the network when the consensus engine runs on
+    /// the same machine.
+    Unix(PathBuf),
+}
+
+impl ListenAddr {
+    pub fn parse(addr: &str) -> Result<Self, BoxError> {
+        if let Some(path) = addr.strip_prefix("unix://") {
+            return Ok(Self::Unix(path.into()));
+        }
+
+        let addr = addr.strip_prefix("tcp://").unwrap_or(addr);
+        if addr.contains("://") {
+            return Err(format!("unsupported ABCI listen address: {addr}").into());
+        }
+
+        Ok(Self::Tcp(addr.into()))
+    }
+}
+
+/// Remove the socket file left behind by a previous run, if any, so that the
+/// socket can be bound again.
+pub(crate) fn remove_stale_socket(path: &Path) -> Result<(), BoxError> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,399 @@
+//! HTTP gateway serving the state machine's queries and broadcasting txs as
+//! REST routes, for browsers and scripts where gRPC is impractical.
+//!
+//! Two sets of routes are served:
+//!
+//! - cw-sdk's own, under `/cw_sdk/v1`, which respond with the same JSON as the
+//!   corresponding `SdkQuery`s;
+//! - a subset of wasmd's, under `/cosmwasm/wasm/v1`, so that tools written for
+//!   CosmWasm chains built on the Cosmos SDK can query contracts.
+//!
+//! Clients may also subscribe to the events of committed blocks over a
+//! WebSocket at `/cw_sdk/v1/subscribe`, optionally filtered by the query
+//! parameters `type`, `contract`, `key` and `value`. A JSON-encoded
+//! `CommittedBlock` is sent for each block with matching events.
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router, Server,
+};
+use cosmwasm_std::{Binary, ContractResult};
+use cw_sdk::{SdkQuery, WasmRawResponse, WasmSmartResponse};
+use cw_state_machine::auth;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tendermint_rpc::HttpClient;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Code, Request, Status};
+use tower_abci::BoxError;
+
+use crate::{
+    grpc::{self, error_to_status, proto, GrpcTx},
+    App, EventFilter,
+};
+
+/// Serve the HTTP gateway at the given TCP address, e.g. `127.0.0.1:1317`,
+/// until the server fails. Txs are broadcasted through the CometBFT RPC at the
+/// given URL, e.g. `http://127.0.0.1:26657`.
+pub async fn serve_rest(app: App, listen_addr: &str, rpc_addr: &str) -> Result<(), BoxError> {
+    let addr: SocketAddr = listen_addr.parse()?;
+    let rpc = HttpClient::new(rpc_addr)?;
+    let router = Router::new()
+        .route("/cw_sdk/v1/info", get(info))
+        .route("/cw_sdk/v1/accounts", get(accounts))
+        .route("/cw_sdk/v1/accounts/:address", get(account))
+        .route("/cw_sdk/v1/contracts", get(contracts))
+        .route("/cw_sdk/v1/contracts/:label", get(contract))
+        .route("/cw_sdk/v1/codes", get(codes))
+        .route("/cw_sdk/v1/codes/:code_id", get(code))
+        .route("/cw_sdk/v1/codes/:code_id/contracts", get(contracts_by_code))
+        .route("/cw_sdk/v1/txs", post(broadcast_tx))
+        .route("/cw_sdk/v1/txs/:hash", get(tx))
+        .route("/cw_sdk/v1/simulate", post(simulate))
+        .route("/cw_sdk/v1/subscribe", get(subscribe))
+        .route("/cosmwasm/wasm/v1/code/:code_id/contracts", get(wasmd_contracts_by_code))
+        .route("/cosmwasm/wasm/v1/contract/:address/smart/:query_data", get(wasmd_smart))
+        .route("/cosmwasm/wasm/v1/contract/:address/raw/:query_data", get(wasmd_raw))
+        .with_state(GrpcTx {
+            app,
+            rpc,
+        });
+
+    Server::bind(&addr).serve(router.into_make_service()).await.map_err(Into::into)
+}
+
+/// An error responded with, in the same shape as grpc-gateway's errors.
+struct RestError(Status);
+
+impl From<Status> for RestError {
+    fn from(status: Status) -> Self {
+        Self(status)
+    }
+}
+
+impl IntoResponse for RestError {
+    fn into_response(self) -> Response {
+        let status_code = match self.0.code() {
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::InvalidArgument => StatusCode::BAD_REQUEST,
+            Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+            Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = json!({
+            "code": self.0.code() as i32,
+            "message": self.0.message(),
+        });
+        (status_code, Json(body)).into_response()
+    }
+}
+
+type RestResult<T = Value> = Result<Json<T>, RestError>;
+
+async fn query(state: &GrpcTx, query: SdkQuery) -> RestResult {
+    grpc::query(&state.app, query).await.map(Json).map_err(Into::into)
+}
+
+#[derive(Deserialize)]
+struct Pagination<T = String> {
+    start_after: Option<T>,
+    limit: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct CodeParams {
+    #[serde(default)]
+    with_byte_code: bool,
+}
+
+async fn info(State(state): State<GrpcTx>) -> RestResult {
+    query(&state, SdkQuery::Info {}).await
+}
+
+async fn account(State(state): State<GrpcTx>, Path(address): Path<String>) -> RestResult {
+    query(
+        &state,
+        SdkQuery::Account {
+            address,
+        },
+    )
+    .await
+}
+
+async fn accounts(State(state): State<GrpcTx>, Query(page): Query<Pagination>) -> RestResult {
+    query(
+        &state,
+        SdkQuery::Accounts {
+            start_after: page.start_after,
+            limit: page.limit,
+        },
+    )
+    .await
+}
+
+async fn contract(State(state): State<GrpcTx>, Path(label): Path<String>) -> RestResult {
+    query(
+        &state,
+        SdkQuery::Contract {
+            label,
+        },
+    )
+    .await
+}
+
+async fn contracts(State(state): State<GrpcTx>, Query(page): Query<Pagination>) -> RestResult {
+    query(
+        &state,
+        SdkQuery::Contracts {
+            start_after: page.start_after,
+            limit: page.limit,
+        },
+    )
+    .await
+}
+
+async fn code(
+    State(state): State<GrpcTx>,
+    Path(code_id): Path<u64>,
+    Query(params): Query<CodeParams>,
+) -> RestResult {
+    query(
+        &state,
+        SdkQuery::Code {
+            code_id,
+            with_byte_code: params.with_byte_code,
+        },
+    )
+    .await
+}
+
+async fn codes(State(state): State<GrpcTx>, Query(page): Query<Pagination<u64>>) -> RestResult {
+    query(
+        &state,
+        SdkQuery::Codes {
+            start_after: page.start_after,
+            limit: page.limit,
+        },
+    )
+    .await
+}
+
+async fn contracts_by_code(
+    State(state): State<GrpcTx>,
+    Path(code_id): Path<u64>,
+    Query(page): Query<Pagination>,
+) -> RestResult {
+    query(
+        &state,
+        SdkQuery::ContractsByCode {
+            code_id,
+            start_after: page.start_after,
+            limit: page.limit,
+        },
+    )
+    .await
+}
+
+async fn tx(State(state): State<GrpcTx>, Path(hash): Path<String>) -> RestResult {
+    query(
+        &state,
+        SdkQuery::Tx {
+            hash,
+        },
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+struct BroadcastTxBody {
+    /// The tx, encoded in either JSON or protobuf, in base64
+    tx_bytes: Binary,
+
+    /// One of `BROADCAST_MODE_SYNC` (the default), `BROADCAST_MODE_ASYNC` or
+    /// `BROADCAST_MODE_COMMIT`
+    mode: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BroadcastTxResult {
+    /// SHA-256 hash of the tx bytes, hex-encoded, or of the original tx's bytes
+    /// if the tx is a retry with the same idempotency key
+    hash: String,
+    code: u32,
+    codespace: String,
+    log: String,
+    height: u64,
+    gas_wanted: u64,
+    gas_used: u64,
+}
+
+async fn broadcast_tx(
+    State(state): State<GrpcTx>,
+    Json(body): Json<BroadcastTxBody>,
+) -> RestResult<BroadcastTxResult> {
+    let mode = match body.mode {
+        Some(mode) => proto::BroadcastMode::from_str_name(&mode)
+            .ok_or_else(|| Status::invalid_argument(format!("unknown broadcast mode: {mode}")))?,
+        None => proto::BroadcastMode::Sync,
+    };
+
+    let request = Request::new(proto::BroadcastTxRequest {
+        tx_bytes: body.tx_bytes.into(),
+        mode: mode as i32,
+    });
+    let response =
+        proto::service_server::Service::broadcast_tx(&state, request).await?.into_inner();
+
+    Ok(Json(BroadcastTxResult {
+        hash: hex::encode_upper(response.hash),
+        code: response.code,
+        codespace: response.codespace,
+        log: response.log,
+        height: response.height,
+        gas_wanted: response.gas_wanted,
+        gas_used: response.gas_used,
+    }))
+}
+
+#[derive(Deserialize)]
+struct SimulateBody {
+    /// The tx, encoded in either JSON or protobuf, in base64. Signatures aren't
+    /// verified.
+    tx_bytes: Binary,
+}
+
+async fn simulate(State(state): State<GrpcTx>, Json(body): Json<SimulateBody>) -> RestResult {
+    let (tx, _) = auth::decode_tx(&body.tx_bytes).map_err(error_to_status)?;
+    query(
+        &state,
+        SdkQuery::Simulate {
+            tx,
+        },
+    )
+    .await
+}
+
+async fn subscribe(
+    State(state): State<GrpcTx>,
+    Query(filter): Query<EventFilter>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| stream_events(socket, state.app, filter))
+}
+
+/// Send the events of each committed block that match the filter, until the
+/// client disconnects or falls too far behind.
+async fn stream_events(mut socket: WebSocket, app: App, filter: EventFilter) {
+    let mut events_rx = app.subscribe();
+    loop {
+        let block = match events_rx.recv().await {
+            Ok(block) => block,
+            Err(RecvError::Lagged(_) | RecvError::Closed) => return,
+        };
+
+        let block = if filter.is_empty() {
+            Some((*block).clone())
+        } else {
+            block.filter(&filter)
+        };
+        let Some(block) = block else {
+            continue;
+        };
+
+        let Ok(text) = serde_json::to_string(&block) else {
+            continue;
+        };
+        if socket.send(Message::Text(text)).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// The contracts of a code, in the shape of wasmd's `ContractsByCode` query.
+async fn wasmd_contracts_by_code(
+    State(state): State<GrpcTx>,
+    Path(code_id): Path<u64>,
+) -> RestResult {
+    let Json(contracts) = query(
+        &state,
+        SdkQuery::ContractsByCode {
+            code_id,
+            start_after: None,
+            limit: None,
+        },
+    )
+    .await?;
+
+    let addresses = contracts
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|contract| contract.get("address").cloned())
+        .collect::<Vec<_>>();
+    Ok(Json(json!({
+        "contracts": addresses,
+    })))
+}
+
+/// A smart query, in the shape of wasmd's `SmartContractState` query. The query
+/// message is given in base64-encoded JSON.
+async fn wasmd_smart(
+    State(state): State<GrpcTx>,
+    Path((address, query_data)): Path<(String, String)>,
+) -> RestResult {
+    let msg = decode_query_data(&query_data)?;
+    let msg = serde_json::from_slice(&msg)
+        .map_err(|err| Status::invalid_argument(format!("invalid query message: {err}")))?;
+    let response: WasmSmartResponse = grpc::query(
+        &state.app,
+        SdkQuery::WasmSmart {
+            contract: address,
+            msg,
+        },
+    )
+    .await?;
+
+    match response.result {
+        ContractResult::Ok(data) => {
+            let data: Value = serde_json::from_slice(&data)
+                .map_err(|err| Status::internal(format!("invalid query response: {err}")))?;
+            Ok(Json(json!({
+                "data": data,
+            })))
+        },
+        ContractResult::Err(err) => Err(Status::unknown(err).into()),
+    }
+}
+
+/// A raw query, in the shape of wasmd's `RawContractState` query. The key is
+/// given in base64.
+async fn wasmd_raw(
+    State(state): State<GrpcTx>,
+    Path((address, query_data)): Path<(String, String)>,
+) -> RestResult {
+    let key = decode_query_data(&query_data)?;
+    let response: WasmRawResponse = grpc::query(
+        &state.app,
+        SdkQuery::WasmRaw {
+            contract: address,
+            key: key.into(),
+        },
+    )
+    .await?;
+    Ok(Json(json!({
+        "data": response.value,
+    })))
+}
+
+fn decode_query_data(query_data: &str) -> Result<Vec<u8>, Status> {
+    base64::decode(query_data)
+        .map_err(|err| Status::invalid_argument(format!("invalid query data: {err}")))
+}
@@ -0,0 +1,74 @@
+use std::{fmt, sync::Arc};
+
+use cosmwasm_std::Binary;
+use cw_sdk::SdkQuery;
+use cw_state_machine::{
+    backend::{ModuleCache, WasmConfig},
+    error::{Error as StateMachineError, Result as StateMachineResult},
+    query,
+};
+use cw_store::Snapshot;
+use tokio::sync::{watch, Semaphore};
+
+/// Answers the queries that only read the committed state, on up to a given
+/// number of blocking threads at a time, so that heavy queries, e.g. smart
+/// queries from indexers, neither wait for nor delay the execution of blocks.
+///
+/// The queries read a snapshot of the committed state, which the driver
+/// publishes every time a block is committed. A query that's already running
+/// keeps reading the snapshot it started with.
+#[derive(Clone)]
+pub struct QueryWorkers {
+    snapshot_rx: watch::Receiver<Snapshot>,
+    permits: Arc<Semaphore>,
+    wasm: WasmConfig,
+    modules: Option<Arc<ModuleCache>>,
+}
+
+impl QueryWorkers {
+    pub fn new(
+        snapshot_rx: watch::Receiver<Snapshot>,
+        workers: usize,
+        wasm: WasmConfig,
+        modules: Option<Arc<ModuleCache>>,
+    ) -> Self {
+        Self {
+            snapshot_rx,
+            permits: Arc::new(Semaphore::new(workers)),
+            wasm,
+            modules,
+        }
+    }
+
+    /// Answer a query against the latest snapshot, once a worker is available.
+    /// The query must be one that `query::reads_committed_state`. If the query
+    /// panics, e.g. in a contract, the panic is returned as an error rather
+    /// than taking down the node.
+    pub async fn query(&self, query: SdkQuery) -> StateMachineResult<Binary> {
+        let permit = Arc::clone(&self.permits).acquire_owned().await.unwrap_or_else(|err| {
+            panic!("failed to acquire a query worker: {err}");
+        });
+
+        let snapshot = self.snapshot_rx.borrow().clone();
+        let wasm = self.wasm;
+        let modules = self.modules.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let result = query::handle(snapshot, query, &wasm, modules.as_deref());
+            drop(permit);
+            result
+        })
+        .await
+        .unwrap_or_else(|err| Err(StateMachineError::query_worker_failed(err)))
+    }
+}
+
+impl fmt::Debug for QueryWorkers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryWorkers")
+            .field("available", &self.permits.available_permits())
+            .field("wasm", &self.wasm)
+            .field("modules", &self.modules)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,276 @@
+//! Checks that a tx must pass to be admitted to the mempool.
+//!
+//! CheckTx runs the same checks as DeliverTx does before executing a tx's
+//! messages: the signers' signatures, account numbers and sequences, the
+//! idempotency key, and the fee payer and allowance. To keep mempool admission
+//! cheap, the only wasm it executes is bounded by the chain's system gas
+//! limits: the `before_tx` hook of signers that are contract accounts, so that
+//! their txs are authenticated before they take up block space, and a dry run
+//! of the fee charge, so that the taxman contract, if one exists, decides the
+//! fee that the allowance must cover and may reject the tx. The dry run's
+//! changes are discarded, so fees aren't deducted in the check state, and each
+//! tx is checked against the payer's full balances, which are read from the
+//! bank contract's storage directly.
+//!
+//! Changes made by the checks, such as signers' sequences being incremented,
+//! are kept in a check state until the next block is committed, so that a
+//! signer may submit several txs with consecutive sequences for the same block.
+//! Once a block is committed, Tendermint rechecks the txs that remain in the
+//! mempool against a fresh check state. Their signatures have already been
+//! verified, so only sequences, idempotency keys and fee allowances are checked
+//! again, and txs whose sequences have been consumed are evicted.
+//!
+//! Operators may configure additional checks, in the form of ante handlers,
+//! which are run in the order they are added, after the built-in ones.
+//!
+//! Before all of these, txs must meet the minimum gas prices configured by the
+//! operator. They're this node's own policy, so they're only enforced on
+//! admission to the mempool, not on txs included in blocks.
+//!
+//! Txs that pass are prioritized in the mempool by their gas price in the fee
+//! denom configured by the operator, which the operator may also override.
+
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, BlockInfo, Coin, Decimal, Empty, Storage, Uint128, Uint256};
+use cw_sdk::{address, Fee, Tx};
+use cw_storage_plus::Map;
+use cw_store::ReadonlyPrefixedStore;
+
+use crate::{
+    error::{Error, Result},
+    state::contract_namespace,
+};
+
+/// The balances in the bank contract's storage, by account and denom. Must be
+/// kept in sync with the bank contract's layout.
+const BANK_BALANCES: Map<(&Addr, &str), Uint128> = Map::new("balances");
+
+/// The denoms whose transfers are frozen in the bank contract, which users
+/// can't pay fees with, although the bank would let the fee transfer through.
+/// Must be kept in sync with the bank contract's layout.
+const BANK_FROZEN: Map<&str, Empty> = Map::new("frozen");
+
+/// Whether a tx is checked for the first time, or rechecked after a block is
+/// committed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckTxType {
+    #[default]
+    New,
+    Recheck,
+}
+
+/// An additional check that a tx must pass to be admitted to the mempool.
+///
+/// Ante handlers are given a view of the check state that includes the changes
+/// made by the built-in checks. Changes they make are kept in the check state
+/// if the tx passes, and discarded otherwise. They are run on rechecks as well.
+pub trait AnteHandler {
+    fn check(&self, store: &mut dyn Storage, block: &BlockInfo, tx: &Tx) -> Result<()>;
+}
+
+/// Overrides the priorities of txs in this node's mempool, e.g. to prioritize
+/// txs sent by the operator's own accounts.
+///
+/// Priorities only affect the order in which this node includes txs in the
+/// blocks it proposes, not whether the txs are valid, so they may differ from
+/// node to node.
+pub trait PriorityOverride {
+    /// Return the priority of the tx, given the priority derived from its fee.
+    fn priority(&self, tx: &Tx, fee_priority: i64) -> i64;
+}
+
+/// Return the priority of a tx derived from its fee: its gas price, i.e. the
+/// amount of the fee denom paid per unit of gas wanted, rounded down.
+///
+/// Coins of other denoms don't count towards the priority. Txs that want no
+/// gas have zero priority.
+pub fn fee_priority(fee: &Fee, fee_denom: &str) -> i64 {
+    if fee.gas_limit == 0 {
+        return 0;
+    }
+
+    let amount = fee
+        .amount
+        .iter()
+        .filter(|coin| coin.denom == fee_denom)
+        .fold(0u128, |amount, coin| amount.saturating_add(coin.amount.u128()));
+
+    (amount / fee.gas_limit as u128).try_into().unwrap_or(i64::MAX)
+}
+
+/// Return error if the fee doesn't meet the minimum gas price of any of the
+/// given denoms, i.e. if in every denom, it pays less than the price times the
+/// gas limit, rounded up. If there are no minimum gas prices, or one of them is
+/// zero, any fee is accepted.
+pub fn check_min_gas_prices(fee: &Fee, min_gas_prices: &BTreeMap<String, Decimal>) -> Result<()> {
+    if min_gas_prices.is_empty() {
+        return Ok(());
+    }
+
+    // computed in 256 bits, so that neither the minimum fee nor the sum of the
+    // coins paid can overflow
+    let one = Uint256::from(Decimal::one().atomics());
+    let gas_limit = Uint256::from(fee.gas_limit);
+
+    let mut min_fees = vec![];
+    for (denom, price) in min_gas_prices {
+        let min_amount = Uint256::from(price.atomics()) * gas_limit;
+        let min_amount = (min_amount + one - Uint256::from(1u128)) / one;
+        let paid = fee
+            .amount
+            .iter()
+            .filter(|coin| coin.denom == *denom)
+            .fold(Uint256::zero(), |paid, coin| paid + Uint256::from(coin.amount));
+        if paid >= min_amount {
+            return Ok(());
+        }
+        min_fees.push(format!("{min_amount}{denom}"));
+    }
+
+    Err(Error::gas_price_too_low(fee.gas_limit, min_fees.join(" or ")))
+}
+
+/// Return error if the payer's balances in the bank contract don't cover the
+/// fee, or if any of the fee's denoms is frozen. The balances are read from the
+/// contract's storage, without executing wasm.
+pub fn check_fee_balance(store: &dyn Storage, payer_addr: &Addr, fee: &[Coin]) -> Result<()> {
+    if fee.is_empty() {
+        return Ok(());
+    }
+
+    let bank_addr = address::derive_from_label("bank")?;
+    let bank = ReadonlyPrefixedStore::new(store, &contract_namespace(&bank_addr));
+
+    for coin in fee {
+        if BANK_FROZEN.has(&bank, &coin.denom) {
+            return Err(Error::fee_deduction_failed(format!(
+                "transfers of denom {} are frozen",
+                coin.denom,
+            )));
+        }
+
+        let balance =
+            BANK_BALANCES.may_load(&bank, (payer_addr, &coin.denom))?.unwrap_or_default();
+        if balance < coin.amount {
+            return Err(Error::fee_deduction_failed(format!(
+                "insufficient balance: {payer_addr} has {balance}{}, fee is {coin}",
+                coin.denom,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// The outcome of a tx that passes CheckTx.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheckTxOutcome {
+    /// The amount of gas that the tx requests, i.e. its fee's gas limit
+    pub gas_wanted: u64,
+
+    /// The tx's priority in the mempool; txs of higher priorities are included
+    /// in blocks first
+    pub priority: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cosmwasm_std::{coin, coins, testing::MockStorage};
+    use cw_store::PrefixedStore;
+
+    use super::*;
+
+    fn fee(amount: Vec<Coin>, gas_limit: u64) -> Fee {
+        Fee {
+            amount,
+            gas_limit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prioritizing_by_fee() {
+        // gas price is rounded down, and other denoms don't count
+        let amount = vec![coin(250, "uatom"), coin(1000, "uosmo")];
+        assert_eq!(fee_priority(&fee(amount, 100), "uatom"), 2);
+
+        assert_eq!(fee_priority(&fee(vec![], 100), "uatom"), 0);
+        assert_eq!(fee_priority(&fee(vec![coin(250, "uatom")], 0), "uatom"), 0);
+
+        // priorities are capped rather than overflowing
+        let amount = vec![coin(u128::MAX, "uatom"), coin(u128::MAX, "uatom")];
+        assert_eq!(fee_priority(&fee(amount, 1), "uatom"), i64::MAX);
+    }
+
+    #[test]
+    fn checking_min_gas_prices() {
+        let min_gas_prices = BTreeMap::from([
+            ("uatom".to_string(), Decimal::from_str("0.025").unwrap()),
+            ("uosmo".to_string(), Decimal::percent(10)),
+        ]);
+
+        // the minimum fee is rounded up: 0.025 * 100 = 2.5 uatom
+        check_min_gas_prices(&fee(coins(3, "uatom"), 100), &min_gas_prices).unwrap();
+        let err = check_min_gas_prices(&fee(coins(2, "uatom"), 100), &min_gas_prices).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::GasPriceTooLow { gas_limit: 100, min_fee } if min_fee == "3uatom or 10uosmo"
+        ));
+
+        // meeting the price of any one denom is enough
+        let amount = vec![coin(1, "uatom"), coin(10, "uosmo")];
+        check_min_gas_prices(&fee(amount, 100), &min_gas_prices).unwrap();
+        let err = check_min_gas_prices(&fee(vec![], 100), &min_gas_prices).unwrap_err();
+        assert!(matches!(err, Error::GasPriceTooLow { .. }));
+        let err =
+            check_min_gas_prices(&fee(coins(100, "ujuno"), 100), &min_gas_prices).unwrap_err();
+        assert!(matches!(err, Error::GasPriceTooLow { .. }));
+
+        // no gas wanted, no minimum fee
+        check_min_gas_prices(&fee(vec![], 0), &min_gas_prices).unwrap();
+
+        // huge prices and gas limits don't overflow
+        let huge = BTreeMap::from([("uatom".to_string(), Decimal::MAX)]);
+        let err =
+            check_min_gas_prices(&fee(coins(u128::MAX, "uatom"), u64::MAX), &huge).unwrap_err();
+        assert!(matches!(err, Error::GasPriceTooLow { .. }));
+
+        // without minimum gas prices, or with a zero one, any fee is accepted
+        check_min_gas_prices(&fee(vec![], 100), &BTreeMap::new()).unwrap();
+        let zero = BTreeMap::from([("uatom".to_string(), Decimal::zero())]);
+        check_min_gas_prices(&fee(vec![], 100), &zero).unwrap();
+    }
+
+    #[test]
+    fn checking_fee_balance() {
+        let mut store = MockStorage::new();
+        let payer_addr = address::derive_from_label("payer").unwrap();
+
+        // txs that pay no fee pass even if there is no bank contract
+        check_fee_balance(&store, &payer_addr, &[]).unwrap();
+        let err = check_fee_balance(&store, &payer_addr, &coins(1, "uatom")).unwrap_err();
+        assert!(matches!(err, Error::FeeDeductionFailed { .. }));
+
+        let bank_addr = address::derive_from_label("bank").unwrap();
+        let mut bank = PrefixedStore::new(&mut store, &contract_namespace(&bank_addr));
+        BANK_BALANCES.save(&mut bank, (&payer_addr, "uatom"), &Uint128::new(100)).unwrap();
+
+        check_fee_balance(&store, &payer_addr, &coins(100, "uatom")).unwrap();
+        let err = check_fee_balance(&store, &payer_addr, &coins(101, "uatom")).unwrap_err();
+        assert!(matches!(err, Error::FeeDeductionFailed { .. }));
+
+        // every denom of the fee must be covered
+        let fee = vec![coin(50, "uatom"), coin(1, "uosmo")];
+        let err = check_fee_balance(&store, &payer_addr, &fee).unwrap_err();
+        assert!(matches!(err, Error::FeeDeductionFailed { .. }));
+
+        // frozen denoms can't be used to pay fees
+        let mut bank = PrefixedStore::new(&mut store, &contract_namespace(&bank_addr));
+        BANK_FROZEN.save(&mut bank, "uatom", &Empty {}).unwrap();
+        let err = check_fee_balance(&store, &payer_addr, &coins(100, "uatom")).unwrap_err();
+        assert!(matches!(err, Error::FeeDeductionFailed { .. }));
+    }
+}
@@ -1,30 +1,333 @@
-use cosmwasm_std::{Addr, BlockInfo, Storage};
-use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, BlockInfo, Coin, ContractInfo, ContractResult, Env, Event, Order,
+    StdResult, Storage,
+};
+use cw_storage_plus::Bound;
+use k256::ecdsa::{recoverable, signature::Verifier, Signature, VerifyingKey};
+use sha3::{Digest, Keccak256};
 
-use cw_sdk::{address, Account, Tx};
+use cw_sdk::{
+    address, canonical::is_canonical, proto, smart_account, Account, Fee, MultisigPubKey,
+    PubKeyType, SignMode, SignerInfo, Tx, TxBody, TxSignature,
+};
 
 use crate::{
     error::{Error, Result},
-    state::ACCOUNTS,
+    execute, feegrant,
+    gas::{SystemCall, SystemGas},
+    sigverify::{SignatureCache, SignatureCheck},
+    state::{
+        params, IdempotencyRecord, Module, ACCOUNTS, ACCOUNT_COUNT, FEE_ALLOWANCES,
+        IDEMPOTENCY_EXPIRIES, IDEMPOTENCY_KEYS,
+    },
 };
 
-/// The response type of `authenticate_tx` function.
+/// How long (in seconds) an idempotency key is remembered after the tx that
+/// carried it is executed. Within this window, another tx from the same sender
+/// carrying the same key is rejected.
+pub const IDEMPOTENCY_WINDOW: u64 = 600;
+
+/// Byte length of an Ethereum-style signature, i.e. `r | s | v`.
+pub const ETH_SIGNATURE_LENGTH: usize = 65;
+
+/// The response type of `authenticate_signer` function.
 pub struct Sender {
     pub address: Addr,
     pub account: Account<Addr>,
+    /// Whether the account is created by this tx, in which case it has been
+    /// assigned the next account number
+    pub created: bool,
+}
+
+/// Decode a tx from the raw bytes provided by Tendermint, which are either
+/// JSON or protobuf. Return the tx, and how its sign bytes are computed.
+///
+/// JSON txs must be in canonical form, so that all clients agree on how a tx
+/// is encoded, and thus on its hash and sign bytes.
+pub fn decode_tx(tx_bytes: &[u8]) -> Result<(Tx, SignMode)> {
+    // a JSON-encoded tx is an object, so it starts with `{`. a protobuf-encoded
+    // tx starts with the tag of its first field, which can't be `{`
+    if tx_bytes.first() != Some(&b'{') {
+        return proto::decode_tx(tx_bytes).map_err(Error::from);
+    }
+
+    if !is_canonical(tx_bytes) {
+        return Err(Error::NonCanonicalTx);
+    }
+    Ok((serde_json::from_slice(tx_bytes)?, SignMode::Json))
+}
+
+/// Authenticate each of the tx's signers, and check that every message is sent
+/// by one of them. Return error if any one fails.
+///
+/// The signers' accounts are updated in the store as they are authenticated,
+/// so that accounts created by the same tx are assigned consecutive account
+/// numbers. The caller is responsible for discarding the changes if this fails.
+///
+/// A signer that is a contract account authenticates the tx itself: instead of
+/// verifying a signature, its `before_tx` hook is sudoed. This is the case in
+/// CheckTx too, so that txs of contract accounts can't take up block space
+/// without being authenticated.
+///
+/// Returns the signers' addresses, in the order they are listed in the tx.
+pub fn authenticate_tx<S>(
+    mut store: S,
+    pending_block: &BlockInfo,
+    tx: &Tx,
+    sign_mode: &SignMode,
+    sigs: &SignatureCache,
+    gas: &SystemGas,
+) -> Result<(Vec<Addr>, S)>
+where
+    S: Storage + 'static,
+{
+    let signer_addrs = validate_basic(tx, pending_block)?;
+
+    for ((signer, signature), signer_addr) in
+        tx.body.signers.iter().zip(&tx.signatures).zip(&signer_addrs)
+    {
+        if is_contract(&store, signer_addr)? {
+            store = before_tx(store, pending_block, tx, signer, signature, sign_mode, gas)?;
+            continue;
+        }
+
+        let sender =
+            authenticate_signer(&store, pending_block, tx, signer, signature, sign_mode, sigs)?;
+        save_sender(&mut store, &sender)?;
+    }
+
+    Ok((signer_addrs, store))
+}
+
+/// Check the tx against the chain's size limits. `tx_len` is the size of the
+/// tx as it is encoded when broadcasted.
+pub fn check_tx_size(store: &dyn Storage, tx: &Tx, tx_len: usize) -> Result<()> {
+    let params = params(store)?;
+
+    let size = tx_len as u64;
+    if size > params.max_tx_bytes {
+        return Err(Error::TxTooLarge {
+            size,
+            max: params.max_tx_bytes,
+        });
+    }
+
+    let count = tx.body.msgs.len() as u64;
+    if count > params.max_msgs_per_tx {
+        return Err(Error::TooManyMsgs {
+            count,
+            max: params.max_msgs_per_tx,
+        });
+    }
+
+    Ok(())
+}
+
+/// Check the parts of a tx that don't depend on the signers' accounts: that
+/// there is a signature for each signer, that the chain id matches, that the
+/// tx hasn't timed out, and that each message is sent by one of the signers.
+/// Return the signers' addresses, in the order they are listed in the tx.
+fn validate_basic(tx: &Tx, pending_block: &BlockInfo) -> Result<Vec<Addr>> {
+    if tx.body.signers.is_empty() {
+        return Err(Error::NoSigners);
+    }
+
+    if tx.body.signers.len() != tx.signatures.len() {
+        return Err(Error::signature_count_mismatch(tx.body.signers.len(), tx.signatures.len()));
+    }
+
+    // the chain id must match
+    if pending_block.chain_id != tx.body.chain_id {
+        return Err(Error::chain_id_mismatch(&pending_block.chain_id, &tx.body.chain_id));
+    }
+
+    // the tx must not have timed out
+    check_timeout_height(&tx.body, pending_block.height)?;
+
+    // no account may sign more than once
+    let mut signer_addrs: Vec<Addr> = vec![];
+    for signer in &tx.body.signers {
+        let signer_addr = address::validate(&signer.address)?;
+        if signer_addrs.contains(&signer_addr) {
+            return Err(Error::duplicate_signer(signer_addr));
+        }
+        signer_addrs.push(signer_addr);
+    }
+
+    // each message must be sent by one of the signers
+    for msg in &tx.body.msgs {
+        if !signer_addrs.iter().any(|addr| *addr == msg.sender) {
+            return Err(Error::msg_sender_not_signer(&msg.sender));
+        }
+    }
+
+    Ok(signer_addrs)
+}
+
+/// Update an authenticated signer's account in the store.
+fn save_sender(store: &mut dyn Storage, sender: &Sender) -> Result<()> {
+    let mut auth = Module::Auth.substore_mut(store);
+    ACCOUNTS.save(&mut auth, &sender.address, &sender.account)?;
+    if sender.created {
+        ACCOUNT_COUNT.update(&mut auth, |count| -> Result<_> {
+            Ok(count + 1)
+        })?;
+    }
+    Ok(())
+}
+
+/// Sudo the `after_tx` hook of each of the tx's signers that is a contract
+/// account, after the tx's messages are executed. Return the events emitted.
+pub fn after_tx<S>(
+    mut store: S,
+    block: &BlockInfo,
+    tx: &Tx,
+    signer_addrs: &[Addr],
+    gas: &SystemGas,
+) -> Result<(Vec<Event>, S)>
+where
+    S: Storage + 'static,
+{
+    let mut events = vec![];
+
+    for signer_addr in signer_addrs {
+        if !is_contract(&store, signer_addr)? {
+            continue;
+        }
+
+        let msg = to_binary(&smart_account::SudoMsg::AfterTx {
+            tx: tx.clone(),
+        })?;
+
+        let env = hook_env(block, signer_addr);
+        let (result, s) = execute::sudo_contract(store, &env, &msg, SystemCall::AfterTx, gas)?;
+        store = s;
+
+        match result {
+            ContractResult::Ok(resp) => events.extend(resp.events),
+            ContractResult::Err(err) => {
+                return Err(Error::tx_rejected_by_account(signer_addr, err));
+            },
+        }
+    }
+
+    Ok((events, store))
+}
+
+/// Return error if the tx has timed out, i.e. the given block height is beyond
+/// the tx's timeout height.
+pub fn check_timeout_height(body: &TxBody, height: u64) -> Result<()> {
+    match body.timeout_height {
+        Some(timeout_height) if height > timeout_height => {
+            Err(Error::tx_timed_out(timeout_height, height))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Authenticate a signer that is a contract account, by sudoing its `before_tx`
+/// hook with the tx, the sign bytes, and the signer's signature.
+fn before_tx<S>(
+    store: S,
+    pending_block: &BlockInfo,
+    tx: &Tx,
+    signer: &SignerInfo,
+    signature: &TxSignature,
+    sign_mode: &SignMode,
+    gas: &SystemGas,
+) -> Result<S>
+where
+    S: Storage + 'static,
+{
+    let signer_addr = Addr::unchecked(&signer.address);
+
+    // the state machine doesn't track contract accounts' account numbers and
+    // sequences, so the sign bytes are computed with the ones provided by the
+    // tx, and the contract is responsible for checking them
+    let sign_bytes = signature.sign_bytes(
+        sign_mode,
+        &tx.body,
+        &pending_block.chain_id,
+        signer.account_number,
+        signer.sequence,
+    )?;
+
+    let msg = to_binary(&smart_account::SudoMsg::BeforeTx {
+        tx: tx.clone(),
+        sign_bytes: sign_bytes.into(),
+        signature: signature.signature.clone(),
+    })?;
+
+    let (result, store) = execute::sudo_contract(
+        store,
+        &hook_env(pending_block, &signer_addr),
+        &msg,
+        SystemCall::BeforeTx,
+        gas,
+    )?;
+
+    match result {
+        ContractResult::Ok(_) => Ok(store),
+        ContractResult::Err(err) => Err(Error::tx_rejected_by_account(signer_addr, err)),
+    }
 }
 
-/// Authenticate the signer's address, pubkey, signature, sequence, and chain id.
+/// The env in which a contract account's hooks are sudoed.
+fn hook_env(block: &BlockInfo, contract_addr: &Addr) -> Env {
+    Env {
+        block: block.clone(),
+        transaction: None,
+        contract: ContractInfo {
+            address: contract_addr.clone(),
+        },
+    }
+}
+
+/// Return whether the account of the given address is a contract.
+fn is_contract(store: &dyn Storage, addr: &Addr) -> Result<bool> {
+    let account = ACCOUNTS.may_load(&Module::Auth.substore(store), addr)?;
+    Ok(matches!(account, Some(Account::Contract { .. })))
+}
+
+/// The key that controls a signer's account.
+enum SignerKey<'a> {
+    /// A single key. The pubkey of a new Ethereum-style account may be unknown
+    /// yet, in which case it is recovered from the signature.
+    Single {
+        pubkey: Option<Binary>,
+        pubkey_type: PubKeyType,
+    },
+
+    /// A threshold multisig key, and the members' signatures
+    Multisig {
+        pubkey: MultisigPubKey,
+        signatures: &'a [Option<Binary>],
+    },
+}
+
+/// Authenticate a signer's address, pubkey, signature, account number and
+/// sequence.
 /// Return error if any one fails.
-/// Returns the sender address and account info if succeeds.
-pub fn authenticate_tx(store: &dyn Storage, pending_block: &BlockInfo, tx: &Tx) -> Result<Sender> {
-    let sender = &tx.body.sender;
+/// Returns the signer address and updated account info if succeeds.
+fn authenticate_signer(
+    store: &dyn Storage,
+    pending_block: &BlockInfo,
+    tx: &Tx,
+    signer: &SignerInfo,
+    signature: &TxSignature,
+    sign_mode: &SignMode,
+    sigs: &SignatureCache,
+) -> Result<Sender> {
+    let sender = &signer.address;
     let sender_addr = address::validate(sender)?;
 
     // find the user's account
-    let (pubkey, mut sequence) = match ACCOUNTS.may_load(store, &sender_addr)? {
+    let auth = Module::Auth.substore(store);
+    let account = ACCOUNTS.may_load(&auth, &sender_addr)?;
+    let (key, account_number, mut sequence, created) = match account {
         // If the sender account is a contract, throw error because contracts
-        // can't sign txs.
+        // can't sign txs. They authenticate txs with their `before_tx` hooks
+        // instead, so `authenticate_tx` doesn't get here for them.
         Some(Account::Contract {
             ..
         }) => {
@@ -36,60 +339,808 @@ pub fn authenticate_tx(store: &dyn Storage, pending_block: &BlockInfo, tx: &Tx)
         // in the tx.
         Some(Account::Base {
             pubkey,
+            pubkey_type,
+            account_number,
             sequence,
         }) => {
-            if let Some(sender_pubkey) = &tx.pubkey {
+            if signature.multisig.is_some() {
+                return Err(Error::signature_type_mismatch(sender));
+            }
+
+            if let Some(sender_pubkey) = &signature.pubkey {
                 if pubkey != *sender_pubkey {
                     return Err(Error::pubkey_mismatch(sender, &pubkey, sender_pubkey));
                 }
             }
 
-            (pubkey, sequence)
+            let key = SignerKey::Single {
+                pubkey: Some(pubkey),
+                pubkey_type,
+            };
+            (key, account_number, sequence, false)
+        },
+
+        // Same for a multisig account.
+        Some(Account::Multisig {
+            pubkey,
+            account_number,
+            sequence,
+        }) => {
+            let multisig = match &signature.multisig {
+                Some(multisig) => multisig,
+                None => return Err(Error::signature_type_mismatch(sender)),
+            };
+
+            if let Some(sender_pubkey) = &multisig.pubkey {
+                if pubkey != *sender_pubkey {
+                    return Err(Error::multisig_pubkey_mismatch(sender));
+                }
+            }
+
+            let key = SignerKey::Multisig {
+                pubkey,
+                signatures: &multisig.signatures,
+            };
+            (key, account_number, sequence, false)
         },
 
         // If not found, meaning it's the first time the account every sends a
-        // tx, use the pubkey provided by the tx and initialize sequence to be 0.
-        // Note, the pubkey must match the sender address.
+        // tx, use the pubkey provided by the tx, assign the next account number,
+        // and initialize sequence to be 0.
+        // The pubkey of an Ethereum-style key may be omitted, in which case it
+        // is recovered from the signature.
         None => {
-            let Some(pubkey) = &tx.pubkey else {
-                return Err(Error::account_not_found(sender));
+            let key = match &signature.multisig {
+                Some(multisig) => {
+                    let pubkey =
+                        multisig.pubkey.clone().ok_or_else(|| Error::account_not_found(sender))?;
+                    validate_multisig_pubkey(&pubkey)?;
+                    SignerKey::Multisig {
+                        pubkey,
+                        signatures: &multisig.signatures,
+                    }
+                },
+                None => {
+                    if signature.pubkey.is_none()
+                        && signature.pubkey_type != PubKeyType::EthSecp256k1
+                    {
+                        return Err(Error::account_not_found(sender));
+                    }
+                    SignerKey::Single {
+                        pubkey: signature.pubkey.clone(),
+                        pubkey_type: signature.pubkey_type,
+                    }
+                },
             };
 
-            let address = address::derive_from_pubkey(pubkey.as_slice())?;
-            if *sender != address {
-                return Err(Error::address_mismatch(address, sender));
-            }
+            let account_number = ACCOUNT_COUNT.may_load(&auth)?.unwrap_or_default();
 
-            (pubkey.clone(), 0)
+            (key, account_number, 0, true)
         },
     };
 
-    // the chain id must match
-    if pending_block.chain_id != tx.body.chain_id {
-        return Err(Error::chain_id_mismatch(&pending_block.chain_id, &tx.body.chain_id));
+    // the account number must match, so that a tx signed for an account that
+    // has since been deleted and recreated can't be replayed
+    if account_number != signer.account_number {
+        return Err(Error::account_number_mismatch(sender, account_number, signer.account_number));
     }
 
     // the account sequence mush match
     sequence += 1;
-    if sequence != tx.body.sequence {
-        return Err(Error::sequence_mismatch(sender, sequence, tx.body.sequence));
+    if sequence != signer.sequence {
+        return Err(Error::sequence_mismatch(sender, sequence, signer.sequence));
     }
 
     // verify the signature
-    // the content to be signed is (the sha256 hash of) the tx body
-    let body_bytes = serde_json::to_vec(&tx.body)?;
-    let signature = Signature::try_from(tx.signature.as_slice())?;
-
-    // if signature is valid, return the sender address and updated account info
-    // otherwise, return error
-    VerifyingKey::from_sec1_bytes(pubkey.as_slice())?
-        .verify(&body_bytes, &signature)
-        .map(|_| Sender {
-            address: sender_addr,
-            account: Account::Base {
+    // the content to be signed is (the hash of) the sign doc, which consists of
+    // the tx body, and the chain id, account number and sequence that we expect.
+    // all members of a multisig sign the same sign doc
+    let sign_bytes = signature.sign_bytes(
+        sign_mode,
+        &tx.body,
+        &pending_block.chain_id,
+        account_number,
+        sequence,
+    )?;
+    let account = match key {
+        SignerKey::Single {
+            pubkey,
+            pubkey_type,
+        } => {
+            let pubkey = match pubkey {
+                Some(pubkey) => pubkey,
+                None => recover_eth_pubkey(&sign_bytes, &signature.signature)?
+                    .to_bytes()
+                    .to_vec()
+                    .into(),
+            };
+            sigs.verify(&SignatureCheck {
+                pubkey_type,
+                pubkey: pubkey.clone(),
+                sign_bytes,
+                signature: signature.signature.clone(),
+            })?;
+
+            // for a new account, the pubkey must match the sender address
+            if created {
+                verify_address(sender, pubkey_type.derive_address(&pubkey)?)?;
+            }
+
+            Account::Base {
                 pubkey,
+                pubkey_type,
+                account_number,
                 sequence,
-            },
-        })
+            }
+        },
+        SignerKey::Multisig {
+            pubkey,
+            signatures,
+        } => {
+            verify_multisig(&pubkey, sign_bytes, signatures, sigs)?;
+
+            if created {
+                verify_address(sender, pubkey.derive_address()?)?;
+            }
+
+            Account::Multisig {
+                pubkey,
+                account_number,
+                sequence,
+            }
+        },
+    };
+
+    Ok(Sender {
+        address: sender_addr,
+        account,
+        created,
+    })
+}
+
+/// Return error if the sender address doesn't match the one derived from the
+/// pubkey.
+fn verify_address(sender: &str, address: Addr) -> Result<()> {
+    if sender != address {
+        return Err(Error::address_mismatch(address, sender));
+    }
+    Ok(())
+}
+
+/// Return error if the multisig pubkey provided by a new account is invalid:
+/// the threshold must be between one and the number of members, and the
+/// members' pubkeys must be sorted, with no duplicates.
+fn validate_multisig_pubkey(pubkey: &MultisigPubKey) -> Result<()> {
+    if pubkey.threshold == 0 || pubkey.threshold as usize > pubkey.pubkeys.len() {
+        return Err(Error::invalid_multisig(format!(
+            "threshold must be between 1 and {}, found {}",
+            pubkey.pubkeys.len(),
+            pubkey.threshold,
+        )));
+    }
+
+    if pubkey.pubkeys.windows(2).any(|pair| pair[0].as_slice() >= pair[1].as_slice()) {
+        return Err(Error::invalid_multisig("pubkeys must be sorted and unique"));
+    }
+
+    Ok(())
+}
+
+/// Verify a multisig signature over the sign bytes. Each of the members'
+/// signatures that is present must be valid, and there must be at least as many
+/// of them as the threshold.
+fn verify_multisig(
+    pubkey: &MultisigPubKey,
+    sign_bytes: Vec<u8>,
+    signatures: &[Option<Binary>],
+    sigs: &SignatureCache,
+) -> Result<()> {
+    if signatures.len() != pubkey.pubkeys.len() {
+        return Err(Error::signature_count_mismatch(pubkey.pubkeys.len(), signatures.len()));
+    }
+
+    let mut count = 0;
+    for (member_pubkey, signature) in pubkey.pubkeys.iter().zip(signatures) {
+        if let Some(signature) = signature {
+            sigs.verify(&SignatureCheck {
+                pubkey_type: PubKeyType::Secp256k1,
+                pubkey: member_pubkey.clone(),
+                sign_bytes: sign_bytes.clone(),
+                signature: signature.clone(),
+            })?;
+            count += 1;
+        }
+    }
+
+    if count < pubkey.threshold {
+        return Err(Error::multisig_threshold_not_met(pubkey.threshold, count));
+    }
+
+    Ok(())
+}
+
+/// Verify a signature over the sign bytes, made by the given pubkey.
+pub fn verify_signature(
+    pubkey_type: PubKeyType,
+    pubkey: &[u8],
+    sign_bytes: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let pubkey = VerifyingKey::from_sec1_bytes(pubkey)?;
+    match pubkey_type {
+        PubKeyType::Secp256k1 => {
+            let signature = Signature::try_from(signature)?;
+            pubkey.verify(sign_bytes, &signature).map_err(Error::from)
+        },
+        PubKeyType::EthSecp256k1 => {
+            if recover_eth_pubkey(sign_bytes, signature)? != pubkey {
+                return Err(k256::ecdsa::Error::new().into());
+            }
+            Ok(())
+        },
+    }
+}
+
+/// Recover the Ethereum-style pubkey that signed the Keccak-256 hash of the
+/// sign bytes.
+///
+/// The signature is `r | s | v`, where `v` is the recovery id. Similar to
+/// Ethereum, `v` may be the recovery id itself (0 or 1), offset by 27, or
+/// offset by `chain_id * 2 + 35` as in EIP-155. The chain id is committed to by
+/// the sign doc already, so in the EIP-155 case only the parity of `v` matters.
+fn recover_eth_pubkey(sign_bytes: &[u8], signature: &[u8]) -> Result<VerifyingKey> {
+    if signature.len() != ETH_SIGNATURE_LENGTH {
+        return Err(Error::invalid_eth_signature_length(signature.len()));
+    }
+    let (rs, v) = (&signature[..64], signature[64]);
+
+    let recovery_id = match v {
+        0 | 1 => v,
+        27 | 28 => v - 27,
+        35.. => (v - 35) % 2,
+        _ => return Err(Error::unsupported_recovery_id(v)),
+    };
+
+    let signature = recoverable::Signature::new(
+        &Signature::try_from(rs)?,
+        recoverable::Id::new(recovery_id)?,
+    )?;
+
+    signature
+        .recover_verifying_key_from_digest(Keccak256::new_with_prefix(sign_bytes))
         .map_err(Error::from)
 }
+
+/// Replace the pubkey of a base account. The account keeps its address,
+/// account number and sequence.
+///
+/// The sender has been authenticated by the tx's signature, i.e. the old key
+/// has approved the rotation.
+pub fn rotate_key(
+    store: &mut dyn Storage,
+    sender_addr: &Addr,
+    new_pubkey: Binary,
+    new_pubkey_type: PubKeyType,
+) -> Result<Event> {
+    // make sure the new pubkey is a valid secp256k1 point, so that the account
+    // doesn't become unusable
+    VerifyingKey::from_sec1_bytes(&new_pubkey)?;
+
+    let mut auth = Module::Auth.substore_mut(store);
+    let account = match ACCOUNTS.may_load(&auth, sender_addr)? {
+        Some(Account::Base {
+            account_number,
+            sequence,
+            ..
+        }) => Account::Base {
+            pubkey: new_pubkey,
+            pubkey_type: new_pubkey_type,
+            account_number,
+            sequence,
+        },
+        Some(_) => return Err(Error::key_rotation_unsupported(sender_addr)),
+        None => return Err(Error::account_not_found(sender_addr)),
+    };
+    ACCOUNTS.save(&mut auth, sender_addr, &account)?;
+
+    Ok(Event::new("rotate_key").add_attribute("address", sender_addr))
+}
+
+/// Return the account from which the tx's fee is to be deducted: the granter
+/// if one is specified, otherwise the payer, which defaults to the primary
+/// signer. Return error if the payer isn't a signer of the tx.
+///
+/// A granter who signs the tx pays directly. Otherwise, the granter must have
+/// granted the payer a fee allowance, which is charged with `use_fee_allowance`
+/// once the coins to deduct are known.
+pub fn fee_payer(store: &dyn Storage, body: &TxBody, signer_addrs: &[Addr]) -> Result<Addr> {
+    let (payer_addr, granter_addr) = resolve_fee_payer(body, signer_addrs)?;
+
+    let Some(granter_addr) = granter_addr else {
+        return Ok(payer_addr);
+    };
+
+    let auth = Module::Auth.substore(store);
+    if !FEE_ALLOWANCES.has(&auth, (&granter_addr, &payer_addr)) {
+        return Err(Error::allowance_not_found(granter_addr, payer_addr));
+    }
+
+    Ok(granter_addr)
+}
+
+/// If the tx's fee is paid from a fee allowance, update the allowance to
+/// reflect the coins deducted, which may differ from the fee set in the tx if
+/// the taxman contract decides so. Return error if it doesn't cover them.
+pub fn use_fee_allowance(
+    store: &mut dyn Storage,
+    block: &BlockInfo,
+    body: &TxBody,
+    signer_addrs: &[Addr],
+    deducted: &[Coin],
+) -> Result<()> {
+    let (payer_addr, granter_addr) = resolve_fee_payer(body, signer_addrs)?;
+
+    let Some(granter_addr) = granter_addr else {
+        return Ok(());
+    };
+
+    feegrant::use_allowance(store, block, &granter_addr, &payer_addr, deducted, &body.msgs)
+}
+
+/// Return the tx's fee payer, and the granter of the allowance that the fee is
+/// paid from, if a granter is specified who doesn't sign. A granter who signs
+/// is returned as the payer.
+fn resolve_fee_payer(body: &TxBody, signer_addrs: &[Addr]) -> Result<(Addr, Option<Addr>)> {
+    let fee = &body.fee;
+
+    let payer_addr = match &fee.payer {
+        Some(payer) => signer_addrs
+            .iter()
+            .find(|addr| *addr == payer)
+            .cloned()
+            .ok_or_else(|| Error::fee_payer_not_signer(payer))?,
+        None => signer_addrs[0].clone(),
+    };
+
+    let granter = match &fee.granter {
+        Some(granter) => granter,
+        None => return Ok((payer_addr, None)),
+    };
+
+    if let Some(granter_addr) = signer_addrs.iter().find(|addr| *addr == granter) {
+        return Ok((granter_addr.clone(), None));
+    }
+
+    Ok((payer_addr, Some(address::validate(granter)?)))
+}
+
+/// Return error if the sender has executed a tx carrying the same idempotency
+/// key within the window. The error includes the hash of the original tx.
+pub fn check_idempotency_key(
+    store: &dyn Storage,
+    block: &BlockInfo,
+    sender_addr: &Addr,
+    key: &str,
+) -> Result<()> {
+    let auth = Module::Auth.substore(store);
+    match IDEMPOTENCY_KEYS.may_load(&auth, (sender_addr, key))? {
+        Some(record) if record.expires > block.time.seconds() => {
+            Err(Error::duplicate_tx(sender_addr, key, &record.tx_hash))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Remember that the sender has executed a tx carrying the idempotency key.
+pub fn record_idempotency_key(
+    store: &mut dyn Storage,
+    block: &BlockInfo,
+    sender_addr: &Addr,
+    key: &str,
+    tx_hash: &[u8],
+) -> Result<()> {
+    let mut auth = Module::Auth.substore_mut(store);
+    let expires = block.time.seconds() + IDEMPOTENCY_WINDOW;
+
+    IDEMPOTENCY_KEYS.save(
+        &mut auth,
+        (sender_addr, key),
+        &IdempotencyRecord {
+            tx_hash: tx_hash.into(),
+            expires,
+        },
+    )?;
+
+    IDEMPOTENCY_EXPIRIES.update(&mut auth, expires, |opt| -> StdResult<_> {
+        let mut keys = opt.unwrap_or_default();
+        keys.push((sender_addr.clone(), key.into()));
+        Ok(keys)
+    })?;
+
+    Ok(())
+}
+
+/// Forget the idempotency keys whose windows have elapsed.
+pub fn prune_idempotency_keys(store: &mut dyn Storage, block: &BlockInfo) -> Result<()> {
+    let mut auth = Module::Auth.substore_mut(store);
+    let now = block.time.seconds();
+
+    let expired = IDEMPOTENCY_EXPIRIES
+        .range(&auth, None, Some(Bound::inclusive(now)), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (expires, keys) in expired {
+        for (sender_addr, key) in keys {
+            // the key may have been reused after it expired, in which case the
+            // record must be kept
+            let record = IDEMPOTENCY_KEYS.may_load(&auth, (&sender_addr, &key))?;
+            if record.map_or(false, |record| record.expires <= now) {
+                IDEMPOTENCY_KEYS.remove(&mut auth, (&sender_addr, &key));
+            }
+        }
+        IDEMPOTENCY_EXPIRIES.remove(&mut auth, expires);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coins, testing::MockStorage, Timestamp};
+    use cw_sdk::{canonical::to_canonical_vec, Allowance, LegacySignDoc, MultiSignature, SdkMsg};
+    use cw_store::Shared;
+    use k256::ecdsa::{
+        signature::{DigestSigner, Signer},
+        SigningKey,
+    };
+
+    use super::*;
+
+    #[test]
+    fn decoding_canonical_tx() {
+        let tx = Tx {
+            body: TxBody {
+                idempotency_key: Some("caf\u{e9}\n".into()),
+                ..TxBody::single("larry", "dev-1", 0, 1, vec![])
+            },
+            signatures: vec![TxSignature {
+                pubkey: None,
+                pubkey_type: PubKeyType::Secp256k1,
+                signature: b"signature".into(),
+                multisig: None,
+                legacy_amino_json: false,
+            }],
+        };
+
+        let tx_bytes = to_canonical_vec(&tx).unwrap();
+        assert_eq!(decode_tx(&tx_bytes).unwrap(), (tx.clone(), SignMode::Json));
+
+        // fields not sorted
+        let tx_bytes = serde_json::to_vec(&tx).unwrap();
+        assert!(matches!(decode_tx(&tx_bytes).unwrap_err(), Error::NonCanonicalTx));
+
+        // extra whitespace
+        let tx_bytes = serde_json::to_vec_pretty(&serde_json::to_value(&tx).unwrap()).unwrap();
+        assert!(matches!(decode_tx(&tx_bytes).unwrap_err(), Error::NonCanonicalTx));
+    }
+
+    #[test]
+    fn decoding_protobuf_tx() {
+        let body = TxBody::single("larry", "dev-1", 3, 1, vec![SdkMsg::Execute {
+            contract: "bank".into(),
+            msg: serde_json::json!({ "transfer": {} }),
+            funds: coins(100, "uatom"),
+        }]);
+        let signatures = vec![TxSignature {
+            pubkey: Some(b"pubkey".into()),
+            pubkey_type: PubKeyType::Secp256k1,
+            signature: b"signature".into(),
+            multisig: None,
+            legacy_amino_json: false,
+        }];
+
+        let body_bytes = proto::encode_body(&body).unwrap();
+        let tx_bytes = proto::encode_tx(body_bytes.clone(), &signatures);
+
+        let (tx, sign_mode) = decode_tx(&tx_bytes).unwrap();
+        assert_eq!(tx.body, body);
+        assert_eq!(tx.signatures, signatures);
+        assert_eq!(sign_mode, SignMode::Direct {
+            body_bytes: body_bytes.into(),
+        });
+    }
+
+    #[test]
+    fn resolving_fee_payer() {
+        let mut store = MockStorage::new();
+        let block = block_at(100);
+        let [larry, jake, sponsor] =
+            ["larry", "jake", "sponsor"].map(|label| address::derive_from_label(label).unwrap());
+        let signer_addrs = [larry.clone(), jake.clone()];
+        let body = |payer: Option<&Addr>, granter: Option<&Addr>| TxBody {
+            fee: Fee {
+                amount: coins(100, "uatom"),
+                gas_limit: 0,
+                payer: payer.map(Addr::to_string),
+                granter: granter.map(Addr::to_string),
+            },
+            ..TxBody::single(&larry, "dev-1", 0, 1, vec![])
+        };
+        let resolve = |body: TxBody| fee_payer(&store, &body, &signer_addrs);
+
+        // the primary signer pays by default
+        assert_eq!(resolve(body(None, None)).unwrap(), larry);
+
+        // the granter takes precedence over the payer
+        assert_eq!(resolve(body(Some(&jake), None)).unwrap(), jake);
+        assert_eq!(resolve(body(Some(&jake), Some(&larry))).unwrap(), larry);
+
+        // the payer must be a signer
+        let err = resolve(body(Some(&sponsor), None)).unwrap_err();
+        assert!(matches!(err, Error::FeePayerNotSigner { .. }));
+
+        // a granter who doesn't sign must have granted the payer an allowance
+        let err = resolve(body(Some(&jake), Some(&sponsor))).unwrap_err();
+        assert!(matches!(err, Error::AllowanceNotFound { .. }));
+
+        let allowance = Allowance::Basic {
+            spend_limit: coins(150, "uatom"),
+            expiration: None,
+        };
+        feegrant::grant_allowance(&mut store, &block, &sponsor, jake.as_str(), allowance).unwrap();
+
+        let resolve = |body: TxBody| fee_payer(&store, &body, &signer_addrs);
+        assert_eq!(resolve(body(Some(&jake), Some(&sponsor))).unwrap(), sponsor);
+
+        // the allowance is granted to the payer only
+        let err = resolve(body(None, Some(&sponsor))).unwrap_err();
+        assert!(matches!(err, Error::AllowanceNotFound { .. }));
+
+        // the allowance is charged the coins actually deducted, rather than the
+        // fee set in the tx, e.g. if the taxman contract charges more
+        let sponsored = body(Some(&jake), Some(&sponsor));
+        let deducted = coins(120, "uatom");
+        use_fee_allowance(&mut store, &block, &sponsored, &signer_addrs, &deducted).unwrap();
+        let err = use_fee_allowance(&mut store, &block, &sponsored, &signer_addrs, &deducted)
+            .unwrap_err();
+        assert!(matches!(err, Error::AllowanceExceeded { .. }));
+
+        // a granter who signs pays directly, without an allowance
+        let signed = body(None, Some(&jake));
+        use_fee_allowance(&mut store, &block, &signed, &signer_addrs, &deducted).unwrap();
+    }
+
+    #[test]
+    fn checking_timeout_height() {
+        let body = TxBody {
+            timeout_height: Some(10),
+            ..TxBody::single("larry", "dev-1", 0, 1, vec![])
+        };
+        check_timeout_height(&body, 9).unwrap();
+        check_timeout_height(&body, 10).unwrap();
+        let err = check_timeout_height(&body, 11).unwrap_err();
+        assert!(matches!(err, Error::TxTimedOut { timeout_height: 10, height: 11 }));
+
+        // no timeout height means the tx never times out
+        let body = TxBody::single("larry", "dev-1", 0, 1, vec![]);
+        check_timeout_height(&body, u64::MAX).unwrap();
+    }
+
+    #[test]
+    fn verifying_eth_signature() {
+        let sk = SigningKey::from_bytes(&[1; 32]).unwrap();
+        let pubkey = sk.verifying_key().to_bytes().to_vec();
+        let sign_bytes = b"sign_doc";
+
+        let signature: recoverable::Signature =
+            sk.sign_digest(Keccak256::new_with_prefix(sign_bytes));
+        let mut signature = signature.as_ref().to_vec();
+
+        // the pubkey can be recovered from the signature, with or without the
+        // recovery id offset
+        assert_eq!(recover_eth_pubkey(sign_bytes, &signature).unwrap(), sk.verifying_key());
+        verify_signature(PubKeyType::EthSecp256k1, &pubkey, sign_bytes, &signature).unwrap();
+        signature[64] += 27;
+        verify_signature(PubKeyType::EthSecp256k1, &pubkey, sign_bytes, &signature).unwrap();
+
+        // the signature doesn't verify for different sign bytes
+        verify_signature(PubKeyType::EthSecp256k1, &pubkey, b"another", &signature).unwrap_err();
+
+        // a signature without the recovery id is rejected
+        let err = recover_eth_pubkey(sign_bytes, &signature[..64]).unwrap_err();
+        assert!(matches!(err, Error::InvalidEthSignatureLength { .. }));
+    }
+
+    #[test]
+    fn authenticating_multisig() {
+        // the members' keys, sorted by their pubkeys
+        let mut members = [1, 2, 3]
+            .map(|i| SigningKey::from_bytes(&[i; 32]).unwrap())
+            .into_iter()
+            .map(|sk| (Binary::from(sk.verifying_key().to_bytes().to_vec()), sk))
+            .collect::<Vec<_>>();
+        members.sort_by(|a, b| a.0.as_slice().cmp(b.0.as_slice()));
+
+        let pubkey = MultisigPubKey {
+            pubkeys: members.iter().map(|(pubkey, _)| pubkey.clone()).collect(),
+            threshold: 2,
+        };
+        let address = pubkey.derive_address().unwrap();
+
+        let body = TxBody::single(address.clone(), "dev-1", 0, 1, vec![]);
+        let sign_bytes = body.sign_doc(&body.signers[0]).unwrap().to_sign_bytes().unwrap();
+        let tx = |pubkey: &MultisigPubKey, signs: [bool; 3]| Tx {
+            body: body.clone(),
+            signatures: vec![TxSignature {
+                pubkey: None,
+                pubkey_type: PubKeyType::Secp256k1,
+                signature: Binary::default(),
+                multisig: Some(MultiSignature {
+                    pubkey: Some(pubkey.clone()),
+                    signatures: members
+                        .iter()
+                        .zip(signs)
+                        .map(|((_, sk), sign)| {
+                            let signature: Signature = sk.sign(&sign_bytes);
+                            sign.then(|| signature.as_ref().to_vec().into())
+                        })
+                        .collect(),
+                }),
+                legacy_amino_json: false,
+            }],
+        };
+        let block = block_at(100);
+        let authenticate = |tx: &Tx| {
+            let store = MockStorage::new();
+            let sigs = SignatureCache::default();
+            authenticate_tx(store, &block, tx, &SignMode::Json, &sigs, &SystemGas::default())
+        };
+
+        // the pubkeys of a new multisig must be sorted
+        let mut unsorted = pubkey.clone();
+        unsorted.pubkeys.reverse();
+        let err = authenticate(&tx(&unsorted, [true; 3])).unwrap_err();
+        assert!(matches!(err, Error::InvalidMultisig { .. }));
+
+        // one signature doesn't meet the threshold
+        let err = authenticate(&tx(&pubkey, [true, false, false])).unwrap_err();
+        assert!(matches!(err, Error::MultisigThresholdNotMet { threshold: 2, found: 1 }));
+
+        // any two members can sign
+        let (signer_addrs, store) = authenticate(&tx(&pubkey, [false, true, true])).unwrap();
+        assert_eq!(signer_addrs, [address.clone()]);
+
+        let account = ACCOUNTS.load(&Module::Auth.substore(&store), &address).unwrap();
+        assert_eq!(account, Account::Multisig {
+            pubkey,
+            account_number: 0,
+            sequence: 1,
+        });
+    }
+
+    #[test]
+    fn rotating_key() {
+        let old_sk = SigningKey::from_bytes(&[1; 32]).unwrap();
+        let new_sk = SigningKey::from_bytes(&[2; 32]).unwrap();
+        let pubkey_of = |sk: &SigningKey| Binary::from(sk.verifying_key().to_bytes().to_vec());
+        let address = address::derive_from_pubkey(&pubkey_of(&old_sk)).unwrap();
+
+        let tx = |sk: &SigningKey, sequence: u64| {
+            let body = TxBody::single(address.clone(), "dev-1", 0, sequence, vec![]);
+            let sign_bytes = body.sign_doc(&body.signers[0]).unwrap().to_sign_bytes().unwrap();
+            let signature: Signature = sk.sign(&sign_bytes);
+            Tx {
+                body,
+                signatures: vec![TxSignature {
+                    pubkey: Some(pubkey_of(sk)),
+                    pubkey_type: PubKeyType::Secp256k1,
+                    signature: signature.as_ref().to_vec().into(),
+                    multisig: None,
+                    legacy_amino_json: false,
+                }],
+            }
+        };
+        let block = block_at(100);
+        let authenticate = |store: Shared<MockStorage>, tx: &Tx| {
+            let sigs = SignatureCache::default();
+            authenticate_tx(store, &block, tx, &SignMode::Json, &sigs, &SystemGas::default())
+        };
+
+        // the account is created by its first tx, signed by the old key
+        let store = Shared::new(MockStorage::new());
+        let (_, mut store) = authenticate(store, &tx(&old_sk, 1)).unwrap();
+
+        // the new key must be a valid pubkey
+        let err =
+            rotate_key(&mut store, &address, b"invalid".into(), PubKeyType::Secp256k1).unwrap_err();
+        assert!(matches!(err, Error::Ecdsa(_)));
+
+        rotate_key(&mut store, &address, pubkey_of(&new_sk), PubKeyType::Secp256k1).unwrap();
+
+        let account = ACCOUNTS.load(&Module::Auth.substore(&store), &address).unwrap();
+        assert_eq!(account, Account::Base {
+            pubkey: pubkey_of(&new_sk),
+            pubkey_type: PubKeyType::Secp256k1,
+            account_number: 0,
+            sequence: 1,
+        });
+
+        // the old key can no longer sign for the account, but the new key can,
+        // even though the address isn't derived from it
+        let err = authenticate(store.share(), &tx(&old_sk, 2)).unwrap_err();
+        assert!(matches!(err, Error::PubkeyMismatch { .. }));
+        let (signer_addrs, _) = authenticate(store, &tx(&new_sk, 2)).unwrap();
+        assert_eq!(signer_addrs, [address]);
+    }
+
+    #[test]
+    fn authenticating_legacy_amino_json() {
+        let sk = SigningKey::from_bytes(&[1; 32]).unwrap();
+        let pubkey = Binary::from(sk.verifying_key().to_bytes().to_vec());
+        let address = address::derive_from_pubkey(&pubkey).unwrap();
+
+        let body = TxBody::single(address.clone(), "dev-1", 0, 1, vec![]);
+        let sign_bytes = LegacySignDoc::new(&body, "dev-1", 0, 1).to_sign_bytes().unwrap();
+        let signature: Signature = sk.sign(&sign_bytes);
+        let tx = |legacy_amino_json: bool| Tx {
+            body: body.clone(),
+            signatures: vec![TxSignature {
+                pubkey: Some(pubkey.clone()),
+                pubkey_type: PubKeyType::Secp256k1,
+                signature: signature.as_ref().to_vec().into(),
+                multisig: None,
+                legacy_amino_json,
+            }],
+        };
+        let block = block_at(100);
+        let authenticate = |tx: &Tx| {
+            let store = MockStorage::new();
+            let sigs = SignatureCache::default();
+            authenticate_tx(store, &block, tx, &SignMode::Json, &sigs, &SystemGas::default())
+        };
+
+        // the legacy sign doc is signed in place of the tx's sign mode
+        let (signer_addrs, _) = authenticate(&tx(true)).unwrap();
+        assert_eq!(signer_addrs, [address]);
+
+        // without the flag, the signature is verified against the JSON sign doc
+        authenticate(&tx(false)).unwrap_err();
+    }
+
+    fn block_at(seconds: u64) -> BlockInfo {
+        BlockInfo {
+            height: 1,
+            time: Timestamp::from_seconds(seconds),
+            chain_id: "dev-1".into(),
+        }
+    }
+
+    #[test]
+    fn deduplicating_idempotency_keys() {
+        let mut store = MockStorage::new();
+        let sender_addr = Addr::unchecked("larry");
+
+        let block = block_at(100);
+        check_idempotency_key(&store, &block, &sender_addr, "key").unwrap();
+        record_idempotency_key(&mut store, &block, &sender_addr, "key", b"hash").unwrap();
+
+        // retry within the window should fail
+        let block = block_at(100 + IDEMPOTENCY_WINDOW - 1);
+        prune_idempotency_keys(&mut store, &block).unwrap();
+        let err = check_idempotency_key(&store, &block, &sender_addr, "key").unwrap_err();
+        assert!(matches!(err, Error::DuplicateTx { .. }));
+
+        // a different key or a different sender should be fine
+        check_idempotency_key(&store, &block, &sender_addr, "another_key").unwrap();
+        check_idempotency_key(&store, &block, &Addr::unchecked("jake"), "key").unwrap();
+
+        // once the window has elapsed, the key is forgotten
+        let block = block_at(100 + IDEMPOTENCY_WINDOW);
+        prune_idempotency_keys(&mut store, &block).unwrap();
+        check_idempotency_key(&store, &block, &sender_addr, "key").unwrap();
+
+        let auth = Module::Auth.substore(&store);
+        assert!(IDEMPOTENCY_KEYS.is_empty(&auth));
+        assert!(IDEMPOTENCY_EXPIRIES.is_empty(&auth));
+    }
+}
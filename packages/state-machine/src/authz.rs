@@ -0,0 +1,238 @@
+//! Authorizations, which let an account (the granter) authorize another account
+//! (the grantee) to send certain messages on its behalf.
+//!
+//! The grantee does so by sending an `Exec` message that wraps the messages,
+//! each of which names the granter as its sender. Each wrapped message must be
+//! covered by an unexpired grant, either of its message type, or, for contract
+//! executions, of the contract being executed.
+
+use cosmwasm_std::{Addr, BlockInfo, Event, Storage};
+use cw_sdk::{address, Authorization, AuthzGrant, SdkMsg};
+
+use crate::{
+    error::{Error, Result},
+    state::{Module, AUTHZ_GRANTS},
+};
+
+/// Grant the grantee an authorization, replacing any existing grant of the
+/// same authorization by the same granter.
+pub fn grant(
+    store: &mut dyn Storage,
+    block: &BlockInfo,
+    granter_addr: &Addr,
+    grantee: &str,
+    authorization: Authorization,
+    expiration: Option<u64>,
+) -> Result<Event> {
+    let grantee_addr = address::validate(grantee)?;
+    if *granter_addr == grantee_addr {
+        return Err(Error::invalid_authz_grant("cannot grant an authorization to self"));
+    }
+
+    if let Some(expiration) = expiration {
+        if expiration <= block.time.seconds() {
+            return Err(Error::invalid_authz_grant("expiration must be in the future"));
+        }
+    }
+
+    let authorization = normalize(authorization)?;
+    if let Authorization::Generic {
+        msg,
+    } = &authorization
+    {
+        // a key rotation must be approved by the old key itself
+        if msg == "exec" || msg == "rotate_key" {
+            return Err(Error::invalid_authz_grant(format!("cannot authorize {msg} messages")));
+        }
+    }
+
+    let key = authorization.key();
+    let grant = AuthzGrant {
+        authorization,
+        expiration,
+    };
+
+    let mut auth = Module::Auth.substore_mut(store);
+    AUTHZ_GRANTS.save(&mut auth, (granter_addr, &grantee_addr, &key), &grant)?;
+
+    Ok(Event::new("grant_authz")
+        .add_attribute("granter", granter_addr)
+        .add_attribute("grantee", grantee_addr)
+        .add_attribute("authorization", key))
+}
+
+/// Revoke an authorization granted to the grantee.
+pub fn revoke(
+    store: &mut dyn Storage,
+    granter_addr: &Addr,
+    grantee: &str,
+    authorization: Authorization,
+) -> Result<Event> {
+    let grantee_addr = address::validate(grantee)?;
+    let key = normalize(authorization)?.key();
+
+    let mut auth = Module::Auth.substore_mut(store);
+    if !AUTHZ_GRANTS.has(&auth, (granter_addr, &grantee_addr, &key)) {
+        return Err(Error::authz_grant_not_found(granter_addr, grantee_addr, key));
+    }
+    AUTHZ_GRANTS.remove(&mut auth, (granter_addr, &grantee_addr, &key));
+
+    Ok(Event::new("revoke_authz")
+        .add_attribute("granter", granter_addr)
+        .add_attribute("grantee", grantee_addr)
+        .add_attribute("authorization", key))
+}
+
+/// Check that the granter has authorized the grantee to send the message on
+/// its behalf. Return error if there is no unexpired grant that covers it.
+pub fn authorize(
+    store: &dyn Storage,
+    block: &BlockInfo,
+    granter_addr: &Addr,
+    grantee_addr: &Addr,
+    msg: &SdkMsg,
+) -> Result<()> {
+    let kind = msg.kind();
+    if let SdkMsg::Exec {
+        ..
+    } = msg
+    {
+        return Err(Error::NestedExec);
+    }
+
+    // a contract execution is covered by either a grant of all executions, or
+    // of executions of that specific contract
+    let mut keys = vec![kind.to_owned()];
+    if let SdkMsg::Execute {
+        contract,
+        ..
+    } = msg
+    {
+        let contract_addr = address::resolve_raw(contract)?;
+        keys.push(
+            Authorization::ContractExecute {
+                contract: contract_addr.into(),
+            }
+            .key(),
+        );
+    }
+
+    let auth = Module::Auth.substore(store);
+    let mut expired = false;
+    for key in keys {
+        match AUTHZ_GRANTS.may_load(&auth, (granter_addr, grantee_addr, &key))? {
+            Some(grant) if is_expired(&grant, block) => expired = true,
+            Some(_) => return Ok(()),
+            None => (),
+        }
+    }
+
+    if expired {
+        Err(Error::authz_grant_expired(granter_addr, grantee_addr, kind))
+    } else {
+        Err(Error::authz_grant_not_found(granter_addr, grantee_addr, kind))
+    }
+}
+
+/// Resolve the contract of a contract execute authorization to its address, so
+/// that a grant made using the contract's label also covers executions using
+/// its address, and vice versa.
+fn normalize(authorization: Authorization) -> Result<Authorization> {
+    match authorization {
+        Authorization::ContractExecute {
+            contract,
+        } => Ok(Authorization::ContractExecute {
+            contract: address::resolve_raw(&contract)?.into(),
+        }),
+        authorization => Ok(authorization),
+    }
+}
+
+fn is_expired(grant: &AuthzGrant, block: &BlockInfo) -> bool {
+    grant.expiration.map_or(false, |expiration| expiration <= block.time.seconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{testing::MockStorage, Timestamp};
+    use cw_sdk::TxMsg;
+
+    use super::*;
+
+    fn block_at(seconds: u64) -> BlockInfo {
+        BlockInfo {
+            height: 1,
+            time: Timestamp::from_seconds(seconds),
+            chain_id: "dev-1".into(),
+        }
+    }
+
+    fn execute_msg(contract: &str) -> SdkMsg {
+        SdkMsg::Execute {
+            contract: contract.into(),
+            msg: serde_json::json!({}),
+            funds: vec![],
+        }
+    }
+
+    #[test]
+    fn authorizing_msgs() {
+        let mut store = MockStorage::new();
+        let granter_addr = address::derive_from_label("granter").unwrap();
+        let grantee_addr = address::derive_from_label("grantee").unwrap();
+        let authorize_at = |store: &dyn Storage, seconds: u64, msg: &SdkMsg| {
+            authorize(store, &block_at(seconds), &granter_addr, &grantee_addr, msg)
+        };
+
+        // nothing has been authorized yet
+        let err = authorize_at(&store, 100, &execute_msg("bank")).unwrap_err();
+        assert!(matches!(err, Error::AuthzGrantNotFound { .. }));
+
+        // authorize executing the bank contract, referred to by its label
+        let authorization = Authorization::ContractExecute {
+            contract: "bank".into(),
+        };
+        let block = block_at(100);
+        grant(&mut store, &block, &granter_addr, grantee_addr.as_str(), authorization, Some(200))
+            .unwrap();
+
+        // executions of the bank contract are covered, whether it's referred to
+        // by its label or its address, but executions of other contracts are not
+        let bank_addr = address::derive_from_label("bank").unwrap();
+        authorize_at(&store, 150, &execute_msg("bank")).unwrap();
+        authorize_at(&store, 150, &execute_msg(bank_addr.as_str())).unwrap();
+        let err = authorize_at(&store, 150, &execute_msg("staking")).unwrap_err();
+        assert!(matches!(err, Error::AuthzGrantNotFound { .. }));
+
+        // the grant can't be used once it expires
+        let err = authorize_at(&store, 200, &execute_msg("bank")).unwrap_err();
+        assert!(matches!(err, Error::AuthzGrantExpired { .. }));
+
+        // authorize all executions, without expiration
+        let authorization = Authorization::Generic {
+            msg: "execute".into(),
+        };
+        let block = block_at(200);
+        grant(&mut store, &block, &granter_addr, grantee_addr.as_str(), authorization, None)
+            .unwrap();
+        authorize_at(&store, 300, &execute_msg("staking")).unwrap();
+
+        // nested execs are never authorized
+        let exec = SdkMsg::Exec {
+            msgs: vec![TxMsg {
+                sender: granter_addr.to_string(),
+                msg: execute_msg("bank"),
+            }],
+        };
+        let err = authorize_at(&store, 300, &exec).unwrap_err();
+        assert!(matches!(err, Error::NestedExec));
+
+        // revoking the grant
+        let authorization = Authorization::Generic {
+            msg: "execute".into(),
+        };
+        revoke(&mut store, &granter_addr, grantee_addr.as_str(), authorization).unwrap();
+        let err = authorize_at(&store, 300, &execute_msg("staking")).unwrap_err();
+        assert!(matches!(err, Error::AuthzGrantNotFound { .. }));
+    }
+}
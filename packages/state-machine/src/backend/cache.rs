@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use cosmwasm_vm::{
+    capabilities_from_csv, Backend, Cache, CacheOptions, Checksum, InstanceOptions, Size,
+    VmResult,
+};
+use tracing::warn;
+
+use super::{
+    BackendApi, BackendQuerier, ContractSubstore, DynStorage, VmInstance, WasmConfig,
+    INSTANCE_MEMORY_LIMIT,
+};
+
+/// The capabilities that contracts may require, i.e. all those the state
+/// machine supports.
+const CAPABILITIES: &str = "iterator,staking,stargate,cosmwasm_1_1";
+
+/// How many instances of a code are created before its module is pinned in
+/// memory, e.g. the bank contract's, which is called for every tx.
+const PIN_AFTER_INSTANCES: u32 = 100;
+
+/// The most modules pinned in memory at once. Pinned modules are never evicted,
+/// so this bounds the memory they take up.
+const MAX_PINNED: usize = 32;
+
+/// A cache of compiled contract modules, both in memory and on disk, so that a
+/// contract isn't compiled again every time it's called, nor every time the
+/// node restarts.
+///
+/// On disk, the modules are saved under the cache directory, keyed by the
+/// checksum of their code, and by the version of the module serialization
+/// format and the target, so that the modules compiled by an older version of
+/// the VM are never loaded.
+///
+/// The modules of the codes that are instantiated most often are pinned in
+/// memory, where they can't be evicted by other modules and are instantiated
+/// the fastest. Each call still gets a fresh instance: the VM can't reset an
+/// instance's memory, so reusing one would leak state between calls.
+///
+/// Clones share the same cache, e.g. the querier of each instance holds one to
+/// create the instances of the contracts it queries.
+#[derive(Clone)]
+pub struct ModuleCache {
+    cache: Arc<Cache<BackendApi, ContractSubstore<DynStorage>, BackendQuerier>>,
+
+    /// The codes called since the node started
+    codes: Arc<Mutex<Codes>>,
+}
+
+#[derive(Default)]
+struct Codes {
+    /// Whether each code can be cached, and how many instances of it have been
+    /// created. A code that the cache rejects, e.g. because it requires a
+    /// capability that the cache doesn't know, is compiled on every call
+    /// instead, exactly as it would be without the cache.
+    stats: HashMap<Checksum, CodeStats>,
+
+    /// How many modules are pinned
+    pinned: usize,
+}
+
+struct CodeStats {
+    cacheable: bool,
+    instances: u32,
+}
+
+impl ModuleCache {
+    /// Open the cache at the given directory, creating it if it doesn't exist.
+    pub fn open(dir: impl AsRef<Path>, wasm: &WasmConfig) -> VmResult<Self> {
+        let options = CacheOptions {
+            base_dir: dir.as_ref().to_path_buf(),
+            available_capabilities: capabilities_from_csv(CAPABILITIES),
+            memory_cache_size: Size::mebi(wasm.module_cache_size as usize),
+            instance_memory_limit: INSTANCE_MEMORY_LIMIT,
+        };
+
+        // SAFETY: the directory is only written to by this cache; the node's
+        // home directory must not be shared by several nodes
+        let cache = unsafe { Cache::new(options)? };
+
+        Ok(Self {
+            cache: Arc::new(cache),
+            codes: Arc::new(Mutex::new(Codes::default())),
+        })
+    }
+
+    /// The checksum of the given code, if it can be cached. The code is saved
+    /// in the cache directory, which the cache compiles it from when its
+    /// module isn't on disk yet.
+    ///
+    /// Whether a code can be cached is remembered until the node restarts.
+    pub(crate) fn checksum(&self, code: &[u8]) -> Option<Checksum> {
+        let checksum = Checksum::generate(code);
+
+        let mut codes = self.codes.lock().unwrap();
+        if let Some(stats) = codes.stats.get(&checksum) {
+            return stats.cacheable.then_some(checksum);
+        }
+
+        // the code may have been saved before the node restarted
+        let saved = if self.cache.load_wasm(&checksum).is_ok() {
+            true
+        } else if let Err(err) = self.cache.save_wasm(code) {
+            warn!(checksum = %checksum.to_hex(), "Code can't be cached: {err}");
+            false
+        } else {
+            true
+        };
+
+        codes.stats.insert(
+            checksum,
+            CodeStats {
+                cacheable: saved,
+                instances: 0,
+            },
+        );
+        saved.then_some(checksum)
+    }
+
+    /// Create an instance of the code of the given checksum, which must have
+    /// been returned by `checksum`.
+    pub(crate) fn instance(
+        &self,
+        checksum: &Checksum,
+        backend: Backend<BackendApi, ContractSubstore<DynStorage>, BackendQuerier>,
+        options: InstanceOptions,
+    ) -> VmResult<VmInstance> {
+        self.count_instance(checksum);
+        self.cache.get_instance(checksum, backend, options)
+    }
+
+    /// Count an instance of the code of the given checksum, pinning its module
+    /// once the code is hot, if there's room.
+    fn count_instance(&self, checksum: &Checksum) {
+        let mut codes = self.codes.lock().unwrap();
+        let Codes {
+            stats,
+            pinned,
+        } = &mut *codes;
+        let Some(stats) = stats.get_mut(checksum) else {
+            return;
+        };
+
+        stats.instances = stats.instances.saturating_add(1);
+        if stats.instances != PIN_AFTER_INSTANCES || *pinned >= MAX_PINNED {
+            return;
+        }
+
+        // failing to pin only makes instantiation slower, so it's not an error
+        match self.cache.pin(checksum) {
+            Ok(()) => *pinned += 1,
+            Err(err) => warn!(checksum = %checksum.to_hex(), "Module can't be pinned: {err}"),
+        }
+    }
+}
+
+impl fmt::Debug for ModuleCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModuleCache").finish_non_exhaustive()
+    }
+}
@@ -1,12 +1,122 @@
 mod api;
+mod cache;
 mod querier;
 mod storage;
 
 pub use api::BackendApi;
+pub use cache::ModuleCache;
 pub use querier::BackendQuerier;
-pub use storage::ContractSubstore;
+pub use storage::{ContractSubstore, DynStorage};
 
-use cosmwasm_vm::BackendError;
+use cosmwasm_std::{Addr, BlockInfo, Storage};
+use cosmwasm_vm::{Backend, BackendError, Instance, InstanceOptions, Size, VmResult};
+use cw_sdk::GasConfig;
+use serde::{Deserialize, Serialize};
+
+/// A wasm instance that a contract is run in.
+pub type VmInstance = Instance<BackendApi, ContractSubstore<DynStorage>, BackendQuerier>;
+
+/// The maximum size of each instance's memory. Whether a call runs out of
+/// memory decides whether it fails, so the limit is the same on every node
+/// rather than configured by the operator. Same as wasmd's.
+pub const INSTANCE_MEMORY_LIMIT: Size = Size::mebi(32);
+
+/// Options of the wasm instances that contracts are run in, e.g. as configured
+/// by the operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WasmConfig {
+    /// Whether contracts' debug messages are printed to stdout
+    pub print_debug: bool,
+
+    /// The maximum amount of (CosmWasm) gas each smart query may consume
+    pub query_gas_limit: u64,
+
+    /// Whether compiled contract modules are cached on disk, so that contracts
+    /// aren't compiled again every time the node restarts
+    pub module_cache: bool,
+
+    /// The size of the in-memory cache of compiled contract modules, in MiB,
+    /// in front of the on-disk cache
+    pub module_cache_size: u32,
+}
+
+impl Default for WasmConfig {
+    fn default() -> Self {
+        Self {
+            print_debug: false,
+            query_gas_limit: 10_000_000_000,
+            module_cache: true,
+            module_cache_size: 100,
+        }
+    }
+}
+
+impl WasmConfig {
+    pub fn instance_options(&self, gas_limit: u64) -> InstanceOptions {
+        InstanceOptions {
+            gas_limit,
+            print_debug: self.print_debug,
+        }
+    }
+}
+
+/// Create an instance of the given code, which the contract at the given
+/// address is run in with the given store, whose ops are charged the given gas
+/// costs. The module is taken from the cache if there is one and the code can
+/// be cached, or compiled otherwise.
+///
+/// The contracts that the instance queries read the same store, and are given
+/// the same block info. Once the call is done, the store can be taken back from
+/// the instance's backend with `DynStorage::downcast`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_instance(
+    code: &[u8],
+    store: impl Storage + 'static,
+    contract_addr: &Addr,
+    block: &BlockInfo,
+    gas_limit: u64,
+    gas_config: GasConfig,
+    wasm: &WasmConfig,
+    modules: Option<&ModuleCache>,
+) -> VmResult<VmInstance> {
+    let store = DynStorage::new(store);
+    let querier = BackendQuerier {
+        store: store.downgrade(),
+        block: block.clone(),
+        gas_config,
+        wasm: *wasm,
+        modules: modules.cloned(),
+        depth: 0,
+    };
+    new_instance(code, store, contract_addr, gas_limit, querier)
+}
+
+/// Create an instance whose contract is run with the given store and querier,
+/// with the querier's gas costs and options.
+fn new_instance(
+    code: &[u8],
+    store: DynStorage,
+    contract_addr: &Addr,
+    gas_limit: u64,
+    querier: BackendQuerier,
+) -> VmResult<VmInstance> {
+    let options = querier.wasm.instance_options(gas_limit);
+    let modules = querier.modules.clone();
+    let backend = Backend {
+        api: BackendApi,
+        storage: ContractSubstore::new(store, contract_addr).metered(querier.gas_config),
+        querier,
+    };
+
+    if let Some(modules) = modules {
+        if let Some(checksum) = modules.checksum(code) {
+            return modules.instance(&checksum, backend, options);
+        }
+    }
+
+    Instance::from_code(code, backend, options, Some(INSTANCE_MEMORY_LIMIT))
+}
 
 fn into_backend_err(err: impl std::error::Error) -> BackendError {
     BackendError::user_err(err.to_string())
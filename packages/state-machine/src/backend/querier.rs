@@ -1,14 +1,158 @@
-use cosmwasm_std::{Binary, ContractResult, SystemResult};
-use cosmwasm_vm::{BackendError, BackendResult, GasInfo, Querier};
+use cosmwasm_std::{
+    from_slice, Binary, BlockInfo, ContractInfo, ContractResult, Empty, Env, QueryRequest, Storage,
+    SystemError, SystemResult, WasmQuery,
+};
+use cosmwasm_vm::{call_query, BackendError, BackendResult, GasInfo, Querier, VmError};
+use cw_sdk::{address, GasConfig};
+use cw_store::prefix::concat;
 
-pub struct BackendQuerier;
+use super::{into_backend_err, new_instance, storage::WeakStorage, ModuleCache, WasmConfig};
+use crate::state::{code_by_address, contract_namespace};
+
+/// How deep contracts may query each other, e.g. a contract querying a contract
+/// that queries another is two levels deep. Same as wasmd's default.
+const MAX_QUERY_DEPTH: u32 = 10;
+
+/// Serves the queries that a contract makes to other contracts, e.g. the bank
+/// contract asking a denom's send hook whether a transfer is allowed.
+///
+/// Queries read the same state as the querying contract, including the writes
+/// it has made so far, and are given the same block info. Smart queries are
+/// charged the gas consumed by the queried contract, and raw queries the cost
+/// of a read, out of the querying contract's gas.
+pub struct BackendQuerier {
+    pub(super) store: WeakStorage,
+    pub(super) block: BlockInfo,
+    pub(super) gas_config: GasConfig,
+    pub(super) wasm: WasmConfig,
+    pub(super) modules: Option<ModuleCache>,
+
+    /// How many contracts up the stack are querying, zero for a contract that
+    /// is executed, or queried from outside the chain
+    pub(super) depth: u32,
+}
 
 impl Querier for BackendQuerier {
     fn query_raw(
         &self,
-        _request: &[u8],
-        _gas_limit: u64,
+        request: &[u8],
+        gas_limit: u64,
     ) -> BackendResult<SystemResult<ContractResult<Binary>>> {
-        (Err(BackendError::user_err("`querier.query_raw` is not yet implemented")), GasInfo::free())
+        let request: QueryRequest<Empty> = match from_slice(request) {
+            Ok(request) => request,
+            Err(err) => {
+                let err = SystemError::InvalidRequest {
+                    error: err.to_string(),
+                    request: request.into(),
+                };
+                return (Ok(SystemResult::Err(err)), GasInfo::free());
+            },
+        };
+
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr,
+                msg,
+            }) => self.query_smart(&contract_addr, &msg, gas_limit),
+            QueryRequest::Wasm(WasmQuery::Raw {
+                contract_addr,
+                key,
+            }) => self.query_raw_key(&contract_addr, &key),
+            _ => {
+                let err = SystemError::UnsupportedRequest {
+                    kind: "only smart and raw wasm queries are supported".into(),
+                };
+                (Ok(SystemResult::Err(err)), GasInfo::free())
+            },
+        }
     }
 }
+
+impl BackendQuerier {
+    fn query_smart(
+        &self,
+        contract: &str,
+        msg: &[u8],
+        gas_limit: u64,
+    ) -> BackendResult<SystemResult<ContractResult<Binary>>> {
+        if self.depth >= MAX_QUERY_DEPTH {
+            let err = SystemError::InvalidRequest {
+                error: format!("contracts may query each other at most {MAX_QUERY_DEPTH} deep"),
+                request: msg.into(),
+            };
+            return (Ok(SystemResult::Err(err)), GasInfo::free());
+        }
+
+        let Some(store) = self.store.upgrade() else {
+            return (Err(BackendError::unknown("querier outlived its store")), GasInfo::free());
+        };
+        let Ok(contract_addr) = address::resolve_raw(contract) else {
+            return no_such_contract(contract);
+        };
+        let Ok(code) = code_by_address(&store, &contract_addr) else {
+            return no_such_contract(contract);
+        };
+
+        let env = Env {
+            block: self.block.clone(),
+            transaction: None,
+            contract: ContractInfo {
+                address: contract_addr.clone(),
+            },
+        };
+        let querier = BackendQuerier {
+            store: store.downgrade(),
+            block: self.block.clone(),
+            gas_config: self.gas_config,
+            wasm: self.wasm,
+            modules: self.modules.clone(),
+            depth: self.depth + 1,
+        };
+
+        let mut instance = match new_instance(&code, store, &contract_addr, gas_limit, querier) {
+            Ok(instance) => instance,
+            Err(err) => return (Err(into_backend_err(err)), GasInfo::free()),
+        };
+        let result = call_query(&mut instance, &env, msg);
+
+        // the queried contract's gas is charged to the querying one, whether
+        // or not the query succeeded
+        let report = instance.create_gas_report();
+        let gas_info = GasInfo::with_externally_used(report.limit - report.remaining);
+
+        match result {
+            Ok(result) => (Ok(SystemResult::Ok(result)), gas_info),
+            Err(VmError::GasDepletion { .. }) => (Err(BackendError::out_of_gas()), gas_info),
+            Err(err) => (Ok(SystemResult::Ok(ContractResult::Err(err.to_string()))), gas_info),
+        }
+    }
+
+    fn query_raw_key(
+        &self,
+        contract: &str,
+        key: &[u8],
+    ) -> BackendResult<SystemResult<ContractResult<Binary>>> {
+        let Some(store) = self.store.upgrade() else {
+            return (Err(BackendError::unknown("querier outlived its store")), GasInfo::free());
+        };
+        let Ok(contract_addr) = address::resolve_raw(contract) else {
+            return no_such_contract(contract);
+        };
+
+        // a missing value is returned as empty, as it is by wasmd
+        let value = store.get(&concat(&contract_namespace(&contract_addr), key));
+        let bytes = key.len() + value.as_ref().map_or(0, Vec::len);
+        let gas_info = GasInfo::with_externally_used(
+            self.gas_config.to_wasm_gas(self.gas_config.read_cost(bytes)),
+        );
+
+        (Ok(SystemResult::Ok(ContractResult::Ok(value.unwrap_or_default().into()))), gas_info)
+    }
+}
+
+fn no_such_contract(contract: &str) -> BackendResult<SystemResult<ContractResult<Binary>>> {
+    let err = SystemError::NoSuchContract {
+        addr: contract.into(),
+    };
+    (Ok(SystemResult::Err(err)), GasInfo::free())
+}
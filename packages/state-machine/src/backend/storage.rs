@@ -1,13 +1,21 @@
-use std::collections::HashMap;
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::BTreeMap,
+    rc::{Rc, Weak},
+};
 
 use cosmwasm_std::{Addr, Order, Record, Storage};
 use cosmwasm_vm::{BackendError, BackendResult, GasInfo};
+use cw_sdk::GasConfig;
 
 use cw_store::{
     iterators::MemIter,
     prefix::{concat, namespace_upper_bound, trim},
 };
 
+use crate::state::contract_namespace;
+
 /// NOTE: cosmwasm-vm requires the backend store to be of 'static lifetime.
 /// This requirement comes from wasmer so not something we can change.
 ///
@@ -15,21 +23,35 @@ use cw_store::{
 /// So it has to be an owned type.
 ///
 /// Here we need both the `store` and `iterators` map be owned.
+///
+/// Iterators are kept in a `BTreeMap` rather than a `HashMap`, as nothing that
+/// reaches consensus output may depend on hash map ordering.
+///
+/// Storage ops are free unless the substore is metered, in which case they're
+/// charged to the instance's gas meter, converted into wasm gas.
 pub struct ContractSubstore<T: Storage> {
     store: T,
     namespace: Vec<u8>,
-    iterators: HashMap<u32, MemIter>,
+    iterators: BTreeMap<u32, MemIter>,
+    gas: Option<GasConfig>,
 }
 
 impl<T: Storage> ContractSubstore<T> {
     pub fn new(store: T, contract_addr: &Addr) -> Self {
         Self {
             store,
-            namespace: contract_addr.to_string().into_bytes(),
-            iterators: HashMap::new(),
+            namespace: contract_namespace(contract_addr),
+            iterators: BTreeMap::new(),
+            gas: None,
         }
     }
 
+    /// Charge storage ops the given costs.
+    pub fn metered(mut self, gas: GasConfig) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
     pub fn recycle(self) -> T {
         self.store
     }
@@ -37,22 +59,30 @@ impl<T: Storage> ContractSubstore<T> {
     fn key(&self, k: &[u8]) -> Vec<u8> {
         concat(&self.namespace, k)
     }
+
+    fn gas_info(&self, cost: impl FnOnce(&GasConfig) -> u64) -> GasInfo {
+        match &self.gas {
+            Some(gas) => GasInfo::with_externally_used(gas.to_wasm_gas(cost(gas))),
+            None => GasInfo::free(),
+        }
+    }
 }
 
 impl<T: Storage> cosmwasm_vm::Storage for ContractSubstore<T> {
     fn get(&self, key: &[u8]) -> BackendResult<Option<Vec<u8>>> {
         let value = self.store.get(&self.key(key));
-        (Ok(value), GasInfo::free())
+        let bytes = key.len() + value.as_ref().map_or(0, Vec::len);
+        (Ok(value), self.gas_info(|gas| gas.read_cost(bytes)))
     }
 
     fn set(&mut self, key: &[u8], value: &[u8]) -> BackendResult<()> {
         self.store.set(&self.key(key), value);
-        (Ok(()), GasInfo::free())
+        (Ok(()), self.gas_info(|gas| gas.write_cost(key.len() + value.len())))
     }
 
     fn remove(&mut self, key: &[u8]) -> BackendResult<()> {
         self.store.remove(&self.key(key));
-        (Ok(()), GasInfo::free())
+        (Ok(()), self.gas_info(|gas| gas.delete))
     }
 
     fn scan(
@@ -84,14 +114,93 @@ impl<T: Storage> cosmwasm_vm::Storage for ContractSubstore<T> {
 
         self.iterators.insert(iterator_id, iter);
 
-        (Ok(iterator_id), GasInfo::free())
+        (Ok(iterator_id), self.gas_info(|gas| gas.read_flat))
     }
 
+    /// Each record is charged by its size, the flat cost of reading having
+    /// been charged when the iterator was created.
     fn next(&mut self, iterator_id: u32) -> BackendResult<Option<Record>> {
         if let Some(iter) = self.iterators.get_mut(&iterator_id) {
-            (Ok(iter.next()), GasInfo::free())
+            let record = iter.next();
+            let bytes = record.as_ref().map_or(0, |(key, value)| key.len() + value.len());
+            (Ok(record), self.gas_info(|gas| gas.read_per_byte.saturating_mul(bytes as u64)))
         } else {
             (Err(BackendError::iterator_does_not_exist(iterator_id)), GasInfo::free())
         }
     }
 }
+
+/// A store of any type, so that the instances of all contracts can be created
+/// from the same module cache, whatever store each of them is given. The store
+/// can be taken back as the type it was created with once the call is done.
+///
+/// The instance's querier keeps a weak reference to the store, so that the
+/// contracts it queries read the same state as the querying contract, e.g.
+/// its writes made earlier in the same call.
+pub struct DynStorage(Rc<RefCell<Box<dyn AnyStorage>>>);
+
+impl DynStorage {
+    pub fn new(store: impl Storage + 'static) -> Self {
+        Self(Rc::new(RefCell::new(Box::new(store))))
+    }
+
+    /// A weak reference to the store, which doesn't keep it from being taken
+    /// back by `downcast`.
+    pub(crate) fn downgrade(&self) -> WeakStorage {
+        WeakStorage(Rc::downgrade(&self.0))
+    }
+
+    /// Take back the store, which must be of the type it was created with, and
+    /// must not be used by any other instance.
+    pub fn downcast<T: Storage + 'static>(self) -> T {
+        let store = Rc::try_unwrap(self.0).unwrap_or_else(|_| {
+            panic!("[cw-state-machine]: store downcast while still in use by another instance");
+        });
+        *store.into_inner().into_any().downcast().unwrap_or_else(|_| {
+            panic!("[cw-state-machine]: store downcast to a type it wasn't created with");
+        })
+    }
+}
+
+impl Storage for DynStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.0.borrow().get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.0.borrow_mut().set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.0.borrow_mut().remove(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        Box::new(MemIter::new(self.0.borrow().range(start, end, order)))
+    }
+}
+
+/// A weak reference to the store of an instance, see `DynStorage::downgrade`.
+pub(crate) struct WeakStorage(Weak<RefCell<Box<dyn AnyStorage>>>);
+
+impl WeakStorage {
+    /// The store, unless it has been taken back from the instance already.
+    pub fn upgrade(&self) -> Option<DynStorage> {
+        self.0.upgrade().map(DynStorage)
+    }
+}
+
+trait AnyStorage: Storage {
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Storage + 'static> AnyStorage for T {
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
@@ -1,3 +1,9 @@
+use cosmwasm_std::StdError;
+use cosmwasm_vm::VmError;
+use cw_sdk::ErrorCode;
+
+use crate::{auth::ETH_SIGNATURE_LENGTH, gas::SystemCall, state::Module};
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -27,6 +33,11 @@ pub enum Error {
     #[error("contract labels must not start with the prefix `cw1`")]
     IllegalLabel,
 
+    #[error("label {label} is reserved for a system contract instantiated at genesis")]
+    ReservedLabel {
+        label: String,
+    },
+
     #[error("an account already exists with the address {address}")]
     AccountFound {
         address: String,
@@ -42,6 +53,28 @@ pub enum Error {
         address: String,
     },
 
+    #[error("account {address} can't rotate its key, as it's not a base account")]
+    KeyRotationUnsupported {
+        address: String,
+    },
+
+    #[error("tx is too large: {size} bytes, max {max}")]
+    TxTooLarge {
+        size: u64,
+        max: u64,
+    },
+
+    #[error("tx has too many messages: {count}, max {max}")]
+    TooManyMsgs {
+        count: u64,
+        max: u64,
+    },
+
+    #[error("tx rejected by ante handler: {reason}")]
+    AnteRejected {
+        reason: String,
+    },
+
     #[error("the account associated with the address {address} is not a contract")]
     AccountIsNotContract {
         address: String,
@@ -52,6 +85,11 @@ pub enum Error {
         code_id: u64,
     },
 
+    #[error("no tx found with hash {hash}")]
+    TxNotFound {
+        hash: String,
+    },
+
     #[error("sender address does not match pubkey: expecting {expect}, found {found}")]
     AddressMismatch {
         // The sender address deduced from the provided pubkey
@@ -69,6 +107,17 @@ pub enum Error {
         found: String,
     },
 
+    #[error("incorrect Ethereum-style signature length: expecting {expect} bytes, found {found}")]
+    InvalidEthSignatureLength {
+        expect: usize,
+        found: usize,
+    },
+
+    #[error("unsupported recovery id in Ethereum-style signature: {v}")]
+    UnsupportedRecoveryId {
+        v: u8,
+    },
+
     #[error("incorrect chain id: expecting {expect}, found {found}")]
     ChainIdMismatch {
         /// The chain id stored on-chain
@@ -77,6 +126,15 @@ pub enum Error {
         found: String,
     },
 
+    #[error("incorrect account number for sender {sender}: expecting {expect}, found {found}")]
+    AccountNumberMismatch {
+        sender: String,
+        /// The account number stored on-chain, or to be assigned to a new account
+        expect: u64,
+        /// The account number provided by the tx
+        found: u64,
+    },
+
     #[error("incorrect sequence number for sender {sender}: expecting {expect}, found {found}")]
     SequenceMismatch {
         sender: String,
@@ -86,6 +144,137 @@ pub enum Error {
         found: u64,
     },
 
+    #[error("signature of {sender} does not match its account type")]
+    SignatureTypeMismatch {
+        sender: String,
+    },
+
+    #[error("multisig pubkey does not match the one stored on-chain for {sender}")]
+    MultisigPubkeyMismatch {
+        sender: String,
+    },
+
+    #[error("invalid multisig: {reason}")]
+    InvalidMultisig {
+        reason: String,
+    },
+
+    #[error("multisig threshold not met: expecting {threshold} signatures, found {found}")]
+    MultisigThresholdNotMet {
+        threshold: u32,
+        found: u32,
+    },
+
+    #[error("tx rejected by contract account {address}: {reason}")]
+    TxRejectedByAccount {
+        address: String,
+        reason: String,
+    },
+
+    #[error("tx has no signers")]
+    NoSigners,
+
+    #[error("incorrect number of signatures: expecting {expect}, found {found}")]
+    SignatureCountMismatch {
+        /// The number of signers listed in the tx body
+        expect: usize,
+        /// The number of signatures provided by the tx
+        found: usize,
+    },
+
+    #[error("signer {address} is listed more than once")]
+    DuplicateSigner {
+        address: String,
+    },
+
+    #[error("message sender {sender} is not a signer of the tx")]
+    MsgSenderNotSigner {
+        sender: String,
+    },
+
+    #[error("tx timed out at height {timeout_height}; current height is {height}")]
+    TxTimedOut {
+        timeout_height: u64,
+        height: u64,
+    },
+
+    #[error("fee payer {payer} is not a signer of the tx")]
+    FeePayerNotSigner {
+        payer: String,
+    },
+
+    #[error("no fee allowance granted by {granter} to {grantee}")]
+    AllowanceNotFound {
+        granter: String,
+        grantee: String,
+    },
+
+    #[error("fee allowance granted by {granter} to {grantee} has expired")]
+    AllowanceExpired {
+        granter: String,
+        grantee: String,
+    },
+
+    #[error("fee exceeds the allowance granted by {granter} to {grantee}")]
+    AllowanceExceeded {
+        granter: String,
+        grantee: String,
+    },
+
+    #[error("message type {kind} is not allowed by the fee allowance")]
+    MsgNotAllowed {
+        kind: String,
+    },
+
+    #[error("invalid fee allowance: {reason}")]
+    InvalidAllowance {
+        reason: String,
+    },
+
+    #[error("{grantee} is not authorized by {granter} to send {msg}")]
+    AuthzGrantNotFound {
+        granter: String,
+        grantee: String,
+        msg: String,
+    },
+
+    #[error("authorization granted by {granter} to {grantee} to send {msg} has expired")]
+    AuthzGrantExpired {
+        granter: String,
+        grantee: String,
+        msg: String,
+    },
+
+    #[error("exec messages can't be nested")]
+    NestedExec,
+
+    #[error("invalid authorization grant: {reason}")]
+    InvalidAuthzGrant {
+        reason: String,
+    },
+
+    #[error("failed to deduct fee: {reason}")]
+    FeeDeductionFailed {
+        reason: String,
+    },
+
+    #[error("fee is below the minimum gas prices: {gas_limit} gas requires at least {min_fee}")]
+    GasPriceTooLow {
+        gas_limit: u64,
+        min_fee: String,
+    },
+
+    #[error("tx is not encoded in canonical JSON")]
+    NonCanonicalTx,
+
+    #[error("sender {sender} already executed tx {tx_hash} with idempotency key {key}")]
+    DuplicateTx {
+        sender: String,
+        key: String,
+        /// Hash of the original tx; hex-encoded bytearray
+        tx_hash: String,
+    },
+
     #[error("failed to transfer funds: {reason}")]
     FundTransferFailed {
         reason: String,
@@ -102,6 +291,162 @@ pub enum Error {
 
     #[error("this query is not supported yet")]
     QueryUnsupported,
+
+    #[error("query worker failed: {reason}")]
+    QueryWorkerFailed {
+        reason: String,
+    },
+
+    #[error("schema version {version} of module {module} is newer than the latest {latest}")]
+    SchemaVersionUnsupported {
+        module: String,
+        version: u32,
+        latest: u32,
+    },
+
+    #[error("no migration found for module {module} from schema version {version}")]
+    MigrationNotFound {
+        module: String,
+        version: u32,
+    },
+
+    #[error("tx ran out of gas: limit {limit}, used {used}")]
+    TxOutOfGas {
+        limit: u64,
+        used: u64,
+    },
+
+    #[error("system call {call} ran out of gas: limit {limit}")]
+    SystemOutOfGas {
+        call: String,
+        limit: u64,
+    },
+
+    #[error("validator {pubkey} is updated more than once in the same block")]
+    DuplicateValidatorUpdate {
+        /// The validator's consensus pubkey; hex-encoded bytearray
+        pubkey: String,
+    },
+
+    #[error("invalid vote extensions: {reason}")]
+    InvalidVoteExtensions {
+        reason: String,
+    },
+
+    #[error("no committed block to roll back; only the last committed block can be rolled back")]
+    NothingToRollback,
+
+    #[error("rolled back to root hash {found}, but app hash of height {height} is {expect}")]
+    RollbackHashMismatch {
+        height: u64,
+        /// Hex-encoded app hash recorded for the height
+        expect: String,
+        /// Hex-encoded root hash of the rolled back state
+        found: String,
+    },
+
+    #[error("the state machine has halted after committing block {height}")]
+    Halted {
+        height: u64,
+    },
+
+    #[error("the state machine is in safe mode and does not execute blocks")]
+    SafeMode,
+
+    #[error("{sender} is not the governance contract, which alone may update the params")]
+    NotGovernance {
+        sender: String,
+    },
+
+    #[error("invalid params: {reason}")]
+    InvalidParams {
+        reason: String,
+    },
+}
+
+impl From<&Error> for ErrorCode {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::Std(StdError::NotFound { .. }) => ErrorCode::NotFound,
+            Error::Std(StdError::ParseErr { .. } | StdError::SerializeErr { .. })
+            | Error::Serde(_)
+            | Error::NonCanonicalTx => ErrorCode::InvalidEncoding,
+            Error::Std(_)
+            | Error::Backend(_)
+            | Error::Merk(_)
+            | Error::DuplicateValidatorUpdate { .. }
+            | Error::SchemaVersionUnsupported { .. }
+            | Error::MigrationNotFound { .. }
+            | Error::NothingToRollback
+            | Error::RollbackHashMismatch { .. }
+            | Error::Halted { .. }
+            | Error::SafeMode
+            | Error::QueryWorkerFailed { .. } => ErrorCode::Internal,
+            Error::Vm(VmError::GasDepletion { .. })
+            | Error::TxOutOfGas { .. }
+            | Error::SystemOutOfGas { .. } => ErrorCode::OutOfGas,
+            Error::Vm(_) => ErrorCode::VmError,
+            Error::Address(_) | Error::AddressMismatch { .. } => ErrorCode::InvalidAddress,
+            Error::Ecdsa(_)
+            | Error::InvalidEthSignatureLength { .. }
+            | Error::UnsupportedRecoveryId { .. }
+            | Error::SignatureTypeMismatch { .. }
+            | Error::InvalidMultisig { .. }
+            | Error::MultisigThresholdNotMet { .. }
+            | Error::SignatureCountMismatch { .. } => ErrorCode::InvalidSignature,
+            Error::PubkeyMismatch { .. } | Error::MultisigPubkeyMismatch { .. } => {
+                ErrorCode::InvalidPubkey
+            },
+            Error::ChainIdMismatch { .. } => ErrorCode::WrongChainId,
+            Error::AccountNumberMismatch { .. } => ErrorCode::WrongAccountNumber,
+            Error::SequenceMismatch { .. } => ErrorCode::WrongSequence,
+            Error::NoSigners
+            | Error::DuplicateSigner { .. }
+            | Error::MsgSenderNotSigner { .. }
+            | Error::FeePayerNotSigner { .. }
+            | Error::NotGovernance { .. } => ErrorCode::Unauthorized,
+            Error::TxRejectedByAccount { .. } => ErrorCode::RejectedByAccount,
+            Error::AnteRejected { .. } => ErrorCode::RejectedByAnteHandler,
+            Error::AccountFound { .. } => ErrorCode::AccountExists,
+            Error::AccountNotFound { .. }
+            | Error::CodeNotFound { .. }
+            | Error::TxNotFound { .. } => ErrorCode::NotFound,
+            Error::AccountIsContract { .. }
+            | Error::AccountIsNotContract { .. }
+            | Error::KeyRotationUnsupported { .. } => ErrorCode::InvalidAccountType,
+            Error::TxTooLarge { .. } => ErrorCode::TxTooLarge,
+            Error::TooManyMsgs { .. } => ErrorCode::TooManyMsgs,
+            Error::TxTimedOut { .. } => ErrorCode::TxTimedOut,
+            Error::DuplicateTx { .. } => ErrorCode::DuplicateTx,
+            Error::SubmessagesUnsupported
+            | Error::FundsUnsupported
+            | Error::MigrationUnsupported
+            | Error::QueryUnsupported => ErrorCode::Unsupported,
+            Error::InvalidVoteExtensions { .. } => ErrorCode::InvalidVoteExtensions,
+            Error::InvalidParams { .. } => ErrorCode::InvalidParams,
+            Error::FeeDeductionFailed { .. } | Error::GasPriceTooLow { .. } => {
+                ErrorCode::InsufficientFee
+            },
+            Error::AllowanceNotFound { .. } => ErrorCode::AllowanceNotFound,
+            Error::AllowanceExpired { .. } => ErrorCode::AllowanceExpired,
+            Error::AllowanceExceeded { .. } => ErrorCode::AllowanceExceeded,
+            Error::MsgNotAllowed { .. } => ErrorCode::MsgNotAllowed,
+            Error::InvalidAllowance { .. } => ErrorCode::InvalidAllowance,
+            Error::AuthzGrantNotFound { .. } => ErrorCode::GrantNotFound,
+            Error::AuthzGrantExpired { .. } => ErrorCode::GrantExpired,
+            Error::NestedExec => ErrorCode::NestedExec,
+            Error::InvalidAuthzGrant { .. } => ErrorCode::InvalidGrant,
+            Error::Contract(_) => ErrorCode::ContractError,
+            Error::IllegalLabel | Error::ReservedLabel { .. } => ErrorCode::InvalidLabel,
+            Error::FundTransferFailed { .. } => ErrorCode::FundTransferFailed,
+        }
+    }
+}
+
+impl From<Error> for ErrorCode {
+    fn from(error: Error) -> Self {
+        ErrorCode::from(&error)
+    }
 }
 
 impl Error {
@@ -123,6 +468,18 @@ impl Error {
         }
     }
 
+    pub fn key_rotation_unsupported(address: impl Into<String>) -> Self {
+        Self::KeyRotationUnsupported {
+            address: address.into(),
+        }
+    }
+
+    pub fn ante_rejected(reason: impl ToString) -> Self {
+        Self::AnteRejected {
+            reason: reason.to_string(),
+        }
+    }
+
     pub fn account_is_not_contract(address: impl Into<String>) -> Self {
         Self::AccountIsNotContract {
             address: address.into(),
@@ -135,6 +492,12 @@ impl Error {
         }
     }
 
+    pub fn tx_not_found(hash: impl Into<String>) -> Self {
+        Self::TxNotFound {
+            hash: hash.into(),
+        }
+    }
+
     pub fn address_mismatch(expect: impl Into<String>, found: impl Into<String>) -> Self {
         Self::AddressMismatch {
             expect: expect.into(),
@@ -150,6 +513,59 @@ impl Error {
         }
     }
 
+    pub fn rollback_hash_mismatch(height: u64, expect: &[u8], found: &[u8]) -> Self {
+        Self::RollbackHashMismatch {
+            height,
+            expect: hex::encode(expect),
+            found: hex::encode(found),
+        }
+    }
+
+    pub fn invalid_eth_signature_length(found: usize) -> Self {
+        Self::InvalidEthSignatureLength {
+            expect: ETH_SIGNATURE_LENGTH,
+            found,
+        }
+    }
+
+    pub fn unsupported_recovery_id(v: u8) -> Self {
+        Self::UnsupportedRecoveryId {
+            v,
+        }
+    }
+
+    pub fn signature_type_mismatch(sender: impl Into<String>) -> Self {
+        Self::SignatureTypeMismatch {
+            sender: sender.into(),
+        }
+    }
+
+    pub fn multisig_pubkey_mismatch(sender: impl Into<String>) -> Self {
+        Self::MultisigPubkeyMismatch {
+            sender: sender.into(),
+        }
+    }
+
+    pub fn invalid_multisig(reason: impl ToString) -> Self {
+        Self::InvalidMultisig {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn multisig_threshold_not_met(threshold: u32, found: u32) -> Self {
+        Self::MultisigThresholdNotMet {
+            threshold,
+            found,
+        }
+    }
+
+    pub fn tx_rejected_by_account(address: impl Into<String>, reason: impl ToString) -> Self {
+        Self::TxRejectedByAccount {
+            address: address.into(),
+            reason: reason.to_string(),
+        }
+    }
+
     pub fn chain_id_mismatch(expect: impl Into<String>, found: impl Into<String>) -> Self {
         Self::ChainIdMismatch {
             expect: expect.into(),
@@ -157,6 +573,14 @@ impl Error {
         }
     }
 
+    pub fn account_number_mismatch(sender: impl Into<String>, expect: u64, found: u64) -> Self {
+        Self::AccountNumberMismatch {
+            sender: sender.into(),
+            expect,
+            found,
+        }
+    }
+
     pub fn sequence_mismatch(sender: impl Into<String>, expect: u64, found: u64) -> Self {
         Self::SequenceMismatch {
             sender: sender.into(),
@@ -165,11 +589,192 @@ impl Error {
         }
     }
 
+    pub fn signature_count_mismatch(expect: usize, found: usize) -> Self {
+        Self::SignatureCountMismatch {
+            expect,
+            found,
+        }
+    }
+
+    pub fn duplicate_signer(address: impl Into<String>) -> Self {
+        Self::DuplicateSigner {
+            address: address.into(),
+        }
+    }
+
+    pub fn msg_sender_not_signer(sender: impl Into<String>) -> Self {
+        Self::MsgSenderNotSigner {
+            sender: sender.into(),
+        }
+    }
+
+    pub fn tx_timed_out(timeout_height: u64, height: u64) -> Self {
+        Self::TxTimedOut {
+            timeout_height,
+            height,
+        }
+    }
+
+    pub fn fee_payer_not_signer(payer: impl Into<String>) -> Self {
+        Self::FeePayerNotSigner {
+            payer: payer.into(),
+        }
+    }
+
+    pub fn allowance_not_found(granter: impl Into<String>, grantee: impl Into<String>) -> Self {
+        Self::AllowanceNotFound {
+            granter: granter.into(),
+            grantee: grantee.into(),
+        }
+    }
+
+    pub fn allowance_expired(granter: impl Into<String>, grantee: impl Into<String>) -> Self {
+        Self::AllowanceExpired {
+            granter: granter.into(),
+            grantee: grantee.into(),
+        }
+    }
+
+    pub fn allowance_exceeded(granter: impl Into<String>, grantee: impl Into<String>) -> Self {
+        Self::AllowanceExceeded {
+            granter: granter.into(),
+            grantee: grantee.into(),
+        }
+    }
+
+    pub fn msg_not_allowed(kind: impl Into<String>) -> Self {
+        Self::MsgNotAllowed {
+            kind: kind.into(),
+        }
+    }
+
+    pub fn invalid_allowance(reason: impl ToString) -> Self {
+        Self::InvalidAllowance {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn authz_grant_not_found(
+        granter: impl Into<String>,
+        grantee: impl Into<String>,
+        msg: impl Into<String>,
+    ) -> Self {
+        Self::AuthzGrantNotFound {
+            granter: granter.into(),
+            grantee: grantee.into(),
+            msg: msg.into(),
+        }
+    }
+
+    pub fn authz_grant_expired(
+        granter: impl Into<String>,
+        grantee: impl Into<String>,
+        msg: impl Into<String>,
+    ) -> Self {
+        Self::AuthzGrantExpired {
+            granter: granter.into(),
+            grantee: grantee.into(),
+            msg: msg.into(),
+        }
+    }
+
+    pub fn invalid_authz_grant(reason: impl ToString) -> Self {
+        Self::InvalidAuthzGrant {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn duplicate_tx(sender: impl Into<String>, key: impl Into<String>, tx_hash: &[u8]) -> Self {
+        Self::DuplicateTx {
+            sender: sender.into(),
+            key: key.into(),
+            tx_hash: hex::encode_upper(tx_hash),
+        }
+    }
+
+    pub fn duplicate_validator_update(pubkey: &[u8]) -> Self {
+        Self::DuplicateValidatorUpdate {
+            pubkey: hex::encode(pubkey),
+        }
+    }
+
+    pub fn invalid_vote_extensions(reason: impl ToString) -> Self {
+        Self::InvalidVoteExtensions {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn schema_version_unsupported(module: Module, version: u32, latest: u32) -> Self {
+        Self::SchemaVersionUnsupported {
+            module: format!("{module:?}"),
+            version,
+            latest,
+        }
+    }
+
+    pub fn migration_not_found(module: Module, version: u32) -> Self {
+        Self::MigrationNotFound {
+            module: format!("{module:?}"),
+            version,
+        }
+    }
+
+    pub fn reserved_label(label: impl Into<String>) -> Self {
+        Self::ReservedLabel {
+            label: label.into(),
+        }
+    }
+
+    pub fn tx_out_of_gas(limit: u64, used: u64) -> Self {
+        Self::TxOutOfGas {
+            limit,
+            used,
+        }
+    }
+
+    pub fn system_out_of_gas(call: SystemCall, limit: u64) -> Self {
+        Self::SystemOutOfGas {
+            call: call.name().into(),
+            limit,
+        }
+    }
+
     pub fn fund_transfer_failed(reason: impl ToString) -> Self {
         Self::FundTransferFailed {
             reason: reason.to_string(),
         }
     }
+
+    pub fn fee_deduction_failed(reason: impl ToString) -> Self {
+        Self::FeeDeductionFailed {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn gas_price_too_low(gas_limit: u64, min_fee: impl Into<String>) -> Self {
+        Self::GasPriceTooLow {
+            gas_limit,
+            min_fee: min_fee.into(),
+        }
+    }
+
+    pub fn not_governance(sender: impl Into<String>) -> Self {
+        Self::NotGovernance {
+            sender: sender.into(),
+        }
+    }
+
+    pub fn invalid_params(reason: impl ToString) -> Self {
+        Self::InvalidParams {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn query_worker_failed(reason: impl ToString) -> Self {
+        Self::QueryWorkerFailed {
+            reason: reason.to_string(),
+        }
+    }
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -1,16 +1,18 @@
 use cosmwasm_std::{
-    to_binary, Addr, Binary, BlockInfo, ContractInfo, ContractResult, Env, Event, MessageInfo,
-    Response, Storage, TransactionInfo,
+    from_binary, to_binary, Addr, Binary, BlockInfo, Coin, ContractInfo, ContractResult, Empty, Env,
+    Event, MessageInfo, Response, Storage, TransactionInfo,
 };
-use cosmwasm_vm::{call_execute, call_instantiate, call_sudo, Backend, Instance, InstanceOptions};
-use cw_sdk::{address, bank, hash::sha256, Account};
+use cosmwasm_vm::{call_execute, call_instantiate, call_sudo, VmError, VmResult};
+use cw_sdk::{address, bank, hash::sha256, taxman, Account, Tx};
 use cw_store::Cached;
-use tracing::{debug, info};
+use tracing::{debug_span, field, Span};
 
 use crate::{
-    backend::{BackendApi, BackendQuerier, ContractSubstore},
+    backend::create_instance,
     error::{Error, Result},
-    state::{code_by_address, ACCOUNTS, CODES, CODE_COUNT},
+    gas::{SystemCall, SystemGas},
+    state::{code_by_address, params, Module, ACCOUNTS, CODES, CODE_COUNT, CONTRACTS_BY_CODE},
+    FEE_COLLECTOR, TAXMAN,
 };
 
 pub fn store_code(
@@ -18,18 +20,18 @@ pub fn store_code(
     sender_addr: &Addr,
     wasm_byte_code: &Binary,
 ) -> Result<Event> {
+    let mut store = Module::Wasm.substore_mut(store);
+
     // increment the code count
-    let code_id = CODE_COUNT.update(store, |count| -> Result<_> {
+    let code_id = CODE_COUNT.update(&mut store, |count| -> Result<_> {
         Ok(count + 1)
     })?;
 
     // save code to the store
-    CODES.save(store, code_id, wasm_byte_code)?;
+    CODES.save(&mut store, code_id, wasm_byte_code)?;
 
     let code_hash = hex::encode(sha256(wasm_byte_code));
 
-    info!(target: "Stored code", id = code_id, hash = code_hash);
-
     Ok(Event::new("store_code")
         .add_attribute("sender", sender_addr)
         .add_attribute("code_id", code_id.to_string())
@@ -37,8 +39,8 @@ pub fn store_code(
 }
 
 #[allow(clippy::too_many_arguments)]
-pub fn instantiate_contract(
-    store: impl Storage + 'static,
+pub fn instantiate_contract<S>(
+    store: S,
     block: BlockInfo,
     transaction: Option<TransactionInfo>,
     info: &MessageInfo,
@@ -46,7 +48,11 @@ pub fn instantiate_contract(
     msg: &[u8],
     label: String,
     admin: Option<Addr>,
-) -> Result<ContractResult<Response>> {
+    gas: &SystemGas,
+) -> Result<ContractResult<Response>>
+where
+    S: Storage + 'static,
+{
     let cache = Cached::new(store);
 
     // validate the label
@@ -64,6 +70,9 @@ pub fn instantiate_contract(
     // now we know the label is valid, derive contract address from it
     let contract_addr = address::derive_from_label(&label)?;
 
+    let span = call_span("instantiate", &contract_addr);
+    let _entered = span.enter();
+
     let env = Env {
         block,
         transaction,
@@ -74,189 +83,216 @@ pub fn instantiate_contract(
     };
 
     // load wasm binary code
-    let code = CODES.load(&cache, code_id)?;
+    let code = CODES.load(&Module::Wasm.substore(&cache), code_id)?;
+    let gas_config = params(&cache)?.gas;
 
-    // create the wasm instance and call the instantiate entry point
-    let mut instance = Instance::from_code(
+    // create the wasm instance and call the instantiate entry point, with only
+    // the gas that the tx has left
+    let mut instance = create_instance(
         &code,
-        Backend {
-            api: BackendApi,
-            storage: ContractSubstore::new(cache, &contract_addr),
-            querier: BackendQuerier,
-        },
-        InstanceOptions {
-            gas_limit: u64::MAX,
-            print_debug: true,
-        },
-        None,
+        cache,
+        &contract_addr,
+        &env.block,
+        gas_config.to_wasm_gas(gas.tx.remaining()),
+        gas_config,
+        &gas.wasm,
+        gas.modules.as_deref(),
     )?;
-    let result = call_instantiate(&mut instance, &env, info, msg)?;
+    let result = call_instantiate(&mut instance, &env, info, msg);
+
+    // account for the gas consumed, whether or not the call succeeded
+    let report = instance.create_gas_report();
+    let gas_used = report.limit - report.remaining;
+    gas.tx.consume(gas_config.to_sdk_gas(gas_used));
+    gas.contracts.record(&contract_addr, gas_used);
+    span.record("gas_used", gas_used);
+    let result = tx_call_result(result, gas)?;
+    record_error(&span, &result);
+
+    // the attributes emitted by a successful call are charged on top
+    if let ContractResult::Ok(resp) = &result {
+        gas.tx.consume(gas_config.events_cost(resp));
+    }
 
     // contract execution is finished; we recycle the cached store
-    let mut cache = instance
+    let mut cache: Cached<S> = instance
         .recycle()
         .expect("[cw-state-machine]: failed to recycle instance")
         .storage
-        .recycle();
+        .recycle()
+        .downcast();
 
     // if the contract execution is successful, we flush the state changes
     // occurred during the instantiation call to the underlying store, and save
     // the contract account.
-    match &result {
-        ContractResult::Ok(_) => {
-            cache.flush();
-            let mut store = cache.recycle();
-
-            ACCOUNTS.update(&mut store, &contract_addr, |opt| {
-                // IMPORTANT: NOTE: do not save the account if one of the same
-                // address already exists.
-                if opt.is_some() {
-                    return Err(Error::account_found(&contract_addr));
-                }
-                Ok(Account::Contract {
-                    code_id,
-                    label: label.clone(),
-                    admin,
-                })
-            })?;
-
-            info!(
-                target: "Instantiated contract",
-                address = contract_addr.to_string(),
+    if result.is_ok() {
+        cache.flush();
+        let mut store = cache.recycle();
+
+        let mut auth = Module::Auth.substore_mut(&mut store);
+        ACCOUNTS.update(&mut auth, &contract_addr, |opt| {
+            // IMPORTANT: NOTE: do not save the account if one of the same
+            // address already exists.
+            if opt.is_some() {
+                return Err(Error::account_found(&contract_addr));
+            }
+            Ok(Account::Contract {
                 code_id,
-                label,
-            );
-        },
-        ContractResult::Err(err) => {
-            debug!(target: "Failed to instantiate contract", code_id, label, reason = err);
-        }
+                label: label.clone(),
+                admin,
+            })
+        })?;
+        CONTRACTS_BY_CODE.save(&mut auth, (code_id, &contract_addr), &Empty {})?;
     }
 
     Ok(result)
 }
 
+/// Sudo a contract on behalf of the state machine itself. The call is bounded
+/// by the gas limit configured for the entry point, and its gas usage is
+/// recorded in the entry point's accounting bucket.
 pub fn sudo_contract<S>(
     store: S,
     env: &Env,
     msg: &[u8],
+    call: SystemCall,
+    gas: &SystemGas,
 ) -> Result<(ContractResult<Response>, S)>
 where
     S: Storage + 'static,
 {
     let cache = Cached::new(store);
+    let params = params(&cache)?;
+    let gas_limit = call.limit(&params.system_gas);
+    let gas_config = params.gas;
+
+    let span = call_span(call.name(), &env.contract.address);
+    let _entered = span.enter();
 
     // load wasm binary code
     let code = code_by_address(&cache, &env.contract.address)?;
 
-    // create the wasm instance and call the execute entry point
-    let mut instance = Instance::from_code(
+    // create the wasm instance and call the sudo entry point
+    let mut instance = create_instance(
         &code,
-        Backend {
-            api: BackendApi,
-            storage: ContractSubstore::new(cache, &env.contract.address),
-            querier: BackendQuerier,
-        },
-        InstanceOptions {
-            gas_limit: u64::MAX,
-            print_debug: true,
-        },
-        None,
+        cache,
+        &env.contract.address,
+        &env.block,
+        gas_limit,
+        gas_config,
+        &gas.wasm,
+        gas.modules.as_deref(),
     )?;
-    let result = call_sudo(&mut instance, env, msg)?;
+    let result = call_sudo(&mut instance, env, msg);
+
+    // account for the gas consumed, whether or not the call succeeded
+    let report = instance.create_gas_report();
+    let gas_used = report.limit - report.remaining;
+    let out_of_gas = matches!(result, Err(VmError::GasDepletion { .. }));
+    gas.meter.record(call, gas_used, out_of_gas);
+    gas.tx.consume(gas_config.to_sdk_gas(gas_used));
+    gas.contracts.record(&env.contract.address, gas_used);
+    span.record("gas_used", gas_used);
+
+    let result = match result {
+        Err(VmError::GasDepletion { .. }) => {
+            return Err(Error::system_out_of_gas(call, gas_limit));
+        },
+        result => result?,
+    };
+    record_error(&span, &result);
+
+    // the attributes emitted by a successful call are charged on top
+    if let ContractResult::Ok(resp) = &result {
+        gas.tx.consume(gas_config.events_cost(resp));
+    }
 
     // contract execution is finished; we recycle the cached store
-    let mut cache = instance
+    let mut cache: Cached<S> = instance
         .recycle()
         .expect("[cw-state-machine]: failed to recycle instance")
         .storage
-        .recycle();
+        .recycle()
+        .downcast();
 
     // if the execution is successful, flush the state changes to the underlying store
-    match &result {
-        ContractResult::Ok(_) => {
-            cache.flush();
-            debug!(
-                target: "Sudoed contract",
-                address = env.contract.address.to_string(),
-            );
-        },
-        ContractResult::Err(err) => {
-            debug!(
-                target: "Failed to sudo contract",
-                address = env.contract.address.to_string(),
-                reason = err,
-            );
-        }
+    if result.is_ok() {
+        cache.flush();
     }
 
     Ok((result, cache.recycle()))
 }
 
-pub fn execute_contract(
-    store: impl Storage + 'static,
+pub fn execute_contract<S>(
+    store: S,
     env: &Env,
     info: &MessageInfo,
     msg: &[u8],
-) -> Result<ContractResult<Response>> {
+    gas: &SystemGas,
+) -> Result<ContractResult<Response>>
+where
+    S: Storage + 'static,
+{
     let cache = Cached::new(store);
 
     // if the message has coins attached to it, we first invoke bank contract to
     // transfer the coins
     let (mut fund_events, cache) = if !info.funds.is_empty() {
-        transfer_funds(cache, env, info)?
+        transfer_funds(cache, env, info, gas)?
     } else {
         (vec![], cache)
     };
 
+    let span = call_span("execute", &env.contract.address);
+    let _entered = span.enter();
+
     // load wasm binary code
     let code = code_by_address(&cache, &env.contract.address)?;
+    let gas_config = params(&cache)?.gas;
 
-    // create the wasm instance and call the execute entry point
-    let mut instance = Instance::from_code(
+    // create the wasm instance and call the execute entry point, with only the
+    // gas that the tx has left
+    let mut instance = create_instance(
         &code,
-        Backend {
-            api: BackendApi,
-            storage: ContractSubstore::new(cache, &env.contract.address),
-            querier: BackendQuerier,
-        },
-        InstanceOptions {
-            gas_limit: u64::MAX,
-            print_debug: true,
-        },
-        None,
+        cache,
+        &env.contract.address,
+        &env.block,
+        gas_config.to_wasm_gas(gas.tx.remaining()),
+        gas_config,
+        &gas.wasm,
+        gas.modules.as_deref(),
     )?;
-    let mut result = call_execute(&mut instance, env, info, msg)?;
+    let result = call_execute(&mut instance, env, info, msg);
+
+    // account for the gas consumed, whether or not the call succeeded
+    let report = instance.create_gas_report();
+    let gas_used = report.limit - report.remaining;
+    gas.tx.consume(gas_config.to_sdk_gas(gas_used));
+    gas.contracts.record(&env.contract.address, gas_used);
+    span.record("gas_used", gas_used);
+    let mut result = tx_call_result(result, gas)?;
+    record_error(&span, &result);
+
+    // the attributes emitted by a successful call are charged on top
+    if let ContractResult::Ok(resp) = &result {
+        gas.tx.consume(gas_config.events_cost(resp));
+    }
 
     // contract execution is finished; we recycle the cached store
-    let mut cache = instance
+    let mut cache: Cached<S> = instance
         .recycle()
         .expect("[cw-state-machine]: failed to recycle instance")
         .storage
-        .recycle();
-
-    match &mut result {
-        ContractResult::Ok(resp) => {
-            // flush the state changes
-            cache.flush();
-
-            // prepend fund transfer events
-            fund_events.extend(resp.events.iter().cloned());
-            resp.events = fund_events;
-
-            debug!(
-                target: "Executed contract",
-                address = env.contract.address.to_string(),
-                sender = info.sender.to_string(),
-            );
-        },
-        ContractResult::Err(err) => {
-            debug!(
-                target: "Failed to execute contract",
-                address = env.contract.address.to_string(),
-                sender = info.sender.to_string(),
-                reason = err,
-            );
-        }
+        .recycle()
+        .downcast();
+
+    if let ContractResult::Ok(resp) = &mut result {
+        // flush the state changes
+        cache.flush();
+
+        // prepend fund transfer events
+        fund_events.extend(resp.events.iter().cloned());
+        resp.events = fund_events;
     }
 
     Ok(result)
@@ -271,28 +307,234 @@ pub fn migrate_contract(
     todo!();
 }
 
-fn transfer_funds<S>(store: S, env: &Env, info: &MessageInfo) -> Result<(Vec<Event>, S)>
+/// Charge the tx's fee, by transferring it from the payer to the fee collector.
+/// If a taxman contract exists, it decides the coins to charge, which may not
+/// be the fee set in the tx, and may reject the tx. Return the coins charged.
+pub fn charge_fee<S>(
+    store: S,
+    block: &BlockInfo,
+    tx: &Tx,
+    payer_addr: &Addr,
+    gas: &SystemGas,
+) -> Result<(Vec<Coin>, Vec<Event>, S)>
 where
     S: Storage + 'static,
 {
-    let sudo_env = Env {
-        block: env.block.clone(),
+    let msg = taxman::SudoMsg::ChargeFee {
+        payer: payer_addr.to_string(),
+        fee: tx.body.fee.amount.clone(),
+        tx: tx.clone(),
+    };
+    let (charged, mut events, store) =
+        sudo_taxman(store, block, &msg, gas).map_err(|err| match err {
+            Error::Contract(err) => Error::fee_deduction_failed(err),
+            err => err,
+        })?;
+    let charged = charged.unwrap_or_else(|| tx.body.fee.amount.clone());
+
+    if charged.is_empty() {
+        return Ok((charged, events, store));
+    }
+
+    let (deduct_events, store) = deduct_fee(store, block, payer_addr, &charged, gas)?;
+    events.extend(deduct_events);
+
+    Ok((charged, events, store))
+}
+
+/// Let the taxman contract, if one exists, settle the fee charged once the
+/// tx's messages are executed, by refunding the payer from the fee collector.
+pub fn settle_fee<S>(
+    store: S,
+    block: &BlockInfo,
+    tx: &Tx,
+    payer_addr: &Addr,
+    charged: &[Coin],
+    gas_used: u64,
+    gas: &SystemGas,
+) -> Result<(Vec<Event>, S)>
+where
+    S: Storage + 'static,
+{
+    let msg = taxman::SudoMsg::SettleFee {
+        payer: payer_addr.to_string(),
+        charged: charged.to_vec(),
+        gas_limit: tx.body.fee.gas_limit,
+        gas_used,
+    };
+    let (refund, mut events, store) = sudo_taxman(store, block, &msg, gas)?;
+
+    let refund = match refund {
+        Some(refund) if !refund.is_empty() => refund,
+        _ => return Ok((events, store)),
+    };
+
+    let fee_collector_addr = address::derive_from_label(FEE_COLLECTOR)?;
+    let (result, store) =
+        transfer_coins(store, block, &fee_collector_addr, payer_addr, &refund, true, gas)?;
+    match result {
+        ContractResult::Ok(resp) => events.extend(resp.events),
+        ContractResult::Err(err) => return Err(Error::Contract(err)),
+    }
+
+    Ok((events, store))
+}
+
+/// Sudo the taxman contract, if one exists, with one of its fee hooks. Return
+/// the coins it set as the response's data, or `None` if it set none or there
+/// is no taxman contract, and the events it emitted.
+fn sudo_taxman<S>(
+    store: S,
+    block: &BlockInfo,
+    msg: &taxman::SudoMsg,
+    gas: &SystemGas,
+) -> Result<(Option<Vec<Coin>>, Vec<Event>, S)>
+where
+    S: Storage + 'static,
+{
+    let taxman_exists = ACCOUNTS
+        .idx
+        .label
+        .may_load(&Module::Auth.substore(&store), TAXMAN.into())?
+        .is_some();
+    if !taxman_exists {
+        return Ok((None, vec![], store));
+    }
+
+    let env = Env {
+        block: block.clone(),
         transaction: None,
         contract: ContractInfo {
-            address: address::derive_from_label("bank")?,
+            address: address::derive_from_label(TAXMAN)?,
         },
     };
+    let msg = to_binary(msg)?;
 
-    let sudo_msg = to_binary(&bank::SudoMsg::Transfer {
-        from: info.sender.to_string(),
-        to: env.contract.address.to_string(),
-        coins: info.funds.clone(),
-    })?;
+    let (result, store) = sudo_contract(store, &env, &msg, SystemCall::Taxman, gas)?;
+    let response = match result {
+        ContractResult::Ok(response) => response,
+        ContractResult::Err(err) => return Err(Error::Contract(err)),
+    };
+
+    let coins = response.data.as_ref().map(from_binary).transpose()?;
 
-    let (result, store) = sudo_contract(store, &sudo_env, &sudo_msg)?;
+    Ok((coins, response.events, store))
+}
+
+/// Transfer the tx's fee from the payer to the fee collector.
+pub fn deduct_fee<S>(
+    store: S,
+    block: &BlockInfo,
+    payer_addr: &Addr,
+    fee: &[Coin],
+    gas: &SystemGas,
+) -> Result<(Vec<Event>, S)>
+where
+    S: Storage + 'static,
+{
+    let fee_collector_addr = address::derive_from_label(FEE_COLLECTOR)?;
+
+    let (result, store) =
+        transfer_coins(store, block, payer_addr, &fee_collector_addr, fee, true, gas)?;
+
+    match result {
+        ContractResult::Ok(resp) => Ok((resp.events, store)),
+        ContractResult::Err(err) => Err(Error::fee_deduction_failed(err)),
+    }
+}
+
+fn transfer_funds<S>(
+    store: S,
+    env: &Env,
+    info: &MessageInfo,
+    gas: &SystemGas,
+) -> Result<(Vec<Event>, S)>
+where
+    S: Storage + 'static,
+{
+    let (result, store) = transfer_coins(
+        store,
+        &env.block,
+        &info.sender,
+        &env.contract.address,
+        &info.funds,
+        false,
+        gas,
+    )?;
 
     match result {
         ContractResult::Ok(resp) => Ok((resp.events, store)),
         ContractResult::Err(err) => Err(Error::fund_transfer_failed(err)),
     }
 }
+
+/// Sudo the bank contract to transfer coins between two accounts, either funds
+/// attached to a message, or a tx's fee, which the bank doesn't query the send
+/// hooks of.
+fn transfer_coins<S>(
+    store: S,
+    block: &BlockInfo,
+    from: &Addr,
+    to: &Addr,
+    coins: &[Coin],
+    is_fee: bool,
+    gas: &SystemGas,
+) -> Result<(ContractResult<Response>, S)>
+where
+    S: Storage + 'static,
+{
+    let sudo_env = Env {
+        block: block.clone(),
+        transaction: None,
+        contract: ContractInfo {
+            address: address::derive_from_label("bank")?,
+        },
+    };
+
+    let (from, to, coins) = (from.to_string(), to.to_string(), coins.to_vec());
+    let sudo_msg = if is_fee {
+        to_binary(&bank::SudoMsg::TransferFee {
+            from,
+            to,
+            coins,
+        })?
+    } else {
+        to_binary(&bank::SudoMsg::Transfer {
+            from,
+            to,
+            coins,
+        })?
+    };
+
+    sudo_contract(store, &sudo_env, &sudo_msg, SystemCall::FundTransfer, gas)
+}
+
+/// Unwrap the result of a call made on behalf of a message. If the call ran out
+/// of gas, the tx has exhausted its gas limit.
+fn tx_call_result<T>(result: VmResult<T>, gas: &SystemGas) -> Result<T> {
+    match result {
+        Err(VmError::GasDepletion { .. }) => {
+            Err(Error::tx_out_of_gas(gas.tx.limit(), gas.tx.used()))
+        },
+        result => result.map_err(Error::from),
+    }
+}
+
+/// A span covering a call into one of a contract's entry points, either on
+/// behalf of a message or by the state machine itself. The gas used, and the
+/// error returned by the contract if any, are recorded once the call returns.
+fn call_span(entry_point: &str, contract_addr: &Addr) -> Span {
+    debug_span!(
+        "call",
+        entry_point,
+        contract = %contract_addr,
+        gas_used = field::Empty,
+        error = field::Empty,
+    )
+}
+
+fn record_error<T>(span: &Span, result: &ContractResult<T>) {
+    if let ContractResult::Err(err) = result {
+        span.record("error", err.as_str());
+    }
+}
@@ -0,0 +1,137 @@
+use cosmwasm_std::{Addr, Binary, Order, StdResult, Storage};
+use cw_sdk::{Account, GenesisAccount, GenesisCode, GenesisContractState, GenesisState};
+use cw_store::ReadonlyPrefixedStore;
+
+use crate::{
+    error::Result,
+    state::{contract_namespace, params, Module, ACCOUNTS, CODES},
+};
+
+/// Walk the store and export its content as a genesis state, from which a new
+/// chain can be started.
+///
+/// The exported state doesn't include any genesis message or deployer.
+pub fn export(store: &dyn Storage) -> Result<GenesisState> {
+    let accounts = ACCOUNTS
+        .range(&Module::Auth.substore(store), None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let codes = CODES
+        .range(&Module::Wasm.substore(store), None, None, Order::Ascending)
+        .map(|item| {
+            let (code_id, wasm_byte_code) = item?;
+            Ok(GenesisCode {
+                code_id,
+                wasm_byte_code,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let contract_states = accounts
+        .iter()
+        .filter_map(|(address, account)| match account {
+            Account::Contract {
+                ..
+            } => Some(export_contract_state(store, address)),
+            _ => None,
+        })
+        .collect();
+
+    let accounts = accounts
+        .into_iter()
+        .map(|(address, account)| GenesisAccount {
+            address: address.into(),
+            account: account.into(),
+        })
+        .collect();
+
+    Ok(GenesisState {
+        params: params(store)?,
+        accounts,
+        codes,
+        contract_states,
+        ..Default::default()
+    })
+}
+
+fn export_contract_state(store: &dyn Storage, contract_addr: &Addr) -> GenesisContractState {
+    let substore = ReadonlyPrefixedStore::new(store, &contract_namespace(contract_addr));
+    GenesisContractState {
+        address: contract_addr.into(),
+        state: substore
+            .range(None, None, Order::Ascending)
+            .map(|(k, v)| (Binary(k), Binary(v)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cw_sdk::PubKeyType;
+    use cw_store::PrefixedStore;
+
+    use super::*;
+
+    #[test]
+    fn exporting() {
+        let mut store = MockStorage::new();
+
+        let user_addr = Addr::unchecked("user");
+        let user = Account::Base {
+            pubkey: b"pubkey".into(),
+            pubkey_type: PubKeyType::Secp256k1,
+            account_number: 0,
+            sequence: 5,
+        };
+        let contract_addr = Addr::unchecked("contract");
+        let contract = Account::Contract {
+            code_id: 1,
+            label: "bank".into(),
+            admin: None,
+        };
+
+        let mut auth = Module::Auth.substore_mut(&mut store);
+        ACCOUNTS.save(&mut auth, &user_addr, &user).unwrap();
+        ACCOUNTS.save(&mut auth, &contract_addr, &contract).unwrap();
+
+        let mut wasm = Module::Wasm.substore_mut(&mut store);
+        CODES.save(&mut wasm, 1, &b"code".into()).unwrap();
+
+        let mut substore = PrefixedStore::new(&mut store, &contract_namespace(&contract_addr));
+        substore.set(b"key1", b"value1");
+        substore.set(b"key2", b"value2");
+
+        let gen_state = export(&store).unwrap();
+        assert_eq!(
+            gen_state.accounts,
+            vec![
+                GenesisAccount {
+                    address: "contract".into(),
+                    account: contract.into(),
+                },
+                GenesisAccount {
+                    address: "user".into(),
+                    account: user.into(),
+                },
+            ],
+        );
+        assert_eq!(
+            gen_state.codes,
+            vec![GenesisCode {
+                code_id: 1,
+                wasm_byte_code: b"code".into(),
+            }],
+        );
+        assert_eq!(
+            gen_state.contract_states,
+            vec![GenesisContractState {
+                address: "contract".into(),
+                state: vec![
+                    (b"key1".into(), b"value1".into()),
+                    (b"key2".into(), b"value2".into()),
+                ],
+            }],
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! Fee allowances, which let an account (the granter) pay the tx fees of
+//! another account (the grantee).
+//!
+//! A tx uses an allowance by naming the granter in its fee. The fee is then
+//! deducted from the granter's balance, provided that the allowance granted to
+//! the tx's fee payer covers it. The allowance is updated to reflect the
+//! amount spent, and is removed once it is used up.
+
+use cosmwasm_std::{Addr, BlockInfo, Coin, Event, Storage};
+use cw_sdk::{address, Allowance, TxMsg};
+
+use crate::{
+    error::{Error, Result},
+    state::{Module, FEE_ALLOWANCES},
+};
+
+/// Why an allowance can't be used to pay a fee.
+enum Rejection {
+    Expired,
+    Exceeded,
+    MsgNotAllowed(&'static str),
+}
+
+/// Grant the grantee an allowance, replacing any existing allowance granted by
+/// the same granter.
+pub fn grant_allowance(
+    store: &mut dyn Storage,
+    block: &BlockInfo,
+    granter_addr: &Addr,
+    grantee: &str,
+    mut allowance: Allowance,
+) -> Result<Event> {
+    let grantee_addr = address::validate(grantee)?;
+    if *granter_addr == grantee_addr {
+        return Err(Error::invalid_allowance("cannot grant an allowance to self"));
+    }
+
+    init_allowance(&mut allowance, block.time.seconds())?;
+
+    let mut auth = Module::Auth.substore_mut(store);
+    FEE_ALLOWANCES.save(&mut auth, (granter_addr, &grantee_addr), &allowance)?;
+
+    Ok(Event::new("grant_allowance")
+        .add_attribute("granter", granter_addr)
+        .add_attribute("grantee", grantee_addr))
+}
+
+/// Revoke the allowance granted to the grantee.
+pub fn revoke_allowance(
+    store: &mut dyn Storage,
+    granter_addr: &Addr,
+    grantee: &str,
+) -> Result<Event> {
+    let grantee_addr = address::validate(grantee)?;
+
+    let mut auth = Module::Auth.substore_mut(store);
+    let key = (granter_addr, &grantee_addr);
+    if !FEE_ALLOWANCES.has(&auth, key) {
+        return Err(Error::allowance_not_found(granter_addr, grantee_addr));
+    }
+    FEE_ALLOWANCES.remove(&mut auth, key);
+
+    Ok(Event::new("revoke_allowance")
+        .add_attribute("granter", granter_addr)
+        .add_attribute("grantee", grantee_addr))
+}
+
+/// Use the allowance granted by the granter to the grantee to pay the fee of a
+/// tx consisting of the given messages. Return error if there is no allowance,
+/// or if it doesn't cover the fee.
+pub fn use_allowance(
+    store: &mut dyn Storage,
+    block: &BlockInfo,
+    granter_addr: &Addr,
+    grantee_addr: &Addr,
+    fee: &[Coin],
+    msgs: &[TxMsg],
+) -> Result<()> {
+    let mut auth = Module::Auth.substore_mut(store);
+    let key = (granter_addr, grantee_addr);
+    let mut allowance = FEE_ALLOWANCES
+        .may_load(&auth, key)?
+        .ok_or_else(|| Error::allowance_not_found(granter_addr, grantee_addr))?;
+
+    match accept(&mut allowance, block.time.seconds(), fee, msgs) {
+        Ok(true) => FEE_ALLOWANCES.remove(&mut auth, key),
+        Ok(false) => FEE_ALLOWANCES.save(&mut auth, key, &allowance)?,
+        Err(Rejection::Expired) => {
+            return Err(Error::allowance_expired(granter_addr, grantee_addr));
+        },
+        Err(Rejection::Exceeded) => {
+            return Err(Error::allowance_exceeded(granter_addr, grantee_addr));
+        },
+        Err(Rejection::MsgNotAllowed(kind)) => {
+            return Err(Error::msg_not_allowed(kind));
+        },
+    }
+
+    Ok(())
+}
+
+/// Validate a newly granted allowance, and start the first period of a periodic
+/// allowance.
+fn init_allowance(allowance: &mut Allowance, now: u64) -> Result<()> {
+    match allowance {
+        Allowance::Basic {
+            expiration,
+            ..
+        } => check_expiration(*expiration, now),
+        Allowance::Periodic {
+            expiration,
+            period,
+            period_spend_limit,
+            period_can_spend,
+            period_reset,
+            ..
+        } => {
+            check_expiration(*expiration, now)?;
+            if *period == 0 {
+                return Err(Error::invalid_allowance("period must not be zero"));
+            }
+            if period_spend_limit.is_empty() {
+                return Err(Error::invalid_allowance("period spend limit must not be empty"));
+            }
+            *period_can_spend = period_spend_limit.clone();
+            *period_reset = now + *period;
+            Ok(())
+        },
+        Allowance::AllowedMsgs {
+            allowance,
+            allowed_msgs,
+        } => {
+            if allowed_msgs.is_empty() {
+                return Err(Error::invalid_allowance("allowed messages must not be empty"));
+            }
+            init_allowance(allowance, now)
+        },
+    }
+}
+
+fn check_expiration(expiration: Option<u64>, now: u64) -> Result<()> {
+    match expiration {
+        Some(expiration) if expiration <= now => {
+            Err(Error::invalid_allowance("expiration must be in the future"))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Deduct the fee from the allowance. Return whether the allowance is used up.
+fn accept(
+    allowance: &mut Allowance,
+    now: u64,
+    fee: &[Coin],
+    msgs: &[TxMsg],
+) -> std::result::Result<bool, Rejection> {
+    match allowance {
+        Allowance::Basic {
+            spend_limit,
+            expiration,
+        } => {
+            check_not_expired(*expiration, now)?;
+            spend(spend_limit, fee)
+        },
+        Allowance::Periodic {
+            spend_limit,
+            expiration,
+            period,
+            period_spend_limit,
+            period_can_spend,
+            period_reset,
+        } => {
+            check_not_expired(*expiration, now)?;
+
+            // start a new period if the current one has ended. if more than one
+            // period has elapsed since, the new period starts now
+            if now >= *period_reset {
+                *period_can_spend = period_spend_limit.clone();
+                *period_reset += *period;
+                if now >= *period_reset {
+                    *period_reset = now + *period;
+                }
+            }
+
+            deduct(period_can_spend, fee)?;
+            spend(spend_limit, fee)
+        },
+        Allowance::AllowedMsgs {
+            allowance,
+            allowed_msgs,
+        } => {
+            for msg in msgs {
+                let kind = msg.msg.kind();
+                if !allowed_msgs.iter().any(|allowed| allowed == kind) {
+                    return Err(Rejection::MsgNotAllowed(kind));
+                }
+            }
+            accept(allowance, now, fee, msgs)
+        },
+    }
+}
+
+fn check_not_expired(expiration: Option<u64>, now: u64) -> std::result::Result<(), Rejection> {
+    match expiration {
+        Some(expiration) if expiration <= now => Err(Rejection::Expired),
+        _ => Ok(()),
+    }
+}
+
+/// Deduct the fee from a spend limit, unless the limit is empty, meaning there
+/// is none. Return whether the limit is used up.
+fn spend(spend_limit: &mut [Coin], fee: &[Coin]) -> std::result::Result<bool, Rejection> {
+    if spend_limit.is_empty() {
+        return Ok(false);
+    }
+    deduct(spend_limit, fee)?;
+    Ok(spend_limit.iter().all(|coin| coin.amount.is_zero()))
+}
+
+/// Deduct the fee from the coins. Coins whose amounts drop to zero are kept,
+/// so that a spend limit that is used up isn't mistaken for no limit.
+fn deduct(coins: &mut [Coin], fee: &[Coin]) -> std::result::Result<(), Rejection> {
+    for fee_coin in fee {
+        let coin = coins
+            .iter_mut()
+            .find(|coin| coin.denom == fee_coin.denom)
+            .ok_or(Rejection::Exceeded)?;
+        coin.amount = coin.amount.checked_sub(fee_coin.amount).map_err(|_| Rejection::Exceeded)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coins, testing::MockStorage, Timestamp};
+    use cw_sdk::SdkMsg;
+
+    use super::*;
+
+    fn block_at(seconds: u64) -> BlockInfo {
+        BlockInfo {
+            height: 1,
+            time: Timestamp::from_seconds(seconds),
+            chain_id: "dev-1".into(),
+        }
+    }
+
+    fn execute_msg() -> TxMsg {
+        TxMsg {
+            sender: "grantee".into(),
+            msg: SdkMsg::Execute {
+                contract: "bank".into(),
+                msg: serde_json::json!({}),
+                funds: vec![],
+            },
+        }
+    }
+
+    fn granter_and_grantee() -> (Addr, Addr) {
+        let granter_addr = address::derive_from_label("granter").unwrap();
+        let grantee_addr = address::derive_from_label("grantee").unwrap();
+        (granter_addr, grantee_addr)
+    }
+
+    fn pay_fee(store: &mut dyn Storage, seconds: u64, amount: u128) -> Result<()> {
+        let (granter_addr, grantee_addr) = granter_and_grantee();
+        let fee = coins(amount, "uatom");
+        let msgs = [execute_msg()];
+        use_allowance(store, &block_at(seconds), &granter_addr, &grantee_addr, &fee, &msgs)
+    }
+
+    #[test]
+    fn using_allowances() {
+        let mut store = MockStorage::new();
+        let (granter_addr, grantee_addr) = granter_and_grantee();
+
+        // no allowance has been granted yet
+        let err = pay_fee(&mut store, 100, 1).unwrap_err();
+        assert!(matches!(err, Error::AllowanceNotFound { .. }));
+
+        // grant 100 uatom in total, and at most 30 uatom per 10 seconds
+        let allowance = Allowance::Periodic {
+            spend_limit: coins(100, "uatom"),
+            expiration: Some(1000),
+            period: 10,
+            period_spend_limit: coins(30, "uatom"),
+            period_can_spend: vec![],
+            period_reset: 0,
+        };
+        grant_allowance(&mut store, &block_at(100), &granter_addr, grantee_addr.as_str(), allowance)
+            .unwrap();
+
+        // the period limit applies
+        pay_fee(&mut store, 101, 20).unwrap();
+        let err = pay_fee(&mut store, 102, 20).unwrap_err();
+        assert!(matches!(err, Error::AllowanceExceeded { .. }));
+        pay_fee(&mut store, 102, 10).unwrap();
+
+        // it resets in the next period, but the total limit still applies
+        pay_fee(&mut store, 110, 30).unwrap();
+        pay_fee(&mut store, 125, 30).unwrap();
+        let err = pay_fee(&mut store, 140, 20).unwrap_err();
+        assert!(matches!(err, Error::AllowanceExceeded { .. }));
+
+        // the allowance is removed once it is used up
+        pay_fee(&mut store, 140, 10).unwrap();
+        let err = pay_fee(&mut store, 150, 1).unwrap_err();
+        assert!(matches!(err, Error::AllowanceNotFound { .. }));
+    }
+
+    #[test]
+    fn filtering_allowed_msgs() {
+        let mut store = MockStorage::new();
+        let (granter_addr, grantee_addr) = granter_and_grantee();
+
+        let allowance = Allowance::AllowedMsgs {
+            allowance: Box::new(Allowance::Basic {
+                spend_limit: vec![],
+                expiration: Some(200),
+            }),
+            allowed_msgs: vec!["execute".into()],
+        };
+        grant_allowance(&mut store, &block_at(100), &granter_addr, grantee_addr.as_str(), allowance)
+            .unwrap();
+
+        let fee = coins(1, "uatom");
+        let store_code = TxMsg {
+            sender: "grantee".into(),
+            msg: SdkMsg::StoreCode {
+                wasm_byte_code: b"wasm".into(),
+            },
+        };
+
+        // only the allowed message types may be paid for
+        use_allowance(&mut store, &block_at(101), &granter_addr, &grantee_addr, &fee, &[
+            execute_msg(),
+        ])
+        .unwrap();
+        let err = use_allowance(&mut store, &block_at(101), &granter_addr, &grantee_addr, &fee, &[
+            execute_msg(),
+            store_code,
+        ])
+        .unwrap_err();
+        assert!(matches!(err, Error::MsgNotAllowed { .. }));
+
+        // the allowance can't be used once it expires
+        let err = use_allowance(&mut store, &block_at(200), &granter_addr, &grantee_addr, &fee, &[
+            execute_msg(),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, Error::AllowanceExpired { .. }));
+    }
+}
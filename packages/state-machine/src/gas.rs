@@ -0,0 +1,342 @@
+//! Gas limits and accounting for system-initiated wasm calls.
+//!
+//! Some wasm calls aren't made on behalf of a user, but by the state machine
+//! itself, e.g. sudoing the bank contract to transfer the funds attached to a
+//! message, sudoing the staking contract at the end of each block, or sudoing a
+//! contract account to authenticate a tx it signs. These
+//! are bounded by gas limits set in the chain's params, one per entry point,
+//! and their gas usage is accounted for separately from user gas.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt::Write,
+    sync::Arc,
+};
+
+use cosmwasm_std::Addr;
+use cw_sdk::{ContractGasUsage, SystemGasLimits};
+use cw_store::metrics::Counter;
+
+use crate::backend::{ModuleCache, WasmConfig};
+
+/// The entry points through which the state machine calls into contracts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemCall {
+    /// Sudoing the bank contract to transfer the funds attached to a message
+    FundTransfer,
+    /// Sudoing the staking contract for validator set updates at the end of a block
+    EndBlock,
+    /// Sudoing a contract account to authenticate a tx it signs
+    BeforeTx,
+    /// Sudoing a contract account after the messages of a tx it signs are executed
+    AfterTx,
+    /// Sudoing the vote extensions contract to extend, verify or process votes
+    VoteExtensions,
+    /// Sudoing the taxman contract to compute the fee of a tx, and to settle it
+    Taxman,
+}
+
+impl SystemCall {
+    pub const ALL: [SystemCall; 6] = [
+        SystemCall::FundTransfer,
+        SystemCall::EndBlock,
+        SystemCall::BeforeTx,
+        SystemCall::AfterTx,
+        SystemCall::VoteExtensions,
+        SystemCall::Taxman,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            SystemCall::FundTransfer => "fund_transfer",
+            SystemCall::EndBlock => "end_block",
+            SystemCall::BeforeTx => "before_tx",
+            SystemCall::AfterTx => "after_tx",
+            SystemCall::VoteExtensions => "vote_extensions",
+            SystemCall::Taxman => "taxman",
+        }
+    }
+
+    /// The maximum amount of (CosmWasm) gas the call may consume, as set in the
+    /// chain's params.
+    pub const fn limit(self, limits: &SystemGasLimits) -> u64 {
+        match self {
+            SystemCall::FundTransfer => limits.fund_transfer,
+            SystemCall::EndBlock => limits.end_block,
+            SystemCall::BeforeTx => limits.before_tx,
+            SystemCall::AfterTx => limits.after_tx,
+            SystemCall::VoteExtensions => limits.vote_extensions,
+            SystemCall::Taxman => limits.taxman,
+        }
+    }
+}
+
+/// Accounting bucket of a single system call entry point.
+#[derive(Debug, Default)]
+pub struct GasBucket {
+    /// Number of calls made
+    pub calls: Counter,
+    /// Number of calls that ran out of gas
+    pub out_of_gas: Counter,
+    /// Total gas consumed by the calls
+    pub gas_used: Counter,
+}
+
+/// Gas consumed by system calls, one bucket per entry point.
+///
+/// The state machine is single-threaded, so like the store's metrics, the
+/// counters use `Cell`s and can be updated through a shared reference.
+#[derive(Debug, Default)]
+pub struct SystemGasMeter {
+    fund_transfer: GasBucket,
+    end_block: GasBucket,
+    before_tx: GasBucket,
+    after_tx: GasBucket,
+    vote_extensions: GasBucket,
+    taxman: GasBucket,
+}
+
+impl SystemGasMeter {
+    pub fn bucket(&self, call: SystemCall) -> &GasBucket {
+        match call {
+            SystemCall::FundTransfer => &self.fund_transfer,
+            SystemCall::EndBlock => &self.end_block,
+            SystemCall::BeforeTx => &self.before_tx,
+            SystemCall::AfterTx => &self.after_tx,
+            SystemCall::VoteExtensions => &self.vote_extensions,
+            SystemCall::Taxman => &self.taxman,
+        }
+    }
+
+    /// Record a call that consumed the given amount of gas.
+    pub fn record(&self, call: SystemCall, gas_used: u64, out_of_gas: bool) {
+        let bucket = self.bucket(call);
+        bucket.calls.inc();
+        bucket.gas_used.inc_by(gas_used);
+        if out_of_gas {
+            bucket.out_of_gas.inc();
+        }
+    }
+
+    /// Render the accounting buckets in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let counters: [(&str, &str, fn(&GasBucket) -> &Counter); 3] = [
+            ("calls_total", "Number of system calls", |bucket| &bucket.calls),
+            ("out_of_gas_total", "Number of system calls that ran out of gas", |bucket| {
+                &bucket.out_of_gas
+            }),
+            ("gas_used_total", "Total gas consumed by system calls", |bucket| &bucket.gas_used),
+        ];
+        for (name, help, counter) in counters {
+            writeln!(out, "# HELP cw_system_{name} {help}").unwrap();
+            writeln!(out, "# TYPE cw_system_{name} counter").unwrap();
+            for call in SystemCall::ALL {
+                let value = counter(self.bucket(call)).get();
+                writeln!(out, "cw_system_{name}{{call=\"{}\"}} {value}", call.name()).unwrap();
+            }
+        }
+
+        out
+    }
+}
+
+/// Gas consumed by the tx that is being executed: by its messages' contract
+/// calls, and by the system calls made on its behalf. It is reset before each
+/// tx is executed, and read afterwards.
+///
+/// Unlike the other meters, which count wasm gas, it counts SDK gas, as
+/// converted and charged according to the chain's `GasConfig`.
+///
+/// The tx's gas limit is set once it's reset, so that the contract calls made
+/// on behalf of its messages are given only the gas that it has left.
+#[derive(Debug, Default)]
+pub struct TxGasMeter {
+    used: Cell<u64>,
+    /// `None` means unlimited, e.g. for genesis messages
+    limit: Cell<Option<u64>>,
+}
+
+impl TxGasMeter {
+    pub fn consume(&self, gas: u64) {
+        self.used.set(self.used.get().saturating_add(gas));
+    }
+
+    /// Return the gas consumed since the last reset, without resetting it.
+    pub fn used(&self) -> u64 {
+        self.used.get()
+    }
+
+    /// Set the gas limit of the tx that is being executed.
+    pub fn set_limit(&self, limit: u64) {
+        self.limit.set(Some(limit));
+    }
+
+    /// Return the gas limit of the tx that is being executed, or `u64::MAX` if
+    /// none is set.
+    pub fn limit(&self) -> u64 {
+        self.limit.get().unwrap_or(u64::MAX)
+    }
+
+    /// Return the gas that may still be consumed before the limit is exceeded.
+    pub fn remaining(&self) -> u64 {
+        self.limit().saturating_sub(self.used())
+    }
+
+    /// Return the gas consumed since the last reset, and reset it to zero,
+    /// lifting the limit.
+    pub fn take(&self) -> u64 {
+        self.limit.take();
+        self.used.take()
+    }
+}
+
+/// Page sizes for querying the contracts that consumed the most gas.
+const CONTRACTS_DEFAULT_LIMIT: u32 = 10;
+const CONTRACTS_MAX_LIMIT: u32 = 100;
+
+/// Gas consumed by calls into each contract, whether on behalf of a message or
+/// by the state machine itself, cumulative since the node started, so that
+/// operators can tell which contracts are the most expensive to run.
+#[derive(Debug, Default)]
+pub struct ContractGasMeter {
+    /// The number of calls and the gas used, by contract address
+    usage: RefCell<BTreeMap<Addr, (u64, u64)>>,
+}
+
+impl ContractGasMeter {
+    /// Record a call into the contract that consumed the given amount of gas.
+    pub fn record(&self, contract_addr: &Addr, gas_used: u64) {
+        let mut usage = self.usage.borrow_mut();
+        let (calls, total) = usage.entry(contract_addr.clone()).or_default();
+        *calls += 1;
+        *total = total.saturating_add(gas_used);
+    }
+
+    /// Return the contracts that consumed the most gas, in descending order by
+    /// gas used.
+    pub fn top(&self, limit: Option<u32>) -> Vec<ContractGasUsage> {
+        let limit = limit.unwrap_or(CONTRACTS_DEFAULT_LIMIT).min(CONTRACTS_MAX_LIMIT) as usize;
+
+        let mut usage = self
+            .usage
+            .borrow()
+            .iter()
+            .map(|(address, (calls, gas_used))| ContractGasUsage {
+                address: address.to_string(),
+                calls: *calls,
+                gas_used: *gas_used,
+            })
+            .collect::<Vec<_>>();
+        usage.sort_by(|a, b| b.gas_used.cmp(&a.gas_used).then_with(|| a.address.cmp(&b.address)));
+        usage.truncate(limit);
+        usage
+    }
+
+    /// Render the usage of each contract in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let usage = self.usage.borrow();
+
+        let counters: [(&str, &str, fn(&(u64, u64)) -> u64); 2] = [
+            ("calls_total", "Number of calls into contracts", |(calls, _)| *calls),
+            ("gas_used_total", "Total gas consumed by calls into contracts", |(_, gas_used)| {
+                *gas_used
+            }),
+        ];
+        for (name, help, counter) in counters {
+            writeln!(out, "# HELP cw_contract_{name} {help}").unwrap();
+            writeln!(out, "# TYPE cw_contract_{name} counter").unwrap();
+            for (address, value) in usage.iter() {
+                let value = counter(value);
+                writeln!(out, "cw_contract_{name}{{contract=\"{address}\"}} {value}").unwrap();
+            }
+        }
+
+        out
+    }
+}
+
+/// The options of the wasm instances that all calls run in, the cache of the
+/// modules they're created from, and the meters that account for the system
+/// calls' usage, for the usage of the current tx, and for the usage of each
+/// contract.
+#[derive(Debug, Default)]
+pub struct SystemGas {
+    pub wasm: WasmConfig,
+    pub modules: Option<Arc<ModuleCache>>,
+    pub meter: SystemGasMeter,
+    pub tx: TxGasMeter,
+    pub contracts: ContractGasMeter,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering() {
+        let meter = SystemGasMeter::default();
+        meter.record(SystemCall::FundTransfer, 100, false);
+        meter.record(SystemCall::FundTransfer, 50, true);
+        meter.record(SystemCall::EndBlock, 7, false);
+
+        let text = meter.render();
+        assert!(text.contains("cw_system_calls_total{call=\"fund_transfer\"} 2\n"));
+        assert!(text.contains("cw_system_calls_total{call=\"end_block\"} 1\n"));
+        assert!(text.contains("cw_system_out_of_gas_total{call=\"fund_transfer\"} 1\n"));
+        assert!(text.contains("cw_system_out_of_gas_total{call=\"end_block\"} 0\n"));
+        assert!(text.contains("cw_system_gas_used_total{call=\"fund_transfer\"} 150\n"));
+    }
+
+    #[test]
+    fn metering_contract_gas() {
+        let meter = ContractGasMeter::default();
+        meter.record(&Addr::unchecked("bank"), 100);
+        meter.record(&Addr::unchecked("staking"), 300);
+        meter.record(&Addr::unchecked("bank"), 50);
+        meter.record(&Addr::unchecked("token"), 150);
+
+        let top = meter.top(Some(2));
+        assert_eq!(
+            top,
+            vec![
+                ContractGasUsage {
+                    address: "staking".into(),
+                    calls: 1,
+                    gas_used: 300,
+                },
+                ContractGasUsage {
+                    address: "bank".into(),
+                    calls: 2,
+                    gas_used: 150,
+                },
+            ],
+        );
+
+        let text = meter.render();
+        assert!(text.contains("cw_contract_calls_total{contract=\"bank\"} 2\n"));
+        assert!(text.contains("cw_contract_gas_used_total{contract=\"bank\"} 150\n"));
+        assert!(text.contains("cw_contract_gas_used_total{contract=\"token\"} 150\n"));
+    }
+
+    #[test]
+    fn metering_tx_gas() {
+        let meter = TxGasMeter::default();
+        meter.consume(100);
+        meter.consume(u64::MAX);
+        assert_eq!(meter.take(), u64::MAX);
+        assert_eq!(meter.take(), 0);
+
+        // the limit lasts until the meter is reset
+        meter.set_limit(150);
+        meter.consume(100);
+        assert_eq!(meter.remaining(), 50);
+        meter.consume(100);
+        assert_eq!(meter.remaining(), 0);
+        assert_eq!(meter.take(), 200);
+        assert_eq!(meter.remaining(), u64::MAX);
+    }
+}
@@ -0,0 +1,189 @@
+use cosmwasm_std::{Addr, Binary, Empty, Order, Storage};
+use cw_sdk::{address, Account, GenesisState};
+use cw_store::PrefixedStore;
+
+use crate::{
+    error::{Error, Result},
+    state::{
+        contract_namespace, Module, ACCOUNTS, ACCOUNT_COUNT, CODES, CODE_COUNT, CONTRACTS_BY_CODE,
+    },
+};
+
+/// Write the accounts, codes and contract states exported from an existing
+/// chain into the store. This is the reverse of `export::export`.
+///
+/// The code count is set to the largest imported code id, so that codes stored
+/// later don't overwrite the imported ones. Similarly, the account count is set
+/// to one plus the largest imported account number.
+pub fn import(store: &mut dyn Storage, gen_state: &GenesisState) -> Result<()> {
+    let mut auth = Module::Auth.substore_mut(store);
+    let mut account_count = 0;
+    for genesis_account in &gen_state.accounts {
+        let addr = address::validate(&genesis_account.address)?;
+        let account = match &genesis_account.account {
+            Account::Base {
+                pubkey,
+                pubkey_type,
+                account_number,
+                sequence,
+            } => {
+                account_count = account_count.max(account_number + 1);
+                Account::Base {
+                    pubkey: pubkey.clone(),
+                    pubkey_type: *pubkey_type,
+                    account_number: *account_number,
+                    sequence: *sequence,
+                }
+            },
+            Account::Multisig {
+                pubkey,
+                account_number,
+                sequence,
+            } => {
+                account_count = account_count.max(account_number + 1);
+                Account::Multisig {
+                    pubkey: pubkey.clone(),
+                    account_number: *account_number,
+                    sequence: *sequence,
+                }
+            },
+            Account::Contract {
+                code_id,
+                label,
+                admin,
+            } => {
+                CONTRACTS_BY_CODE.save(&mut auth, (*code_id, &addr), &Empty {})?;
+                Account::Contract {
+                    code_id: *code_id,
+                    label: label.clone(),
+                    admin: admin.as_deref().map(address::validate).transpose()?,
+                }
+            },
+        };
+        ACCOUNTS.save(&mut auth, &addr, &account)?;
+    }
+    ACCOUNT_COUNT.save(&mut auth, &account_count)?;
+
+    let mut wasm = Module::Wasm.substore_mut(store);
+    let mut code_count = 0;
+    for code in &gen_state.codes {
+        CODES.save(&mut wasm, code.code_id, &code.wasm_byte_code)?;
+        code_count = code_count.max(code.code_id);
+    }
+    CODE_COUNT.save(&mut wasm, &code_count)?;
+
+    for contract_state in &gen_state.contract_states {
+        let contract_addr = address::validate(&contract_state.address)?;
+        let mut substore = PrefixedStore::new(store, &contract_namespace(&contract_addr));
+        for (key, value) in &contract_state.state {
+            substore.set(key, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace all key-value pairs in a contract's store with the given ones, e.g.
+/// ones exported from another chain. The contract must exist.
+pub fn import_contract_state(
+    store: &mut dyn Storage,
+    contract_addr: &Addr,
+    state: &[(Binary, Binary)],
+) -> Result<()> {
+    match ACCOUNTS.may_load(&Module::Auth.substore(store), contract_addr)? {
+        Some(Account::Contract {
+            ..
+        }) => (),
+        Some(_) => return Err(Error::account_is_not_contract(contract_addr.as_str())),
+        None => return Err(Error::account_not_found(contract_addr.as_str())),
+    }
+
+    let mut substore = PrefixedStore::new(store, &contract_namespace(contract_addr));
+    let keys: Vec<_> = substore.range(None, None, Order::Ascending).map(|(key, _)| key).collect();
+    for key in keys {
+        substore.remove(&key);
+    }
+    for (key, value) in state {
+        substore.set(key, value);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cw_sdk::{GenesisAccount, GenesisCode, GenesisContractState};
+
+    use super::*;
+    use crate::export::export;
+
+    #[test]
+    fn importing_exported_state() {
+        let contract_addr = address::derive_from_label("bank").unwrap();
+        let gen_state = GenesisState {
+            accounts: vec![GenesisAccount {
+                address: contract_addr.to_string(),
+                account: Account::Contract {
+                    code_id: 2,
+                    label: "bank".into(),
+                    admin: None,
+                },
+            }],
+            codes: vec![
+                GenesisCode {
+                    code_id: 1,
+                    wasm_byte_code: b"code1".into(),
+                },
+                GenesisCode {
+                    code_id: 2,
+                    wasm_byte_code: b"code2".into(),
+                },
+            ],
+            contract_states: vec![GenesisContractState {
+                address: contract_addr.to_string(),
+                state: vec![(b"key".into(), b"value".into())],
+            }],
+            ..Default::default()
+        };
+
+        let mut store = MockStorage::new();
+        import(&mut store, &gen_state).unwrap();
+
+        assert_eq!(CODE_COUNT.load(&Module::Wasm.substore(&store)).unwrap(), 2);
+        assert_eq!(export(&store).unwrap(), gen_state);
+    }
+
+    #[test]
+    fn importing_contract_state() {
+        let contract_addr = address::derive_from_label("bank").unwrap();
+        let gen_state = GenesisState {
+            accounts: vec![GenesisAccount {
+                address: contract_addr.to_string(),
+                account: Account::Contract {
+                    code_id: 1,
+                    label: "bank".into(),
+                    admin: None,
+                },
+            }],
+            contract_states: vec![GenesisContractState {
+                address: contract_addr.to_string(),
+                state: vec![(b"key1".into(), b"old".into()), (b"key2".into(), b"old".into())],
+            }],
+            ..Default::default()
+        };
+
+        let mut store = MockStorage::new();
+        import(&mut store, &gen_state).unwrap();
+
+        let state = vec![(b"key2".into(), b"new".into()), (b"key3".into(), b"new".into())];
+        import_contract_state(&mut store, &contract_addr, &state).unwrap();
+        assert_eq!(export(&store).unwrap().contract_states, vec![GenesisContractState {
+            address: contract_addr.to_string(),
+            state,
+        }]);
+
+        let err = import_contract_state(&mut store, &Addr::unchecked("nobody"), &[]).unwrap_err();
+        assert!(matches!(err, Error::AccountNotFound { .. }));
+    }
+}
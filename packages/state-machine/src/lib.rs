@@ -1,22 +1,131 @@
+pub mod ante;
 pub mod auth;
+pub mod authz;
 pub mod backend;
 pub mod error;
 pub mod execute;
+pub mod export;
+pub mod feegrant;
+pub mod gas;
+pub mod import;
+pub mod migrations;
+pub mod proposal;
 pub mod query;
+pub mod sigverify;
 pub mod state;
+pub mod tx_index;
+pub mod vote_extensions;
+
+#[cfg(test)]
+mod testing;
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use cosmwasm_std::{
-    to_binary, Addr, Binary, BlockInfo, ContractInfo, Env, Event, MessageInfo, Storage, Timestamp,
-    TransactionInfo,
+    from_binary, to_binary, Addr, Binary, BlockInfo, ContractInfo, ContractResult, Decimal, Env,
+    Event, MessageInfo, Response, Storage, Timestamp, TransactionInfo,
+};
+use cw_sdk::{
+    address,
+    canonical::to_canonical_vec,
+    hash::{sha256, HASH_LENGTH},
+    staking, vote_extensions::SudoMsg as VoteExtensionsMsg, ExtendedVote, GenesisState, SdkMsg,
+    SdkQuery, SignMode, SimulateResponse, Tx, Validator,
 };
-use cw_sdk::{address, hash::HASH_LENGTH, GenesisState, SdkMsg, SdkQuery, Tx};
-use cw_store::{Cached, Shared, Store};
+use cw_store::{Cached, PendingStoreWrapper, Shared, Snapshot, Store};
+use tracing::{field, info_span, Span};
 
 use crate::{
+    ante::{AnteHandler, CheckTxOutcome, CheckTxType, PriorityOverride},
+    backend::{ModuleCache, WasmConfig},
     error::{Error, Result},
-    state::{ACCOUNTS, BLOCK, CODE_COUNT},
+    gas::{SystemCall, SystemGas},
+    proposal::{ProposalHandler, ProposalTx},
+    sigverify::SignatureCache,
+    state::{
+        active_validator_set, apply_validator_updates, latest_validator_set,
+        normalize_validator_updates, update_params, Module, ACCOUNTS, BLOCK, PARAMS,
+        VALIDATOR_SETS,
+    },
+    tx_index::PendingTxs,
+    vote_extensions::{check_votes, decode_votes_tx, encode_votes_tx, VoteExtensionHandler},
 };
 
+/// Label of the staking contract, which is invoked at the end of each block to
+/// provide validator set updates.
+pub const STAKING: &str = "staking";
+
+/// Label of the contract to which tx fees are transferred, which is responsible
+/// for distributing them.
+pub const FEE_COLLECTOR: &str = "distribution";
+
+/// Label of the contract which, if one exists, decides the fee each tx is
+/// charged, and the refund it's given once executed.
+pub const TAXMAN: &str = "taxman";
+
+/// Label of the governance contract, which alone may update the chain-level
+/// parameters.
+pub const GOVERNANCE: &str = "gov";
+
+/// Labels of the system contracts, which may only be instantiated by the
+/// genesis messages, so that no one can claim them once the chain is live.
+pub const RESERVED_LABELS: &[&str] = &[FEE_COLLECTOR];
+
+/// Version of the state machine's protocol, reported to Tendermint in the Info
+/// response. To be bumped whenever a change makes the same blocks produce
+/// different results, so that nodes on different versions can be told apart.
+pub const APP_VERSION: u64 = 2;
+
+/// Semantic version of the state machine, reported to Tendermint in the Info
+/// response.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The outcome of a tx executed in a block, whether it succeeded or not.
+#[derive(Debug)]
+pub struct TxOutcome {
+    /// The amount of gas that the tx requests, i.e. its fee's gas limit
+    pub gas_wanted: u64,
+
+    /// The amount of gas that the tx consumed, up to the point where it failed
+    /// if it did
+    pub gas_used: u64,
+
+    /// The events emitted by the tx, or the error it failed with
+    pub result: Result<Vec<Event>>,
+}
+
+impl TxOutcome {
+    /// The outcome of a tx that failed before consuming any gas, e.g. because
+    /// it couldn't be decoded.
+    pub fn failed(error: Error) -> Self {
+        Self {
+            gas_wanted: 0,
+            gas_used: 0,
+            result: Err(error),
+        }
+    }
+}
+
+/// The outcome of a block executed by `StateMachine::finalize_block`.
+#[derive(Debug)]
+pub struct FinalizeBlockOutcome {
+    /// Events emitted at the beginning and the end of the block
+    pub events: Vec<Event>,
+
+    /// The outcome of each tx, in the same order as the txs
+    pub tx_results: Vec<TxOutcome>,
+
+    /// Validator set updates provided by the staking contract
+    pub validator_updates: Vec<Validator>,
+
+    /// The app hash after the block is committed
+    pub app_hash: [u8; HASH_LENGTH],
+}
+
 pub struct StateMachine {
     /// The database backend, which stores blockchain state persistently.
     ///
@@ -35,6 +144,74 @@ pub struct StateMachine {
     ///   state using the BLOCK storage constant.
     pending_block: Option<BlockInfo>,
 
+    /// In safe mode, queries are served from the last committed state, but
+    /// InitChain, BeginBlock, DeliverTx, EndBlock and Commit are all refused,
+    /// so that the state can't diverge any further.
+    safe_mode: bool,
+
+    /// Options of the calls that the state machine itself makes into contracts,
+    /// and the accounting of their gas usage. Their gas limits are set in the
+    /// chain's params.
+    system_gas: SystemGas,
+
+    /// If non-zero, the state machine halts once the block at this height is
+    /// committed, i.e. refuses to begin any further block.
+    halt_height: u64,
+
+    /// If non-zero, a UNIX timestamp (in seconds). The state machine halts once
+    /// a block whose time is at or after it is committed.
+    halt_time: u64,
+
+    /// Signatures that have been verified ahead of DeliverTx, in CheckTx or in
+    /// a batch, so that DeliverTx doesn't verify them again.
+    signature_cache: SignatureCache,
+
+    /// The state against which txs are checked before being admitted to the
+    /// mempool, including the changes made by the txs checked so far. Set when
+    /// the first tx is checked, and cleared when a block is committed.
+    check_state: Option<Shared<Cached<PendingStoreWrapper>>>,
+
+    /// Additional checks that txs must pass to be admitted to the mempool,
+    /// run in order after the built-in ones.
+    ante_handlers: Vec<Box<dyn AnteHandler>>,
+
+    /// The denom in which gas prices are measured, which determine the txs'
+    /// priorities in the mempool. If empty, all txs have zero priority.
+    fee_denom: String,
+
+    /// The minimum gas prices, by denom, that txs must pay in one of the denoms
+    /// to be admitted to this node's mempool. If empty, any gas price is
+    /// accepted.
+    min_gas_prices: BTreeMap<String, Decimal>,
+
+    /// If set, determines the txs' priorities in place of their gas prices.
+    priority_override: Option<Box<dyn PriorityOverride>>,
+
+    /// The maximum number of txs of the same primary signer that this node
+    /// includes in a block it proposes. Zero means unlimited.
+    max_txs_per_account: u64,
+
+    /// If set, drops or reorders the txs of the blocks this node proposes,
+    /// after the built-in checks.
+    proposal_handler: Option<Box<dyn ProposalHandler>>,
+
+    /// If set, extends this node's validator's votes and verifies those of
+    /// other validators, and processes the extensions included in blocks.
+    vote_extension_handler: Option<VoteExtensionHandler>,
+
+    /// The chain's `vote_extensions_enable_height` consensus parameter, above
+    /// which proposals must start with the extended votes for the last block.
+    /// Zero means votes aren't extended by consensus.
+    vote_extensions_enable_height: u64,
+
+    /// The outcomes of the txs of the block being executed, to be indexed when
+    /// the block is committed.
+    pending_txs: RefCell<PendingTxs>,
+
+    /// The span covering the execution of the pending block, from BeginBlock
+    /// to Commit, which the spans of its txs are nested in.
+    block_span: Span,
+
     // TODO: load pinned contracts and codes
 }
 
@@ -43,13 +220,165 @@ impl StateMachine {
         Self {
             store,
             pending_block: None,
+            safe_mode: false,
+            system_gas: SystemGas::default(),
+            halt_height: 0,
+            halt_time: 0,
+            signature_cache: SignatureCache::default(),
+            check_state: None,
+            ante_handlers: vec![],
+            fee_denom: String::new(),
+            min_gas_prices: BTreeMap::new(),
+            priority_override: None,
+            max_txs_per_account: 0,
+            proposal_handler: None,
+            vote_extension_handler: None,
+            vote_extensions_enable_height: 0,
+            pending_txs: RefCell::default(),
+            block_span: Span::none(),
+        }
+    }
+
+    /// Set the height and time at which the state machine halts, e.g. for a
+    /// coordinated upgrade. Zero means the respective condition is disabled.
+    pub fn set_halt(&mut self, halt_height: u64, halt_time: u64) {
+        self.halt_height = halt_height;
+        self.halt_time = halt_time;
+    }
+
+    /// Set the options of the wasm instances that contracts are run in, e.g. as
+    /// configured by the operator.
+    pub fn set_wasm_config(&mut self, wasm: WasmConfig) {
+        self.system_gas.wasm = wasm;
+    }
+
+    /// Create contract instances from the modules in the given cache, rather
+    /// than compiling the contract's code on every call.
+    pub fn set_module_cache(&mut self, modules: ModuleCache) {
+        self.system_gas.modules = Some(Arc::new(modules));
+    }
+
+    /// Add a check that txs must pass to be admitted to the mempool, after the
+    /// built-in checks and the ante handlers added before it.
+    pub fn add_ante_handler(&mut self, handler: impl AnteHandler + 'static) {
+        self.ante_handlers.push(Box::new(handler));
+    }
+
+    /// Set the denom in which gas prices are measured, e.g. as configured by
+    /// the operator.
+    pub fn set_fee_denom(&mut self, fee_denom: impl Into<String>) {
+        self.fee_denom = fee_denom.into();
+    }
+
+    /// Set the minimum gas prices, by denom, of the txs admitted to this node's
+    /// mempool, e.g. as configured by the operator. A tx must meet the price of
+    /// one of the denoms. If empty, any gas price is accepted.
+    pub fn set_min_gas_prices(&mut self, min_gas_prices: BTreeMap<String, Decimal>) {
+        self.min_gas_prices = min_gas_prices;
+    }
+
+    /// Override the priorities of txs in this node's mempool.
+    pub fn set_priority_override(&mut self, priority_override: impl PriorityOverride + 'static) {
+        self.priority_override = Some(Box::new(priority_override));
+    }
+
+    /// Limit the number of txs of the same primary signer in the blocks this
+    /// node proposes, e.g. as configured by the operator. Zero means unlimited.
+    pub fn set_max_txs_per_account(&mut self, max_txs_per_account: u64) {
+        self.max_txs_per_account = max_txs_per_account;
+    }
+
+    /// Set the handler that drops or reorders the txs of the blocks this node
+    /// proposes.
+    pub fn set_proposal_handler(&mut self, handler: impl ProposalHandler + 'static) {
+        self.proposal_handler = Some(Box::new(handler));
+    }
+
+    /// Set the handler of vote extensions, and the height from which consensus
+    /// extends votes, i.e. the chain's `vote_extensions_enable_height`
+    /// consensus parameter. All validators must set the same ones, or they will
+    /// disagree on which proposals are valid.
+    pub fn set_vote_extension_handler(
+        &mut self,
+        handler: VoteExtensionHandler,
+        enable_height: u64,
+    ) {
+        self.vote_extension_handler = Some(handler);
+        self.vote_extensions_enable_height = enable_height;
+    }
+
+    /// Set whether an undo checkpoint of the store is taken before each commit,
+    /// so that the last commit can be rolled back. Not keeping it saves the
+    /// time taken to checkpoint, and the disk space of overwritten files.
+    pub fn set_keep_undo_log(&mut self, keep_undo_log: bool) {
+        self.store.set_keep_undo(keep_undo_log);
+    }
+
+    /// Upgrade the layouts of the modules' states to their latest versions. This
+    /// should be called on startup, before any block is executed.
+    ///
+    /// The changes are not committed right away, but together with the next
+    /// block. This way, the app hash of the last committed block remains the
+    /// same, as Tendermint expects, while all nodes that upgrade at the same
+    /// height arrive at the same app hash for the next block.
+    pub fn migrate(&mut self) -> Result<()> {
+        migrations::migrate(&mut self.store.pending_wrap())
+    }
+
+    /// Put the state machine in safe mode. See the `safe_mode` field for details.
+    pub fn enable_safe_mode(&mut self) {
+        self.safe_mode = true;
+    }
+
+    fn assert_not_safe_mode(&self) -> Result<()> {
+        if self.safe_mode {
+            return Err(Error::SafeMode);
+        }
+        Ok(())
+    }
+
+    /// Return error if the last committed block meets the halt height or time.
+    fn assert_not_halted(&self) -> Result<()> {
+        let Some(block) = BLOCK.may_load(&Module::Chain.substore(&self.store.wrap()))? else {
+            return Ok(());
+        };
+
+        let height_reached = self.halt_height > 0 && block.height >= self.halt_height;
+        let time_reached = self.halt_time > 0 && block.time.seconds() >= self.halt_time;
+        if height_reached || time_reached {
+            return Err(Error::Halted {
+                height: block.height,
+            });
         }
+
+        Ok(())
     }
 
-    /// Decode genesis bytes and run genesis messages. Return app hash.
+    /// Decode genesis bytes and run genesis messages and txs. Return the app
+    /// hash, and the initial validator set if the staking contract provides
+    /// one, in which case it replaces the one in Tendermint's genesis.
+    ///
+    /// If the genesis state includes accounts, codes and contract states
+    /// exported from an existing chain, they are imported before the messages
+    /// are run.
     ///
-    /// TODO: Once a staking contract is created, return the validator set as well
-    pub fn init_chain(&self, chain_id: String, gen_state: GenesisState) -> Result<[u8; HASH_LENGTH]> {
+    /// If the genesis state includes txs, e.g. validator operators creating
+    /// their validators, the staking contract is invoked after they are
+    /// executed for the initial validator set.
+    ///
+    /// The chain may start at a non-zero height, e.g. when restarting from an
+    /// exported state. The initial block height, as provided by Tendermint, is
+    /// the height of the first block to be executed; genesis is recorded at the
+    /// height prior to it, as is the initial validator set.
+    pub fn init_chain(
+        &self,
+        chain_id: String,
+        initial_height: u64,
+        validators: Vec<Validator>,
+        gen_state: GenesisState,
+    ) -> Result<([u8; HASH_LENGTH], Vec<Validator>)> {
+        self.assert_not_safe_mode()?;
+
         // make a cache of the store. only flush it if the entire init chain
         // flow is successful.
         // additionally, wrap the cached store in `Rc<RefCell<T>>` so that it
@@ -57,97 +386,597 @@ impl StateMachine {
         let mut cache = Shared::new(Cached::new(self.store.pending_wrap()));
 
         let block = BlockInfo {
-            height: 0,
+            height: initial_height.saturating_sub(1),
             time: Timestamp::default(),
             chain_id,
         };
 
-        BLOCK.save(&mut cache, &block)?;
-        CODE_COUNT.save(&mut cache, &0)?;
+        // the states are created with the latest layouts
+        migrations::init_versions(&mut cache)?;
+
+        // import exported state. this also initializes the code count
+        import::import(&mut cache, &gen_state)?;
 
-        let deployer_addr = address::validate(&gen_state.deployer)?;
+        let mut chain = Module::Chain.substore_mut(&mut cache);
+        let validators = apply_validator_updates(vec![], &validators);
+        BLOCK.save(&mut chain, &block)?;
+        VALIDATOR_SETS.save(&mut chain, block.height, &validators)?;
+
+        PARAMS.save(&mut Module::Params.substore_mut(&mut cache), &gen_state.params)?;
 
         // execute messages in order.
         // ResponseInitChain doesn't take events, so we discard the emitted events here.
-        for msg in gen_state.msgs {
-            self.handle_msg(
+        //
+        // an exported genesis state may not have any messages, in which case
+        // a deployer isn't needed.
+        if !gen_state.msgs.is_empty() {
+            let deployer_addr = address::validate(&gen_state.deployer)?;
+            for msg in gen_state.msgs {
+                self.handle_msg(
+                    cache.share(),
+                    block.clone(),
+                    None,
+                    &deployer_addr,
+                    msg,
+                    true,
+                )?;
+            }
+        }
+
+        // execute the genesis txs, each on behalf of its signers, whose
+        // signatures are verified like in DeliverTx. unlike in DeliverTx, a
+        // failed tx fails the entire genesis
+        for tx in &gen_state.gen_txs {
+            let tx_bytes = to_canonical_vec(tx)?;
+            let (mut tx_cache, result) = self.execute_tx(
                 cache.share(),
-                block.clone(),
-                None,
-                &deployer_addr,
-                msg,
+                &block,
+                tx,
+                &SignMode::Json,
+                &self.signature_cache,
+                &sha256(&tx_bytes),
+                tx_bytes.len(),
+                u64::MAX,
             )?;
+            result?;
+            tx_cache.borrow_mut().flush();
+        }
+
+        let mut initial_validators = vec![];
+        if !gen_state.gen_txs.is_empty() {
+            let (_, updates, _) = self.staking_updates(cache.share(), &block)?;
+            if !updates.is_empty() {
+                initial_validators = apply_validator_updates(validators, &updates);
+                let mut chain = Module::Chain.substore_mut(&mut cache);
+                VALIDATOR_SETS.save(&mut chain, block.height, &initial_validators)?;
+            }
         }
 
         // init chain is successful; flush the state changes
         cache.borrow_mut().flush();
 
-        Ok(self.store.root_hash())
+        Ok((self.store.root_hash(), initial_validators))
     }
 
     pub fn begin_block(&mut self, block: BlockInfo) -> Result<Vec<Event>> {
-        // current we just update pending block and do nothing else
+        self.assert_not_safe_mode()?;
+        self.assert_not_halted()?;
+
+        self.block_span = info_span!(
+            "block",
+            height = block.height,
+            txs = field::Empty,
+            gas_used = field::Empty,
+        );
+        let _entered = self.block_span.enter();
+
+        // forget idempotency keys that have expired
+        auth::prune_idempotency_keys(&mut self.store.pending_wrap(), &block)?;
+
         // TODO: read cosmos-sdk code and see what to do here
         self.pending_block = Some(block);
 
         Ok(vec![])
     }
 
-    pub fn deliver_tx(&self, tx: Tx) -> Result<Vec<Event>> {
-        // make a cache of the store. it will only be flushed if the entire tx
-        // is successful
-        let mut cache = Cached::new(self.store.pending_wrap());
+    /// Check a tx before it is admitted to the mempool. Return error if the tx
+    /// would certainly be rejected if it was included in the next block.
+    ///
+    /// The tx is checked against the check state, as if it was executed after
+    /// the txs checked before it, but its messages aren't executed; see the
+    /// `ante` module for what is checked. On recheck, signatures are not verified
+    /// again. `tx_len` is the size of the tx as it is encoded when broadcasted.
+    pub fn check_tx(
+        &mut self,
+        tx: &Tx,
+        sign_mode: &SignMode,
+        tx_len: usize,
+        check_type: CheckTxType,
+    ) -> Result<CheckTxOutcome> {
+        // the minimum gas prices are this node's own policy, so they're only
+        // enforced on admission to the mempool, not on txs included in blocks
+        ante::check_min_gas_prices(&tx.body.fee, &self.min_gas_prices)?;
+
+        let check_state = self
+            .check_state
+            .get_or_insert_with(|| Shared::new(Cached::new(self.store.pending_wrap())))
+            .share();
+
+        let unverified = SignatureCache::unverified();
+        let sigs = match check_type {
+            CheckTxType::New => &self.signature_cache,
+            CheckTxType::Recheck => &unverified,
+        };
+
+        // only flush the changes into the check state if all checks pass
+        let (outcome, mut cache) =
+            self.run_checks(Cached::new(check_state), tx, sign_mode, tx_len, sigs)?;
+        cache.borrow_mut().flush();
+
+        Ok(outcome)
+    }
+
+    /// Run the checks of CheckTx against the given store, and return it with
+    /// the changes the checks made, for the caller to flush if they pass.
+    fn run_checks<S>(
+        &self,
+        store: S,
+        tx: &Tx,
+        sign_mode: &SignMode,
+        tx_len: usize,
+        sigs: &SignatureCache,
+    ) -> Result<(CheckTxOutcome, Shared<S>)>
+    where
+        S: Storage + 'static,
+    {
+        auth::check_tx_size(&store, tx, tx_len)?;
+
+        let last_block = BLOCK.load(&Module::Chain.substore(&store))?;
+        let block = BlockInfo {
+            height: last_block.height + 1,
+            ..last_block
+        };
+
+        // contract accounts authenticate the tx by their `before_tx` hooks,
+        // which are only bounded by the system gas limit, not the tx's
+        self.system_gas.tx.take();
+        let (signer_addrs, store) =
+            auth::authenticate_tx(store, &block, tx, sign_mode, sigs, &self.system_gas)?;
+        let mut store = Shared::new(store);
+
+        if let Some(key) = &tx.body.idempotency_key {
+            auth::check_idempotency_key(&store, &block, &signer_addrs[0], key)?;
+        }
+
+        // dry-run the fee charge, so that the taxman contract, if one exists,
+        // decides the coins to charge and may reject the tx. the deduction is
+        // discarded along with the cache, so that each tx is checked against
+        // the payer's full balances
+        let fee_payer_addr = auth::fee_payer(&store, &tx.body, &signer_addrs)?;
+        let (charged, ..) = execute::charge_fee(
+            Cached::new(store.share()),
+            &block,
+            tx,
+            &fee_payer_addr,
+            &self.system_gas,
+        )?;
+        auth::use_fee_allowance(&mut store, &block, &tx.body, &signer_addrs, &charged)?;
+        ante::check_fee_balance(&store, &fee_payer_addr, &charged)?;
+
+        for handler in &self.ante_handlers {
+            handler.check(&mut store, &block, tx)?;
+        }
+
+        let outcome = CheckTxOutcome {
+            gas_wanted: tx.body.fee.gas_limit,
+            priority: self.priority(tx),
+        };
+
+        Ok((outcome, store))
+    }
+
+    /// Return the priority of a tx in the mempool: its gas price in the fee
+    /// denom, unless overridden.
+    fn priority(&self, tx: &Tx) -> i64 {
+        let priority = ante::fee_priority(&tx.body.fee, &self.fee_denom);
+        match &self.priority_override {
+            Some(priority_override) => priority_override.priority(tx, priority),
+            None => priority,
+        }
+    }
+
+    /// Verify the signatures of many txs in a batch, e.g. those of a block
+    /// that is about to be delivered, so that DeliverTx doesn't need to verify
+    /// them one by one. Return the number of signatures found valid.
+    ///
+    /// Pubkeys of existing accounts are looked up in the last committed state.
+    /// Txs whose signatures can't be collected, e.g. because their sign bytes
+    /// can't be computed, are skipped, to be rejected in DeliverTx.
+    pub fn verify_signatures(&self, txs: &[(Tx, SignMode)]) -> usize {
+        let store = self.store.wrap();
+        let checks = txs
+            .iter()
+            .filter_map(|(tx, sign_mode)| sigverify::signature_checks(&store, tx, sign_mode).ok())
+            .flatten()
+            .collect::<Vec<_>>();
+        self.signature_cache.verify_batch(&checks)
+    }
+
+    /// Decode and execute a tx of the block, as provided by Tendermint. A tx
+    /// that can't be decoded fails without consuming any gas.
+    ///
+    /// The tx's outcome is recorded, to be indexed when the block is committed.
+    pub fn deliver_raw_tx(&self, tx_bytes: &[u8]) -> TxOutcome {
+        let _block = self.block_span.enter();
+
+        let tx_hash = sha256(tx_bytes);
+        let span = info_span!(
+            "tx",
+            hash = %hex::encode_upper(tx_hash),
+            gas_wanted = field::Empty,
+            gas_used = field::Empty,
+            error = field::Empty,
+        );
+        let _entered = span.enter();
+
+        let outcome = match auth::decode_tx(tx_bytes) {
+            Ok((tx, sign_mode)) => self.deliver_tx(tx, &sign_mode, &tx_hash, tx_bytes.len()),
+            Err(err) => TxOutcome::failed(err),
+        };
+
+        span.record("gas_wanted", outcome.gas_wanted);
+        span.record("gas_used", outcome.gas_used);
+        if let Err(err) = &outcome.result {
+            span.record("error", field::display(err));
+        }
+
+        self.record_tx(&tx_hash, &outcome);
+        outcome
+    }
+
+    fn record_tx(&self, tx_hash: &[u8], outcome: &TxOutcome) {
+        let height = self.pending_block.as_ref().map_or(0, |block| block.height);
+        self.pending_txs.borrow_mut().record(height, tx_hash, outcome);
+    }
+
+    /// Execute a tx. The tx hash is the SHA-256 hash of the raw tx bytes, as
+    /// provided by Tendermint, and the tx length is their size. The sign mode
+    /// depends on how the tx is encoded.
+    ///
+    /// Along with the tx's result, the gas it wants and the gas it consumed
+    /// are returned.
+    pub fn deliver_tx(
+        &self,
+        tx: Tx,
+        sign_mode: &SignMode,
+        tx_hash: &[u8],
+        tx_len: usize,
+    ) -> TxOutcome {
+        // the meter is also reset when the tx is executed, but the tx may be
+        // rejected before that
+        self.system_gas.tx.take();
+
+        let gas_wanted = tx.body.fee.gas_limit;
+        let result = self.execute_block_tx(tx, sign_mode, tx_hash, tx_len);
+
+        TxOutcome {
+            gas_wanted,
+            gas_used: self.system_gas.tx.take(),
+            result,
+        }
+    }
+
+    fn execute_block_tx(
+        &self,
+        tx: Tx,
+        sign_mode: &SignMode,
+        tx_hash: &[u8],
+        tx_len: usize,
+    ) -> Result<Vec<Event>> {
+        self.assert_not_safe_mode()?;
+
+        let block = self.pending_block.as_ref().unwrap();
+
+        let (mut cache, result) = self.execute_tx(
+            self.store.pending_wrap(),
+            block,
+            &tx,
+            sign_mode,
+            &self.signature_cache,
+            tx_hash,
+            tx_len,
+            tx.body.fee.gas_limit,
+        )?;
+
+        // the tx is authenticated and its fee deducted: flush the state changes,
+        // which include those of its messages only if they are successful
+        cache.borrow_mut().flush();
+
+        result.map(|(events, _)| events)
+    }
+
+    /// Execute a tx against the last committed state, as if it was included in
+    /// the next block, without committing it. Signatures aren't verified.
+    pub fn simulate(&self, tx: Tx) -> Result<SimulateResponse> {
+        let store = self.store.wrap();
+        let last_block = BLOCK.load(&Module::Chain.substore(&store))?;
+        let block = BlockInfo {
+            height: last_block.height + 1,
+            ..last_block
+        };
+        let tx_bytes = to_canonical_vec(&tx)?;
+        let tx_hash = sha256(&tx_bytes);
+
+        // the state changes are discarded along with the cache. the committed
+        // store is read-only, so they couldn't be flushed to it anyway
+        let (_, result) = self.execute_tx(
+            store,
+            &block,
+            &tx,
+            &SignMode::Json,
+            &SignatureCache::unverified(),
+            &tx_hash,
+            tx_bytes.len(),
+            u64::MAX,
+        )?;
+        let (events, data) = result?;
+
+        Ok(SimulateResponse {
+            gas_used: self.system_gas.tx.take(),
+            events,
+            data,
+        })
+    }
+
+    /// Authenticate a tx and deduct its fee, in a cache of the given store, then
+    /// execute its messages in a cache on top of it, which is flushed into the
+    /// former only if they are all successful.
+    ///
+    /// If the tx is rejected before its fee is deducted, an error is returned.
+    /// Otherwise, the cache is returned, which the caller may either flush or
+    /// discard, along with the events emitted and the data returned by each
+    /// message, or the error that made them fail. This way, a tx that is
+    /// included in a block pays its fee and uses up its sequence even if its
+    /// messages fail, so that it can't be replayed for free. Either way, the
+    /// taxman contract, if one exists, settles the fee afterwards.
+    ///
+    /// The gas consumed is accounted for in the tx gas meter, which is reset
+    /// beforehand. The messages fail once it exceeds the given limit; contract
+    /// calls are only given the gas the tx has left, so they stop as soon as
+    /// it runs out.
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    fn execute_tx<S>(
+        &self,
+        store: S,
+        block: &BlockInfo,
+        tx: &Tx,
+        sign_mode: &SignMode,
+        sigs: &SignatureCache,
+        tx_hash: &[u8],
+        tx_len: usize,
+        gas_limit: u64,
+    ) -> Result<(Shared<Cached<S>>, Result<(Vec<Event>, Vec<Option<Binary>>)>)>
+    where
+        S: Storage + 'static,
+    {
+        self.system_gas.tx.take();
+        self.system_gas.tx.set_limit(gas_limit);
+
+        // make a cache of the store, for the changes that are kept whether or
+        // not the tx's messages are successful
+        let cache = Cached::new(store);
+
+        auth::check_tx_size(&cache, tx, tx_len)?;
+
+        // authenticate signatures, chain id, sequences, etc., and update the
+        // signers' accounts. contract accounts authenticate the tx themselves
+        let (signer_addrs, mut cache) =
+            auth::authenticate_tx(cache, block, tx, sign_mode, sigs, &self.system_gas)?;
+
+        // reject the tx if it's a retry of one that has already been executed
+        // the idempotency key is attributed to the primary signer
+        if let Some(key) = &tx.body.idempotency_key {
+            let primary_addr = &signer_addrs[0];
+            auth::check_idempotency_key(&cache, block, primary_addr, key)?;
+            auth::record_idempotency_key(&mut cache, block, primary_addr, key, tx_hash)?;
+        }
+
+        // deduct the fee from the payer, or the granter if one is specified,
+        // using the granter's fee allowance if the granter doesn't sign. the
+        // taxman contract, if one exists, decides the coins to charge, which
+        // the allowance must cover
+        let fee_payer_addr = auth::fee_payer(&cache, &tx.body, &signer_addrs)?;
+        let (charged, fee_events, mut cache) =
+            execute::charge_fee(cache, block, tx, &fee_payer_addr, &self.system_gas)?;
+        auth::use_fee_allowance(&mut cache, block, &tx.body, &signer_addrs, &charged)?;
+
+        // wrap the cached store in a `Rc<RefCell<T>>` so that it can be shared
+        // as an owned value, both with the messages' cache and the caller
+        let cache = Shared::new(cache);
+
+        let result = match self.execute_msgs(cache.share(), block, tx, &signer_addrs, gas_limit) {
+            // let the taxman contract, if one exists, refund part of the fee,
+            // e.g. for the gas the tx didn't use. the messages' changes are
+            // only kept if the fee is settled too
+            Ok((events, data, msgs_cache)) => execute::settle_fee(
+                msgs_cache,
+                block,
+                tx,
+                &fee_payer_addr,
+                &charged,
+                self.system_gas.tx.used(),
+                &self.system_gas,
+            )
+            .map(|(settle_events, mut msgs_cache)| {
+                msgs_cache.borrow_mut().flush();
+                let events = fee_events.into_iter().chain(events).chain(settle_events).collect();
+                (events, data)
+            }),
+
+            // the fee is settled even if the messages fail, in a cache of its
+            // own: if settling fails as well, the fee charged is kept in full
+            Err(err) => {
+                if let Ok((_, mut settle_cache)) = execute::settle_fee(
+                    Cached::new(cache.share()),
+                    block,
+                    tx,
+                    &fee_payer_addr,
+                    &charged,
+                    self.system_gas.tx.used(),
+                    &self.system_gas,
+                ) {
+                    settle_cache.flush();
+                }
+                Err(err)
+            },
+        };
 
-        // authenticate signature, chain id, sequence, etc.
-        let sender = auth::authenticate_tx(&cache, self.pending_block.as_ref().unwrap(), &tx)?;
+        Ok((cache, result))
+    }
 
-        // update the sender's account in the store
-        ACCOUNTS.save(&mut cache, &sender.address, &sender.account)?;
+    /// Execute the messages of a tx that has been authenticated and charged
+    /// its fee, in a cache of the given store. Return the events emitted, the
+    /// data returned by each message, and the cache, to be flushed if the tx
+    /// is to be committed.
+    #[allow(clippy::type_complexity)]
+    fn execute_msgs<S>(
+        &self,
+        store: S,
+        block: &BlockInfo,
+        tx: &Tx,
+        signer_addrs: &[Addr],
+        gas_limit: u64,
+    ) -> Result<(Vec<Event>, Vec<Option<Binary>>, Shared<Cached<S>>)>
+    where
+        S: Storage + 'static,
+    {
+        // the fee was charged for the gas limit, so the tx may not consume more,
+        // including what it took to authenticate it and charge the fee
+        self.check_gas_limit(gas_limit)?;
 
         // wrap the cached store in a `Rc<RefCell<T>>` so that it can be shared
         // as an owned value across the execution of multiple messages
-        let mut cache = Shared::new(cache);
+        let cache = Shared::new(Cached::new(store));
 
         let mut events = vec![];
+        let mut data = vec![];
+        for msg in tx.body.msgs.iter().cloned() {
+            // authenticate_tx has checked that the sender is a signer
+            let sender_addr = Addr::unchecked(msg.sender);
 
-        tx
-            .body
-            .msgs
-            .into_iter()
-            .map(|msg| {
-                self.handle_msg(
-                    cache.share(),
-                    self.pending_block.clone().unwrap(),
-                    None,
-                    &sender.address,
-                    msg,
-                )
-            })
-            .try_for_each(|res| -> Result<_> {
-                events.extend(res?);
-                Ok(())
-            })?;
+            let span = msg_span(&msg.msg);
+            let _entered = span.enter();
+            let gas_before = self.system_gas.tx.used();
+            let res =
+                self.handle_msg(cache.share(), block.clone(), None, &sender_addr, msg.msg, false);
+            span.record("gas_used", self.system_gas.tx.used() - gas_before);
 
-        // tx is successful: flush the state changes
-        cache.borrow_mut().flush();
+            let res = res?;
+            self.check_gas_limit(gas_limit)?;
+            events.extend(res.events);
+            data.push(res.data);
+        }
+
+        // let the signers that are contract accounts inspect the outcome, and
+        // possibly revert the tx
+        let (after_tx_events, _) =
+            auth::after_tx(cache.share(), block, tx, signer_addrs, &self.system_gas)?;
+        events.extend(after_tx_events);
+        self.check_gas_limit(gas_limit)?;
+
+        Ok((events, data, cache))
+    }
 
-        Ok(events)
+    /// Return an error if the tx has consumed more gas than the given limit.
+    fn check_gas_limit(&self, gas_limit: u64) -> Result<()> {
+        let gas_used = self.system_gas.tx.used();
+        if gas_used > gas_limit {
+            return Err(Error::tx_out_of_gas(gas_limit, gas_used));
+        }
+        Ok(())
+    }
+
+    /// Invoke the staking contract, if one exists, for validator set updates.
+    /// Record the resulting validator set under the pending block's height.
+    /// Return the events emitted and the validator set updates.
+    pub fn end_block(&mut self) -> Result<(Vec<Event>, Vec<Validator>)> {
+        self.assert_not_safe_mode()?;
+
+        let _entered = self.block_span.enter();
+
+        let block = self.pending_block.clone().unwrap();
+        let (events, updates, mut store) = self.staking_updates(self.store.pending_wrap(), &block)?;
+
+        // only record the validator set at the heights where it changes
+        if !updates.is_empty() {
+            let validators = apply_validator_updates(latest_validator_set(&store)?, &updates);
+            let mut chain = Module::Chain.substore_mut(&mut store);
+            VALIDATOR_SETS.save(&mut chain, block.height, &validators)?;
+        }
+
+        Ok((events, updates))
+    }
+
+    /// Invoke the staking contract, if one exists, for validator set updates.
+    /// Return the events emitted, the updates, and the store.
+    fn staking_updates<S>(
+        &self,
+        store: S,
+        block: &BlockInfo,
+    ) -> Result<(Vec<Event>, Vec<Validator>, S)>
+    where
+        S: Storage + 'static,
+    {
+        let staking_exists = ACCOUNTS
+            .idx
+            .label
+            .may_load(&Module::Auth.substore(&store), STAKING.into())?
+            .is_some();
+        if !staking_exists {
+            return Ok((vec![], vec![], store));
+        }
+
+        let env = Env {
+            block: block.clone(),
+            transaction: None,
+            contract: ContractInfo {
+                address: address::derive_from_label(STAKING)?,
+            },
+        };
+        let msg = to_binary(&staking::SudoMsg::EndBlock {})?;
+
+        let (result, store) =
+            execute::sudo_contract(store, &env, &msg, SystemCall::EndBlock, &self.system_gas)?;
+        let response = match result {
+            ContractResult::Ok(response) => response,
+            ContractResult::Err(err) => return Err(Error::Contract(err)),
+        };
+
+        let updates = match &response.data {
+            Some(data) => normalize_validator_updates(from_binary(data)?)?,
+            None => vec![],
+        };
+
+        Ok((response.events, updates, store))
     }
 
-    fn handle_msg(
+    /// Handle a message on behalf of the sender. Only the genesis messages may
+    /// instantiate contracts with reserved labels.
+    fn handle_msg<S: Storage + 'static>(
         &self,
-        mut store: impl Storage + 'static,
+        mut store: Shared<S>,
         block: BlockInfo,
         transaction: Option<TransactionInfo>,
         sender_addr: &Addr,
         msg: SdkMsg,
-    ) -> Result<Vec<Event>> {
+        genesis: bool,
+    ) -> Result<Response> {
         match msg {
             SdkMsg::StoreCode {
                 wasm_byte_code,
             } => {
                 let event = execute::store_code(&mut store, sender_addr, &wasm_byte_code)?;
-                Ok(vec![event])
+                Ok(Response::new().add_event(event))
             },
             SdkMsg::Instantiate {
                 code_id,
@@ -158,6 +987,10 @@ impl StateMachine {
             } => {
                 let admin_addr = admin.map(|admin| address::resolve_raw(&admin)).transpose()?;
 
+                if !genesis && RESERVED_LABELS.contains(&label.as_str()) {
+                    return Err(Error::reserved_label(label));
+                }
+
                 if !funds.is_empty() {
                     return Err(Error::FundsUnsupported);
                 }
@@ -175,6 +1008,7 @@ impl StateMachine {
                     &serde_json::to_vec(&msg)?,
                     label,
                     admin_addr,
+                    &self.system_gas,
                 )?
                 .into_result();
 
@@ -184,7 +1018,7 @@ impl StateMachine {
                     }
                 }
 
-                result.map(|res| res.events).map_err(Error::Contract)
+                result.map_err(Error::Contract)
             },
             SdkMsg::Execute {
                 contract,
@@ -209,6 +1043,7 @@ impl StateMachine {
                     &env,
                     &info,
                     &serde_json::to_vec(&msg)?,
+                    &self.system_gas,
                 )?
                 .into_result();
 
@@ -218,7 +1053,7 @@ impl StateMachine {
                     }
                 }
 
-                result.map(|res| res.events).map_err(Error::Contract)
+                result.map_err(Error::Contract)
             },
             SdkMsg::Migrate {
                 contract,
@@ -247,72 +1082,1483 @@ impl StateMachine {
                     }
                 }
 
-                result.map(|res| res.events).map_err(Error::Contract)
+                result.map_err(Error::Contract)
+            },
+            SdkMsg::GrantAllowance {
+                grantee,
+                allowance,
+            } => {
+                let event = feegrant::grant_allowance(
+                    &mut store,
+                    &block,
+                    sender_addr,
+                    &grantee,
+                    allowance,
+                )?;
+                Ok(Response::new().add_event(event))
+            },
+            SdkMsg::RevokeAllowance {
+                grantee,
+            } => {
+                let event = feegrant::revoke_allowance(&mut store, sender_addr, &grantee)?;
+                Ok(Response::new().add_event(event))
+            },
+            SdkMsg::GrantAuthz {
+                grantee,
+                authorization,
+                expiration,
+            } => {
+                let event = authz::grant(
+                    &mut store,
+                    &block,
+                    sender_addr,
+                    &grantee,
+                    authorization,
+                    expiration,
+                )?;
+                Ok(Response::new().add_event(event))
+            },
+            SdkMsg::RevokeAuthz {
+                grantee,
+                authorization,
+            } => {
+                let event = authz::revoke(&mut store, sender_addr, &grantee, authorization)?;
+                Ok(Response::new().add_event(event))
+            },
+            SdkMsg::Exec {
+                msgs,
+            } => {
+                let mut events = vec![];
+                for msg in msgs {
+                    // the wrapped message is sent by the granter, as long as
+                    // the granter has authorized the sender of the exec
+                    let granter_addr = address::validate(&msg.sender)?;
+                    if granter_addr != *sender_addr {
+                        authz::authorize(&store, &block, &granter_addr, sender_addr, &msg.msg)?;
+                    }
+                    let res = self.handle_msg(
+                        store.share(),
+                        block.clone(),
+                        transaction.clone(),
+                        &granter_addr,
+                        msg.msg,
+                        genesis,
+                    )?;
+                    events.extend(res.events);
+                }
+                Ok(Response::new().add_events(events))
+            },
+            SdkMsg::RotateKey {
+                new_pubkey,
+                new_pubkey_type,
+            } => {
+                let event = auth::rotate_key(&mut store, sender_addr, new_pubkey, new_pubkey_type)?;
+                Ok(Response::new().add_event(event))
+            },
+            SdkMsg::UpdateParams {
+                params,
+            } => {
+                let event = update_params(&mut store, sender_addr, &params)?;
+                Ok(Response::new().add_event(event))
             },
         }
     }
 
+    /// Return the height and app hash of the last committed block.
+    ///
+    /// The app hash is the one recorded when the block was committed, so that
+    /// Tendermint's handshake on restart compares it against the hash it has
+    /// stored for the same block.
     pub fn info(&self) -> Result<(i64, [u8; HASH_LENGTH])> {
-        let block = BLOCK.may_load(&self.store.wrap())?;
-        let app_hash = self.store.root_hash();
-        Ok((
-            // when initializing a new chain scratch, Tendermint sends an Info
-            // request prior to the InitChain request.
-            // at this point the height hasn't been initialized yet. therefore
-            // we do unwrap_or(0)
-            block.map(|b| b.height as i64).unwrap_or(0),
-            app_hash,
-        ))
-    }
+        // when initializing a new chain scratch, Tendermint sends an Info
+        // request prior to the InitChain request.
+        // at this point the height hasn't been initialized yet. therefore
+        // we default to 0
+        let height = BLOCK
+            .may_load(&Module::Chain.substore(&self.store.wrap()))?
+            .map(|b| b.height)
+            .unwrap_or(0);
 
-    pub fn query(&self, query: SdkQuery) -> Result<Binary> {
-        let store = self.store.wrap();
-        match query {
-            SdkQuery::Info {} => to_binary(&query::info(&store)?),
-            SdkQuery::Account {
-                address,
-            } => to_binary(&query::account(&store, address)?),
-            SdkQuery::Accounts {
-                start_after,
-                limit,
-            } => to_binary(&query::accounts(&store, start_after, limit)?),
-            SdkQuery::Contract {
-                label
-            } => to_binary(&query::contract(&store, label)?),
-            SdkQuery::Contracts {
-                start_after,
-                limit,
-            } => to_binary(&query::contracts(&store, start_after, limit)?),
-            SdkQuery::Code {
-                code_id,
-            } => to_binary(&query::code(&store, code_id)?),
-            SdkQuery::Codes {
-                start_after,
-                limit,
-            } => to_binary(&query::codes(&store, start_after, limit)?),
-            SdkQuery::WasmRaw {
-                contract,
-                key,
-            } => to_binary(&query::wasm_raw(store, &contract, &key)?),
-            SdkQuery::WasmSmart {
-                contract,
-                msg,
-            } => to_binary(&query::wasm_smart(store, &contract, &serde_json::to_vec(&msg)?)?),
-        }
-        .map_err(Error::from)
+        // if the node stopped between committing the block and recording it,
+        // the record is of the block before; the hash is then that of the
+        // committed state, which is the same as long as nothing else has been
+        // committed since
+        let app_hash = match self.store.last_commit()? {
+            Some(commit) if commit.height == height => commit.app_hash,
+            _ => self.store.root_hash(),
+        };
+
+        Ok((height as i64, app_hash))
     }
 
-    pub fn commit(&mut self) -> Result<(i64, [u8; HASH_LENGTH])> {
-        // save the current pending block as the last committed block
-        BLOCK.save(&mut self.store.pending_wrap(), self.pending_block.as_ref().unwrap())?;
+    /// Revert the state to before the last committed block, e.g. to recover
+    /// from an app hash mismatch caused by a bad upgrade. Return the height
+    /// and app hash that are now the last committed ones.
+    ///
+    /// Only the last committed block can be reverted.
+    pub fn rollback(&mut self) -> Result<(i64, [u8; HASH_LENGTH])> {
+        if !self.store.rollback()? {
+            return Err(Error::NothingToRollback);
+        }
 
-        // clear the pending block
         self.pending_block = None;
 
-        // commit pending ops to the underlying store
-        self.store.commit()?;
+        // the store is restored exactly as it was before the block, so its root
+        // must be the app hash recorded for the block before
+        let (height, app_hash) = self.info()?;
+        let root_hash = self.store.root_hash();
+        if root_hash != app_hash {
+            return Err(Error::rollback_hash_mismatch(height as u64, &app_hash, &root_hash));
+        }
+
+        Ok((height, app_hash))
+    }
+
+    /// Export the last committed state as a genesis state.
+    pub fn export(&self) -> Result<GenesisState> {
+        export::export(&self.store.wrap())
+    }
+
+    /// Replace all key-value pairs in a contract's store with the given ones,
+    /// and commit the change as part of the last committed block. Return the
+    /// height and the new app hash.
+    ///
+    /// As the app hash of the last committed block changes, CometBFT refuses to
+    /// start with the resulting state. This is meant for devnets, or for states
+    /// that are then forked into a new genesis.
+    pub fn import_contract_state(
+        &mut self,
+        contract: &str,
+        state: &[(Binary, Binary)],
+    ) -> Result<(i64, [u8; HASH_LENGTH])> {
+        self.assert_not_safe_mode()?;
+
+        let contract_addr = address::validate(contract)?;
+        import::import_contract_state(&mut self.store.pending_wrap(), &contract_addr, state)?;
+
+        let (height, _) = self.info()?;
+        self.store.commit()?;
+        self.store.save_last_commit(height as u64)?;
+        // rolling back would only revert the import, not the block
+        self.store.prune()?;
+
+        self.info()
+    }
+
+    /// Render the store's metrics, and the gas usage of system calls, in
+    /// Prometheus text exposition format.
+    pub fn metrics(&self) -> String {
+        self.store.render_metrics()
+            + &self.system_gas.meter.render()
+            + &self.system_gas.contracts.render()
+    }
+
+    /// Answer a query. Most queries only read the committed state, and are
+    /// answered the same way as by the query workers reading a snapshot of it;
+    /// see `query::handle`. The rest need the state machine itself.
+    pub fn query(&self, query: SdkQuery) -> Result<Binary> {
+        match query {
+            SdkQuery::Simulate {
+                tx,
+            } => to_binary(&self.simulate(tx)?),
+            SdkQuery::Tx {
+                hash,
+            } => to_binary(&tx_index::tx(&self.store, hash)?),
+            SdkQuery::TxsByEvent {
+                event,
+                value,
+                start_after,
+                limit,
+            } => to_binary(&tx_index::txs_by_event(&self.store, event, value, start_after, limit)?),
+            SdkQuery::ContractGasUsage {
+                limit,
+            } => to_binary(&self.system_gas.contracts.top(limit)),
+            query => {
+                let modules = self.system_gas.modules.as_deref();
+                return query::handle(self.store.wrap(), query, &self.system_gas.wasm, modules);
+            },
+        }
+        .map_err(Error::from)
+    }
+
+    /// Start keeping an in-memory snapshot of the committed state, which query
+    /// workers on other threads can read while blocks are being executed.
+    pub fn enable_snapshot(&self) {
+        self.store.enable_snapshot();
+    }
+
+    /// The snapshot of the committed state, if enabled. It reflects the last
+    /// block committed at the time it's taken.
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        self.store.snapshot()
+    }
+
+    /// The options of the wasm instances that contracts are run in.
+    pub fn wasm_config(&self) -> WasmConfig {
+        self.system_gas.wasm
+    }
+
+    /// The cache of compiled contract modules, if set.
+    pub fn module_cache(&self) -> Option<Arc<ModuleCache>> {
+        self.system_gas.modules.clone()
+    }
+
+    /// The block that votes are being cast for, as seen by the vote extension
+    /// handler. It has the time of the last committed block, as the time of
+    /// the block being voted for isn't known to the state machine yet.
+    fn voted_block(&self, height: u64) -> Result<BlockInfo> {
+        let last_block = BLOCK.load(&Module::Chain.substore(&self.store.wrap()))?;
+        Ok(BlockInfo {
+            height,
+            ..last_block
+        })
+    }
+
+    /// Return the extension to attach to this node's validator's vote for the
+    /// block at the given height. Empty if no handler is set, or if the handler
+    /// doesn't extend the vote.
+    ///
+    /// The handler is invoked against the last committed state, and the state
+    /// changes it makes are discarded.
+    pub fn extend_vote(&self, height: u64) -> Result<Vec<u8>> {
+        let Some(handler) = &self.vote_extension_handler else {
+            return Ok(vec![]);
+        };
+
+        let block = self.voted_block(height)?;
+
+        match handler {
+            VoteExtensionHandler::Contract(label) => {
+                let (response, _) = vote_extensions::sudo_contract(
+                    Cached::new(self.store.wrap()),
+                    label,
+                    &block,
+                    &VoteExtensionsMsg::ExtendVote {},
+                    &self.system_gas,
+                )?;
+                Ok(response.data.map(|data| data.to_vec()).unwrap_or_default())
+            },
+            VoteExtensionHandler::Native(handler) => {
+                handler.extend_vote(&self.store.wrap(), &block)
+            },
+        }
+    }
+
+    /// Return error if a validator's extension of its vote for the block at the
+    /// given height is invalid. If no handler is set, only empty extensions are
+    /// valid.
+    pub fn verify_vote_extension(
+        &self,
+        height: u64,
+        validator: &[u8],
+        extension: &[u8],
+    ) -> Result<()> {
+        let Some(handler) = &self.vote_extension_handler else {
+            if !extension.is_empty() {
+                return Err(Error::invalid_vote_extensions("vote extensions are not enabled"));
+            }
+            return Ok(());
+        };
+
+        let block = self.voted_block(height)?;
+
+        match handler {
+            VoteExtensionHandler::Contract(label) => {
+                vote_extensions::sudo_contract(
+                    Cached::new(self.store.wrap()),
+                    label,
+                    &block,
+                    &VoteExtensionsMsg::VerifyVoteExtension {
+                        validator: validator.to_vec().into(),
+                        extension: extension.to_vec().into(),
+                    },
+                    &self.system_gas,
+                )?;
+                Ok(())
+            },
+            VoteExtensionHandler::Native(handler) => {
+                handler.verify_vote_extension(&self.store.wrap(), &block, validator, extension)
+            },
+        }
+    }
+
+    /// Build the txs of a block this node proposes: the extended votes for the
+    /// last block, if a vote extension handler is set, followed by the given
+    /// mempool txs, filtered and ordered as described in the `proposal` module,
+    /// as many as fit in `max_tx_bytes`.
+    pub fn prepare_proposal(
+        &self,
+        votes: Vec<ExtendedVote>,
+        txs: Vec<Vec<u8>>,
+        max_tx_bytes: u64,
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut proposal = vec![];
+        if self.vote_extension_handler.is_some() && !votes.is_empty() {
+            proposal.push(encode_votes_tx(&votes)?);
+        }
+
+        let mut candidates = txs
+            .into_iter()
+            .filter_map(|bytes| {
+                let (tx, sign_mode) = auth::decode_tx(&bytes).ok()?;
+                Some(ProposalTx {
+                    priority: self.priority(&tx),
+                    bytes,
+                    tx,
+                    sign_mode,
+                })
+            })
+            .collect::<Vec<_>>();
+        proposal::order_by_priority(&mut candidates);
+
+        // check the txs in order, against a state that includes the changes
+        // made by the checks of the txs before them
+        let state = Shared::new(Cached::new(self.store.pending_wrap()));
+        let mut txs_per_account = HashMap::new();
+        let mut candidates = candidates
+            .into_iter()
+            .filter(|candidate| {
+                let Some(signer) = candidate.tx.body.signers.first() else {
+                    return false;
+                };
+                let count = txs_per_account.entry(signer.address.clone()).or_insert(0);
+                if self.max_txs_per_account > 0 && *count >= self.max_txs_per_account {
+                    return false;
+                }
+
+                let cache = Cached::new(state.share());
+                let tx = &candidate.tx;
+                let tx_len = candidate.bytes.len();
+                let sigs = &self.signature_cache;
+                let Ok((_, mut cache)) =
+                    self.run_checks(cache, tx, &candidate.sign_mode, tx_len, sigs)
+                else {
+                    return false;
+                };
+                cache.borrow_mut().flush();
+
+                *count += 1;
+                true
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(handler) = &self.proposal_handler {
+            let store = self.store.wrap();
+            let last_block = BLOCK.load(&Module::Chain.substore(&store))?;
+            let block = BlockInfo {
+                height: last_block.height + 1,
+                ..last_block
+            };
+            candidates = handler.prepare(&store, &block, candidates);
+        }
+
+        let mut size = proposal.iter().map(|tx| tx.len() as u64).sum::<u64>();
+        for candidate in candidates {
+            size += candidate.bytes.len() as u64;
+            if size > max_tx_bytes {
+                break;
+            }
+            proposal.push(candidate.bytes);
+        }
+
+        Ok(proposal)
+    }
+
+    /// Return error if a block proposed by another validator must be rejected,
+    /// i.e. if the extended votes it includes are invalid or not signed by the
+    /// validators, or don't carry more than two thirds of the voting power, or
+    /// if it leaves them out above the height from which votes are extended.
+    /// Invalid txs in the proposal don't cause it to be rejected; they fail
+    /// individually when it's executed.
+    pub fn process_proposal(&self, height: u64, txs: &[Vec<u8>]) -> Result<()> {
+        let Some(votes) = txs.first().and_then(|tx| decode_votes_tx(tx)) else {
+            // once consensus extends votes, the proposer can't leave out the
+            // extensions of the last block
+            let enable_height = self.vote_extensions_enable_height;
+            if self.vote_extension_handler.is_some() && enable_height > 0 && height > enable_height
+            {
+                return Err(Error::invalid_vote_extensions(
+                    "proposal doesn't start with the extended votes for the last block",
+                ));
+            }
+            return Ok(());
+        };
+        let votes = votes?;
+
+        if self.vote_extension_handler.is_none() {
+            return Err(Error::invalid_vote_extensions("vote extensions are not enabled"));
+        }
+
+        // the votes are for the block before the proposed one, so they are
+        // checked against the validator set that signed it
+        let voted_height = height.saturating_sub(1);
+        let store = self.store.wrap();
+        let chain_id = BLOCK.load(&Module::Chain.substore(&store))?.chain_id;
+        let validators = active_validator_set(&store, voted_height)?;
+        check_votes(&validators, &chain_id, voted_height, &votes)?;
+
+        for vote in &votes {
+            if !vote.extension.is_empty() {
+                self.verify_vote_extension(voted_height, &vote.validator, &vote.extension)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hand the extended votes included in a block to the handler. Fails if no
+    /// handler is set. State changes are only kept if the handler succeeds.
+    fn process_vote_extensions(&self, votes: Vec<ExtendedVote>) -> Result<Vec<Event>> {
+        let Some(handler) = &self.vote_extension_handler else {
+            return Err(Error::invalid_vote_extensions("vote extensions are not enabled"));
+        };
+
+        let block = self.pending_block.clone().unwrap();
+
+        match handler {
+            VoteExtensionHandler::Contract(label) => {
+                // the votes are for the previous block, so they were cast by
+                // the validator set that signed it
+                let validators =
+                    active_validator_set(&self.store.pending_wrap(), block.height - 1)?;
+                let total_power = validators.iter().map(|val| val.power).sum();
+                let (response, _) = vote_extensions::sudo_contract(
+                    self.store.pending_wrap(),
+                    label,
+                    &block,
+                    &VoteExtensionsMsg::ProcessVoteExtensions {
+                        votes,
+                        total_power,
+                    },
+                    &self.system_gas,
+                )?;
+                Ok(response.events)
+            },
+            VoteExtensionHandler::Native(handler) => {
+                let mut cache = Cached::new(self.store.pending_wrap());
+                let events = handler.process_vote_extensions(&mut cache, &block, &votes)?;
+                cache.flush();
+                Ok(events)
+            },
+        }
+    }
+
+    /// Execute a block as a whole, as CometBFT 0.38 provides it in the
+    /// FinalizeBlock ABCI method, and commit it. The block has the same chain
+    /// id as the last one.
+    ///
+    /// Each tx is decoded from its raw bytes and executed in order. A tx that
+    /// fails doesn't fail the block; its error is reported in the outcome. If
+    /// the first tx carries the extended votes for the last block, it is handed
+    /// to the vote extension handler instead, and its outcome reported likewise.
+    ///
+    /// The block is committed right away, rather than in the Commit ABCI
+    /// method, because FinalizeBlock must return the app hash, which is only
+    /// known once the changes are applied to the Merk tree. If the node crashes
+    /// before Commit, CometBFT finds the app one block ahead on restart, and
+    /// replays the responses it saved instead of the block.
+    pub fn finalize_block(
+        &mut self,
+        height: u64,
+        time: Timestamp,
+        txs: &[Vec<u8>],
+    ) -> Result<FinalizeBlockOutcome> {
+        let last_block = BLOCK.load(&Module::Chain.substore(&self.store.pending_wrap()))?;
+        let block = BlockInfo {
+            height,
+            time,
+            chain_id: last_block.chain_id,
+        };
+
+        // verify the txs' signatures in a batch, in parallel if enabled, before
+        // they are delivered one by one, which then finds them in the cache
+        let decoded: Vec<_> =
+            txs.iter().filter_map(|tx_bytes| auth::decode_tx(tx_bytes).ok()).collect();
+        self.verify_signatures(&decoded);
+
+        let mut events = self.begin_block(block)?;
+
+        let tx_results = txs
+            .iter()
+            .enumerate()
+            .map(|(index, tx_bytes)| {
+                if index == 0 {
+                    if let Some(votes) = decode_votes_tx(tx_bytes) {
+                        self.system_gas.tx.take();
+                        let result = votes.and_then(|votes| self.process_vote_extensions(votes));
+                        let outcome = TxOutcome {
+                            gas_wanted: 0,
+                            gas_used: self.system_gas.tx.take(),
+                            result,
+                        };
+                        self.record_tx(&sha256(tx_bytes), &outcome);
+                        return outcome;
+                    }
+                }
+                self.deliver_raw_tx(tx_bytes)
+            })
+            .collect();
+
+        let (end_block_events, validator_updates) = self.end_block()?;
+        events.extend(end_block_events);
+
+        let (_, app_hash) = self.commit()?;
+
+        Ok(FinalizeBlockOutcome {
+            events,
+            tx_results,
+            validator_updates,
+            app_hash,
+        })
+    }
+
+    pub fn commit(&mut self) -> Result<(i64, [u8; HASH_LENGTH])> {
+        self.assert_not_safe_mode()?;
+
+        // save the current pending block as the last committed block, and
+        // clear it
+        let block = self.pending_block.take().unwrap();
+        let mut store = self.store.pending_wrap();
+        let mut chain = Module::Chain.substore_mut(&mut store);
+        BLOCK.save(&mut chain, &block)?;
+
+        // txs remaining in the mempool are rechecked against the new state
+        self.check_state = None;
+
+        // the block span is closed once the block is committed
+        let span = std::mem::replace(&mut self.block_span, Span::none());
+        let _entered = span.enter();
+        let pending_txs = self.pending_txs.get_mut();
+        span.record("txs", pending_txs.len());
+        span.record("gas_used", pending_txs.gas_used());
+
+        // index the block's txs before the block is committed, so that if the
+        // node crashes in between, they're indexed again when the block is
+        // executed again
+        pending_txs.flush(&self.store)?;
+
+        // commit pending ops to the underlying store, and record the height
+        // and app hash of the block so they can be reported after a restart
+        self.store.commit()?;
+        self.store.save_last_commit(block.height)?;
+
+        // forget signatures verified before the block that was just committed
+        self.signature_cache.rotate();
 
         // return the block height and app hash that was just committed
         self.info()
     }
 }
+
+/// A span covering the execution of a message, with its type and the address
+/// of the contract it calls, if any. The gas used is recorded once the message
+/// has been executed.
+fn msg_span(msg: &SdkMsg) -> Span {
+    let contract_addr = match msg {
+        SdkMsg::Instantiate {
+            label,
+            ..
+        } => address::derive_from_label(label).ok(),
+        SdkMsg::Execute {
+            contract,
+            ..
+        }
+        | SdkMsg::Migrate {
+            contract,
+            ..
+        } => address::resolve_raw(contract).ok(),
+        _ => None,
+    };
+
+    info_span!(
+        "msg",
+        msg_type = msg.kind(),
+        contract = contract_addr.as_ref().map(field::display),
+        gas_used = field::Empty,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coins, to_vec};
+    use cw_sdk::{
+        canonical::to_canonical_vec, hash::sha256, proto::vote_extension_sign_bytes, Account,
+        ErrorCode, GenesisAccount, Params, PubKeyType, SignerInfo, TxBody, TxMsg, TxResponse,
+        TxSignature,
+    };
+    use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+    use super::*;
+    use crate::{
+        state::CODES,
+        testing::{
+            address, block, chain_with_contracts, pubkey, signing_key, temp_path, wat_contract,
+            CHAIN_ID,
+        },
+    };
+
+    /// Sign a tx body with each of the given keys, which must be listed in the
+    /// same order as the signers in the body.
+    fn sign_body(body: TxBody, sks: &[&SigningKey]) -> Tx {
+        let signatures = body
+            .signers
+            .iter()
+            .zip(sks)
+            .map(|(signer, sk)| {
+                let sign_bytes = body.sign_doc(signer).unwrap().to_sign_bytes().unwrap();
+                let signature: Signature = sk.sign(&sign_bytes);
+                TxSignature {
+                    pubkey: Some(pubkey(sk).into()),
+                    pubkey_type: PubKeyType::Secp256k1,
+                    signature: signature.to_vec().into(),
+                    multisig: None,
+                    legacy_amino_json: false,
+                }
+            })
+            .collect();
+        Tx {
+            body,
+            signatures,
+        }
+    }
+
+    fn sign_tx(
+        sk: &SigningKey,
+        account_number: u64,
+        sequence: u64,
+        idempotency_key: Option<&str>,
+    ) -> Tx {
+        let body = TxBody {
+            idempotency_key: idempotency_key.map(Into::into),
+            ..TxBody::single(address(sk), CHAIN_ID, account_number, sequence, vec![])
+        };
+        sign_body(body, &[sk])
+    }
+
+    /// Deliver a tx, encoded in canonical JSON.
+    fn deliver_tx(sm: &StateMachine, tx: Tx) -> Result<Vec<Event>> {
+        let tx_bytes = to_canonical_vec(&tx).unwrap();
+        sm.deliver_tx(tx, &SignMode::Json, &sha256(&tx_bytes), tx_bytes.len()).result
+    }
+
+    /// Check a tx, encoded in canonical JSON.
+    fn check_tx(sm: &mut StateMachine, tx: &Tx, check_type: CheckTxType) -> Result<CheckTxOutcome> {
+        let tx_len = to_canonical_vec(tx).unwrap().len();
+        sm.check_tx(tx, &SignMode::Json, tx_len, check_type)
+    }
+
+    /// Run a chain through a fixed sequence of blocks, and record every output
+    /// that reaches consensus, serialized into bytes.
+    ///
+    /// Genesis accounts and validators are provided in the given orders, which
+    /// must not affect the outputs.
+    fn replay(accounts: Vec<GenesisAccount>, validators: Vec<Validator>) -> Vec<Vec<u8>> {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        let mut transcript = vec![];
+
+        let gen_state = GenesisState {
+            accounts,
+            ..Default::default()
+        };
+        let (app_hash, _) = sm.init_chain(CHAIN_ID.into(), 1, validators, gen_state).unwrap();
+        transcript.push(app_hash.to_vec());
+
+        let keys = [signing_key(1), signing_key(2), signing_key(3), signing_key(4)];
+        let blocks = vec![
+            vec![sign_tx(&keys[0], 0, 1, None), sign_tx(&keys[1], 1, 1, Some("key"))],
+            // a retry with the same idempotency key, and a bad sequence
+            vec![sign_tx(&keys[1], 1, 2, Some("key")), sign_tx(&keys[2], 2, 5, None)],
+            // a bad account number, and a new account
+            vec![sign_tx(&keys[2], 0, 1, None), sign_tx(&keys[3], 3, 1, None)],
+        ];
+
+        for (height, txs) in (1..).zip(blocks) {
+            transcript.push(to_vec(&sm.begin_block(block(height)).unwrap()).unwrap());
+
+            for tx in txs {
+                let output = match deliver_tx(&sm, tx) {
+                    Ok(events) => to_vec(&events).unwrap(),
+                    Err(err) => err.to_string().into_bytes(),
+                };
+                transcript.push(output);
+            }
+
+            transcript.push(to_vec(&sm.end_block().unwrap()).unwrap());
+
+            let (height, app_hash) = sm.commit().unwrap();
+            transcript.push(height.to_be_bytes().to_vec());
+            transcript.push(app_hash.to_vec());
+        }
+
+        transcript.push(to_vec(&sm.export().unwrap()).unwrap());
+
+        transcript
+    }
+
+    #[test]
+    fn replaying_is_deterministic() {
+        let accounts = (1..=3)
+            .map(|seed| {
+                let sk = signing_key(seed);
+                GenesisAccount {
+                    address: address(&sk).into(),
+                    account: Account::Base {
+                        pubkey: pubkey(&sk).into(),
+                        pubkey_type: PubKeyType::Secp256k1,
+                        account_number: seed as u64 - 1,
+                        sequence: 0,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+        let validators = (1..=3)
+            .map(|seed| Validator {
+                pubkey: vec![seed; 32].into(),
+                power: seed as u64,
+            })
+            .collect::<Vec<_>>();
+
+        let transcript = replay(accounts.clone(), validators.clone());
+
+        // running the same chain again should produce byte-identical outputs
+        assert_eq!(replay(accounts.clone(), validators.clone()), transcript);
+
+        // so should providing the genesis accounts and validators in a
+        // different order
+        let mut accounts = accounts;
+        let mut validators = validators;
+        accounts.reverse();
+        validators.reverse();
+        assert_eq!(replay(accounts, validators), transcript);
+    }
+
+    #[test]
+    fn signing_with_multiple_signers() {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+
+        let keys = [signing_key(1), signing_key(2), signing_key(3)];
+        let accounts = keys[..2]
+            .iter()
+            .zip(0..)
+            .map(|(sk, account_number)| GenesisAccount {
+                address: address(sk).into(),
+                account: Account::Base {
+                    pubkey: pubkey(sk).into(),
+                    pubkey_type: PubKeyType::Secp256k1,
+                    account_number,
+                    sequence: 0,
+                },
+            })
+            .collect();
+        let gen_state = GenesisState {
+            accounts,
+            ..Default::default()
+        };
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], gen_state).unwrap();
+
+        sm.begin_block(block(1)).unwrap();
+
+        let signer = |sk: &SigningKey, account_number| SignerInfo {
+            address: address(sk).into(),
+            account_number,
+            sequence: 1,
+        };
+        let body = TxBody {
+            chain_id: CHAIN_ID.into(),
+            signers: vec![signer(&keys[0], 0), signer(&keys[1], 1)],
+            msgs: vec![],
+            fee: Default::default(),
+            timeout_height: None,
+            idempotency_key: None,
+        };
+
+        // every signer must provide a signature
+        let tx = sign_body(body.clone(), &[&keys[0]]);
+        let err = deliver_tx(&sm, tx).unwrap_err();
+        assert!(matches!(err, Error::SignatureCountMismatch { expect: 2, found: 1 }));
+
+        // every message must be sent by one of the signers
+        let tx = sign_body(
+            TxBody {
+                msgs: vec![TxMsg {
+                    sender: address(&keys[2]).into(),
+                    msg: SdkMsg::Execute {
+                        contract: "bank".into(),
+                        msg: serde_json::json!({}),
+                        funds: vec![],
+                    },
+                }],
+                ..body.clone()
+            },
+            &[&keys[0], &keys[1]],
+        );
+        let err = deliver_tx(&sm, tx).unwrap_err();
+        assert!(matches!(err, Error::MsgSenderNotSigner { .. }));
+
+        // the failed txs didn't change any state, and the sequences of both
+        // signers are incremented
+        let tx = sign_body(body, &[&keys[0], &keys[1]]);
+        deliver_tx(&sm, tx).unwrap();
+
+        let store = sm.store.pending_wrap();
+        for sk in &keys[..2] {
+            let account = ACCOUNTS.load(&Module::Auth.substore(&store), &address(sk)).unwrap();
+            assert!(matches!(account, Account::Base { sequence: 1, .. }));
+        }
+    }
+
+    #[test]
+    fn executing_gen_txs() {
+        let sk = signing_key(1);
+        let accounts = vec![GenesisAccount {
+            address: address(&sk).into(),
+            account: Account::Base {
+                pubkey: pubkey(&sk).into(),
+                pubkey_type: PubKeyType::Secp256k1,
+                account_number: 0,
+                sequence: 0,
+            },
+        }];
+
+        // gen txs are authenticated like any other tx, including their
+        // signatures, and a failed one fails the entire genesis
+        let gen_state = GenesisState {
+            accounts: accounts.clone(),
+            gen_txs: vec![sign_tx(&sk, 0, 2, None)],
+            ..Default::default()
+        };
+        let sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        let err = sm.init_chain(CHAIN_ID.into(), 1, vec![], gen_state).unwrap_err();
+        assert!(matches!(err, Error::SequenceMismatch { .. }));
+
+        // a gen tx whose signature was made over another body is rejected, so
+        // a tampered genesis can't act on behalf of an account
+        let mut tx = sign_tx(&sk, 0, 1, None);
+        tx.signatures = sign_tx(&sk, 0, 2, None).signatures;
+        let gen_state = GenesisState {
+            accounts: accounts.clone(),
+            gen_txs: vec![tx],
+            ..Default::default()
+        };
+        let sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        let err = sm.init_chain(CHAIN_ID.into(), 1, vec![], gen_state).unwrap_err();
+        assert!(matches!(err, Error::Ecdsa(_)));
+
+        let gen_state = GenesisState {
+            accounts,
+            gen_txs: vec![sign_tx(&sk, 0, 1, None)],
+            ..Default::default()
+        };
+        let sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        let (_, validators) = sm.init_chain(CHAIN_ID.into(), 1, vec![], gen_state).unwrap();
+
+        // without a staking contract, Tendermint's validator set is kept
+        assert!(validators.is_empty());
+
+        let store = sm.store.pending_wrap();
+        let account = ACCOUNTS.load(&Module::Auth.substore(&store), &address(&sk)).unwrap();
+        assert!(matches!(account, Account::Base { sequence: 1, .. }));
+    }
+
+    #[test]
+    fn simulating_tx() {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], GenesisState::default()).unwrap();
+        sm.begin_block(block(1)).unwrap();
+        sm.end_block().unwrap();
+        sm.commit().unwrap();
+
+        // the tx doesn't need to be signed, as long as the pubkey is provided
+        let sk = signing_key(1);
+        let mut tx = sign_tx(&sk, 0, 1, None);
+        tx.signatures[0].signature = Binary::default();
+
+        let res = sm.simulate(tx.clone()).unwrap();
+        assert!(res.events.is_empty());
+        assert!(res.data.is_empty());
+
+        // the account creation wasn't committed, so the tx can be simulated
+        // again, and other checks still apply
+        sm.simulate(tx).unwrap();
+        let err = sm.simulate(sign_tx(&sk, 0, 2, None)).unwrap_err();
+        assert!(matches!(err, Error::SequenceMismatch { .. }));
+        let account = ACCOUNTS.may_load(&Module::Auth.substore(&sm.store.wrap()), &address(&sk));
+        assert_eq!(account.unwrap(), None);
+    }
+
+    #[test]
+    fn keeping_sequence_of_failed_tx() {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], GenesisState::default()).unwrap();
+        sm.begin_block(block(1)).unwrap();
+
+        // a reserved label can't be claimed once the chain is live
+        let sk = signing_key(1);
+        let msg = SdkMsg::Instantiate {
+            code_id: 1,
+            msg: serde_json::json!({}),
+            funds: vec![],
+            label: FEE_COLLECTOR.into(),
+            admin: None,
+        };
+        let body = TxBody::single(address(&sk), CHAIN_ID, 0, 1, vec![msg]);
+        let err = deliver_tx(&sm, sign_body(body, &[&sk])).unwrap_err();
+        assert!(matches!(err, Error::ReservedLabel { .. }));
+
+        // the tx failed, but it was authenticated, so the account is created
+        // with its sequence used up, and the tx can't be replayed
+        let store = sm.store.pending_wrap();
+        let account = ACCOUNTS.load(&Module::Auth.substore(&store), &address(&sk)).unwrap();
+        assert!(matches!(account, Account::Base { sequence: 1, .. }));
+        let err = deliver_tx(&sm, sign_tx(&sk, 0, 1, None)).unwrap_err();
+        assert!(matches!(err, Error::SequenceMismatch { .. }));
+    }
+
+    /// A contract account whose `before_tx` and `after_tx` hooks accept or
+    /// reject every tx, as given.
+    fn hooks_wasm(before_tx_ok: bool, after_tx_ok: bool) -> Vec<u8> {
+        // the addresses of the regions of the ok and error responses
+        let region = |ok: bool| if ok { 16 } else { 32 };
+        let sudo = format!(
+            r#";; the message is either `{{"before_tx":...}}` or `{{"after_tx":...}}`
+            (func (export "sudo") (param $env i32) (param $msg i32) (result i32)
+                (if (result i32)
+                    (i32.eq (i32.load8_u offset=2 (i32.load (local.get $msg))) (i32.const 98))
+                    (then (i32.const {}))
+                    (else (i32.const {}))))"#,
+            region(before_tx_ok),
+            region(after_tx_ok),
+        );
+        wat_contract(&[r#"{"error":"rejected by account"}"#], &sudo)
+    }
+
+    /// A tx storing a code, sent by the contract account of the given label.
+    /// The signature is opaque to the state machine.
+    fn contract_account_tx(label: &str) -> Tx {
+        let sender = address::derive_from_label(label).unwrap();
+        let msg = SdkMsg::StoreCode {
+            wasm_byte_code: hooks_wasm(true, true).into(),
+        };
+        Tx {
+            body: TxBody::single(sender, CHAIN_ID, 0, 1, vec![msg]),
+            signatures: vec![TxSignature {
+                pubkey: None,
+                pubkey_type: PubKeyType::Secp256k1,
+                signature: b"signed".to_vec().into(),
+                multisig: None,
+                legacy_amino_json: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn sudoing_tx_hooks() {
+        // deploy three contract accounts: one that accepts txs, one that
+        // rejects them before they're executed, and one that reverts them after
+        let sm = chain_with_contracts(vec![
+            ("accept", hooks_wasm(true, true)),
+            ("reject", hooks_wasm(false, true)),
+            ("revert", hooks_wasm(true, false)),
+        ]);
+
+        // each account sends a tx storing a code, which is the fourth if the
+        // tx is committed
+        let send_tx = |sm: &StateMachine, label: &str| deliver_tx(sm, contract_account_tx(label));
+        let code_exists = |sm: &StateMachine| {
+            let store = sm.store.pending_wrap();
+            CODES.has(&Module::Wasm.substore(&store), 4)
+        };
+
+        let err = send_tx(&sm, "reject").unwrap_err();
+        assert!(matches!(err, Error::TxRejectedByAccount { .. }));
+        assert!(!code_exists(&sm));
+
+        let err = send_tx(&sm, "revert").unwrap_err();
+        assert!(matches!(err, Error::TxRejectedByAccount { .. }));
+        assert!(!code_exists(&sm));
+
+        send_tx(&sm, "accept").unwrap();
+        assert!(code_exists(&sm));
+    }
+
+    #[test]
+    fn checking_contract_account_tx() {
+        let mut sm = chain_with_contracts(vec![
+            ("accept", hooks_wasm(true, true)),
+            ("reject", hooks_wasm(false, true)),
+        ]);
+
+        // contract accounts authenticate their txs in CheckTx too, so a tx
+        // that the account would reject can't take up space in a block
+        let tx = contract_account_tx("reject");
+        let err = check_tx(&mut sm, &tx, CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::TxRejectedByAccount { .. }));
+
+        let tx = contract_account_tx("accept");
+        check_tx(&mut sm, &tx, CheckTxType::New).unwrap();
+    }
+
+    /// A taxman contract whose `charge_fee` and `settle_fee` hooks succeed or
+    /// fail, as given. Neither changes the fee.
+    fn taxman_wasm(charge_ok: bool, settle_ok: bool) -> Vec<u8> {
+        // the addresses of the regions of the ok and error responses
+        let region = |ok: bool| if ok { 16 } else { 32 };
+        let sudo = format!(
+            r#";; the message is either `{{"charge_fee":...}}` or `{{"settle_fee":...}}`
+            (func (export "sudo") (param $env i32) (param $msg i32) (result i32)
+                (if (result i32)
+                    (i32.eq (i32.load8_u offset=2 (i32.load (local.get $msg))) (i32.const 99))
+                    (then (i32.const {}))
+                    (else (i32.const {}))))"#,
+            region(charge_ok),
+            region(settle_ok),
+        );
+        wat_contract(&[r#"{"error":"rejected by taxman"}"#], &sudo)
+    }
+
+    #[test]
+    fn sudoing_taxman() {
+        let sk = signing_key(1);
+        let store_code_tx = || {
+            let msg = SdkMsg::StoreCode {
+                wasm_byte_code: taxman_wasm(true, true).into(),
+            };
+            sign_body(TxBody::single(address(&sk), CHAIN_ID, 0, 1, vec![msg]), &[&sk])
+        };
+
+        // CheckTx dry-runs the fee charge, so a tx that the taxman rejects
+        // can't take up space in a block
+        let mut sm = chain_with_contracts(vec![("taxman", taxman_wasm(false, true))]);
+        let err = check_tx(&mut sm, &store_code_tx(), CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::FeeDeductionFailed { .. }));
+
+        // the fee is settled after the messages are executed, which are
+        // reverted if settling fails, while the tx still pays its fee and uses
+        // up its sequence
+        let mut sm = chain_with_contracts(vec![("taxman", taxman_wasm(true, false))]);
+        check_tx(&mut sm, &store_code_tx(), CheckTxType::New).unwrap();
+        let err = deliver_tx(&sm, store_code_tx()).unwrap_err();
+        assert!(matches!(err, Error::Contract(_)));
+
+        let store = sm.store.pending_wrap();
+        assert!(!CODES.has(&Module::Wasm.substore(&store), 2));
+        let account = ACCOUNTS.load(&Module::Auth.substore(&store), &address(&sk)).unwrap();
+        assert!(matches!(account, Account::Base { sequence: 1, .. }));
+    }
+
+    /// A contract whose execute entry point never returns.
+    fn looping_wasm() -> Vec<u8> {
+        let execute = r#"(func (export "execute") (param i32 i32 i32) (result i32)
+            (loop $spin (br $spin))
+            (i32.const 16))"#;
+        wat_contract(&[], execute)
+    }
+
+    #[test]
+    fn running_out_of_gas() {
+        let sm = chain_with_contracts(vec![("spin", looping_wasm())]);
+
+        // the contract is stopped once the tx's gas runs out, rather than
+        // stalling the block
+        let sk = signing_key(1);
+        let msg = SdkMsg::Execute {
+            contract: "spin".into(),
+            msg: serde_json::json!({}),
+            funds: vec![],
+        };
+        let mut body = TxBody::single(address(&sk), CHAIN_ID, 0, 1, vec![msg]);
+        body.fee.gas_limit = 10;
+        let tx = sign_body(body, &[&sk]);
+        let tx_bytes = to_canonical_vec(&tx).unwrap();
+        let outcome = sm.deliver_tx(tx, &SignMode::Json, &sha256(&tx_bytes), tx_bytes.len());
+        assert!(matches!(outcome.result, Err(Error::TxOutOfGas { limit: 10, .. })));
+        assert!(outcome.gas_used <= 10);
+
+        // the tx still uses up its sequence, as it would pay its fee
+        let store = sm.store.pending_wrap();
+        let account = ACCOUNTS.load(&Module::Auth.substore(&store), &address(&sk)).unwrap();
+        assert!(matches!(account, Account::Base { sequence: 1, .. }));
+    }
+
+    /// A contract whose query entry point accepts or rejects every query, as
+    /// given.
+    fn query_hook_wasm(accept: bool) -> Vec<u8> {
+        let queried = if accept {
+            r#"{"ok":"e30="}"#
+        } else {
+            r#"{"error":"rejected by hook"}"#
+        };
+        let query = r#"(func (export "query") (param i32 i32) (result i32)
+            (i32.const 32))"#;
+        wat_contract(&[queried], query)
+    }
+
+    /// A contract whose execute entry point smart-queries the given contract,
+    /// and fails unless the query succeeds.
+    fn querying_wasm(contract: &str) -> Vec<u8> {
+        let failed = r#"{"error":"query failed"}"#;
+        let request = format!(
+            r#"{{"wasm":{{"smart":{{"contract_addr":"{contract}","msg":"e30="}}}}}}"#
+        );
+        let execute = r#";; byte 8 of the query's result is the `o` of the inner `ok` if the query
+            ;; succeeded, or the `e` of `error` if the queried contract failed
+            (func (export "execute") (param i32 i32 i32) (result i32)
+                (if (result i32)
+                    (i32.eq
+                        (i32.load8_u offset=8 (i32.load (call $query_chain (i32.const 48))))
+                        (i32.const 111))
+                    (then (i32.const 16))
+                    (else (i32.const 32))))"#;
+        wat_contract(&[failed, &request], execute)
+    }
+
+    #[test]
+    fn querying_contracts() {
+        let sm = chain_with_contracts(vec![
+            ("accept", query_hook_wasm(true)),
+            ("reject", query_hook_wasm(false)),
+            ("ask-accept", querying_wasm("accept")),
+            ("ask-reject", querying_wasm("reject")),
+        ]);
+
+        let sk = signing_key(1);
+        let execute = |contract: &str, sequence| {
+            let msg = SdkMsg::Execute {
+                contract: contract.into(),
+                msg: serde_json::json!({}),
+                funds: vec![],
+            };
+            let body = TxBody::single(address(&sk), CHAIN_ID, 0, sequence, vec![msg]);
+            deliver_tx(&sm, sign_body(body, &[&sk]))
+        };
+
+        // a contract can query another while it's executed, and fail if the
+        // queried contract rejects the query, e.g. a send hook rejecting a
+        // transfer
+        execute("ask-accept", 1).unwrap();
+        let err = execute("ask-reject", 2).unwrap_err();
+        assert!(matches!(err, Error::Contract(msg) if msg == "query failed"));
+    }
+
+    #[test]
+    fn checking_tx() {
+        struct RejectAll;
+
+        impl AnteHandler for RejectAll {
+            fn check(&self, _: &mut dyn Storage, _: &BlockInfo, _: &Tx) -> Result<()> {
+                Err(Error::ante_rejected("no txs allowed"))
+            }
+        }
+
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], GenesisState::default()).unwrap();
+
+        let sk = signing_key(1);
+        let txs = [sign_tx(&sk, 0, 1, None), sign_tx(&sk, 0, 2, None)];
+        let outcome = check_tx(&mut sm, &txs[0], CheckTxType::New).unwrap();
+        assert_eq!(outcome.gas_wanted, txs[0].body.fee.gas_limit);
+
+        // sequences are incremented in the check state, so consecutive txs of
+        // the same signer can be checked before any of them is delivered, but
+        // a tx can't be checked twice
+        check_tx(&mut sm, &txs[1], CheckTxType::New).unwrap();
+        let err = check_tx(&mut sm, &txs[0], CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::SequenceMismatch { .. }));
+
+        // the check state isn't persisted
+        let store = sm.store.pending_wrap();
+        let account = ACCOUNTS.may_load(&Module::Auth.substore(&store), &address(&sk));
+        assert_eq!(account.unwrap(), None);
+
+        // deliver the first tx. on recheck, the first tx is evicted, since its
+        // sequence has been consumed, but the second one is kept
+        sm.begin_block(block(1)).unwrap();
+        deliver_tx(&sm, txs[0].clone()).unwrap();
+        sm.end_block().unwrap();
+        sm.commit().unwrap();
+
+        let err = check_tx(&mut sm, &txs[0], CheckTxType::Recheck).unwrap_err();
+        assert!(matches!(err, Error::SequenceMismatch { .. }));
+        check_tx(&mut sm, &txs[1], CheckTxType::Recheck).unwrap();
+
+        // txs whose payer can't afford the fee are rejected
+        let mut body = TxBody::single(address(&sk), CHAIN_ID, 0, 3, vec![]);
+        body.fee.amount = coins(1, "ucosm");
+        let err = check_tx(&mut sm, &sign_body(body, &[&sk]), CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::FeeDeductionFailed { .. }));
+
+        // ante handlers run after the built-in checks
+        sm.add_ante_handler(RejectAll);
+        let tx = sign_tx(&sk, 0, 3, None);
+        let err = check_tx(&mut sm, &tx, CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::AnteRejected { .. }));
+
+        // txs paying less than the node's minimum gas price are rejected
+        // before any other check
+        sm.set_min_gas_prices(BTreeMap::from([("ucosm".into(), Decimal::percent(150))]));
+        let err = check_tx(&mut sm, &tx, CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::GasPriceTooLow { .. }));
+    }
+
+    #[test]
+    fn enforcing_size_limits() {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        let gen_state = GenesisState {
+            params: Params {
+                max_tx_bytes: 1024,
+                max_msgs_per_tx: 1,
+            },
+            ..Default::default()
+        };
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], gen_state).unwrap();
+        sm.begin_block(block(1)).unwrap();
+
+        let sk = signing_key(1);
+        let store_code = |size| SdkMsg::StoreCode {
+            wasm_byte_code: vec![0; size].into(),
+        };
+        let sign_msgs = |msgs| {
+            let body = TxBody::single(address(&sk), CHAIN_ID, 0, 1, msgs);
+            sign_body(body, &[&sk])
+        };
+
+        // limits are enforced both in CheckTx and DeliverTx
+        let tx = sign_msgs(vec![store_code(1024)]);
+        let err = check_tx(&mut sm, &tx, CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::TxTooLarge { max: 1024, .. }));
+        let err = deliver_tx(&sm, tx).unwrap_err();
+        assert!(matches!(err, Error::TxTooLarge { max: 1024, .. }));
+
+        let tx = sign_msgs(vec![store_code(1), store_code(1)]);
+        let err = check_tx(&mut sm, &tx, CheckTxType::New).unwrap_err();
+        assert!(matches!(err, Error::TooManyMsgs { count: 2, max: 1 }));
+        let err = deliver_tx(&sm, tx).unwrap_err();
+        assert!(matches!(err, Error::TooManyMsgs { count: 2, max: 1 }));
+    }
+
+    #[test]
+    fn verifying_signatures_in_batch() {
+        let sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], GenesisState::default()).unwrap();
+
+        let valid = sign_tx(&signing_key(1), 0, 1, None);
+        let mut invalid = sign_tx(&signing_key(2), 1, 1, None);
+        invalid.body.chain_id = "other-1".into();
+        let txs = vec![(valid.clone(), SignMode::Json), (invalid, SignMode::Json)];
+        assert_eq!(sm.verify_signatures(&txs), 1);
+
+        // signatures already verified aren't verified again
+        assert_eq!(sm.verify_signatures(&[(valid, SignMode::Json)]), 0);
+    }
+
+    #[test]
+    fn finalizing_block() {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], GenesisState::default()).unwrap();
+
+        let sk = signing_key(1);
+        let txs = vec![to_canonical_vec(&sign_tx(&sk, 0, 1, None)).unwrap(), b"garbage".to_vec()];
+        let outcome = sm.finalize_block(1, Timestamp::from_seconds(5), &txs).unwrap();
+
+        // a tx that fails doesn't fail the block
+        assert_eq!(outcome.tx_results.len(), 2);
+        assert!(outcome.tx_results[0].result.is_ok());
+        assert!(outcome.tx_results[1].result.is_err());
+
+        // a tx that can't be decoded consumes no gas, and fails with a code
+        assert_eq!(outcome.tx_results[1].gas_used, 0);
+        let error = outcome.tx_results[1].result.as_ref().unwrap_err();
+        assert_eq!(ErrorCode::from(error), ErrorCode::InvalidEncoding);
+
+        // the block is committed, with the same chain id as genesis
+        assert_eq!(sm.info().unwrap(), (1, outcome.app_hash));
+        let block = BLOCK.load(&Module::Chain.substore(&sm.store.wrap())).unwrap();
+        assert_eq!(block.chain_id, CHAIN_ID);
+
+        // both txs are indexed
+        let query = SdkQuery::Tx {
+            hash: hex::encode(sha256(&txs[1])),
+        };
+        let tx: TxResponse = from_binary(&sm.query(query).unwrap()).unwrap();
+        assert_eq!((tx.height, tx.index), (1, 1));
+        assert_eq!(tx.code, ErrorCode::InvalidEncoding.code());
+    }
+
+    /// Keeps only the txs of the given signer.
+    struct OnlySigner(String);
+
+    impl ProposalHandler for OnlySigner {
+        fn prepare(
+            &self,
+            _store: &dyn Storage,
+            _block: &BlockInfo,
+            txs: Vec<ProposalTx>,
+        ) -> Vec<ProposalTx> {
+            txs.into_iter().filter(|tx| tx.tx.body.signers[0].address == self.0).collect()
+        }
+    }
+
+    #[test]
+    fn preparing_proposal() {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.set_max_txs_per_account(2);
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], GenesisState::default()).unwrap();
+
+        let sks = [signing_key(1), signing_key(2)];
+        let encode = |tx: Tx| to_canonical_vec(&tx).unwrap();
+        let mempool = vec![
+            encode(sign_tx(&sks[0], 0, 1, None)),
+            b"garbage".to_vec(),
+            // sequence is already consumed by the first tx
+            encode(sign_tx(&sks[0], 0, 1, None)),
+            encode(sign_tx(&sks[1], 1, 1, None)),
+            encode(sign_tx(&sks[0], 0, 2, None)),
+            // beyond the per-account limit
+            encode(sign_tx(&sks[0], 0, 3, None)),
+        ];
+
+        let txs = sm.prepare_proposal(vec![], mempool.clone(), 1 << 20).unwrap();
+        assert_eq!(txs, [mempool[0].clone(), mempool[3].clone(), mempool[4].clone()]);
+
+        // only as many txs as fit in the block
+        let max_tx_bytes = (mempool[0].len() + mempool[3].len()) as u64;
+        let txs = sm.prepare_proposal(vec![], mempool.clone(), max_tx_bytes).unwrap();
+        assert_eq!(txs, [mempool[0].clone(), mempool[3].clone()]);
+
+        // the proposal handler is applied after the built-in checks
+        sm.set_proposal_handler(OnlySigner(address(&sks[1]).into()));
+        let txs = sm.prepare_proposal(vec![], mempool.clone(), 1 << 20).unwrap();
+        assert_eq!(txs, [mempool[3].clone()]);
+    }
+
+    /// Extends votes with a fixed price, and records the number of votes whose
+    /// extensions were processed.
+    struct FixedPrice;
+
+    impl vote_extensions::VoteExtensions for FixedPrice {
+        fn extend_vote(&self, _store: &dyn Storage, _block: &BlockInfo) -> Result<Vec<u8>> {
+            Ok(b"price".to_vec())
+        }
+
+        fn verify_vote_extension(
+            &self,
+            _store: &dyn Storage,
+            _block: &BlockInfo,
+            _validator: &[u8],
+            extension: &[u8],
+        ) -> Result<()> {
+            if extension != b"price" {
+                return Err(Error::invalid_vote_extensions("wrong price"));
+            }
+            Ok(())
+        }
+
+        fn process_vote_extensions(
+            &self,
+            store: &mut dyn Storage,
+            _block: &BlockInfo,
+            votes: &[ExtendedVote],
+        ) -> Result<Vec<Event>> {
+            store.set(b"votes", &votes.len().to_be_bytes());
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn extending_votes() {
+        use ed25519_zebra::{SigningKey as ConsensusKey, VerificationKey};
+
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.set_vote_extension_handler(VoteExtensionHandler::Native(Box::new(FixedPrice)), 1);
+
+        let keys = [ConsensusKey::from([1; 32]), ConsensusKey::from([2; 32])];
+        let validator = |key: &ConsensusKey, power: u64| Validator {
+            pubkey: <[u8; 32]>::from(VerificationKey::from(key)).to_vec().into(),
+            power,
+        };
+        let vals = vec![validator(&keys[0], 30), validator(&keys[1], 70)];
+        sm.init_chain(CHAIN_ID.into(), 1, vals.clone(), GenesisState::default()).unwrap();
+
+        let extension = sm.extend_vote(0).unwrap();
+        assert_eq!(extension, b"price");
+        sm.verify_vote_extension(0, &[], &extension).unwrap();
+        assert!(sm.verify_vote_extension(0, &[], b"cheap").is_err());
+
+        let vote = |index: usize, height: u64, extension: &[u8]| {
+            let sign_bytes = vote_extension_sign_bytes(extension, height, 0, CHAIN_ID);
+            ExtendedVote {
+                validator: vote_extensions::consensus_address(&vals[index].pubkey).into(),
+                power: vals[index].power,
+                round: 0,
+                extension: extension.to_vec().into(),
+                extension_signature: <[u8; 64]>::from(keys[index].sign(&sign_bytes))
+                    .to_vec()
+                    .into(),
+            }
+        };
+
+        // the votes are included at the start of the proposal
+        let votes = vec![vote(0, 0, b"price"), vote(1, 0, b"price")];
+        let tx = to_canonical_vec(&sign_tx(&signing_key(1), 0, 1, None)).unwrap();
+        let txs = sm.prepare_proposal(votes, vec![tx], 1024).unwrap();
+        assert_eq!(txs.len(), 2);
+        sm.process_proposal(1, &txs).unwrap();
+
+        // proposals with too little power, bad extensions or forged signatures
+        // are rejected
+        let weak = sm.prepare_proposal(vec![vote(0, 0, b"price")], vec![], 1024).unwrap();
+        assert!(sm.process_proposal(1, &weak).is_err());
+        let votes = vec![vote(0, 0, b"price"), vote(1, 0, b"cheap")];
+        let bad = sm.prepare_proposal(votes, vec![], 1024).unwrap();
+        assert!(sm.process_proposal(1, &bad).is_err());
+        let mut forged = vote(1, 0, b"price");
+        forged.extension_signature = vote(0, 0, b"price").extension_signature;
+        let forged = sm.prepare_proposal(vec![vote(0, 0, b"price"), forged], vec![], 1024).unwrap();
+        assert!(sm.process_proposal(1, &forged).is_err());
+
+        // the votes are processed when the block is executed
+        let outcome = sm.finalize_block(1, Timestamp::from_seconds(5), &txs).unwrap();
+        assert!(outcome.tx_results[0].result.is_ok());
+        assert!(outcome.tx_results[1].result.is_ok());
+        assert_eq!(sm.store.wrap().get(b"votes"), Some(2usize.to_be_bytes().to_vec()));
+
+        // a validator set change at the end of block 1 only takes effect at
+        // height 3, so the votes for block 2 are still by the genesis set
+        let mut store = sm.store.pending_wrap();
+        let new_vals = vec![validator(&keys[1], 70)];
+        VALIDATOR_SETS.save(&mut Module::Chain.substore_mut(&mut store), 1, &new_vals).unwrap();
+        sm.store.commit().unwrap();
+
+        let votes = vec![vote(0, 2, b"price"), vote(1, 2, b"price")];
+        let txs = sm.prepare_proposal(votes, vec![], 1024).unwrap();
+        sm.process_proposal(3, &txs).unwrap();
+        let txs = sm.prepare_proposal(vec![vote(0, 3, b"price")], vec![], 1024).unwrap();
+        assert!(sm.process_proposal(4, &txs).is_err());
+        let txs = sm.prepare_proposal(vec![vote(1, 3, b"price")], vec![], 1024).unwrap();
+        sm.process_proposal(4, &txs).unwrap();
+
+        // once votes are extended, proposals that leave out the votes are
+        // rejected, whether they're empty or start with a regular tx
+        let tx = to_canonical_vec(&sign_tx(&signing_key(1), 0, 1, None)).unwrap();
+        assert!(sm.process_proposal(4, &[]).is_err());
+        assert!(sm.process_proposal(4, &[tx.clone()]).is_err());
+        sm.process_proposal(1, &[tx]).unwrap();
+    }
+
+    #[test]
+    fn halting() {
+        let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+        sm.set_halt(2, 0);
+        sm.init_chain(CHAIN_ID.into(), 1, vec![], GenesisState::default()).unwrap();
+
+        for height in 1..=2 {
+            sm.begin_block(block(height)).unwrap();
+            sm.end_block().unwrap();
+            sm.commit().unwrap();
+        }
+
+        // the block at the halt height is committed, but no further block is
+        let err = sm.begin_block(block(3)).unwrap_err();
+        assert!(matches!(err, Error::Halted { height: 2 }));
+    }
+}
@@ -0,0 +1,395 @@
+use cosmwasm_std::{Empty, Order, StdResult, Storage};
+use cw_sdk::Account;
+use cw_storage_plus::Item;
+use tracing::info;
+
+use crate::{
+    error::{Error, Result},
+    state::{Module, ACCOUNTS, ACCOUNT_COUNT, BLOCK, CONTRACTS_BY_CODE, VALIDATOR_SETS},
+};
+
+/// The schema version of a module's state, i.e. the layout of the keys and
+/// values in its substore. Belongs to the module whose version it records.
+pub const SCHEMA_VERSION: Item<u32> = Item::new("schema_version");
+
+/// The schema version of every module when it is first created. This is also
+/// assumed for states created before schema versions were recorded.
+pub const INITIAL_VERSION: u32 = 1;
+
+/// A migration upgrades a module's state from one schema version to the next.
+pub struct Migration {
+    pub module: Module,
+
+    /// The version to upgrade from. The migration upgrades to `from + 1`.
+    pub from: u32,
+
+    /// The function that performs the upgrade. It is given the root store, as
+    /// the migration may need to read other modules' states.
+    pub migrate: fn(&mut dyn Storage) -> Result<()>,
+}
+
+/// The registered migrations.
+///
+/// When changing the layout of a module's state, bump its version by adding a
+/// migration here that converts the old layout to the new one.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        module: Module::Auth,
+        from: 1,
+        migrate: assign_account_numbers,
+    },
+    Migration {
+        module: Module::Auth,
+        from: 2,
+        migrate: index_contracts_by_code,
+    },
+    Migration {
+        module: Module::Chain,
+        from: 1,
+        migrate: dedupe_validator_sets,
+    },
+];
+
+/// Return the latest schema version of a module, i.e. the version after all
+/// registered migrations are applied.
+pub fn latest_version(module: Module) -> u32 {
+    latest_version_in(MIGRATIONS, module)
+}
+
+/// Record the latest schema versions of all modules. Called at genesis, where
+/// the states are created with the latest layouts.
+pub fn init_versions(store: &mut dyn Storage) -> Result<()> {
+    for module in Module::ALL {
+        SCHEMA_VERSION.save(&mut module.substore_mut(store), &latest_version(module))?;
+    }
+    Ok(())
+}
+
+/// Upgrade the state of each module to its latest schema version, by applying
+/// the registered migrations in order.
+///
+/// Return error if a module's state is at a version newer than the latest one,
+/// i.e. it was written by a newer version of the software.
+pub fn migrate(store: &mut dyn Storage) -> Result<()> {
+    migrate_with(store, MIGRATIONS)
+}
+
+/// Auth v1 -> v2: assign account numbers to existing base accounts, in
+/// ascending order by addresses, and record the account count.
+fn assign_account_numbers(store: &mut dyn Storage) -> Result<()> {
+    let mut auth = Module::Auth.substore_mut(store);
+
+    let accounts = ACCOUNTS
+        .range(&auth, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut account_count = 0;
+    for (address, account) in accounts {
+        if let Account::Base {
+            pubkey,
+            pubkey_type,
+            sequence,
+            ..
+        } = account
+        {
+            let account = Account::Base {
+                pubkey,
+                pubkey_type,
+                account_number: account_count,
+                sequence,
+            };
+            ACCOUNTS.save(&mut auth, &address, &account)?;
+            account_count += 1;
+        }
+    }
+
+    ACCOUNT_COUNT.save(&mut auth, &account_count)?;
+
+    Ok(())
+}
+
+/// Auth v2 -> v3: index existing contracts by their code ids.
+fn index_contracts_by_code(store: &mut dyn Storage) -> Result<()> {
+    let mut auth = Module::Auth.substore_mut(store);
+
+    let accounts = ACCOUNTS
+        .range(&auth, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (address, account) in accounts {
+        if let Account::Contract {
+            code_id,
+            ..
+        } = account
+        {
+            CONTRACTS_BY_CODE.save(&mut auth, (code_id, &address), &Empty {})?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Chain v1 -> v2: only keep the validator sets recorded at the heights where
+/// the set changed, dropping those identical to the set recorded before them.
+fn dedupe_validator_sets(store: &mut dyn Storage) -> Result<()> {
+    let mut chain = Module::Chain.substore_mut(store);
+
+    let sets = VALIDATOR_SETS
+        .range(&chain, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for pair in sets.windows(2) {
+        let ((_, prev), (height, set)) = (&pair[0], &pair[1]);
+        if set == prev {
+            VALIDATOR_SETS.remove(&mut chain, *height);
+        }
+    }
+
+    Ok(())
+}
+
+fn latest_version_in(migrations: &[Migration], module: Module) -> u32 {
+    migrations
+        .iter()
+        .filter(|migration| migration.module == module)
+        .map(|migration| migration.from + 1)
+        .max()
+        .unwrap_or(INITIAL_VERSION)
+}
+
+fn migrate_with(store: &mut dyn Storage, migrations: &[Migration]) -> Result<()> {
+    // the chain hasn't been initialized yet, so there's nothing to migrate.
+    // versions will be recorded at genesis
+    if BLOCK.may_load(&Module::Chain.substore(store))?.is_none() {
+        return Ok(());
+    }
+
+    for module in Module::ALL {
+        let latest = latest_version_in(migrations, module);
+        let recorded = SCHEMA_VERSION.may_load(&module.substore(store))?;
+
+        let mut version = recorded.unwrap_or(INITIAL_VERSION);
+        if version > latest {
+            return Err(Error::schema_version_unsupported(module, version, latest));
+        }
+
+        while version < latest {
+            let migration = migrations
+                .iter()
+                .find(|migration| migration.module == module && migration.from == version)
+                .ok_or_else(|| Error::migration_not_found(module, version))?;
+
+            (migration.migrate)(store)?;
+            version += 1;
+
+            info!(?module, version, "Migrated store");
+        }
+
+        if recorded != Some(version) {
+            SCHEMA_VERSION.save(&mut module.substore_mut(store), &version)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{testing::MockStorage, Addr, BlockInfo};
+    use cw_sdk::{PubKeyType, Validator};
+
+    use super::*;
+
+    const OLD_ITEM: Item<u64> = Item::new("old");
+    const NEW_ITEM: Item<u64> = Item::new("new");
+
+    fn rename_item(store: &mut dyn Storage) -> Result<()> {
+        let mut auth = Module::Auth.substore_mut(store);
+        let value = OLD_ITEM.load(&auth)?;
+        OLD_ITEM.remove(&mut auth);
+        NEW_ITEM.save(&mut auth, &value)?;
+        Ok(())
+    }
+
+    fn double_item(store: &mut dyn Storage) -> Result<()> {
+        let mut auth = Module::Auth.substore_mut(store);
+        NEW_ITEM.update(&mut auth, |value| -> Result<_> {
+            Ok(value * 2)
+        })?;
+        Ok(())
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            module: Module::Auth,
+            from: 1,
+            migrate: rename_item,
+        },
+        Migration {
+            module: Module::Auth,
+            from: 2,
+            migrate: double_item,
+        },
+    ];
+
+    fn setup_test() -> MockStorage {
+        let mut store = MockStorage::new();
+        let block = BlockInfo {
+            height: 1,
+            time: Default::default(),
+            chain_id: "dev-1".into(),
+        };
+        BLOCK.save(&mut Module::Chain.substore_mut(&mut store), &block).unwrap();
+        OLD_ITEM.save(&mut Module::Auth.substore_mut(&mut store), &21).unwrap();
+        store
+    }
+
+    fn version(store: &dyn Storage, module: Module) -> Option<u32> {
+        SCHEMA_VERSION.may_load(&module.substore(store)).unwrap()
+    }
+
+    #[test]
+    fn migrating() {
+        // no version recorded, meaning the state is at the initial version
+        let mut store = setup_test();
+        migrate_with(&mut store, TEST_MIGRATIONS).unwrap();
+
+        let auth = Module::Auth.substore(&store);
+        assert_eq!(OLD_ITEM.may_load(&auth).unwrap(), None);
+        assert_eq!(NEW_ITEM.load(&auth).unwrap(), 42);
+        assert_eq!(version(&store, Module::Auth), Some(3));
+        assert_eq!(version(&store, Module::Wasm), Some(INITIAL_VERSION));
+
+        // migrating again should be a no-op
+        migrate_with(&mut store, TEST_MIGRATIONS).unwrap();
+        assert_eq!(NEW_ITEM.load(&Module::Auth.substore(&store)).unwrap(), 42);
+    }
+
+    #[test]
+    fn migrating_partially() {
+        // the state is already at version 2, so only the second migration runs
+        let mut store = setup_test();
+        let mut auth = Module::Auth.substore_mut(&mut store);
+        OLD_ITEM.remove(&mut auth);
+        NEW_ITEM.save(&mut auth, &5).unwrap();
+        SCHEMA_VERSION.save(&mut auth, &2).unwrap();
+
+        migrate_with(&mut store, TEST_MIGRATIONS).unwrap();
+        assert_eq!(NEW_ITEM.load(&Module::Auth.substore(&store)).unwrap(), 10);
+        assert_eq!(version(&store, Module::Auth), Some(3));
+    }
+
+    #[test]
+    fn rejecting_newer_versions() {
+        let mut store = setup_test();
+        SCHEMA_VERSION.save(&mut Module::Auth.substore_mut(&mut store), &4).unwrap();
+
+        let err = migrate_with(&mut store, TEST_MIGRATIONS).unwrap_err();
+        assert!(matches!(err, Error::SchemaVersionUnsupported { .. }));
+    }
+
+    #[test]
+    fn assigning_account_numbers() {
+        let mut store = MockStorage::new();
+
+        let base = |sequence| Account::Base {
+            pubkey: b"pubkey".into(),
+            pubkey_type: PubKeyType::Secp256k1,
+            account_number: 0,
+            sequence,
+        };
+        let contract = Account::Contract {
+            code_id: 1,
+            label: "bank".into(),
+            admin: None,
+        };
+
+        let mut auth = Module::Auth.substore_mut(&mut store);
+        ACCOUNTS.save(&mut auth, &Addr::unchecked("ccc"), &base(3)).unwrap();
+        ACCOUNTS.save(&mut auth, &Addr::unchecked("aaa"), &base(1)).unwrap();
+        ACCOUNTS.save(&mut auth, &Addr::unchecked("bbb"), &contract).unwrap();
+
+        assign_account_numbers(&mut store).unwrap();
+
+        let auth = Module::Auth.substore(&store);
+        let number = |addr: &str| match ACCOUNTS.load(&auth, &Addr::unchecked(addr)).unwrap() {
+            Account::Base {
+                account_number,
+                ..
+            } => account_number,
+            _ => panic!("not a base account"),
+        };
+        assert_eq!(number("aaa"), 0);
+        assert_eq!(number("ccc"), 1);
+        assert_eq!(ACCOUNT_COUNT.load(&auth).unwrap(), 2);
+    }
+
+    #[test]
+    fn indexing_contracts_by_code() {
+        let mut store = MockStorage::new();
+
+        let contract = |code_id, label: &str| Account::Contract {
+            code_id,
+            label: label.into(),
+            admin: None,
+        };
+        let base = Account::Base {
+            pubkey: b"pubkey".into(),
+            pubkey_type: PubKeyType::Secp256k1,
+            account_number: 0,
+            sequence: 0,
+        };
+
+        let mut auth = Module::Auth.substore_mut(&mut store);
+        ACCOUNTS.save(&mut auth, &Addr::unchecked("aaa"), &contract(2, "aaa")).unwrap();
+        ACCOUNTS.save(&mut auth, &Addr::unchecked("bbb"), &contract(1, "bbb")).unwrap();
+        ACCOUNTS.save(&mut auth, &Addr::unchecked("ccc"), &contract(2, "ccc")).unwrap();
+        ACCOUNTS.save(&mut auth, &Addr::unchecked("ddd"), &base).unwrap();
+
+        index_contracts_by_code(&mut store).unwrap();
+
+        let auth = Module::Auth.substore(&store);
+        let addresses = |code_id| {
+            CONTRACTS_BY_CODE
+                .prefix(code_id)
+                .keys(&auth, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap()
+        };
+        assert_eq!(addresses(1), [Addr::unchecked("bbb")]);
+        assert_eq!(addresses(2), [Addr::unchecked("aaa"), Addr::unchecked("ccc")]);
+        assert_eq!(addresses(3), Vec::<Addr>::new());
+    }
+
+    #[test]
+    fn deduping_validator_sets() {
+        let mut store = MockStorage::new();
+
+        let set = |power| {
+            vec![Validator {
+                pubkey: b"pubkey".into(),
+                power,
+            }]
+        };
+
+        let mut chain = Module::Chain.substore_mut(&mut store);
+        for (height, power) in [(1, 1), (2, 1), (3, 2), (4, 2), (5, 1)] {
+            VALIDATOR_SETS.save(&mut chain, height, &set(power)).unwrap();
+        }
+
+        dedupe_validator_sets(&mut store).unwrap();
+
+        let heights = VALIDATOR_SETS
+            .keys(&Module::Chain.substore(&store), None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(heights, [1, 3, 5]);
+    }
+
+    #[test]
+    fn skipping_uninitialized_chain() {
+        let mut store = MockStorage::new();
+        migrate_with(&mut store, TEST_MIGRATIONS).unwrap();
+        assert_eq!(version(&store, Module::Auth), None);
+    }
+}
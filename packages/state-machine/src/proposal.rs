@@ -0,0 +1,87 @@
+//! Building the blocks that this node proposes.
+//!
+//! Instead of proposing the mempool's contents as they are, the proposer:
+//!
+//! - drops txs that can't be decoded;
+//! - orders the rest by priority, as determined in CheckTx, keeping txs of the
+//!   same priority in their mempool order;
+//! - checks them in that order against a fresh state, as CheckTx does, and
+//!   drops those that fail, e.g. because a tx before them consumed their
+//!   sequence;
+//! - drops the txs of an account beyond the per-account limit configured by the
+//!   operator, if any;
+//! - hands the result to the proposal handler set by the operator, if any,
+//!   which may drop or reorder txs further;
+//! - includes as many of the remaining txs, in order, as fit in the block.
+//!
+//! Dropped txs remain in the mempool, so they may be included in a later block.
+//! None of this is checked when other validators process the proposal, so
+//! operators are free to build their proposals differently.
+
+use std::cmp::Reverse;
+
+use cosmwasm_std::{BlockInfo, Storage};
+use cw_sdk::{SignMode, Tx};
+
+/// A mempool tx that is a candidate for inclusion in a proposed block.
+#[derive(Debug, Clone)]
+pub struct ProposalTx {
+    /// The tx as it was broadcasted
+    pub bytes: Vec<u8>,
+
+    pub tx: Tx,
+
+    pub sign_mode: SignMode,
+
+    /// The tx's priority in the mempool, including the operator's override
+    pub priority: i64,
+}
+
+/// Drops or reorders the txs of the blocks this node proposes, e.g. to include
+/// the operator's own txs first.
+///
+/// The txs given have passed the built-in checks in the order they're given.
+/// If the handler reorders txs of the same signer, some of them may fail when
+/// the block is executed.
+pub trait ProposalHandler {
+    fn prepare(
+        &self,
+        store: &dyn Storage,
+        block: &BlockInfo,
+        txs: Vec<ProposalTx>,
+    ) -> Vec<ProposalTx>;
+}
+
+/// Order txs by priority, from highest to lowest. Txs of the same priority keep
+/// their relative order.
+pub fn order_by_priority(txs: &mut [ProposalTx]) {
+    txs.sort_by_key(|tx| Reverse(tx.priority));
+}
+
+#[cfg(test)]
+mod tests {
+    use cw_sdk::TxBody;
+
+    use super::*;
+
+    fn proposal_tx(sequence: u64, priority: i64) -> ProposalTx {
+        ProposalTx {
+            bytes: vec![],
+            tx: Tx {
+                body: TxBody::single("sender", "chain", 0, sequence, vec![]),
+                signatures: vec![],
+            },
+            sign_mode: SignMode::Json,
+            priority,
+        }
+    }
+
+    #[test]
+    fn ordering_by_priority() {
+        let mut txs = vec![proposal_tx(1, 5), proposal_tx(2, 10), proposal_tx(3, 5)];
+        order_by_priority(&mut txs);
+
+        let sequences = txs.iter().map(|tx| tx.tx.body.signers[0].sequence).collect::<Vec<_>>();
+        assert_eq!(sequences, [2, 1, 3]);
+    }
+}
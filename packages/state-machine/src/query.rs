@@ -1,28 +1,152 @@
-use cosmwasm_std::{Binary, ContractInfo, Env, Order, Storage};
-use cosmwasm_vm::{call_query, Backend, Instance, InstanceOptions, Storage as VmStorage};
+use cosmwasm_std::{
+    from_binary, to_binary, to_vec, Binary, Coin, ContractInfo, ContractResult, Env, Order,
+    StdError, Storage,
+};
+use cosmwasm_vm::{call_query, Storage as VmStorage};
 use cw_paginate::{collect, paginate_indexed_map, paginate_map};
 use cw_sdk::{
-    address, Account, AccountResponse, CodeResponse, ContractResponse, InfoResponse,
-    WasmRawResponse, WasmSmartResponse,
+    address, bank, hash::sha256, Account, AccountResponse, AllowanceResponse, AuthzGrant,
+    CodeResponse, ContractResponse, InfoResponse, SdkQuery, ValidatorsResponse, WasmRawResponse,
+    WasmSmartResponse,
 };
 use cw_storage_plus::Bound;
+use cw_store::{prefix::namespace_upper_bound, ReadonlyPrefixedStore};
+use serde::de::DeserializeOwned;
 
 use crate::{
-    backend::{BackendApi, BackendQuerier, ContractSubstore},
-    error::Result,
-    state::{code_by_address, ACCOUNTS, BLOCK, CODES, CODE_COUNT},
+    backend::{create_instance, ContractSubstore, ModuleCache, WasmConfig},
+    error::{Error, Result},
+    state::{
+        code_by_address, contract_namespace, params, Module, ACCOUNTS, ACCOUNT_COUNT, BLOCK, CODES,
+        validator_set_at, CODE_COUNT, CONTRACTS_BY_CODE, AUTHZ_GRANTS, FEE_ALLOWANCES,
+    },
 };
 
+/// Page sizes for enumerating raw contract states. These are larger than the
+/// page sizes of other queries, as they are typically used to dump a contract's
+/// entire state.
+const RAW_DEFAULT_LIMIT: u32 = 100;
+const RAW_MAX_LIMIT: u32 = 1000;
+
+/// Whether the query can be answered from the committed state alone, and hence
+/// by `handle`, e.g. on a query worker reading a snapshot. Simulations, tx
+/// lookups and contract gas usage need the state machine itself.
+pub fn reads_committed_state(query: &SdkQuery) -> bool {
+    !matches!(
+        query,
+        SdkQuery::Simulate { .. }
+            | SdkQuery::Tx { .. }
+            | SdkQuery::TxsByEvent { .. }
+            | SdkQuery::ContractGasUsage { .. }
+    )
+}
+
+/// Answer a query that only reads the committed state, as determined by
+/// `reads_committed_state`, against the given store. Other queries are
+/// rejected.
+pub fn handle(
+    store: impl Storage + 'static,
+    query: SdkQuery,
+    wasm: &WasmConfig,
+    modules: Option<&ModuleCache>,
+) -> Result<Binary> {
+    match query {
+        SdkQuery::Info {} => to_binary(&info(&store)?),
+        SdkQuery::Account {
+            address,
+        } => to_binary(&account(&store, address)?),
+        SdkQuery::Accounts {
+            start_after,
+            limit,
+        } => to_binary(&accounts(&store, start_after, limit)?),
+        SdkQuery::Contract {
+            label,
+        } => to_binary(&contract(&store, label)?),
+        SdkQuery::Contracts {
+            start_after,
+            limit,
+        } => to_binary(&contracts(&store, start_after, limit)?),
+        SdkQuery::ContractsByCode {
+            code_id,
+            start_after,
+            limit,
+        } => to_binary(&contracts_by_code(&store, code_id, start_after, limit)?),
+        SdkQuery::Code {
+            code_id,
+            with_byte_code,
+        } => to_binary(&code(&store, code_id, with_byte_code)?),
+        SdkQuery::Codes {
+            start_after,
+            limit,
+        } => to_binary(&codes(&store, start_after, limit)?),
+        SdkQuery::Validators {
+            height,
+        } => to_binary(&validators(&store, height)?),
+        SdkQuery::Allowance {
+            granter,
+            grantee,
+        } => to_binary(&allowance(&store, granter, grantee)?),
+        SdkQuery::Allowances {
+            granter,
+            start_after,
+            limit,
+        } => to_binary(&allowances(&store, granter, start_after, limit)?),
+        SdkQuery::AuthzGrants {
+            granter,
+            grantee,
+            start_after,
+            limit,
+        } => to_binary(&authz_grants(&store, granter, grantee, start_after, limit)?),
+        SdkQuery::WasmRaw {
+            contract,
+            key,
+        } => to_binary(&wasm_raw(store, &contract, &key)?),
+        SdkQuery::WasmAllRaw {
+            contract,
+            prefix,
+            start_after,
+            limit,
+        } => to_binary(&wasm_all_raw(&store, &contract, prefix, start_after, limit)?),
+        SdkQuery::Balance {
+            address,
+            denom,
+        } => to_binary(&balance(store, address, denom, wasm, modules)?),
+        SdkQuery::AllBalances {
+            address,
+            start_after,
+            limit,
+        } => {
+            to_binary(&all_balances(store, address, start_after, limit, wasm, modules)?)
+        },
+        SdkQuery::Supply {
+            denom,
+        } => to_binary(&supply(store, denom, wasm, modules)?),
+        SdkQuery::WasmSmart {
+            contract,
+            msg,
+        } => {
+            let msg = serde_json::to_vec(&msg)?;
+            to_binary(&wasm_smart(store, &contract, &msg, wasm, modules)?)
+        },
+        _ => return Err(Error::QueryUnsupported),
+    }
+    .map_err(Error::from)
+}
+
 pub fn info(store: &dyn Storage) -> Result<InfoResponse> {
     Ok(InfoResponse {
-        last_committed_block: BLOCK.load(store)?,
-        code_count: CODE_COUNT.load(store)?,
+        last_committed_block: BLOCK.load(&Module::Chain.substore(store))?,
+        code_count: CODE_COUNT.load(&Module::Wasm.substore(store))?,
+        account_count: ACCOUNT_COUNT
+            .may_load(&Module::Auth.substore(store))?
+            .unwrap_or_default(),
+        params: params(store)?,
     })
 }
 
 pub fn account(store: &dyn Storage, address: String) -> Result<AccountResponse> {
     let addr = address::resolve_raw(&address)?;
-    let account = ACCOUNTS.load(store, &addr)?;
+    let account = ACCOUNTS.load(&Module::Auth.substore(store), &addr)?;
     Ok(AccountResponse {
         address,
         account: account.into(),
@@ -35,7 +159,8 @@ pub fn accounts(
     limit: Option<u32>,
 ) -> Result<Vec<AccountResponse>> {
     let start = start_after.map(|address| Bound::ExclusiveRaw(address.into_bytes()));
-    paginate_indexed_map(ACCOUNTS, store, start, limit, |address, account| {
+    let store = Module::Auth.substore(store);
+    paginate_indexed_map(ACCOUNTS, &store, start, limit, |address, account| {
         Ok(AccountResponse {
             address: address.into(),
             account: account.into(),
@@ -44,7 +169,7 @@ pub fn accounts(
 }
 
 pub fn contract(store: &dyn Storage, label: String) -> Result<ContractResponse> {
-    let (address, account) = ACCOUNTS.idx.label.load(store, label)?;
+    let (address, account) = ACCOUNTS.idx.label.load(&Module::Auth.substore(store), label)?;
     match account {
         Account::Contract {
             code_id,
@@ -66,7 +191,8 @@ pub fn contracts(
     limit: Option<u32>,
 ) -> Result<Vec<ContractResponse>> {
     let start = start_after.map(Bound::exclusive);
-    let iter = ACCOUNTS.idx.label.range(store, start, None, Order::Ascending);
+    let store = Module::Auth.substore(store);
+    let iter = ACCOUNTS.idx.label.range(&store, start, None, Order::Ascending);
     collect(iter, limit, |address, account| match account {
         Account::Contract {
             code_id,
@@ -82,27 +208,122 @@ pub fn contracts(
     })
 }
 
-pub fn code(store: &dyn Storage, code_id: u64) -> Result<CodeResponse> {
-    Ok(CodeResponse {
-        code_id,
-        wasm_byte_code: CODES.load(store, code_id)?,
+/// Enumerate the contracts instantiated from the given code, by address.
+pub fn contracts_by_code(
+    store: &dyn Storage,
+    code_id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<ContractResponse>> {
+    let start = start_after.map(|address| Bound::ExclusiveRaw(address.into_bytes()));
+    let store = Module::Auth.substore(store);
+    let iter = CONTRACTS_BY_CODE.prefix(code_id).range(&store, start, None, Order::Ascending);
+    collect(iter, limit, |address, _| match ACCOUNTS.load(&store, &address)? {
+        Account::Contract {
+            code_id,
+            label,
+            admin,
+        } => Ok(ContractResponse {
+            address: address.into(),
+            code_id,
+            label,
+            admin: admin.map(String::from),
+        }),
+        _ => unreachable!(),
     })
 }
 
+pub fn code(store: &dyn Storage, code_id: u64, with_byte_code: bool) -> Result<CodeResponse> {
+    let wasm_byte_code = CODES.load(&Module::Wasm.substore(store), code_id)?;
+    Ok(code_response(code_id, wasm_byte_code, with_byte_code))
+}
+
 pub fn codes(
     store: &dyn Storage,
     start_after: Option<u64>,
     limit: Option<u32>,
 ) -> Result<Vec<CodeResponse>> {
     let start = start_after.map(Bound::exclusive);
-    paginate_map(CODES, store, start, limit, |code_id, wasm_byte_code| {
-        Ok(CodeResponse {
-            code_id,
-            wasm_byte_code,
+    let store = Module::Wasm.substore(store);
+    paginate_map(CODES, &store, start, limit, |code_id, wasm_byte_code| {
+        Ok(code_response(code_id, wasm_byte_code, false))
+    })
+}
+
+fn code_response(code_id: u64, wasm_byte_code: Binary, with_byte_code: bool) -> CodeResponse {
+    CodeResponse {
+        code_id,
+        code_hash: hex::encode(sha256(&wasm_byte_code)),
+        size: wasm_byte_code.len() as u64,
+        wasm_byte_code: with_byte_code.then_some(wasm_byte_code),
+    }
+}
+
+pub fn validators(store: &dyn Storage, height: Option<u64>) -> Result<ValidatorsResponse> {
+    let height = match height {
+        Some(height) => height,
+        None => BLOCK.load(&Module::Chain.substore(store))?.height,
+    };
+    let validators = validator_set_at(store, height)?
+        .ok_or_else(|| StdError::not_found("validator set"))?;
+    Ok(ValidatorsResponse {
+        height,
+        validators,
+    })
+}
+
+pub fn allowance(
+    store: &dyn Storage,
+    granter: String,
+    grantee: String,
+) -> Result<AllowanceResponse> {
+    let granter_addr = address::validate(&granter)?;
+    let grantee_addr = address::validate(&grantee)?;
+    let allowance =
+        FEE_ALLOWANCES.load(&Module::Auth.substore(store), (&granter_addr, &grantee_addr))?;
+    Ok(AllowanceResponse {
+        granter,
+        grantee,
+        allowance,
+    })
+}
+
+pub fn allowances(
+    store: &dyn Storage,
+    granter: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<AllowanceResponse>> {
+    let granter_addr = address::validate(&granter)?;
+    let start = start_after.map(|grantee| Bound::ExclusiveRaw(grantee.into_bytes()));
+    let store = Module::Auth.substore(store);
+    let iter = FEE_ALLOWANCES.prefix(&granter_addr).range(&store, start, None, Order::Ascending);
+    collect(iter, limit, |grantee, allowance| {
+        Ok(AllowanceResponse {
+            granter: granter.clone(),
+            grantee: grantee.into(),
+            allowance,
         })
     })
 }
 
+pub fn authz_grants(
+    store: &dyn Storage,
+    granter: String,
+    grantee: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<AuthzGrant>> {
+    let granter_addr = address::validate(&granter)?;
+    let grantee_addr = address::validate(&grantee)?;
+    let start = start_after.map(|key| Bound::ExclusiveRaw(key.into_bytes()));
+    let store = Module::Auth.substore(store);
+    let iter = AUTHZ_GRANTS
+        .prefix((&granter_addr, &grantee_addr))
+        .range(&store, start, None, Order::Ascending);
+    collect(iter, limit, |_, grant| Ok(grant))
+}
+
 pub fn wasm_raw(store: impl Storage, contract: &str, key: &[u8]) -> Result<WasmRawResponse> {
     let contract_addr = address::resolve_raw(contract)?;
     let substore = ContractSubstore::new(store, &contract_addr);
@@ -112,15 +333,57 @@ pub fn wasm_raw(store: impl Storage, contract: &str, key: &[u8]) -> Result<WasmR
     })
 }
 
+pub fn wasm_all_raw(
+    store: &dyn Storage,
+    contract: &str,
+    prefix: Option<Binary>,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> Result<Vec<(Binary, Binary)>> {
+    let contract_addr = address::resolve_raw(contract)?;
+    let substore = ReadonlyPrefixedStore::new(store, &contract_namespace(&contract_addr));
+
+    let prefix = prefix.map(|prefix| prefix.0).unwrap_or_default();
+
+    // start_after is exclusive; the smallest key greater than it is itself
+    // appended with a zero byte.
+    // the start must not be smaller than the prefix.
+    let start = match start_after {
+        Some(start_after) => {
+            let mut start = start_after.0;
+            start.push(0);
+            start.max(prefix.clone())
+        },
+        None => prefix.clone(),
+    };
+    let end = if prefix.is_empty() {
+        None
+    } else {
+        Some(namespace_upper_bound(&prefix))
+    };
+
+    let limit = limit.unwrap_or(RAW_DEFAULT_LIMIT).min(RAW_MAX_LIMIT) as usize;
+
+    Ok(substore
+        .range(Some(&start), end.as_deref(), Order::Ascending)
+        .take(limit)
+        .map(|(k, v)| (Binary(k), Binary(v)))
+        .collect())
+}
+
+/// Smart-query a contract, bounded by the configured query gas limit.
 pub fn wasm_smart(
     store: impl Storage + 'static,
     contract: &str,
     msg: &[u8],
+    wasm: &WasmConfig,
+    modules: Option<&ModuleCache>,
 ) -> Result<WasmSmartResponse> {
     let contract_addr = address::resolve_raw(contract)?;
 
-    // load contract binary code
+    // load contract binary code, and the gas costs of its storage ops
     let code = code_by_address(&store, &contract_addr)?;
+    let gas_config = params(&store)?.gas;
 
     // load block info and prepare env
     //
@@ -129,7 +392,7 @@ pub fn wasm_smart(
     //   by the Store::pending_wrap method) and the pending block
     // - when querying during Query, we use the commited store (created by the
     //   Store::wrap method) and the last committed block
-    let block = BLOCK.load(&store)?;
+    let block = BLOCK.load(&Module::Chain.substore(&store))?;
     let env = Env {
         block,
         transaction: None,
@@ -138,18 +401,15 @@ pub fn wasm_smart(
         },
     };
 
-    let mut instance = Instance::from_code(
+    let mut instance = create_instance(
         &code,
-        Backend {
-            api: BackendApi,
-            storage: ContractSubstore::new(store, &contract_addr),
-            querier: BackendQuerier,
-        },
-        InstanceOptions {
-            gas_limit: u64::MAX,
-            print_debug: true,
-        },
-        None,
+        store,
+        &contract_addr,
+        &env.block,
+        wasm.query_gas_limit,
+        gas_config,
+        wasm,
+        modules,
     )?;
 
     let result = call_query(&mut instance, &env, msg)?;
@@ -158,3 +418,110 @@ pub fn wasm_smart(
         result,
     })
 }
+
+pub fn balance(
+    store: impl Storage + 'static,
+    address: String,
+    denom: String,
+    wasm: &WasmConfig,
+    modules: Option<&ModuleCache>,
+) -> Result<Coin> {
+    query_bank(
+        store,
+        &bank::QueryMsg::Balance {
+            address,
+            denom,
+        },
+        wasm,
+        modules,
+    )
+}
+
+pub fn all_balances(
+    store: impl Storage + 'static,
+    address: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    wasm: &WasmConfig,
+    modules: Option<&ModuleCache>,
+) -> Result<Vec<Coin>> {
+    query_bank(
+        store,
+        &bank::QueryMsg::Balances {
+            address,
+            start_after,
+            limit,
+        },
+        wasm,
+        modules,
+    )
+}
+
+pub fn supply(
+    store: impl Storage + 'static,
+    denom: String,
+    wasm: &WasmConfig,
+    modules: Option<&ModuleCache>,
+) -> Result<Coin> {
+    query_bank(
+        store,
+        &bank::QueryMsg::Supply {
+            denom,
+        },
+        wasm,
+        modules,
+    )
+}
+
+/// Smart-query the bank contract, and decode its response.
+fn query_bank<T: DeserializeOwned>(
+    store: impl Storage + 'static,
+    msg: &bank::QueryMsg,
+    wasm: &WasmConfig,
+    modules: Option<&ModuleCache>,
+) -> Result<T> {
+    match wasm_smart(store, "bank", &to_vec(msg)?, wasm, modules)?.result {
+        ContractResult::Ok(data) => from_binary(&data).map_err(Error::from),
+        ContractResult::Err(err) => Err(Error::Contract(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{testing::MockStorage, Addr};
+    use cw_store::PrefixedStore;
+
+    use super::*;
+
+    #[test]
+    fn querying_all_raw() {
+        let mut store = MockStorage::new();
+
+        let contract_addr = address::derive_from_label("bank").unwrap();
+        let mut substore = PrefixedStore::new(&mut store, &contract_namespace(&contract_addr));
+        for key in [b"aa", b"ab", b"ac", b"ba"] {
+            substore.set(key, b"value");
+        }
+
+        // another contract's state should not be included
+        let other_addr = Addr::unchecked("other");
+        PrefixedStore::new(&mut store, &contract_namespace(&other_addr)).set(b"ad", b"value");
+
+        let keys = |pairs: Vec<(Binary, Binary)>| {
+            pairs.into_iter().map(|(k, _)| k.to_vec()).collect::<Vec<_>>()
+        };
+
+        let pairs = wasm_all_raw(&store, "bank", None, None, None).unwrap();
+        assert_eq!(
+            keys(pairs),
+            vec![b"aa".to_vec(), b"ab".to_vec(), b"ac".to_vec(), b"ba".to_vec()],
+        );
+
+        let pairs = wasm_all_raw(&store, "bank", Some(b"a".into()), None, Some(2)).unwrap();
+        assert_eq!(keys(pairs), vec![b"aa".to_vec(), b"ab".to_vec()]);
+
+        let pairs =
+            wasm_all_raw(&store, "bank", Some(b"a".into()), Some(b"ab".into()), Some(2)).unwrap();
+        assert_eq!(keys(pairs), vec![b"ac".to_vec()]);
+    }
+}
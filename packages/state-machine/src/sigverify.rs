@@ -0,0 +1,250 @@
+//! Signature verification ahead of DeliverTx.
+//!
+//! Verifying secp256k1 signatures is the dominant non-wasm cost of executing a
+//! tx. Since a signature only depends on the tx itself and the signer's pubkey,
+//! it can be verified before the tx is delivered: for a single tx in CheckTx,
+//! or for many txs at once in a batch, which is done in parallel if the
+//! `parallel` feature is enabled.
+//!
+//! Signatures that are found valid are recorded in a `SignatureCache`, which
+//! `auth::authenticate_tx` consults before verifying a signature itself.
+//! Signatures that are found invalid aren't recorded, so that DeliverTx
+//! verifies them again and reports the error.
+
+use std::{cell::RefCell, collections::HashSet};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use cosmwasm_std::{Binary, Storage};
+use cw_sdk::{address, hash::sha256, Account, PubKeyType, SignMode, Tx};
+
+use crate::{
+    auth::verify_signature,
+    error::Result,
+    state::{Module, ACCOUNTS},
+};
+
+/// A signature to be verified: the signer's pubkey, the bytes that are signed,
+/// and the signature over them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureCheck {
+    pub pubkey_type: PubKeyType,
+    pub pubkey: Binary,
+    pub sign_bytes: Vec<u8>,
+    pub signature: Binary,
+}
+
+impl SignatureCheck {
+    pub fn verify(&self) -> Result<()> {
+        verify_signature(self.pubkey_type, &self.pubkey, &self.sign_bytes, &self.signature)
+    }
+
+    /// The key under which the check is recorded in the cache, which commits
+    /// to all of its fields.
+    fn cache_key(&self) -> Vec<u8> {
+        let pubkey_type = match self.pubkey_type {
+            PubKeyType::Secp256k1 => 0,
+            PubKeyType::EthSecp256k1 => 1,
+        };
+        let mut preimage = vec![pubkey_type];
+        for bytes in [self.pubkey.as_slice(), &self.sign_bytes, self.signature.as_slice()] {
+            preimage.extend((bytes.len() as u32).to_be_bytes());
+            preimage.extend(bytes);
+        }
+        sha256(&preimage)
+    }
+}
+
+/// Signatures that have been verified ahead of DeliverTx.
+///
+/// Entries are kept for two blocks, so that a signature verified in CheckTx
+/// is still cached when the tx is included in the next block.
+#[derive(Debug, Default)]
+pub struct SignatureCache {
+    current: RefCell<HashSet<Vec<u8>>>,
+    previous: HashSet<Vec<u8>>,
+
+    /// Whether to accept every signature without verifying it, which is the
+    /// case when simulating txs that may not have been signed yet
+    unverified: bool,
+}
+
+impl SignatureCache {
+    /// A cache that accepts every signature without verifying it.
+    pub fn unverified() -> Self {
+        Self {
+            unverified: true,
+            ..Default::default()
+        }
+    }
+
+    /// Verify the signature, unless it has been verified already.
+    pub fn verify(&self, check: &SignatureCheck) -> Result<()> {
+        if self.unverified {
+            return Ok(());
+        }
+
+        let key = check.cache_key();
+        if self.previous.contains(&key) || self.current.borrow().contains(&key) {
+            return Ok(());
+        }
+        check.verify()?;
+        self.current.borrow_mut().insert(key);
+        Ok(())
+    }
+
+    /// Verify many signatures at once, and record the ones that are valid.
+    /// Return the number of signatures found valid.
+    pub fn verify_batch(&self, checks: &[SignatureCheck]) -> usize {
+        let pending = checks
+            .iter()
+            .map(|check| (check.cache_key(), check))
+            .filter(|(key, _)| !self.previous.contains(key) && !self.current.borrow().contains(key))
+            .collect::<Vec<_>>();
+
+        #[cfg(feature = "parallel")]
+        let iter = pending.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let iter = pending.into_iter();
+
+        let verified = iter
+            .filter(|(_, check)| check.verify().is_ok())
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+
+        let count = verified.len();
+        self.current.borrow_mut().extend(verified);
+        count
+    }
+
+    /// Forget the signatures verified before the last block. Called once each
+    /// block is committed.
+    pub fn rotate(&mut self) {
+        self.previous = self.current.take();
+    }
+}
+
+/// Collect the signatures of a tx that can be verified without executing it.
+///
+/// The sign bytes are computed using the account numbers and sequences that
+/// the signers claim; whether these are correct is checked in DeliverTx. The
+/// pubkey of a signer is either included in the tx, or else that of its
+/// existing account. Signatures whose pubkeys are unknown, such as those of
+/// contract accounts, are skipped.
+pub fn signature_checks(
+    store: &dyn Storage,
+    tx: &Tx,
+    sign_mode: &SignMode,
+) -> Result<Vec<SignatureCheck>> {
+    let auth = Module::Auth.substore(store);
+    let mut checks = vec![];
+
+    for (signer, signature) in tx.body.signers.iter().zip(&tx.signatures) {
+        let account = match address::validate(&signer.address) {
+            Ok(signer_addr) => ACCOUNTS.may_load(&auth, &signer_addr)?,
+            Err(_) => continue,
+        };
+
+        let sign_bytes = signature.sign_bytes(
+            sign_mode,
+            &tx.body,
+            &tx.body.chain_id,
+            signer.account_number,
+            signer.sequence,
+        )?;
+
+        match &signature.multisig {
+            Some(multisig) => {
+                let pubkey = match (&multisig.pubkey, account) {
+                    (Some(pubkey), _) => pubkey.clone(),
+                    (
+                        None,
+                        Some(Account::Multisig {
+                            pubkey,
+                            ..
+                        }),
+                    ) => pubkey,
+                    _ => continue,
+                };
+                for (member_pubkey, member_signature) in
+                    pubkey.pubkeys.into_iter().zip(&multisig.signatures)
+                {
+                    if let Some(member_signature) = member_signature {
+                        checks.push(SignatureCheck {
+                            pubkey_type: PubKeyType::Secp256k1,
+                            pubkey: member_pubkey,
+                            sign_bytes: sign_bytes.clone(),
+                            signature: member_signature.clone(),
+                        });
+                    }
+                }
+            },
+            None => {
+                let pubkey = match (&signature.pubkey, account) {
+                    (Some(pubkey), _) => pubkey.clone(),
+                    (
+                        None,
+                        Some(Account::Base {
+                            pubkey,
+                            ..
+                        }),
+                    ) => pubkey,
+                    _ => continue,
+                };
+                checks.push(SignatureCheck {
+                    pubkey_type: signature.pubkey_type,
+                    pubkey,
+                    sign_bytes,
+                    signature: signature.signature.clone(),
+                });
+            },
+        }
+    }
+
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+    use super::*;
+
+    fn check(sk: &SigningKey, sign_bytes: &[u8]) -> SignatureCheck {
+        let signature: Signature = sk.sign(sign_bytes);
+        SignatureCheck {
+            pubkey_type: PubKeyType::Secp256k1,
+            pubkey: sk.verifying_key().to_bytes().to_vec().into(),
+            sign_bytes: sign_bytes.to_vec(),
+            signature: signature.as_ref().to_vec().into(),
+        }
+    }
+
+    #[test]
+    fn caching_verified_signatures() {
+        let sk = SigningKey::from_bytes(&[1; 32]).unwrap();
+        let valid = check(&sk, b"foo");
+        let invalid = SignatureCheck {
+            sign_bytes: b"bar".to_vec(),
+            ..valid.clone()
+        };
+
+        // only valid signatures are recorded
+        let mut cache = SignatureCache::default();
+        assert_eq!(cache.verify_batch(&[valid.clone(), invalid.clone()]), 1);
+        assert!(cache.current.borrow().contains(&valid.cache_key()));
+        assert!(!cache.current.borrow().contains(&invalid.cache_key()));
+        assert!(cache.verify(&invalid).is_err());
+
+        // cached signatures aren't verified again
+        assert_eq!(cache.verify_batch(&[valid.clone()]), 0);
+
+        // they're kept for one more block, then forgotten
+        cache.rotate();
+        assert_eq!(cache.verify_batch(&[valid.clone()]), 0);
+        cache.rotate();
+        cache.rotate();
+        assert_eq!(cache.verify_batch(&[valid]), 1);
+    }
+}
@@ -1,30 +1,151 @@
-use cosmwasm_std::{Addr, Binary, Storage, BlockInfo};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Storage, BlockInfo, Empty, Event, Order};
 use cw_optional_indexes::OptionalUniqueIndex;
-use cw_sdk::Account;
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map};
+use cw_sdk::{address, Account, Allowance, AuthzGrant, Params, Validator};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map};
+use cw_store::{prefix::concat, PrefixedStore, ReadonlyPrefixedStore};
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    gas::SystemCall,
+    GOVERNANCE,
+};
 
-/// Info of the last committed block.
+/// The modules that the chain's state is divided into.
+///
+/// Each module owns a substore, i.e. the keyspace under the module's prefix in
+/// the root store. State items below note the module they belong to; they must
+/// always be accessed through that module's substore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Module {
+    /// Accounts, both base accounts and contract accounts
+    Auth,
+    /// Reserved for native token balances and supplies. Currently unused, as
+    /// these are managed by the bank contract, whose state lives in `Wasm`.
+    Bank,
+    /// Block info and validator sets
+    Chain,
+    /// Chain-level parameters
+    Params,
+    /// Wasm byte codes, and the internal states of contracts
+    Wasm,
+}
+
+impl Module {
+    pub const ALL: [Module; 5] =
+        [Module::Auth, Module::Bank, Module::Chain, Module::Params, Module::Wasm];
+
+    pub const fn prefix(self) -> &'static [u8] {
+        match self {
+            Module::Auth => b"auth/",
+            Module::Bank => b"bank/",
+            Module::Chain => b"chain/",
+            Module::Params => b"params/",
+            Module::Wasm => b"wasm/",
+        }
+    }
+
+    /// Return a read-only handle to the module's substore.
+    pub fn substore(self, store: &dyn Storage) -> ReadonlyPrefixedStore {
+        ReadonlyPrefixedStore::new(store, self.prefix())
+    }
+
+    /// Return a mutable handle to the module's substore.
+    pub fn substore_mut(self, store: &mut dyn Storage) -> PrefixedStore {
+        PrefixedStore::new(store, self.prefix())
+    }
+}
+
+/// Return the namespace of a contract's internal state in the root store.
+///
+/// Contract states live in the wasm module's substore. The contract addresses
+/// are all of the same length, so no contract's namespace is a prefix of
+/// another's.
+pub fn contract_namespace(contract_addr: &Addr) -> Vec<u8> {
+    concat(Module::Wasm.prefix(), contract_addr.as_bytes())
+}
+
+/// Chain-level parameters. Belongs to `Module::Params`.
+///
+/// Chains started before parameters were introduced don't have this item; the
+/// defaults apply to them. Use `params` to load it.
+pub const PARAMS: Item<Params> = Item::new("params");
+
+/// Info of the last committed block. Belongs to `Module::Chain`.
 pub const BLOCK: Item<BlockInfo> = Item::new("block");
 
-/// The total number of wasm byte codes stored on chain.
+/// The total number of wasm byte codes stored on chain. Belongs to `Module::Wasm`.
 pub const CODE_COUNT: Item<u64> = Item::new("code_count");
 
-/// The wasm byte codes, indexed by code ids.
+/// The number of account numbers assigned so far. Belongs to `Module::Auth`.
+pub const ACCOUNT_COUNT: Item<u64> = Item::new("account_count");
+
+/// The wasm byte codes, indexed by code ids. Belongs to `Module::Wasm`.
 pub const CODES: Map<u64, Binary> = Map::new("codes");
 
+/// The validator set at each height, indexed by block heights.
+///
+/// The set recorded under height `H` is the one that results from applying
+/// the updates returned to Tendermint at the end of block `H`. Note that per
+/// Tendermint's spec, this set only takes effect at height `H + 2`.
+///
+/// A set is only recorded at the heights where it changes; the set at any other
+/// height is the one most recently recorded before it. Use `validator_set_at`
+/// to load it, or `active_validator_set` for the set that signs a given block.
+///
+/// Belongs to `Module::Chain`.
+pub const VALIDATOR_SETS: Map<u64, Vec<Validator>> = Map::new("validator_sets");
+
 /// Accounts, either base (i.e. externally-owned) accounts or smart contract
 /// accounts, indexed by addresses.
 /// Contracts are additionally indexed by their labels, which must be unique.
+///
+/// Belongs to `Module::Auth`.
 pub const ACCOUNTS: IndexedMap<&Addr, Account<Addr>, AccountIndexes> = IndexedMap::new(
     "accounts",
     AccountIndexes::new("accounts__label"),
 );
 
+/// Addresses of contracts, indexed by their code ids, so that the instances of
+/// a code can be enumerated. Must be updated whenever a contract account is
+/// saved with a new code id.
+///
+/// Belongs to `Module::Auth`.
+pub const CONTRACTS_BY_CODE: Map<(u64, &Addr), Empty> = Map::new("contracts_by_code");
+
+/// Fee allowances, indexed by granter and grantee addresses.
+///
+/// Belongs to `Module::Auth`.
+pub const FEE_ALLOWANCES: Map<(&Addr, &Addr), Allowance> = Map::new("fee_allowances");
+
+/// Authorization grants, indexed by granter and grantee addresses, and the
+/// authorizations' keys.
+///
+/// Belongs to `Module::Auth`.
+pub const AUTHZ_GRANTS: Map<(&Addr, &Addr, &str), AuthzGrant> = Map::new("authz_grants");
+
+/// Idempotency keys of executed txs, indexed by sender addresses and the keys.
+///
+/// Belongs to `Module::Auth`.
+pub const IDEMPOTENCY_KEYS: Map<(&Addr, &str), IdempotencyRecord> = Map::new("idempotency_keys");
+
+/// The senders and idempotency keys to be forgotten, indexed by the UNIX
+/// timestamps (in seconds) at which they expire.
+///
+/// Belongs to `Module::Auth`.
+pub const IDEMPOTENCY_EXPIRIES: Map<u64, Vec<(Addr, String)>> = Map::new("idempotency_expiries");
+
+#[cw_serde]
+pub struct IdempotencyRecord {
+    /// Hash of the tx that carried the idempotency key
+    pub tx_hash: Binary,
+    /// UNIX timestamp (in seconds) after which the key may be reused
+    pub expires: u64,
+}
+
 /// The index types used to index accounts in cw-sdk
 pub struct AccountIndexes<'a> {
-    /// Index accounts by contract labels. If an account is a base account
+    /// Index accounts by contract labels. If an account is not a contract
     /// then it is not indexed.
     pub label: OptionalUniqueIndex<'a, String, Account<Addr>, &'a Addr>,
 }
@@ -34,13 +155,11 @@ impl<'a> AccountIndexes<'a> {
         Self {
             label: OptionalUniqueIndex::new(
                 |account| match account {
-                    Account::Base {
-                        ..
-                    } => None,
                     Account::Contract {
                         label,
                         ..
                     } => Some(label.clone()),
+                    _ => None,
                 },
                 label_namespace,
             ),
@@ -57,26 +176,124 @@ impl<'a> IndexList<Account<Addr>> for AccountIndexes<'a> {
 
 /// Helper function for loading the wasm code of a given contract address.
 pub fn code_by_address(store: &dyn Storage, contract_addr: &Addr) -> Result<Binary> {
-    let code_id = match ACCOUNTS.may_load(store, contract_addr)? {
+    let code_id = match ACCOUNTS.may_load(&Module::Auth.substore(store), contract_addr)? {
         Some(Account::Contract {
             code_id,
             ..
         }) => code_id,
-        Some(Account::Base {
-            ..
-        }) => {
+        Some(_) => {
             return Err(Error::account_is_not_contract(contract_addr));
         },
         None => {
             return Err(Error::account_not_found(contract_addr));
         },
     };
-    CODES.load(store, code_id).map_err(Error::from)
+    CODES.load(&Module::Wasm.substore(store), code_id).map_err(Error::from)
+}
+
+/// Helper function for loading the most recently recorded validator set.
+/// Return an empty set if none has been recorded yet.
+pub fn latest_validator_set(store: &dyn Storage) -> Result<Vec<Validator>> {
+    validator_set_at(store, u64::MAX).map(Option::unwrap_or_default)
+}
+
+/// Helper function for loading the validator set as of the given height, i.e.
+/// the one most recently recorded at or before it. Return `None` if none had
+/// been recorded by then.
+pub fn validator_set_at(store: &dyn Storage, height: u64) -> Result<Option<Vec<Validator>>> {
+    let max = Some(Bound::inclusive(height));
+    VALIDATOR_SETS
+        .range(&Module::Chain.substore(store), None, max, Order::Descending)
+        .next()
+        .transpose()
+        .map(|opt| opt.map(|(_, validators)| validators))
+        .map_err(Error::from)
+}
+
+/// Helper function for loading the validator set that signs the block at the
+/// given height, i.e. the one recorded at least two heights before it. The
+/// genesis set signs the first blocks, before any update takes effect. Return
+/// an empty set if none has been recorded.
+pub fn active_validator_set(store: &dyn Storage, height: u64) -> Result<Vec<Validator>> {
+    if let Some(validators) = validator_set_at(store, height.saturating_sub(2))? {
+        return Ok(validators);
+    }
+
+    VALIDATOR_SETS
+        .range(&Module::Chain.substore(store), None, None, Order::Ascending)
+        .next()
+        .transpose()
+        .map(|opt| opt.map(|(_, validators)| validators).unwrap_or_default())
+        .map_err(Error::from)
+}
+
+/// Load the chain-level parameters, or the defaults if none have been set.
+pub fn params(store: &dyn Storage) -> Result<Params> {
+    Ok(PARAMS.may_load(&Module::Params.substore(store))?.unwrap_or_default())
+}
+
+/// Replace the chain-level parameters, as the governance contract has decided.
+/// Return error if the sender isn't the governance contract, or if the new
+/// parameters would make the chain unusable.
+pub fn update_params(
+    store: &mut dyn Storage,
+    sender_addr: &Addr,
+    params: &Params,
+) -> Result<Event> {
+    if *sender_addr != address::derive_from_label(GOVERNANCE)? {
+        return Err(Error::not_governance(sender_addr));
+    }
+
+    if params.max_tx_bytes == 0 || params.max_msgs_per_tx == 0 {
+        return Err(Error::invalid_params("txs must be allowed at least one byte and message"));
+    }
+    if params.gas.wasm_gas_per_sdk_gas == 0 {
+        return Err(Error::invalid_params("wasm gas per SDK gas must not be zero"));
+    }
+    let zero_limit = SystemCall::ALL.into_iter().find(|call| call.limit(&params.system_gas) == 0);
+    if let Some(call) = zero_limit {
+        return Err(Error::invalid_params(format!("{} gas limit must not be zero", call.name())));
+    }
+
+    PARAMS.save(&mut Module::Params.substore_mut(store), params)?;
+
+    Ok(Event::new("update_params").add_attribute("sender", sender_addr))
+}
+
+/// Sort validator set updates by pubkeys, so that the updates returned to
+/// Tendermint don't depend on the order in which the staking contract provides
+/// them. Return error if a validator is updated more than once.
+pub fn normalize_validator_updates(mut updates: Vec<Validator>) -> Result<Vec<Validator>> {
+    updates.sort_by(|a, b| a.pubkey.as_slice().cmp(b.pubkey.as_slice()));
+    if let Some(pair) = updates.windows(2).find(|pair| pair[0].pubkey == pair[1].pubkey) {
+        return Err(Error::duplicate_validator_update(pair[0].pubkey.as_slice()));
+    }
+    Ok(updates)
+}
+
+/// Apply validator set updates to a validator set. A validator whose power is
+/// updated to zero is removed from the set.
+///
+/// The resulting set is sorted by pubkeys, so that its serialization doesn't
+/// depend on the order in which the updates are provided.
+pub fn apply_validator_updates(
+    mut validators: Vec<Validator>,
+    updates: &[Validator],
+) -> Vec<Validator> {
+    for update in updates {
+        validators.retain(|validator| validator.pubkey != update.pubkey);
+        if update.power > 0 {
+            validators.push(update.clone());
+        }
+    }
+    validators.sort_by(|a, b| a.pubkey.as_slice().cmp(b.pubkey.as_slice()));
+    validators
 }
 
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::{testing::MockStorage, Order, StdError, StdResult};
+    use cw_sdk::PubKeyType;
 
     use super::*;
 
@@ -92,10 +309,14 @@ mod tests {
         let accounts = [
             Account::Base {
                 pubkey: b"base1pubkey".into(),
+                pubkey_type: PubKeyType::Secp256k1,
+                account_number: 0,
                 sequence: 0,
             },
             Account::Base {
                 pubkey: b"base2pubkey".into(),
+                pubkey_type: PubKeyType::Secp256k1,
+                account_number: 1,
                 sequence: 123,
             },
             Account::Contract {
@@ -185,4 +406,140 @@ mod tests {
         let err = ACCOUNTS.save(&mut store, &addr, &acct).unwrap_err();
         assert_eq!(err, StdError::generic_err("Violates unique constraint on index"));
     }
+
+    #[test]
+    fn applying_validator_updates() {
+        let validator = |pubkey: &[u8], power| Validator {
+            pubkey: pubkey.into(),
+            power,
+        };
+
+        let validators = vec![validator(b"bbb", 10), validator(b"ccc", 20)];
+        let updates = [
+            validator(b"ddd", 5),
+            validator(b"bbb", 0),
+            validator(b"aaa", 15),
+            validator(b"ccc", 25),
+        ];
+
+        assert_eq!(
+            apply_validator_updates(validators, &updates),
+            vec![validator(b"aaa", 15), validator(b"ccc", 25), validator(b"ddd", 5)],
+        );
+    }
+
+    #[test]
+    fn normalizing_validator_updates() {
+        let validator = |pubkey: &[u8], power| Validator {
+            pubkey: pubkey.into(),
+            power,
+        };
+
+        let updates = vec![validator(b"ccc", 1), validator(b"aaa", 0), validator(b"bbb", 2)];
+        assert_eq!(
+            normalize_validator_updates(updates).unwrap(),
+            vec![validator(b"aaa", 0), validator(b"bbb", 2), validator(b"ccc", 1)],
+        );
+
+        let updates = vec![validator(b"aaa", 1), validator(b"bbb", 2), validator(b"aaa", 3)];
+        let err = normalize_validator_updates(updates).unwrap_err();
+        assert!(matches!(err, Error::DuplicateValidatorUpdate { .. }));
+    }
+
+    #[test]
+    fn loading_latest_validator_set() {
+        let mut store = MockStorage::new();
+
+        assert_eq!(latest_validator_set(&store).unwrap(), vec![]);
+
+        let set_1 = vec![Validator {
+            pubkey: b"aaa".into(),
+            power: 1,
+        }];
+        let set_2 = vec![Validator {
+            pubkey: b"bbb".into(),
+            power: 2,
+        }];
+        let mut chain = Module::Chain.substore_mut(&mut store);
+        VALIDATOR_SETS.save(&mut chain, 1, &set_1).unwrap();
+        VALIDATOR_SETS.save(&mut chain, 2, &set_2).unwrap();
+
+        assert_eq!(latest_validator_set(&store).unwrap(), set_2);
+    }
+
+    #[test]
+    fn loading_validator_set_at_height() {
+        let mut store = MockStorage::new();
+
+        let set_1 = vec![Validator {
+            pubkey: b"aaa".into(),
+            power: 1,
+        }];
+        let set_5 = vec![Validator {
+            pubkey: b"bbb".into(),
+            power: 2,
+        }];
+        let mut chain = Module::Chain.substore_mut(&mut store);
+        VALIDATOR_SETS.save(&mut chain, 1, &set_1).unwrap();
+        VALIDATOR_SETS.save(&mut chain, 5, &set_5).unwrap();
+
+        // heights at which the set didn't change have the last recorded set
+        assert_eq!(validator_set_at(&store, 0).unwrap(), None);
+        assert_eq!(validator_set_at(&store, 1).unwrap(), Some(set_1.clone()));
+        assert_eq!(validator_set_at(&store, 4).unwrap(), Some(set_1));
+        assert_eq!(validator_set_at(&store, 5).unwrap(), Some(set_5.clone()));
+        assert_eq!(validator_set_at(&store, 100).unwrap(), Some(set_5));
+    }
+
+    #[test]
+    fn loading_active_validator_set() {
+        let mut store = MockStorage::new();
+
+        let set_1 = vec![Validator {
+            pubkey: b"aaa".into(),
+            power: 1,
+        }];
+        let set_5 = vec![Validator {
+            pubkey: b"bbb".into(),
+            power: 2,
+        }];
+        let mut chain = Module::Chain.substore_mut(&mut store);
+        VALIDATOR_SETS.save(&mut chain, 1, &set_1).unwrap();
+        VALIDATOR_SETS.save(&mut chain, 5, &set_5).unwrap();
+
+        // the genesis set signs the first blocks, and an update takes effect
+        // two heights after the block that returned it
+        assert_eq!(active_validator_set(&store, 2).unwrap(), set_1);
+        assert_eq!(active_validator_set(&store, 6).unwrap(), set_1);
+        assert_eq!(active_validator_set(&store, 7).unwrap(), set_5);
+    }
+
+    #[test]
+    fn updating_params() {
+        let mut store = MockStorage::new();
+        let gov_addr = address::derive_from_label(GOVERNANCE).unwrap();
+
+        let mut new_params = Params::default();
+        new_params.gas.write_flat = 5000;
+
+        // only the governance contract may update the params
+        let err = update_params(&mut store, &Addr::unchecked("alice"), &new_params).unwrap_err();
+        assert!(matches!(err, Error::NotGovernance { .. }));
+
+        // the conversion from wasm gas must not divide by zero
+        let mut bad_params = new_params.clone();
+        bad_params.gas.wasm_gas_per_sdk_gas = 0;
+        let err = update_params(&mut store, &gov_addr, &bad_params).unwrap_err();
+        assert!(matches!(err, Error::InvalidParams { .. }));
+        assert_eq!(params(&store).unwrap(), Params::default());
+
+        // system calls must be allowed some gas
+        let mut bad_params = new_params.clone();
+        bad_params.system_gas.end_block = 0;
+        let err = update_params(&mut store, &gov_addr, &bad_params).unwrap_err();
+        assert!(matches!(err, Error::InvalidParams { .. }));
+
+        update_params(&mut store, &gov_addr, &new_params).unwrap();
+        assert_eq!(params(&store).unwrap(), new_params);
+    }
 }
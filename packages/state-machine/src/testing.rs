@@ -0,0 +1,143 @@
+//! Helpers shared by the state machine's tests: signing keys, contracts written
+//! in WAT, so that tests don't depend on contracts being compiled to wasm
+//! beforehand, and chains with such contracts deployed at genesis.
+
+use std::{env::temp_dir, path::PathBuf, time::SystemTime};
+
+use cosmwasm_std::{Addr, BlockInfo, Timestamp};
+use cw_sdk::{GenesisState, SdkMsg};
+use cw_store::Store;
+use k256::ecdsa::SigningKey;
+
+use crate::StateMachine;
+
+pub const CHAIN_ID: &str = "replay-1";
+
+/// The response of an entry point that succeeds without doing anything.
+pub const EMPTY_RESPONSE: &str =
+    r#"{"ok":{"messages":[],"attributes":[],"events":[],"data":null}}"#;
+
+/// How many bytes each piece of data of a WAT contract may take up.
+const WAT_DATA_SIZE: u32 = 256;
+
+pub fn temp_path() -> PathBuf {
+    let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos();
+    temp_dir().join(format!("merk-replay-{time}"))
+}
+
+pub fn signing_key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32]).unwrap()
+}
+
+pub fn pubkey(sk: &SigningKey) -> Vec<u8> {
+    sk.verifying_key().to_bytes().to_vec()
+}
+
+pub fn address(sk: &SigningKey) -> Addr {
+    cw_sdk::address::derive_from_pubkey(&pubkey(sk)).unwrap()
+}
+
+/// The block at the given height, five seconds after the previous one.
+pub fn block(height: u64) -> BlockInfo {
+    BlockInfo {
+        height,
+        time: Timestamp::from_seconds(height * 5),
+        chain_id: CHAIN_ID.into(),
+    }
+}
+
+/// Compile a contract made of the given WAT entry points, e.g. `execute`, and
+/// the prelude that every contract needs: its memory, the `allocate` and
+/// `deallocate` exports, and an `instantiate` entry point that returns
+/// `EMPTY_RESPONSE`. The entry points may call the `query_chain` import.
+///
+/// Entry points return the address of the region of their response. The region
+/// of `EMPTY_RESPONSE` is at address 16, and those of the given data, i.e. the
+/// other responses and the requests the contract makes, at 32, 48 and so on.
+pub fn wat_contract(data: &[&str], entry_points: &str) -> Vec<u8> {
+    let data = [EMPTY_RESPONSE].into_iter().chain(data.iter().copied()).collect::<Vec<_>>();
+    let segments = data
+        .iter()
+        .zip(1..)
+        .map(|(data, index)| {
+            assert!(data.len() as u32 <= WAT_DATA_SIZE, "WAT contract data too long: {data}");
+            let offset = index * WAT_DATA_SIZE;
+            format!(
+                "(data (i32.const {}) \"{}\")\n(data (i32.const {offset}) \"{}\")\n",
+                index * 16,
+                wat_region(offset, data),
+                data.replace('"', "\\\""),
+            )
+        })
+        .collect::<String>();
+    let heap = (data.len() as u32 + 1) * WAT_DATA_SIZE;
+
+    let wat = format!(
+        r#"(module
+        (import "env" "query_chain" (func $query_chain (param i32) (result i32)))
+        (memory (export "memory") 1)
+        ;; each call gets a new instance, so memory is never freed
+        (global $heap (mut i32) (i32.const {heap}))
+        {segments}
+        (func (export "interface_version_8"))
+        (func (export "allocate") (param $size i32) (result i32)
+            (local $region i32)
+            (local.set $region (global.get $heap))
+            (i32.store (local.get $region) (i32.add (local.get $region) (i32.const 12)))
+            (i32.store offset=4 (local.get $region) (local.get $size))
+            (i32.store offset=8 (local.get $region) (i32.const 0))
+            (global.set $heap
+                (i32.add (i32.add (local.get $region) (i32.const 12)) (local.get $size)))
+            (local.get $region))
+        (func (export "deallocate") (param i32))
+        (func (export "instantiate") (param i32 i32 i32) (result i32)
+            (i32.const 16))
+        {entry_points})"#
+    );
+    wat::parse_str(wat).unwrap()
+}
+
+/// The data of a region in WAT: the offset, capacity and length of the data it
+/// points to, as little-endian u32s.
+fn wat_region(offset: u32, data: &str) -> String {
+    let len = data.len() as u32;
+    [offset, len, len]
+        .iter()
+        .flat_map(|n| n.to_le_bytes())
+        .map(|byte| format!("\\{byte:02x}"))
+        .collect()
+}
+
+/// A chain whose genesis deploys the given contracts under the given labels,
+/// in order, so that the code of the first is code 1. The genesis messages are
+/// sent by the account of `signing_key(1)`, and the block at height 1 is begun.
+pub fn chain_with_contracts(contracts: Vec<(&str, Vec<u8>)>) -> StateMachine {
+    let msgs = contracts
+        .into_iter()
+        .zip(1..)
+        .flat_map(|((label, code), code_id)| {
+            [
+                SdkMsg::StoreCode {
+                    wasm_byte_code: code.into(),
+                },
+                SdkMsg::Instantiate {
+                    code_id,
+                    msg: serde_json::json!({}),
+                    funds: vec![],
+                    label: label.into(),
+                    admin: None,
+                },
+            ]
+        })
+        .collect();
+    let gen_state = GenesisState {
+        deployer: address(&signing_key(1)).into(),
+        msgs,
+        ..Default::default()
+    };
+
+    let mut sm = StateMachine::new(Store::open(temp_path()).unwrap());
+    sm.init_chain(CHAIN_ID.into(), 1, vec![], gen_state).unwrap();
+    sm.begin_block(block(1)).unwrap();
+    sm
+}
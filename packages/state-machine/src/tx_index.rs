@@ -0,0 +1,219 @@
+//! An index of the txs executed by this node, so that clients can look up txs
+//! and their results without depending on Tendermint's tx indexer.
+//!
+//! The index lives in the store's auxiliary column, so it's not part of the
+//! app hash, and each node maintains its own. The txs of a block are indexed
+//! right before the block is committed. If the node crashes in between, the
+//! block is executed again on restart, and indexed again.
+//!
+//! Two kinds of entries are saved:
+//!
+//! - under `tx/{hash}`, the result of each tx, in JSON;
+//! - under `tx_event/{type}/{key}/{value}`, the hashes of the txs that emitted
+//!   an event with the given attribute, concatenated in the order the txs were
+//!   executed. The type and key are length-prefixed.
+//!
+//! Entries are not removed if a block is rolled back; they're overwritten when
+//! the block is executed again.
+
+use std::collections::BTreeMap;
+
+use cw_sdk::{hash::HASH_LENGTH, ErrorCode, TxResponse};
+use cw_store::Store;
+
+use crate::{
+    error::{Error, Result},
+    TxOutcome,
+};
+
+/// Page sizes for enumerating txs by event.
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+const TX_PREFIX: &[u8] = b"tx/";
+const TX_EVENT_PREFIX: &[u8] = b"tx_event/";
+
+/// The txs of the block being executed, in the order they're executed, to be
+/// indexed when the block is committed.
+#[derive(Debug, Default)]
+pub struct PendingTxs {
+    txs: Vec<(Vec<u8>, TxResponse)>,
+}
+
+impl PendingTxs {
+    /// Record the outcome of the next tx of the block at the given height.
+    pub fn record(&mut self, height: u64, hash: &[u8], outcome: &TxOutcome) {
+        let (code, codespace, log, events) = match &outcome.result {
+            Ok(events) => (0, String::new(), String::new(), events.clone()),
+            Err(err) => {
+                let error_code = ErrorCode::from(err);
+                (error_code.code(), error_code.codespace().into(), err.to_string(), vec![])
+            },
+        };
+
+        let response = TxResponse {
+            hash: hex::encode_upper(hash),
+            height,
+            index: self.txs.len() as u32,
+            code,
+            codespace,
+            log,
+            gas_wanted: outcome.gas_wanted,
+            gas_used: outcome.gas_used,
+            events,
+        };
+        self.txs.push((hash.to_vec(), response));
+    }
+
+    /// The number of recorded txs.
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// The total gas consumed by the recorded txs.
+    pub fn gas_used(&self) -> u64 {
+        self.txs.iter().map(|(_, response)| response.gas_used).sum()
+    }
+
+    /// Save the recorded txs in the index, and clear them.
+    pub fn flush(&mut self, store: &Store) -> Result<()> {
+        let mut values = BTreeMap::new();
+        let mut events: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+
+        for (hash, response) in self.txs.drain(..) {
+            for event in &response.events {
+                for attr in &event.attributes {
+                    let key = event_key(&event.ty, &attr.key, &attr.value);
+                    events.entry(key).or_default().extend(&hash);
+                }
+            }
+            values.insert(tx_key(&hash), serde_json::to_vec(&response)?);
+        }
+
+        // append to the hashes indexed by earlier blocks, unless the block has
+        // been executed before, i.e. re-executed after a rollback or a crash
+        for (key, hashes) in events {
+            let mut indexed = store.get_aux(&key)?.unwrap_or_default();
+            for hash in hashes.chunks(HASH_LENGTH) {
+                if !indexed.chunks(HASH_LENGTH).any(|indexed_hash| indexed_hash == hash) {
+                    indexed.extend(hash);
+                }
+            }
+            values.insert(key, indexed);
+        }
+
+        store.put_aux(values).map_err(Error::from)
+    }
+}
+
+fn tx_key(hash: &[u8]) -> Vec<u8> {
+    [TX_PREFIX, hash].concat()
+}
+
+fn event_key(ty: &str, key: &str, value: &str) -> Vec<u8> {
+    let mut bytes = TX_EVENT_PREFIX.to_vec();
+    for part in [ty, key] {
+        bytes.extend((part.len() as u16).to_be_bytes());
+        bytes.extend(part.as_bytes());
+    }
+    bytes.extend(value.as_bytes());
+    bytes
+}
+
+fn decode_hash(hash: &str) -> Result<Vec<u8>> {
+    hex::decode(hash).map_err(|_| Error::tx_not_found(hash))
+}
+
+pub fn tx(store: &Store, hash: String) -> Result<TxResponse> {
+    let Some(bytes) = store.get_aux(&tx_key(&decode_hash(&hash)?))? else {
+        return Err(Error::tx_not_found(hash));
+    };
+    serde_json::from_slice(&bytes).map_err(Error::from)
+}
+
+/// Enumerate the txs that emitted an event with the given attribute. The event
+/// is given as `{type}.{key}`, as in Tendermint's `tx_search` queries.
+pub fn txs_by_event(
+    store: &Store,
+    event: String,
+    value: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<TxResponse>> {
+    let Some((ty, key)) = event.split_once('.') else {
+        return Ok(vec![]);
+    };
+    let hashes = store.get_aux(&event_key(ty, key, &value))?.unwrap_or_default();
+    let hashes = hashes.chunks(HASH_LENGTH).collect::<Vec<_>>();
+
+    let start = match start_after {
+        Some(start_after) => {
+            let start_after = decode_hash(&start_after)?;
+            hashes.iter().position(|hash| *hash == start_after).map_or(hashes.len(), |i| i + 1)
+        },
+        None => 0,
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    hashes
+        .into_iter()
+        .skip(start)
+        .take(limit)
+        .map(|hash| tx(store, hex::encode_upper(hash)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, time::SystemTime};
+
+    use cosmwasm_std::Event;
+
+    use super::*;
+
+    fn outcome(events: Vec<Event>) -> TxOutcome {
+        TxOutcome {
+            gas_wanted: 100,
+            gas_used: 50,
+            result: Ok(events),
+        }
+    }
+
+    #[test]
+    fn indexing_txs() {
+        let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos();
+        let store = Store::open(temp_dir().join(format!("merk-tx-index-{time}"))).unwrap();
+
+        let transfer = Event::new("wasm").add_attribute("_contract_address", "bank");
+        let hashes = [[1; HASH_LENGTH], [2; HASH_LENGTH], [3; HASH_LENGTH]];
+
+        // index the same block twice, as if it was executed again after a crash
+        for _ in 0..2 {
+            let mut pending = PendingTxs::default();
+            pending.record(1, &hashes[0], &outcome(vec![transfer.clone()]));
+            pending.record(1, &hashes[1], &TxOutcome::failed(Error::NoSigners));
+            pending.record(1, &hashes[2], &outcome(vec![transfer.clone()]));
+            pending.flush(&store).unwrap();
+        }
+
+        let failed = tx(&store, hex::encode(hashes[1])).unwrap();
+        assert_eq!(failed.index, 1);
+        assert_eq!(failed.code, ErrorCode::Unauthorized.code());
+        assert!(tx(&store, hex::encode([4; HASH_LENGTH])).is_err());
+
+        // the txs are found once each, in order
+        let event = "wasm._contract_address".to_string();
+        let txs = txs_by_event(&store, event.clone(), "bank".into(), None, None).unwrap();
+        let indexes = txs.iter().map(|tx| tx.index).collect::<Vec<_>>();
+        assert_eq!(indexes, [0, 2]);
+
+        let start_after = Some(txs[0].hash.clone());
+        let txs = txs_by_event(&store, event, "bank".into(), start_after, None).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].index, 2);
+    }
+}
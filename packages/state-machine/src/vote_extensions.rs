@@ -0,0 +1,236 @@
+//! Vote extensions, which let validators attach arbitrary data to their votes,
+//! e.g. the prices observed by an oracle.
+//!
+//! Extensions are produced and verified by a handler that the operator sets:
+//! either a contract that implements the `vote_extensions` sudo hooks, or a
+//! native handler. All validators must set the same handler, since proposals
+//! are checked using it.
+//!
+//! The proposer of a block aggregates the extensions of the votes for the
+//! previous block into a pseudo-tx at the start of the block. Validators accept
+//! the proposal only if each extension in it is signed by the validator that
+//! attached it and passes verification, and the votes carry more than two
+//! thirds of the power of the validator set that voted. When the block is
+//! executed, the extensions are handed to the handler before any tx is.
+
+use cosmwasm_crypto::ed25519_verify;
+use cosmwasm_std::{
+    to_binary, BlockInfo, ContractInfo, ContractResult, Env, Event, Response, Storage,
+};
+use cw_sdk::{
+    address, canonical::to_canonical_vec, hash::sha256, proto::vote_extension_sign_bytes,
+    vote_extensions::SudoMsg, ExtendedVote, Validator,
+};
+
+use crate::{
+    error::{Error, Result},
+    execute,
+    gas::{SystemCall, SystemGas},
+};
+
+/// Prefix of the pseudo-tx that carries the aggregated vote extensions, which
+/// can't be mistaken for a JSON or protobuf encoded tx.
+pub const VOTE_EXTENSIONS_TX_PREFIX: &[u8] = b"\x00vote_extensions";
+
+/// Vote extensions handled natively, instead of by a contract.
+pub trait VoteExtensions {
+    /// Return the extension to attach to this node's validator's vote, or an
+    /// empty one to not extend the vote.
+    fn extend_vote(&self, store: &dyn Storage, block: &BlockInfo) -> Result<Vec<u8>>;
+
+    /// Return error if another validator's vote extension is invalid.
+    fn verify_vote_extension(
+        &self,
+        store: &dyn Storage,
+        block: &BlockInfo,
+        validator: &[u8],
+        extension: &[u8],
+    ) -> Result<()>;
+
+    /// Process the extensions of the votes for the previous block.
+    fn process_vote_extensions(
+        &self,
+        store: &mut dyn Storage,
+        block: &BlockInfo,
+        votes: &[ExtendedVote],
+    ) -> Result<Vec<Event>>;
+}
+
+/// Extends and verifies votes, and processes the extensions.
+pub enum VoteExtensionHandler {
+    /// A contract, referred to by its label, that implements the
+    /// `vote_extensions` sudo hooks
+    Contract(String),
+
+    Native(Box<dyn VoteExtensions>),
+}
+
+/// Encode the aggregated vote extensions as the pseudo-tx that is included at
+/// the start of a block.
+pub fn encode_votes_tx(votes: &[ExtendedVote]) -> Result<Vec<u8>> {
+    let mut tx = VOTE_EXTENSIONS_TX_PREFIX.to_vec();
+    tx.extend(to_canonical_vec(&votes)?);
+    Ok(tx)
+}
+
+/// Decode the aggregated vote extensions from a pseudo-tx. Return `None` if the
+/// tx isn't one.
+pub fn decode_votes_tx(tx: &[u8]) -> Option<Result<Vec<ExtendedVote>>> {
+    let votes = tx.strip_prefix(VOTE_EXTENSIONS_TX_PREFIX)?;
+    Some(serde_json::from_slice(votes).map_err(Error::invalid_vote_extensions))
+}
+
+/// Return a validator's consensus address, i.e. the first 20 bytes of the
+/// SHA-256 hash of its consensus public key.
+pub fn consensus_address(pubkey: &[u8]) -> Vec<u8> {
+    sha256(pubkey)[..20].to_vec()
+}
+
+/// Check that each vote is by a member of the validator set that voted at the
+/// given height, with its power, and that its extension is signed by it. Then
+/// check that the votes carry more than two thirds of the set's power.
+pub fn check_votes(
+    validators: &[Validator],
+    chain_id: &str,
+    height: u64,
+    votes: &[ExtendedVote],
+) -> Result<()> {
+    let mut voted = vec![false; validators.len()];
+    let mut voted_power = 0u128;
+
+    for vote in votes {
+        let index = validators
+            .iter()
+            .position(|val| consensus_address(&val.pubkey) == vote.validator.as_slice())
+            .ok_or_else(|| Error::invalid_vote_extensions("vote by a non-validator"))?;
+
+        if voted[index] {
+            return Err(Error::invalid_vote_extensions("more than one vote by a validator"));
+        }
+        if validators[index].power != vote.power {
+            return Err(Error::invalid_vote_extensions("vote power doesn't match"));
+        }
+
+        let sign_bytes = vote_extension_sign_bytes(&vote.extension, height, vote.round, chain_id);
+        let valid =
+            ed25519_verify(&sign_bytes, &vote.extension_signature, &validators[index].pubkey)
+                .unwrap_or(false);
+        if !valid {
+            return Err(Error::invalid_vote_extensions("invalid extension signature"));
+        }
+
+        voted[index] = true;
+        voted_power += vote.power as u128;
+    }
+
+    let total_power = validators.iter().map(|val| val.power as u128).sum::<u128>();
+    if voted_power * 3 <= total_power * 2 {
+        return Err(Error::invalid_vote_extensions("votes don't carry enough power"));
+    }
+
+    Ok(())
+}
+
+/// Sudo the vote extensions contract. State changes made by the call are
+/// flushed to the store only if the call succeeds.
+pub fn sudo_contract<S>(
+    store: S,
+    label: &str,
+    block: &BlockInfo,
+    msg: &SudoMsg,
+    gas: &SystemGas,
+) -> Result<(Response, S)>
+where
+    S: Storage + 'static,
+{
+    let env = Env {
+        block: block.clone(),
+        transaction: None,
+        contract: ContractInfo {
+            address: address::derive_from_label(label)?,
+        },
+    };
+    let msg = to_binary(msg)?;
+
+    let (result, store) =
+        execute::sudo_contract(store, &env, &msg, SystemCall::VoteExtensions, gas)?;
+
+    match result {
+        ContractResult::Ok(response) => Ok((response, store)),
+        ContractResult::Err(err) => Err(Error::Contract(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_zebra::{SigningKey, VerificationKey};
+
+    use super::*;
+
+    const CHAIN_ID: &str = "dev-1";
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from([seed; 32])
+    }
+
+    fn validator(seed: u8, power: u64) -> Validator {
+        Validator {
+            pubkey: <[u8; 32]>::from(VerificationKey::from(&signing_key(seed))).to_vec().into(),
+            power,
+        }
+    }
+
+    fn vote(seed: u8, val: &Validator) -> ExtendedVote {
+        let extension = b"price";
+        let sign_bytes = vote_extension_sign_bytes(extension, 1, 0, CHAIN_ID);
+        ExtendedVote {
+            validator: consensus_address(&val.pubkey).into(),
+            power: val.power,
+            round: 0,
+            extension: extension.to_vec().into(),
+            extension_signature: <[u8; 64]>::from(signing_key(seed).sign(&sign_bytes))
+                .to_vec()
+                .into(),
+        }
+    }
+
+    #[test]
+    fn checking_votes() {
+        let vals = [validator(1, 30), validator(2, 30), validator(3, 40)];
+        let check = |votes: &[ExtendedVote]| check_votes(&vals, CHAIN_ID, 1, votes);
+
+        // exactly two thirds isn't enough
+        check(&[vote(1, &vals[0]), vote(3, &vals[2])]).unwrap();
+        let err = check(&[vote(1, &vals[0]), vote(2, &vals[1])]).unwrap_err();
+        assert!(matches!(err, Error::InvalidVoteExtensions { .. }));
+
+        // votes can't be counted twice, or claim a different power
+        let err = check(&[vote(3, &vals[2]), vote(3, &vals[2])]).unwrap_err();
+        assert!(matches!(err, Error::InvalidVoteExtensions { .. }));
+        let mut forged = vote(3, &vals[2]);
+        forged.power = 100;
+        let err = check(&[forged]).unwrap_err();
+        assert!(matches!(err, Error::InvalidVoteExtensions { .. }));
+
+        // extensions must be signed by the validator, for the same height
+        let err = check(&[vote(1, &vals[0]), vote(1, &vals[2])]).unwrap_err();
+        assert!(matches!(err, Error::InvalidVoteExtensions { .. }));
+        let mut forged = vote(3, &vals[2]);
+        forged.extension = b"cheap".to_vec().into();
+        let err = check(&[vote(1, &vals[0]), forged]).unwrap_err();
+        assert!(matches!(err, Error::InvalidVoteExtensions { .. }));
+        let err = check_votes(&vals, CHAIN_ID, 2, &[vote(1, &vals[0]), vote(3, &vals[2])])
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidVoteExtensions { .. }));
+    }
+
+    #[test]
+    fn encoding_votes_tx() {
+        let votes = vec![vote(1, &validator(1, 30))];
+        let tx = encode_votes_tx(&votes).unwrap();
+        assert_eq!(decode_votes_tx(&tx).unwrap().unwrap(), votes);
+
+        // ordinary txs aren't mistaken for it
+        assert!(decode_votes_tx(br#"{"body":{}}"#).is_none());
+    }
+}
@@ -0,0 +1,102 @@
+//! A minimal binary encoding for batches of Merk ops.
+//!
+//! Batches are written to the Merk store's auxiliary column, e.g. as a
+//! write-ahead log entry. Each op in the batch is encoded as:
+//!
+//! ```plain
+//! key_len (u32, big endian) | key | tag (u8) | [value_len (u32, big endian) | value]
+//! ```
+//!
+//! where tag is `0` for `Op::Put` and `1` for `Op::Delete`. The value length
+//! and value are only present for puts.
+
+use merk::Op;
+
+const TAG_PUT: u8 = 0;
+const TAG_DELETE: u8 = 1;
+
+/// Encode a batch of ops into bytes.
+pub(crate) fn encode_batch(batch: &[(Vec<u8>, Op)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (key, op) in batch {
+        write_slice(&mut bytes, key);
+        match op {
+            Op::Put(value) => {
+                bytes.push(TAG_PUT);
+                write_slice(&mut bytes, value);
+            },
+            Op::Delete => bytes.push(TAG_DELETE),
+        }
+    }
+    bytes
+}
+
+/// Decode bytes into a batch of ops. Return `None` if the bytes are malformed.
+pub(crate) fn decode_batch(mut bytes: &[u8]) -> Option<Vec<(Vec<u8>, Op)>> {
+    let mut batch = vec![];
+    while !bytes.is_empty() {
+        let key = read_slice(&mut bytes)?;
+        let (tag, rest) = bytes.split_first()?;
+        bytes = rest;
+        let op = match *tag {
+            TAG_PUT => Op::Put(read_slice(&mut bytes)?),
+            TAG_DELETE => Op::Delete,
+            _ => return None,
+        };
+        batch.push((key, op));
+    }
+    Some(batch)
+}
+
+fn write_slice(bytes: &mut Vec<u8>, slice: &[u8]) {
+    let len = u32::try_from(slice.len()).expect("[cw-store]: slice too long to be encoded");
+    bytes.extend(len.to_be_bytes());
+    bytes.extend_from_slice(slice);
+}
+
+fn read_slice(bytes: &mut &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (len_bytes, rest) = bytes.split_at(4);
+    let len = u32::from_be_bytes(len_bytes.try_into().ok()?) as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (slice, rest) = rest.split_at(len);
+    *bytes = rest;
+    Some(slice.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding_decoding() {
+        let batch = vec![
+            (b"key1".to_vec(), Op::Put(b"value1".to_vec())),
+            (b"key2".to_vec(), Op::Delete),
+            (b"key3".to_vec(), Op::Put(vec![])),
+        ];
+
+        let bytes = encode_batch(&batch);
+        let decoded = decode_batch(&bytes).unwrap();
+
+        // merk::Op doesn't implement PartialEq, so we compare the debug strings
+        assert_eq!(format!("{decoded:?}"), format!("{batch:?}"));
+    }
+
+    #[test]
+    fn decoding_malformed() {
+        let bytes = encode_batch(&[(b"key1".to_vec(), Op::Put(b"value1".to_vec()))]);
+
+        // truncated value
+        assert!(decode_batch(&bytes[..bytes.len() - 1]).is_none());
+
+        // unknown tag
+        let mut bytes = encode_batch(&[(b"key1".to_vec(), Op::Delete)]);
+        *bytes.last_mut().unwrap() = 69;
+        assert!(decode_batch(&bytes).is_none());
+    }
+}
@@ -1,14 +1,23 @@
 #![feature(btree_drain_filter)]
 
 mod cache;
+mod codec;
 mod helpers;
 pub mod iterators;
+pub mod metrics;
 pub mod prefix;
 mod share;
+mod snapshot;
 mod store;
 
 pub use crate::cache::Cached;
+pub use crate::prefix::{PrefixedStore, ReadonlyPrefixedStore};
 pub use crate::share::Shared;
-pub use crate::store::{PendingStoreWrapper, Store, StoreBase, StoreWrapper};
+pub use crate::snapshot::Snapshot;
+pub use crate::store::{
+    compact, undo_path, CommitInfo, PendingStoreWrapper, Store, StoreBase, StoreWrapper,
+    LAST_COMMIT_KEY, WAL_KEY,
+};
 
 pub use merk::Error as MerkError;
+pub use rocksdb::Error as RocksDbError;
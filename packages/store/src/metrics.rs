@@ -0,0 +1,177 @@
+//! Counters and histograms for instrumenting the store.
+//!
+//! The store is single-threaded, so the metrics use `Cell`s instead of atomics.
+//! They can be rendered in the Prometheus text exposition format:
+//! https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use std::{cell::Cell, fmt::Write, time::Duration};
+
+/// A monotonically increasing counter.
+#[derive(Debug, Default)]
+pub struct Counter(Cell<u64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, n: u64) {
+        self.0.set(self.0.get() + n);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+/// A histogram of durations, with fixed bucket upper bounds in seconds.
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    buckets: Vec<Cell<u64>>,
+    sum: Cell<f64>,
+    count: Cell<u64>,
+}
+
+/// Default bucket upper bounds, in seconds.
+pub const DEFAULT_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUCKETS)
+    }
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: bounds.iter().map(|_| Cell::new(0)).collect(),
+            sum: Cell::new(0.0),
+            count: Cell::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            if secs <= *bound {
+                bucket.set(bucket.get() + 1);
+            }
+        }
+        self.sum.set(self.sum.get() + secs);
+        self.count.set(self.count.get() + 1);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.get()
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum.get()
+    }
+}
+
+/// Metrics collected by the store.
+#[derive(Debug, Default)]
+pub struct StoreMetrics {
+    /// Number of reads of single keys
+    pub reads: Counter,
+    /// Total byte length of values read
+    pub read_bytes: Counter,
+    /// Number of reads that were served by the pending ops, without accessing
+    /// the Merk tree
+    pub cache_hits: Counter,
+    /// Number of reads that were not served by the pending ops, and thus
+    /// accessed the Merk tree
+    pub cache_misses: Counter,
+    /// Number of writes (including deletes)
+    pub writes: Counter,
+    /// Total byte length of keys and values written
+    pub write_bytes: Counter,
+    /// Number of iterators created
+    pub iterations: Counter,
+    /// Number of ops committed to the Merk tree
+    pub committed_ops: Counter,
+    /// Time spent committing and flushing the pending ops to disk
+    pub flush_duration: Histogram,
+}
+
+impl StoreMetrics {
+    /// Record a read of a single key, which returned the given value.
+    pub fn record_read(&self, value: Option<&Vec<u8>>) {
+        self.reads.inc();
+        self.read_bytes.inc_by(value.map_or(0, |value| value.len() as u64));
+    }
+
+    /// Record a write of a single key. A value of `None` means a delete.
+    pub fn record_write(&self, key: &[u8], value: Option<&[u8]>) {
+        self.writes.inc();
+        self.write_bytes.inc_by((key.len() + value.map_or(0, <[u8]>::len)) as u64);
+    }
+
+    /// Render the metrics in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let counters = [
+            ("reads_total", "Number of reads of single keys", &self.reads),
+            ("read_bytes_total", "Total byte length of values read", &self.read_bytes),
+            ("cache_hits_total", "Number of reads served by pending ops", &self.cache_hits),
+            ("cache_misses_total", "Number of reads served by the Merk tree", &self.cache_misses),
+            ("writes_total", "Number of writes, including deletes", &self.writes),
+            ("write_bytes_total", "Total byte length of keys and values set", &self.write_bytes),
+            ("iterations_total", "Number of iterators created", &self.iterations),
+            ("committed_ops_total", "Number of ops committed to Merk", &self.committed_ops),
+        ];
+        for (name, help, counter) in counters {
+            writeln!(out, "# HELP cw_store_{name} {help}").unwrap();
+            writeln!(out, "# TYPE cw_store_{name} counter").unwrap();
+            writeln!(out, "cw_store_{name} {}", counter.get()).unwrap();
+        }
+
+        let name = "cw_store_flush_duration_seconds";
+        let hist = &self.flush_duration;
+        writeln!(out, "# HELP {name} Time spent committing and flushing pending ops").unwrap();
+        writeln!(out, "# TYPE {name} histogram").unwrap();
+        for (bound, bucket) in hist.bounds.iter().zip(&hist.buckets) {
+            writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {}", bucket.get()).unwrap();
+        }
+        writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", hist.count()).unwrap();
+        writeln!(out, "{name}_sum {}", hist.sum()).unwrap();
+        writeln!(out, "{name}_count {}", hist.count()).unwrap();
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observing_histogram() {
+        let hist = Histogram::new(&[0.01, 0.1, 1.0]);
+
+        hist.observe(Duration::from_millis(5));
+        hist.observe(Duration::from_millis(50));
+        hist.observe(Duration::from_millis(5000));
+
+        let buckets = hist.buckets.iter().map(Cell::get).collect::<Vec<_>>();
+        assert_eq!(buckets, vec![1, 2, 2]);
+        assert_eq!(hist.count(), 3);
+    }
+
+    #[test]
+    fn rendering() {
+        let metrics = StoreMetrics::default();
+        metrics.reads.inc_by(3);
+        metrics.flush_duration.observe(Duration::from_millis(2));
+
+        let text = metrics.render();
+        assert!(text.contains("cw_store_reads_total 3\n"));
+        assert!(text.contains("cw_store_flush_duration_seconds_bucket{le=\"0.001\"} 0\n"));
+        assert!(text.contains("cw_store_flush_duration_seconds_bucket{le=\"0.005\"} 1\n"));
+        assert!(text.contains("cw_store_flush_duration_seconds_count 1\n"));
+    }
+}
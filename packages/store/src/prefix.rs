@@ -1,6 +1,110 @@
 //! Contents of this file are adapted from cw-storage-plus:
 //! https://github.com/CosmWasm/cw-multi-test/blob/v0.16.0/src/prefixed_storage.rs
 
+use cosmwasm_std::{Order, Record, Storage};
+
+/// A handle to the substore under the given prefix of a root store.
+///
+/// Keys are prefixed with the namespace when written to the root store, and
+/// trimmed when read back, so the substore appears as its own keyspace.
+pub struct PrefixedStore<'a> {
+    storage: &'a mut dyn Storage,
+    namespace: Vec<u8>,
+}
+
+impl<'a> PrefixedStore<'a> {
+    pub fn new(storage: &'a mut dyn Storage, namespace: &[u8]) -> Self {
+        Self {
+            storage,
+            namespace: namespace.to_vec(),
+        }
+    }
+}
+
+impl<'a> Storage for PrefixedStore<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(&concat(&self.namespace, key))
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.storage.set(&concat(&self.namespace, key), value);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.storage.remove(&concat(&self.namespace, key));
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        range_with_prefix(self.storage, &self.namespace, start, end, order)
+    }
+}
+
+/// A read-only handle to the substore under the given prefix of a root store.
+pub struct ReadonlyPrefixedStore<'a> {
+    storage: &'a dyn Storage,
+    namespace: Vec<u8>,
+}
+
+impl<'a> ReadonlyPrefixedStore<'a> {
+    pub fn new(storage: &'a dyn Storage, namespace: &[u8]) -> Self {
+        Self {
+            storage,
+            namespace: namespace.to_vec(),
+        }
+    }
+}
+
+impl<'a> Storage for ReadonlyPrefixedStore<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(&concat(&self.namespace, key))
+    }
+
+    fn set(&mut self, _key: &[u8], _value: &[u8]) {
+        panic!("[cw-store]: can't write to a read-only prefixed store");
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        panic!("[cw-store]: can't remove from a read-only prefixed store");
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        range_with_prefix(self.storage, &self.namespace, start, end, order)
+    }
+}
+
+fn range_with_prefix<'a>(
+    storage: &'a dyn Storage,
+    namespace: &[u8],
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+    order: Order,
+) -> Box<dyn Iterator<Item = Record> + 'a> {
+    let start = match start {
+        Some(s) => concat(namespace, s),
+        None => namespace.to_vec(),
+    };
+    let end = match end {
+        Some(e) => concat(namespace, e),
+        None => namespace_upper_bound(namespace),
+    };
+    let namespace = namespace.to_vec();
+    Box::new(
+        storage
+            .range(Some(&start), Some(&end), order)
+            .map(move |(k, v)| (trim(&namespace, &k), v)),
+    )
+}
+
 pub fn concat(a: &[u8], b: &[u8]) -> Vec<u8> {
     let mut c = a.to_vec();
     c.extend_from_slice(b);
@@ -27,3 +131,35 @@ pub fn namespace_upper_bound(input: &[u8]) -> Vec<u8> {
     }
     copy
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+
+    #[test]
+    fn prefixed_storage() {
+        let mut storage = MockStorage::new();
+        storage.set(b"bar/key", b"0");
+
+        let mut foo = PrefixedStore::new(&mut storage, b"foo/");
+        foo.set(b"key1", b"1");
+        foo.set(b"key2", b"2");
+        assert_eq!(foo.get(b"key1"), Some(b"1".to_vec()));
+
+        let items = foo.range(None, None, Order::Descending).collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![(b"key2".to_vec(), b"2".to_vec()), (b"key1".to_vec(), b"1".to_vec())],
+        );
+
+        foo.remove(b"key2");
+        assert_eq!(storage.get(b"foo/key1"), Some(b"1".to_vec()));
+        assert_eq!(storage.get(b"foo/key2"), None);
+
+        let bar = ReadonlyPrefixedStore::new(&storage, b"bar/");
+        let items = bar.range(None, None, Order::Ascending).collect::<Vec<_>>();
+        assert_eq!(items, vec![(b"key".to_vec(), b"0".to_vec())]);
+    }
+}
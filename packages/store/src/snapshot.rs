@@ -0,0 +1,83 @@
+use std::iter;
+
+use cosmwasm_std::{Order, Record, Storage};
+use im::OrdMap;
+use merk::{Merk, Op};
+
+use crate::iterators::{range_bounds, MerkIter};
+
+/// An in-memory copy of the committed state, which can be shared across
+/// threads, so that queries can be served while the next block is executed.
+///
+/// The copy is an immutable map with structural sharing: cloning a snapshot
+/// is cheap, and applying a committed batch to it only copies the nodes along
+/// the paths of the keys changed, so the snapshots held by queries in flight
+/// are left untouched.
+///
+/// The trade-off is that the whole state is held in memory, on top of the Merk
+/// store, which is why snapshots are only kept if enabled.
+#[derive(Clone, Default)]
+pub struct Snapshot {
+    map: OrdMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Snapshot {
+    /// Copy all key-value pairs in the Merk tree.
+    pub(crate) fn load(merk: &Merk) -> Self {
+        Self {
+            map: MerkIter::new(merk, None, None, Order::Ascending).collect(),
+        }
+    }
+
+    /// Apply a batch of ops that has just been committed to the Merk tree.
+    pub(crate) fn apply(&mut self, batch: &[(Vec<u8>, Op)]) {
+        for (key, op) in batch {
+            match op {
+                Op::Put(value) => {
+                    self.map.insert(key.clone(), value.clone());
+                },
+                Op::Delete => {
+                    self.map.remove(key);
+                },
+            }
+        }
+    }
+}
+
+impl Storage for Snapshot {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.map.get(key).cloned()
+    }
+
+    fn set(&mut self, _key: &[u8], _value: &[u8]) {
+        panic!("[cw-store]: `set` method invoked on read-only snapshot");
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        panic!("[cw-store]: `remove` method invoked on read-only snapshot");
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Box::new(iter::empty());
+            }
+        }
+
+        // unlike the Merk store, the map isn't behind a RefCell, so the items
+        // can be iterated lazily rather than collected in memory first
+        let raw = self
+            .map
+            .range(range_bounds(start, end))
+            .map(|(key, value)| (key.clone(), value.clone()));
+        match order {
+            Order::Ascending => Box::new(raw),
+            Order::Descending => Box::new(raw.rev()),
+        }
+    }
+}
@@ -1,31 +1,109 @@
 use std::{
     cell::{RefCell, Ref, RefMut},
     collections::BTreeMap,
-    iter,
-    path::Path,
+    ffi::OsString,
+    fs, iter, mem,
+    path::{Path, PathBuf},
     rc::Rc,
+    time::Instant,
 };
 
 use cosmwasm_std::{Order, Record, Storage};
 use cw_sdk::hash::HASH_LENGTH;
 use merk::{Merk, Op};
+use tracing::warn;
 
 use crate::{
+    codec::{decode_batch, encode_batch},
     helpers::must_get,
     iterators::{range_bounds, MemIter, MergedIter, MerkIter},
+    metrics::StoreMetrics,
+    snapshot::Snapshot,
     MerkError,
 };
 
+/// Key in Merk's auxiliary column under which the write-ahead log is saved.
+///
+/// During the ABCI "Commit" request, the batch of ops to be committed is first
+/// persisted under this key, before being applied to the Merk tree. Once the
+/// batch has been applied, the log is deleted in the same atomic write.
+///
+/// If the node crashes in between, the log is replayed the next time the store
+/// is opened. Since replaying puts and deletes is idempotent, it is safe to do
+/// so even if the batch had in fact been applied.
+pub const WAL_KEY: &[u8] = b"wal";
+
+/// Suffix of the directory, next to the database, holding the undo checkpoint.
+///
+/// Right before a batch is applied to the Merk tree, a RocksDB checkpoint of the
+/// database is taken here, i.e. a copy of it that hard links its files. This
+/// allows the last commit to be rolled back to exactly the state before it,
+/// including the shape of the tree, which reapplying the prior values of the
+/// keys touched wouldn't restore, as it depends on the order of insertions and
+/// deletions. Only the last commit is retained, so at most one block can be
+/// rolled back.
+const UNDO_SUFFIX: &str = ".undo";
+
+/// Suffix of the directory in which the undo checkpoint is taken, before it's
+/// moved into place, so that a crash half way through doesn't leave behind a
+/// partial checkpoint.
+const UNDO_TMP_SUFFIX: &str = ".undo-tmp";
+
+/// Suffix of the directory to which the database is moved while it's being
+/// rolled back, before it's deleted.
+const ROLLED_BACK_SUFFIX: &str = ".rolled-back";
+
+/// Key in Merk's auxiliary column under which the height and app hash of the
+/// last committed block are saved, so that they can be reported to Tendermint
+/// on startup exactly as they were when the block was committed.
+pub const LAST_COMMIT_KEY: &[u8] = b"last_commit";
+
+/// The height and app hash of a committed block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub height: u64,
+    pub app_hash: [u8; HASH_LENGTH],
+}
+
+impl CommitInfo {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.height.to_be_bytes().to_vec();
+        bytes.extend(self.app_hash);
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let (height, app_hash) = bytes.split_first_chunk::<8>()?;
+        Some(Self {
+            height: u64::from_be_bytes(*height),
+            app_hash: app_hash.try_into().ok()?,
+        })
+    }
+}
+
 pub struct StoreBase {
     /// The Merk tree which holds the key-value data.
     pub(crate) merk: Merk,
 
+    /// The path of the database, next to which the undo checkpoint is kept
+    pub(crate) path: PathBuf,
+
+    /// Whether an undo checkpoint is taken before each commit
+    pub(crate) keep_undo: bool,
+
     /// Database operations from by BeginBlock, DeliverTx, and EndBlock
     /// executions, but not yet committed to the Merk store.
     ///
     /// Upon an ABCI "Commit" request, these ops will be committed to the Merk
     /// store, and this map cleared.
     pub(crate) pending_ops: BTreeMap<Vec<u8>, Op>,
+
+    /// Counters and histograms of store operations, for diagnosing slow blocks.
+    pub(crate) metrics: StoreMetrics,
+
+    /// An in-memory copy of the committed state, kept up to date on every
+    /// commit, if enabled.
+    pub(crate) snapshot: Option<Snapshot>,
 }
 
 /// Wrap a storage object inside an `Rc<RefCell<T>>` so that it can be shared as
@@ -39,10 +117,25 @@ pub struct StoreBase {
 pub struct Store(Rc<RefCell<StoreBase>>);
 
 impl Store {
+    /// Open the Merk store at the given path. If a write-ahead log is found,
+    /// i.e. the node crashed during a commit, replay it before returning.
+    ///
+    /// Likewise, if the node crashed while rolling back the last commit, finish
+    /// the rollback first.
     pub fn open(path: impl AsRef<Path>) -> Result<Self, MerkError> {
+        let path = path.as_ref().to_path_buf();
+        finish_rollback(&path)?;
+
+        let mut merk = Merk::open(&path)?;
+        recover(&mut merk)?;
+
         let base = StoreBase {
-            merk: Merk::open(path)?,
+            merk,
+            path,
+            keep_undo: true,
             pending_ops: BTreeMap::new(),
+            metrics: StoreMetrics::default(),
+            snapshot: None,
         };
         Ok(Self(Rc::new(RefCell::new(base))))
     }
@@ -59,6 +152,31 @@ impl Store {
         self.0.borrow_mut()
     }
 
+    /// Start keeping an in-memory snapshot of the committed state, which can be
+    /// shared with other threads, e.g. to serve queries. The state is copied
+    /// once, then the snapshot is updated on every commit.
+    pub fn enable_snapshot(&self) {
+        let mut ref_mut = self.borrow_mut();
+        let snapshot = Snapshot::load(&ref_mut.merk);
+        ref_mut.snapshot = Some(snapshot);
+    }
+
+    /// Return the snapshot of the committed state, if enabled.
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        self.borrow().snapshot.clone()
+    }
+
+    /// Set whether an undo checkpoint is taken before each commit, so that the
+    /// last commit can be rolled back. Enabled by default.
+    pub fn set_keep_undo(&self, keep_undo: bool) {
+        self.borrow_mut().keep_undo = keep_undo;
+    }
+
+    /// Render the store's metrics in Prometheus text exposition format.
+    pub fn render_metrics(&self) -> String {
+        self.borrow().metrics.render()
+    }
+
     /// Derive the root hash of the blockchain state.
     pub fn root_hash(&self) -> [u8; HASH_LENGTH] {
         self.borrow().merk.root_hash()
@@ -67,7 +185,19 @@ impl Store {
     /// Commit the pending changes to the underlying Merk store.
     /// This also writes the changes to disk, so should only be called during
     /// ABCI "Commit" requests.
+    ///
+    /// The commit happens in two phases, after the undo checkpoint is taken if
+    /// enabled:
+    ///
+    /// 1. the batch is written to the write-ahead log, which is flushed to disk;
+    /// 2. the batch is applied to the Merk tree, and the write-ahead log deleted
+    ///    in the same atomic write, which is again flushed to disk.
+    ///
+    /// This way, once this function returns, the changes are guaranteed to
+    /// survive a crash, and a crash half way through can be recovered from
+    /// when the store is opened again.
     pub fn commit(&self) -> Result<(), MerkError> {
+        let start = Instant::now();
         let mut ref_mut = self.borrow_mut();
 
         // use `drain_filter` to clear the map and take ownership of all items.
@@ -75,9 +205,141 @@ impl Store {
         // it'd be great if BTreeMap has a simple `drain_all` method
         let batch: Vec<_> = ref_mut.pending_ops.drain_filter(|_, _| true).collect();
 
-        // we know the ops are sorted by keys (as they are collected from a
-        // btreemap), so we skip the checking step
-        unsafe { ref_mut.merk.apply_unchecked(&batch, &[]) }
+        // a checkpoint left from before would be stale once the batch is applied
+        if ref_mut.keep_undo {
+            take_undo_checkpoint(&ref_mut.merk, &ref_mut.path)?;
+        } else {
+            remove_undo_checkpoint(&ref_mut.path)?;
+        }
+
+        // phase 1: persist the write-ahead log
+        let wal = encode_batch(&batch);
+        ref_mut.merk.apply(&[], &[(WAL_KEY.to_vec(), Op::Put(wal))])?;
+        ref_mut.merk.flush()?;
+
+        // phase 2: apply the batch and delete the log
+        apply_batch(&mut ref_mut.merk, &batch)?;
+        ref_mut.merk.flush()?;
+
+        if let Some(snapshot) = &mut ref_mut.snapshot {
+            snapshot.apply(&batch);
+        }
+
+        ref_mut.metrics.committed_ops.inc_by(batch.len() as u64);
+        ref_mut.metrics.flush_duration.observe(start.elapsed());
+
+        Ok(())
+    }
+
+    /// Record the given height, along with the current root hash, as those of
+    /// the last committed block. This should be called right after `commit`.
+    ///
+    /// The record is written separately from the commit itself, as the root
+    /// hash is only known once the commit has been applied. If the node crashes
+    /// in between, the record is one block behind, which the caller can detect
+    /// by comparing its height against the committed state's.
+    pub fn save_last_commit(&self, height: u64) -> Result<CommitInfo, MerkError> {
+        let mut ref_mut = self.borrow_mut();
+
+        let info = CommitInfo {
+            height,
+            app_hash: ref_mut.merk.root_hash(),
+        };
+
+        let aux = [(LAST_COMMIT_KEY.to_vec(), Op::Put(info.encode()))];
+        ref_mut.merk.apply(&[], &aux)?;
+        ref_mut.merk.flush()?;
+
+        Ok(info)
+    }
+
+    /// Return the height and app hash of the last committed block, as recorded
+    /// by `save_last_commit`, if any.
+    pub fn last_commit(&self) -> Result<Option<CommitInfo>, MerkError> {
+        let Some(bytes) = self.borrow().merk.get_aux(LAST_COMMIT_KEY)? else {
+            return Ok(None);
+        };
+
+        let info = CommitInfo::decode(&bytes).unwrap_or_else(|| {
+            panic!("[cw-store]: failed to decode last commit; the database may be corrupted");
+        });
+
+        Ok(Some(info))
+    }
+
+    /// Return the value saved under the given key in Merk's auxiliary column,
+    /// if any.
+    pub fn get_aux(&self, key: &[u8]) -> Result<Option<Vec<u8>>, MerkError> {
+        self.borrow().merk.get_aux(key)
+    }
+
+    /// Save the given values in Merk's auxiliary column right away, bypassing
+    /// the pending ops.
+    ///
+    /// Aux values aren't part of the root hash, so they can hold data that is
+    /// local to this node, such as indexes. They are reverted by `rollback`
+    /// along with the rest of the database. The keys must not collide with the
+    /// ones used by the store itself, such as `WAL_KEY` and `LAST_COMMIT_KEY`.
+    pub fn put_aux(&self, values: BTreeMap<Vec<u8>, Vec<u8>>) -> Result<(), MerkError> {
+        let mut ref_mut = self.borrow_mut();
+
+        // collected from a btreemap, so the aux ops are sorted
+        let aux = values.into_iter().map(|(key, value)| (key, Op::Put(value))).collect::<Vec<_>>();
+        ref_mut.merk.apply(&[], &aux)?;
+        ref_mut.merk.flush()?;
+
+        Ok(())
+    }
+
+    /// Revert the last commit by restoring the undo checkpoint taken before it.
+    /// Return whether there was a commit to revert.
+    ///
+    /// Only the last commit can be reverted, after which the checkpoint is
+    /// gone. Any pending ops are discarded. The state, including the record of
+    /// the last commit, is exactly as it was before the commit, so the root
+    /// hash is that of the block before it.
+    pub fn rollback(&self) -> Result<bool, MerkError> {
+        let mut ref_mut = self.borrow_mut();
+        let path = ref_mut.path.clone();
+        let undo_path = undo_path(&path);
+        let rolled_back_path = sibling(&path, ROLLED_BACK_SUFFIX);
+
+        if !undo_path.exists() {
+            return Ok(false);
+        }
+
+        // the databases must be closed to be moved, so while they are, the
+        // store holds the checkpoint open, then the database rolled back
+        let live = mem::replace(&mut ref_mut.merk, Merk::open(&undo_path)?);
+        drop(live);
+        fs::rename(&path, &rolled_back_path)?;
+
+        let undo = mem::replace(&mut ref_mut.merk, Merk::open(&rolled_back_path)?);
+        drop(undo);
+        fs::rename(&undo_path, &path)?;
+
+        let rolled_back = mem::replace(&mut ref_mut.merk, Merk::open(&path)?);
+        drop(rolled_back);
+        fs::remove_dir_all(&rolled_back_path)?;
+
+        ref_mut.pending_ops.clear();
+
+        // the checkpoint may differ in any key, so copy the state over again
+        if ref_mut.snapshot.is_some() {
+            let snapshot = Snapshot::load(&ref_mut.merk);
+            ref_mut.snapshot = Some(snapshot);
+        }
+
+        Ok(true)
+    }
+
+    /// Delete the undo checkpoint, after which the last commit can no longer be
+    /// rolled back. Return whether there was a checkpoint to delete.
+    ///
+    /// The checkpoint lives outside the database, so this doesn't affect the
+    /// root hash.
+    pub fn prune(&self) -> Result<bool, MerkError> {
+        remove_undo_checkpoint(&self.borrow().path)
     }
 
     /// Wrap the store into a StoreWrapper.
@@ -107,6 +369,125 @@ impl Store {
     }
 }
 
+/// Compact the database at the given path, so that the disk space taken by
+/// deleted and overwritten values is reclaimed.
+///
+/// The database is accessed directly through RocksDB rather than Merk, so the
+/// store must not be open, whether by this or another process.
+pub fn compact(path: impl AsRef<Path>) -> Result<(), rocksdb::Error> {
+    let opts = rocksdb::Options::default();
+    let cfs = rocksdb::DB::list_cf(&opts, &path)?;
+    let db = rocksdb::DB::open_cf(&opts, &path, &cfs)?;
+
+    for cf in &cfs {
+        if let Some(handle) = db.cf_handle(cf) {
+            db.compact_range_cf(handle, None::<&[u8]>, None::<&[u8]>);
+        }
+    }
+
+    Ok(())
+}
+
+/// The path of the undo checkpoint of the database at the given path.
+pub fn undo_path(path: impl AsRef<Path>) -> PathBuf {
+    sibling(path.as_ref(), UNDO_SUFFIX)
+}
+
+/// Replay the write-ahead log if one exists. Return whether a replay happened.
+fn recover(merk: &mut Merk) -> Result<bool, MerkError> {
+    let Some(wal) = merk.get_aux(WAL_KEY)? else {
+        return Ok(false);
+    };
+
+    // the log is written by this crate, so if it can't be decoded, the database
+    // is corrupted and there is nothing we can do
+    let batch = decode_batch(&wal).unwrap_or_else(|| {
+        panic!("[cw-store]: failed to decode write-ahead log; the database may be corrupted");
+    });
+
+    warn!(ops = batch.len(), "Found write-ahead log from an interrupted commit, replaying");
+
+    // the log is deleted in the same atomic write as the batch is applied, so
+    // if it still exists, the batch hasn't been applied. the undo checkpoint,
+    // if any, was taken before the log was written, so it's still valid
+    apply_batch(merk, &batch)?;
+    merk.flush()?;
+
+    Ok(true)
+}
+
+/// Apply a batch to the Merk tree, and delete the write-ahead log, in the same
+/// atomic write.
+///
+/// The batch must be sorted by keys, which is the case if it is collected from
+/// a btreemap, so we skip the checking step.
+fn apply_batch(merk: &mut Merk, batch: &[(Vec<u8>, Op)]) -> Result<(), MerkError> {
+    unsafe { merk.apply_unchecked(batch, &[(WAL_KEY.to_vec(), Op::Delete)]) }
+}
+
+/// Replace the undo checkpoint with one of the database as it is now. The new
+/// checkpoint is moved into place once complete, so that if the node crashes
+/// half way through, either the old checkpoint or none is left behind.
+fn take_undo_checkpoint(merk: &Merk, path: &Path) -> Result<(), MerkError> {
+    let undo_path = undo_path(path);
+    let tmp_path = sibling(path, UNDO_TMP_SUFFIX);
+
+    if tmp_path.exists() {
+        fs::remove_dir_all(&tmp_path)?;
+    }
+    drop(merk.checkpoint(&tmp_path)?);
+
+    if undo_path.exists() {
+        fs::remove_dir_all(&undo_path)?;
+    }
+    fs::rename(tmp_path, undo_path)?;
+
+    Ok(())
+}
+
+/// Delete the undo checkpoint of the database at the given path. Return whether
+/// there was a checkpoint to delete.
+fn remove_undo_checkpoint(path: &Path) -> Result<bool, MerkError> {
+    let undo_path = undo_path(path);
+
+    if !undo_path.exists() {
+        return Ok(false);
+    }
+
+    fs::remove_dir_all(undo_path)?;
+
+    Ok(true)
+}
+
+/// Finish rolling back the database at the given path if the node crashed in
+/// the middle of it, i.e. after the database was moved aside but before the
+/// checkpoint was moved into its place. Return whether there was a rollback to
+/// finish.
+fn finish_rollback(path: &Path) -> Result<bool, MerkError> {
+    let undo_path = undo_path(path);
+    let rolled_back_path = sibling(path, ROLLED_BACK_SUFFIX);
+
+    if !rolled_back_path.exists() {
+        return Ok(false);
+    }
+
+    warn!("Found database from an interrupted rollback, finishing the rollback");
+
+    if !path.exists() {
+        fs::rename(undo_path, path)?;
+    }
+    fs::remove_dir_all(rolled_back_path)?;
+
+    Ok(true)
+}
+
+/// The path of a directory next to the database, named after it with a suffix.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
 /// A read-only wrapper of the `Store` object, with the `cosmwasm_std::Storage`
 /// trait implemented. When reading from this object, the underlying Merk store
 /// is accessed, while the pending ops are ignored.
@@ -119,7 +500,10 @@ pub struct StoreWrapper {
 
 impl Storage for StoreWrapper {
     fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
-        must_get(&self.inner.borrow().merk, key)
+        let store = self.inner.borrow();
+        let value = must_get(&store.merk, key);
+        store.metrics.record_read(value.as_ref());
+        value
     }
 
     fn set(&mut self, _key: &[u8], _value: &[u8]) {
@@ -141,7 +525,9 @@ impl Storage for StoreWrapper {
                 return Box::new(iter::empty());
             }
         }
-        Box::new(MemIter::new(MerkIter::new(&self.inner.borrow().merk, start, end, order)))
+        let store = self.inner.borrow();
+        store.metrics.iterations.inc();
+        Box::new(MemIter::new(MerkIter::new(&store.merk, start, end, order)))
     }
 }
 
@@ -157,27 +543,33 @@ pub struct PendingStoreWrapper {
 impl Storage for PendingStoreWrapper {
     fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
         let store = self.inner.borrow();
-        let Some(op) = store.pending_ops.get(key) else {
-            return must_get(&store.merk, key);
+        let value = match store.pending_ops.get(key) {
+            Some(op) => {
+                store.metrics.cache_hits.inc();
+                match op {
+                    Op::Put(value) => Some(value.clone()),
+                    Op::Delete => None,
+                }
+            },
+            None => {
+                store.metrics.cache_misses.inc();
+                must_get(&store.merk, key)
+            },
         };
-        match op {
-            Op::Put(value) => Some(value.clone()),
-            Op::Delete => None,
-        }
+        store.metrics.record_read(value.as_ref());
+        value
     }
 
     fn set(&mut self, key: &[u8], value: &[u8]) {
-        self.inner
-            .borrow_mut()
-            .pending_ops
-            .insert(key.to_vec(), Op::Put(value.to_vec()));
+        let mut store = self.inner.borrow_mut();
+        store.metrics.record_write(key, Some(value));
+        store.pending_ops.insert(key.to_vec(), Op::Put(value.to_vec()));
     }
 
     fn remove(&mut self, key: &[u8]) {
-        self.inner
-            .borrow_mut()
-            .pending_ops
-            .insert(key.to_vec(), Op::Delete);
+        let mut store = self.inner.borrow_mut();
+        store.metrics.record_write(key, None);
+        store.pending_ops.insert(key.to_vec(), Op::Delete);
     }
 
     fn range<'a>(
@@ -193,6 +585,7 @@ impl Storage for PendingStoreWrapper {
         }
 
         let store = self.inner.borrow();
+        store.metrics.iterations.inc();
 
         let base = MerkIter::new(&store.merk, start, end, order);
 
@@ -212,22 +605,26 @@ impl Storage for PendingStoreWrapper {
 
 #[cfg(test)]
 mod tests {
-    use std::{env::temp_dir, time::SystemTime};
+    use std::{env::temp_dir, path::PathBuf, time::SystemTime};
 
     use super::*;
 
-    /// Open a `Store` at an autogenerated, temporary file path.
+    /// Generate a temporary file path for opening a `Store`.
     /// Adapted from `merk::test_utils::TempMerk`:
     /// https://github.com/nomic-io/merk/blob/develop/src/test_utils/temp_merk.rs
-    fn setup_test() -> Store {
+    fn temp_path() -> PathBuf {
         let mut path = temp_dir();
         let time = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_nanos();
         path.push(format!("merk-temp-{time}"));
+        path
+    }
 
-        let store = Store::open(path).unwrap();
+    /// Open a `Store` at an autogenerated, temporary file path.
+    fn setup_test() -> Store {
+        let store = Store::open(temp_path()).unwrap();
 
         // add some key-values for testing
         let batch = &[
@@ -282,6 +679,206 @@ mod tests {
         assert!(store.borrow().pending_ops.is_empty());
     }
 
+    #[test]
+    fn recovering() {
+        let path = temp_path();
+
+        // simulate a crash after the write-ahead log is persisted, but before
+        // the batch is applied
+        {
+            let mut merk = Merk::open(&path).unwrap();
+            let batch = vec![
+                (b"key1".to_vec(), Op::Put(b"value1".to_vec())),
+                (b"key2".to_vec(), Op::Put(b"value2".to_vec())),
+            ];
+            merk.apply(&[], &[(WAL_KEY.to_vec(), Op::Put(encode_batch(&batch)))]).unwrap();
+            merk.flush().unwrap();
+        }
+
+        // upon reopening, the log should have been replayed and then deleted
+        let store = Store::open(&path).unwrap();
+        let wrapper = store.wrap();
+        assert_eq!(wrapper.get(b"key1"), Some(b"value1".to_vec()));
+        assert_eq!(wrapper.get(b"key2"), Some(b"value2".to_vec()));
+        assert_eq!(store.borrow().merk.get_aux(WAL_KEY).unwrap(), None);
+    }
+
+    #[test]
+    fn recording_metrics() {
+        let store = setup_test();
+
+        let wrapper = store.pending_wrap();
+        wrapper.get(b"key1"); // read from merk
+        wrapper.get(b"key2"); // read from pending ops
+        wrapper.get(b"key3"); // read from pending ops (deleted)
+
+        let base = store.borrow();
+        assert_eq!(base.metrics.reads.get(), 3);
+        assert_eq!(base.metrics.read_bytes.get(), 16); // "value1" + "value23456"
+        assert_eq!(base.metrics.cache_hits.get(), 2);
+        assert_eq!(base.metrics.cache_misses.get(), 1);
+        assert_eq!(base.metrics.writes.get(), 3); // from `setup_test`
+        drop(base);
+
+        store.commit().unwrap();
+        assert_eq!(store.borrow().metrics.committed_ops.get(), 3);
+        assert_eq!(store.borrow().metrics.flush_duration.count(), 1);
+    }
+
+    #[test]
+    fn committing_clears_log() {
+        let store = setup_test();
+
+        store.commit().unwrap();
+
+        assert_eq!(store.borrow().merk.get_aux(WAL_KEY).unwrap(), None);
+    }
+
+    #[test]
+    fn rolling_back() {
+        let store = setup_test();
+
+        // nothing has been committed through the store yet
+        assert!(!store.rollback().unwrap());
+
+        let root_hash = store.root_hash();
+        store.commit().unwrap();
+        assert_ne!(store.root_hash(), root_hash);
+
+        // the state should be restored to before the commit
+        assert!(store.rollback().unwrap());
+        assert_eq!(store.root_hash(), root_hash);
+
+        let wrapper = store.wrap();
+        assert_eq!(wrapper.get(b"key2"), Some(b"value2".to_vec()));
+        assert_eq!(wrapper.get(b"key3"), Some(b"value3".to_vec()));
+        assert_eq!(wrapper.get(b"key3333"), None);
+
+        // only the last commit can be rolled back
+        assert!(!store.rollback().unwrap());
+    }
+
+    #[test]
+    fn saving_last_commit() {
+        let store = setup_test();
+        assert_eq!(store.last_commit().unwrap(), None);
+
+        store.commit().unwrap();
+        let first = store.save_last_commit(1).unwrap();
+        assert_eq!(first.app_hash, store.root_hash());
+
+        store.pending_wrap().set(b"key5", b"value5");
+        store.commit().unwrap();
+        let second = store.save_last_commit(2).unwrap();
+        assert_eq!(store.last_commit().unwrap(), Some(second));
+
+        // rolling back restores the record of the commit before, as well as
+        // the root hash it recorded
+        assert!(store.rollback().unwrap());
+        assert_eq!(store.last_commit().unwrap(), Some(first));
+        assert_eq!(store.root_hash(), first.app_hash);
+    }
+
+    #[test]
+    fn finishing_rollback() {
+        let path = temp_path();
+        let store = Store::open(&path).unwrap();
+        store.pending_wrap().set(b"key1", b"value1");
+        store.commit().unwrap();
+        let root_hash = store.root_hash();
+        store.pending_wrap().set(b"key1", b"value2");
+        store.commit().unwrap();
+        drop(store);
+
+        // simulate a crash after the database is moved aside, but before the
+        // checkpoint is moved into its place
+        fs::rename(&path, sibling(&path, ROLLED_BACK_SUFFIX)).unwrap();
+
+        // upon reopening, the rollback should have been finished
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.root_hash(), root_hash);
+        assert_eq!(store.wrap().get(b"key1"), Some(b"value1".to_vec()));
+        assert!(!sibling(&path, ROLLED_BACK_SUFFIX).exists());
+        assert!(!store.rollback().unwrap());
+    }
+
+    #[test]
+    fn snapshotting() {
+        let store = setup_test();
+        assert!(store.snapshot().is_none());
+
+        store.enable_snapshot();
+        let before = store.snapshot().unwrap();
+        assert_eq!(before.get(b"key2"), Some(b"value2".to_vec()));
+
+        store.commit().unwrap();
+        let after = store.snapshot().unwrap();
+        assert_eq!(after.get(b"key2"), Some(b"value23456".to_vec()));
+        assert_eq!(after.get(b"key3"), None);
+
+        // the snapshot taken before the commit is left untouched
+        assert_eq!(before.get(b"key2"), Some(b"value2".to_vec()));
+        assert_eq!(before.get(b"key3"), Some(b"value3".to_vec()));
+
+        let keys = after
+            .range(None, None, Order::Descending)
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![b"key4".to_vec(), b"key3333".to_vec(), b"key2".to_vec(), b"key1".to_vec()],
+        );
+
+        // rolling back restores the snapshot as well
+        assert!(store.rollback().unwrap());
+        assert_eq!(store.snapshot().unwrap().get(b"key3"), Some(b"value3".to_vec()));
+    }
+
+    #[test]
+    fn putting_aux() {
+        let store = setup_test();
+        let root_hash = store.root_hash();
+
+        let values = BTreeMap::from([
+            (b"index/b".to_vec(), b"2".to_vec()),
+            (b"index/a".to_vec(), b"1".to_vec()),
+        ]);
+        store.put_aux(values).unwrap();
+
+        assert_eq!(store.get_aux(b"index/a").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(store.get_aux(b"index/c").unwrap(), None);
+
+        // aux values don't affect the root hash
+        assert_eq!(store.root_hash(), root_hash);
+    }
+
+    #[test]
+    fn pruning() {
+        let store = setup_test();
+
+        store.commit().unwrap();
+        assert!(undo_path(&store.borrow().path).exists());
+
+        // once pruned, the last commit can no longer be rolled back
+        assert!(store.prune().unwrap());
+        assert!(!store.prune().unwrap());
+        assert!(!store.rollback().unwrap());
+
+        // the committed state is intact
+        let wrapper = store.wrap();
+        assert_eq!(wrapper.get(b"key2"), Some(b"value23456".to_vec()));
+        assert_eq!(wrapper.get(b"key3333"), Some(b"value3333".to_vec()));
+        drop(wrapper);
+
+        // if checkpoints are disabled, one left from before is deleted on the
+        // next commit, as it would be stale
+        store.commit().unwrap();
+        store.set_keep_undo(false);
+        store.pending_wrap().set(b"key5", b"value5");
+        store.commit().unwrap();
+        assert!(!store.rollback().unwrap());
+    }
+
     #[test]
     #[should_panic = "[cw-store]: `set` method invoked on read-only store wrapper"]
     fn illegal_set() {
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Coin, Timestamp};
+use cw_sdk::{Account, GenesisAccount, GenesisState, Params, PubKeyType, SdkMsg};
+use cw_sdk_client::Signer;
+use cw_state_machine::StateMachine;
+use cw_store::Store;
+use serde_json::json;
+
+use crate::{
+    chain::{TempDir, TestAccount},
+    TestChain,
+};
+
+/// Chain id of the test chain, by default
+pub const DEFAULT_CHAIN_ID: &str = "test-1";
+
+/// Label of the bank contract, which the state machine invokes for fee
+/// payments and balance queries
+pub const BANK: &str = "bank";
+
+/// Name of the account that executes the genesis messages
+pub const DEPLOYER: &str = "deployer";
+
+/// Time of the genesis block: 2023-01-01T00:00:00Z
+const GENESIS_TIME_SECONDS: u64 = 1_672_531_200;
+
+/// Sets up the genesis state of a `TestChain`.
+#[derive(Debug, Clone)]
+pub struct TestChainBuilder {
+    chain_id: String,
+    params: Params,
+    bank_code: Option<Vec<u8>>,
+    accounts: Vec<(String, Vec<Coin>)>,
+    msgs: Vec<SdkMsg>,
+}
+
+impl Default for TestChainBuilder {
+    fn default() -> Self {
+        Self {
+            chain_id: DEFAULT_CHAIN_ID.into(),
+            params: Params::default(),
+            bank_code: None,
+            accounts: vec![],
+            msgs: vec![],
+        }
+    }
+}
+
+impl TestChainBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+
+    pub fn params(mut self, params: Params) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Store and instantiate the bank contract at genesis, from the given wasm
+    /// byte code, with the balances of the accounts added to the builder. The
+    /// deployer is its owner.
+    pub fn bank(mut self, wasm_byte_code: impl Into<Vec<u8>>) -> Self {
+        self.bank_code = Some(wasm_byte_code.into());
+        self
+    }
+
+    /// Add an account whose key is derived from the given name, with the given
+    /// initial balances. Balances require the bank contract.
+    pub fn account(mut self, name: impl Into<String>, coins: Vec<Coin>) -> Self {
+        self.accounts.push((name.into(), coins));
+        self
+    }
+
+    /// Append a message to be executed at genesis by the deployer, after the
+    /// bank contract is instantiated
+    pub fn msg(mut self, msg: SdkMsg) -> Self {
+        self.msgs.push(msg);
+        self
+    }
+
+    /// Initialize the chain in a temporary directory, which is deleted once the
+    /// chain is dropped.
+    ///
+    /// Panics if the genesis state is invalid, e.g. if accounts are funded
+    /// without the bank contract, or a genesis message fails.
+    pub fn build(self) -> TestChain {
+        let deployer = TestAccount::new(DEPLOYER);
+        let mut accounts = BTreeMap::new();
+        let mut gen_accounts = vec![];
+        let mut balances = vec![];
+        for (account_number, (name, coins)) in self.accounts.into_iter().enumerate() {
+            let account = TestAccount::new(&name);
+            gen_accounts.push(GenesisAccount {
+                address: account.address.to_string(),
+                account: Account::Base {
+                    pubkey: account.signer.pubkey(),
+                    pubkey_type: PubKeyType::Secp256k1,
+                    account_number: account_number as u64,
+                    sequence: 0,
+                },
+            });
+            if !coins.is_empty() {
+                balances.push(json!({
+                    "address": account.address,
+                    "coins": coins,
+                }));
+            }
+            if accounts.insert(name.clone(), account).is_some() {
+                panic!("account {name} is added more than once");
+            }
+        }
+
+        let mut msgs = vec![];
+        match self.bank_code {
+            Some(wasm_byte_code) => {
+                msgs.push(SdkMsg::StoreCode {
+                    wasm_byte_code: wasm_byte_code.into(),
+                });
+                msgs.push(SdkMsg::Instantiate {
+                    code_id: 1,
+                    msg: json!({
+                        "owner": deployer.address,
+                        "balances": balances,
+                        "namespace_cfgs": [],
+                    }),
+                    funds: vec![],
+                    label: BANK.into(),
+                    admin: Some(deployer.address.to_string()),
+                });
+            },
+            None if !balances.is_empty() => {
+                panic!("accounts can only be funded if the bank contract is added");
+            },
+            None => (),
+        }
+        msgs.extend(self.msgs);
+
+        let gen_state = GenesisState {
+            deployer: deployer.address.to_string(),
+            params: self.params,
+            msgs,
+            accounts: gen_accounts,
+            ..Default::default()
+        };
+
+        let dir = TempDir::new();
+        let sm = StateMachine::new(Store::open(&dir.path).expect("failed to open store"));
+        sm.init_chain(self.chain_id.clone(), 1, vec![], gen_state)
+            .expect("failed to initialize chain");
+        accounts.insert(DEPLOYER.into(), deployer);
+
+        TestChain {
+            sm,
+            _dir: dir,
+            chain_id: self.chain_id,
+            height: 0,
+            time: Timestamp::from_seconds(GENESIS_TIME_SECONDS),
+            accounts,
+        }
+    }
+}
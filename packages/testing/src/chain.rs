@@ -0,0 +1,317 @@
+use std::{
+    collections::BTreeMap,
+    env::temp_dir,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use cosmwasm_std::{from_slice, Addr, Coin, ContractResult, Event, StdError, Timestamp};
+use cw_sdk::{
+    address, canonical::to_canonical_vec, hash::sha256, Account, AccountResponse, InfoResponse,
+    SdkMsg, SdkQuery, WasmSmartResponse,
+};
+use cw_sdk_client::{SecpSigner, Signer, TxBuilder};
+use cw_state_machine::{
+    error::{Error, Result},
+    StateMachine, TxOutcome,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{AppResponse, TestChainBuilder};
+
+/// How much time passes between blocks, by default
+pub const BLOCK_TIME: Duration = Duration::from_secs(5);
+
+/// A key pair, derived from a name, and the address of its account.
+pub struct TestAccount {
+    pub signer: SecpSigner,
+    pub address: Addr,
+}
+
+impl TestAccount {
+    /// Derive the key from the SHA-256 hash of the name, so that an account of
+    /// the same name has the same address across tests.
+    pub fn new(name: &str) -> Self {
+        let signer = SecpSigner::from_bytes(&sha256(name.as_bytes()))
+            .expect("hash of name isn't a valid private key");
+        let address = signer.address().expect("failed to derive address");
+        Self {
+            signer,
+            address,
+        }
+    }
+}
+
+/// A directory that is deleted once dropped.
+pub(crate) struct TempDir {
+    pub path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        Self {
+            path: temp_dir().join(format!("cw-sdk-testing-{time}")),
+        }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.path).ok();
+    }
+}
+
+/// A chain running in-process, on the actual state machine and store.
+///
+/// Each tx is executed in a block of its own, `BLOCK_TIME` after the previous
+/// block, so that contracts see the height and time advance as on a real chain.
+pub struct TestChain {
+    pub(crate) sm: StateMachine,
+
+    /// Declared after the state machine, so that the store is closed before the
+    /// directory is deleted
+    pub(crate) _dir: TempDir,
+
+    pub(crate) chain_id: String,
+    pub(crate) height: u64,
+    pub(crate) time: Timestamp,
+    pub(crate) accounts: BTreeMap<String, TestAccount>,
+}
+
+impl TestChain {
+    pub fn builder() -> TestChainBuilder {
+        TestChainBuilder::new()
+    }
+
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    /// Height of the last committed block
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Time of the last committed block
+    pub fn time(&self) -> Timestamp {
+        self.time
+    }
+
+    /// The underlying state machine, e.g. to change its configuration or to
+    /// export its state
+    pub fn state_machine(&mut self) -> &mut StateMachine {
+        &mut self.sm
+    }
+
+    /// Return the account of the given name. Panics if it hasn't been added.
+    pub fn account(&self, name: &str) -> &TestAccount {
+        self.accounts.get(name).unwrap_or_else(|| panic!("account {name} not found"))
+    }
+
+    /// Add an account of the given name, which doesn't exist on chain until it
+    /// sends its first tx. Its address may receive coins before that.
+    pub fn add_account(&mut self, name: &str) -> &TestAccount {
+        self.accounts.entry(name.into()).or_insert_with(|| TestAccount::new(name))
+    }
+
+    /// Address of the contract of the given label, whether it exists or not
+    pub fn contract_address(&self, label: &str) -> Addr {
+        address::derive_from_label(label).expect("invalid contract label")
+    }
+
+    /// Produce an empty block, e.g. for time-dependent logic to kick in.
+    pub fn next_block(&mut self) -> Result<Vec<Event>> {
+        Ok(self.finalize_block(vec![])?.0)
+    }
+
+    /// Produce empty blocks until at least the given duration has passed since
+    /// the last block.
+    pub fn advance_time(&mut self, duration: Duration) -> Result<()> {
+        let target = self.time.plus_nanos(duration.as_nanos() as u64);
+        while self.time < target {
+            self.next_block()?;
+        }
+        Ok(())
+    }
+
+    /// Sign a tx with the messages, sent by the account of the given name, and
+    /// execute it in a new block. Fail if the tx does.
+    pub fn execute_tx(&mut self, sender: &str, msgs: Vec<SdkMsg>) -> Result<AppResponse> {
+        self.execute_builder(sender, TxBuilder::new().msgs(msgs))
+    }
+
+    /// Same as `execute_tx`, but with the tx's other parameters, e.g. its fee,
+    /// set in the builder. The chain id and signer info are always filled in.
+    pub fn execute_builder(&mut self, sender: &str, builder: TxBuilder) -> Result<AppResponse> {
+        let account = self.account(sender);
+        let (account_number, sequence) = self.signer_info(&account.address)?;
+        let tx = builder
+            .sign(&account.signer, &self.chain_id, account_number, sequence)
+            .map_err(|err| Error::Contract(err.to_string()))?;
+        let tx_bytes = to_canonical_vec(&tx)?;
+        let hash = hex::encode_upper(sha256(&tx_bytes));
+
+        let (_, mut tx_results) = self.finalize_block(vec![tx_bytes])?;
+        let outcome = tx_results.remove(0);
+        Ok(AppResponse {
+            height: self.height,
+            hash,
+            gas_used: outcome.gas_used,
+            events: outcome.result?,
+        })
+    }
+
+    /// Store wasm byte code, returning its code id.
+    pub fn store_code(&mut self, sender: &str, wasm_byte_code: impl Into<Vec<u8>>) -> Result<u64> {
+        let response = self.execute_tx(sender, vec![SdkMsg::StoreCode {
+            wasm_byte_code: wasm_byte_code.into().into(),
+        }])?;
+        let code_id = response
+            .attribute("store_code", "code_id")
+            .expect("store_code event has no code_id attribute");
+        Ok(code_id.parse().expect("code id isn't a number"))
+    }
+
+    /// Instantiate a contract with the sender as its admin, returning the
+    /// response and the contract's address.
+    pub fn instantiate(
+        &mut self,
+        sender: &str,
+        code_id: u64,
+        label: &str,
+        msg: &impl Serialize,
+        funds: Vec<Coin>,
+    ) -> Result<(AppResponse, Addr)> {
+        let admin = self.account(sender).address.to_string();
+        let response = self.execute_tx(sender, vec![SdkMsg::Instantiate {
+            code_id,
+            msg: serde_json::to_value(msg)?,
+            funds,
+            label: label.into(),
+            admin: Some(admin),
+        }])?;
+        Ok((response, self.contract_address(label)))
+    }
+
+    /// Execute a contract, which may be referred to by its label.
+    pub fn execute(
+        &mut self,
+        sender: &str,
+        contract: &str,
+        msg: &impl Serialize,
+        funds: Vec<Coin>,
+    ) -> Result<AppResponse> {
+        self.execute_tx(sender, vec![SdkMsg::Execute {
+            contract: contract.into(),
+            msg: serde_json::to_value(msg)?,
+            funds,
+        }])
+    }
+
+    /// Send a query to the state machine and deserialize the JSON response.
+    pub fn query<R: DeserializeOwned>(&self, query: SdkQuery) -> Result<R> {
+        let response = self.sm.query(query)?;
+        from_slice(&response).map_err(Into::into)
+    }
+
+    /// Send a smart query to a contract, which may be referred to by its label,
+    /// and deserialize its JSON response. If the contract returns an error,
+    /// it's returned as `Error::Contract`.
+    pub fn query_wasm_smart<R: DeserializeOwned>(
+        &self,
+        contract: &str,
+        msg: &impl Serialize,
+    ) -> Result<R> {
+        let response: WasmSmartResponse = self.query(SdkQuery::WasmSmart {
+            contract: contract.into(),
+            msg: serde_json::to_value(msg)?,
+        })?;
+        match response.result {
+            ContractResult::Ok(data) => from_slice(&data).map_err(Into::into),
+            ContractResult::Err(err) => Err(Error::Contract(err)),
+        }
+    }
+
+    /// Query an address's balance of a coin from the bank contract.
+    pub fn balance(&self, address: impl Into<String>, denom: &str) -> Result<Coin> {
+        self.query(SdkQuery::Balance {
+            address: address.into(),
+            denom: denom.into(),
+        })
+    }
+
+    /// Return the account number and the sequence of the next tx of the given
+    /// address, the same way as the client does.
+    fn signer_info(&self, address: &Addr) -> Result<(u64, u64)> {
+        let response: Result<AccountResponse> = self.query(SdkQuery::Account {
+            address: address.to_string(),
+        });
+        match response {
+            Ok(AccountResponse {
+                account:
+                    Account::Base {
+                        account_number,
+                        sequence,
+                        ..
+                    }
+                    | Account::Multisig {
+                        account_number,
+                        sequence,
+                        ..
+                    },
+                ..
+            }) => Ok((account_number, sequence + 1)),
+            Ok(_) => Err(Error::account_is_contract(address)),
+            Err(Error::Std(StdError::NotFound {
+                ..
+            })) => {
+                let info: InfoResponse = self.query(SdkQuery::Info {})?;
+                Ok((info.account_count, 1))
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    fn finalize_block(&mut self, txs: Vec<Vec<u8>>) -> Result<(Vec<Event>, Vec<TxOutcome>)> {
+        let height = self.height + 1;
+        let time = self.time.plus_nanos(BLOCK_TIME.as_nanos() as u64);
+        let outcome = self.sm.finalize_block(height, time, &txs)?;
+        self.height = height;
+        self.time = time;
+        Ok((outcome.events, outcome.tx_results))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executing_txs() {
+        let mut chain = TestChain::builder().account("alice", vec![]).build();
+        let alice = chain.account("alice").address.clone();
+
+        let response = chain.execute_tx("alice", vec![]).unwrap();
+        assert_eq!(response.height, 1);
+        chain.execute_tx("alice", vec![]).unwrap();
+        assert_eq!(chain.signer_info(&alice).unwrap(), (0, 3));
+
+        // an account that isn't in genesis is created by its first tx
+        let bob = chain.add_account("bob").address.clone();
+        assert_eq!(chain.signer_info(&bob).unwrap(), (1, 1));
+        chain.execute_tx("bob", vec![]).unwrap();
+        assert_eq!(chain.signer_info(&bob).unwrap(), (1, 2));
+
+        // a failed tx is still included in a block
+        assert!(chain.store_code("alice", b"not wasm".to_vec()).is_err());
+        assert_eq!(chain.height(), 4);
+
+        chain.advance_time(Duration::from_secs(12)).unwrap();
+        assert_eq!(chain.height(), 7);
+    }
+}
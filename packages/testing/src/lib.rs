@@ -0,0 +1,14 @@
+//! In-process integration testing of contracts, like cw-multi-test but on the
+//! actual state machine and store rather than a mock of them.
+//!
+//! - `TestChainBuilder` sets up the genesis state: the bank contract, and
+//!   accounts funded with it;
+//! - `TestChain` executes txs signed by those accounts, each in a block of its
+//!   own, and queries the state machine;
+//! - `AppResponse` holds the events emitted by a tx, to be asserted on.
+
+mod builder;
+mod chain;
+mod response;
+
+pub use crate::{builder::*, chain::*, response::*};
@@ -0,0 +1,71 @@
+use cosmwasm_std::Event;
+
+/// The result of a tx that was executed successfully.
+#[derive(Debug, Clone)]
+pub struct AppResponse {
+    /// Height of the block in which the tx was executed
+    pub height: u64,
+
+    /// SHA-256 hash of the tx bytes, hex-encoded
+    pub hash: String,
+
+    pub gas_used: u64,
+
+    pub events: Vec<Event>,
+}
+
+impl AppResponse {
+    /// Whether an event was emitted of the same type as the expected one, with
+    /// all of its attributes. The emitted event may have other attributes too.
+    pub fn has_event(&self, expected: &Event) -> bool {
+        self.events.iter().any(|event| {
+            event.ty == expected.ty
+                && expected.attributes.iter().all(|attr| event.attributes.contains(attr))
+        })
+    }
+
+    /// Panic if no event matching the expected one was emitted, listing the
+    /// events that were.
+    pub fn assert_event(&self, expected: &Event) {
+        assert!(
+            self.has_event(expected),
+            "expected tx to emit event {expected:?}, but it only emitted {:#?}",
+            self.events,
+        );
+    }
+
+    /// Return the value of the first attribute of the given key, in the first
+    /// event of the given type that has it.
+    pub fn attribute(&self, ty: &str, key: &str) -> Option<&str> {
+        self.events
+            .iter()
+            .filter(|event| event.ty == ty)
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_events() {
+        let response = AppResponse {
+            height: 1,
+            hash: String::new(),
+            gas_used: 0,
+            events: vec![Event::new("store_code")
+                .add_attribute("sender", "alice")
+                .add_attribute("code_id", "1")],
+        };
+
+        assert!(response.has_event(&Event::new("store_code")));
+        assert!(response.has_event(&Event::new("store_code").add_attribute("code_id", "1")));
+        assert!(!response.has_event(&Event::new("store_code").add_attribute("code_id", "2")));
+        assert!(!response.has_event(&Event::new("instantiate")));
+        assert_eq!(response.attribute("store_code", "code_id"), Some("1"));
+        assert_eq!(response.attribute("store_code", "code_hash"), None);
+    }
+}